@@ -13,6 +13,9 @@ use splice::solver::faer_sparse::FaerSparseSolver;
 use splice::solver::Solver;
 use splice::spot::*;
 
+/// The frequencies of the AC sweeps.
+const SWEEP_FREQUENCIES: usize = 20;
+
 pub fn generate_solvable_system(
     n: usize,
     density: Numeric,
@@ -40,9 +43,7 @@ pub fn generate_solvable_system(
     let mut a = vec![vec![Numeric::zero(); n]; n];
     for i in 0..n {
         for j in 0..n {
-            for k in 0..n {
-                a[i][j] += l[i][k] * l[j][k];
-            }
+            a[i][j] += l[i].iter().zip(&l[j]).map(|(x, y)| x * y).sum::<Numeric>();
         }
     }
 
@@ -178,3 +179,46 @@ pub fn faer_sparse_solve(c: &mut Criterion) {
 
     group.finish();
 }
+
+/// Stamps the AC system of an RC ladder with `nodes` nodes at the frequency `freq`.
+fn stamp_rc_ladder_ac(solver: &mut FaerSparseSolver, nodes: usize, freq: Numeric) {
+    let g = ComplexNumeric::new(1e-3, 0.0);
+    let y = ComplexNumeric::new(0.0, 2.0 * std::f64::consts::PI * freq * 1e-6);
+    let one = ComplexNumeric::new(1.0, 0.0);
+
+    for node in 1..nodes {
+        solver.insert_cplx_a(&(node - 1, node - 1, g));
+        solver.insert_cplx_a(&(node, node, g));
+        solver.insert_cplx_a(&(node - 1, node, -g));
+        solver.insert_cplx_a(&(node, node - 1, -g));
+        solver.insert_cplx_a(&(node, node, y));
+    }
+    solver.insert_cplx_a(&(0, nodes, one));
+    solver.insert_cplx_a(&(nodes, 0, one));
+    solver.insert_cplx_b(&(nodes, one));
+}
+
+/// Benchmarks AC sweeps over RC ladders, which reuse the analysis of the complex pattern for all
+/// frequencies.
+pub fn faer_sparse_solve_cplx(c: &mut Criterion) {
+    let mut group = c.benchmark_group("FaerSparse::solve_cplx");
+
+    for nodes in [100, 1000, 10000] {
+        let mut solver = FaerSparseSolver::new(nodes + 1).unwrap();
+        solver.init(vec![], vec![]);
+        let freqs: Vec<Numeric> = (0..SWEEP_FREQUENCIES)
+            .map(|idx| NUMERIC_TEN.powf(idx as Numeric / 4.0))
+            .collect();
+
+        group.bench_function(format!("FaerSparse::solve_cplx rc ladder n={nodes}"), |b| {
+            b.iter(|| {
+                for &freq in &freqs {
+                    stamp_rc_ladder_ac(&mut solver, nodes, freq);
+                    black_box(solver.solve_cplx().unwrap());
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
@@ -38,9 +38,7 @@ pub fn generate_solvable_system(
     let mut a = vec![vec![Numeric::zero(); n]; n];
     for i in 0..n {
         for j in 0..n {
-            for k in 0..n {
-                a[i][j] += l[i][k] * l[j][k];
-            }
+            a[i][j] += l[i].iter().zip(&l[j]).map(|(x, y)| x * y).sum::<Numeric>();
         }
     }
 
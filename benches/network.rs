@@ -18,6 +18,7 @@ fn create_simple_rc_circuit() -> SerdeCircuit {
                 node0: "n1".to_string(),
                 node1: "0".to_string(),
                 value: 1000.0,
+                ..Default::default()
             }),
             SerdeElement::VSource(SerdeVSource {
                 name: "V1".to_string(),
@@ -29,8 +30,11 @@ fn create_simple_rc_circuit() -> SerdeCircuit {
         ],
         simulations: vec![SerdeSimulation::OP],
         options: vec![SerdeOption {
-            out: "n1".to_string(),
+            out: Some("n1".to_string()),
+            ..Default::default()
         }],
+        ic: vec![],
+        nodeset: vec![],
     }
 }
 
@@ -58,6 +62,7 @@ fn create_medium_circuit(size: usize) -> SerdeCircuit {
             },
             node1: format!("n{}", i + 1),
             value: 1000.0,
+            ..Default::default()
         }));
     }
 
@@ -67,14 +72,18 @@ fn create_medium_circuit(size: usize) -> SerdeCircuit {
         node0: format!("n{}", size + 1),
         node1: "0".to_string(),
         value: 1000.0,
+        ..Default::default()
     }));
 
     SerdeCircuit {
         elements,
         simulations: vec![SerdeSimulation::OP],
         options: vec![SerdeOption {
-            out: format!("n{}", size + 1),
+            out: Some(format!("n{}", size + 1)),
+            ..Default::default()
         }],
+        ic: vec![],
+        nodeset: vec![],
     }
 }
 
@@ -108,6 +117,7 @@ fn create_large_circuit(rows: usize, cols: usize) -> SerdeCircuit {
                     node0: current_node.to_string(),
                     node1: next_node.to_string(),
                     value: 1000.0,
+                    ..Default::default()
                 }));
                 node_counter += 1;
             }
@@ -122,6 +132,7 @@ fn create_large_circuit(rows: usize, cols: usize) -> SerdeCircuit {
                 node0: start_node.to_string(),
                 node1: end_node.to_string(),
                 value: 1000.0,
+                ..Default::default()
             }));
         }
     }
@@ -130,8 +141,11 @@ fn create_large_circuit(rows: usize, cols: usize) -> SerdeCircuit {
         elements,
         simulations: vec![SerdeSimulation::OP],
         options: vec![SerdeOption {
-            out: "n1".to_string(),
+            out: Some("n1".to_string()),
+            ..Default::default()
         }],
+        ic: vec![],
+        nodeset: vec![],
     }
 }
 
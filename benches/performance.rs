@@ -22,6 +22,10 @@ criterion_group!(
     real_world_benches,
     bench_resistor_network,
     bench_resistor_ladder,
+    bench_diode_ladder,
+    bench_rc_ladder,
+    bench_rlc_ladder,
+    bench_power_grid,
 );
 
 criterion_group!(
@@ -61,6 +65,8 @@ criterion_group!(
     rsparse_solve
 );
 
+criterion_group!(ac_benches, faer_sparse_solve_cplx);
+
 criterion_group!(
     network_benches_quick,
     bench_msgpack_serialization_quick,
@@ -76,4 +82,4 @@ criterion_group!(
     bench_payload_scaling_long,
 );
 
-criterion_main!(real_world_benches, network_benches_quick);
+criterion_main!(real_world_benches, ac_benches, network_benches_quick);
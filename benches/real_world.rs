@@ -6,7 +6,7 @@ use criterion::{BenchmarkId, Criterion};
 use splice::{
     frontends::create_simulation_from_spice,
     run_sim_for_benchmark,
-    solver::{FaerSolver, FaerSparseSolver, NalgebraSolver},
+    solver::{FaerSolver, FaerSparseSolver, KrylovSolver, NalgebraSolver, RSparseSolver},
 };
 
 /// Generate SPICE code for a resistor ladder circuit
@@ -26,6 +26,48 @@ fn generate_resistor_ladder(n_resistors: usize) -> String {
     spice_code
 }
 
+/// Generate SPICE code for a ladder of resistors in series and diodes to ground
+fn generate_diode_ladder(n_diodes: usize) -> String {
+    let mut spice_code = String::from("V1 1 0 5\n");
+
+    for i in 1..=n_diodes {
+        spice_code.push_str(&format!("R{i} {} {} 100\n", i, i + 1));
+        spice_code.push_str(&format!("D{i} 0 {}\n", i + 1));
+    }
+
+    spice_code.push_str(".op");
+    spice_code
+}
+
+/// Generate SPICE code for a RC ladder, charged by a voltage step
+fn generate_rc_ladder(n_stages: usize) -> String {
+    let mut spice_code = String::from("V1 1 0 10\n");
+
+    for i in 1..=n_stages {
+        spice_code.push_str(&format!("R{i} {} {} 1000\n", i, i + 1));
+        spice_code.push_str(&format!("C{i} {} 0 1e-6\n", i + 1));
+    }
+
+    spice_code.push_str(".tran 1e-5 1e-3 0 1e-5 uic");
+    spice_code
+}
+
+/// Generate SPICE code for a RLC ladder of series resistors and inductors with capacitors to
+/// ground, charged by a voltage step
+fn generate_rlc_ladder(n_stages: usize) -> String {
+    let mut spice_code = String::from("V1 1 0 10\n");
+
+    for i in 1..=n_stages {
+        let node = 2 * i - 1;
+        spice_code.push_str(&format!("R{i} {} {} 10\n", node, node + 1));
+        spice_code.push_str(&format!("L{i} {} {} 1e-3\n", node + 1, node + 2));
+        spice_code.push_str(&format!("C{i} {} 0 1e-6\n", node + 2));
+    }
+
+    spice_code.push_str(".tran 1e-5 1e-3 0 1e-5 uic");
+    spice_code
+}
+
 /// Generate SPICE code for a resistor network (grid)
 fn generate_resistor_network(rows: usize, cols: usize) -> String {
     let mut spice_code = String::from("V1 1 0 5\n");
@@ -69,6 +111,43 @@ fn generate_resistor_network(rows: usize, cols: usize) -> String {
     spice_code
 }
 
+/// Generate SPICE code for a power grid mesh with a load current at every node and a supply
+/// pad at every tenth node in both directions
+fn generate_power_grid(size: usize) -> String {
+    let mut spice_code = String::new();
+    let node = |row: usize, col: usize| row * size + col + 1;
+
+    for row in 0..size {
+        for col in 0..size {
+            if col + 1 < size {
+                spice_code.push_str(&format!(
+                    "R{row}x{col}h {} {} 0.1\n",
+                    node(row, col),
+                    node(row, col + 1)
+                ));
+            }
+            if row + 1 < size {
+                spice_code.push_str(&format!(
+                    "R{row}x{col}v {} {} 0.1\n",
+                    node(row, col),
+                    node(row + 1, col)
+                ));
+            }
+            spice_code.push_str(&format!("I{row}x{col} {} 0 0.001\n", node(row, col)));
+            if row % 10 == 5 && col % 10 == 5 {
+                spice_code.push_str(&format!("V{row}x{col} p{row}x{col} 0 1\n"));
+                spice_code.push_str(&format!(
+                    "R{row}x{col}p p{row}x{col} {} 0.01\n",
+                    node(row, col)
+                ));
+            }
+        }
+    }
+
+    spice_code.push_str(".op");
+    spice_code
+}
+
 /// Benchmark resistor ladder circuits with different sizes
 pub fn bench_resistor_ladder(c: &mut Criterion) {
     let mut group = c.benchmark_group("Resistor Ladder");
@@ -149,3 +228,126 @@ pub fn bench_resistor_network(c: &mut Criterion) {
 
     group.finish();
 }
+
+/// Benchmark the sparse solvers on a nonlinear ladder, which refactors the matrix in every
+/// Newton iteration
+pub fn bench_diode_ladder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Diode Ladder");
+    let sizes = [10, 100, 1000];
+
+    for &size in &sizes {
+        let spice_code = generate_diode_ladder(size);
+        let network =
+            create_simulation_from_spice(&spice_code).expect("Failed to parse diode ladder");
+
+        group.bench_with_input(BenchmarkId::new("Faer Sparse", size), &size, |b, _| {
+            b.iter(|| {
+                run_sim_for_benchmark::<FaerSparseSolver>(network.clone())
+                    .expect("Simulation failed");
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("RSparse", size), &size, |b, _| {
+            b.iter(|| {
+                run_sim_for_benchmark::<RSparseSolver>(network.clone()).expect("Simulation failed");
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark the sparse solvers on a linear transient ladder, which reuses the factorisation
+/// for all time steps of the same size
+pub fn bench_rc_ladder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("RC Ladder");
+    group.sample_size(20);
+    let sizes = [10, 100, 500];
+
+    for &size in &sizes {
+        let spice_code = generate_rc_ladder(size);
+        let network = create_simulation_from_spice(&spice_code).expect("Failed to parse RC ladder");
+
+        group.bench_with_input(BenchmarkId::new("Faer Sparse", size), &size, |b, _| {
+            b.iter(|| {
+                run_sim_for_benchmark::<FaerSparseSolver>(network.clone())
+                    .expect("Simulation failed");
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("RSparse", size), &size, |b, _| {
+            b.iter(|| {
+                run_sim_for_benchmark::<RSparseSolver>(network.clone()).expect("Simulation failed");
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark the sparse solvers on a linear transient ladder with inductors, whose branch
+/// currents add a variable per stage
+pub fn bench_rlc_ladder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("RLC Ladder");
+    group.sample_size(20);
+    let sizes = [10, 100, 500];
+
+    for &size in &sizes {
+        let spice_code = generate_rlc_ladder(size);
+        let network =
+            create_simulation_from_spice(&spice_code).expect("Failed to parse RLC ladder");
+
+        group.bench_with_input(BenchmarkId::new("Faer Sparse", size), &size, |b, _| {
+            b.iter(|| {
+                run_sim_for_benchmark::<FaerSparseSolver>(network.clone())
+                    .expect("Simulation failed");
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("RSparse", size), &size, |b, _| {
+            b.iter(|| {
+                run_sim_for_benchmark::<RSparseSolver>(network.clone()).expect("Simulation failed");
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark the direct and the iterative sparse solver on the IR drop of a power grid mesh
+pub fn bench_power_grid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Power Grid");
+    group.sample_size(10);
+    let sizes = [50, 100, 200];
+
+    for &size in &sizes {
+        let size_desc = format!("{size}x{size}");
+        let spice_code = generate_power_grid(size);
+        let network =
+            create_simulation_from_spice(&spice_code).expect("Failed to parse power grid");
+
+        group.bench_with_input(
+            BenchmarkId::new("Faer Sparse", &size_desc),
+            &size_desc,
+            |b, _| {
+                b.iter(|| {
+                    run_sim_for_benchmark::<FaerSparseSolver>(network.clone())
+                        .expect("Simulation failed");
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("Krylov", &size_desc),
+            &size_desc,
+            |b, _| {
+                b.iter(|| {
+                    run_sim_for_benchmark::<KrylovSolver>(network.clone())
+                        .expect("Simulation failed");
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
@@ -22,8 +22,13 @@ impl Backend for CsvBackend {
     ///
     /// Returns a `BackendError` if there is a problem with the output.
     fn output(&self, results: SimulationResults) -> Result<(), BackendError> {
+        let sweep = results.is_temperature_sweep();
         let options = results.options;
-        for res in results.results.iter() {
+        for (idx, res) in results.results.iter().enumerate() {
+            // Each result set of a temperature sweep starts with the temperature it belongs to
+            if let Some(temp) = results.temperatures.get(idx).filter(|_| sweep) {
+                println!("Temp,{temp},°C");
+            }
             match res {
                 Sim::Op(res) => Self::output_op(res),
                 Sim::Dc(res) => Self::output_dc(res, options.clone()),
@@ -16,6 +16,9 @@ struct NetworkResponse {
 struct NetworkSimulationResult {
     r#type: String,
     variables: Vec<NetworkVariable>,
+    /// The circuit temperature in °C, set only for the results of a temperature sweep
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temp: Option<f64>,
 }
 
 #[derive(serde::Serialize)]
@@ -46,7 +49,9 @@ impl NetworkBackend {
     fn convert_results(&self, results: SimulationResults) -> Result<NetworkResponse, BackendError> {
         let mut network_results = Vec::new();
 
-        for sim_result in results.results {
+        let sweep = results.is_temperature_sweep();
+        for (idx, sim_result) in results.results.into_iter().enumerate() {
+            let temp = results.temperatures.get(idx).copied().filter(|_| sweep);
             match sim_result {
                 crate::sim::simulation_result::Sim::Op(vars) => {
                     let variables = vars
@@ -61,6 +66,7 @@ impl NetworkBackend {
                     network_results.push(NetworkSimulationResult {
                         r#type: "op".to_string(),
                         variables,
+                        temp,
                     });
                 }
                 crate::sim::simulation_result::Sim::Dc(dc_results) => {
@@ -78,6 +84,7 @@ impl NetworkBackend {
                         network_results.push(NetworkSimulationResult {
                             r#type: format!("dc_step_{}", step_idx),
                             variables: vars_converted,
+                            temp,
                         });
                     }
                 }
@@ -95,6 +102,7 @@ impl NetworkBackend {
                         network_results.push(NetworkSimulationResult {
                             r#type: format!("ac_{}", freq),
                             variables: vars_converted,
+                            temp,
                         });
                     }
                 }
@@ -112,6 +120,7 @@ impl NetworkBackend {
                         network_results.push(NetworkSimulationResult {
                             r#type: format!("tran_{}", time),
                             variables: vars_converted,
+                            temp,
                         });
                     }
                 }
@@ -177,8 +177,8 @@ impl PlotBackend {
         for _ in 0..var_count {
             series.push(Vec::new());
         }
-        for var in 0..var_count {
-            units.push(filtered_data[0][var].0.unit())
+        for (var, _) in filtered_data[0].iter().take(var_count) {
+            units.push(var.unit())
         }
         for var in 0..var_count {
             for step_data in filtered_data.iter() {
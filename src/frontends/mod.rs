@@ -15,6 +15,7 @@ use thiserror::Error;
 use crate::models::*;
 use crate::sim::commands::SimulationCommand;
 use crate::sim::options::SimulationOption;
+use crate::spot::Numeric;
 pub(crate) use kicad::KicadFrontend;
 pub(crate) use network::NetworkFrontend;
 pub(crate) use serde::SerdeFrontend;
@@ -61,6 +62,22 @@ pub enum FrontendError {
     #[diagnostic(help("{0}"))]
     ParseCommandError(String),
 
+    #[error("Invalid {command} command")]
+    #[diagnostic(help("{reason}"))]
+    InvalidCommand { command: String, reason: String },
+
+    #[error("Model {0} is not defined")]
+    #[diagnostic(help("Add a .model card with the name {0}"))]
+    ModelNotFound(String),
+
+    #[error("Invalid model {model}")]
+    #[diagnostic(help("{reason}"))]
+    InvalidModel { model: String, reason: String },
+
+    #[error("Invalid expression of {element}")]
+    #[diagnostic(help("{reason}"))]
+    InvalidExpression { element: String, reason: String },
+
     #[error("FileReadError")]
     #[diagnostic(help("{0}"))]
     FileReadError(String),
@@ -204,6 +221,42 @@ pub(crate) fn get_variable(
     Some(new_variable)
 }
 
+/// Looks up or creates the internal node `<element>#<node>` of an element, like the node behind
+/// the base resistance of a transistor.
+pub(crate) fn get_internal_variable(
+    element: &str,
+    node: &str,
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) -> Option<Variable> {
+    get_variable(&format!("{element}#{node}"), Unit::Volt, variables, var_map)
+}
+
+/// Checks the times of a transient analysis. The timesteps `tstep` and `tmax` must be positive
+/// and the output starts at `tstart >= 0` before the stop time `tstop`.
+pub(crate) fn check_tran_times(
+    tstep: Numeric,
+    tstop: Numeric,
+    tstart: Numeric,
+    tmax: Option<Numeric>,
+) -> Result<(), FrontendError> {
+    let reason = if tstep <= 0.0 {
+        format!("The timestep tstep = {tstep} must be positive")
+    } else if tstart < 0.0 {
+        format!("The start time tstart = {tstart} must not be negative")
+    } else if tstop <= tstart {
+        format!("The stop time tstop = {tstop} must be after the start time tstart = {tstart}")
+    } else if let Some(tmax) = tmax.filter(|&tmax| tmax <= 0.0) {
+        format!("The maximum timestep tmax = {tmax} must be positive")
+    } else {
+        return Ok(());
+    };
+    Err(FrontendError::InvalidCommand {
+        command: ".tran".into(),
+        reason,
+    })
+}
+
 /// Public function to create a Simulation from SPICE code for benchmarking
 /// This provides access to the SpiceParser for external benchmarks
 /// Parses directly from string without creating temporary files
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use super::serde::ProcessSerdeElement;
 use super::serde::SerdeCircuit;
 use super::serde::SerdeElement;
+use super::serde::SerdeFrontend;
 use super::serde::SerdeSimulation;
 use super::Element;
 use super::Frontend;
@@ -77,6 +78,24 @@ impl NetworkFrontend {
                 SerdeElement::Mosfet(ele) => {
                     ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
                 }
+                SerdeElement::Mos1(ele) => {
+                    ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+                }
+                SerdeElement::Bjt(ele) => {
+                    ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+                }
+                SerdeElement::Jfet(ele) => {
+                    ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+                }
+                SerdeElement::Switch(ele) => {
+                    ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+                }
+                SerdeElement::CSwitch(ele) => {
+                    ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+                }
+                SerdeElement::Behavioral(ele) => {
+                    ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+                }
                 SerdeElement::Gain(ele) => {
                     ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
                 }
@@ -123,16 +142,18 @@ impl NetworkFrontend {
                     ));
                 }
                 SerdeSimulation::Tran(tran) => {
-                    commands.push(SimulationCommand::Tran(tran.tstep(), tran.tend()));
+                    SerdeFrontend::process_tran(&mut commands, tran)?;
                 }
             }
         }
 
         // Process options
         for option in circuit.options {
-            options.push(SimulationOption::Out(vec![Arc::from(option.out)]));
+            SerdeFrontend::process_option(&mut options, option);
         }
 
+        SerdeFrontend::process_node_voltages(&mut options, circuit.ic, circuit.nodeset);
+
         Ok(Simulation {
             elements,
             commands,
@@ -3,16 +3,20 @@ use std::fs::File;
 use std::io::Read;
 use std::sync::Arc;
 
-use super::{Element, Frontend, FrontendError, Simulation};
+use super::{check_tran_times, Element, Frontend, FrontendError, Simulation};
 
+use crate::models::behavioral::serde::SerdeBehavioral;
+use crate::models::bjt::serde::SerdeBjt;
 use crate::models::capacitor::serde::SerdeCapacitor;
 use crate::models::controlled_sources::serde::{SerdeCCCS, SerdeCCVS, SerdeVCCS, SerdeVCVS};
 use crate::models::diode::serde::SerdeDiode;
 use crate::models::gain::serde::SerdeGain;
 use crate::models::inductor::serde::SerdeInductor;
 use crate::models::isource::serde::SerdeISource;
-use crate::models::mosfet::serde::SerdeMos0;
+use crate::models::jfet::serde::SerdeJfet;
+use crate::models::mosfet::serde::{SerdeMos0, SerdeMos1};
 use crate::models::resistor::serde::SerdeResistor;
+use crate::models::switch::serde::{SerdeCSwitch, SerdeSwitch};
 use crate::models::vsource::serde::SerdeVSource;
 use crate::models::vsource_sine::serde::SerdeVSourceSin;
 use crate::models::vsource_step::serde::SerdeVSourceStep;
@@ -20,7 +24,7 @@ use crate::models::SerdeCoupledInductors;
 use crate::models::Variable;
 use crate::sim::commands::ACMode;
 use crate::sim::commands::SimulationCommand;
-use crate::sim::options::SimulationOption;
+use crate::sim::options::{DumpAnalysis, IntegrationMethod, OpStrategy, SimulationOption};
 use crate::spot::*;
 use serde::{Deserialize, Serialize};
 
@@ -49,6 +53,18 @@ pub enum SerdeElement {
     Diode(SerdeDiode),
     #[serde(rename = "mosfet")]
     Mosfet(SerdeMos0),
+    #[serde(rename = "mos1")]
+    Mos1(SerdeMos1),
+    #[serde(rename = "bjt")]
+    Bjt(SerdeBjt),
+    #[serde(rename = "jfet")]
+    Jfet(SerdeJfet),
+    #[serde(rename = "switch")]
+    Switch(SerdeSwitch),
+    #[serde(rename = "cswitch")]
+    CSwitch(SerdeCSwitch),
+    #[serde(rename = "bsource")]
+    Behavioral(SerdeBehavioral),
     #[serde(rename = "gain")]
     Gain(SerdeGain),
     #[serde(rename = "vcvs")]
@@ -137,13 +153,20 @@ impl SerdeAC {
     }
 }
 
-/// Configuration for an AC analysis simulation.
-/// Specifies the start frequency, stop frequency, and number of steps.
+/// Configuration for a transient analysis simulation.
+/// Specifies the output timestep, the stop time and optionally the start time of the output,
+/// the maximum internal timestep and whether initial conditions are used instead of an OP.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "type", rename = "simulations")]
 pub struct SerdeTran {
     tstep: Numeric,
     tend: Numeric,
+    #[serde(default)]
+    tstart: Numeric,
+    #[serde(default)]
+    tmax: Option<Numeric>,
+    #[serde(default)]
+    uic: bool,
 }
 
 impl SerdeTran {
@@ -154,15 +177,98 @@ impl SerdeTran {
     pub fn tend(&self) -> Numeric {
         self.tend
     }
+
+    pub fn tstart(&self) -> Numeric {
+        self.tstart
+    }
+
+    pub fn tmax(&self) -> Option<Numeric> {
+        self.tmax
+    }
+
+    pub fn uic(&self) -> bool {
+        self.uic
+    }
 }
 
-/// Represents simulation output options.
-/// Currently only supports specifying output variables.
+/// Represents simulation options.
+/// Supports specifying output variables and the integration method.
 #[derive(Default, Debug, Deserialize, Serialize)]
 #[serde(rename = "option")]
 pub struct SerdeOption {
     /// The output variable or node to save.
-    pub out: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub out: Option<String>,
+    /// The integration method of transient analyses (like `.options method=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<IntegrationMethod>,
+    /// The strategies tried to find the operating point (like `.options opstrategy=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opstrategy: Option<Vec<OpStrategy>>,
+    /// The number of gmin stepping decades (like `.options gminsteps=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gminsteps: Option<usize>,
+    /// The number of source stepping steps (like `.options srcsteps=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srcsteps: Option<usize>,
+    /// The maximum number of pseudo transient steps (like `.options ptransteps=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ptransteps: Option<usize>,
+    /// The relative convergence tolerance (like `.options reltol=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reltol: Option<Numeric>,
+    /// The absolute current tolerance (like `.options abstol=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abstol: Option<Numeric>,
+    /// The absolute voltage tolerance (like `.options vntol=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vntol: Option<Numeric>,
+    /// The minimum conductance of nonlinear junctions (like `.options gmin=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gmin: Option<Numeric>,
+    /// The iteration limit of operating points (like `.options itl1=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub itl1: Option<usize>,
+    /// The iteration limit of transient timepoints (like `.options itl4=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub itl4: Option<usize>,
+    /// The circuit temperature in degrees Celsius (like `.options temp=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp: Option<Numeric>,
+    /// The circuit temperatures in degrees Celsius, all analyses run once per temperature
+    /// (like `.temp`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temps: Option<Vec<Numeric>>,
+    /// The nominal temperature of the device parameters in degrees Celsius
+    /// (like `.options tnom=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tnom: Option<Numeric>,
+    /// The condition number above which the solver warns about an ill-conditioned matrix
+    /// (like `.options condlimit=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condlimit: Option<Numeric>,
+    /// The analysis whose assembled systems are written to Matrix Market files
+    /// (like `.options dump=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dump: Option<DumpAnalysis>,
+    /// The Newton iteration of the written systems (like `.options dumpiter=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dumpiter: Option<usize>,
+    /// The time of the written transient timestep in seconds (like `.options dumptime=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dumptime: Option<Numeric>,
+    /// The path prefix of the written files (like `.options dumpprefix=`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dumpprefix: Option<String>,
+}
+
+/// Represents a node voltage of an initial condition or nodeset.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SerdeNodeVoltage {
+    /// The node the voltage is assigned to.
+    pub node: String,
+    /// The voltage of the node.
+    pub value: Numeric,
 }
 
 /// Represents a circuit defined in a serialization format (e.g., YAML or JSON).
@@ -174,6 +280,12 @@ pub struct SerdeCircuit {
     pub simulations: Vec<SerdeSimulation>,
     #[serde(default)]
     pub options: Vec<SerdeOption>,
+    /// Node voltages at the start of a transient analysis (like `.ic`).
+    #[serde(default)]
+    pub ic: Vec<SerdeNodeVoltage>,
+    /// Initial guesses for the operating point (like `.nodeset`).
+    #[serde(default)]
+    pub nodeset: Vec<SerdeNodeVoltage>,
 }
 
 /// Frontend for parsing and processing circuit definitions from serialized formats (YAML/JSON).
@@ -275,6 +387,18 @@ impl SerdeFrontend {
                 SerdeElement::Mosfet(ele) => {
                     ele.process(&mut variables, &mut elements, &mut var_map)
                 }
+                SerdeElement::Mos1(ele) => ele.process(&mut variables, &mut elements, &mut var_map),
+                SerdeElement::Bjt(ele) => ele.process(&mut variables, &mut elements, &mut var_map),
+                SerdeElement::Jfet(ele) => ele.process(&mut variables, &mut elements, &mut var_map),
+                SerdeElement::Switch(ele) => {
+                    ele.process(&mut variables, &mut elements, &mut var_map)
+                }
+                SerdeElement::CSwitch(ele) => {
+                    ele.process(&mut variables, &mut elements, &mut var_map)
+                }
+                SerdeElement::Behavioral(ele) => {
+                    ele.process(&mut variables, &mut elements, &mut var_map)
+                }
                 SerdeElement::Gain(ele) => ele.process(&mut variables, &mut elements, &mut var_map),
                 SerdeElement::VCVS(ele) => ele.process(&mut variables, &mut elements, &mut var_map),
                 SerdeElement::VCCS(ele) => ele.process(&mut variables, &mut elements, &mut var_map),
@@ -288,14 +412,16 @@ impl SerdeFrontend {
                 SerdeSimulation::OP => Self::process_op(&mut commands),
                 SerdeSimulation::DC(serdedc) => Self::process_dc(&mut commands, serdedc),
                 SerdeSimulation::AC(serdeac) => Self::process_ac(&mut commands, serdeac),
-                SerdeSimulation::Tran(serdetran) => Self::process_tran(&mut commands, serdetran),
+                SerdeSimulation::Tran(serdetran) => Self::process_tran(&mut commands, serdetran)?,
             };
         }
 
         for option in circuit.options {
-            Self::process_out(&mut options, option);
+            Self::process_option(&mut options, option);
         }
 
+        Self::process_node_voltages(&mut options, circuit.ic, circuit.nodeset);
+
         Ok(Self {
             commands,
             options,
@@ -331,13 +457,108 @@ impl SerdeFrontend {
     }
 
     /// Processes a transient analysis simulation.
-    fn process_tran(commands: &mut Vec<SimulationCommand>, serdetran: SerdeTran) {
-        commands.push(SimulationCommand::Tran(serdetran.tstep, serdetran.tend))
+    pub(crate) fn process_tran(
+        commands: &mut Vec<SimulationCommand>,
+        serdetran: SerdeTran,
+    ) -> Result<(), FrontendError> {
+        check_tran_times(
+            serdetran.tstep,
+            serdetran.tend,
+            serdetran.tstart,
+            serdetran.tmax,
+        )?;
+        commands.push(SimulationCommand::Tran(
+            serdetran.tstep,
+            serdetran.tend,
+            serdetran.tstart,
+            serdetran.tmax,
+            serdetran.uic,
+        ));
+        Ok(())
+    }
+
+    /// Processes output and integration options.
+    pub(crate) fn process_option(options: &mut Vec<SimulationOption>, option: SerdeOption) {
+        if let Some(out) = option.out {
+            options.push(SimulationOption::Out(vec![Arc::from(out.as_str())]));
+        }
+        if let Some(method) = option.method {
+            options.push(SimulationOption::IntegrationMethod(method));
+        }
+        if let Some(strategies) = option.opstrategy {
+            options.push(SimulationOption::OpStrategies(strategies));
+        }
+        if let Some(steps) = option.gminsteps {
+            options.push(SimulationOption::GminSteps(steps));
+        }
+        if let Some(steps) = option.srcsteps {
+            options.push(SimulationOption::SourceSteps(steps));
+        }
+        if let Some(steps) = option.ptransteps {
+            options.push(SimulationOption::PseudoTransientSteps(steps));
+        }
+        if let Some(reltol) = option.reltol {
+            options.push(SimulationOption::RelTol(reltol));
+        }
+        if let Some(abstol) = option.abstol {
+            options.push(SimulationOption::AbsTol(abstol));
+        }
+        if let Some(vntol) = option.vntol {
+            options.push(SimulationOption::VnTol(vntol));
+        }
+        if let Some(gmin) = option.gmin {
+            options.push(SimulationOption::Gmin(gmin));
+        }
+        if let Some(iterations) = option.itl1 {
+            options.push(SimulationOption::OpMaxIter(iterations));
+        }
+        if let Some(iterations) = option.itl4 {
+            options.push(SimulationOption::TranMaxIter(iterations));
+        }
+        if let Some(temp) = option.temp {
+            options.push(SimulationOption::Temperature(temp));
+        }
+        if let Some(temps) = option.temps {
+            options.push(SimulationOption::Temperatures(temps));
+        }
+        if let Some(tnom) = option.tnom {
+            options.push(SimulationOption::NominalTemperature(tnom));
+        }
+        if let Some(limit) = option.condlimit {
+            options.push(SimulationOption::ConditionLimit(limit));
+        }
+        if let Some(analysis) = option.dump {
+            options.push(SimulationOption::DumpAnalysis(analysis));
+        }
+        if let Some(iteration) = option.dumpiter {
+            options.push(SimulationOption::DumpIteration(iteration));
+        }
+        if let Some(time) = option.dumptime {
+            options.push(SimulationOption::DumpTime(time));
+        }
+        if let Some(prefix) = option.dumpprefix {
+            options.push(SimulationOption::DumpPrefix(Arc::from(prefix)));
+        }
     }
 
-    /// Processes output options.
-    fn process_out(options: &mut Vec<SimulationOption>, option: SerdeOption) {
-        options.push(SimulationOption::Out(vec![Arc::from(option.out.as_str())]))
+    /// Processes the initial conditions and nodesets of a circuit.
+    pub(crate) fn process_node_voltages(
+        options: &mut Vec<SimulationOption>,
+        ic: Vec<SerdeNodeVoltage>,
+        nodeset: Vec<SerdeNodeVoltage>,
+    ) {
+        let convert = |values: Vec<SerdeNodeVoltage>| {
+            values
+                .into_iter()
+                .map(|v| (Arc::from(v.node.as_str()), v.value))
+                .collect::<Vec<_>>()
+        };
+        if !ic.is_empty() {
+            options.push(SimulationOption::InitialConditions(convert(ic)));
+        }
+        if !nodeset.is_empty() {
+            options.push(SimulationOption::NodeSet(convert(nodeset)));
+        }
     }
 }
 
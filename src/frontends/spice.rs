@@ -4,21 +4,24 @@ use std::io::Read;
 use std::path::Path;
 use std::sync::Arc;
 
-use log::trace;
+use log::{trace, warn};
+use num::Zero;
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 
 use super::{
-    CCCSBundle, CCVSBundle, CapacitorBundle, CoupledInductorsBundle, DiodeBundle, Element,
-    GainBundle, ISourceBundle, InductorBundle, Mos0Bundle, ResistorBundle, VCCSBundle, VCVSBundle,
-    Variable,
+    BehavioralBundle, BjtBundle, CCCSBundle, CCVSBundle, CapacitorBundle, CoupledInductorsBundle,
+    DiodeBundle, Element, GainBundle, ISourceBundle, InductorBundle, JfetBundle, Mos0Bundle,
+    Mos1Bundle, ResistorBundle, SwitchBundle, VCCSBundle, VCVSBundle, Variable,
 };
-use crate::frontends::{Frontend, FrontendError, Simulation};
+use crate::frontends::{check_tran_times, Frontend, FrontendError, Simulation};
+use crate::models::behavioral::expression::UserFunction;
+use crate::models::behavioral::spice::process_function;
 use crate::models::vsource_sine::VSourceSinBundle;
 use crate::models::vsource_step::VSourceStepBundle;
 use crate::models::VSourceBundle;
 use crate::sim::commands::{ACMode, SimulationCommand};
-use crate::sim::options::SimulationOption;
+use crate::sim::options::{DumpAnalysis, IntegrationMethod, OpStrategy, SimulationOption};
 use crate::spot::*;
 
 #[derive(Parser, Debug)]
@@ -39,6 +42,8 @@ impl SpiceFrontend {
         let mut elements = Vec::new();
         let mut variables = Vec::new();
         let mut var_map = HashMap::new();
+        let mut models = Vec::new();
+        let mut functions = Vec::new();
 
         let parse_result = SpiceParser::parse(Rule::SPICE, spice_code)?
             .next()
@@ -54,9 +59,13 @@ impl SpiceFrontend {
                     &mut elements,
                     &mut variables,
                     &mut var_map,
+                    &mut models,
+                    &mut functions,
                 )?;
             }
         }
+        apply_models(&models, &mut elements, &mut variables, &mut var_map)?;
+        apply_functions(&functions, &mut elements, &mut variables, &mut var_map)?;
 
         // Ensure all element names are unique
         let mut names = HashSet::new();
@@ -92,6 +101,8 @@ impl Frontend for SpiceFrontend {
         let mut elements = Vec::new();
         let mut variables = Vec::new();
         let mut var_map = HashMap::new();
+        let mut models = Vec::new();
+        let mut functions = Vec::new();
 
         for pair in parse_result.into_inner() {
             if pair.as_rule() == Rule::DIRECTIVE {
@@ -102,9 +113,13 @@ impl Frontend for SpiceFrontend {
                     &mut elements,
                     &mut variables,
                     &mut var_map,
+                    &mut models,
+                    &mut functions,
                 )?
             }
         }
+        apply_models(&models, &mut elements, &mut variables, &mut var_map)?;
+        apply_functions(&functions, &mut elements, &mut variables, &mut var_map)?;
 
         trace!("Check Schematic!");
         // Ensure all element names are unique
@@ -130,6 +145,7 @@ impl SpiceFrontend {
         SpiceFrontend { pth }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_directive(
         &self,
         directive: Pair<Rule>,
@@ -138,19 +154,22 @@ impl SpiceFrontend {
         elements: &mut Vec<Element>,
         variables: &mut Vec<Variable>,
         var_map: &mut HashMap<Arc<str>, usize>,
+        models: &mut Vec<SpiceModel>,
+        functions: &mut Vec<UserFunction>,
     ) -> Result<(), FrontendError> {
         for inner in directive.into_inner() {
             match inner.as_rule() {
                 Rule::ELEMENT => self.process_element(inner, variables, elements, var_map)?,
-                Rule::COMMAND => {
-                    self.process_command(inner, commands, options, elements, variables, var_map)?
-                }
+                Rule::COMMAND => self.process_command(
+                    inner, commands, options, elements, variables, var_map, models, functions,
+                )?,
                 _ => {}
             }
         }
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_command(
         &self,
         command: Pair<Rule>,
@@ -159,6 +178,8 @@ impl SpiceFrontend {
         elements: &mut Vec<Element>,
         variables: &mut Vec<Variable>,
         var_map: &mut HashMap<Arc<str>, usize>,
+        models: &mut Vec<SpiceModel>,
+        functions: &mut Vec<UserFunction>,
     ) -> Result<(), FrontendError> {
         let command = command
             .into_inner()
@@ -170,15 +191,34 @@ impl SpiceFrontend {
             Rule::CMD_DC => self.process_dc(command, commands)?,
             Rule::CMD_AC => self.process_ac(command, commands)?,
             Rule::CMD_TRAN => self.process_tran(command, commands)?,
-            Rule::CMD_INCLUDE => {
-                self.process_include(command, commands, options, elements, variables, var_map)?
-            }
+            Rule::CMD_INCLUDE => self.process_include(
+                command, commands, options, elements, variables, var_map, models, functions,
+            )?,
+            Rule::CMD_MODEL => models.push(self.process_model(command)?),
+            Rule::CMD_FUNC => functions.push(process_function(command)?),
             Rule::CMD_OUT => self.process_out(command, options)?,
+            Rule::CMD_OPTIONS => self.process_options(command, options)?,
+            Rule::CMD_TEMP => {
+                let temps = command
+                    .into_inner()
+                    .map(|value| value.as_str().parse::<Numeric>())
+                    .collect::<Result<Vec<_>, _>>()?;
+                options.push(SimulationOption::Temperatures(temps));
+            }
+            Rule::CMD_IC => {
+                let values = self.process_node_voltages(command)?;
+                options.push(SimulationOption::InitialConditions(values));
+            }
+            Rule::CMD_NODESET => {
+                let values = self.process_node_voltages(command)?;
+                options.push(SimulationOption::NodeSet(values));
+            }
             _ => {}
         }
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_include(
         &self,
         command: Pair<Rule>,
@@ -187,6 +227,8 @@ impl SpiceFrontend {
         elements: &mut Vec<Element>,
         variables: &mut Vec<Variable>,
         var_map: &mut HashMap<Arc<str>, usize>,
+        models: &mut Vec<SpiceModel>,
+        functions: &mut Vec<UserFunction>,
     ) -> Result<(), FrontendError> {
         let current_path = Path::new(&self.pth)
             .parent()
@@ -237,7 +279,9 @@ impl SpiceFrontend {
 
         for pair in parse_result.into_inner() {
             if pair.as_rule() == Rule::DIRECTIVE {
-                self.process_directive(pair, commands, options, elements, variables, var_map)?
+                self.process_directive(
+                    pair, commands, options, elements, variables, var_map, models, functions,
+                )?
             }
         }
         Ok(())
@@ -403,7 +447,31 @@ impl SpiceFrontend {
             .parse::<Numeric>()
             .map_err(|_| FrontendError::ParseError("Invalid tstop value".into()))?;
 
-        commands.push(SimulationCommand::Tran(tstep, tstop));
+        let mut tstart = Numeric::zero();
+        let mut tmax = None;
+        let mut uic = false;
+        for pair in inner {
+            match pair.as_rule() {
+                Rule::CMD_TRAN_TSTART => {
+                    tstart = pair
+                        .as_str()
+                        .parse::<Numeric>()
+                        .map_err(|_| FrontendError::ParseError("Invalid tstart value".into()))?;
+                }
+                Rule::CMD_TRAN_TMAX => {
+                    tmax = Some(
+                        pair.as_str()
+                            .parse::<Numeric>()
+                            .map_err(|_| FrontendError::ParseError("Invalid tmax value".into()))?,
+                    );
+                }
+                Rule::CMD_TRAN_UIC => uic = true,
+                _ => {}
+            }
+        }
+
+        check_tran_times(tstep, tstop, tstart, tmax)?;
+        commands.push(SimulationCommand::Tran(tstep, tstop, tstart, tmax, uic));
         Ok(())
     }
 
@@ -427,6 +495,124 @@ impl SpiceFrontend {
         Ok(())
     }
 
+    /// Processes the `<Name>=<Value>` entries of `.options`.
+    /// Unknown options are ignored with a warning, as other simulators may support them.
+    fn process_options(
+        &self,
+        command: Pair<Rule>,
+        options: &mut Vec<SimulationOption>,
+    ) -> Result<(), FrontendError> {
+        for entry in command.into_inner() {
+            let mut inner = entry.into_inner();
+            let (Some(name), Some(value)) = (inner.next(), inner.next()) else {
+                return Err(FrontendError::ParseError("Malformed .options entry".into()));
+            };
+
+            match name.as_str().to_ascii_lowercase().as_str() {
+                "method" => {
+                    let method = value
+                        .as_str()
+                        .parse::<IntegrationMethod>()
+                        .map_err(FrontendError::ParseCommandError)?;
+                    options.push(SimulationOption::IntegrationMethod(method));
+                }
+                "opstrategy" => {
+                    let strategies = value
+                        .as_str()
+                        .split(',')
+                        .map(str::parse::<OpStrategy>)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(FrontendError::ParseCommandError)?;
+                    options.push(SimulationOption::OpStrategies(strategies));
+                }
+                "gminsteps" => options.push(SimulationOption::GminSteps(value.as_str().parse()?)),
+                "srcsteps" => options.push(SimulationOption::SourceSteps(value.as_str().parse()?)),
+                "ptransteps" => options.push(SimulationOption::PseudoTransientSteps(
+                    value.as_str().parse()?,
+                )),
+                "reltol" => options.push(SimulationOption::RelTol(value.as_str().parse()?)),
+                "abstol" => options.push(SimulationOption::AbsTol(value.as_str().parse()?)),
+                "vntol" => options.push(SimulationOption::VnTol(value.as_str().parse()?)),
+                "gmin" => options.push(SimulationOption::Gmin(value.as_str().parse()?)),
+                "itl1" => options.push(SimulationOption::OpMaxIter(value.as_str().parse()?)),
+                "itl4" => options.push(SimulationOption::TranMaxIter(value.as_str().parse()?)),
+                "temp" => options.push(SimulationOption::Temperature(value.as_str().parse()?)),
+                "tnom" => options.push(SimulationOption::NominalTemperature(
+                    value.as_str().parse()?,
+                )),
+                "condlimit" => {
+                    options.push(SimulationOption::ConditionLimit(value.as_str().parse()?))
+                }
+                "dump" => {
+                    let analysis = value
+                        .as_str()
+                        .parse::<DumpAnalysis>()
+                        .map_err(FrontendError::ParseCommandError)?;
+                    options.push(SimulationOption::DumpAnalysis(analysis));
+                }
+                "dumpiter" => {
+                    options.push(SimulationOption::DumpIteration(value.as_str().parse()?))
+                }
+                "dumptime" => options.push(SimulationOption::DumpTime(value.as_str().parse()?)),
+                "dumpprefix" => {
+                    options.push(SimulationOption::DumpPrefix(Arc::from(value.as_str())))
+                }
+                other => warn!("Ignoring unsupported option '{other}'"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes a `.model` card with its `<Name>=<Value>` parameters.
+    fn process_model(&self, command: Pair<Rule>) -> Result<SpiceModel, FrontendError> {
+        let mut inner = command.into_inner();
+        let (Some(name), Some(kind)) = (inner.next(), inner.next()) else {
+            return Err(FrontendError::ParseError("Malformed .model card".into()));
+        };
+        let parameters = inner
+            .map(|parameter| {
+                let mut inner = parameter.into_inner();
+                let (Some(name), Some(value)) = (inner.next(), inner.next()) else {
+                    return Err(FrontendError::ParseError(
+                        "Malformed .model parameter".into(),
+                    ));
+                };
+                Ok((name.as_str().to_ascii_lowercase(), value.as_str().parse()?))
+            })
+            .collect::<Result<_, FrontendError>>()?;
+
+        Ok(SpiceModel {
+            name: Arc::from(name.as_str()),
+            kind: Arc::from(kind.as_str()),
+            parameters,
+        })
+    }
+
+    /// Processes the `V(<Node>)=<Value>` assignments of `.ic` and `.nodeset`.
+    fn process_node_voltages(
+        &self,
+        command: Pair<Rule>,
+    ) -> Result<Vec<(Arc<str>, Numeric)>, FrontendError> {
+        command
+            .into_inner()
+            .map(|assignment| {
+                let mut inner = assignment.into_inner();
+                let node = inner
+                    .next()
+                    .ok_or_else(|| FrontendError::ParseError("Missing node in V(...)".into()))?;
+                let value = inner
+                    .next()
+                    .ok_or_else(|| FrontendError::ParseError("Missing value in V(...)".into()))?
+                    .as_str()
+                    .parse::<Numeric>()
+                    .map_err(|_| {
+                        FrontendError::ParseError(format!("Invalid value for V({})", node.as_str()))
+                    })?;
+                Ok((Arc::from(node.as_str()), value))
+            })
+            .collect()
+    }
+
     fn process_element(
         &self,
         element: Pair<Rule>,
@@ -455,7 +641,16 @@ impl SpiceFrontend {
                 CoupledInductorsBundle::process(element, variables, elements, var_map)?
             }
             Rule::ELE_DIODE => DiodeBundle::process(element, variables, elements, var_map)?,
+            Rule::ELE_MOS1 => Mos1Bundle::process(element, variables, elements, var_map)?,
             Rule::ELE_MOSFET => Mos0Bundle::process(element, variables, elements, var_map)?,
+            Rule::ELE_BJT => BjtBundle::process(element, variables, elements, var_map)?,
+            Rule::ELE_JFET => JfetBundle::process(element, variables, elements, var_map)?,
+            Rule::ELE_SWITCH | Rule::ELE_CSWITCH => {
+                SwitchBundle::process(element, variables, elements, var_map)?
+            }
+            Rule::ELE_BEHAVIORAL => {
+                BehavioralBundle::process(element, variables, elements, var_map)?
+            }
             Rule::ELE_GAIN => GainBundle::process(element, variables, elements, var_map)?,
             Rule::ELE_VCVS => VCVSBundle::process(element, variables, elements, var_map)?,
             Rule::ELE_VCCS => VCCSBundle::process(element, variables, elements, var_map)?,
@@ -473,6 +668,84 @@ impl From<pest::error::Error<Rule>> for FrontendError {
     }
 }
 
+/// A `.model` card, which holds the device parameters of the elements referring to its name.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SpiceModel {
+    pub(crate) name: Arc<str>,
+    /// The device type, like `NPN` or `PNP`
+    pub(crate) kind: Arc<str>,
+    /// The parameters with their lowercase names
+    pub(crate) parameters: Vec<(String, Numeric)>,
+}
+
+/// Elements, which take their device parameters from a `.model` card.
+pub(crate) trait ApplySpiceModel {
+    /// Returns the name of the model the element refers to.
+    fn model_name(&self) -> Option<Arc<str>>;
+
+    /// Applies the parameters of `model` to the element and creates its internal nodes.
+    fn apply_model(
+        &mut self,
+        model: &SpiceModel,
+        variables: &mut Vec<Variable>,
+        var_map: &mut HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError>;
+}
+
+/// Applies the `.model` cards to the elements after parsing, as a card may follow the elements
+/// referring to it. The names are case-insensitive and later cards replace earlier ones.
+fn apply_models(
+    models: &[SpiceModel],
+    elements: &mut [Element],
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) -> Result<(), FrontendError> {
+    for element in elements {
+        match element {
+            Element::Bjt(ele) => apply_model(ele.as_mut(), models, variables, var_map)?,
+            Element::Diode(ele) => apply_model(ele, models, variables, var_map)?,
+            Element::Mos1(ele) => apply_model(ele, models, variables, var_map)?,
+            Element::Jfet(ele) => apply_model(ele, models, variables, var_map)?,
+            Element::Switch(ele) => apply_model(ele, models, variables, var_map)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Defines the expressions of the behavioral sources with the `.func` definitions after parsing,
+/// as a definition may follow the sources using it.
+fn apply_functions(
+    functions: &[UserFunction],
+    elements: &mut [Element],
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) -> Result<(), FrontendError> {
+    for element in elements {
+        if let Element::Behavioral(ele) = element {
+            ele.define_expression(functions, variables, var_map)?;
+        }
+    }
+    Ok(())
+}
+
+fn apply_model<E: ApplySpiceModel>(
+    element: &mut E,
+    models: &[SpiceModel],
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) -> Result<(), FrontendError> {
+    let Some(name) = element.model_name() else {
+        return Ok(());
+    };
+    let model = models
+        .iter()
+        .rev()
+        .find(|model| model.name.eq_ignore_ascii_case(&name))
+        .ok_or_else(|| FrontendError::ModelNotFound(name.to_string()))?;
+    element.apply_model(model, variables, var_map)
+}
+
 pub(crate) trait ProcessSpiceElement {
     fn process(
         element: Pair<Rule>,
@@ -1,6 +1,7 @@
 //! Helper functions and structures for SPICE element parsing
 //! This module provides abstractions to reduce code duplication in element parsing
 
+use crate::frontends::spice::Rule;
 use crate::frontends::FrontendError;
 use crate::spot::Numeric;
 use pest::iterators::Pair;
@@ -97,6 +98,121 @@ impl<'a> SpiceElementParser<'a> {
         })
     }
 
+    /// Parse an optional named parameter like `IC=5`, returning the value of the parameter
+    pub fn parse_optional_parameter(
+        &mut self,
+        element_type: &str,
+        element_name: &str,
+        parameter_name: &str,
+    ) -> Result<Option<Numeric>, FrontendError> {
+        let Some(pair) = self.inner.next() else {
+            return Ok(None);
+        };
+        let value_str = pair
+            .into_inner()
+            .last()
+            .map(|v| v.as_str())
+            .unwrap_or_default();
+        value_str.parse::<Numeric>().map(Some).map_err(|_| {
+            FrontendError::ParseError(format!(
+                "Invalid {} in {} '{}': must be a number",
+                parameter_name, element_type, element_name
+            ))
+        })
+    }
+
+    /// Parse the optional model name, which follows the nodes of the element
+    pub fn parse_model(&mut self) -> Option<&'a str> {
+        self.inner
+            .next_if(|pair| pair.as_rule() == Rule::ELE_MODEL)
+            .map(|pair| pair.as_str())
+    }
+
+    /// Parse the next pair, which has to be of the rule `rule`, like the expression of a
+    /// behavioral source
+    pub fn parse_pair(
+        &mut self,
+        rule: Rule,
+        element_type: &str,
+        element_name: &str,
+        pair_name: &str,
+    ) -> Result<Pair<'a, Rule>, FrontendError> {
+        self.inner
+            .next_if(|pair| pair.as_rule() == rule)
+            .ok_or_else(|| {
+                FrontendError::ParseError(format!(
+                    "Missing {} in {} '{}'",
+                    pair_name, element_type, element_name
+                ))
+            })
+    }
+
+    /// Parse the optional initial state `ON` or `OFF` of a switch, returning whether it is on
+    pub fn parse_switch_state(&mut self) -> Option<bool> {
+        self.inner
+            .next_if(|pair| pair.as_rule() == Rule::SWITCH_STATE)
+            .map(|pair| pair.as_str().eq_ignore_ascii_case("on"))
+    }
+
+    /// Parse the optional inline diode model `D BV=<Value>`, returning its breakdown voltage
+    pub fn parse_diode_breakdown(
+        &mut self,
+        element_name: &str,
+    ) -> Result<Option<Numeric>, FrontendError> {
+        let Some(model) = self
+            .inner
+            .next_if(|pair| pair.as_rule() == Rule::DIODE_MODEL)
+        else {
+            return Ok(None);
+        };
+        let value = model
+            .into_inner()
+            .flat_map(|parameter| parameter.into_inner())
+            .map(|value| value.as_str())
+            .next()
+            .unwrap_or_default();
+        value.parse::<Numeric>().map(Some).map_err(|_| {
+            FrontendError::ParseError(format!(
+                "Invalid bv in diode '{}': must be a number",
+                element_name
+            ))
+        })
+    }
+
+    /// Parse the remaining named parameters like `TEMP=50`, returning their lowercase names
+    /// and values. Other remaining pairs, like model specifications, are skipped.
+    pub fn parse_named_parameters(
+        &mut self,
+        element_type: &str,
+        element_name: &str,
+    ) -> Result<Vec<(String, Numeric)>, FrontendError> {
+        let mut parameters = Vec::new();
+        for pair in self.inner.by_ref() {
+            if !matches!(
+                pair.as_rule(),
+                Rule::ELE_PAR_TEMP | Rule::ELE_PAR_TC | Rule::ELE_PAR_GEOMETRY
+            ) {
+                continue;
+            }
+            let mut inner = pair.into_inner();
+            let (Some(name), Some(value)) = (inner.next(), inner.next()) else {
+                return Err(FrontendError::ParseError(format!(
+                    "Malformed parameter in {} '{}'",
+                    element_type, element_name
+                )));
+            };
+            let name = name.as_str().to_ascii_lowercase();
+            let value = value.as_str().parse::<Numeric>().map_err(|_| {
+                FrontendError::ParseError(format!(
+                    "Invalid {} in {} '{}': must be a number",
+                    name, element_type, element_name
+                ))
+            })?;
+            parameters.push((name, value));
+        }
+        Ok(parameters)
+    }
+
     /// Parse remaining values as strings
     pub fn parse_remaining_values(&mut self) -> Vec<&'a str> {
         let mut result = Vec::new();
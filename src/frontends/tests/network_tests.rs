@@ -13,6 +13,7 @@ fn test_network_frontend_basic() {
                 node0: "n1".to_string(),
                 node1: "0".to_string(),
                 value: 10.0,
+                ..Default::default()
             }),
             SerdeElement::VSource(crate::models::vsource::serde::SerdeVSource {
                 name: "V1".to_string(),
@@ -24,8 +25,11 @@ fn test_network_frontend_basic() {
         ],
         simulations: vec![SerdeSimulation::OP],
         options: vec![SerdeOption {
-            out: "n1".to_string(),
+            out: Some("n1".to_string()),
+            ..Default::default()
         }],
+        ic: vec![],
+        nodeset: vec![],
     };
 
     // Test MessagePack serialization/deserialization
@@ -50,6 +54,7 @@ fn test_network_backend_basic() {
             Variable::new(Arc::from("n1"), crate::models::Unit::Volt, 0),
             5.0,
         )])],
+        temperatures: vec![27.0],
     };
 
     let serialized = rmp_serde::to_vec(&results).unwrap();
@@ -65,6 +70,7 @@ fn test_network_integration() {
                 node0: "n1".to_string(),
                 node1: "0".to_string(),
                 value: 100.0,
+                ..Default::default()
             }),
             SerdeElement::VSource(crate::models::vsource::serde::SerdeVSource {
                 name: "V1".to_string(),
@@ -76,8 +82,11 @@ fn test_network_integration() {
         ],
         simulations: vec![SerdeSimulation::OP],
         options: vec![SerdeOption {
-            out: "n1".to_string(),
+            out: Some("n1".to_string()),
+            ..Default::default()
         }],
+        ic: vec![],
+        nodeset: vec![],
     };
 
     // Test that the circuit can be processed through the Serde frontend
@@ -115,6 +124,7 @@ fn test_network_dc_analysis() {
                 node0: "n1".to_string(),
                 node1: "0".to_string(),
                 value: 100.0,
+                ..Default::default()
             }),
             SerdeElement::VSource(crate::models::vsource::serde::SerdeVSource {
                 name: "V1".to_string(),
@@ -128,8 +138,11 @@ fn test_network_dc_analysis() {
             crate::frontends::serde::SerdeDC::new("V1".to_string(), 0.0, 10.0, 2.5),
         )],
         options: vec![SerdeOption {
-            out: "n1".to_string(),
+            out: Some("n1".to_string()),
+            ..Default::default()
         }],
+        ic: vec![],
+        nodeset: vec![],
     };
 
     // Test that the DC circuit can be processed through the Serde frontend
@@ -125,6 +125,15 @@ fn process_tran_command() {
         .unwrap();
 }
 
+#[test]
+fn process_tran_command_full() {
+    let input = ".tran 0.1 1 0.5 0.01 uic";
+    SpiceParser::parse(Rule::SPICE, input)
+        .expect("unsuccessful parse")
+        .next()
+        .unwrap();
+}
+
 #[test]
 fn process_with_suffix1() {
     let input = ".tran 1e-3 1";
@@ -242,6 +251,15 @@ fn process_out_option3() {
         .unwrap();
 }
 
+#[test]
+fn process_options_command() {
+    let input = ".options method=trbdf2 reltol = 1e-3\n";
+    SpiceParser::parse(Rule::SPICE, input)
+        .expect("unsuccessful parse")
+        .next()
+        .unwrap();
+}
+
 #[test]
 fn process_sine() {
     let input = "V1 0 1 sine 0 10 10 0";
@@ -261,3 +279,21 @@ fn process_sine2() {
         .unwrap();
     println!("{test:?}")
 }
+
+#[test]
+fn process_controlled_forms() {
+    for (rule, input) in [
+        (Rule::ELE_VCVS, "E1 out 0 in 0 10"),
+        (Rule::ELE_VCVS, "E1 out 0 POLY(2) (a, 0) (b, 0) 0 1 1 0.5"),
+        (Rule::ELE_VCVS, "E1 out 0 TABLE {V(in)} = (-1, -10) (1, 10)"),
+        (Rule::ELE_VCCS, "G1 out 0 table V(in, ref) (-1 -1e-3), (1 1e-3)"),
+        (Rule::ELE_CCCS, "F1 out 0 VSENSE 2"),
+        (Rule::ELE_CCVS, "H1 out 0 poly(1) VSENSE 0 -2.5e3"),
+    ] {
+        let element = SpiceParser::parse(rule, input)
+            .expect("unsuccessful parse")
+            .next()
+            .unwrap();
+        assert_eq!(element.as_str(), input);
+    }
+}
@@ -1,9 +1,21 @@
 use std::sync::Arc;
 
 use crate::{
-    frontends::{DiodeBundle, ResistorBundle, VSourceBundle},
-    models::{Element, ISourceBundle, Unit, Variable},
+    frontends::{DiodeBundle, FrontendError, ResistorBundle, VSourceBundle},
+    models::behavioral::expression::Expression,
+    models::behavioral::spice::parse_expression_str,
+    models::bjt::{BjtOptions, BjtPolarity},
+    models::controlled_sources::nonlinear::{polynomial, table, voltage_control},
+    models::diode::DiodeOptions,
+    models::jfet::{JfetOptions, JfetPolarity},
+    models::mosfet::{Mos1Options, MosPolarity},
+    models::switch::{SwitchControl, SwitchOptions},
+    models::{
+        BehavioralBundle, BehavioralOutput, BjtBundle, DeviceTemperature, Element, ISourceBundle,
+        JfetBundle, Mos0Bundle, Mos1Bundle, SwitchBundle, Unit, Variable,
+    },
     sim::commands::{ACMode, SimulationCommand},
+    sim::options::{IntegrationMethod, OpStrategy, SimulationOption},
     Frontend, Simulation,
 };
 
@@ -743,6 +755,168 @@ fn parse_minimal_tran() {
     println!("{variables:?}");
 }
 
+#[test]
+fn parse_full_tran() {
+    let main_path = "src/frontends/tests/spice_files/parse_tran_full.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation { commands, .. } = parser.simulation().unwrap();
+
+    assert_eq!(
+        commands,
+        vec![SimulationCommand::Tran(1e-3, 1.0, 0.5, Some(1e-4), true)]
+    );
+}
+
+#[test]
+fn parse_tran_invalid_times() {
+    // A zero tstep, tstart after tstop and a zero tmax
+    for tran in [
+        ".tran 0 1e-3",
+        ".tran 1e-6 1e-3 2e-3",
+        ".tran 1e-6 1e-3 0 0",
+    ] {
+        let result = SpiceFrontend::parse_spice_code(&format!("R1 1 0 1000\n{tran}\n"));
+        assert!(
+            matches!(result, Err(FrontendError::InvalidCommand { .. })),
+            "{tran} was accepted"
+        );
+    }
+
+    // The grammar has no negative tstart
+    let result = SpiceFrontend::parse_spice_code("R1 1 0 1000\n.tran 1e-6 1e-3 -1e-3\n");
+    assert!(matches!(result, Err(FrontendError::PestError(_))));
+}
+
+#[test]
+fn parse_initial_conditions() {
+    let main_path = "src/frontends/tests/spice_files/parse_ic.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        options, elements, ..
+    } = parser.simulation().unwrap();
+
+    match (&elements[1], &elements[2]) {
+        (Element::Capacitor(cap), Element::Inductor(ind)) => {
+            assert_eq!(cap.initial_condition(), Some(3.0));
+            assert_eq!(ind.initial_condition(), Some(-0.5));
+        }
+        _ => panic!("Expected a capacitor and an inductor"),
+    }
+    assert_eq!(
+        options,
+        vec![
+            SimulationOption::InitialConditions(vec![
+                (Arc::from("2"), 5.0),
+                (Arc::from("1"), -1.0)
+            ]),
+            SimulationOption::NodeSet(vec![(Arc::from("1"), 0.5)]),
+        ]
+    );
+}
+
+#[test]
+fn parse_options_method() {
+    let main_path = "src/frontends/tests/spice_files/parse_options.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation { options, .. } = parser.simulation().unwrap();
+
+    assert_eq!(
+        options,
+        vec![SimulationOption::IntegrationMethod(IntegrationMethod::Gear)]
+    );
+}
+
+#[test]
+fn parse_options_unknown_method() {
+    let main_path = "src/frontends/tests/spice_files/parse_options_invalid.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    assert!(matches!(
+        parser.simulation(),
+        Err(FrontendError::ParseCommandError(_))
+    ));
+}
+
+#[test]
+fn parse_options_op_strategies() {
+    let main_path = "src/frontends/tests/spice_files/parse_options_op.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation { options, .. } = parser.simulation().unwrap();
+
+    assert_eq!(
+        options,
+        vec![
+            SimulationOption::OpStrategies(vec![
+                OpStrategy::SourceStepping,
+                OpStrategy::PseudoTransient
+            ]),
+            SimulationOption::SourceSteps(20),
+            SimulationOption::PseudoTransientSteps(50),
+            SimulationOption::GminSteps(5),
+        ]
+    );
+}
+
+#[test]
+fn parse_options_tolerances() {
+    let main_path = "src/frontends/tests/spice_files/parse_options_tolerances.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation { options, .. } = parser.simulation().unwrap();
+
+    assert_eq!(
+        options,
+        vec![
+            SimulationOption::RelTol(1e-4),
+            SimulationOption::AbsTol(1e-10),
+            SimulationOption::VnTol(1e-5),
+            SimulationOption::Gmin(1e-13),
+            SimulationOption::OpMaxIter(200),
+            SimulationOption::TranMaxIter(20),
+            SimulationOption::Temperature(85.0),
+            SimulationOption::ConditionLimit(1e12),
+        ]
+    );
+}
+
+#[test]
+fn parse_temperature() {
+    let main_path = "src/frontends/tests/spice_files/parse_temperature.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        options,
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    assert_eq!(
+        options,
+        vec![
+            SimulationOption::Temperatures(vec![0.0, 27.0, 100.0]),
+            SimulationOption::NominalTemperature(25.0),
+        ]
+    );
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let mut resistor = ResistorBundle::new(Arc::from("R1"), node("1"), node("2"), 1000.0);
+    resistor.set_temperature_coefficients(1e-3, 1e-6);
+    resistor.set_temperature(DeviceTemperature::new(None, Some(5.0)));
+    let mut diode = DiodeBundle::new(Arc::from("D1"), node("2"), None, None);
+    diode.set_temperature(DeviceTemperature::new(Some(50.0), None));
+    let mut mosfet = Mos0Bundle::new(Arc::from("M1"), node("1"), node("2"), None, None);
+    mosfet.set_temperature(DeviceTemperature::new(None, Some(-10.0)));
+
+    assert_eq!(elements[1], Element::Resistor(resistor));
+    assert_eq!(elements[2], Element::Diode(diode));
+    assert_eq!(elements[3], Element::Mos0(mosfet));
+}
+
 #[test]
 fn parse_gain() {
     let main_path = "src/frontends/tests/spice_files/parse_gain.cir";
@@ -777,3 +951,388 @@ fn parse_sine() {
     println!("{elements:?}");
     println!("{variables:?}");
 }
+
+#[test]
+fn parse_bjt() {
+    let main_path = "src/frontends/tests/spice_files/parse_bjt.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let mut options = BjtOptions::default();
+    for (name, value) in [("bf", 200.0), ("vaf", 50.0), ("rb", 100.0)] {
+        options.set_parameter(name, value);
+    }
+    let mut npn = BjtBundle::new(Arc::from("Q1"), node("1"), node("2"), None, Some(options));
+    npn.set_model(Arc::from("qmod"));
+    npn.set_temperature(DeviceTemperature::new(Some(50.0), None));
+    // The base resistance adds an internal node
+    npn.set_internal_nodes(node("Q1#base"), None, None);
+
+    let mut options = BjtOptions::default().with_polarity(BjtPolarity::Pnp);
+    options.set_parameter("is", 1e-15);
+    let mut pnp = BjtBundle::new(Arc::from("Q2"), None, node("2"), node("1"), Some(options));
+    pnp.set_model(Arc::from("QPNP"));
+
+    assert!(node("Q1#base").is_some());
+    assert_eq!(elements[1], Element::Bjt(Box::new(npn)));
+    assert_eq!(elements[2], Element::Bjt(Box::new(pnp)));
+}
+
+#[test]
+fn parse_bjt_unknown_model() {
+    let result = SpiceFrontend::parse_spice_code("V1 0 1 5\nQ1 1 1 0 QMOD\n.op\n");
+
+    assert!(matches!(result, Err(FrontendError::ModelNotFound(model)) if model == "QMOD"));
+}
+
+#[test]
+fn parse_bjt_invalid_model_type() {
+    let result =
+        SpiceFrontend::parse_spice_code("V1 0 1 5\nQ1 1 1 0 QMOD\n.model QMOD D (IS=1e-14)\n.op\n");
+
+    assert!(matches!(result, Err(FrontendError::InvalidModel { .. })));
+}
+
+#[test]
+fn parse_mos1() {
+    let main_path = "src/frontends/tests/spice_files/parse_mos1.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let mut options = Mos1Options::default();
+    for (name, value) in [("vto", 0.7), ("kp", 1e-4), ("lambda", 0.02)] {
+        options.set_parameter(name, value);
+    }
+    let mut nmos = Mos1Bundle::new(
+        Arc::from("M1"),
+        node("2"),
+        node("3"),
+        None,
+        None,
+        Some(options),
+    );
+    nmos.set_model(Arc::from("NMOD"));
+    nmos.set_geometry(10e-6, 1e-6);
+
+    let mut options = Mos1Options::default().with_polarity(MosPolarity::Pmos);
+    for (name, value) in [("vto", -0.7), ("kp", 4e-5), ("gamma", 0.4)] {
+        options.set_parameter(name, value);
+    }
+    let mut pmos = Mos1Bundle::new(
+        Arc::from("M2"),
+        node("2"),
+        node("3"),
+        node("1"),
+        node("1"),
+        Some(options),
+    );
+    pmos.set_model(Arc::from("pmod"));
+    pmos.set_geometry(20e-6, 1e-6);
+    pmos.set_temperature(DeviceTemperature::new(Some(50.0), None));
+
+    assert_eq!(elements[1], Element::Mos1(nmos));
+    assert_eq!(elements[2], Element::Mos1(pmos));
+    // The three-node syntax still creates the simple MOSFET
+    assert!(matches!(elements[3], Element::Mos0(_)));
+}
+
+#[test]
+fn parse_mos1_unsupported_level() {
+    let result = SpiceFrontend::parse_spice_code(
+        "V1 0 1 5\nM1 1 1 0 0 NMOD\n.model NMOD NMOS (LEVEL=3)\n.op\n",
+    );
+
+    assert!(matches!(result, Err(FrontendError::InvalidModel { .. })));
+}
+
+#[test]
+fn parse_diode_model() {
+    let main_path = "src/frontends/tests/spice_files/parse_diode_model.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let mut options = DiodeOptions::default();
+    for (name, value) in [("is", 1e-15), ("rs", 10.0), ("cjo", 2e-12), ("tt", 5e-9)] {
+        options.set_parameter(name, value);
+    }
+    let mut d1 = DiodeBundle::new(Arc::from("D1"), node("1"), node("2"), Some(options));
+    d1.set_model(Arc::from("DMOD"));
+    d1.set_temperature(DeviceTemperature::new(Some(50.0), None));
+    // The series resistance adds an internal anode
+    d1.set_internal_node(node("D1#anode"));
+
+    let d2 = DiodeBundle::new(
+        Arc::from("D2"),
+        None,
+        node("2"),
+        Some(DiodeOptions::default().with_breakdown(5.1, 1e-3)),
+    );
+
+    let mut options = DiodeOptions::default();
+    options.set_parameter("cjo", 1e-11);
+    options.set_parameter("m", 0.4);
+    let mut d3 = DiodeBundle::new(Arc::from("D3"), node("2"), None, Some(options));
+    d3.set_model(Arc::from("dvar"));
+
+    assert!(node("D1#anode").is_some());
+    assert_eq!(elements[1], Element::Diode(d1));
+    assert_eq!(elements[2], Element::Diode(d2));
+    assert_eq!(elements[3], Element::Diode(d3));
+}
+
+#[test]
+fn parse_diode_invalid_model_type() {
+    let result =
+        SpiceFrontend::parse_spice_code("V1 0 1 5\nD1 1 0 DMOD\n.model DMOD NPN (BF=100)\n.op\n");
+
+    assert!(matches!(result, Err(FrontendError::InvalidModel { .. })));
+}
+
+#[test]
+fn parse_element_without_model_before_next_line() {
+    // The optional model name must not take the name of the element on the next line
+    let simulation =
+        SpiceFrontend::parse_spice_code("V1 0 1 5\nQ1 1 1 0\nR1 1 0 1000\n.op\n").unwrap();
+
+    assert_eq!(simulation.elements.len(), 3);
+}
+
+#[test]
+fn parse_jfet() {
+    let main_path = "src/frontends/tests/spice_files/parse_jfet.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let mut options = JfetOptions::default();
+    for (name, value) in [("vto", -1.5), ("beta", 2e-3), ("rd", 10.0)] {
+        options.set_parameter(name, value);
+    }
+    let mut njf = JfetBundle::new(Arc::from("J1"), node("1"), node("2"), None, Some(options));
+    njf.set_model(Arc::from("jmod"));
+    njf.set_temperature(DeviceTemperature::new(Some(50.0), None));
+    // The drain resistance adds an internal node
+    njf.set_internal_nodes(node("J1#drain"), None);
+
+    let mut options = JfetOptions::default().with_polarity(JfetPolarity::Pjf);
+    options.set_parameter("vto", 1.0);
+    let mut pjf = JfetBundle::new(Arc::from("J2"), None, node("2"), node("1"), Some(options));
+    pjf.set_model(Arc::from("JP"));
+
+    assert!(node("J1#drain").is_some());
+    assert_eq!(elements[1], Element::Jfet(njf));
+    assert_eq!(elements[2], Element::Jfet(pjf));
+}
+
+#[test]
+fn parse_jfet_invalid_model_type() {
+    let result =
+        SpiceFrontend::parse_spice_code("V1 0 1 5\nJ1 1 0 0 JMOD\n.model JMOD NMOS (VTO=1)\n.op\n");
+
+    assert!(matches!(result, Err(FrontendError::InvalidModel { .. })));
+}
+
+#[test]
+fn parse_switch() {
+    let main_path = "src/frontends/tests/spice_files/parse_switch.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let options = SwitchOptions::default()
+        .with_resistances(0.1, 1e6)
+        .with_threshold(2.5, 0.5);
+    let mut s1 = SwitchBundle::new(
+        Arc::from("S1"),
+        node("1"),
+        None,
+        SwitchControl::Voltage(node("c"), None),
+        Some(options),
+    );
+    s1.set_model(Arc::from("smod"));
+    s1.set_initial_state(false);
+
+    let options = SwitchOptions::default().with_threshold(1e-3, -2e-4);
+    let mut w1 = SwitchBundle::new(
+        Arc::from("W1"),
+        node("2"),
+        None,
+        SwitchControl::Current(node("VSENSE#branch")),
+        Some(options),
+    );
+    w1.set_model(Arc::from("WMOD"));
+    w1.set_initial_state(true);
+
+    assert_eq!(elements[2], Element::Switch(s1));
+    assert_eq!(elements[3], Element::Switch(w1));
+}
+
+#[test]
+fn parse_switch_invalid_model_type() {
+    // A current controlled switch needs a CSW model
+    let result =
+        SpiceFrontend::parse_spice_code("V1 0 1 5\nW1 1 0 V1 SMOD\n.model SMOD SW (VT=1)\n.op\n");
+
+    assert!(matches!(result, Err(FrontendError::InvalidModel { .. })));
+}
+
+#[test]
+fn parse_behavioral() {
+    let main_path = "src/frontends/tests/spice_files/parse_behavioral.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let expression = |text: &str| {
+        let mut expression = parse_expression_str(text).unwrap().define(&[]).unwrap();
+        expression.bind().unwrap();
+        expression
+    };
+    // The `.func` definition may follow the source using it
+    let b1 = BehavioralBundle::new(
+        Arc::from("B1"),
+        node("out"),
+        None,
+        BehavioralOutput::Voltage(node("B1#branch")),
+        expression("V(a) * V(a) * (V(a) - V(b))"),
+        vec![node("a"), node("b")],
+    );
+    let b2 = BehavioralBundle::new(
+        Arc::from("b2"),
+        node("out2"),
+        None,
+        BehavioralOutput::Current,
+        expression("limit(I(VSENSE), 0, 1e-3) + time"),
+        vec![node("VSENSE#branch")],
+    );
+
+    assert_eq!(elements[2], Element::Behavioral(b1));
+    assert_eq!(elements[3], Element::Behavioral(b2));
+}
+
+#[test]
+fn parse_controlled_nonlinear() {
+    let main_path = "src/frontends/tests/spice_files/parse_controlled_nonlinear.cir";
+    let parser = SpiceFrontend::new(main_path.to_string());
+
+    let Simulation {
+        elements,
+        variables,
+        ..
+    } = parser.simulation().unwrap();
+
+    let node = |name: &str| variables.iter().find(|var| *var.name() == *name).cloned();
+    let source = |name: &str, positive: &str, output, expression: Expression| {
+        let mut expression = expression.define(&[]).unwrap();
+        let inputs = expression
+            .bind()
+            .unwrap()
+            .into_iter()
+            .map(|(input, _)| node(&input))
+            .collect();
+        let source = BehavioralBundle::new(
+            Arc::from(name),
+            node(positive),
+            None,
+            output,
+            expression,
+            inputs,
+        );
+        Element::Behavioral(source)
+    };
+    let current = |source: &str| Expression::Current(Arc::from(source));
+
+    let e1 = source(
+        "E1",
+        "out1",
+        BehavioralOutput::Voltage(node("E1#branch")),
+        polynomial(
+            &[voltage_control("a", "0"), voltage_control("b", "0")],
+            &[0.0, 1.0, 1.0],
+        ),
+    );
+    let g1 = source(
+        "G1",
+        "out2",
+        BehavioralOutput::Current,
+        table(voltage_control("a", "b"), &[(-1.0, -1e-3), (1.0, 1e-3)]).unwrap(),
+    );
+    // A single coefficient of POLY(1) is the gain
+    let f1 = source(
+        "F1",
+        "out3",
+        BehavioralOutput::Current,
+        polynomial(&[current("VSENSE")], &[0.0, 2.5]),
+    );
+    let h1 = source(
+        "H1",
+        "out4",
+        BehavioralOutput::Voltage(node("H1#branch")),
+        polynomial(&[current("VSENSE"), current("V1")], &[1.0, 0.0, 0.0, 0.5]),
+    );
+
+    assert_eq!(elements[3..], [e1, g1, f1, h1]);
+}
+
+#[test]
+fn parse_controlled_nonlinear_invalid() {
+    let missing_coefficients = SpiceFrontend::parse_spice_code("E1 1 0 POLY(2) a 0 b\n.op\n");
+    let descending_table =
+        SpiceFrontend::parse_spice_code("G1 1 0 TABLE {V(2)} = (1, 0) (0, 1)\n.op\n");
+    let unknown_source = SpiceFrontend::parse_spice_code("F1 1 0 POLY(1) V9 2\n.op\n");
+
+    for result in [missing_coefficients, descending_table, unknown_source] {
+        assert!(matches!(
+            result,
+            Err(FrontendError::InvalidExpression { .. })
+        ));
+    }
+}
+
+#[test]
+fn parse_behavioral_invalid_expression() {
+    let unknown_function = SpiceFrontend::parse_spice_code("B1 1 0 V=foo(V(2))\n.op\n");
+    let unknown_source = SpiceFrontend::parse_spice_code("B1 1 0 I={2 * I(V9)}\n.op\n");
+
+    assert!(matches!(
+        unknown_function,
+        Err(FrontendError::InvalidExpression { .. })
+    ));
+    assert!(matches!(
+        unknown_source,
+        Err(FrontendError::InvalidExpression { .. })
+    ));
+}
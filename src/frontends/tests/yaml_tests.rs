@@ -17,3 +17,433 @@ fn process_full() {
     input.read_to_string(&mut input_string).unwrap();
     let _: SerdeCircuit = serde_yml::from_str(&input_string).unwrap();
 }
+
+#[test]
+fn process_tran_full() {
+    use crate::frontends::Frontend;
+    use crate::sim::commands::SimulationCommand;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/transient_full.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    assert_eq!(
+        sim.commands,
+        vec![SimulationCommand::Tran(1e-3, 1.0, 0.5, Some(1e-4), true)]
+    );
+}
+
+#[test]
+fn process_tran_invalid_times() {
+    use crate::frontends::{Frontend, FrontendError};
+
+    // A zero tstep, tstart after tstop, a negative tstart and a zero tmax
+    for times in [
+        "tstep: 0.0, tend: 1.0e-3",
+        "tstep: 1.0e-6, tend: 1.0e-3, tstart: 2.0e-3",
+        "tstep: 1.0e-6, tend: 1.0e-3, tstart: -1.0e-3",
+        "tstep: 1.0e-6, tend: 1.0e-3, tmax: 0.0",
+    ] {
+        let input = format!(
+            "elements:\n  - {{type: resistor, name: R1, node0: n1, node1: \"0\", value: 1.0}}\n\
+             simulations:\n  - {{type: tran, {times}}}\n"
+        );
+        let result = SerdeFrontend::try_new_from_string(input, SerdeFormat::Yaml)
+            .and_then(|frontend| frontend.simulation());
+        assert!(
+            matches!(result, Err(FrontendError::InvalidCommand { .. })),
+            "{times} was accepted"
+        );
+    }
+}
+
+#[test]
+fn process_initial_conditions() {
+    use std::sync::Arc;
+
+    use crate::frontends::Frontend;
+    use crate::models::Element;
+    use crate::sim::options::SimulationOption;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/initial_conditions.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    match &sim.elements[1] {
+        Element::Capacitor(cap) => assert_eq!(cap.initial_condition(), Some(2.0)),
+        _ => panic!("Expected a capacitor"),
+    }
+    assert_eq!(
+        sim.options,
+        vec![
+            SimulationOption::InitialConditions(vec![(Arc::from("n1"), 5.0)]),
+            SimulationOption::NodeSet(vec![(Arc::from("n1"), 1.0)]),
+        ]
+    );
+}
+
+#[test]
+fn process_integration_method() {
+    use std::sync::Arc;
+
+    use crate::frontends::Frontend;
+    use crate::sim::options::{IntegrationMethod, SimulationOption};
+
+    let mut input = File::open("src/frontends/tests/yaml_files/integration_method.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    assert_eq!(
+        sim.options,
+        vec![
+            SimulationOption::IntegrationMethod(IntegrationMethod::TrBdf2),
+            SimulationOption::Out(vec![Arc::from("n1")]),
+        ]
+    );
+}
+
+#[test]
+fn process_op_strategies() {
+    use crate::frontends::Frontend;
+    use crate::sim::options::{OpStrategy, SimulationOption};
+
+    let mut input = File::open("src/frontends/tests/yaml_files/op_strategies.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    assert_eq!(
+        sim.options,
+        vec![
+            SimulationOption::OpStrategies(vec![OpStrategy::GminStepping, OpStrategy::Newton]),
+            SimulationOption::GminSteps(8),
+        ]
+    );
+}
+
+#[test]
+fn process_tolerances() {
+    use crate::frontends::Frontend;
+    use crate::sim::options::SimulationOption;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/tolerances.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    assert_eq!(
+        sim.options,
+        vec![
+            SimulationOption::RelTol(1e-4),
+            SimulationOption::AbsTol(1e-10),
+            SimulationOption::VnTol(1e-5),
+            SimulationOption::Gmin(1e-13),
+            SimulationOption::OpMaxIter(200),
+            SimulationOption::TranMaxIter(20),
+            SimulationOption::Temperature(85.0),
+            SimulationOption::ConditionLimit(1e12),
+        ]
+    );
+}
+
+#[test]
+fn process_temperature() {
+    use crate::frontends::Frontend;
+    use crate::models::{DeviceTemperature, Element, ResistorBundle, Unit, Variable};
+    use crate::sim::options::SimulationOption;
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/temperature.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let node = Variable::new(Arc::from("n1"), Unit::Volt, 0);
+    let mut resistor = ResistorBundle::new(Arc::from("R1"), Some(node), None, 1000.0);
+    resistor.set_temperature_coefficients(1e-3, 0.0);
+    resistor.set_temperature(DeviceTemperature::new(None, Some(5.0)));
+    assert_eq!(sim.elements, vec![Element::Resistor(resistor)]);
+    assert_eq!(
+        sim.options,
+        vec![
+            SimulationOption::Temperatures(vec![0.0, 100.0]),
+            SimulationOption::NominalTemperature(25.0),
+        ]
+    );
+}
+
+#[test]
+fn process_bjt() {
+    use crate::frontends::Frontend;
+    use crate::models::bjt::{BjtOptions, BjtPolarity};
+    use crate::models::{BjtBundle, Element, Unit, Variable};
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/bjt.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let mut options = BjtOptions::default().with_polarity(BjtPolarity::Pnp);
+    options.set_parameter("bf", 200.0);
+    options.set_parameter("re", 2.0);
+    let collector = Variable::new(Arc::from("c"), Unit::Volt, 0);
+    let base = Variable::new(Arc::from("b"), Unit::Volt, 1);
+    let emitter = Variable::new(Arc::from("Q1#emitter"), Unit::Volt, 2);
+    let mut bjt = BjtBundle::new(
+        Arc::from("Q1"),
+        Some(collector),
+        Some(base),
+        None,
+        Some(options),
+    );
+    bjt.set_internal_nodes(None, None, Some(emitter));
+    assert_eq!(sim.elements, vec![Element::Bjt(Box::new(bjt))]);
+}
+
+#[test]
+fn process_mos1() {
+    use crate::frontends::Frontend;
+    use crate::models::mosfet::{Mos1Options, MosPolarity};
+    use crate::models::{Element, Mos1Bundle, Unit, Variable};
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/mos1.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let mut options = Mos1Options::default().with_polarity(MosPolarity::Pmos);
+    options.set_parameter("vto", -0.7);
+    options.set_parameter("lambda", 0.02);
+    let drain = Variable::new(Arc::from("d"), Unit::Volt, 0);
+    let gate = Variable::new(Arc::from("g"), Unit::Volt, 1);
+    let mut mosfet = Mos1Bundle::new(
+        Arc::from("M1"),
+        Some(drain),
+        Some(gate),
+        None,
+        None,
+        Some(options),
+    );
+    mosfet.set_geometry(2e-5, 1e-6);
+    assert_eq!(sim.elements, vec![Element::Mos1(mosfet)]);
+}
+
+#[test]
+fn process_diode_parameters() {
+    use crate::frontends::Frontend;
+    use crate::models::diode::DiodeOptions;
+    use crate::models::{DiodeBundle, Element, Unit, Variable};
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/diode.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let options = DiodeOptions::default()
+        .with_series_resistance(5.0)
+        .with_breakdown(6.2, 1e-3);
+    let anode = Variable::new(Arc::from("a"), Unit::Volt, 0);
+    let internal = Variable::new(Arc::from("D1#anode"), Unit::Volt, 1);
+    let mut diode = DiodeBundle::new(Arc::from("D1"), Some(anode), None, Some(options));
+    diode.set_internal_node(Some(internal));
+    assert_eq!(sim.elements, vec![Element::Diode(diode)]);
+}
+
+#[test]
+fn process_jfet() {
+    use crate::frontends::Frontend;
+    use crate::models::jfet::{JfetOptions, JfetPolarity};
+    use crate::models::{Element, JfetBundle, Unit, Variable};
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/jfet.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let mut options = JfetOptions::default().with_polarity(JfetPolarity::Pjf);
+    options.set_parameter("vto", 1.5);
+    options.set_parameter("rs", 5.0);
+    let drain = Variable::new(Arc::from("d"), Unit::Volt, 0);
+    let gate = Variable::new(Arc::from("g"), Unit::Volt, 1);
+    let source = Variable::new(Arc::from("J1#source"), Unit::Volt, 2);
+    let mut jfet = JfetBundle::new(
+        Arc::from("J1"),
+        Some(drain),
+        Some(gate),
+        None,
+        Some(options),
+    );
+    jfet.set_internal_nodes(None, Some(source));
+    assert_eq!(sim.elements, vec![Element::Jfet(jfet)]);
+}
+
+#[test]
+fn process_switch() {
+    use crate::frontends::Frontend;
+    use crate::models::switch::{SwitchControl, SwitchOptions};
+    use crate::models::{Element, SwitchBundle, Unit, Variable};
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/switch.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let node = |name: &str, unit, idx| Some(Variable::new(Arc::from(name), unit, idx));
+    let mut options = SwitchOptions::default();
+    options.set_parameter("ron", 0.5);
+    options.set_parameter("vt", 2.0);
+    let mut s1 = SwitchBundle::new(
+        Arc::from("S1"),
+        node("1", Unit::Volt, 0),
+        None,
+        SwitchControl::Voltage(node("c", Unit::Volt, 1), None),
+        Some(options),
+    );
+    s1.set_initial_state(true);
+    let w1 = SwitchBundle::new(
+        Arc::from("W1"),
+        node("2", Unit::Volt, 2),
+        None,
+        SwitchControl::Current(node("V1#branch", Unit::Ampere, 3)),
+        Some(SwitchOptions::default().with_threshold(1e-3, 0.0)),
+    );
+    assert_eq!(sim.elements, vec![Element::Switch(s1), Element::Switch(w1)]);
+}
+
+#[test]
+fn process_behavioral() {
+    use crate::frontends::Frontend;
+    use crate::models::behavioral::spice::parse_expression_str;
+    use crate::models::{BehavioralBundle, BehavioralOutput, Element, Unit, Variable};
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/behavioral.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let node = |name: &str, unit, idx| Some(Variable::new(Arc::from(name), unit, idx));
+    let expression = |text: &str| {
+        let mut expression = parse_expression_str(text).unwrap().define(&[]).unwrap();
+        expression.bind().unwrap();
+        expression
+    };
+    let b1 = BehavioralBundle::new(
+        Arc::from("B1"),
+        node("out", Unit::Volt, 0),
+        None,
+        BehavioralOutput::Voltage(node("B1#branch", Unit::Ampere, 1)),
+        expression("V(a) * V(b)"),
+        vec![node("a", Unit::Volt, 2), node("b", Unit::Volt, 3)],
+    );
+    let b2 = BehavioralBundle::new(
+        Arc::from("B2"),
+        None,
+        node("out", Unit::Volt, 0),
+        BehavioralOutput::Current,
+        expression("limit(I(V1), 0, 1e-3)"),
+        vec![node("V1#branch", Unit::Ampere, 4)],
+    );
+    assert_eq!(
+        sim.elements,
+        vec![Element::Behavioral(b1), Element::Behavioral(b2)]
+    );
+}
+
+#[test]
+fn process_behavioral_invalid_expression() {
+    let input = "elements:\n  - type: bsource\n    name: B1\n    positive: out\n    negative: \"0\"\n    voltage: V(a) +\nsimulations:\n  - type: op\n";
+    let frontend = SerdeFrontend::try_new_from_string(input.to_string(), SerdeFormat::Yaml);
+
+    assert!(frontend.is_err());
+}
+
+#[test]
+fn process_controlled_nonlinear() {
+    use crate::frontends::Frontend;
+    use crate::models::behavioral::expression::Expression;
+    use crate::models::controlled_sources::nonlinear::{polynomial, table, voltage_control};
+    use crate::models::{BehavioralBundle, BehavioralOutput, Element, Unit, Variable};
+    use std::sync::Arc;
+
+    let mut input = File::open("src/frontends/tests/yaml_files/controlled_nonlinear.yaml").unwrap();
+    let mut input_string = String::new();
+    input.read_to_string(&mut input_string).unwrap();
+    let frontend = SerdeFrontend::try_new_from_string(input_string, SerdeFormat::Yaml).unwrap();
+    let sim = frontend.simulation().unwrap();
+
+    let node = |name: &str, unit, idx| Some(Variable::new(Arc::from(name), unit, idx));
+    let bound = |expression: Expression| {
+        let mut expression = expression.define(&[]).unwrap();
+        expression.bind().unwrap();
+        expression
+    };
+    let e1 = BehavioralBundle::new(
+        Arc::from("E1"),
+        node("out", Unit::Volt, 0),
+        None,
+        BehavioralOutput::Voltage(node("E1#branch", Unit::Ampere, 1)),
+        bound(polynomial(
+            &[voltage_control("a", "0"), voltage_control("b", "0")],
+            &[0.0, 1.0, 1.0],
+        )),
+        vec![node("a", Unit::Volt, 2), node("b", Unit::Volt, 3)],
+    );
+    let g1 = BehavioralBundle::new(
+        Arc::from("G1"),
+        node("out", Unit::Volt, 0),
+        None,
+        BehavioralOutput::Current,
+        bound(table(voltage_control("a", "b"), &[(-1.0, -1e-3), (1.0, 1e-3)]).unwrap()),
+        vec![node("a", Unit::Volt, 2), node("b", Unit::Volt, 3)],
+    );
+    let h1 = BehavioralBundle::new(
+        Arc::from("H1"),
+        node("out2", Unit::Volt, 4),
+        None,
+        BehavioralOutput::Voltage(node("H1#branch", Unit::Ampere, 5)),
+        bound(polynomial(
+            &[Expression::Current(Arc::from("V1"))],
+            &[0.0, 2.5],
+        )),
+        vec![node("V1#branch", Unit::Ampere, 6)],
+    );
+    assert_eq!(
+        sim.elements[..3],
+        [
+            Element::Behavioral(e1),
+            Element::Behavioral(g1),
+            Element::Behavioral(h1)
+        ]
+    );
+    // The linear form is unchanged
+    assert!(matches!(sim.elements[3], Element::CCCS(_)));
+}
+
+#[test]
+fn process_controlled_nonlinear_invalid_table() {
+    let input = "elements:\n  - type: vccs\n    name: G1\n    positive: out\n    negative: \"0\"\n    table:\n      input: V(a)\n      points: [[1, 0], [0, 1]]\nsimulations:\n  - type: op\n";
+    let frontend = SerdeFrontend::try_new_from_string(input.to_string(), SerdeFormat::Yaml);
+
+    assert!(frontend.is_err());
+}
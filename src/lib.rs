@@ -15,7 +15,7 @@ use thiserror::Error;
 use backends::*;
 use frontends::*;
 use sim::Simulator;
-use solver::{FaerSolver, NalgebraSolver, RSparseSolver, Solvers};
+use solver::{AutoSolver, FaerSolver, KrylovSolver, NalgebraSolver, RSparseSolver, Solvers};
 
 // Network imports
 
@@ -26,7 +26,7 @@ use crate::{
     models::{Element, Variable},
     sim::{
         commands::{ACMode, SimulationCommand},
-        options::SimulationOption,
+        options::{DumpAnalysis, SimulationOption},
     },
     FrontendError,
 };
@@ -35,6 +35,7 @@ use crate::{
     frontends::serde::SerdeFormat,
     sim::{simulation_result::SimulationResults, SimulatorError},
     solver::{FaerSparseSolver, Solver},
+    spot::Numeric,
 };
 
 #[derive(Debug, Error, Diagnostic)]
@@ -62,11 +63,57 @@ struct Cli {
     #[arg(long, default_value = "false")]
     autotune: bool,
 
+    /// Only check the circuit topology without simulating it
+    #[arg(long, default_value = "false")]
+    check: bool,
+
+    /// Write the assembled systems of an analysis to Matrix Market files for debugging
+    #[arg(long)]
+    dump: Option<DumpAnalysis>,
+
+    /// Only dump the systems of this Newton iteration, counted from 1
+    #[arg(long)]
+    dump_iteration: Option<usize>,
+
+    /// Only dump the systems of the first timestep ending at or after this time in s
+    #[arg(long)]
+    dump_time: Option<Numeric>,
+
+    /// The path prefix of the dumped files
+    #[arg(long)]
+    dump_prefix: Option<String>,
+
     path: Option<String>,
 }
 
-fn run_sim<T: Solver>(sim: Simulation) -> Result<SimulationResults, SimulatorError> {
+impl Cli {
+    /// Returns the options of the matrix dump, which override the ones of the circuit.
+    fn dump_options(&self) -> Vec<SimulationOption> {
+        let mut options = Vec::new();
+        if let Some(analysis) = self.dump {
+            options.push(SimulationOption::DumpAnalysis(analysis));
+        }
+        if let Some(iteration) = self.dump_iteration {
+            options.push(SimulationOption::DumpIteration(iteration));
+        }
+        if let Some(time) = self.dump_time {
+            options.push(SimulationOption::DumpTime(time));
+        }
+        if let Some(prefix) = &self.dump_prefix {
+            options.push(SimulationOption::DumpPrefix(prefix.as_str().into()));
+        }
+        options
+    }
+}
+
+fn run_sim<T: Solver>(
+    sim: Simulation,
+    autotune: bool,
+) -> Result<SimulationResults, SimulatorError> {
     let mut sim: Simulator<T> = Simulator::from(sim);
+    if autotune {
+        sim = sim.with_autotune();
+    }
     sim.run()
 }
 
@@ -92,6 +139,7 @@ pub fn run() -> Result<()> {
     }
 
     info!("Splice - a blazingly fast circuit simulator");
+    let dump_options = cli.dump_options();
     let pth = match cli.path {
         Some(pth) => pth,
         None => return Err(ApplicationError::NoPathGiven.into()),
@@ -118,6 +166,7 @@ pub fn run() -> Result<()> {
     };
 
     let mut sim = frontend.simulation()?;
+    sim.options.extend(dump_options);
 
     // Setup coupled inductors by setting their node indices
     // This setup should ideally be part of the Simulation struct initialization
@@ -134,20 +183,25 @@ pub fn run() -> Result<()> {
         .into());
     }
 
-    // Apply autotune if enabled
+    if cli.check {
+        info!("Check circuit topology");
+        sim::topology::check_simulation(&sim)?;
+        info!("No problems found in the circuit topology");
+        return Ok(());
+    }
+
     if cli.autotune {
         info!("Autotune mode enabled");
-        let autotune_options =
-            sim::autotune::analyze_circuit_and_suggest_settings(&sim.elements, &sim.commands);
-        sim.options.extend(autotune_options);
     }
 
     info!("Simulate!");
     let results = match cli.solver {
-        Solvers::Rsparse => run_sim::<RSparseSolver>(sim)?,
-        Solvers::Nalgebra => run_sim::<NalgebraSolver>(sim)?,
-        Solvers::Faer => run_sim::<FaerSolver>(sim)?,
-        Solvers::FaerSparse => run_sim::<FaerSparseSolver>(sim)?,
+        Solvers::Rsparse => run_sim::<RSparseSolver>(sim, cli.autotune)?,
+        Solvers::Nalgebra => run_sim::<NalgebraSolver>(sim, cli.autotune)?,
+        Solvers::Faer => run_sim::<FaerSolver>(sim, cli.autotune)?,
+        Solvers::FaerSparse => run_sim::<FaerSparseSolver>(sim, cli.autotune)?,
+        Solvers::Krylov => run_sim::<KrylovSolver>(sim, cli.autotune)?,
+        Solvers::Auto => run_sim::<AutoSolver>(sim, cli.autotune)?,
     };
 
     let out: Box<dyn Backend> = match cli.backend {
@@ -247,10 +301,12 @@ fn handle_network_connection(
 
     // Run simulation
     let results = match solver {
-        Solvers::Rsparse => run_sim::<RSparseSolver>(sim),
-        Solvers::Nalgebra => run_sim::<NalgebraSolver>(sim),
-        Solvers::Faer => run_sim::<FaerSolver>(sim),
-        Solvers::FaerSparse => run_sim::<FaerSparseSolver>(sim),
+        Solvers::Rsparse => run_sim::<RSparseSolver>(sim, false),
+        Solvers::Nalgebra => run_sim::<NalgebraSolver>(sim, false),
+        Solvers::Faer => run_sim::<FaerSolver>(sim, false),
+        Solvers::FaerSparse => run_sim::<FaerSparseSolver>(sim, false),
+        Solvers::Krylov => run_sim::<KrylovSolver>(sim, false),
+        Solvers::Auto => run_sim::<AutoSolver>(sim, false),
     };
 
     let results = match results {
@@ -318,6 +374,24 @@ fn convert_serde_circuit_to_simulation(circuit: SerdeCircuit) -> Result<Simulati
             SerdeElement::Mosfet(ele) => {
                 ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
             }
+            SerdeElement::Mos1(ele) => {
+                ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+            }
+            SerdeElement::Bjt(ele) => {
+                ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+            }
+            SerdeElement::Jfet(ele) => {
+                ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+            }
+            SerdeElement::Switch(ele) => {
+                ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+            }
+            SerdeElement::CSwitch(ele) => {
+                ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+            }
+            SerdeElement::Behavioral(ele) => {
+                ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
+            }
             SerdeElement::Gain(ele) => {
                 ProcessSerdeElement::process(&ele, &mut variables, &mut elements, &mut var_map);
             }
@@ -364,16 +438,18 @@ fn convert_serde_circuit_to_simulation(circuit: SerdeCircuit) -> Result<Simulati
                 ));
             }
             SerdeSimulation::Tran(tran) => {
-                commands.push(SimulationCommand::Tran(tran.tstep(), tran.tend()));
+                SerdeFrontend::process_tran(&mut commands, tran)?;
             }
         }
     }
 
     // Process options
     for option in circuit.options {
-        options.push(SimulationOption::Out(vec![Arc::from(option.out)]));
+        SerdeFrontend::process_option(&mut options, option);
     }
 
+    SerdeFrontend::process_node_voltages(&mut options, circuit.ic, circuit.nodeset);
+
     Ok(Simulation {
         elements,
         commands,
@@ -0,0 +1,525 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::models::Unit;
+use crate::spot::Numeric;
+
+/// The maximal number of inputs of an expression, whose derivatives are stamped.
+/// A current source stamps every derivative into two rows of its at most 24 nonlinear triples.
+pub const MAX_INPUTS: usize = 12;
+
+/// The maximal depth of nested `.func` calls, which catches recursive functions.
+const MAX_CALL_DEPTH: usize = 64;
+
+/// The abstract syntax tree of a behavioral expression.
+///
+/// A parsed expression refers to nodes, sources, parameters and functions by their names.
+/// [`Expression::define`] resolves the names of functions and constants and
+/// [`Expression::bind`] replaces the voltages and currents by the inputs of the element.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Expression {
+    Number(Numeric),
+    /// A constant, a variable or a parameter of a `.func` before the definition
+    Name(Arc<str>),
+    /// The simulation time in s, zero outside of a transient analysis
+    Time,
+    /// The circuit temperature in °C
+    Temp,
+    /// The voltage of a node before the binding
+    Voltage(Arc<str>),
+    /// The current through a voltage source before the binding
+    Current(Arc<str>),
+    /// The `n`-th input of the element
+    Input(usize),
+    Unary(UnaryOp, Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+    /// A call of a built-in or a `.func` function before the definition
+    Call(Arc<str>, Vec<Expression>),
+    Function(Function, Vec<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+/// The built-in functions of expressions.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Function {
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sinh,
+    Cosh,
+    Tanh,
+    /// The sign of the argument
+    Sgn,
+    /// The unit step, one for positive arguments
+    Step,
+    Floor,
+    Ceil,
+    /// `pow(x, y)` is `x ** y`
+    Pow,
+    /// `pwr(x, y)` is `|x| ** y`
+    Pwr,
+    Min,
+    Max,
+    /// `limit(x, a, b)` clamps `x` between `a` and `b`
+    Limit,
+    /// `if(c, a, b)` is `a` for a non-zero condition `c` and `b` otherwise
+    If,
+    /// `table(x, x1, y1, x2, y2, ...)` interpolates the points with ascending `x1, x2, ...`
+    /// linearly and is constant beyond the first and the last point
+    Table,
+}
+
+impl FromStr for Function {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "abs" => Function::Abs,
+            "sqrt" => Function::Sqrt,
+            "exp" => Function::Exp,
+            "ln" | "log" => Function::Ln,
+            "log10" => Function::Log10,
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "asin" => Function::Asin,
+            "acos" => Function::Acos,
+            "atan" => Function::Atan,
+            "atan2" => Function::Atan2,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "sgn" | "sign" => Function::Sgn,
+            "u" => Function::Step,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "pow" => Function::Pow,
+            "pwr" => Function::Pwr,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "limit" => Function::Limit,
+            "if" => Function::If,
+            "table" => Function::Table,
+            _ => return Err(format!("Unknown function '{s}'")),
+        })
+    }
+}
+
+impl Function {
+    /// Returns the number of arguments of the function, the least number for `table`.
+    pub fn arity(&self) -> usize {
+        match self {
+            Function::Atan2 | Function::Pow | Function::Pwr | Function::Min | Function::Max => 2,
+            Function::Limit | Function::If | Function::Table => 3,
+            _ => 1,
+        }
+    }
+
+    /// Returns whether the function takes `count` arguments.
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Function::Table => count >= 3 && count % 2 == 1,
+            _ => count == self.arity(),
+        }
+    }
+}
+
+/// A user function of a `.func` definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserFunction {
+    pub name: Arc<str>,
+    pub parameters: Vec<Arc<str>>,
+    pub body: Expression,
+}
+
+/// The values an expression is evaluated with.
+#[derive(Debug, Clone, Copy)]
+pub struct Environment<'a> {
+    /// The values of the inputs of the element
+    pub inputs: &'a [Numeric],
+    pub time: Numeric,
+    pub temp: Numeric,
+}
+
+/// A value with its gradient by the inputs of an expression, which carries the derivatives
+/// through the evaluation – forward mode automatic differentiation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual {
+    pub value: Numeric,
+    pub grad: [Numeric; MAX_INPUTS],
+}
+
+impl Dual {
+    /// Creates a value, which does not depend on the inputs.
+    pub fn constant(value: Numeric) -> Self {
+        Self {
+            value,
+            grad: [0.0; MAX_INPUTS],
+        }
+    }
+
+    /// Creates the value of the input `idx`.
+    pub fn input(value: Numeric, idx: usize) -> Self {
+        let mut dual = Self::constant(value);
+        dual.grad[idx] = 1.0;
+        dual
+    }
+
+    /// Returns the value of `f(self)` with the derivative `df` by `self`.
+    /// Zero derivatives of the inputs stay zero, even where `df` is infinite.
+    fn chain(self, value: Numeric, df: Numeric) -> Self {
+        Self {
+            value,
+            grad: self
+                .grad
+                .map(|grad| if grad == 0.0 { 0.0 } else { grad * df }),
+        }
+    }
+
+    /// Returns the value of `f(self, other)` with the partial derivatives `da` and `db`.
+    fn chain2(self, other: Self, value: Numeric, da: Numeric, db: Numeric) -> Self {
+        let mut dual = self.chain(value, da);
+        for (grad, other) in dual.grad.iter_mut().zip(other.grad) {
+            if other != 0.0 {
+                *grad += other * db;
+            }
+        }
+        dual
+    }
+
+    /// Returns the truth value of `condition` as one or zero.
+    fn boolean(condition: bool) -> Self {
+        Self::constant(if condition { 1.0 } else { 0.0 })
+    }
+}
+
+impl Expression {
+    /// Resolves the calls of built-in and user functions as well as the constants and the
+    /// variables `time` and `temp`. User functions are inlined with their arguments.
+    /// Later definitions of a function replace earlier ones, the names are case-insensitive.
+    pub fn define(self, functions: &[UserFunction]) -> Result<Expression, String> {
+        self.define_at(functions, 0)
+    }
+
+    fn define_at(self, functions: &[UserFunction], depth: usize) -> Result<Expression, String> {
+        let define = |expression: Expression| expression.define_at(functions, depth);
+        Ok(match self {
+            Expression::Name(name) => match name.to_ascii_lowercase().as_str() {
+                "time" => Expression::Time,
+                "temp" => Expression::Temp,
+                "pi" => Expression::Number(std::f64::consts::PI),
+                _ => return Err(format!("Unknown parameter '{name}'")),
+            },
+            Expression::Unary(op, operand) => Expression::Unary(op, Box::new(define(*operand)?)),
+            Expression::Binary(op, lhs, rhs) => {
+                Expression::Binary(op, Box::new(define(*lhs)?), Box::new(define(*rhs)?))
+            }
+            Expression::Call(name, arguments) => {
+                let arguments = arguments
+                    .into_iter()
+                    .map(define)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let user = functions
+                    .iter()
+                    .rev()
+                    .find(|function| function.name.eq_ignore_ascii_case(&name));
+                if let Some(function) = user {
+                    if depth >= MAX_CALL_DEPTH {
+                        return Err(format!("The function '{name}' calls itself"));
+                    }
+                    if arguments.len() != function.parameters.len() {
+                        return Err(format!(
+                            "The function '{name}' takes {} arguments, not {}",
+                            function.parameters.len(),
+                            arguments.len()
+                        ));
+                    }
+                    let body = function
+                        .body
+                        .clone()
+                        .substitute(&function.parameters, &arguments);
+                    return body.define_at(functions, depth + 1);
+                }
+
+                let function = name.parse::<Function>()?;
+                if !function.accepts(arguments.len()) {
+                    return Err(match function {
+                        Function::Table => format!(
+                            "The function '{name}' takes an input and pairs of points, not {} \
+                             arguments",
+                            arguments.len()
+                        ),
+                        _ => format!(
+                            "The function '{name}' takes {} arguments, not {}",
+                            function.arity(),
+                            arguments.len()
+                        ),
+                    });
+                }
+                Expression::Function(function, arguments)
+            }
+            Expression::Function(function, arguments) => Expression::Function(
+                function,
+                arguments
+                    .into_iter()
+                    .map(define)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            expression => expression,
+        })
+    }
+
+    /// Replaces the names of `parameters` by the defined `arguments`.
+    fn substitute(self, parameters: &[Arc<str>], arguments: &[Expression]) -> Expression {
+        let substitute = |expression: Expression| expression.substitute(parameters, arguments);
+        match self {
+            Expression::Name(name) => parameters
+                .iter()
+                .position(|parameter| parameter.eq_ignore_ascii_case(&name))
+                .map_or(Expression::Name(name), |idx| arguments[idx].clone()),
+            Expression::Unary(op, operand) => Expression::Unary(op, Box::new(substitute(*operand))),
+            Expression::Binary(op, lhs, rhs) => {
+                Expression::Binary(op, Box::new(substitute(*lhs)), Box::new(substitute(*rhs)))
+            }
+            Expression::Call(name, arguments) => {
+                Expression::Call(name, arguments.into_iter().map(substitute).collect())
+            }
+            Expression::Function(function, arguments) => {
+                Expression::Function(function, arguments.into_iter().map(substitute).collect())
+            }
+            expression => expression,
+        }
+    }
+
+    /// Replaces the voltages and currents by the inputs of the element.
+    /// Returns the names and units of the inputs: the nodes and the branches `<Source>#branch`.
+    /// The voltage of the ground node `0` is zero.
+    pub fn bind(&mut self) -> Result<Vec<(Arc<str>, Unit)>, String> {
+        let mut inputs = Vec::new();
+        self.bind_inputs(&mut inputs);
+        if inputs.len() > MAX_INPUTS {
+            return Err(format!(
+                "The expression depends on {} voltages and currents, at most {MAX_INPUTS} are \
+                 supported",
+                inputs.len()
+            ));
+        }
+        Ok(inputs)
+    }
+
+    fn bind_inputs(&mut self, inputs: &mut Vec<(Arc<str>, Unit)>) {
+        let (name, unit) = match self {
+            Expression::Voltage(node) if &**node == "0" => {
+                *self = Expression::Number(0.0);
+                return;
+            }
+            Expression::Voltage(node) => (node.clone(), Unit::Volt),
+            Expression::Current(source) => (Arc::from(format!("{source}#branch")), Unit::Ampere),
+            Expression::Unary(_, operand) => return operand.bind_inputs(inputs),
+            Expression::Binary(_, lhs, rhs) => {
+                lhs.bind_inputs(inputs);
+                return rhs.bind_inputs(inputs);
+            }
+            Expression::Call(_, arguments) | Expression::Function(_, arguments) => {
+                return arguments
+                    .iter_mut()
+                    .for_each(|argument| argument.bind_inputs(inputs));
+            }
+            _ => return,
+        };
+        let idx = inputs
+            .iter()
+            .position(|(input, _)| *input == name)
+            .unwrap_or_else(|| {
+                inputs.push((name, unit));
+                inputs.len() - 1
+            });
+        *self = Expression::Input(idx);
+    }
+
+    /// Returns the value of the expression with its derivatives by the inputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the expression is not defined and bound.
+    pub fn evaluate(&self, env: &Environment) -> Dual {
+        match self {
+            Expression::Number(value) => Dual::constant(*value),
+            Expression::Time => Dual::constant(env.time),
+            Expression::Temp => Dual::constant(env.temp),
+            Expression::Input(idx) => Dual::input(env.inputs[*idx], *idx),
+            Expression::Unary(op, operand) => {
+                let x = operand.evaluate(env);
+                match op {
+                    UnaryOp::Neg => x.chain(-x.value, -1.0),
+                    UnaryOp::Not => Dual::boolean(x.value == 0.0),
+                }
+            }
+            Expression::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.evaluate(env), rhs.evaluate(env));
+                binary(*op, a, b)
+            }
+            Expression::Function(Function::If, arguments) => {
+                if arguments[0].evaluate(env).value != 0.0 {
+                    arguments[1].evaluate(env)
+                } else {
+                    arguments[2].evaluate(env)
+                }
+            }
+            Expression::Function(function, arguments) => {
+                let arguments: Vec<Dual> = arguments.iter().map(|arg| arg.evaluate(env)).collect();
+                call(*function, &arguments)
+            }
+            Expression::Name(_)
+            | Expression::Voltage(_)
+            | Expression::Current(_)
+            | Expression::Call(..) => {
+                unreachable!("Expressions are defined and bound before the evaluation")
+            }
+        }
+    }
+}
+
+fn binary(op: BinaryOp, a: Dual, b: Dual) -> Dual {
+    let (x, y) = (a.value, b.value);
+    match op {
+        BinaryOp::Add => a.chain2(b, x + y, 1.0, 1.0),
+        BinaryOp::Sub => a.chain2(b, x - y, 1.0, -1.0),
+        BinaryOp::Mul => a.chain2(b, x * y, y, x),
+        BinaryOp::Div => a.chain2(b, x / y, 1.0 / y, -x / (y * y)),
+        BinaryOp::Pow => power(a, b),
+        BinaryOp::Lt => Dual::boolean(x < y),
+        BinaryOp::Le => Dual::boolean(x <= y),
+        BinaryOp::Gt => Dual::boolean(x > y),
+        BinaryOp::Ge => Dual::boolean(x >= y),
+        BinaryOp::Eq => Dual::boolean(x == y),
+        BinaryOp::Ne => Dual::boolean(x != y),
+        BinaryOp::And => Dual::boolean(x != 0.0 && y != 0.0),
+        BinaryOp::Or => Dual::boolean(x != 0.0 || y != 0.0),
+    }
+}
+
+/// Returns `a ** b`. The exponent only contributes a derivative for a positive base.
+fn power(a: Dual, b: Dual) -> Dual {
+    let (x, y) = (a.value, b.value);
+    let value = x.powf(y);
+    let db = if x > 0.0 { value * x.ln() } else { 0.0 };
+    a.chain2(b, value, y * x.powf(y - 1.0), db)
+}
+
+fn call(function: Function, args: &[Dual]) -> Dual {
+    let a = args[0];
+    let x = a.value;
+    match function {
+        Function::Abs => a.chain(x.abs(), x.signum()),
+        Function::Sqrt => a.chain(x.sqrt(), 0.5 / x.sqrt()),
+        Function::Exp => a.chain(x.exp(), x.exp()),
+        Function::Ln => a.chain(x.ln(), 1.0 / x),
+        Function::Log10 => a.chain(x.log10(), 1.0 / (x * std::f64::consts::LN_10)),
+        Function::Sin => a.chain(x.sin(), x.cos()),
+        Function::Cos => a.chain(x.cos(), -x.sin()),
+        Function::Tan => a.chain(x.tan(), 1.0 / (x.cos() * x.cos())),
+        Function::Asin => a.chain(x.asin(), 1.0 / (1.0 - x * x).sqrt()),
+        Function::Acos => a.chain(x.acos(), -1.0 / (1.0 - x * x).sqrt()),
+        Function::Atan => a.chain(x.atan(), 1.0 / (1.0 + x * x)),
+        Function::Atan2 => {
+            let (b, y) = (args[1], args[1].value);
+            let norm = x * x + y * y;
+            a.chain2(b, x.atan2(y), y / norm, -x / norm)
+        }
+        Function::Sinh => a.chain(x.sinh(), x.cosh()),
+        Function::Cosh => a.chain(x.cosh(), x.sinh()),
+        Function::Tanh => a.chain(x.tanh(), 1.0 - x.tanh() * x.tanh()),
+        Function::Sgn => Dual::constant(if x == 0.0 { 0.0 } else { x.signum() }),
+        Function::Step => Dual::boolean(x > 0.0),
+        Function::Floor => Dual::constant(x.floor()),
+        Function::Ceil => Dual::constant(x.ceil()),
+        Function::Pow => power(a, args[1]),
+        Function::Pwr => {
+            let abs = a.chain(x.abs(), x.signum());
+            power(abs, args[1])
+        }
+        Function::Min => {
+            if x <= args[1].value {
+                a
+            } else {
+                args[1]
+            }
+        }
+        Function::Max => {
+            if x >= args[1].value {
+                a
+            } else {
+                args[1]
+            }
+        }
+        Function::Limit => {
+            let (low, high) = if args[1].value <= args[2].value {
+                (args[1], args[2])
+            } else {
+                (args[2], args[1])
+            };
+            if x < low.value {
+                low
+            } else if x > high.value {
+                high
+            } else {
+                a
+            }
+        }
+        Function::Table => table(a, &args[1..]),
+        Function::If => unreachable!("Conditions only evaluate the selected branch"),
+    }
+}
+
+/// Returns the linear interpolation of the `points` `x1, y1, x2, y2, ...` at `x`.
+/// The points are arguments as well, so the result carries their derivatives too.
+fn table(x: Dual, points: &[Dual]) -> Dual {
+    if x.value <= points[0].value {
+        return points[1];
+    }
+    for segment in points.windows(4).step_by(2) {
+        let (x0, y0, x1, y1) = (segment[0], segment[1], segment[2], segment[3]);
+        if x.value <= x1.value {
+            let slope = binary(
+                BinaryOp::Div,
+                binary(BinaryOp::Sub, y1, y0),
+                binary(BinaryOp::Sub, x1, x0),
+            );
+            let offset = binary(BinaryOp::Sub, x, x0);
+            return binary(BinaryOp::Add, y0, binary(BinaryOp::Mul, slope, offset));
+        }
+    }
+    points[points.len() - 1]
+}
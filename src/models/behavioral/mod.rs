@@ -0,0 +1,261 @@
+/// The Behavioral Module. As every module this module encapsulates exerything regarding a
+/// behavioral source bundle. This includes parsing from various formats as well as the
+/// evaluation of its expression.
+pub mod expression;
+pub(crate) mod serde;
+pub(crate) mod spice;
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
+
+use self::expression::{Dual, Environment, Expression};
+use super::*;
+use crate::sim::options::SimulatorOptions;
+use crate::spot::*;
+
+/// The quantity a behavioral source forces between its nodes.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum BehavioralOutput {
+    /// The voltage `V(positive) - V(negative)` with the variable of the branch current
+    Voltage(Option<Variable>),
+    /// The current flowing from the positive through the source to the negative node
+    Current,
+}
+
+/// A structure representing a behavioral source - B element.
+///
+/// The output of the source is an arbitrary expression of node voltages, branch currents,
+/// `time` and `temp`. The expression is evaluated with its derivatives by its inputs, which
+/// stamp the exact Jacobian of the source in every Newton iteration.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct BehavioralBundle {
+    name: Arc<str>,
+    positive: Option<Variable>,
+    negative: Option<Variable>,
+    output: BehavioralOutput,
+    /// The defined and bound expression of the output
+    expression: Expression,
+    /// The node voltages and branch currents the expression depends on
+    inputs: Vec<Option<Variable>>,
+    /// The simulation time in s
+    time: Numeric,
+    /// The circuit temperature in °C
+    temp: Numeric,
+}
+
+impl BehavioralBundle {
+    /// Creates a new `BehavioralBundle` object. The `expression` is defined and bound to the
+    /// `inputs`, see [`Expression::define`] and [`Expression::bind`].
+    pub fn new(
+        name: Arc<str>,
+        positive: Option<Variable>,
+        negative: Option<Variable>,
+        output: BehavioralOutput,
+        expression: Expression,
+        inputs: Vec<Option<Variable>>,
+    ) -> BehavioralBundle {
+        BehavioralBundle {
+            name,
+            positive,
+            negative,
+            output,
+            expression,
+            inputs,
+            time: 0.0,
+            temp: TEMP - CELSIUS_TO_KELVIN,
+        }
+    }
+
+    /// Replaces the expression and its inputs.
+    pub fn set_expression(&mut self, expression: Expression, inputs: Vec<Option<Variable>>) {
+        self.expression = expression;
+        self.inputs = inputs;
+    }
+
+    /// Returns the expression of the output.
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Returns the quantity the source forces between its nodes.
+    pub fn output(&self) -> &BehavioralOutput {
+        &self.output
+    }
+
+    /// Returns the name of the behavioral bundle.
+    pub fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    /// Returns the number of node voltages and branch currents the expression depends on.
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns the inputs of the expression at the solution `x_vec`.
+    pub fn voltages(&self, x_vec: &[Numeric]) -> Vec<Numeric> {
+        self.inputs
+            .iter()
+            .map(|input| input.as_ref().map_or(0.0, |input| x_vec[input.idx()]))
+            .collect()
+    }
+
+    /// Sets the simulation time of the next evaluations.
+    pub fn set_time(&mut self, time: Numeric) {
+        self.time = time;
+    }
+
+    /// Applies the circuit temperature of the simulator.
+    pub fn apply_options(&mut self, options: &SimulatorOptions) {
+        self.temp = options.temp;
+    }
+
+    /// Returns the output and its derivatives by the inputs at the values `inputs`.
+    pub fn evaluate(&self, inputs: &[Numeric]) -> Dual {
+        self.expression.evaluate(&Environment {
+            inputs,
+            time: self.time,
+            temp: self.temp,
+        })
+    }
+
+    /// Returns a reference to the triples representing matrix A.
+    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 24> {
+        self.triples_at(&self.voltages(x_vec))
+    }
+
+    /// Returns the triples of the derivatives of the output linearised at `inputs`.
+    pub fn triples_at(&self, inputs: &[Numeric]) -> Triples<Numeric, 24> {
+        let output = self.evaluate(inputs);
+        Triples::new(&self.stamps(&output.grad))
+    }
+
+    /// Returns the indices of the triples representing matrix A.
+    pub fn triple_idx(&self) -> Option<TripleIdx<24>> {
+        let indices: Vec<_> = self
+            .stamps(&[1.0; expression::MAX_INPUTS])
+            .into_iter()
+            .map(|(row, col, _)| (row, col))
+            .collect();
+        (!indices.is_empty()).then(|| TripleIdx::new(&indices))
+    }
+
+    /// Returns a reference to the pairs representing vector b.
+    pub fn pairs(&self, x_vec: &[Numeric]) -> Pairs<Numeric, 4> {
+        self.pairs_at(&self.voltages(x_vec))
+    }
+
+    /// Returns the pairs of the linearised output `f(x0) - J * x0` at `inputs`.
+    pub fn pairs_at(&self, inputs: &[Numeric]) -> Pairs<Numeric, 4> {
+        let output = self.evaluate(inputs);
+        let value = output.value
+            - inputs
+                .iter()
+                .zip(output.grad)
+                .map(|(input, grad)| input * grad)
+                .sum::<Numeric>();
+        let pairs: Vec<_> = match &self.output {
+            BehavioralOutput::Voltage(branch) => vec![(branch_idx(branch), value)],
+            BehavioralOutput::Current => {
+                vec![(self.node0_idx(), -value), (self.node1_idx(), value)]
+            }
+        }
+        .into_iter()
+        .filter_map(|(row, value)| Some((row?, value)))
+        .collect();
+        Pairs::new(&pairs)
+    }
+
+    /// Returns the constant triples of the branch current of a voltage output.
+    pub fn constant_triples(&self) -> Option<Triples<Numeric, 4>> {
+        self.incidence(1.0)
+    }
+
+    /// Returns the indices of the constant triples.
+    pub fn constant_triple_idx(&self) -> Option<TripleIdx<4>> {
+        let indices: Vec<_> = self
+            .incidence(1.0)?
+            .into_iter()
+            .map(|&(row, col, _)| (row, col))
+            .collect();
+        Some(TripleIdx::new(&indices))
+    }
+
+    /// Returns the complex triples of the branch current of a voltage output.
+    pub fn ac_triples(&self) -> Option<Triples<ComplexNumeric, 4>> {
+        self.incidence(ComplexNumeric::from(1.0))
+    }
+
+    /// Returns the small-signal triples of the derivatives linearised at the operating point
+    /// `x_op`.
+    pub fn small_signal_triples(&self, x_op: &[Numeric]) -> Triples<ComplexNumeric, 24> {
+        let output = self.evaluate(&self.voltages(x_op));
+        let triples: Vec<_> = self
+            .stamps(&output.grad)
+            .into_iter()
+            .map(|(row, col, value)| (row, col, ComplexNumeric::from(value)))
+            .collect();
+        Triples::new(&triples)
+    }
+
+    /// Returns the triples of the derivatives `grad` of the output by the inputs.
+    /// A voltage output subtracts them in the row of its branch, a current output adds them to
+    /// the current leaving the positive node.
+    fn stamps(&self, grad: &[Numeric]) -> Vec<(usize, usize, Numeric)> {
+        let rows = match &self.output {
+            BehavioralOutput::Voltage(branch) => [(branch_idx(branch), -1.0), (None, 0.0)],
+            BehavioralOutput::Current => [(self.node0_idx(), 1.0), (self.node1_idx(), -1.0)],
+        };
+        let mut triples = Vec::with_capacity(2 * self.inputs.len());
+        for (row, sign) in rows {
+            for (input, value) in self.inputs.iter().zip(grad) {
+                if let (Some(row), Some(input)) = (row, input) {
+                    triples.push((row, input.idx(), sign * value));
+                }
+            }
+        }
+        triples
+    }
+
+    /// Returns the triples of the branch current of a voltage output, like a voltage source.
+    fn incidence<T>(&self, one: T) -> Option<Triples<T, 4>>
+    where
+        T: Copy + Default + std::ops::Neg<Output = T>,
+    {
+        let BehavioralOutput::Voltage(branch) = &self.output else {
+            return None;
+        };
+        let branch = branch_idx(branch)?;
+        let mut triples = Vec::with_capacity(4);
+        for (node, value) in [(self.node0_idx(), one), (self.node1_idx(), -one)] {
+            if let Some(node) = node {
+                triples.push((branch, node, value));
+                triples.push((node, branch, value));
+            }
+        }
+        Some(Triples::new(&triples))
+    }
+
+    /// Returns the indices of the node voltages the expression depends on.
+    pub fn input_node_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.inputs
+            .iter()
+            .flatten()
+            .filter(|input| input.unit() == Unit::Volt)
+            .map(Variable::idx)
+    }
+
+    pub fn node0_idx(&self) -> Option<usize> {
+        self.positive.as_ref().map(|v| v.idx())
+    }
+
+    pub fn node1_idx(&self) -> Option<usize> {
+        self.negative.as_ref().map(|v| v.idx())
+    }
+}
+
+fn branch_idx(branch: &Option<Variable>) -> Option<usize> {
+    branch.as_ref().map(Variable::idx)
+}
@@ -0,0 +1,95 @@
+/// The Behavioral Source - yaml parsing module
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::expression::Expression;
+use super::{BehavioralBundle, BehavioralOutput};
+use crate::frontends::{get_variable, serde::ProcessSerdeElement};
+use crate::models::{Element, Unit, Variable};
+
+/// A behavioral source - B element
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SerdeBehavioral {
+    pub name: String,
+    pub positive: String,
+    pub negative: String,
+    /// The voltage or current of the source, like `voltage: V(a) * V(b)`
+    #[serde(flatten)]
+    pub output: SerdeBehavioralOutput,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerdeBehavioralOutput {
+    Voltage(SerdeExpression),
+    Current(SerdeExpression),
+}
+
+/// An expression, which is parsed, defined and bound while deserializing.
+/// Invalid expressions are reported as deserialization errors.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SerdeExpression {
+    text: String,
+    expression: Expression,
+    inputs: Vec<(Arc<str>, Unit)>,
+}
+
+impl TryFrom<String> for SerdeExpression {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let mut expression = super::spice::parse_expression_str(&text)
+            .map_err(|err| format!("Invalid expression '{text}': {err}"))?
+            .define(&[])?;
+        let inputs = expression.bind()?;
+        Ok(Self {
+            text,
+            expression,
+            inputs,
+        })
+    }
+}
+
+impl From<SerdeExpression> for String {
+    fn from(expression: SerdeExpression) -> Self {
+        expression.text
+    }
+}
+
+impl ProcessSerdeElement for SerdeBehavioral {
+    fn process(
+        &self,
+        variables: &mut Vec<Variable>,
+        elements: &mut Vec<Element>,
+        var_map: &mut HashMap<Arc<str>, usize>,
+    ) {
+        let positive = get_variable(self.positive.as_str(), Unit::Volt, variables, var_map);
+        let negative = get_variable(self.negative.as_str(), Unit::Volt, variables, var_map);
+        let (output, expression) = match &self.output {
+            SerdeBehavioralOutput::Voltage(expression) => {
+                let branch = format!("{}#branch", self.name);
+                let branch = get_variable(&branch, Unit::Ampere, variables, var_map);
+                (BehavioralOutput::Voltage(branch), expression)
+            }
+            SerdeBehavioralOutput::Current(expression) => (BehavioralOutput::Current, expression),
+        };
+        let inputs = expression
+            .inputs
+            .iter()
+            .map(|(name, unit)| get_variable(name, *unit, variables, var_map))
+            .collect();
+
+        let behavioral = BehavioralBundle::new(
+            Arc::from(self.name.as_str()),
+            positive,
+            negative,
+            output,
+            expression.expression.clone(),
+            inputs,
+        );
+        elements.push(Element::Behavioral(behavioral));
+    }
+}
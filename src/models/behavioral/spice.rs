@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use pest::iterators::Pair;
+use pest::pratt_parser::{Assoc, Op, PrattParser};
+use pest::Parser;
+
+use super::expression::{BinaryOp, Expression, UnaryOp, UserFunction};
+use super::{BehavioralBundle, BehavioralOutput};
+use crate::frontends::get_variable;
+use crate::frontends::spice::{ProcessSpiceElement, Rule, SpiceParser};
+use crate::frontends::spice_parser_helpers::SpiceElementParser;
+use crate::frontends::FrontendError;
+use crate::models::{Element, Unit, Variable};
+
+/// The precedence of the operators from the lowest to the highest. The power binds stronger than
+/// the sign, so `-2^2` is `-4`.
+static PRATT_PARSER: LazyLock<PrattParser<Rule>> = LazyLock::new(|| {
+    PrattParser::new()
+        .op(Op::infix(Rule::EXPR_OR, Assoc::Left))
+        .op(Op::infix(Rule::EXPR_AND, Assoc::Left))
+        .op(Op::infix(Rule::EXPR_EQ, Assoc::Left) | Op::infix(Rule::EXPR_NE, Assoc::Left))
+        .op(Op::infix(Rule::EXPR_LT, Assoc::Left)
+            | Op::infix(Rule::EXPR_LE, Assoc::Left)
+            | Op::infix(Rule::EXPR_GT, Assoc::Left)
+            | Op::infix(Rule::EXPR_GE, Assoc::Left))
+        .op(Op::infix(Rule::EXPR_ADD, Assoc::Left) | Op::infix(Rule::EXPR_SUB, Assoc::Left))
+        .op(Op::infix(Rule::EXPR_MUL, Assoc::Left) | Op::infix(Rule::EXPR_DIV, Assoc::Left))
+        .op(Op::prefix(Rule::EXPR_NEG) | Op::prefix(Rule::EXPR_PLUS) | Op::prefix(Rule::EXPR_NOT))
+        .op(Op::infix(Rule::EXPR_POW, Assoc::Right))
+});
+
+impl ProcessSpiceElement for BehavioralBundle {
+    fn process(
+        element: Pair<Rule>,
+        variables: &mut Vec<Variable>,
+        elements: &mut Vec<Element>,
+        var_map: &mut HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError> {
+        let mut parser = SpiceElementParser::new(element);
+
+        let name = parser.parse_name("behavioral source")?;
+        let positive = parser.parse_node("behavioral source", name, "positive node")?;
+        let positive = get_variable(positive, Unit::Volt, variables, var_map);
+        let negative = parser.parse_node("behavioral source", name, "negative node")?;
+        let negative = get_variable(negative, Unit::Volt, variables, var_map);
+        let output =
+            parser.parse_pair(Rule::BEHAVIORAL_OUTPUT, "behavioral source", name, "V or I")?;
+        let output = if output.as_str().eq_ignore_ascii_case("v") {
+            let branch = format!("{name}#branch");
+            BehavioralOutput::Voltage(get_variable(&branch, Unit::Ampere, variables, var_map))
+        } else {
+            BehavioralOutput::Current
+        };
+        let expression =
+            parser.parse_pair(Rule::EXPRESSION, "behavioral source", name, "expression")?;
+
+        // The expression is defined with the `.func` definitions after parsing
+        let behavioral = BehavioralBundle::new(
+            Arc::from(name),
+            positive,
+            negative,
+            output,
+            parse_expression(expression)?,
+            Vec::new(),
+        );
+        elements.push(Element::Behavioral(behavioral));
+        Ok(())
+    }
+}
+
+impl BehavioralBundle {
+    /// Defines the parsed expression with the user functions `functions` and binds it to the
+    /// node voltages and branch currents. Currents refer to voltage sources of the circuit.
+    pub(crate) fn define_expression(
+        &mut self,
+        functions: &[UserFunction],
+        variables: &mut Vec<Variable>,
+        var_map: &mut HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError> {
+        let invalid = |reason: String| FrontendError::InvalidExpression {
+            element: self.name().to_string(),
+            reason,
+        };
+        let mut expression = self
+            .expression()
+            .clone()
+            .define(functions)
+            .map_err(invalid)?;
+        let names = expression.bind().map_err(invalid)?;
+
+        let mut inputs = Vec::with_capacity(names.len());
+        for (name, unit) in names {
+            if unit == Unit::Ampere && !var_map.contains_key(&name) {
+                let source = name.trim_end_matches("#branch");
+                return Err(invalid(format!(
+                    "I({source}) needs a voltage source named {source}"
+                )));
+            }
+            inputs.push(get_variable(&name, unit, variables, var_map));
+        }
+        self.set_expression(expression, inputs);
+        Ok(())
+    }
+}
+
+/// Processes a `.func <Name>(<Parameter>, ...) {<Expression>}` definition.
+pub(crate) fn process_function(command: Pair<Rule>) -> Result<UserFunction, FrontendError> {
+    let mut name = None;
+    let mut parameters = Vec::new();
+    let mut body = None;
+    for pair in command.into_inner() {
+        match pair.as_rule() {
+            Rule::EXPR_IDENTIFIER if name.is_none() => name = Some(Arc::from(pair.as_str())),
+            Rule::EXPR_IDENTIFIER => parameters.push(Arc::from(pair.as_str())),
+            Rule::EXPRESSION => body = Some(parse_expression(pair)?),
+            _ => {}
+        }
+    }
+    let (Some(name), Some(body)) = (name, body) else {
+        return Err(FrontendError::ParseError(
+            "Malformed .func definition".into(),
+        ));
+    };
+    Ok(UserFunction {
+        name,
+        parameters,
+        body,
+    })
+}
+
+/// Parses a standalone expression like `V(a) * V(b)`, which may be enclosed in braces.
+pub(crate) fn parse_expression_str(text: &str) -> Result<Expression, FrontendError> {
+    let expression = SpiceParser::parse(Rule::STANDALONE_EXPRESSION, text)?
+        .find(|pair| pair.as_rule() == Rule::EXPRESSION)
+        .ok_or_else(|| FrontendError::ParseError(format!("Missing expression in '{text}'")))?;
+    parse_expression(expression)
+}
+
+/// Builds the syntax tree of an `EXPRESSION` pair.
+pub(crate) fn parse_expression(expression: Pair<Rule>) -> Result<Expression, FrontendError> {
+    PRATT_PARSER
+        .map_primary(parse_primary)
+        .map_prefix(|op, operand| {
+            let operand = operand?;
+            Ok(match op.as_rule() {
+                Rule::EXPR_NEG => Expression::Unary(UnaryOp::Neg, Box::new(operand)),
+                Rule::EXPR_NOT => Expression::Unary(UnaryOp::Not, Box::new(operand)),
+                _ => operand,
+            })
+        })
+        .map_infix(|lhs, op, rhs| {
+            let op = match op.as_rule() {
+                Rule::EXPR_OR => BinaryOp::Or,
+                Rule::EXPR_AND => BinaryOp::And,
+                Rule::EXPR_EQ => BinaryOp::Eq,
+                Rule::EXPR_NE => BinaryOp::Ne,
+                Rule::EXPR_LT => BinaryOp::Lt,
+                Rule::EXPR_LE => BinaryOp::Le,
+                Rule::EXPR_GT => BinaryOp::Gt,
+                Rule::EXPR_GE => BinaryOp::Ge,
+                Rule::EXPR_ADD => BinaryOp::Add,
+                Rule::EXPR_SUB => BinaryOp::Sub,
+                Rule::EXPR_MUL => BinaryOp::Mul,
+                Rule::EXPR_DIV => BinaryOp::Div,
+                _ => BinaryOp::Pow,
+            };
+            Ok(Expression::Binary(op, Box::new(lhs?), Box::new(rhs?)))
+        })
+        .parse(expression.into_inner())
+}
+
+fn parse_primary(primary: Pair<Rule>) -> Result<Expression, FrontendError> {
+    let text = primary.as_str();
+    Ok(match primary.as_rule() {
+        Rule::EXPR_NUMBER => Expression::Number(text.parse()?),
+        Rule::EXPR_VOLTAGE => {
+            let mut nodes = primary
+                .into_inner()
+                .map(|node| Expression::Voltage(Arc::from(node.as_str())));
+            match (nodes.next(), nodes.next()) {
+                (Some(positive), Some(negative)) => {
+                    Expression::Binary(BinaryOp::Sub, Box::new(positive), Box::new(negative))
+                }
+                (Some(node), None) => node,
+                _ => {
+                    return Err(FrontendError::ParseError(format!(
+                        "Missing node in '{text}'"
+                    )))
+                }
+            }
+        }
+        Rule::EXPR_CURRENT => {
+            let source = primary
+                .into_inner()
+                .next()
+                .ok_or_else(|| FrontendError::ParseError(format!("Missing source in '{text}'")))?;
+            Expression::Current(Arc::from(source.as_str()))
+        }
+        Rule::EXPR_CALL => {
+            let mut inner = primary.into_inner();
+            let name = inner.next().ok_or_else(|| {
+                FrontendError::ParseError(format!("Missing function in '{text}'"))
+            })?;
+            let arguments = inner.map(parse_expression).collect::<Result<_, _>>()?;
+            Expression::Call(Arc::from(name.as_str()), arguments)
+        }
+        Rule::EXPR_NAME => Expression::Name(Arc::from(text)),
+        Rule::EXPRESSION => parse_expression(primary)?,
+        rule => {
+            return Err(FrontendError::ParseError(format!(
+                "Unexpected {rule:?} in expression '{text}'"
+            )))
+        }
+    })
+}
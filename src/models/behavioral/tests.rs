@@ -0,0 +1,221 @@
+// Basic tests for behavioral source model
+use super::expression::{Environment, Expression, UserFunction};
+use super::spice::parse_expression_str;
+use super::{BehavioralBundle, BehavioralOutput};
+use crate::models::{Unit, Variable};
+use crate::spot::Numeric;
+use std::sync::Arc;
+
+/// Parses, defines and binds `text` with the user functions `functions`.
+fn expression(text: &str, functions: &[UserFunction]) -> (Expression, Vec<(Arc<str>, Unit)>) {
+    let mut expression = parse_expression_str(text)
+        .unwrap()
+        .define(functions)
+        .unwrap();
+    let inputs = expression.bind().unwrap();
+    (expression, inputs)
+}
+
+/// Evaluates the constant expression `text`.
+fn value(text: &str) -> Numeric {
+    let (expression, _) = expression(text, &[]);
+    let env = Environment {
+        inputs: &[],
+        time: 0.0,
+        temp: 27.0,
+    };
+    expression.evaluate(&env).value
+}
+
+/// Creates a behavioral source between the indices 0 and 1, whose inputs are the variables
+/// following the nodes.
+fn source(text: &str, output: BehavioralOutput) -> BehavioralBundle {
+    let (expression, names) = expression(text, &[]);
+    let inputs = names
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (name, unit))| Some(Variable::new(name, unit, idx + 3)))
+        .collect();
+    BehavioralBundle::new(
+        Arc::from("B1"),
+        Some(Variable::new(Arc::from("pos"), Unit::Volt, 0)),
+        Some(Variable::new(Arc::from("neg"), Unit::Volt, 1)),
+        output,
+        expression,
+        inputs,
+    )
+}
+
+/// Returns the linearised equations `G * x - b` of the source at `x_vec`.
+fn residuals(source: &BehavioralBundle, x_vec: &[Numeric]) -> Vec<Numeric> {
+    let mut residuals = vec![0.0; x_vec.len()];
+    let constant = source.constant_triples();
+    for &(row, col, value) in source
+        .triples(x_vec)
+        .into_iter()
+        .chain(constant.iter().flatten())
+    {
+        residuals[row] += value * x_vec[col];
+    }
+    for &(row, value) in &source.pairs(x_vec) {
+        residuals[row] -= value;
+    }
+    residuals
+}
+
+#[test]
+fn test_expression_precedence() {
+    assert_eq!(value("1 + 2 * 3"), 7.0);
+    assert_eq!(value("{(1 + 2) * 3}"), 9.0);
+    assert_eq!(value("-2^2"), -4.0);
+    assert_eq!(value("2**3^2"), 512.0);
+    assert_eq!(value("8 / 4 / 2"), 1.0);
+    assert_eq!(value("1 < 2 && 3 >= 4 || !0"), 1.0);
+    assert_eq!(value("1 + 1 == 2"), 1.0);
+}
+
+#[test]
+fn test_expression_functions() {
+    assert_eq!(value("if(1 != 1, 2, 3)"), 3.0);
+    assert_eq!(value("limit(5, 1, 2)"), 2.0);
+    assert_eq!(value("limit(-5, 2, 1)"), 1.0);
+    assert_eq!(value("min(2, -3) + max(2, -3)"), -1.0);
+    assert_eq!(value("pwr(-4, 0.5)"), 2.0);
+    assert_eq!(value("u(0) + u(1e-3) + sgn(-2)"), 0.0);
+    assert_eq!(value("LOG10(1000)"), 3.0);
+    assert_eq!(value("atan2(0, 1)"), 0.0);
+    assert!((value("sin(pi / 2)") - 1.0).abs() < 1e-15);
+    assert_eq!(value("temp"), 27.0);
+    assert_eq!(value("table(0.5, 0, 0, 1, 2) + table(3, 0, 0, 1, 2)"), 3.0);
+}
+
+#[test]
+fn test_expression_user_functions() {
+    let functions = [
+        UserFunction {
+            name: Arc::from("square"),
+            parameters: vec![Arc::from("x")],
+            body: parse_expression_str("x * x").unwrap(),
+        },
+        UserFunction {
+            name: Arc::from("gain"),
+            parameters: vec![Arc::from("a"), Arc::from("b")],
+            body: parse_expression_str("SQUARE(a) - b").unwrap(),
+        },
+    ];
+    let (expression, inputs) = expression("gain(V(in), V(in, ref)) + time", &functions);
+    assert_eq!(
+        inputs,
+        vec![
+            (Arc::from("in"), Unit::Volt),
+            (Arc::from("ref"), Unit::Volt)
+        ]
+    );
+
+    let env = Environment {
+        inputs: &[3.0, 1.0],
+        time: 0.5,
+        temp: 27.0,
+    };
+    let output = expression.evaluate(&env);
+    assert_eq!(output.value, 9.0 - 2.0 + 0.5);
+    assert_eq!(output.grad[..2], [6.0 - 1.0, 1.0]);
+}
+
+#[test]
+fn test_expression_errors() {
+    let define = |text: &str, functions: &[UserFunction]| {
+        parse_expression_str(text).unwrap().define(functions)
+    };
+    assert!(parse_expression_str("V(a) *").is_err());
+    assert!(define("foo(1)", &[]).is_err());
+    assert!(define("limit(1, 2)", &[]).is_err());
+    assert!(define("table(1, 2)", &[]).is_err());
+    assert!(define("vdd * 2", &[]).is_err());
+
+    let recursive = [UserFunction {
+        name: Arc::from("f"),
+        parameters: vec![Arc::from("x")],
+        body: parse_expression_str("f(x) + 1").unwrap(),
+    }];
+    assert!(define("f(1)", &recursive).is_err());
+    assert!(define("f(1, 2)", &recursive).is_err());
+
+    let many = (0..13)
+        .map(|node| format!("V(n{node})"))
+        .collect::<Vec<_>>();
+    let mut expression = define(&many.join(" + "), &[]).unwrap();
+    assert!(expression.bind().is_err());
+}
+
+#[test]
+fn test_expression_ground_and_currents() {
+    let (expression, inputs) = expression("V(a, 0) * I(V1) + V(0)", &[]);
+    assert_eq!(
+        inputs,
+        vec![
+            (Arc::from("a"), Unit::Volt),
+            (Arc::from("V1#branch"), Unit::Ampere)
+        ]
+    );
+    let env = Environment {
+        inputs: &[2.0, 1e-3],
+        time: 0.0,
+        temp: 27.0,
+    };
+    assert_eq!(expression.evaluate(&env).value, 2e-3);
+}
+
+#[test]
+fn test_behavioral_exact_jacobian() {
+    let h = 1e-6;
+    let size = 5;
+    let branch = Variable::new(Arc::from("B1#branch"), Unit::Ampere, 2);
+    for output in [
+        BehavioralOutput::Current,
+        BehavioralOutput::Voltage(Some(branch)),
+    ] {
+        let source = source(
+            "V(a) * V(b) + exp(V(a) / 2) - limit(V(b), -1, 1) ** 3",
+            output,
+        );
+        for inputs in [[0.3, -0.4], [1.2, 2.0], [-0.7, -3.0]] {
+            let mut x_vec = vec![0.5, -0.25, 1e-3, 0.0, 0.0];
+            x_vec[3..].copy_from_slice(&inputs);
+            let mut jacobian = vec![vec![0.0; size]; size];
+            for &(row, col, value) in &source.triples(&x_vec) {
+                jacobian[row][col] += value;
+            }
+            for col in 3..size {
+                let mut x_plus = x_vec.clone();
+                let mut x_minus = x_vec.clone();
+                x_plus[col] += h;
+                x_minus[col] -= h;
+                let plus = residuals(&source, &x_plus);
+                let minus = residuals(&source, &x_minus);
+                for row in 0..3 {
+                    let numeric = (plus[row] - minus[row]) / (2.0 * h);
+                    assert!(
+                        (jacobian[row][col] - numeric).abs() < 1e-6 * numeric.abs() + 1e-8,
+                        "d{row}/dx{col} at {inputs:?}: {} != {numeric}",
+                        jacobian[row][col]
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_behavioral_stamps() {
+    let current = source("2 * V(a)", BehavioralOutput::Current);
+    let x_vec = [0.0, 0.0, 0.0, 1.5];
+    // The current of 3 A leaves the positive and enters the negative node
+    assert_eq!(residuals(&current, &x_vec), vec![3.0, -3.0, 0.0, 0.0]);
+
+    let branch = Variable::new(Arc::from("B1#branch"), Unit::Ampere, 2);
+    let voltage = source("V(a) ** 2", BehavioralOutput::Voltage(Some(branch)));
+    // The branch equation V(pos) - V(neg) - f(V(a)) vanishes at the solution
+    let x_vec = [5.0, 1.0, 0.25, 2.0];
+    assert_eq!(residuals(&voltage, &x_vec), vec![0.25, -0.25, 0.0, 0.0]);
+}
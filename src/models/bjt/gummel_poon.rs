@@ -0,0 +1,717 @@
+use std::ops::Neg;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use num::traits::FloatConst;
+use num::{One, Zero};
+use serde::{Deserialize, Serialize};
+
+use super::super::charge::{junction_charge, voltage_between, Charge};
+use super::super::limiting;
+use super::super::temperature::{self, DeviceTemperature};
+use super::super::*;
+use crate::sim::options::SimulatorOptions;
+
+/// The rows and columns of the intrinsic transistor in its conductance matrix.
+const COLLECTOR: usize = 0;
+const BASE: usize = 1;
+const EMITTER: usize = 2;
+
+/// A structure representing a bipolar junction transistor with the Gummel-Poon model.
+///
+/// The junctions sit between the internal nodes behind the base, collector and emitter
+/// resistances. Without a resistance the internal node is the terminal itself.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct BjtBundle {
+    name: Arc<str>,
+    collector: Option<Variable>,
+    base: Option<Variable>,
+    emitter: Option<Variable>,
+    /// The internal collector node behind `rc`
+    internal_collector: Option<Variable>,
+    /// The internal base node behind `rb`
+    internal_base: Option<Variable>,
+    /// The internal emitter node behind `re`
+    internal_emitter: Option<Variable>,
+    /// The name of the `.model` card of the transistor
+    model: Option<Arc<str>>,
+    options: BjtOptions,
+    temperature: DeviceTemperature,
+    /// The thermal voltage at the temperature of the transistor
+    ut: Numeric,
+    /// The transport saturation current at the temperature of the transistor
+    is: Numeric,
+    /// The base-emitter leakage saturation current at the temperature of the transistor
+    ise: Numeric,
+    /// The base-collector leakage saturation current at the temperature of the transistor
+    isc: Numeric,
+    /// The forward current gain at the temperature of the transistor
+    bf: Numeric,
+    /// The reverse current gain at the temperature of the transistor
+    br: Numeric,
+    /// The conductance in parallel to the junctions
+    gmin: Numeric,
+}
+
+/// The polarity of a bipolar transistor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BjtPolarity {
+    #[default]
+    Npn,
+    Pnp,
+}
+
+impl BjtPolarity {
+    /// Returns the sign of the junction voltages and terminal currents of an NPN transistor.
+    fn sign(self) -> Numeric {
+        match self {
+            BjtPolarity::Npn => 1.0,
+            BjtPolarity::Pnp => -1.0,
+        }
+    }
+}
+
+impl FromStr for BjtPolarity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "npn" => Ok(BjtPolarity::Npn),
+            "pnp" => Ok(BjtPolarity::Pnp),
+            _ => Err(format!("Unknown BJT model type '{s}'. Use NPN or PNP")),
+        }
+    }
+}
+
+/// A struct representing the Gummel-Poon parameters of a BJT, as in a SPICE `.model` card.
+/// Infinite voltages and currents are given as zero, as in SPICE.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct BjtOptions {
+    polarity: BjtPolarity,
+    /// Transport saturation current
+    is: Numeric,
+    /// Ideal maximum forward current gain
+    bf: Numeric,
+    /// Forward emission coefficient
+    nf: Numeric,
+    /// Forward Early voltage
+    vaf: Numeric,
+    /// Corner of the forward high-level injection roll-off
+    ikf: Numeric,
+    /// Base-emitter leakage saturation current
+    ise: Numeric,
+    /// Base-emitter leakage emission coefficient
+    ne: Numeric,
+    /// Ideal maximum reverse current gain
+    br: Numeric,
+    /// Reverse emission coefficient
+    nr: Numeric,
+    /// Reverse Early voltage
+    var: Numeric,
+    /// Corner of the reverse high-level injection roll-off
+    ikr: Numeric,
+    /// Base-collector leakage saturation current
+    isc: Numeric,
+    /// Base-collector leakage emission coefficient
+    nc: Numeric,
+    /// Base resistance
+    rb: Numeric,
+    /// Collector resistance
+    rc: Numeric,
+    /// Emitter resistance
+    re: Numeric,
+    /// Zero-bias base-emitter depletion capacitance
+    cje: Numeric,
+    /// Base-emitter built-in potential
+    vje: Numeric,
+    /// Base-emitter grading coefficient
+    mje: Numeric,
+    /// Ideal forward transit time
+    tf: Numeric,
+    /// Zero-bias base-collector depletion capacitance
+    cjc: Numeric,
+    /// Base-collector built-in potential
+    vjc: Numeric,
+    /// Base-collector grading coefficient
+    mjc: Numeric,
+    /// Ideal reverse transit time
+    tr: Numeric,
+    /// Forward-bias depletion capacitance coefficient
+    fc: Numeric,
+    /// Band gap energy in eV
+    eg: Numeric,
+    /// Saturation current temperature exponent
+    xti: Numeric,
+    /// Current gain temperature exponent
+    xtb: Numeric,
+}
+
+impl Default for BjtOptions {
+    fn default() -> Self {
+        Self {
+            polarity: BjtPolarity::Npn,
+            is: 1e-16,
+            bf: 100.0,
+            nf: 1.0,
+            vaf: 0.0,
+            ikf: 0.0,
+            ise: 0.0,
+            ne: 1.5,
+            br: 1.0,
+            nr: 1.0,
+            var: 0.0,
+            ikr: 0.0,
+            isc: 0.0,
+            nc: 2.0,
+            rb: 0.0,
+            rc: 0.0,
+            re: 0.0,
+            cje: 0.0,
+            vje: 0.75,
+            mje: 0.33,
+            tf: 0.0,
+            cjc: 0.0,
+            vjc: 0.75,
+            mjc: 0.33,
+            tr: 0.0,
+            fc: 0.5,
+            eg: 1.11,
+            xti: 3.0,
+            xtb: 0.0,
+        }
+    }
+}
+
+impl BjtOptions {
+    /// Sets the polarity of the transistor.
+    pub fn with_polarity(mut self, polarity: BjtPolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Sets the model parameter `name` with its SPICE name, like `BF` or `VAF`.
+    /// Returns false if `name` is not a parameter of the model.
+    pub fn set_parameter(&mut self, name: &str, value: Numeric) -> bool {
+        let parameter = match name.to_ascii_lowercase().as_str() {
+            "is" => &mut self.is,
+            "bf" => &mut self.bf,
+            "nf" => &mut self.nf,
+            "vaf" | "va" => &mut self.vaf,
+            "ikf" | "ik" => &mut self.ikf,
+            "ise" => &mut self.ise,
+            "ne" => &mut self.ne,
+            "br" => &mut self.br,
+            "nr" => &mut self.nr,
+            "var" | "vb" => &mut self.var,
+            "ikr" => &mut self.ikr,
+            "isc" => &mut self.isc,
+            "nc" => &mut self.nc,
+            "rb" => &mut self.rb,
+            "rc" => &mut self.rc,
+            "re" => &mut self.re,
+            "cje" => &mut self.cje,
+            "vje" | "pe" => &mut self.vje,
+            "mje" | "me" => &mut self.mje,
+            "tf" => &mut self.tf,
+            "cjc" => &mut self.cjc,
+            "vjc" | "pc" => &mut self.vjc,
+            "mjc" | "mc" => &mut self.mjc,
+            "tr" => &mut self.tr,
+            "fc" => &mut self.fc,
+            "eg" => &mut self.eg,
+            "xti" => &mut self.xti,
+            "xtb" => &mut self.xtb,
+            _ => return false,
+        };
+        *parameter = value;
+        true
+    }
+
+    /// Returns the base, collector and emitter resistances.
+    pub fn resistances(&self) -> [Numeric; 3] {
+        [self.rb, self.rc, self.re]
+    }
+}
+
+impl BjtBundle {
+    /// Creates a new `BjtBundle` object with the terminals in SPICE order.
+    pub fn new(
+        name: Arc<str>,
+        collector: Option<Variable>,
+        base: Option<Variable>,
+        emitter: Option<Variable>,
+        options: Option<BjtOptions>,
+    ) -> BjtBundle {
+        let options = options.unwrap_or_default();
+
+        BjtBundle {
+            name,
+            collector,
+            base,
+            emitter,
+            internal_collector: None,
+            internal_base: None,
+            internal_emitter: None,
+            model: None,
+            is: options.is,
+            ise: options.ise,
+            isc: options.isc,
+            bf: options.bf,
+            br: options.br,
+            options,
+            temperature: DeviceTemperature::default(),
+            ut: UT,
+            gmin: GMIN,
+        }
+    }
+
+    /// Sets the name of the `.model` card of the transistor.
+    pub fn set_model(&mut self, model: Arc<str>) {
+        self.model = Some(model);
+    }
+
+    /// Returns the name of the `.model` card of the transistor.
+    pub fn model(&self) -> Option<Arc<str>> {
+        self.model.clone()
+    }
+
+    /// Replaces the model parameters of the transistor.
+    pub fn set_options(&mut self, options: BjtOptions) {
+        self.is = options.is;
+        self.ise = options.ise;
+        self.isc = options.isc;
+        self.bf = options.bf;
+        self.br = options.br;
+        self.options = options;
+    }
+
+    /// Returns the model parameters of the transistor.
+    pub fn options(&self) -> &BjtOptions {
+        &self.options
+    }
+
+    /// Sets the internal base, collector and emitter nodes behind the resistances.
+    /// `None` connects the junctions to the terminal directly.
+    pub fn set_internal_nodes(
+        &mut self,
+        base: Option<Variable>,
+        collector: Option<Variable>,
+        emitter: Option<Variable>,
+    ) {
+        self.internal_base = base;
+        self.internal_collector = collector;
+        self.internal_emitter = emitter;
+    }
+
+    /// Sets the instance temperature of the transistor.
+    pub fn set_temperature(&mut self, temperature: DeviceTemperature) {
+        self.temperature = temperature;
+    }
+
+    /// Scales the saturation currents and current gains to the temperature of the transistor
+    /// and applies gmin of the simulator settings.
+    pub fn apply_options(&mut self, options: &SimulatorOptions) {
+        let temp = self.temperature.kelvin(options);
+        let tnom = options.tnom_kelvin();
+        let BjtOptions {
+            is,
+            ise,
+            ne,
+            isc,
+            nc,
+            eg,
+            xti,
+            xtb,
+            ..
+        } = self.options;
+        // The current gains rise with (T / Tnom)^xtb, the leakage currents fall with it
+        let gain = (temp / tnom).powf(xtb);
+
+        self.ut = temperature::thermal_voltage(temp);
+        self.is = temperature::saturation_current(is, 1.0, eg, xti, temp, tnom);
+        self.ise = temperature::saturation_current(ise, ne, eg, xti, temp, tnom) / gain;
+        self.isc = temperature::saturation_current(isc, nc, eg, xti, temp, tnom) / gain;
+        self.bf = self.options.bf * gain;
+        self.br = self.options.br * gain;
+        self.gmin = options.gmin;
+    }
+
+    /// Returns the name of the BJT bundle.
+    pub fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    /// Returns a reference to the triples representing matrix A.
+    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 24> {
+        let [v_be, v_bc] = self.voltages(x_vec);
+        self.triples_at(v_be, v_bc)
+    }
+
+    /// Returns the triples of the terminal resistances and of the junctions linearised at
+    /// `v_be` and `v_bc`.
+    pub fn triples_at(&self, v_be: Numeric, v_bc: Numeric) -> Triples<Numeric, 24> {
+        self.stamps(self.operating_point(v_be, v_bc).conductances())
+    }
+
+    /// Returns the indices of the triples representing matrix A.
+    pub fn triple_idx(&self) -> Option<TripleIdx<24>> {
+        let indices: Vec<_> = self
+            .stamps([[0.0; 3]; 3])
+            .into_iter()
+            .map(|&(row, col, _)| (row, col))
+            .collect();
+        Some(TripleIdx::new(&indices))
+    }
+
+    /// Returns a reference to the pairs representing vector b.
+    pub fn pairs(&self, x_vec: &[Numeric]) -> Pairs<Numeric, 4> {
+        let [v_be, v_bc] = self.voltages(x_vec);
+        self.pairs_at(v_be, v_bc)
+    }
+
+    /// Returns the pairs of the equivalent current sources `i - gbe * vbe - gbc * vbc` of the
+    /// collector, base and emitter currents linearised at `v_be` and `v_bc`.
+    /// The currents flow into the transistor and enter the RHS with the opposite sign.
+    pub fn pairs_at(&self, v_be: Numeric, v_bc: Numeric) -> Pairs<Numeric, 4> {
+        let op = self.operating_point(v_be, v_bc);
+        let sign = self.options.polarity.sign();
+
+        let pairs: Vec<_> = self
+            .internal_nodes()
+            .into_iter()
+            .zip(op.currents())
+            .filter_map(|(node, (current, g_be, g_bc))| {
+                let i_eq = current - g_be * v_be - g_bc * v_bc;
+                Some((node?, -sign * i_eq))
+            })
+            .collect();
+        Pairs::new(&pairs)
+    }
+
+    /// Returns the small-signal triples at the frequency `freq` in Hz, linearised at the
+    /// operating point `x_vec`. The junction capacitances are part of the junctions.
+    pub fn ac_triples(&self, freq: Numeric, x_vec: &[Numeric]) -> Triples<ComplexNumeric, 24> {
+        let [v_be, v_bc] = self.voltages(x_vec);
+        let conductances = self.operating_point(v_be, v_bc).conductances();
+        let omega = 2.0 * Numeric::PI() * freq;
+        let [c_be, c_bc] = self.charges(x_vec).map(|charge| charge.capacitance);
+
+        let mut admittances = conductances.map(|row| row.map(ComplexNumeric::from));
+        for (node, c) in [(EMITTER, c_be), (COLLECTOR, c_bc)] {
+            let y = ComplexNumeric::new(0.0, omega * c);
+            admittances[BASE][BASE] += y;
+            admittances[node][node] += y;
+            admittances[BASE][node] -= y;
+            admittances[node][BASE] -= y;
+        }
+        self.stamps(admittances)
+    }
+
+    /// Returns the triples of the admittance matrix of the intrinsic transistor between the
+    /// internal collector, base and emitter and of the terminal resistances.
+    fn stamps<T>(&self, matrix: [[T; 3]; 3]) -> Triples<T, 24>
+    where
+        T: Copy + Default + From<Numeric> + Neg<Output = T>,
+    {
+        let nodes = self.internal_nodes();
+        let mut triples = Vec::with_capacity(24);
+        for (row, values) in nodes.iter().zip(matrix) {
+            for (col, value) in nodes.iter().zip(values) {
+                if let (Some(row), Some(col)) = (row, col) {
+                    triples.push((*row, *col, value));
+                }
+            }
+        }
+
+        let terminals = [
+            (&self.base, &self.internal_base),
+            (&self.collector, &self.internal_collector),
+            (&self.emitter, &self.internal_emitter),
+        ];
+        for ((terminal, internal), resistance) in
+            terminals.into_iter().zip(self.options.resistances())
+        {
+            let Some(internal) = internal.as_ref().map(Variable::idx) else {
+                continue;
+            };
+            let terminal = terminal.as_ref().map(Variable::idx);
+            let g = T::from(resistance.recip());
+            triples.push((internal, internal, g));
+            if let Some(terminal) = terminal {
+                triples.extend([
+                    (terminal, terminal, g),
+                    (terminal, internal, -g),
+                    (internal, terminal, -g),
+                ]);
+            }
+        }
+        Triples::new(&triples)
+    }
+
+    /// Returns the base-emitter and base-collector voltages of an NPN transistor at the
+    /// solution `x_vec`. The voltages of a PNP transistor are inverted.
+    pub fn voltages(&self, x_vec: &[Numeric]) -> [Numeric; 2] {
+        let sign = self.options.polarity.sign();
+        [
+            sign * voltage_between(self.bi_idx(), self.ei_idx(), x_vec),
+            sign * voltage_between(self.bi_idx(), self.ci_idx(), x_vec),
+        ]
+    }
+
+    /// Limits the Newton steps of the base-emitter and base-collector voltages.
+    /// Returns the limited voltages and whether any of them was limited.
+    pub fn limit_voltages(&self, new: [Numeric; 2], old: [Numeric; 2]) -> ([Numeric; 2], bool) {
+        let vt_f = self.options.nf * self.ut;
+        let vt_r = self.options.nr * self.ut;
+        let (v_be, be_limited) = limiting::pn_junction(
+            new[0],
+            old[0],
+            vt_f,
+            limiting::pn_critical_voltage(vt_f, self.is),
+        );
+        let (v_bc, bc_limited) = limiting::pn_junction(
+            new[1],
+            old[1],
+            vt_r,
+            limiting::pn_critical_voltage(vt_r, self.is),
+        );
+        ([v_be, v_bc], be_limited || bc_limited)
+    }
+
+    /// Returns the operating region at `v_be` and `v_bc`.
+    pub fn region(&self, v_be: Numeric, v_bc: Numeric) -> &'static str {
+        match (v_be >= 0.0, v_bc >= 0.0) {
+            (true, false) => "forward active",
+            (true, true) => "saturation",
+            (false, true) => "reverse active",
+            (false, false) => "cutoff",
+        }
+    }
+
+    /// Evaluates the Gummel-Poon equations at `v_be` and `v_bc`.
+    ///
+    /// The transport current `(ibf - ibr) / qb` is divided by the normalised base charge `qb`,
+    /// which models the Early effect by `vaf` and `var` and the high-level injection by `ikf`
+    /// and `ikr`. The base current adds the leakage of both junctions to the ideal currents
+    /// divided by the current gains.
+    fn operating_point(&self, v_be: Numeric, v_bc: Numeric) -> OperatingPoint {
+        let options = &self.options;
+        let (i_bf, g_bf) = junction(self.is, v_be, options.nf * self.ut);
+        let (i_br, g_br) = junction(self.is, v_bc, options.nr * self.ut);
+        let (i_le, g_le) = junction(self.ise, v_be, options.ne * self.ut);
+        let (i_lc, g_lc) = junction(self.isc, v_bc, options.nc * self.ut);
+        let (qb, dqb_dvbe, dqb_dvbc) = self.base_charge(v_be, v_bc);
+
+        let i_t = (i_bf - i_br) / qb;
+        let dit_dvbe = (g_bf - i_t * dqb_dvbe) / qb;
+        let dit_dvbc = (-g_br - i_t * dqb_dvbc) / qb;
+
+        let gmin = self.gmin;
+        OperatingPoint {
+            ic: i_t - i_br / self.br - i_lc - gmin * v_bc,
+            dic_dvbe: dit_dvbe,
+            dic_dvbc: dit_dvbc - g_br / self.br - g_lc - gmin,
+            ib: i_bf / self.bf + i_le + i_br / self.br + i_lc + gmin * (v_be + v_bc),
+            dib_dvbe: g_bf / self.bf + g_le + gmin,
+            dib_dvbc: g_br / self.br + g_lc + gmin,
+        }
+    }
+
+    /// Returns the normalised base charge `qb` at `v_be` and `v_bc` with its derivatives by
+    /// `v_be` and `v_bc`.
+    fn base_charge(&self, v_be: Numeric, v_bc: Numeric) -> (Numeric, Numeric, Numeric) {
+        let options = &self.options;
+        let (i_bf, g_bf) = junction(self.is, v_be, options.nf * self.ut);
+        let (i_br, g_br) = junction(self.is, v_bc, options.nr * self.ut);
+
+        // The Early effect widens or narrows the base
+        let q1 = (1.0 - v_bc * inverse(options.vaf) - v_be * inverse(options.var)).recip();
+        let dq1_dvbe = q1 * q1 * inverse(options.var);
+        let dq1_dvbc = q1 * q1 * inverse(options.vaf);
+
+        // The high-level injection adds the charge of the minority carriers
+        let q2 = i_bf * inverse(options.ikf) + i_br * inverse(options.ikr);
+        let sqrt = (1.0 + 4.0 * q2).max(0.0).sqrt();
+        let qb = q1 * (1.0 + sqrt) / 2.0;
+        if sqrt > 0.0 {
+            (
+                qb,
+                dq1_dvbe * (1.0 + sqrt) / 2.0 + q1 * g_bf * inverse(options.ikf) / sqrt,
+                dq1_dvbc * (1.0 + sqrt) / 2.0 + q1 * g_br * inverse(options.ikr) / sqrt,
+            )
+        } else {
+            (qb, dq1_dvbe / 2.0, dq1_dvbc / 2.0)
+        }
+    }
+
+    /// Returns the base-emitter and base-collector charges at the solution `x_vec`.
+    /// Each consists of the depletion charge of the junction and the diffusion charge. The
+    /// base-emitter diffusion charge `tf * ibf / qb` is the forward transit time times the
+    /// forward transport current, so it saturates with the base charge `qb` like the current.
+    /// The base-collector diffusion charge is `tr * ibr`.
+    ///
+    /// The capacitance is the derivative by the voltage of the own junction. SPICE also stamps
+    /// the derivative of the base-emitter charge by `v_bc` through `qb` as a transcapacitance,
+    /// which is left out. The bias dependence of the transit time by `xtf`, `vtf` and `itf` is
+    /// not modelled yet, so these parameters are rejected by [`BjtOptions::set_parameter`].
+    pub fn charges(&self, x_vec: &[Numeric]) -> [Charge; 2] {
+        let options = &self.options;
+        let [v_be, v_bc] = self.voltages(x_vec);
+        let (q_be, c_be) = junction_charge(v_be, options.cje, options.vje, options.mje, options.fc);
+        let (q_bc, c_bc) = junction_charge(v_bc, options.cjc, options.vjc, options.mjc, options.fc);
+        let (i_bf, g_bf) = junction(self.is, v_be, options.nf * self.ut);
+        let (i_br, g_br) = junction(self.is, v_bc, options.nr * self.ut);
+        let (qb, dqb_dvbe, _) = self.base_charge(v_be, v_bc);
+        let i_f = i_bf / qb;
+        let g_f = (g_bf - i_f * dqb_dvbe) / qb;
+
+        // The charges of a PNP transistor are stored from the emitter and collector to the base
+        let (be_nodes, bc_nodes) = match options.polarity {
+            BjtPolarity::Npn => (
+                (self.bi_idx(), self.ei_idx()),
+                (self.bi_idx(), self.ci_idx()),
+            ),
+            BjtPolarity::Pnp => (
+                (self.ei_idx(), self.bi_idx()),
+                (self.ci_idx(), self.bi_idx()),
+            ),
+        };
+        [
+            Charge::new(
+                be_nodes.0,
+                be_nodes.1,
+                v_be,
+                q_be + options.tf * i_f,
+                c_be + options.tf * g_f,
+            ),
+            Charge::new(
+                bc_nodes.0,
+                bc_nodes.1,
+                v_bc,
+                q_bc + options.tr * i_br,
+                c_bc + options.tr * g_br,
+            ),
+        ]
+    }
+
+    /// Returns the internal collector, base and emitter nodes, in the order of the rows of the
+    /// conductance matrix.
+    fn internal_nodes(&self) -> [Option<usize>; 3] {
+        [self.ci_idx(), self.bi_idx(), self.ei_idx()]
+    }
+
+    /// Returns the indices of the internal nodes behind the terminal resistances.
+    pub fn internal_node_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.resistor_nodes().map(|(_, internal)| internal)
+    }
+
+    /// Returns the terminal and the internal node of each terminal resistance.
+    pub fn resistor_nodes(&self) -> impl Iterator<Item = (Option<usize>, usize)> + '_ {
+        [
+            (&self.base, &self.internal_base),
+            (&self.collector, &self.internal_collector),
+            (&self.emitter, &self.internal_emitter),
+        ]
+        .into_iter()
+        .filter_map(|(terminal, internal)| {
+            let internal = internal.as_ref()?.idx();
+            Some((terminal.as_ref().map(Variable::idx), internal))
+        })
+    }
+
+    pub fn b_idx(&self) -> Option<usize> {
+        self.base.as_ref().map(|v| v.idx())
+    }
+
+    pub fn c_idx(&self) -> Option<usize> {
+        self.collector.as_ref().map(|v| v.idx())
+    }
+
+    pub fn e_idx(&self) -> Option<usize> {
+        self.emitter.as_ref().map(|v| v.idx())
+    }
+
+    /// Returns the index of the internal base node, which is the base without `rb`.
+    pub fn bi_idx(&self) -> Option<usize> {
+        self.internal_base
+            .as_ref()
+            .map(|v| v.idx())
+            .or(self.b_idx())
+    }
+
+    /// Returns the index of the internal collector node, which is the collector without `rc`.
+    pub fn ci_idx(&self) -> Option<usize> {
+        self.internal_collector
+            .as_ref()
+            .map(|v| v.idx())
+            .or(self.c_idx())
+    }
+
+    /// Returns the index of the internal emitter node, which is the emitter without `re`.
+    pub fn ei_idx(&self) -> Option<usize> {
+        self.internal_emitter
+            .as_ref()
+            .map(|v| v.idx())
+            .or(self.e_idx())
+    }
+}
+
+/// The currents into the collector and base of an NPN transistor and their derivatives by the
+/// base-emitter and base-collector voltages.
+#[derive(Debug, Clone, Copy)]
+struct OperatingPoint {
+    ic: Numeric,
+    dic_dvbe: Numeric,
+    dic_dvbc: Numeric,
+    ib: Numeric,
+    dib_dvbe: Numeric,
+    dib_dvbc: Numeric,
+}
+
+impl OperatingPoint {
+    /// Returns the currents into the collector, base and emitter with their derivatives by
+    /// `v_be` and `v_bc`.
+    fn currents(&self) -> [(Numeric, Numeric, Numeric); 3] {
+        [
+            (self.ic, self.dic_dvbe, self.dic_dvbc),
+            (self.ib, self.dib_dvbe, self.dib_dvbc),
+            (
+                -self.ic - self.ib,
+                -self.dic_dvbe - self.dib_dvbe,
+                -self.dic_dvbc - self.dib_dvbc,
+            ),
+        ]
+    }
+
+    /// Returns the derivatives of the currents into the collector, base and emitter by the
+    /// voltages of the internal collector, base and emitter nodes.
+    /// They are the same for both polarities, as the voltages and currents invert together.
+    fn conductances(&self) -> [[Numeric; 3]; 3] {
+        self.currents().map(|(_, g_be, g_bc)| {
+            let mut row = [0.0; 3];
+            row[COLLECTOR] = -g_bc;
+            row[BASE] = g_be + g_bc;
+            row[EMITTER] = -g_be;
+            row
+        })
+    }
+}
+
+/// Returns the current `is * (exp(v / vt) - 1)` of an ideal junction and its conductance.
+fn junction(is: Numeric, voltage: Numeric, vt: Numeric) -> (Numeric, Numeric) {
+    if is.is_zero() {
+        return (Numeric::zero(), Numeric::zero());
+    }
+    let exp = Numeric::exp(voltage / vt);
+    (is * (exp - Numeric::one()), is * exp / vt)
+}
+
+/// Returns the inverse of a model parameter, which is infinite if given as zero.
+fn inverse(value: Numeric) -> Numeric {
+    if value.is_zero() {
+        Numeric::zero()
+    } else {
+        value.recip()
+    }
+}
@@ -1,4 +1,36 @@
-mod bjt0;
+/// The BJT Module. As every module this module encapsulates exerything regarding a BJT bundle
+/// This includes parsing from various formats as well as the conductance-behaviour.
+mod gummel_poon;
+pub(crate) mod serde;
+pub(crate) mod spice;
 
 #[cfg(test)]
 mod tests;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::frontends::get_internal_variable;
+use crate::models::Variable;
+use crate::spot::Numeric;
+
+pub use gummel_poon::{BjtBundle, BjtOptions, BjtPolarity};
+
+/// Creates the internal nodes behind the non-zero terminal resistances of the transistor.
+pub(crate) fn add_internal_nodes(
+    bjt: &mut BjtBundle,
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) {
+    let name = bjt.name();
+    let [rb, rc, re] = bjt.options().resistances();
+    let mut internal = |resistance: Numeric, node: &str| {
+        (resistance > 0.0)
+            .then(|| get_internal_variable(&name, node, variables, var_map))
+            .flatten()
+    };
+    let base = internal(rb, "base");
+    let collector = internal(rc, "collector");
+    let emitter = internal(re, "emitter");
+    bjt.set_internal_nodes(base, collector, emitter);
+}
@@ -0,0 +1,59 @@
+/// The BJT - yaml parsing module
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::{add_internal_nodes, BjtOptions, BjtPolarity};
+use crate::frontends::{get_variable, serde::ProcessSerdeElement};
+use crate::models::{BjtBundle, DeviceTemperature, Element, Unit};
+use crate::spot::Numeric;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SerdeBjt {
+    pub name: String,
+    pub collector: String,
+    pub base: String,
+    pub emitter: String,
+    /// `npn` or `pnp`, NPN by default
+    #[serde(default)]
+    pub polarity: BjtPolarity,
+    /// The Gummel-Poon parameters with their SPICE names, like `bf: 200`
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Numeric>,
+    /// Instance temperature in °C
+    pub temp: Option<Numeric>,
+    /// Difference to the circuit temperature in K
+    pub dtemp: Option<Numeric>,
+}
+
+impl ProcessSerdeElement for SerdeBjt {
+    fn process(
+        &self,
+        variables: &mut Vec<crate::models::Variable>,
+        elements: &mut Vec<Element>,
+        var_map: &mut HashMap<std::sync::Arc<str>, usize>,
+    ) {
+        let mut options = BjtOptions::default().with_polarity(self.polarity);
+        for (parameter, value) in &self.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of {}",
+                    self.name
+                );
+            }
+        }
+
+        let mut bjt = BjtBundle::new(
+            Arc::from(self.name.as_str()),
+            get_variable(self.collector.as_str(), Unit::Volt, variables, var_map),
+            get_variable(self.base.as_str(), Unit::Volt, variables, var_map),
+            get_variable(self.emitter.as_str(), Unit::Volt, variables, var_map),
+            Some(options),
+        );
+        add_internal_nodes(&mut bjt, variables, var_map);
+        bjt.set_temperature(DeviceTemperature::new(self.temp, self.dtemp));
+        elements.push(Element::Bjt(Box::new(bjt)));
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use log::warn;
+
+use super::{add_internal_nodes, BjtOptions, BjtPolarity};
+use crate::frontends::get_variable;
+use crate::frontends::spice::{ApplySpiceModel, ProcessSpiceElement, SpiceModel};
+use crate::frontends::spice_parser_helpers::SpiceElementParser;
+use crate::frontends::FrontendError;
+use crate::models::{BjtBundle, DeviceTemperature, Element, Unit};
+
+impl ProcessSpiceElement for BjtBundle {
+    fn process(
+        element: pest::iterators::Pair<crate::frontends::spice::Rule>,
+        variables: &mut Vec<crate::models::Variable>,
+        elements: &mut Vec<crate::models::Element>,
+        var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
+    ) -> Result<(), crate::frontends::FrontendError> {
+        let mut parser = SpiceElementParser::new(element);
+
+        let name = parser.parse_name("bjt")?;
+        let collector = parser.parse_node("bjt", name, "collector")?;
+        let base = parser.parse_node("bjt", name, "base")?;
+        let emitter = parser.parse_node("bjt", name, "emitter")?;
+        let model = parser.parse_model();
+
+        let mut temperature = DeviceTemperature::default();
+        for (parameter, value) in parser.parse_named_parameters("bjt", name)? {
+            temperature.set_parameter(&parameter, value);
+        }
+
+        let mut bjt = BjtBundle::new(
+            Arc::from(name),
+            get_variable(collector, Unit::Volt, variables, var_map),
+            get_variable(base, Unit::Volt, variables, var_map),
+            get_variable(emitter, Unit::Volt, variables, var_map),
+            None,
+        );
+        if let Some(model) = model {
+            bjt.set_model(Arc::from(model));
+        }
+        bjt.set_temperature(temperature);
+        elements.push(Element::Bjt(Box::new(bjt)));
+        Ok(())
+    }
+}
+
+impl ApplySpiceModel for BjtBundle {
+    fn model_name(&self) -> Option<Arc<str>> {
+        self.model()
+    }
+
+    fn apply_model(
+        &mut self,
+        model: &SpiceModel,
+        variables: &mut Vec<crate::models::Variable>,
+        var_map: &mut std::collections::HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError> {
+        let polarity =
+            model
+                .kind
+                .parse::<BjtPolarity>()
+                .map_err(|reason| FrontendError::InvalidModel {
+                    model: model.name.to_string(),
+                    reason,
+                })?;
+        let mut options = BjtOptions::default().with_polarity(polarity);
+        for (parameter, value) in &model.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of model {}",
+                    model.name
+                );
+            }
+        }
+        self.set_options(options);
+        add_internal_nodes(self, variables, var_map);
+        Ok(())
+    }
+}
@@ -1,280 +1,251 @@
 // Basic tests for BJT model
-use super::bjt0::{Bjt0Bundle, Bjt0Options};
+use super::{BjtBundle, BjtOptions, BjtPolarity};
 use crate::models::{Unit, Variable};
+use crate::spot::{Numeric, UT};
 use std::sync::Arc;
 
-#[test]
-fn test_bjt_creation() {
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 1);
+/// Creates a transistor with the collector, base and emitter at the indices 0, 1 and 2.
+fn bjt(options: Option<BjtOptions>) -> BjtBundle {
+    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 0);
+    let base = Variable::new(Arc::from("base"), Unit::Volt, 1);
     let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 2);
 
-    let bjt = Bjt0Bundle::new(
+    BjtBundle::new(
         Arc::from("Q1"),
-        Some(base),
         Some(collector),
+        Some(base),
         Some(emitter),
-        None, // Use default options
-    );
-
-    assert_eq!(bjt.name(), "Q1".into());
-    assert_eq!(bjt.b_idx(), Some(0));
-    assert_eq!(bjt.c_idx(), Some(1));
-    assert_eq!(bjt.e_idx(), Some(2));
+        options,
+    )
 }
 
-#[test]
-fn test_bjt_default_options() {
-    let _options = Bjt0Options::default();
-    // Just test that default options can be created
-    // Fields are private, so we can't access them directly
-    // Test passes if we reach this point without panicking
+/// Returns the currents into the nodes of the transistor at `x_vec`, which are the linearised
+/// currents `G * x - b` of its stamps.
+fn node_currents(bjt: &BjtBundle, x_vec: &[Numeric]) -> Vec<Numeric> {
+    let mut currents = vec![0.0; x_vec.len()];
+    for &(row, col, value) in &bjt.triples(x_vec) {
+        currents[row] += value * x_vec[col];
+    }
+    for &(row, value) in &bjt.pairs(x_vec) {
+        currents[row] -= value;
+    }
+    currents
 }
 
 #[test]
-fn test_bjt_custom_options() {
-    // Test that we can create a BJT with default options
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 1);
-    let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 2);
-
-    let bjt = Bjt0Bundle::new(
-        Arc::from("Q1"),
-        Some(base),
-        Some(collector),
-        Some(emitter),
-        Some(Bjt0Options::default()),
-    );
+fn test_bjt_creation() {
+    let bjt = bjt(None);
 
     assert_eq!(bjt.name(), "Q1".into());
-    // Test passes if we reach this point without panicking
+    assert_eq!(bjt.c_idx(), Some(0));
+    assert_eq!(bjt.b_idx(), Some(1));
+    assert_eq!(bjt.e_idx(), Some(2));
+    // Without terminal resistances the junctions sit at the terminals
+    assert_eq!(bjt.ci_idx(), Some(0));
+    assert_eq!(bjt.bi_idx(), Some(1));
+    assert_eq!(bjt.ei_idx(), Some(2));
+    assert_eq!(bjt.internal_node_indices().count(), 0);
 }
 
 #[test]
-fn test_bjt_triples_basic() {
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 1);
-    let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 2);
+fn test_bjt_set_parameter() {
+    let mut options = BjtOptions::default();
+    assert!(options.set_parameter("RB", 100.0));
+    assert!(options.set_parameter("re", 2.0));
+    assert!(options.set_parameter("VA", 50.0));
+    assert!(!options.set_parameter("xtf", 1.0));
+    assert_eq!(options.resistances(), [100.0, 0.0, 2.0]);
+}
 
-    let bjt = Bjt0Bundle::new(
-        Arc::from("Q1"),
-        Some(base),
-        Some(collector),
-        Some(emitter),
-        None,
-    );
+#[test]
+fn test_bjt_polarity_from_str() {
+    assert_eq!("NPN".parse::<BjtPolarity>(), Ok(BjtPolarity::Npn));
+    assert_eq!("pnp".parse::<BjtPolarity>(), Ok(BjtPolarity::Pnp));
+    assert!("nmos".parse::<BjtPolarity>().is_err());
+}
 
-    // Test with zero voltages
-    let x_vec = vec![0.0, 0.0, 0.0];
-    let triples = bjt.triples(&x_vec);
+#[test]
+fn test_bjt_zero_bias() {
+    let bjt = bjt(None);
+    let currents = node_currents(&bjt, &[0.0, 0.0, 0.0]);
 
-    // At zero voltage, conductances should be zero (no exponential terms)
-    let triples_data = triples.data();
-    for (_, _, value) in triples_data.iter() {
-        if *value != 0.0 {
-            // Allow for very small floating point errors
-            assert!(
-                value.abs() < 1e-10,
-                "Conductance should be zero at zero voltage, got {}",
-                value
-            );
-        }
+    for current in currents {
+        assert!(current.abs() < 1e-15, "Current at zero bias: {current}");
     }
+    assert_eq!(bjt.region(-0.1, -1.0), "cutoff");
 }
 
 #[test]
-fn test_bjt_pairs_basic() {
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 1);
-    let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 2);
+fn test_bjt_forward_active() {
+    let bjt = bjt(None);
+    // V_BE = 0.7V, V_CE = 5V
+    let x_vec = [5.0, 0.7, 0.0];
+    let currents = node_currents(&bjt, &x_vec);
 
-    let bjt = Bjt0Bundle::new(
-        Arc::from("Q1"),
-        Some(base),
-        Some(collector),
-        Some(emitter),
-        None,
+    let is = 1e-16;
+    let i_bf = is * ((0.7 / UT).exp() - 1.0);
+    let i_br = is * ((-4.3 / UT).exp() - 1.0);
+    let ic = i_bf - i_br - i_br - 1e-12 * -4.3;
+    let ib = i_bf / 100.0 + i_br + 1e-12 * (0.7 - 4.3);
+
+    assert!(
+        (currents[0] - ic).abs() < 1e-9 * ic,
+        "Collector current {}",
+        currents[0]
     );
+    assert!(
+        (currents[1] - ib).abs() < 1e-9 * ib,
+        "Base current {}",
+        currents[1]
+    );
+    assert!((currents[0] + currents[1] + currents[2]).abs() < 1e-15);
+    assert_eq!(bjt.region(0.7, -4.3), "forward active");
+}
 
-    // Test with zero voltages
-    let x_vec = vec![0.0, 0.0, 0.0];
-    let pairs = bjt.pairs(&x_vec);
+#[test]
+fn test_bjt_jacobian() {
+    let mut options = BjtOptions::default();
+    for (name, value) in [("vaf", 50.0), ("var", 20.0), ("ikf", 1e-3), ("ise", 1e-14)] {
+        options.set_parameter(name, value);
+    }
+    let bjt = bjt(Some(options));
+    let x_vec = [3.0, 0.72, 0.05];
+    let h = 1e-7;
 
-    // At zero voltage, currents should be zero
-    let pairs_data = pairs.data();
-    for (_, value) in pairs_data.iter() {
-        if *value != 0.0 {
-            // Allow for very small floating point errors
+    let mut jacobian = [[0.0; 3]; 3];
+    for &(row, col, value) in &bjt.triples(&x_vec) {
+        jacobian[row][col] += value;
+    }
+    for col in 0..3 {
+        let mut x_plus = x_vec;
+        let mut x_minus = x_vec;
+        x_plus[col] += h;
+        x_minus[col] -= h;
+        let plus = node_currents(&bjt, &x_plus);
+        let minus = node_currents(&bjt, &x_minus);
+        for row in 0..3 {
+            let numeric = (plus[row] - minus[row]) / (2.0 * h);
+            let tolerance = 1e-5 * numeric.abs() + 1e-10;
             assert!(
-                value.abs() < 1e-10,
-                "Current should be zero at zero voltage, got {}",
-                value
+                (jacobian[row][col] - numeric).abs() < tolerance,
+                "dI{row}/dV{col}: {} != {numeric}",
+                jacobian[row][col]
             );
         }
     }
 }
 
 #[test]
-fn test_bjt_forward_active() {
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 1);
-    let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 2);
-
-    let bjt = Bjt0Bundle::new(
-        Arc::from("Q1"),
-        Some(base),
-        Some(collector),
-        Some(emitter),
-        None,
+fn test_bjt_diffusion_charge() {
+    let mut options = BjtOptions::default();
+    for (name, value) in [("tf", 1e-9), ("vaf", 50.0), ("ikf", 1e-3)] {
+        options.set_parameter(name, value);
+    }
+    let bjt = bjt(Some(options));
+    // High-level injection at V_BE = 0.8V, V_CE = 3V
+    let x_vec = [3.0, 0.8, 0.0];
+    let [q_be, _] = bjt.charges(&x_vec);
+
+    // The diffusion charge saturates with the base charge like the transport current
+    let i_bf = 1e-16 * ((0.8 / UT).exp() - 1.0);
+    assert!(q_be.charge < 0.5 * 1e-9 * i_bf, "{}", q_be.charge);
+
+    // The capacitance is the derivative by V_BE, including the one of the base charge
+    let h = 1e-7;
+    let charge_at = |v_e: Numeric| bjt.charges(&[3.0, 0.8, v_e])[0].charge;
+    let numeric = (charge_at(-h) - charge_at(h)) / (2.0 * h);
+    assert!(
+        (q_be.capacitance - numeric).abs() < 1e-5 * numeric,
+        "{} != {numeric}",
+        q_be.capacitance
     );
+}
 
-    // Forward active mode: V_BE = 0.7V, V_CE = 5V
-    let v_base = 0.7;
-    let v_emitter = 0.0;
-    let v_collector = 5.0;
-    let x_vec = vec![v_base, v_collector, v_emitter];
-
-    let pairs = bjt.pairs(&x_vec);
-    let pairs_data = pairs.data();
-
-    // In forward active mode, we should have non-zero currents
-    let base_current = pairs_data
-        .iter()
-        .find(|(idx, _)| *idx == 0)
-        .map(|(_, val)| *val)
-        .unwrap_or(0.0);
-
-    let collector_current = pairs_data
-        .iter()
-        .find(|(idx, _)| *idx == 1)
-        .map(|(_, val)| *val)
-        .unwrap_or(0.0);
-
-    let emitter_current = pairs_data
-        .iter()
-        .find(|(idx, _)| *idx == 2)
-        .map(|(_, val)| *val)
-        .unwrap_or(0.0);
-
-    // Debug output
-    eprintln!("Base current: {}", base_current);
-    eprintln!("Collector current: {}", collector_current);
-    eprintln!("Emitter current: {}", emitter_current);
+#[test]
+fn test_bjt_early_effect() {
+    let mut options = BjtOptions::default();
+    options.set_parameter("vaf", 50.0);
+    let bjt = bjt(Some(options));
 
-    // Currents should be non-zero in forward active mode
-    // Note: Base current can be very small (microamps range) in forward active mode
-    assert!(
-        base_current.abs() < 1e-9 || base_current > 0.0,
-        "Base current should be positive or very small in forward active mode, got {}",
-        base_current
-    );
-    assert!(
-        collector_current < 0.0,
-        "Collector current should be negative (flowing out), got {}",
-        collector_current
-    );
-    assert!(
-        emitter_current > 0.0,
-        "Emitter current should be positive (flowing in), got {}",
-        emitter_current
-    );
+    let ic_5 = node_currents(&bjt, &[5.0, 0.7, 0.0])[0];
+    let ic_10 = node_currents(&bjt, &[10.0, 0.7, 0.0])[0];
 
-    // Current gain: I_C should be approximately beta * I_B
-    // Note: When base current is very small, current gain can be very large
-    let base_current_abs = base_current.abs();
-    if base_current_abs > 1e-12 {
-        // Only check current gain if base current is significant
-        let current_gain = collector_current.abs() / base_current_abs;
-        assert!(
-            current_gain > 50.0,
-            "Current gain should be significant, got {}",
-            current_gain
-        );
-    } else {
-        // When base current is very small, we can't meaningfully check current gain
-        eprintln!(
-            "Base current too small ({} A) to check current gain",
-            base_current_abs
-        );
-    }
+    // The collector current rises with 1 - V_BC / VAF
+    let expected = (1.0 + 9.3 / 50.0) / (1.0 + 4.3 / 50.0);
+    assert!((ic_10 / ic_5 - expected).abs() < 1e-6);
 }
 
 #[test]
-fn test_bjt_triple_idx() {
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 1);
-    let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 2);
+fn test_bjt_pnp_mirrors_npn() {
+    let npn = bjt(None);
+    let pnp = bjt(Some(BjtOptions::default().with_polarity(BjtPolarity::Pnp)));
 
-    let bjt = Bjt0Bundle::new(
-        Arc::from("Q1"),
-        Some(base),
-        Some(collector),
-        Some(emitter),
-        None,
-    );
+    let npn_currents = node_currents(&npn, &[5.0, 0.7, 0.0]);
+    let pnp_currents = node_currents(&pnp, &[-5.0, -0.7, 0.0]);
 
-    let triple_idx = bjt.triple_idx();
-    assert!(
-        triple_idx.is_some(),
-        "Should have triple indices for full BJT"
+    for (npn, pnp) in npn_currents.iter().zip(&pnp_currents) {
+        assert!((npn + pnp).abs() < 1e-12 * npn.abs().max(1e-12));
+    }
+    assert_eq!(
+        pnp.voltages(&[-5.0, -0.7, 0.0]),
+        npn.voltages(&[5.0, 0.7, 0.0])
     );
-
-    // Should have triple indices for full BJT model
-    // Test passes if we reach this point without panicking
 }
 
 #[test]
-fn test_bjt_partial_connections() {
-    // Test with only base and emitter connected
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 1);
-
-    let bjt = Bjt0Bundle::new(
-        Arc::from("Q1"),
-        Some(base),
-        None, // No collector
-        Some(emitter),
-        None,
-    );
+fn test_bjt_internal_nodes() {
+    let mut options = BjtOptions::default();
+    options.set_parameter("rb", 100.0);
+    options.set_parameter("re", 1.0);
+    let mut bjt = bjt(Some(options));
+    let internal_base = Variable::new(Arc::from("Q1#base"), Unit::Volt, 3);
+    let internal_emitter = Variable::new(Arc::from("Q1#emitter"), Unit::Volt, 4);
+    bjt.set_internal_nodes(Some(internal_base), None, Some(internal_emitter));
+
+    assert_eq!(bjt.bi_idx(), Some(3));
+    assert_eq!(bjt.ci_idx(), Some(0));
+    assert_eq!(bjt.ei_idx(), Some(4));
+    assert_eq!(bjt.internal_node_indices().collect::<Vec<_>>(), vec![3, 4]);
+
+    // The junctions draw no current through the resistances at zero bias
+    let x_vec = [5.0, 0.7, 0.0, 0.7, 0.0];
+    let triples = bjt.triples(&x_vec);
+    let conductance = |row, col| {
+        triples
+            .into_iter()
+            .filter(|&&(r, c, _)| (r, c) == (row, col))
+            .map(|&(_, _, value)| value)
+            .sum::<Numeric>()
+    };
+    assert_eq!(conductance(1, 1), 0.01);
+    assert_eq!(conductance(1, 3), -0.01);
+    assert_eq!(conductance(2, 4), -1.0);
+    // 9 entries of the junctions and 4 of each resistance
+    assert_eq!(bjt.triple_idx().unwrap().len(), 9 + 4 + 4);
+}
 
-    assert_eq!(bjt.b_idx(), Some(0));
-    assert_eq!(bjt.c_idx(), None);
-    assert_eq!(bjt.e_idx(), Some(1));
+#[test]
+fn test_bjt_ac_triples_at_dc() {
+    let bjt = bjt(None);
+    let x_vec = [5.0, 0.7, 0.0];
 
-    // Should still work with partial connections
-    let x_vec = vec![0.7, 0.0];
-    let pairs = bjt.pairs(&x_vec);
-    let pairs_data = pairs.data();
+    let triples = bjt.triples(&x_vec);
+    let ac_triples = bjt.ac_triples(0.0, &x_vec);
 
-    // Count non-zero entries
-    let non_zero_count = pairs_data.iter().filter(|(_, val)| *val != 0.0).count();
-    assert_eq!(
-        non_zero_count, 2,
-        "Should have 2 currents for base and emitter"
-    );
+    assert_eq!(triples.len(), ac_triples.len());
+    for (real, complex) in triples.into_iter().zip(&ac_triples) {
+        assert_eq!((real.0, real.1), (complex.0, complex.1));
+        assert_eq!(real.2, complex.2.re);
+        assert_eq!(complex.2.im, 0.0);
+    }
 }
 
 #[test]
 fn test_bjt_numerical_stability() {
-    let base = Variable::new(Arc::from("base"), Unit::Volt, 0);
-    let collector = Variable::new(Arc::from("collector"), Unit::Volt, 1);
-    let emitter = Variable::new(Arc::from("emitter"), Unit::Volt, 2);
-
-    let bjt = Bjt0Bundle::new(
-        Arc::from("Q1"),
-        Some(base),
-        Some(collector),
-        Some(emitter),
-        None,
-    );
+    let bjt = bjt(None);
 
     // Test with very negative voltage (should not overflow)
-    let x_vec = vec![-100.0, 0.0, 0.0];
-    let pairs = bjt.pairs(&x_vec);
-    let pairs_data = pairs.data();
-
-    // Should handle negative voltages gracefully
-    for (_, value) in pairs_data.iter() {
+    for value in node_currents(&bjt, &[0.0, -100.0, 0.0]) {
         assert!(
             value.is_finite(),
             "Should not produce NaN or infinite values, got {}",
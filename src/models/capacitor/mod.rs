@@ -8,6 +8,8 @@ use std::sync::Arc;
 use num::traits::FloatConst;
 use num::{Complex, One, Zero};
 
+use super::charge::Charge;
+use super::integration::IntegrationMethod;
 use super::*;
 use crate::spot::*;
 
@@ -18,9 +20,8 @@ pub struct CapacitorBundle {
     pub node0: Option<Variable>,
     pub node1: Option<Variable>,
     pub value: Numeric,
-    /// Previous voltage across the capacitor for transient simulation
-    /// This stores the voltage from the last time step for proper integration
-    pub previous_voltage: Numeric,
+    /// Optional initial voltage across the capacitor (`IC=`), used for transient analysis with UIC
+    pub initial_condition: Option<Numeric>,
 }
 
 impl CapacitorBundle {
@@ -49,10 +50,20 @@ impl CapacitorBundle {
             node0,
             node1,
             value,
-            previous_voltage: Numeric::zero(), // Initialize to 0V
+            initial_condition: None,
         }
     }
 
+    /// Sets the initial voltage across the capacitor.
+    pub fn set_initial_condition(&mut self, voltage: Option<Numeric>) {
+        self.initial_condition = voltage;
+    }
+
+    /// Returns the initial voltage across the capacitor, if one was given.
+    pub fn initial_condition(&self) -> Option<Numeric> {
+        self.initial_condition
+    }
+
     /// Returns the name of the capacitor bundle.
     pub fn name(&self) -> Arc<str> {
         self.name.clone()
@@ -68,21 +79,16 @@ impl CapacitorBundle {
         self.node1.as_ref().map(|v| v.idx())
     }
 
-    /// Updates the previous voltage for transient simulation
-    /// This should be called after each time step with the current voltage
-    pub fn update_previous_voltage(&mut self, voltage: Numeric) {
-        self.previous_voltage = voltage;
-    }
-
-    /// Returns the previous voltage across the capacitor
-    pub fn previous_voltage(&self) -> Numeric {
-        self.previous_voltage
+    /// Returns the charge stored in the capacitor at the solution `x_vec`.
+    pub fn charge(&self, x_vec: &[Numeric]) -> Charge {
+        Charge::linear(self.node0_idx(), self.node1_idx(), self.value, x_vec)
     }
 
     /// Returns a reference to the triples representing matrix A.
-    pub fn triples(&self, delta_t: Option<&Numeric>) -> Triples<Numeric, 4> {
-        let equivalent_conductance = match delta_t {
-            Some(delta_t) => self.value / delta_t,
+    /// If an integration method is provided, the equivalent conductance of its companion model is used.
+    pub fn triples(&self, integrator: Option<&dyn IntegrationMethod>) -> Triples<Numeric, 4> {
+        let equivalent_conductance = match integrator {
+            Some(integrator) => integrator.capacitor_equivalent_conductance(self.value),
             None => Numeric::zero(),
         };
 
@@ -167,66 +173,6 @@ impl CapacitorBundle {
             ])),
         }
     }
-
-    /// Returns the pairs representing the right-hand side (RHS) for transient simulation
-    /// This implements the backward Euler integration: i = C * (v_current - v_prev) / Δt
-    /// Which rearranges to: (C/Δt)*v_current - (C/Δt)*v_prev = i
-    /// In MNA, this becomes part of the RHS vector as: b = (C/Δt) * v_prev
-    pub fn pairs(&self, delta_t: &Numeric) -> Pairs<Numeric, 2> {
-        let g = self.value / delta_t; // Equivalent conductance
-        let v_prev = self.previous_voltage;
-
-        let node0_idx = if let Some(idx) = self.node0_idx() {
-            idx
-        } else {
-            // If node0 doesn't exist, capacitor is connected to ground through node1
-            let node1_idx = self
-                .node1_idx()
-                .expect("Capacitor must have at least one node connected");
-            return Pairs::new(&[(node1_idx, -g * v_prev)]);
-        };
-        let node1_idx = if let Some(idx) = self.node1_idx() {
-            idx
-        } else {
-            return Pairs::new(&[(node0_idx, g * v_prev)]);
-        };
-
-        // The RHS represents the current contribution from the previous time step
-        // For backward Euler: i = C * (v_current - v_prev) / Δt
-        // Rearranged: C*v_current/Δt - C*v_prev/Δt = i
-        // In MNA, the RHS should be: -C*v_prev/Δt (current flowing INTO the node)
-        Pairs::new(&[(node0_idx, -g * v_prev), (node1_idx, g * v_prev)])
-    }
-
-    /// Returns the pairs representing the right-hand side (RHS) for transient simulation using trapezoidal integration
-    /// This implements the trapezoidal rule: i = C * (v_current - v_prev) / (Δt/2)
-    /// Which rearranges to: (2C/Δt)*v_current - (2C/Δt)*v_prev = i
-    /// In MNA, this becomes part of the RHS vector as: b = (2C/Δt) * v_prev
-    pub fn pairs_trapezoidal(&self, delta_t: &Numeric) -> Pairs<Numeric, 2> {
-        let g = (self.value * 2.0) / delta_t; // Equivalent conductance for trapezoidal rule
-        let v_prev = self.previous_voltage;
-
-        let node0_idx = if let Some(idx) = self.node0_idx() {
-            idx
-        } else {
-            // If node0 doesn't exist, capacitor is connected to ground through node1
-            let node1_idx = self
-                .node1_idx()
-                .expect("Capacitor must have at least one node connected");
-            return Pairs::new(&[(node1_idx, -g * v_prev)]);
-        };
-        let node1_idx = if let Some(idx) = self.node1_idx() {
-            idx
-        } else {
-            return Pairs::new(&[(node0_idx, g * v_prev)]);
-        };
-
-        // The RHS represents the current contribution from the previous time step
-        // For trapezoidal rule: i = C * (v_current - v_prev) / (Δt/2)
-        // Rearranged: (2C/Δt)*v_current - (2C/Δt)*v_prev = i
-        // In MNA, the RHS should be: -(2C/Δt)*v_prev (current flowing INTO the node)
-        Pairs::new(&[(node0_idx, -g * v_prev), (node1_idx, g * v_prev)])
-    }
 }
 
 #[cfg(test)]
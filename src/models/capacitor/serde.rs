@@ -12,6 +12,7 @@ pub struct SerdeCapacitor {
     pub node0: String,
     pub node1: String,
     pub value: Numeric,
+    pub ic: Option<Numeric>,
 }
 
 impl ProcessSerdeElement for SerdeCapacitor {
@@ -21,12 +22,13 @@ impl ProcessSerdeElement for SerdeCapacitor {
         elements: &mut Vec<Element>,
         var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
     ) {
-        let res = CapacitorBundle::new(
+        let mut res = CapacitorBundle::new(
             Arc::from(self.name.as_str()),
             get_variable(self.node0.as_str(), Unit::Volt, variables, var_map),
             get_variable(self.node1.as_str(), Unit::Volt, variables, var_map),
             self.value,
         );
+        res.set_initial_condition(self.ic);
         elements.push(Element::Capacitor(res));
     }
 }
@@ -20,14 +20,16 @@ impl ProcessSpiceElement for CapacitorBundle {
         let node0 = parser.parse_node("capacitor", name, "node0")?;
         let node1 = parser.parse_node("capacitor", name, "node1")?;
         let value = parser.parse_value("capacitor", name, "value")?;
+        let initial_condition = parser.parse_optional_parameter("capacitor", name, "ic")?;
 
         // Create the capacitor element
-        let cap = CapacitorBundle::new(
+        let mut cap = CapacitorBundle::new(
             Arc::from(name),
             get_variable(node0, Unit::Volt, variables, var_map),
             get_variable(node1, Unit::Volt, variables, var_map),
             value,
         );
+        cap.set_initial_condition(initial_condition);
         elements.push(Element::Capacitor(cap));
         Ok(())
     }
@@ -1,4 +1,5 @@
 use super::*;
+use crate::models::integration::BackwardEuler;
 
 #[test]
 fn test_new_capacitor_bundle() {
@@ -187,7 +188,7 @@ fn test_triple_idx_both_none() {
 fn test_transient_triples_both_nodes() {
     let cap = CapacitorBundle::new(Arc::from("C1"), make_var(0), make_var(1), 2.0);
     let delta_t = 0.01;
-    let triples = cap.triples(Some(&delta_t));
+    let triples = cap.triples(Some(&BackwardEuler::new(delta_t)));
 
     let expected_conductance = 200.0;
 
@@ -210,7 +211,7 @@ fn test_transient_triples_both_nodes() {
 fn test_transient_triples_node0_none() {
     let cap = CapacitorBundle::new(Arc::from("C1"), None, make_var(1), 2.0);
     let delta_t = 0.01;
-    let triples = cap.triples(Some(&delta_t));
+    let triples = cap.triples(Some(&BackwardEuler::new(delta_t)));
 
     assert_eq!(triples.len(), 1);
     assert_eq!(triples[0].0, 1);
@@ -222,7 +223,7 @@ fn test_transient_triples_node0_none() {
 fn test_transient_triples_node1_none() {
     let cap = CapacitorBundle::new(Arc::from("C1"), make_var(0), None, 2.0);
     let delta_t = 0.01;
-    let triples = cap.triples(Some(&delta_t));
+    let triples = cap.triples(Some(&BackwardEuler::new(delta_t)));
 
     assert_eq!(triples.len(), 1);
     assert_eq!(triples[0].0, 0);
@@ -234,7 +235,7 @@ fn test_transient_triples_node1_none() {
 fn test_transient_triples_zero_capacitance() {
     let cap = CapacitorBundle::new(Arc::from("C1"), make_var(0), make_var(1), 0.0);
     let delta_t = 0.01;
-    let triples = cap.triples(Some(&delta_t));
+    let triples = cap.triples(Some(&BackwardEuler::new(delta_t)));
 
     assert_eq!(triples.len(), 4);
     assert_eq!(triples[0].2, Numeric::zero());
@@ -247,7 +248,7 @@ fn test_transient_triples_zero_capacitance() {
 fn test_transient_triples_large_delta_t() {
     let cap = CapacitorBundle::new(Arc::from("C1"), make_var(0), make_var(1), 1.0);
     let delta_t = 100.0;
-    let triples = cap.triples(Some(&delta_t));
+    let triples = cap.triples(Some(&BackwardEuler::new(delta_t)));
 
     let expected_conductance = 0.01;
 
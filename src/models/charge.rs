@@ -0,0 +1,156 @@
+/// The Charge Module. Reactive behaviour of the elements is described by the charges they store,
+/// which are integrated by the transient analysis.
+use num::Zero;
+
+use super::integration::{IntegrationMethod, StateHistory};
+use super::*;
+use crate::spot::*;
+
+/// The distance of the grading coefficient from one, below which the junction charge is the
+/// logarithmic limit of the power law.
+const GRADING_LOG_LIMIT: Numeric = 1e-6;
+
+/// A charge `q(v)` stored between two nodes, evaluated at the voltage `v = v(node0) - v(node1)`.
+///
+/// The current through the charge is `i = dq/dt`, which the integration method approximates as
+/// `i = a0 * q(v) + h`. Around the voltage of the current Newton iteration this is linearised as
+/// `i = a0 * c * v + (a0 * (q - c * v) + h)`, with the capacitance `c = dq/dv`.
+/// Charges and their histories are therefore conserved also for voltage dependent capacitances.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Charge {
+    pub node0: Option<usize>,
+    pub node1: Option<usize>,
+    /// The voltage the charge was evaluated at
+    pub voltage: Numeric,
+    /// The stored charge q(v)
+    pub charge: Numeric,
+    /// The capacitance dq/dv
+    pub capacitance: Numeric,
+}
+
+impl Charge {
+    /// Creates a new charge evaluated at `voltage`.
+    pub fn new(
+        node0: Option<usize>,
+        node1: Option<usize>,
+        voltage: Numeric,
+        charge: Numeric,
+        capacitance: Numeric,
+    ) -> Self {
+        Self {
+            node0,
+            node1,
+            voltage,
+            charge,
+            capacitance,
+        }
+    }
+
+    /// Creates the charge of a linear capacitance between two nodes.
+    pub fn linear(
+        node0: Option<usize>,
+        node1: Option<usize>,
+        capacitance: Numeric,
+        x_vec: &[Numeric],
+    ) -> Self {
+        let voltage = voltage_between(node0, node1, x_vec);
+        Self::new(node0, node1, voltage, capacitance * voltage, capacitance)
+    }
+
+    /// Returns the triples of the equivalent conductance `a0 * c`.
+    pub fn triples(&self, integrator: &dyn IntegrationMethod) -> Triples<Numeric, 4> {
+        let conductance = integrator.derivative_coefficient() * self.capacitance;
+
+        match (self.node0, self.node1) {
+            (None, None) => Triples::new(&[]),
+            (Some(idx_0), None) => Triples::new(&[(idx_0, idx_0, conductance)]),
+            (None, Some(idx_1)) => Triples::new(&[(idx_1, idx_1, conductance)]),
+            (Some(idx_0), Some(idx_1)) => Triples::new(&[
+                (idx_0, idx_0, conductance),
+                (idx_1, idx_1, conductance),
+                (idx_0, idx_1, -conductance),
+                (idx_1, idx_0, -conductance),
+            ]),
+        }
+    }
+
+    /// Returns the pairs of the equivalent current source `a0 * (q - c * v) + h`.
+    /// The source current flows from node0 to node1 and enters the RHS with the opposite sign.
+    pub fn pairs(
+        &self,
+        history: &StateHistory,
+        integrator: &dyn IntegrationMethod,
+    ) -> Pairs<Numeric, 2> {
+        let current = integrator.derivative_coefficient()
+            * (self.charge - self.capacitance * self.voltage)
+            + integrator.derivative_history(history);
+
+        match (self.node0, self.node1) {
+            (None, None) => Pairs::new(&[]),
+            (Some(idx_0), None) => Pairs::new(&[(idx_0, -current)]),
+            (None, Some(idx_1)) => Pairs::new(&[(idx_1, current)]),
+            (Some(idx_0), Some(idx_1)) => Pairs::new(&[(idx_0, -current), (idx_1, current)]),
+        }
+    }
+}
+
+/// Returns the voltage between two nodes, where a missing node is ground.
+pub(crate) fn voltage_between(
+    node0: Option<usize>,
+    node1: Option<usize>,
+    x_vec: &[Numeric],
+) -> Numeric {
+    let v0 = node0.map(|idx| x_vec[idx]).unwrap_or(Numeric::zero());
+    let v1 = node1.map(|idx| x_vec[idx]).unwrap_or(Numeric::zero());
+    v0 - v1
+}
+
+/// Returns the charge and capacitance of a pn junction depletion region.
+/// Above `fc * vj` the capacitance is extrapolated linearly, as in SPICE.
+///
+/// # Arguments
+///
+/// * `voltage` - The voltage across the junction.
+/// * `cj0` - The zero-bias junction capacitance.
+/// * `vj` - The junction potential.
+/// * `m` - The grading coefficient.
+/// * `fc` - The forward-bias depletion capacitance coefficient.
+pub(crate) fn junction_charge(
+    voltage: Numeric,
+    cj0: Numeric,
+    vj: Numeric,
+    m: Numeric,
+    fc: Numeric,
+) -> (Numeric, Numeric) {
+    if cj0 == Numeric::zero() {
+        return (Numeric::zero(), Numeric::zero());
+    }
+
+    let v_fc = fc * vj;
+    if voltage < v_fc {
+        let arg = 1.0 - voltage / vj;
+        let charge = cj0 * vj * graded(arg, m);
+        let capacitance = cj0 * arg.powf(-m);
+        (charge, capacitance)
+    } else {
+        // Linear extrapolation of the capacitance beyond fc * vj
+        let f1 = vj * graded(1.0 - fc, m);
+        let f2 = (1.0 - fc).powf(1.0 + m);
+        let f3 = 1.0 - fc * (1.0 + m);
+        let dv = voltage - v_fc;
+        let charge =
+            cj0 * (f1 + (f3 * dv + m / (2.0 * vj) * (voltage * voltage - v_fc * v_fc)) / f2);
+        let capacitance = cj0 * (f3 + m * voltage / vj) / f2;
+        (charge, capacitance)
+    }
+}
+
+/// Returns `(1 - arg^(1 - m)) / (1 - m)`, the integral of the grading `arg^-m` of a junction.
+/// Close to `m = 1` it is replaced by its limit `-ln(arg)`.
+fn graded(arg: Numeric, m: Numeric) -> Numeric {
+    if (1.0 - m).abs() < GRADING_LOG_LIMIT {
+        -arg.ln()
+    } else {
+        (1.0 - arg.powf(1.0 - m)) / (1.0 - m)
+    }
+}
@@ -53,6 +53,19 @@ impl CCVSBundle {
         self.name.clone()
     }
 
+    /// Returns the output node indices for the CCVS
+    pub fn node_indices(&self) -> (Option<usize>, Option<usize>) {
+        (
+            self.positive.as_ref().map(|v| v.idx()),
+            self.negative.as_ref().map(|v| v.idx()),
+        )
+    }
+
+    /// Returns the variable of the branch whose current controls the CCVS
+    pub fn controlling_branch(&self) -> Option<&Variable> {
+        self.controlling_branch.as_ref()
+    }
+
     pub fn triples(&self) -> Triples<Numeric, 4> {
         Triples::new(&[])
     }
@@ -4,9 +4,11 @@
 // - VCCS (Voltage-Controlled Current Source) - G source
 // - CCCS (Current-Controlled Current Source) - F source
 // - CCVS (Current-Controlled Voltage Source) - H source
+// Their POLY and TABLE forms are nonlinear behavioral sources, see `nonlinear`.
 
 pub mod cccs;
 pub mod ccvs;
+pub mod nonlinear;
 pub mod serde;
 pub mod spice;
 pub mod vccs;
@@ -0,0 +1,220 @@
+// Nonlinear forms of the controlled sources
+// Polynomials POLY(n) of several controlling voltages or currents and piecewise-linear tables
+// TABLE are behavioral sources, whose expressions supply the exact derivatives of the transfer.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::frontends::get_variable;
+use crate::models::behavioral::expression::{BinaryOp, Expression, Function};
+use crate::models::{BehavioralBundle, BehavioralOutput, Unit};
+use crate::spot::Numeric;
+use crate::Variable;
+
+/// The four kinds of controlled sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlledKind {
+    VCVS,
+    VCCS,
+    CCCS,
+    CCVS,
+}
+
+impl ControlledKind {
+    /// Returns the abbreviation of the kind, like `VCVS`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ControlledKind::VCVS => "VCVS",
+            ControlledKind::VCCS => "VCCS",
+            ControlledKind::CCCS => "CCCS",
+            ControlledKind::CCVS => "CCVS",
+        }
+    }
+
+    /// Returns whether the source is controlled by voltages between pairs of nodes.
+    pub fn is_voltage_controlled(&self) -> bool {
+        matches!(self, ControlledKind::VCVS | ControlledKind::VCCS)
+    }
+
+    /// Returns whether the source forces a voltage between its nodes.
+    pub fn is_voltage_output(&self) -> bool {
+        matches!(self, ControlledKind::VCVS | ControlledKind::CCVS)
+    }
+}
+
+/// Returns the polynomial of the `controls` with the `coefficients` in SPICE order: the constant,
+/// the linear terms and then the products of every degree, like `x1², x1·x2, x2²` for two
+/// controls. A single coefficient of a polynomial of one control is its linear gain.
+pub fn polynomial(controls: &[Expression], coefficients: &[Numeric]) -> Expression {
+    let linear;
+    let coefficients = match coefficients {
+        [gain] if controls.len() == 1 => {
+            linear = [0.0, *gain];
+            &linear[..]
+        }
+        _ => coefficients,
+    };
+    if controls.is_empty() {
+        return Expression::Number(coefficients.first().copied().unwrap_or(0.0));
+    }
+
+    let mut sum = None;
+    // The indices of the controls of the current term, ascending
+    let mut factors: Vec<usize> = Vec::new();
+    for &coefficient in coefficients {
+        if coefficient != 0.0 {
+            let term = factors
+                .iter()
+                .fold(Expression::Number(coefficient), |term, &idx| {
+                    Expression::Binary(
+                        BinaryOp::Mul,
+                        Box::new(term),
+                        Box::new(controls[idx].clone()),
+                    )
+                });
+            sum = Some(match sum {
+                Some(sum) => Expression::Binary(BinaryOp::Add, Box::new(sum), Box::new(term)),
+                None => term,
+            });
+        }
+        match factors.iter().rposition(|&idx| idx + 1 < controls.len()) {
+            Some(pos) => {
+                let next = factors[pos] + 1;
+                factors[pos..].iter_mut().for_each(|idx| *idx = next);
+            }
+            None => factors = vec![0; factors.len() + 1],
+        }
+    }
+    sum.unwrap_or(Expression::Number(0.0))
+}
+
+/// Returns the linear interpolation of the `points` at `input`, which is constant beyond the
+/// first and the last point. The inputs of the points have to ascend.
+pub fn table(input: Expression, points: &[(Numeric, Numeric)]) -> Result<Expression, String> {
+    if points.is_empty() {
+        return Err("The table has no points".into());
+    }
+    if points.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+        return Err("The inputs of the table points have to ascend".into());
+    }
+    let mut arguments = Vec::with_capacity(2 * points.len() + 1);
+    arguments.push(input);
+    for &(x, y) in points {
+        arguments.push(Expression::Number(x));
+        arguments.push(Expression::Number(y));
+    }
+    Ok(Expression::Function(Function::Table, arguments))
+}
+
+/// Returns the voltage between the nodes `positive` and `negative`.
+pub fn voltage_control(positive: &str, negative: &str) -> Expression {
+    Expression::Binary(
+        BinaryOp::Sub,
+        Box::new(Expression::Voltage(Arc::from(positive))),
+        Box::new(Expression::Voltage(Arc::from(negative))),
+    )
+}
+
+/// Creates the behavioral source of a nonlinear controlled source of the `kind`.
+/// The `expression` is bound to its inputs with [`BehavioralBundle::set_expression`].
+pub(crate) fn controlled_source(
+    kind: ControlledKind,
+    name: &str,
+    positive: &str,
+    negative: &str,
+    expression: Expression,
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) -> BehavioralBundle {
+    let positive = get_variable(positive, Unit::Volt, variables, var_map);
+    let negative = get_variable(negative, Unit::Volt, variables, var_map);
+    let output = if kind.is_voltage_output() {
+        let branch = format!("{name}#branch");
+        BehavioralOutput::Voltage(get_variable(&branch, Unit::Ampere, variables, var_map))
+    } else {
+        BehavioralOutput::Current
+    };
+    BehavioralBundle::new(
+        Arc::from(name),
+        positive,
+        negative,
+        output,
+        expression,
+        Vec::new(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::behavioral::expression::{Dual, Environment};
+
+    /// Binds `expression` to the inputs `x1, x2, ...` and evaluates it at `inputs`.
+    fn evaluate(expression: Expression, inputs: &[Numeric]) -> Dual {
+        let mut expression = expression.define(&[]).unwrap();
+        expression.bind().unwrap();
+        expression.evaluate(&Environment {
+            inputs,
+            time: 0.0,
+            temp: 27.0,
+        })
+    }
+
+    fn controls(count: usize) -> Vec<Expression> {
+        (1..=count)
+            .map(|idx| Expression::Voltage(Arc::from(format!("x{idx}"))))
+            .collect()
+    }
+
+    #[test]
+    fn test_polynomial_order() {
+        // 1 + 2 x1 + 3 x2 + 4 x1² + 5 x1 x2 + 6 x2²
+        let poly = polynomial(&controls(2), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let output = evaluate(poly, &[2.0, 3.0]);
+        assert_eq!(output.value, 1.0 + 4.0 + 9.0 + 16.0 + 30.0 + 54.0);
+        assert_eq!(output.grad[..2], [2.0 + 16.0 + 15.0, 3.0 + 10.0 + 36.0]);
+
+        // The cubic terms follow as x1³, x1² x2, x1 x2², x2³
+        let mut coefficients = [0.0; 10];
+        coefficients[7] = 1.0;
+        let poly = polynomial(&controls(2), &coefficients);
+        let output = evaluate(poly, &[2.0, 3.0]);
+        assert_eq!(output.value, 12.0);
+        assert_eq!(output.grad[..2], [12.0, 4.0]);
+
+        // x1 x3 is the sixth of the quadratic terms of three controls, its only inputs
+        let mut coefficients = [0.0; 10];
+        coefficients[6] = 1.0;
+        let poly = polynomial(&controls(3), &coefficients);
+        assert_eq!(evaluate(poly, &[2.0, 5.0]).value, 10.0);
+    }
+
+    #[test]
+    fn test_polynomial_gain() {
+        let output = evaluate(polynomial(&controls(1), &[2.5]), &[2.0]);
+        assert_eq!(output.value, 5.0);
+        assert_eq!(output.grad[0], 2.5);
+
+        let output = evaluate(polynomial(&controls(1), &[2.5, 1.0]), &[2.0]);
+        assert_eq!(output.value, 4.5);
+    }
+
+    #[test]
+    fn test_table() {
+        let points = [(-1.0, -10.0), (1.0, 10.0), (2.0, 12.0)];
+        let input = || Expression::Voltage(Arc::from("x1"));
+        for (x, value, slope) in [
+            (-3.0, -10.0, 0.0),
+            (0.5, 5.0, 10.0),
+            (1.5, 11.0, 2.0),
+            (4.0, 12.0, 0.0),
+        ] {
+            let output = evaluate(table(input(), &points).unwrap(), &[x]);
+            assert_eq!(output.value, value);
+            assert_eq!(output.grad[0], slope);
+        }
+
+        assert!(table(input(), &[]).is_err());
+        assert!(table(input(), &[(1.0, 0.0), (0.0, 1.0)]).is_err());
+    }
+}
@@ -6,8 +6,12 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use super::nonlinear::{controlled_source, polynomial, table, voltage_control, ControlledKind};
 use super::{CCCSBundle, CCVSBundle, VCCSBundle, VCVSBundle};
+use crate::frontends::get_variable;
 use crate::frontends::serde::ProcessSerdeElement;
+use crate::models::behavioral::expression::Expression;
+use crate::models::behavioral::spice::parse_expression_str;
 use crate::models::controlled_sources::cccs::CCCSOptions;
 use crate::models::controlled_sources::ccvs::CCVSOptions;
 use crate::models::controlled_sources::vccs::VCCSOptions;
@@ -21,9 +25,20 @@ pub struct SerdeVCVS {
     pub name: String,
     pub positive: String,
     pub negative: String,
-    pub controlling_positive: String,
-    pub controlling_negative: String,
-    pub gain: f64,
+    #[serde(flatten)]
+    pub control: SerdeVCVSControl,
+}
+
+/// The linear gain or the nonlinear transfer of a VCVS source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SerdeVCVSControl {
+    Linear {
+        controlling_positive: String,
+        controlling_negative: String,
+        gain: f64,
+    },
+    Nonlinear(SerdeTransfer),
 }
 
 /// Serde representation of a VCCS source
@@ -32,9 +47,20 @@ pub struct SerdeVCCS {
     pub name: String,
     pub positive: String,
     pub negative: String,
-    pub controlling_positive: String,
-    pub controlling_negative: String,
-    pub transconductance: f64,
+    #[serde(flatten)]
+    pub control: SerdeVCCSControl,
+}
+
+/// The linear transconductance or the nonlinear transfer of a VCCS source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SerdeVCCSControl {
+    Linear {
+        controlling_positive: String,
+        controlling_negative: String,
+        transconductance: f64,
+    },
+    Nonlinear(SerdeTransfer),
 }
 
 /// Serde representation of a CCCS source
@@ -43,8 +69,8 @@ pub struct SerdeCCCS {
     pub name: String,
     pub positive: String,
     pub negative: String,
-    pub controlling_branch: String,
-    pub gain: f64,
+    #[serde(flatten)]
+    pub control: SerdeCurrentControl,
 }
 
 /// Serde representation of a CCVS source
@@ -53,8 +79,131 @@ pub struct SerdeCCVS {
     pub name: String,
     pub positive: String,
     pub negative: String,
-    pub controlling_branch: String,
-    pub gain: f64,
+    #[serde(flatten)]
+    pub control: SerdeCurrentControl,
+}
+
+/// The linear gain or the nonlinear transfer of a current controlled source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SerdeCurrentControl {
+    Linear {
+        controlling_branch: String,
+        gain: f64,
+    },
+    Nonlinear(SerdeTransfer),
+}
+
+/// The polynomial or the table of a nonlinear controlled source, which is built and bound while
+/// deserializing. Invalid transfers are reported as deserialization errors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerdeTransferForm", into = "SerdeTransferForm")]
+pub struct SerdeTransfer {
+    form: SerdeTransferForm,
+    expression: Expression,
+    inputs: Vec<(Arc<str>, Unit)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerdeTransferForm {
+    /// A polynomial with the coefficients in SPICE order, like
+    /// `poly: {controls: [[a, "0"], [b, "0"]], coefficients: [0, 1, 1]}`
+    Poly {
+        controls: Vec<SerdeControl>,
+        coefficients: Vec<f64>,
+    },
+    /// A piecewise-linear table, like `table: {input: V(in), points: [[-1, -10], [1, 10]]}`
+    Table {
+        input: String,
+        points: Vec<(f64, f64)>,
+    },
+}
+
+/// A controlling voltage between two nodes or the current through a voltage source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SerdeControl {
+    Voltage(String, String),
+    Current(String),
+}
+
+impl TryFrom<SerdeTransferForm> for SerdeTransfer {
+    type Error = String;
+
+    fn try_from(form: SerdeTransferForm) -> Result<Self, Self::Error> {
+        let expression = match &form {
+            SerdeTransferForm::Poly {
+                controls,
+                coefficients,
+            } => {
+                if controls.is_empty() {
+                    return Err("The polynomial has no controls".into());
+                }
+                let controls: Vec<Expression> = controls
+                    .iter()
+                    .map(|control| match control {
+                        SerdeControl::Voltage(positive, negative) => {
+                            voltage_control(positive, negative)
+                        }
+                        SerdeControl::Current(source) => {
+                            Expression::Current(Arc::from(source.as_str()))
+                        }
+                    })
+                    .collect();
+                polynomial(&controls, coefficients)
+            }
+            SerdeTransferForm::Table { input, points } => {
+                let input = parse_expression_str(input)
+                    .map_err(|err| format!("Invalid expression '{input}': {err}"))?;
+                table(input, points)?
+            }
+        };
+        let mut expression = expression.define(&[])?;
+        let inputs = expression.bind()?;
+        Ok(Self {
+            form,
+            expression,
+            inputs,
+        })
+    }
+}
+
+impl From<SerdeTransfer> for SerdeTransferForm {
+    fn from(transfer: SerdeTransfer) -> Self {
+        transfer.form
+    }
+}
+
+impl SerdeTransfer {
+    /// Creates the behavioral source of a controlled source of the `kind` with this transfer.
+    fn source(
+        &self,
+        kind: ControlledKind,
+        name: &str,
+        positive: &str,
+        negative: &str,
+        variables: &mut Vec<Variable>,
+        var_map: &mut HashMap<Arc<str>, usize>,
+    ) -> Element {
+        let expression = self.expression.clone();
+        let mut source = controlled_source(
+            kind,
+            name,
+            positive,
+            negative,
+            expression.clone(),
+            variables,
+            var_map,
+        );
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|(name, unit)| get_variable(name, *unit, variables, var_map))
+            .collect();
+        source.set_expression(expression, inputs);
+        Element::Behavioral(source)
+    }
 }
 
 impl ProcessSerdeElement for SerdeVCVS {
@@ -64,6 +213,25 @@ impl ProcessSerdeElement for SerdeVCVS {
         elements: &mut Vec<Element>,
         var_map: &mut HashMap<Arc<str>, usize>,
     ) {
+        let (controlling_positive, controlling_negative, gain) = match &self.control {
+            SerdeVCVSControl::Linear {
+                controlling_positive,
+                controlling_negative,
+                gain,
+            } => (controlling_positive, controlling_negative, *gain),
+            SerdeVCVSControl::Nonlinear(transfer) => {
+                elements.push(transfer.source(
+                    ControlledKind::VCVS,
+                    &self.name,
+                    &self.positive,
+                    &self.negative,
+                    variables,
+                    var_map,
+                ));
+                return;
+            }
+        };
+
         // Create variables for output nodes
         let pos_var = Variable::new(
             Arc::from(self.positive.as_str()),
@@ -85,12 +253,12 @@ impl ProcessSerdeElement for SerdeVCVS {
 
         // Create variables for controlling nodes
         let ctrl_pos_var = Variable::new(
-            Arc::from(self.controlling_positive.as_str()),
+            Arc::from(controlling_positive.as_str()),
             Unit::Volt,
             variables.len(),
         );
         let ctrl_neg_var = Variable::new(
-            Arc::from(self.controlling_negative.as_str()),
+            Arc::from(controlling_negative.as_str()),
             Unit::Volt,
             variables.len() + 1,
         );
@@ -98,13 +266,13 @@ impl ProcessSerdeElement for SerdeVCVS {
         // Add controlling variables to vectors and map
         variables.push(ctrl_pos_var.clone());
         var_map.insert(
-            Arc::from(self.controlling_positive.as_str()),
+            Arc::from(controlling_positive.as_str()),
             variables.len() - 1,
         );
 
         variables.push(ctrl_neg_var.clone());
         var_map.insert(
-            Arc::from(self.controlling_negative.as_str()),
+            Arc::from(controlling_negative.as_str()),
             variables.len() - 1,
         );
 
@@ -115,7 +283,7 @@ impl ProcessSerdeElement for SerdeVCVS {
             Some(neg_var),
             Some(ctrl_pos_var),
             Some(ctrl_neg_var),
-            Some(VCVSOptions { gain: self.gain }),
+            Some(VCVSOptions { gain }),
         );
 
         elements.push(Element::VCVS(vcvs));
@@ -129,6 +297,29 @@ impl ProcessSerdeElement for SerdeVCCS {
         elements: &mut Vec<Element>,
         var_map: &mut HashMap<Arc<str>, usize>,
     ) {
+        let (controlling_positive, controlling_negative, transconductance) = match &self.control {
+            SerdeVCCSControl::Linear {
+                controlling_positive,
+                controlling_negative,
+                transconductance,
+            } => (
+                controlling_positive,
+                controlling_negative,
+                *transconductance,
+            ),
+            SerdeVCCSControl::Nonlinear(transfer) => {
+                elements.push(transfer.source(
+                    ControlledKind::VCCS,
+                    &self.name,
+                    &self.positive,
+                    &self.negative,
+                    variables,
+                    var_map,
+                ));
+                return;
+            }
+        };
+
         // Create variables for output nodes
         let pos_var = Variable::new(
             Arc::from(self.positive.as_str()),
@@ -150,12 +341,12 @@ impl ProcessSerdeElement for SerdeVCCS {
 
         // Create variables for controlling nodes
         let ctrl_pos_var = Variable::new(
-            Arc::from(self.controlling_positive.as_str()),
+            Arc::from(controlling_positive.as_str()),
             Unit::Volt,
             variables.len(),
         );
         let ctrl_neg_var = Variable::new(
-            Arc::from(self.controlling_negative.as_str()),
+            Arc::from(controlling_negative.as_str()),
             Unit::Volt,
             variables.len() + 1,
         );
@@ -163,13 +354,13 @@ impl ProcessSerdeElement for SerdeVCCS {
         // Add controlling variables to vectors and map
         variables.push(ctrl_pos_var.clone());
         var_map.insert(
-            Arc::from(self.controlling_positive.as_str()),
+            Arc::from(controlling_positive.as_str()),
             variables.len() - 1,
         );
 
         variables.push(ctrl_neg_var.clone());
         var_map.insert(
-            Arc::from(self.controlling_negative.as_str()),
+            Arc::from(controlling_negative.as_str()),
             variables.len() - 1,
         );
 
@@ -180,9 +371,7 @@ impl ProcessSerdeElement for SerdeVCCS {
             Some(neg_var),
             Some(ctrl_pos_var),
             Some(ctrl_neg_var),
-            Some(VCCSOptions {
-                transconductance: self.transconductance,
-            }),
+            Some(VCCSOptions { transconductance }),
         );
 
         elements.push(Element::VCCS(vccs));
@@ -196,6 +385,24 @@ impl ProcessSerdeElement for SerdeCCCS {
         elements: &mut Vec<Element>,
         var_map: &mut HashMap<Arc<str>, usize>,
     ) {
+        let (controlling_branch, gain) = match &self.control {
+            SerdeCurrentControl::Linear {
+                controlling_branch,
+                gain,
+            } => (controlling_branch, *gain),
+            SerdeCurrentControl::Nonlinear(transfer) => {
+                elements.push(transfer.source(
+                    ControlledKind::CCCS,
+                    &self.name,
+                    &self.positive,
+                    &self.negative,
+                    variables,
+                    var_map,
+                ));
+                return;
+            }
+        };
+
         // Create variables for output nodes
         let pos_var = Variable::new(
             Arc::from(self.positive.as_str()),
@@ -217,17 +424,14 @@ impl ProcessSerdeElement for SerdeCCCS {
 
         // Create variable for controlling branch
         let ctrl_branch_var = Variable::new(
-            Arc::from(self.controlling_branch.as_str()),
+            Arc::from(controlling_branch.as_str()),
             Unit::Volt,
             variables.len(),
         );
 
         // Add controlling variable to vectors and map
         variables.push(ctrl_branch_var.clone());
-        var_map.insert(
-            Arc::from(self.controlling_branch.as_str()),
-            variables.len() - 1,
-        );
+        var_map.insert(Arc::from(controlling_branch.as_str()), variables.len() - 1);
 
         // Create CCCS bundle
         let cccs = CCCSBundle::new(
@@ -235,7 +439,7 @@ impl ProcessSerdeElement for SerdeCCCS {
             Some(pos_var),
             Some(neg_var),
             Some(ctrl_branch_var),
-            Some(CCCSOptions { gain: self.gain }),
+            Some(CCCSOptions { gain }),
         );
 
         elements.push(Element::CCCS(cccs));
@@ -249,6 +453,24 @@ impl ProcessSerdeElement for SerdeCCVS {
         elements: &mut Vec<Element>,
         var_map: &mut HashMap<Arc<str>, usize>,
     ) {
+        let (controlling_branch, gain) = match &self.control {
+            SerdeCurrentControl::Linear {
+                controlling_branch,
+                gain,
+            } => (controlling_branch, *gain),
+            SerdeCurrentControl::Nonlinear(transfer) => {
+                elements.push(transfer.source(
+                    ControlledKind::CCVS,
+                    &self.name,
+                    &self.positive,
+                    &self.negative,
+                    variables,
+                    var_map,
+                ));
+                return;
+            }
+        };
+
         // Create variables for output nodes
         let pos_var = Variable::new(
             Arc::from(self.positive.as_str()),
@@ -270,17 +492,14 @@ impl ProcessSerdeElement for SerdeCCVS {
 
         // Create variable for controlling branch
         let ctrl_branch_var = Variable::new(
-            Arc::from(self.controlling_branch.as_str()),
+            Arc::from(controlling_branch.as_str()),
             Unit::Volt,
             variables.len(),
         );
 
         // Add controlling variable to vectors and map
         variables.push(ctrl_branch_var.clone());
-        var_map.insert(
-            Arc::from(self.controlling_branch.as_str()),
-            variables.len() - 1,
-        );
+        var_map.insert(Arc::from(controlling_branch.as_str()), variables.len() - 1);
 
         // Create CCVS bundle
         let ccvs = CCVSBundle::new(
@@ -288,7 +507,7 @@ impl ProcessSerdeElement for SerdeCCVS {
             Some(pos_var),
             Some(neg_var),
             Some(ctrl_branch_var),
-            Some(CCVSOptions { gain: self.gain }),
+            Some(CCVSOptions { gain }),
         );
 
         elements.push(Element::CCVS(ccvs));
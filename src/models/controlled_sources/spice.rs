@@ -1,8 +1,11 @@
 // SPICE parser support for controlled sources
 // This module handles parsing of controlled sources from SPICE netlists
 
+use super::nonlinear::{controlled_source, polynomial, table, voltage_control, ControlledKind};
 use super::{CCCSBundle, CCVSBundle, VCCSBundle, VCVSBundle};
 use crate::frontends::spice::Rule;
+use crate::models::behavioral::expression::Expression;
+use crate::models::behavioral::spice::parse_expression;
 use crate::models::controlled_sources::cccs::CCCSOptions;
 use crate::models::controlled_sources::ccvs::CCVSOptions;
 use crate::models::controlled_sources::vccs::VCCSOptions;
@@ -16,12 +19,16 @@ use std::sync::Arc;
 
 /// Processes a VCVS (E) source from SPICE format
 /// Syntax: E{name} {pos} {neg} {ctrl_pos} {ctrl_neg} {gain}
+///         E{name} {pos} {neg} POLY({n}) {ctrl_pos1} {ctrl_neg1} ... {p0} {p1} ...
+///         E{name} {pos} {neg} TABLE {expression} = ({x1}, {y1}) ({x2}, {y2}) ...
 pub fn process_vcvs(
     element: Pair<Rule>,
     variables: &mut Vec<Variable>,
     elements: &mut Vec<Element>,
     var_map: &mut std::collections::HashMap<Arc<str>, usize>,
 ) -> Result<(), FrontendError> {
+    // The full name of the element, ELEMENT_NAME lacks the letter of the source
+    let element_name = element.as_str().split_whitespace().next().unwrap_or_default();
     let mut inner = element.into_inner();
 
     // Parse name
@@ -37,6 +44,26 @@ pub fn process_vcvs(
     let neg_node = inner
         .next()
         .ok_or_else(|| FrontendError::ParseError("Missing negative node for VCVS source".into()))?;
+
+    // The POLY and TABLE forms are nonlinear
+    if let Some(form) = inner.peek().filter(|pair| {
+        matches!(
+            pair.as_rule(),
+            Rule::CONTROLLED_POLY | Rule::CONTROLLED_TABLE
+        )
+    }) {
+        let source = process_nonlinear(
+            ControlledKind::VCVS,
+            element_name,
+            pos_node.as_str(),
+            neg_node.as_str(),
+            form,
+            variables,
+            var_map,
+        )?;
+        elements.push(source);
+        return Ok(());
+    }
     let ctrl_pos_node = inner.next().ok_or_else(|| {
         FrontendError::ParseError("Missing controlling positive node for VCVS source".into())
     })?;
@@ -101,12 +128,16 @@ pub fn process_vcvs(
 
 /// Processes a VCCS (G) source from SPICE format
 /// Syntax: G{name} {pos} {neg} {ctrl_pos} {ctrl_neg} {transconductance}
+///         G{name} {pos} {neg} POLY({n}) {ctrl_pos1} {ctrl_neg1} ... {p0} {p1} ...
+///         G{name} {pos} {neg} TABLE {expression} = ({x1}, {y1}) ({x2}, {y2}) ...
 pub fn process_vccs(
     element: Pair<Rule>,
     variables: &mut Vec<Variable>,
     elements: &mut Vec<Element>,
     var_map: &mut HashMap<Arc<str>, usize>,
 ) -> Result<(), FrontendError> {
+    // The full name of the element, ELEMENT_NAME lacks the letter of the source
+    let element_name = element.as_str().split_whitespace().next().unwrap_or_default();
     let mut inner = element.into_inner();
 
     // Parse name
@@ -122,6 +153,26 @@ pub fn process_vccs(
     let neg_node = inner
         .next()
         .ok_or_else(|| FrontendError::ParseError("Missing negative node for VCCS source".into()))?;
+
+    // The POLY and TABLE forms are nonlinear
+    if let Some(form) = inner.peek().filter(|pair| {
+        matches!(
+            pair.as_rule(),
+            Rule::CONTROLLED_POLY | Rule::CONTROLLED_TABLE
+        )
+    }) {
+        let source = process_nonlinear(
+            ControlledKind::VCCS,
+            element_name,
+            pos_node.as_str(),
+            neg_node.as_str(),
+            form,
+            variables,
+            var_map,
+        )?;
+        elements.push(source);
+        return Ok(());
+    }
     let ctrl_pos_node = inner.next().ok_or_else(|| {
         FrontendError::ParseError("Missing controlling positive node for VCCS source".into())
     })?;
@@ -185,12 +236,15 @@ pub fn process_vccs(
 
 /// Processes a CCCS (F) source from SPICE format
 /// Syntax: F{name} {pos} {neg} {ctrl_branch} {gain}
+///         F{name} {pos} {neg} POLY({n}) {ctrl_branch1} ... {p0} {p1} ...
 pub fn process_cccs(
     element: Pair<Rule>,
     variables: &mut Vec<Variable>,
     elements: &mut Vec<Element>,
     var_map: &mut HashMap<Arc<str>, usize>,
 ) -> Result<(), FrontendError> {
+    // The full name of the element, ELEMENT_NAME lacks the letter of the source
+    let element_name = element.as_str().split_whitespace().next().unwrap_or_default();
     let mut inner = element.into_inner();
 
     // Parse name
@@ -206,6 +260,26 @@ pub fn process_cccs(
     let neg_node = inner
         .next()
         .ok_or_else(|| FrontendError::ParseError("Missing negative node for CCCS source".into()))?;
+
+    // The POLY and TABLE forms are nonlinear
+    if let Some(form) = inner.peek().filter(|pair| {
+        matches!(
+            pair.as_rule(),
+            Rule::CONTROLLED_POLY | Rule::CONTROLLED_TABLE
+        )
+    }) {
+        let source = process_nonlinear(
+            ControlledKind::CCCS,
+            element_name,
+            pos_node.as_str(),
+            neg_node.as_str(),
+            form,
+            variables,
+            var_map,
+        )?;
+        elements.push(source);
+        return Ok(());
+    }
     let ctrl_branch_node = inner.next().ok_or_else(|| {
         FrontendError::ParseError("Missing controlling branch node for CCCS source".into())
     })?;
@@ -258,12 +332,15 @@ pub fn process_cccs(
 
 /// Processes a CCVS (H) source from SPICE format
 /// Syntax: H{name} {pos} {neg} {ctrl_branch} {gain}
+///         H{name} {pos} {neg} POLY({n}) {ctrl_branch1} ... {p0} {p1} ...
 pub fn process_ccvs(
     element: Pair<Rule>,
     variables: &mut Vec<Variable>,
     elements: &mut Vec<Element>,
     var_map: &mut HashMap<Arc<str>, usize>,
 ) -> Result<(), FrontendError> {
+    // The full name of the element, ELEMENT_NAME lacks the letter of the source
+    let element_name = element.as_str().split_whitespace().next().unwrap_or_default();
     let mut inner = element.into_inner();
 
     // Parse name
@@ -279,6 +356,26 @@ pub fn process_ccvs(
     let neg_node = inner
         .next()
         .ok_or_else(|| FrontendError::ParseError("Missing negative node for CCVS source".into()))?;
+
+    // The POLY and TABLE forms are nonlinear
+    if let Some(form) = inner.peek().filter(|pair| {
+        matches!(
+            pair.as_rule(),
+            Rule::CONTROLLED_POLY | Rule::CONTROLLED_TABLE
+        )
+    }) {
+        let source = process_nonlinear(
+            ControlledKind::CCVS,
+            element_name,
+            pos_node.as_str(),
+            neg_node.as_str(),
+            form,
+            variables,
+            var_map,
+        )?;
+        elements.push(source);
+        return Ok(());
+    }
     let ctrl_branch_node = inner.next().ok_or_else(|| {
         FrontendError::ParseError("Missing controlling branch node for CCVS source".into())
     })?;
@@ -328,3 +425,92 @@ pub fn process_ccvs(
 
     Ok(())
 }
+
+/// Processes the POLY or TABLE form of a controlled source into a behavioral source.
+/// Its expression is defined and bound with the behavioral sources after parsing, so a
+/// controlling current may refer to a voltage source further down the netlist.
+fn process_nonlinear(
+    kind: ControlledKind,
+    name: &str,
+    pos_node: &str,
+    neg_node: &str,
+    form: Pair<Rule>,
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) -> Result<Element, FrontendError> {
+    let label = kind.label();
+    let invalid = |reason: String| FrontendError::InvalidExpression {
+        element: name.to_string(),
+        reason,
+    };
+    let expression = if form.as_rule() == Rule::CONTROLLED_TABLE {
+        let mut inner = form.into_inner();
+        let input = inner.next().ok_or_else(|| {
+            FrontendError::ParseError(format!("Missing table input for {label} source"))
+        })?;
+        let input = parse_expression(input)?;
+        let points = inner
+            .map(|point| {
+                let mut values = point.into_inner();
+                match (values.next(), values.next()) {
+                    (Some(x), Some(y)) => Ok((x.as_str().parse()?, y.as_str().parse()?)),
+                    _ => Err(FrontendError::ParseError(format!(
+                        "Missing table value for {label} source"
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<(Numeric, Numeric)>, FrontendError>>()?;
+        table(input, &points).map_err(invalid)?
+    } else {
+        let mut inner = form.into_inner();
+        let dimension: usize = inner
+            .next()
+            .ok_or_else(|| {
+                FrontendError::ParseError(format!("Missing POLY dimension for {label} source"))
+            })?
+            .as_str()
+            .parse()
+            .map_err(|_| {
+                FrontendError::ParseError(format!("Invalid POLY dimension for {label} source"))
+            })?;
+        let arguments: Vec<&str> = inner.map(|argument| argument.as_str()).collect();
+        let control_count = if kind.is_voltage_controlled() {
+            2 * dimension
+        } else {
+            dimension
+        };
+        if dimension == 0 || arguments.len() <= control_count {
+            return Err(invalid(format!(
+                "POLY({dimension}) needs {control_count} controls followed by coefficients"
+            )));
+        }
+        let (names, coefficients) = arguments.split_at(control_count);
+        let controls: Vec<Expression> = if kind.is_voltage_controlled() {
+            names
+                .chunks(2)
+                .map(|pair| voltage_control(pair[0], pair[1]))
+                .collect()
+        } else {
+            names
+                .iter()
+                .map(|source| Expression::Current(Arc::from(*source)))
+                .collect()
+        };
+        let coefficients = coefficients
+            .iter()
+            .map(|coefficient| {
+                coefficient.parse().map_err(|_| {
+                    FrontendError::ParseError(format!(
+                        "Invalid coefficient '{coefficient}' for {label} source"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<Numeric>, _>>()?;
+        polynomial(&controls, &coefficients)
+    };
+
+    let source = controlled_source(
+        kind, name, pos_node, neg_node, expression, variables, var_map,
+    );
+    Ok(Element::Behavioral(source))
+}
@@ -327,6 +327,11 @@ mod tests {
     ) -> Element {
         Element::Inductor(crate::models::InductorBundle {
             name: Arc::from(name),
+            branch: Variable::new(
+                Arc::from(format!("{}#branch", name)),
+                crate::models::Unit::Ampere,
+                0,
+            ),
             node0: node0.map(|idx| {
                 Variable::new(
                     Arc::from(format!("node{}", idx)),
@@ -342,7 +347,7 @@ mod tests {
                 )
             }),
             value,
-            previous_current: Numeric::zero(),
+            initial_condition: None,
         })
     }
 
@@ -3,11 +3,19 @@
 pub(crate) mod serde;
 pub(crate) mod spice;
 
+use std::collections::HashMap;
+use std::ops::Neg;
 use std::sync::Arc;
 
-use num::{One, Zero};
+use num::traits::FloatConst;
+use num::One;
 
+use super::charge::{junction_charge, voltage_between, Charge};
+use super::limiting;
+use super::temperature::{self, DeviceTemperature};
 use super::*;
+use crate::frontends::get_internal_variable;
+use crate::sim::options::SimulatorOptions;
 use crate::spot::*;
 
 /// A structure representing a Diode with all their options.
@@ -16,7 +24,18 @@ pub struct DiodeBundle {
     name: Arc<str>,
     anode: Option<Variable>,
     cathode: Option<Variable>,
+    /// The internal anode node behind `rs`
+    internal_anode: Option<Variable>,
+    /// The name of the `.model` card of the diode
+    model: Option<Arc<str>>,
     value: DiodeOptions,
+    temperature: DeviceTemperature,
+    /// The thermal voltage at the temperature of the diode
+    ut: Numeric,
+    /// The saturation current at the temperature of the diode
+    is: Numeric,
+    /// The conductance in parallel to the junction
+    gmin: Numeric,
 }
 
 /// An struct representing possible Diode options.
@@ -24,7 +43,26 @@ pub struct DiodeBundle {
 pub struct DiodeOptions {
     is: Numeric,
     n: Numeric,
-    rs: Option<Numeric>,
+    /// Series resistance, zero for none
+    rs: Numeric,
+    /// Zero-bias junction capacitance
+    cj0: Numeric,
+    /// Junction potential
+    vj: Numeric,
+    /// Grading coefficient
+    m: Numeric,
+    /// Forward-bias depletion capacitance coefficient
+    fc: Numeric,
+    /// Transit time
+    tt: Numeric,
+    /// Band gap energy in eV
+    eg: Numeric,
+    /// Saturation current temperature exponent
+    xti: Numeric,
+    /// Reverse breakdown voltage, zero for none
+    bv: Numeric,
+    /// Reverse current at the breakdown voltage
+    ibv: Numeric,
 }
 
 impl Default for DiodeOptions {
@@ -32,11 +70,95 @@ impl Default for DiodeOptions {
         Self {
             is: 1e-14,
             n: 1.0,
-            rs: None,
+            rs: 0.0,
+            cj0: 0.0,
+            vj: 1.0,
+            m: 0.5,
+            fc: 0.5,
+            tt: 0.0,
+            eg: 1.11,
+            xti: 3.0,
+            bv: 0.0,
+            ibv: 1e-3,
         }
     }
 }
 
+impl DiodeOptions {
+    /// Sets the depletion capacitance of the junction.
+    pub fn with_junction_capacitance(mut self, cj0: Numeric, vj: Numeric, m: Numeric) -> Self {
+        self.cj0 = cj0;
+        self.vj = vj;
+        self.m = m;
+        self
+    }
+
+    /// Sets the transit time, which determines the diffusion capacitance.
+    pub fn with_transit_time(mut self, tt: Numeric) -> Self {
+        self.tt = tt;
+        self
+    }
+
+    /// Sets the band gap energy and the temperature exponent of the saturation current.
+    pub fn with_temperature_scaling(mut self, eg: Numeric, xti: Numeric) -> Self {
+        self.eg = eg;
+        self.xti = xti;
+        self
+    }
+
+    /// Sets the series resistance between the anode and the junction.
+    pub fn with_series_resistance(mut self, rs: Numeric) -> Self {
+        self.rs = rs;
+        self
+    }
+
+    /// Sets the reverse breakdown voltage and the current at it.
+    pub fn with_breakdown(mut self, bv: Numeric, ibv: Numeric) -> Self {
+        self.bv = bv;
+        self.ibv = ibv;
+        self
+    }
+
+    /// Sets the model parameter `name` with its SPICE name, like `IS` or `CJO`.
+    /// Returns false if `name` is not a parameter of the model.
+    pub fn set_parameter(&mut self, name: &str, value: Numeric) -> bool {
+        let parameter = match name.to_ascii_lowercase().as_str() {
+            "is" => &mut self.is,
+            "n" => &mut self.n,
+            "rs" => &mut self.rs,
+            "cjo" | "cj0" | "cj" => &mut self.cj0,
+            "vj" | "pb" => &mut self.vj,
+            "m" | "mj" => &mut self.m,
+            "fc" => &mut self.fc,
+            "tt" => &mut self.tt,
+            "eg" => &mut self.eg,
+            "xti" => &mut self.xti,
+            "bv" => &mut self.bv,
+            "ibv" => &mut self.ibv,
+            _ => return false,
+        };
+        *parameter = value;
+        true
+    }
+
+    /// Returns the series resistance, which is zero for none.
+    pub fn series_resistance(&self) -> Numeric {
+        self.rs
+    }
+}
+
+/// Creates the internal anode behind a non-zero series resistance of the diode.
+pub(crate) fn add_internal_node(
+    diode: &mut DiodeBundle,
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) {
+    let internal = (diode.value.rs > 0.0)
+        .then(|| get_internal_variable(&diode.name, "anode", variables, var_map))
+        .flatten();
+    diode.set_internal_node(internal);
+}
+
 impl DiodeBundle {
     /// Creates a new `DiodeBundle` object.
     ///
@@ -64,115 +186,210 @@ impl DiodeBundle {
             name,
             anode,
             cathode,
+            internal_anode: None,
+            model: None,
+            is: value.is,
             value,
+            temperature: DeviceTemperature::default(),
+            ut: UT,
+            gmin: GMIN,
         }
     }
 
+    /// Sets the name of the `.model` card of the diode.
+    pub fn set_model(&mut self, model: Arc<str>) {
+        self.model = Some(model);
+    }
+
+    /// Returns the name of the `.model` card of the diode.
+    pub fn model(&self) -> Option<Arc<str>> {
+        self.model.clone()
+    }
+
+    /// Replaces the model parameters of the diode.
+    pub fn set_options(&mut self, options: DiodeOptions) {
+        self.is = options.is;
+        self.value = options;
+    }
+
+    /// Returns the model parameters of the diode.
+    pub fn options(&self) -> &DiodeOptions {
+        &self.value
+    }
+
+    /// Sets the internal anode behind the series resistance.
+    /// `None` connects the junction to the anode directly.
+    pub fn set_internal_node(&mut self, internal_anode: Option<Variable>) {
+        self.internal_anode = internal_anode;
+    }
+
+    /// Sets the instance temperature of the diode.
+    pub fn set_temperature(&mut self, temperature: DeviceTemperature) {
+        self.temperature = temperature;
+    }
+
+    /// Scales the thermal voltage and the saturation current to the temperature of the diode
+    /// and applies gmin of the simulator settings.
+    pub fn apply_options(&mut self, options: &SimulatorOptions) {
+        let temp = self.temperature.kelvin(options);
+        let DiodeOptions { is, n, eg, xti, .. } = self.value;
+        self.ut = temperature::thermal_voltage(temp);
+        self.is = temperature::saturation_current(is, n, eg, xti, temp, options.tnom_kelvin());
+        self.gmin = options.gmin;
+    }
+
     /// Returns the name of the diode bundle.
     pub fn name(&self) -> Arc<str> {
         self.name.clone()
     }
 
     /// Returns a reference to the triples representing matrix A.
-    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 4> {
-        let a_voltage = match self.a_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
-        };
-        let c_voltage = match self.c_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
-        };
-        let v_diode = a_voltage - c_voltage;
+    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 8> {
+        self.triples_at(self.voltage(x_vec))
+    }
 
-        // Conductance of this diode - Schokley equation
-        let cond = (self.value.is * (Numeric::exp(v_diode / (self.value.n * UT)) - Numeric::one()))
-            / (self.value.n * UT);
+    /// Returns the triples of the junction conductance linearised at `voltage` and of the
+    /// series resistance.
+    pub fn triples_at(&self, voltage: Numeric) -> Triples<Numeric, 8> {
+        let (_, cond) = self.current(voltage);
+        self.stamps(cond)
+    }
 
-        let a_idx = if let Some(idx) = self.a_idx() {
-            idx
-        } else {
-            // If anode doesn't exist, diode is connected to ground through cathode
-            let c_idx = self
-                .c_idx()
-                .expect("Diode must have at least one node connected");
-            return Triples::new(&[(c_idx, c_idx, cond)]);
-        };
-        let c_idx = if let Some(idx) = self.c_idx() {
-            idx
-        } else {
-            // If cathode doesn't exist, diode is connected to ground through anode
-            let a_idx = self
-                .a_idx()
-                .expect("Diode must have at least one node connected");
-            return Triples::new(&[(a_idx, a_idx, cond)]);
-        };
+    /// Returns a reference to the triples representing matrix A.
+    pub fn triple_idx(&self) -> Option<TripleIdx<8>> {
+        let indices: Vec<_> = self
+            .stamps(0.0)
+            .into_iter()
+            .map(|&(row, col, _)| (row, col))
+            .collect();
+        (!indices.is_empty()).then(|| TripleIdx::new(&indices))
+    }
 
-        Triples::new(&[
-            (a_idx, a_idx, cond),
-            (c_idx, c_idx, cond),
-            (a_idx, c_idx, -cond),
-            (c_idx, a_idx, -cond),
-        ])
+    /// Returns the small-signal triples at the frequency `freq` in Hz, linearised at the
+    /// operating point `x_vec`, with the capacitance of the junction as admittance.
+    pub fn ac_triples(&self, freq: Numeric, x_vec: &[Numeric]) -> Triples<ComplexNumeric, 8> {
+        let (_, cond) = self.current(self.voltage(x_vec));
+        let omega = 2.0 * Numeric::PI() * freq;
+        let capacitance = self.charge(x_vec).capacitance;
+        self.stamps(ComplexNumeric::new(cond, omega * capacitance))
     }
 
-    /// Returns a reference to the triples representing matrix A.
-    pub fn triple_idx(&self) -> Option<TripleIdx<4>> {
-        match (self.a_idx(), self.c_idx()) {
-            (None, None) => None,
-            (None, Some(idx_1)) => Some(TripleIdx::new(&[(idx_1, idx_1)])),
-            (Some(idx_0), None) => Some(TripleIdx::new(&[(idx_0, idx_0)])),
-            (Some(idx_0), Some(idx_1)) => Some(TripleIdx::new(&[
-                (idx_0, idx_0),
-                (idx_1, idx_1),
-                (idx_0, idx_1),
-                (idx_1, idx_0),
-            ])),
+    /// Returns the triples of the admittance `y` of the junction between the internal anode and
+    /// the cathode and of the series resistance.
+    fn stamps<T>(&self, y: T) -> Triples<T, 8>
+    where
+        T: Copy + Default + From<Numeric> + Neg<Output = T>,
+    {
+        let mut triples = Vec::with_capacity(8);
+        let mut stamp = |node0: Option<usize>, node1: Option<usize>, y: T| match (node0, node1) {
+            (None, None) => {}
+            (Some(idx), None) | (None, Some(idx)) => triples.push((idx, idx, y)),
+            (Some(idx_0), Some(idx_1)) => triples.extend([
+                (idx_0, idx_0, y),
+                (idx_1, idx_1, y),
+                (idx_0, idx_1, -y),
+                (idx_1, idx_0, -y),
+            ]),
+        };
+        stamp(self.ai_idx(), self.c_idx(), y);
+        if let Some(internal) = self.internal_node_index() {
+            stamp(self.a_idx(), Some(internal), T::from(self.value.rs.recip()));
         }
+        Triples::new(&triples)
     }
 
     /// Returns a reference to the pairs representing vector b.
     pub fn pairs(&self, x_vec: &[Numeric]) -> Pairs<Numeric, 2> {
-        let a_voltage = match self.a_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
-        };
-        let c_voltage = match self.c_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
-        };
-        let v_diode = a_voltage - c_voltage;
+        self.pairs_at(self.voltage(x_vec))
+    }
 
-        // Conductance of this diode - Schokley equation
-        let cond = (self.value.is * (Numeric::exp(v_diode / (self.value.n * UT)) - Numeric::one()))
-            / (self.value.n * UT);
+    /// Returns the pairs of the equivalent current source `id - gd * vd` linearised at `voltage`.
+    /// The current flows from anode to cathode and enters the RHS with the opposite sign.
+    pub fn pairs_at(&self, voltage: Numeric) -> Pairs<Numeric, 2> {
+        let (current, cond) = self.current(voltage);
+        let i_eq = current - cond * voltage;
 
-        let ca = cond * v_diode
-            - self.value.is * (Numeric::exp(v_diode / (self.value.n * UT)) - Numeric::one());
-        let cc = -cond * v_diode
-            - self.value.is * (Numeric::exp(v_diode / (self.value.n * UT)) - Numeric::one());
+        match (self.ai_idx(), self.c_idx()) {
+            (None, None) => Pairs::new(&[]),
+            (Some(a_idx), None) => Pairs::new(&[(a_idx, -i_eq)]),
+            (None, Some(c_idx)) => Pairs::new(&[(c_idx, i_eq)]),
+            (Some(a_idx), Some(c_idx)) => Pairs::new(&[(a_idx, -i_eq), (c_idx, i_eq)]),
+        }
+    }
 
-        let a_idx = if let Some(idx) = self.a_idx() {
-            idx
-        } else {
-            // If anode doesn't exist, diode is connected to ground through cathode
-            let c_idx = self
-                .c_idx()
-                .expect("Diode must have at least one node connected");
-            return Pairs::new(&[(c_idx, cc)]);
-        };
+    /// Returns the junction voltage at the solution `x_vec`.
+    pub fn voltage(&self, x_vec: &[Numeric]) -> Numeric {
+        voltage_between(self.ai_idx(), self.c_idx(), x_vec)
+    }
+
+    /// Limits the Newton step of the junction voltage from `v_old` to `v_new`.
+    /// Returns the limited voltage and whether it was limited.
+    pub fn limit_voltage(&self, v_new: Numeric, v_old: Numeric) -> (Numeric, bool) {
+        let vt = self.value.n * self.ut;
+        let v_crit = limiting::pn_critical_voltage(vt, self.is);
+        let bv = self.value.bv;
 
-        let c_idx = if let Some(idx) = self.c_idx() {
-            idx
+        // Close to breakdown the step is limited like a forward junction mirrored at -bv
+        if bv > 0.0 && v_new < Numeric::min(0.0, -bv + 10.0 * vt) {
+            let (mirrored, limited) =
+                limiting::pn_junction(-(v_new + bv), -(v_old + bv), vt, v_crit);
+            return (-(mirrored + bv), limited);
+        }
+        limiting::pn_junction(v_new, v_old, vt, v_crit)
+    }
+
+    /// Returns the operating region of the junction at `voltage`.
+    pub fn region(&self, voltage: Numeric) -> &'static str {
+        if voltage >= 0.0 {
+            "forward biased"
+        } else if self.value.bv > 0.0 && voltage <= -self.value.bv {
+            "breakdown"
         } else {
-            // If cathode doesn't exist, diode is connected to ground through anode
-            let a_idx = self
-                .a_idx()
-                .expect("Diode must have at least one node connected");
-            return Pairs::new(&[(a_idx, ca)]);
-        };
+            "reverse biased"
+        }
+    }
+
+    /// Returns the current and the conductance of the junction at `voltage` - Shockley equation.
+    /// A conductance of `gmin` in parallel keeps the junction from floating in reverse bias.
+    /// The reverse breakdown adds a current, which rises exponentially below `-bv` and is `ibv`
+    /// at `-bv`.
+    fn current(&self, voltage: Numeric) -> (Numeric, Numeric) {
+        let vt = self.value.n * self.ut;
+        let exp = Numeric::exp(voltage / vt);
+        let mut current = self.is * (exp - Numeric::one()) + self.gmin * voltage;
+        let mut cond = self.is * exp / vt + self.gmin;
 
-        Pairs::new(&[(a_idx, ca), (c_idx, cc)])
+        let DiodeOptions { bv, ibv, .. } = self.value;
+        if bv > 0.0 {
+            // Offset to vanish at zero bias
+            let exp_bv = Numeric::exp(-(bv + voltage) / vt);
+            current -= ibv * (exp_bv - Numeric::exp(-bv / vt));
+            cond += ibv * exp_bv / vt;
+        }
+        (current, cond)
+    }
+
+    /// Returns the charge stored in the diode at the solution `x_vec`.
+    /// It consists of the depletion charge of the junction and the diffusion charge `tt * id`.
+    pub fn charge(&self, x_vec: &[Numeric]) -> Charge {
+        let options = &self.value;
+        let voltage = self.voltage(x_vec);
+
+        let (q_dep, c_dep) =
+            junction_charge(voltage, options.cj0, options.vj, options.m, options.fc);
+
+        let vt = options.n * self.ut;
+        let exp = Numeric::exp(voltage / vt);
+        let current = self.is * (exp - Numeric::one());
+        let conductance = self.is * exp / vt;
+
+        Charge::new(
+            self.ai_idx(),
+            self.c_idx(),
+            voltage,
+            q_dep + options.tt * current,
+            c_dep + options.tt * conductance,
+        )
     }
 
     pub fn a_idx(&self) -> Option<usize> {
@@ -182,6 +399,19 @@ impl DiodeBundle {
     pub fn c_idx(&self) -> Option<usize> {
         self.cathode.as_ref().map(|v| v.idx())
     }
+
+    /// Returns the index of the internal anode, which is the anode without `rs`.
+    pub fn ai_idx(&self) -> Option<usize> {
+        self.internal_anode
+            .as_ref()
+            .map(|v| v.idx())
+            .or(self.a_idx())
+    }
+
+    /// Returns the index of the internal anode behind the series resistance.
+    pub fn internal_node_index(&self) -> Option<usize> {
+        self.internal_anode.as_ref().map(|v| v.idx())
+    }
 }
 
 #[cfg(test)]
@@ -1,17 +1,27 @@
 /// The Diode - yaml parsing module
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+use log::warn;
 use serde::{Deserialize, Serialize};
 
+use super::{add_internal_node, DiodeOptions};
 use crate::frontends::{get_variable, serde::ProcessSerdeElement};
-use crate::models::{DiodeBundle, Element, Unit};
+use crate::models::{DeviceTemperature, DiodeBundle, Element, Unit};
+use crate::spot::Numeric;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SerdeDiode {
     pub name: String,
     pub anode: String,
     pub cathode: String,
+    /// The model parameters with their SPICE names, like `rs: 10`
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Numeric>,
+    /// Instance temperature in °C
+    pub temp: Option<Numeric>,
+    /// Difference to the circuit temperature in K
+    pub dtemp: Option<Numeric>,
 }
 
 impl ProcessSerdeElement for SerdeDiode {
@@ -21,12 +31,24 @@ impl ProcessSerdeElement for SerdeDiode {
         elements: &mut Vec<Element>,
         var_map: &mut HashMap<std::sync::Arc<str>, usize>,
     ) {
-        let res = DiodeBundle::new(
+        let mut options = DiodeOptions::default();
+        for (parameter, value) in &self.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of {}",
+                    self.name
+                );
+            }
+        }
+
+        let mut res = DiodeBundle::new(
             Arc::from(self.name.as_str()),
             get_variable(self.anode.as_str(), Unit::Volt, variables, var_map),
             get_variable(self.cathode.as_str(), Unit::Volt, variables, var_map),
-            None,
+            Some(options),
         );
+        add_internal_node(&mut res, variables, var_map);
+        res.set_temperature(DeviceTemperature::new(self.temp, self.dtemp));
         elements.push(Element::Diode(res));
     }
 }
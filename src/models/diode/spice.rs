@@ -1,9 +1,13 @@
 use std::sync::Arc;
 
+use log::warn;
+
+use super::{add_internal_node, DiodeOptions};
 use crate::frontends::get_variable;
-use crate::frontends::spice::ProcessSpiceElement;
+use crate::frontends::spice::{ApplySpiceModel, ProcessSpiceElement, SpiceModel};
 use crate::frontends::spice_parser_helpers::SpiceElementParser;
-use crate::models::{DiodeBundle, Element, Unit};
+use crate::frontends::FrontendError;
+use crate::models::{DeviceTemperature, DiodeBundle, Element, Unit};
 
 impl ProcessSpiceElement for DiodeBundle {
     fn process(
@@ -18,15 +22,63 @@ impl ProcessSpiceElement for DiodeBundle {
         let name = parser.parse_name("diode")?;
         let node0 = parser.parse_node("diode", name, "node0")?;
         let node1 = parser.parse_node("diode", name, "node1")?;
+        let breakdown = parser.parse_diode_breakdown(name)?;
+        let model = parser.parse_model();
+
+        let mut temperature = DeviceTemperature::default();
+        for (parameter, value) in parser.parse_named_parameters("diode", name)? {
+            temperature.set_parameter(&parameter, value);
+        }
 
         // Create the diode element
-        let dio = DiodeBundle::new(
+        let options = breakdown.map(|bv| {
+            let mut options = DiodeOptions::default();
+            options.set_parameter("bv", bv);
+            options
+        });
+        let mut dio = DiodeBundle::new(
             Arc::from(name),
             get_variable(node0, Unit::Volt, variables, var_map),
             get_variable(node1, Unit::Volt, variables, var_map),
-            None,
+            options,
         );
+        if let Some(model) = model {
+            dio.set_model(Arc::from(model));
+        }
+        dio.set_temperature(temperature);
         elements.push(Element::Diode(dio));
         Ok(())
     }
 }
+
+impl ApplySpiceModel for DiodeBundle {
+    fn model_name(&self) -> Option<Arc<str>> {
+        self.model()
+    }
+
+    fn apply_model(
+        &mut self,
+        model: &SpiceModel,
+        variables: &mut Vec<crate::models::Variable>,
+        var_map: &mut std::collections::HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError> {
+        if !model.kind.eq_ignore_ascii_case("d") {
+            return Err(FrontendError::InvalidModel {
+                model: model.name.to_string(),
+                reason: format!("Unknown diode model type '{}'. Use D", model.kind),
+            });
+        }
+        let mut options = DiodeOptions::default();
+        for (parameter, value) in &model.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of model {}",
+                    model.name
+                );
+            }
+        }
+        self.set_options(options);
+        add_internal_node(self, variables, var_map);
+        Ok(())
+    }
+}
@@ -73,3 +73,131 @@ fn test_pairs3() {
 
     assert_eq!(diode_bundle.pairs(&[0.7, 0.0]).len(), 1);
 }
+
+#[test]
+fn test_companion_reproduces_current() {
+    let diode_bundle = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(Variable::new(Arc::from("Node0"), Unit::Volt, 0)),
+        None,
+        Some(DiodeOptions::default()),
+    );
+
+    // The linearised current g * v - b equals the Shockley current at the linearisation point
+    let voltage = 0.65;
+    let cond = diode_bundle.triples_at(voltage)[0].2;
+    let rhs = diode_bundle.pairs_at(voltage)[0].1;
+    let expected = 1e-14 * ((voltage / UT).exp() - 1.0) + GMIN * voltage;
+    assert!((cond * voltage - rhs - expected).abs() < 1e-9 * expected);
+}
+
+#[test]
+fn test_limit_voltage() {
+    let diode_bundle = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(Variable::new(Arc::from("Node0"), Unit::Volt, 0)),
+        None,
+        Some(DiodeOptions::default()),
+    );
+
+    let (voltage, limited) = diode_bundle.limit_voltage(50.0, 0.6);
+    assert!(limited);
+    assert!(voltage < 1.0);
+    assert_eq!(diode_bundle.limit_voltage(0.61, 0.6), (0.61, false));
+}
+
+#[test]
+fn test_set_parameter() {
+    let mut options = DiodeOptions::default();
+    assert!(options.set_parameter("RS", 10.0));
+    assert!(options.set_parameter("cjo", 1e-12));
+    assert!(options.set_parameter("BV", 5.1));
+    assert!(!options.set_parameter("kf", 1.0));
+    assert_eq!(options.series_resistance(), 10.0);
+    assert_eq!(
+        options,
+        DiodeOptions::default()
+            .with_series_resistance(10.0)
+            .with_junction_capacitance(1e-12, 1.0, 0.5)
+            .with_breakdown(5.1, 1e-3)
+    );
+}
+
+#[test]
+fn test_series_resistance() {
+    let mut diode_bundle = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(Variable::new(Arc::from("Node0"), Unit::Volt, 0)),
+        None,
+        Some(DiodeOptions::default().with_series_resistance(10.0)),
+    );
+    diode_bundle.set_internal_node(Some(Variable::new(Arc::from("D1#anode"), Unit::Volt, 1)));
+
+    // The junction sits between the internal anode and the cathode
+    assert_eq!(diode_bundle.ai_idx(), Some(1));
+    assert_eq!(diode_bundle.voltage(&[1.0, 0.6]), 0.6);
+    let triples = diode_bundle.triples(&[1.0, 0.6]);
+    assert_eq!(triples.len(), 5);
+    assert!(triples.into_iter().any(|&entry| entry == (0, 1, -0.1)));
+    assert_eq!(diode_bundle.pairs(&[1.0, 0.6])[0].0, 1);
+    assert_eq!(diode_bundle.triple_idx().unwrap().len(), 5);
+}
+
+#[test]
+fn test_breakdown() {
+    let diode_bundle = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(Variable::new(Arc::from("Node0"), Unit::Volt, 0)),
+        None,
+        Some(DiodeOptions::default().with_breakdown(5.0, 1e-3)),
+    );
+
+    // The reverse current is ibv at -bv and rises by a decade every n * UT * ln(10)
+    let current = |voltage: Numeric| {
+        let (cond, rhs) = (
+            diode_bundle.triples_at(voltage)[0].2,
+            diode_bundle.pairs_at(voltage)[0].1,
+        );
+        cond * voltage - rhs
+    };
+    assert!((current(-5.0) + 1e-3).abs() < 1e-6);
+    assert!((current(-5.0 - UT * Numeric::ln(10.0)) + 1e-2).abs() < 1e-5);
+    assert!(current(-4.0).abs() < 1e-9);
+    assert!(current(0.0).abs() < 1e-20);
+    assert_eq!(diode_bundle.region(-5.0), "breakdown");
+    assert_eq!(diode_bundle.region(-4.0), "reverse biased");
+
+    // The conductance is the derivative of the current
+    let h = 1e-7;
+    for voltage in [-5.1, -4.9, 0.6] {
+        let numeric = (current(voltage + h) - current(voltage - h)) / (2.0 * h);
+        let cond = diode_bundle.triples_at(voltage)[0].2;
+        assert!((cond - numeric).abs() < 1e-5 * numeric.abs() + 1e-12);
+    }
+
+    // Newton steps deep into breakdown are limited like forward steps
+    let (voltage, limited) = diode_bundle.limit_voltage(-50.0, -5.0);
+    assert!(limited);
+    assert!(voltage > -6.0);
+}
+
+#[test]
+fn test_ac_triples() {
+    let diode_bundle = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(Variable::new(Arc::from("Node0"), Unit::Volt, 0)),
+        None,
+        Some(DiodeOptions::default().with_junction_capacitance(1e-12, 1.0, 0.5)),
+    );
+
+    // A reverse biased junction is almost purely capacitive
+    let x_vec = [-3.0];
+    let triples = diode_bundle.triples(&x_vec);
+    let ac_triples = diode_bundle.ac_triples(1e6, &x_vec);
+    let capacitance = 1e-12 / (1.0 as Numeric + 3.0).sqrt();
+
+    assert_eq!(ac_triples.len(), 1);
+    assert_eq!(ac_triples[0].2.re, triples[0].2);
+    let expected = 2.0 * Numeric::PI() * 1e6 * capacitance;
+    assert!((ac_triples[0].2.im - expected).abs() < 1e-12 * expected);
+}
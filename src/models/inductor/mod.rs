@@ -8,19 +8,24 @@ use std::sync::Arc;
 use num::traits::FloatConst;
 use num::{Complex, One, Zero};
 
+use super::integration::{IntegrationMethod, StateHistory};
 use super::*;
 use crate::spot::*;
 
 /// A structure representing a bundle of inductors.
+///
+/// The current through the inductor is an unknown of its own, flowing from `node0` to `node1`.
+/// In operating points the inductor shorts its nodes and the branch carries the DC current, in
+/// transient analyses the branch equation is the companion model of the flux.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct InductorBundle {
     pub name: Arc<str>,
+    pub branch: Variable,
     pub node0: Option<Variable>,
     pub node1: Option<Variable>,
     pub value: Numeric,
-    /// Previous current through the inductor for transient simulation
-    /// This stores the current from the last time step for proper integration
-    pub previous_current: Numeric,
+    /// Optional initial current through the inductor (`IC=`), used for transient analysis
+    pub initial_condition: Option<Numeric>,
 }
 
 impl InductorBundle {
@@ -29,6 +34,7 @@ impl InductorBundle {
     /// # Arguments
     ///
     /// * `name` - The name of the inductor bundle.
+    /// * `branch` - The current through the inductor.
     /// * `node0` - The first node of the inductor.
     /// * `node1` - The second node of the inductor.
     /// * `value` - The value of the inductor.
@@ -38,85 +44,101 @@ impl InductorBundle {
     /// A new `InductorBundle` object.
     pub fn new(
         name: Arc<str>,
+        branch: Variable,
         node0: Option<Variable>,
         node1: Option<Variable>,
         value: Numeric,
     ) -> InductorBundle {
         InductorBundle {
             name,
+            branch,
             node0,
             node1,
             value,
-            previous_current: Numeric::zero(), // Initialize to 0A
+            initial_condition: None,
         }
     }
 
+    /// Sets the initial current through the inductor.
+    pub fn set_initial_condition(&mut self, current: Option<Numeric>) {
+        self.initial_condition = current;
+    }
+
+    /// Returns the initial current through the inductor, if one was given.
+    pub fn initial_condition(&self) -> Option<Numeric> {
+        self.initial_condition
+    }
+
     /// Returns the name of the inductor bundle.
     pub fn name(&self) -> Arc<str> {
         self.name.clone()
     }
 
-    /// Updates the previous current for transient simulation
-    /// This should be called after each time step with the current current
-    pub fn update_previous_current(&mut self, current: Numeric) {
-        self.previous_current = current;
+    /// Returns the magnetic flux of the inductor carrying `current`.
+    pub fn flux(&self, current: Numeric) -> Numeric {
+        self.value * current
     }
 
-    /// Returns the previous current through the inductor
-    pub fn previous_current(&self) -> Numeric {
-        self.previous_current
+    /// Returns the index of the branch current.
+    pub fn branch_idx(&self) -> usize {
+        self.branch.idx()
     }
 
-    /// Returns the triples representing the inductor's contribution to matrix A.
-    /// If `delta_t` is provided, the transient resistance is calculated using Euler integration.
+    /// Returns the triples connecting the branch current to the nodes.
+    /// Without further stamps the branch equation `v0 - v1 = 0` shorts the inductor, as in DC.
+    pub fn triples(&self) -> Triples<Numeric, 4> {
+        let branch_idx = self.branch_idx();
+        match (self.node0_idx(), self.node1_idx()) {
+            (None, Some(node1_idx)) => Triples::new(&[
+                (branch_idx, node1_idx, -Numeric::one()),
+                (node1_idx, branch_idx, -Numeric::one()),
+            ]),
+            (Some(node0_idx), None) => Triples::new(&[
+                (branch_idx, node0_idx, Numeric::one()),
+                (node0_idx, branch_idx, Numeric::one()),
+            ]),
+            (Some(node0_idx), Some(node1_idx)) => Triples::new(&[
+                (branch_idx, node0_idx, Numeric::one()),
+                (node0_idx, branch_idx, Numeric::one()),
+                (branch_idx, node1_idx, -Numeric::one()),
+                (node1_idx, branch_idx, -Numeric::one()),
+            ]),
+            (None, None) => Triples::new(&[]),
+        }
+    }
+
+    /// Returns the transient triple of the branch equation.
+    /// The integration method approximates the flux: v = dphi/dt = a0 * L * i + h,
+    /// so the branch equation reads v0 - v1 - a0 * L * i = h.
     ///
     /// # Arguments
     ///
-    /// * `delta_t` - Optional time step for transient simulation.
-    pub fn triples(&self, delta_t: Option<&Numeric>) -> Triples<Numeric, 4> {
-        // Äquivalenter Leitwert für die Induktivität
-        let equivalent_conductance = match delta_t {
-            Some(dt) => dt / self.value, // L / delta_t → Leitwert = delta_t / L
-            None => DEFAULT_CONDUCTANCE, // Standardwert für DC/AC-Analyse
-        };
-
-        let node0_idx = if let Some(node) = &self.node0 {
-            node.idx()
-        } else {
-            // If node0 doesn't exist, inductor is connected to ground through node1
-            let node1_idx = self
-                .node1
-                .as_ref()
-                .expect("Inductor must have at least one node connected")
-                .idx();
-            return Triples::new(&[(node1_idx, node1_idx, equivalent_conductance)]);
-        };
-
-        let node1_idx = if let Some(node) = &self.node1 {
-            node.idx()
-        } else {
-            return Triples::new(&[(node0_idx, node0_idx, equivalent_conductance)]);
-        };
-
-        Triples::new(&[
-            (node0_idx, node0_idx, equivalent_conductance),
-            (node1_idx, node1_idx, equivalent_conductance),
-            (node0_idx, node1_idx, -equivalent_conductance),
-            (node1_idx, node0_idx, -equivalent_conductance),
-        ])
+    /// * `integrator` - The integration method of the time step.
+    pub fn transient_triples(&self, integrator: &dyn IntegrationMethod) -> Triples<Numeric, 4> {
+        let branch_idx = self.branch_idx();
+        Triples::new(&[(
+            branch_idx,
+            branch_idx,
+            -integrator.derivative_coefficient() * self.value,
+        )])
     }
 
     /// Returns the triples indices.
-    pub fn triple_idx(&self) -> Option<TripleIdx<4>> {
+    pub fn triple_idx(&self) -> Option<TripleIdx<5>> {
+        let branch_idx = self.branch_idx();
         match (self.node0_idx(), self.node1_idx()) {
             (None, None) => None,
-            (None, Some(idx_1)) => Some(TripleIdx::new(&[(idx_1, idx_1)])),
-            (Some(idx_0), None) => Some(TripleIdx::new(&[(idx_0, idx_0)])),
+            (None, Some(idx)) | (Some(idx), None) => Some(TripleIdx::new(&[
+                (branch_idx, idx),
+                (idx, branch_idx),
+                (branch_idx, branch_idx),
+            ])),
             (Some(idx_0), Some(idx_1)) => Some(TripleIdx::new(&[
-                (idx_0, idx_0),
-                (idx_1, idx_1),
-                (idx_0, idx_1),
-                (idx_1, idx_0),
+                (branch_idx, idx_0),
+                (idx_0, branch_idx),
+                (branch_idx, idx_1),
+                (idx_1, branch_idx),
+                (branch_idx, branch_idx),
             ])),
         }
     }
@@ -131,143 +153,50 @@ impl InductorBundle {
         self.node1.as_ref().map(|v| v.idx())
     }
 
-    /// Returns the pairs representing the right-hand side (RHS) for transient simulation
-    /// This implements the backward Euler integration: v = L * (i_current - i_prev) / Δt
-    /// Which rearranges to: (L/Δt)*i_current - (L/Δt)*i_prev = v
-    /// In MNA, this becomes part of the RHS vector as: b = (L/Δt) * i_prev
-    pub fn pairs(&self, delta_t: &Numeric) -> Pairs<Numeric, 2> {
-        let r = delta_t / self.value; // Equivalent resistance = Δt/L
-        let i_prev = self.previous_current;
-
-        let node0_idx = if let Some(idx) = self.node0_idx() {
-            idx
-        } else {
-            // If node0 doesn't exist, inductor is connected to ground through node1
-            let node1_idx = self
-                .node1_idx()
-                .expect("Inductor must have at least one node connected");
-            return Pairs::new(&[(node1_idx, r * i_prev)]);
-        };
-        let node1_idx = if let Some(idx) = self.node1_idx() {
-            idx
-        } else {
-            return Pairs::new(&[(node0_idx, -r * i_prev)]);
-        };
-
-        // The RHS represents the voltage contribution from the previous time step
-        // For backward Euler: v = L * (i_current - i_prev) / Δt
-        // Rearranged: L*i_current/Δt - L*i_prev/Δt = v
-        // In MNA, the RHS should be: (L/Δt) * i_prev (voltage drop across the inductor)
-        Pairs::new(&[(node0_idx, r * i_prev), (node1_idx, -r * i_prev)])
-    }
-
-    /// Returns the pairs representing the right-hand side (RHS) for transient simulation using trapezoidal integration
-    /// This implements the trapezoidal rule: v = L * (i_current - i_prev) / (Δt/2)
-    /// Which rearranges to: (2L/Δt)*i_current - (2L/Δt)*i_prev = v
-    /// In MNA, this becomes part of the RHS vector as: b = (2L/Δt) * i_prev
-    pub fn pairs_trapezoidal(&self, delta_t: &Numeric) -> Pairs<Numeric, 2> {
-        let r = (delta_t * 2.0) / self.value; // Equivalent resistance for trapezoidal rule = 2Δt/L
-        let i_prev = self.previous_current;
-
-        let node0_idx = if let Some(idx) = self.node0_idx() {
-            idx
-        } else {
-            // If node0 doesn't exist, inductor is connected to ground through node1
-            let node1_idx = self
-                .node1_idx()
-                .expect("Inductor must have at least one node connected");
-            return Pairs::new(&[(node1_idx, r * i_prev)]);
-        };
-        let node1_idx = if let Some(idx) = self.node1_idx() {
-            idx
-        } else {
-            return Pairs::new(&[(node0_idx, -r * i_prev)]);
-        };
-
-        // The RHS represents the voltage contribution from the previous time step
-        // For trapezoidal rule: v = L * (i_current - i_prev) / (Δt/2)
-        // Rearranged: (2L/Δt)*i_current - (2L/Δt)*i_prev = v
-        // In MNA, the RHS should be: (2L/Δt) * i_prev (voltage drop across the inductor)
-        Pairs::new(&[(node0_idx, r * i_prev), (node1_idx, -r * i_prev)])
+    /// Returns the pair representing the right-hand side (RHS) for transient simulation,
+    /// the history term `h` of the flux derivative in the branch equation.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - The flux history of the inductor.
+    /// * `integrator` - The integration method of the time step.
+    pub fn pairs(
+        &self,
+        history: &StateHistory,
+        integrator: &dyn IntegrationMethod,
+    ) -> Pairs<Numeric, 2> {
+        Pairs::new(&[(self.branch_idx(), integrator.derivative_history(history))])
     }
 
     /// Returns the triples representing the inductor's contribution to matrix A.
-    pub fn ac_triples(&self, freq: Numeric) -> Triples<ComplexNumeric, 4> {
-        // TODO: Consider refactoring to reduce nesting complexity
-        let node0_idx = if let Some(node) = &self.node0 {
-            node.idx()
-        } else {
-            // If node0 doesn't exist, inductor is connected to ground through node1
-            let node1_idx = self
-                .node1
-                .as_ref()
-                .expect("Inductor must have at least one node connected")
-                .idx();
-            return Triples::new(&[(
-                node1_idx,
-                node1_idx,
-                Complex {
-                    re: Numeric::zero(),
-                    im: Numeric::one()
-                        / ((Numeric::one() + Numeric::one()) * Numeric::PI() * freq * self.value),
-                },
-            )]);
-        };
-
-        // TODO: Consider refactoring to reduce nesting complexity
-        let node1_idx = if let Some(node) = &self.node1 {
-            node.idx()
-        } else {
-            return Triples::new(&[(
-                node0_idx,
-                node0_idx,
-                Complex {
-                    re: Numeric::zero(),
-                    im: Numeric::one()
-                        / ((Numeric::one() + Numeric::one()) * Numeric::PI() * freq * self.value),
-                },
-            )]);
+    /// The branch equation is v0 - v1 - jωL * i = 0.
+    pub fn ac_triples(&self, freq: Numeric) -> Triples<ComplexNumeric, 5> {
+        let one = Complex::one();
+        let branch_idx = self.branch_idx();
+        let impedance = Complex {
+            re: Numeric::zero(),
+            im: (Numeric::one() + Numeric::one()) * Numeric::PI() * freq * self.value,
         };
-
-        // TODO: Consider refactoring to reduce nesting complexity
-        Triples::new(&[
-            (
-                node0_idx,
-                node0_idx,
-                Complex {
-                    re: Numeric::zero(),
-                    im: Numeric::one()
-                        / ((Numeric::one() + Numeric::one()) * Numeric::PI() * freq * self.value),
-                },
-            ),
-            (
-                node1_idx,
-                node1_idx,
-                Complex {
-                    re: Numeric::zero(),
-                    im: Numeric::one()
-                        / ((Numeric::one() + Numeric::one()) * Numeric::PI() * freq * self.value),
-                },
-            ),
-            (
-                node0_idx,
-                node1_idx,
-                -Complex {
-                    re: Numeric::zero(),
-                    im: Numeric::one()
-                        / ((Numeric::one() + Numeric::one()) * Numeric::PI() * freq * self.value),
-                },
-            ),
-            (
-                node1_idx,
-                node0_idx,
-                -Complex {
-                    re: Numeric::zero(),
-                    im: Numeric::one()
-                        / ((Numeric::one() + Numeric::one()) * Numeric::PI() * freq * self.value),
-                },
-            ),
-        ])
+        match (self.node0_idx(), self.node1_idx()) {
+            (None, Some(node1_idx)) => Triples::new(&[
+                (branch_idx, node1_idx, -one),
+                (node1_idx, branch_idx, -one),
+                (branch_idx, branch_idx, -impedance),
+            ]),
+            (Some(node0_idx), None) => Triples::new(&[
+                (branch_idx, node0_idx, one),
+                (node0_idx, branch_idx, one),
+                (branch_idx, branch_idx, -impedance),
+            ]),
+            (Some(node0_idx), Some(node1_idx)) => Triples::new(&[
+                (branch_idx, node0_idx, one),
+                (node0_idx, branch_idx, one),
+                (branch_idx, node1_idx, -one),
+                (node1_idx, branch_idx, -one),
+                (branch_idx, branch_idx, -impedance),
+            ]),
+            (None, None) => Triples::new(&[]),
+        }
     }
 }
 
@@ -12,6 +12,7 @@ pub struct SerdeInductor {
     pub node0: String,
     pub node1: String,
     pub value: Numeric,
+    pub ic: Option<Numeric>,
 }
 
 impl ProcessSerdeElement for SerdeInductor {
@@ -21,12 +22,17 @@ impl ProcessSerdeElement for SerdeInductor {
         elements: &mut Vec<Element>,
         var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
     ) {
-        let res = InductorBundle::new(
+        let node0 = get_variable(self.node0.as_str(), Unit::Volt, variables, var_map);
+        let node1 = get_variable(self.node1.as_str(), Unit::Volt, variables, var_map);
+        let branch = format!("{}#branch", self.name);
+        let mut res = InductorBundle::new(
             Arc::from(self.name.as_str()),
-            get_variable(self.node0.as_str(), Unit::Volt, variables, var_map),
-            get_variable(self.node1.as_str(), Unit::Volt, variables, var_map),
+            get_variable(&branch, Unit::Ampere, variables, var_map).unwrap(),
+            node0,
+            node1,
             self.value,
         );
+        res.set_initial_condition(self.ic);
         elements.push(Element::Inductor(res));
     }
 }
@@ -1,6 +1,7 @@
 use crate::frontends::get_variable;
 use crate::frontends::spice::ProcessSpiceElement;
 use crate::frontends::spice_parser_helpers::SpiceElementParser;
+use crate::frontends::FrontendError;
 use crate::models::{Element, InductorBundle, Unit};
 
 use std::sync::Arc;
@@ -11,7 +12,7 @@ impl ProcessSpiceElement for InductorBundle {
         variables: &mut Vec<crate::models::Variable>,
         elements: &mut Vec<crate::models::Element>,
         var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
-    ) -> Result<(), crate::frontends::FrontendError> {
+    ) -> Result<(), FrontendError> {
         // Use the helper parser for common parsing logic
         let mut parser = SpiceElementParser::new(element);
 
@@ -20,14 +21,21 @@ impl ProcessSpiceElement for InductorBundle {
         let node0 = parser.parse_node("inductor", name, "node0")?;
         let node1 = parser.parse_node("inductor", name, "node1")?;
         let value = parser.parse_value("inductor", name, "value")?;
+        let initial_condition = parser.parse_optional_parameter("inductor", name, "ic")?;
+
+        let node0 = get_variable(node0, Unit::Volt, variables, var_map);
+        let node1 = get_variable(node1, Unit::Volt, variables, var_map);
+        let branch = get_variable(&format!("{name}#branch"), Unit::Ampere, variables, var_map)
+            .ok_or_else(|| {
+                FrontendError::ParseError(format!(
+                    "Failed to create branch variable for inductor: {}",
+                    name
+                ))
+            })?;
 
         // Create the inductor element
-        let ind = InductorBundle::new(
-            Arc::from(name),
-            get_variable(node0, Unit::Volt, variables, var_map),
-            get_variable(node1, Unit::Volt, variables, var_map),
-            value,
-        );
+        let mut ind = InductorBundle::new(Arc::from(name), branch, node0, node1, value);
+        ind.set_initial_condition(initial_condition);
         elements.push(Element::Inductor(ind));
         Ok(())
     }
@@ -1,6 +1,6 @@
 // Integration method traits and implementations
 // This module provides a trait-based abstraction for different integration methods
-// used in transient simulation (e.g., Backward Euler, Forward Euler, Trapezoidal)
+// used in transient simulation (e.g., Backward Euler, Forward Euler, Trapezoidal, Gear)
 // 
 // The integration methods are implemented as a separate trait system rather than being
 // generic parameters to models. This design choice provides several advantages:
@@ -15,9 +15,43 @@
 
 use crate::spot::Numeric;
 
+/// The history of a state variable (e.g. the voltage across a capacitor or the current through
+/// an inductor) as required by the integration methods.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct StateHistory {
+    /// The state at the previous time point
+    pub x1: Numeric,
+    /// The state at the time point before the previous one
+    pub x2: Numeric,
+    /// The derivative of the state at the previous time point
+    pub dxdt1: Numeric,
+    /// The voltage across a charge at the previous time point
+    pub v1: Numeric,
+}
+
+impl StateHistory {
+    /// Creates a history of a state which has been constant so far
+    pub fn new(x: Numeric) -> Self {
+        Self {
+            x1: x,
+            x2: x,
+            dxdt1: 0.0,
+            v1: 0.0,
+        }
+    }
+
+    /// Shifts the history by one time point
+    pub fn push(&mut self, x: Numeric, dxdt: Numeric) {
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.dxdt1 = dxdt;
+    }
+}
+
 /// Trait for integration methods used in transient simulation
-/// This trait abstracts the mathematical operations needed for different
-/// numerical integration methods
+/// Every method approximates the derivative of a state as `dx/dt = a0 * x + h`,
+/// where `a0` is the derivative coefficient and `h` the history term of the previous states.
+/// All reactive elements derive their companion models from these coefficients.
 pub trait IntegrationMethod {
     /// Returns the name of the integration method
     fn name(&self) -> &str;
@@ -25,24 +59,42 @@ pub trait IntegrationMethod {
     /// Returns the time step size
     fn delta_t(&self) -> Numeric;
 
+    /// Returns the coefficient `a0` of the current state in the derivative approximation
+    fn derivative_coefficient(&self) -> Numeric;
+
+    /// Returns the history term `h` of the derivative approximation
+    fn derivative_history(&self, history: &StateHistory) -> Numeric;
+
+    /// Returns the approximated derivative of the state `x`
+    fn derivative(&self, x: Numeric, history: &StateHistory) -> Numeric {
+        self.derivative_coefficient() * x + self.derivative_history(history)
+    }
+
     /// Calculates the equivalent conductance for a capacitor
-    /// For a capacitor: I = C * dV/dt
-    /// Different integration methods transform this into different equivalent conductances
-    fn capacitor_equivalent_conductance(&self, capacitance: Numeric) -> Numeric;
+    /// For a capacitor: I = C * dV/dt = C * a0 * V + C * h
+    fn capacitor_equivalent_conductance(&self, capacitance: Numeric) -> Numeric {
+        capacitance * self.derivative_coefficient()
+    }
 
     /// Calculates the equivalent conductance for an inductor
-    /// For an inductor: V = L * dI/dt
-    /// Different integration methods transform this into different equivalent conductances
-    fn inductor_equivalent_conductance(&self, inductance: Numeric) -> Numeric;
+    /// For an inductor: V = L * dI/dt = L * a0 * I + L * h
+    fn inductor_equivalent_conductance(&self, inductance: Numeric) -> Numeric {
+        1.0 / (inductance * self.derivative_coefficient())
+    }
 
-    /// Calculates the history current source for a capacitor
-    /// This represents the contribution from previous time steps
-    fn capacitor_history_current(&self, capacitance: Numeric, previous_voltage: Numeric)
-        -> Numeric;
+    /// Calculates the history current of a capacitor due to its previous voltage
+    fn capacitor_history_current(
+        &self,
+        capacitance: Numeric,
+        previous_voltage: Numeric,
+    ) -> Numeric {
+        self.capacitor_equivalent_conductance(capacitance) * previous_voltage
+    }
 
-    /// Calculates the history current source for an inductor
-    /// This represents the contribution from previous time steps
-    fn inductor_history_current(&self, inductance: Numeric, previous_current: Numeric) -> Numeric;
+    /// Calculates the history current of an inductor due to its previous current
+    fn inductor_history_current(&self, _inductance: Numeric, previous_current: Numeric) -> Numeric {
+        previous_current
+    }
 
     /// Calculates the equivalent conductance adjustment for nonlinear elements
     /// This is a placeholder for future extensions (e.g., MOSFETs with charge conservation)
@@ -79,28 +131,13 @@ impl IntegrationMethod for BackwardEuler {
         self.delta_t
     }
 
-    fn capacitor_equivalent_conductance(&self, capacitance: Numeric) -> Numeric {
-        // For Backward Euler: G_eq = C / delta_t
-        capacitance / self.delta_t
+    fn derivative_coefficient(&self) -> Numeric {
+        // dx/dt = (x - x1) / delta_t
+        1.0 / self.delta_t
     }
 
-    fn inductor_equivalent_conductance(&self, inductance: Numeric) -> Numeric {
-        // For Backward Euler: G_eq = delta_t / L
-        self.delta_t / inductance
-    }
-
-    fn capacitor_history_current(
-        &self,
-        capacitance: Numeric,
-        previous_voltage: Numeric,
-    ) -> Numeric {
-        // For Backward Euler: I_hist = (C / delta_t) * V_prev
-        (capacitance / self.delta_t) * previous_voltage
-    }
-
-    fn inductor_history_current(&self, inductance: Numeric, previous_current: Numeric) -> Numeric {
-        // For Backward Euler: I_hist = (delta_t / L) * I_prev
-        (self.delta_t / inductance) * previous_current
+    fn derivative_history(&self, history: &StateHistory) -> Numeric {
+        -history.x1 / self.delta_t
     }
 
     fn is_implicit(&self) -> bool {
@@ -132,28 +169,13 @@ impl IntegrationMethod for ForwardEuler {
         self.delta_t
     }
 
-    fn capacitor_equivalent_conductance(&self, capacitance: Numeric) -> Numeric {
-        // For Forward Euler: G_eq = C / delta_t (same as Backward Euler for capacitors)
-        capacitance / self.delta_t
-    }
-
-    fn inductor_equivalent_conductance(&self, inductance: Numeric) -> Numeric {
-        // For Forward Euler: G_eq = delta_t / L (same as Backward Euler for inductors)
-        self.delta_t / inductance
-    }
-
-    fn capacitor_history_current(
-        &self,
-        capacitance: Numeric,
-        previous_voltage: Numeric,
-    ) -> Numeric {
-        // For Forward Euler: I_hist = (C / delta_t) * V_prev (same as Backward Euler)
-        (capacitance / self.delta_t) * previous_voltage
+    fn derivative_coefficient(&self) -> Numeric {
+        // The companion model is the same as for Backward Euler
+        1.0 / self.delta_t
     }
 
-    fn inductor_history_current(&self, inductance: Numeric, previous_current: Numeric) -> Numeric {
-        // For Forward Euler: I_hist = (delta_t / L) * I_prev (same as Backward Euler)
-        (self.delta_t / inductance) * previous_current
+    fn derivative_history(&self, history: &StateHistory) -> Numeric {
+        -history.x1 / self.delta_t
     }
 
     fn is_implicit(&self) -> bool {
@@ -185,28 +207,70 @@ impl IntegrationMethod for Trapezoidal {
         self.delta_t
     }
 
-    fn capacitor_equivalent_conductance(&self, capacitance: Numeric) -> Numeric {
-        // For Trapezoidal: G_eq = (2C) / delta_t
-        (2.0 * capacitance) / self.delta_t
+    fn derivative_coefficient(&self) -> Numeric {
+        // (dx/dt + dx1/dt) / 2 = (x - x1) / delta_t
+        2.0 / self.delta_t
     }
 
-    fn inductor_equivalent_conductance(&self, inductance: Numeric) -> Numeric {
-        // For Trapezoidal: G_eq = (2 * delta_t) / L
-        (2.0 * self.delta_t) / inductance
+    fn derivative_history(&self, history: &StateHistory) -> Numeric {
+        -2.0 * history.x1 / self.delta_t - history.dxdt1
     }
 
-    fn capacitor_history_current(
-        &self,
-        capacitance: Numeric,
-        previous_voltage: Numeric,
-    ) -> Numeric {
-        // For Trapezoidal: I_hist = (2C / delta_t) * V_prev
-        (2.0 * capacitance / self.delta_t) * previous_voltage
+    fn is_implicit(&self) -> bool {
+        true
+    }
+}
+
+/// Second order backward differentiation formula (Gear)
+/// This is an implicit, L-stable method which damps the ringing of the trapezoidal rule
+/// The coefficients account for a change of the time step between the last two steps
+#[derive(Debug, Clone)]
+pub struct Bdf2 {
+    delta_t: Numeric,
+    previous_delta_t: Numeric,
+}
+
+impl Bdf2 {
+    /// Creates a new BDF2 integrator with the given and the previous time step
+    /// Without a previous time step (e.g. the first step) it falls back to Backward Euler
+    pub fn new(delta_t: Numeric, previous_delta_t: Numeric) -> Self {
+        Self {
+            delta_t,
+            previous_delta_t,
+        }
+    }
+
+    /// Returns the ratio of the current to the previous time step
+    fn ratio(&self) -> Option<Numeric> {
+        (self.previous_delta_t > 0.0).then(|| self.delta_t / self.previous_delta_t)
     }
+}
 
-    fn inductor_history_current(&self, inductance: Numeric, previous_current: Numeric) -> Numeric {
-        // For Trapezoidal: I_hist = (2 * delta_t / L) * I_prev
-        (2.0 * self.delta_t / inductance) * previous_current
+impl IntegrationMethod for Bdf2 {
+    fn name(&self) -> &str {
+        "Gear"
+    }
+
+    fn delta_t(&self) -> Numeric {
+        self.delta_t
+    }
+
+    fn derivative_coefficient(&self) -> Numeric {
+        match self.ratio() {
+            Some(rho) => (1.0 + 2.0 * rho) / (self.delta_t * (1.0 + rho)),
+            None => 1.0 / self.delta_t,
+        }
+    }
+
+    fn derivative_history(&self, history: &StateHistory) -> Numeric {
+        match self.ratio() {
+            Some(rho) => {
+                let a1 = -(1.0 + rho) / self.delta_t;
+                let a2 = rho * rho / (self.delta_t * (1.0 + rho));
+                a1 * history.x1 + a2 * history.x2
+            }
+            None => -history.x1 / self.delta_t,
+        }
     }
 
     fn is_implicit(&self) -> bool {
@@ -214,10 +278,21 @@ impl IntegrationMethod for Trapezoidal {
     }
 }
 
+/// The fraction of a TR-BDF2 step which is integrated with the trapezoidal rule
+pub const TR_BDF2_GAMMA: Numeric = 2.0 - std::f64::consts::SQRT_2;
+
+/// Splits a TR-BDF2 step into its two stages:
+/// A trapezoidal step over `gamma * delta_t`, followed by a BDF2 step over the rest
+pub fn tr_bdf2_stages(delta_t: Numeric) -> (Trapezoidal, Bdf2) {
+    let trapezoidal_step = TR_BDF2_GAMMA * delta_t;
+    (
+        Trapezoidal::new(trapezoidal_step),
+        Bdf2::new(delta_t - trapezoidal_step, trapezoidal_step),
+    )
+}
+
 /// Default integration method used when none is specified
 /// This is typically Backward Euler for stability
 pub fn default_integration_method() -> impl IntegrationMethod {
     BackwardEuler::new(1e-6) // Default time step
 }
-
-
@@ -48,6 +48,16 @@ impl ISourceBundle {
         self.name.clone()
     }
 
+    /// Returns the index of node0 if it exists.
+    pub fn node0_idx(&self) -> Option<usize> {
+        self.node0.as_ref().map(|v| v.idx())
+    }
+
+    /// Returns the index of node1 if it exists.
+    pub fn node1_idx(&self) -> Option<usize> {
+        self.node1.as_ref().map(|v| v.idx())
+    }
+
     /// Returns the pair representing the current source contributions to the vector b.
     pub fn pairs(&self) -> Pairs<Numeric, 2> {
         match (&self.node0, &self.node1) {
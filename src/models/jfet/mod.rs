@@ -0,0 +1,35 @@
+pub(crate) mod serde;
+/// The JFET Module. As every module this module encapsulates exerything regarding a JFET bundle
+/// This includes parsing from various formats as well as the conductance-behaviour.
+mod shichman_hodges;
+pub(crate) mod spice;
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::frontends::get_internal_variable;
+use crate::models::Variable;
+use crate::spot::Numeric;
+
+pub use shichman_hodges::{JfetBundle, JfetOptions, JfetPolarity};
+
+/// Creates the internal nodes behind the non-zero drain and source resistances of the transistor.
+pub(crate) fn add_internal_nodes(
+    jfet: &mut JfetBundle,
+    variables: &mut Vec<Variable>,
+    var_map: &mut HashMap<Arc<str>, usize>,
+) {
+    let name = jfet.name();
+    let [rd, rs] = jfet.options().resistances();
+    let mut internal = |resistance: Numeric, node: &str| {
+        (resistance > 0.0)
+            .then(|| get_internal_variable(&name, node, variables, var_map))
+            .flatten()
+    };
+    let drain = internal(rd, "drain");
+    let source = internal(rs, "source");
+    jfet.set_internal_nodes(drain, source);
+}
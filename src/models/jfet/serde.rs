@@ -0,0 +1,59 @@
+/// The JFET - yaml parsing module
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::{add_internal_nodes, JfetOptions, JfetPolarity};
+use crate::frontends::{get_variable, serde::ProcessSerdeElement};
+use crate::models::{DeviceTemperature, Element, JfetBundle, Unit};
+use crate::spot::Numeric;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SerdeJfet {
+    pub name: String,
+    pub drain: String,
+    pub gate: String,
+    pub source: String,
+    /// `njf` or `pjf`, N-channel by default
+    #[serde(default)]
+    pub polarity: JfetPolarity,
+    /// The Shichman-Hodges parameters with their SPICE names, like `vto: -2`
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Numeric>,
+    /// Instance temperature in °C
+    pub temp: Option<Numeric>,
+    /// Difference to the circuit temperature in K
+    pub dtemp: Option<Numeric>,
+}
+
+impl ProcessSerdeElement for SerdeJfet {
+    fn process(
+        &self,
+        variables: &mut Vec<crate::models::Variable>,
+        elements: &mut Vec<Element>,
+        var_map: &mut HashMap<std::sync::Arc<str>, usize>,
+    ) {
+        let mut options = JfetOptions::default().with_polarity(self.polarity);
+        for (parameter, value) in &self.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of {}",
+                    self.name
+                );
+            }
+        }
+
+        let mut jfet = JfetBundle::new(
+            Arc::from(self.name.as_str()),
+            get_variable(self.drain.as_str(), Unit::Volt, variables, var_map),
+            get_variable(self.gate.as_str(), Unit::Volt, variables, var_map),
+            get_variable(self.source.as_str(), Unit::Volt, variables, var_map),
+            Some(options),
+        );
+        add_internal_nodes(&mut jfet, variables, var_map);
+        jfet.set_temperature(DeviceTemperature::new(self.temp, self.dtemp));
+        elements.push(Element::Jfet(jfet));
+    }
+}
@@ -0,0 +1,607 @@
+use std::ops::Neg;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use num::traits::FloatConst;
+use num::{One, Zero};
+use serde::{Deserialize, Serialize};
+
+use super::super::charge::{junction_charge, voltage_between, Charge};
+use super::super::limiting;
+use super::super::temperature::{self, DeviceTemperature};
+use super::super::*;
+use crate::sim::options::SimulatorOptions;
+
+/// The rows and columns of the intrinsic transistor in its conductance matrix.
+const DRAIN: usize = 0;
+const GATE: usize = 1;
+const SOURCE: usize = 2;
+
+/// The grading coefficient of the gate junctions, which is fixed in the SPICE JFET model.
+const GRADING: Numeric = 0.5;
+
+/// A structure representing a junction field-effect transistor with the Shichman-Hodges model.
+///
+/// The channel and the gate junctions sit between the internal nodes behind the drain and
+/// source resistances. Without a resistance the internal node is the terminal itself.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct JfetBundle {
+    name: Arc<str>,
+    drain: Option<Variable>,
+    gate: Option<Variable>,
+    source: Option<Variable>,
+    /// The internal drain node behind `rd`
+    internal_drain: Option<Variable>,
+    /// The internal source node behind `rs`
+    internal_source: Option<Variable>,
+    /// The name of the `.model` card of the transistor
+    model: Option<Arc<str>>,
+    options: JfetOptions,
+    temperature: DeviceTemperature,
+    /// The threshold voltage at the temperature of the transistor
+    vto: Numeric,
+    /// The transconductance parameter at the temperature of the transistor
+    beta: Numeric,
+    /// The thermal voltage at the temperature of the transistor
+    ut: Numeric,
+    /// The gate saturation current at the temperature of the transistor
+    is: Numeric,
+    /// The conductance in parallel to the gate junctions
+    gmin: Numeric,
+}
+
+/// The polarity of a JFET.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JfetPolarity {
+    #[default]
+    Njf,
+    Pjf,
+}
+
+impl JfetPolarity {
+    /// Returns the sign of the terminal voltages and currents of an N-channel transistor.
+    fn sign(self) -> Numeric {
+        match self {
+            JfetPolarity::Njf => 1.0,
+            JfetPolarity::Pjf => -1.0,
+        }
+    }
+}
+
+impl FromStr for JfetPolarity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "njf" => Ok(JfetPolarity::Njf),
+            "pjf" => Ok(JfetPolarity::Pjf),
+            _ => Err(format!("Unknown JFET model type '{s}'. Use NJF or PJF")),
+        }
+    }
+}
+
+/// A struct representing the parameters of a JFET, as in a SPICE `.model` card.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct JfetOptions {
+    polarity: JfetPolarity,
+    /// Threshold (pinch-off) voltage, negative for a depletion N-channel transistor
+    vto: Numeric,
+    /// Transconductance parameter in A/V²
+    beta: Numeric,
+    /// Channel-length modulation in 1/V
+    lambda: Numeric,
+    /// Drain resistance
+    rd: Numeric,
+    /// Source resistance
+    rs: Numeric,
+    /// Zero-bias gate-source junction capacitance
+    cgs: Numeric,
+    /// Zero-bias gate-drain junction capacitance
+    cgd: Numeric,
+    /// Gate junction potential
+    pb: Numeric,
+    /// Gate junction saturation current
+    is: Numeric,
+    /// Gate junction emission coefficient
+    n: Numeric,
+    /// Forward-bias depletion capacitance coefficient
+    fc: Numeric,
+    /// Band gap energy in eV
+    eg: Numeric,
+    /// Saturation current temperature exponent
+    xti: Numeric,
+    /// Temperature coefficient of the threshold voltage in V/K
+    vtotc: Numeric,
+    /// Exponential temperature coefficient of the transconductance in %/K
+    betatce: Numeric,
+}
+
+impl Default for JfetOptions {
+    fn default() -> Self {
+        Self {
+            polarity: JfetPolarity::Njf,
+            vto: -2.0,
+            beta: 1e-4,
+            lambda: 0.0,
+            rd: 0.0,
+            rs: 0.0,
+            cgs: 0.0,
+            cgd: 0.0,
+            pb: 1.0,
+            is: 1e-14,
+            n: 1.0,
+            fc: 0.5,
+            eg: 1.11,
+            xti: 3.0,
+            vtotc: 0.0,
+            betatce: 0.0,
+        }
+    }
+}
+
+impl JfetOptions {
+    /// Sets the polarity of the transistor.
+    pub fn with_polarity(mut self, polarity: JfetPolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Sets the model parameter `name` with its SPICE name, like `VTO` or `BETA`.
+    /// Returns false if `name` is not a parameter of the model.
+    pub fn set_parameter(&mut self, name: &str, value: Numeric) -> bool {
+        let parameter = match name.to_ascii_lowercase().as_str() {
+            "vto" | "vt0" => &mut self.vto,
+            "beta" => &mut self.beta,
+            "lambda" => &mut self.lambda,
+            "rd" => &mut self.rd,
+            "rs" => &mut self.rs,
+            "cgs" => &mut self.cgs,
+            "cgd" => &mut self.cgd,
+            "pb" => &mut self.pb,
+            "is" => &mut self.is,
+            "n" => &mut self.n,
+            "fc" => &mut self.fc,
+            "eg" => &mut self.eg,
+            "xti" => &mut self.xti,
+            "vtotc" => &mut self.vtotc,
+            "betatce" => &mut self.betatce,
+            _ => return false,
+        };
+        *parameter = value;
+        true
+    }
+
+    /// Returns the drain and source resistances.
+    pub fn resistances(&self) -> [Numeric; 2] {
+        [self.rd, self.rs]
+    }
+}
+
+impl JfetBundle {
+    /// Creates a new `JfetBundle` object with the terminals in SPICE order.
+    pub fn new(
+        name: Arc<str>,
+        drain: Option<Variable>,
+        gate: Option<Variable>,
+        source: Option<Variable>,
+        options: Option<JfetOptions>,
+    ) -> JfetBundle {
+        let options = options.unwrap_or_default();
+
+        JfetBundle {
+            name,
+            drain,
+            gate,
+            source,
+            internal_drain: None,
+            internal_source: None,
+            model: None,
+            vto: options.vto,
+            beta: options.beta,
+            is: options.is,
+            options,
+            temperature: DeviceTemperature::default(),
+            ut: UT,
+            gmin: GMIN,
+        }
+    }
+
+    /// Sets the name of the `.model` card of the transistor.
+    pub fn set_model(&mut self, model: Arc<str>) {
+        self.model = Some(model);
+    }
+
+    /// Returns the name of the `.model` card of the transistor.
+    pub fn model(&self) -> Option<Arc<str>> {
+        self.model.clone()
+    }
+
+    /// Replaces the model parameters of the transistor.
+    pub fn set_options(&mut self, options: JfetOptions) {
+        self.vto = options.vto;
+        self.beta = options.beta;
+        self.is = options.is;
+        self.options = options;
+    }
+
+    /// Returns the model parameters of the transistor.
+    pub fn options(&self) -> &JfetOptions {
+        &self.options
+    }
+
+    /// Sets the internal drain and source nodes behind the resistances.
+    /// `None` connects the channel to the terminal directly.
+    pub fn set_internal_nodes(&mut self, drain: Option<Variable>, source: Option<Variable>) {
+        self.internal_drain = drain;
+        self.internal_source = source;
+    }
+
+    /// Sets the instance temperature of the transistor.
+    pub fn set_temperature(&mut self, temperature: DeviceTemperature) {
+        self.temperature = temperature;
+    }
+
+    /// Scales the threshold voltage, the transconductance parameter and the gate saturation
+    /// current to the temperature of the transistor and applies gmin of the simulator settings.
+    /// As in SPICE the threshold shifts linearly by `vtotc` and the transconductance changes
+    /// by `betatce` percent per kelvin, both of which default to zero.
+    pub fn apply_options(&mut self, options: &SimulatorOptions) {
+        let temp = self.temperature.kelvin(options);
+        let tnom = options.tnom_kelvin();
+        let JfetOptions {
+            vto,
+            beta,
+            is,
+            n,
+            eg,
+            xti,
+            vtotc,
+            betatce,
+            ..
+        } = self.options;
+        self.vto = vto + vtotc * (temp - tnom);
+        self.beta = beta * (1.01 as Numeric).powf(betatce * (temp - tnom));
+        self.ut = temperature::thermal_voltage(temp);
+        self.is = temperature::saturation_current(is, n, eg, xti, temp, tnom);
+        self.gmin = options.gmin;
+    }
+
+    /// Returns the name of the JFET bundle.
+    pub fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    /// Returns a reference to the triples representing matrix A.
+    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 24> {
+        let [v_gs, v_gd] = self.voltages(x_vec);
+        self.triples_at(v_gs, v_gd)
+    }
+
+    /// Returns the triples of the terminal resistances, the channel and the gate junctions
+    /// linearised at `v_gs` and `v_gd`.
+    pub fn triples_at(&self, v_gs: Numeric, v_gd: Numeric) -> Triples<Numeric, 24> {
+        self.stamps(self.operating_point(v_gs, v_gd).conductances())
+    }
+
+    /// Returns the indices of the triples representing matrix A.
+    pub fn triple_idx(&self) -> Option<TripleIdx<24>> {
+        let indices: Vec<_> = self
+            .stamps([[0.0; 3]; 3])
+            .into_iter()
+            .map(|&(row, col, _)| (row, col))
+            .collect();
+        Some(TripleIdx::new(&indices))
+    }
+
+    /// Returns a reference to the pairs representing vector b.
+    pub fn pairs(&self, x_vec: &[Numeric]) -> Pairs<Numeric, 4> {
+        let [v_gs, v_gd] = self.voltages(x_vec);
+        self.pairs_at(v_gs, v_gd)
+    }
+
+    /// Returns the pairs of the equivalent current sources `i - ggs * vgs - ggd * vgd` of the
+    /// drain, gate and source currents linearised at `v_gs` and `v_gd`.
+    /// The currents flow into the transistor and enter the RHS with the opposite sign.
+    pub fn pairs_at(&self, v_gs: Numeric, v_gd: Numeric) -> Pairs<Numeric, 4> {
+        let op = self.operating_point(v_gs, v_gd);
+        let sign = self.options.polarity.sign();
+
+        let pairs: Vec<_> = self
+            .internal_nodes()
+            .into_iter()
+            .zip(op.currents())
+            .filter_map(|(node, (current, g_gs, g_gd))| {
+                let i_eq = current - g_gs * v_gs - g_gd * v_gd;
+                Some((node?, -sign * i_eq))
+            })
+            .collect();
+        Pairs::new(&pairs)
+    }
+
+    /// Returns the small-signal triples at the frequency `freq` in Hz, linearised at the
+    /// operating point `x_vec`, with the gate junction capacitances as admittances.
+    pub fn ac_triples(&self, freq: Numeric, x_vec: &[Numeric]) -> Triples<ComplexNumeric, 24> {
+        let [v_gs, v_gd] = self.voltages(x_vec);
+        let conductances = self.operating_point(v_gs, v_gd).conductances();
+        let omega = 2.0 * Numeric::PI() * freq;
+        let [c_gs, c_gd] = self.charges(x_vec).map(|charge| charge.capacitance);
+
+        let mut admittances = conductances.map(|row| row.map(ComplexNumeric::from));
+        for (node, c) in [(SOURCE, c_gs), (DRAIN, c_gd)] {
+            let y = ComplexNumeric::new(0.0, omega * c);
+            admittances[GATE][GATE] += y;
+            admittances[node][node] += y;
+            admittances[GATE][node] -= y;
+            admittances[node][GATE] -= y;
+        }
+        self.stamps(admittances)
+    }
+
+    /// Returns the triples of the admittance matrix of the intrinsic transistor between the
+    /// internal drain, the gate and the internal source and of the terminal resistances.
+    fn stamps<T>(&self, matrix: [[T; 3]; 3]) -> Triples<T, 24>
+    where
+        T: Copy + Default + From<Numeric> + Neg<Output = T>,
+    {
+        let nodes = self.internal_nodes();
+        let mut triples = Vec::with_capacity(24);
+        for (row, values) in nodes.iter().zip(matrix) {
+            for (col, value) in nodes.iter().zip(values) {
+                if let (Some(row), Some(col)) = (row, col) {
+                    triples.push((*row, *col, value));
+                }
+            }
+        }
+
+        let terminals = [
+            (&self.drain, &self.internal_drain),
+            (&self.source, &self.internal_source),
+        ];
+        for ((terminal, internal), resistance) in
+            terminals.into_iter().zip(self.options.resistances())
+        {
+            let Some(internal) = internal.as_ref().map(Variable::idx) else {
+                continue;
+            };
+            let terminal = terminal.as_ref().map(Variable::idx);
+            let g = T::from(resistance.recip());
+            triples.push((internal, internal, g));
+            if let Some(terminal) = terminal {
+                triples.extend([
+                    (terminal, terminal, g),
+                    (terminal, internal, -g),
+                    (internal, terminal, -g),
+                ]);
+            }
+        }
+        Triples::new(&triples)
+    }
+
+    /// Returns the gate-source and gate-drain voltages of an N-channel transistor at the
+    /// solution `x_vec`. The voltages of a P-channel transistor are inverted.
+    pub fn voltages(&self, x_vec: &[Numeric]) -> [Numeric; 2] {
+        let sign = self.options.polarity.sign();
+        [
+            sign * voltage_between(self.g_idx(), self.si_idx(), x_vec),
+            sign * voltage_between(self.g_idx(), self.di_idx(), x_vec),
+        ]
+    }
+
+    /// Limits the Newton steps of the gate-source and gate-drain voltages, first as junctions
+    /// and then around the threshold voltage, as in SPICE.
+    /// Returns the limited voltages and whether any of them was limited.
+    pub fn limit_voltages(&self, new: [Numeric; 2], old: [Numeric; 2]) -> ([Numeric; 2], bool) {
+        let vt = self.options.n * self.ut;
+        let v_crit = limiting::pn_critical_voltage(vt, self.is);
+        let vto = self.vto;
+
+        let mut limited = false;
+        let voltages = [0, 1].map(|idx| {
+            let (voltage, junction_limited) = limiting::pn_junction(new[idx], old[idx], vt, v_crit);
+            let (voltage, fet_limited) = limiting::fet_gate(voltage, old[idx], vto);
+            limited |= junction_limited || fet_limited;
+            voltage
+        });
+        (voltages, limited)
+    }
+
+    /// Returns the operating region at `v_gs` and `v_gd`.
+    pub fn region(&self, v_gs: Numeric, v_gd: Numeric) -> &'static str {
+        let op = self.operating_point(v_gs, v_gd);
+        // In reverse mode drain and source have swapped their roles
+        match (op.region, op.reverse) {
+            ("linear", true) => "linear, reverse",
+            ("saturation", true) => "saturation, reverse",
+            (region, _) => region,
+        }
+    }
+
+    /// Evaluates the channel current and the gate junctions at `v_gs` and `v_gd`.
+    /// For a negative `v_ds` drain and source swap their roles.
+    fn operating_point(&self, v_gs: Numeric, v_gd: Numeric) -> OperatingPoint {
+        let v_ds = v_gs - v_gd;
+        let reverse = v_ds < Numeric::zero();
+
+        // The channel current and its derivatives by v_gs and v_gd
+        let (region, id, did_dvgs, did_dvgd) = if reverse {
+            let (region, id, gm, gds) = self.channel(v_gd, -v_ds);
+            (region, -id, gds, -gm - gds)
+        } else {
+            let (region, id, gm, gds) = self.channel(v_gs, v_ds);
+            (region, id, gm + gds, -gds)
+        };
+
+        let vt = self.options.n * self.ut;
+        let (i_gs, g_gs) = junction(self.is, v_gs, vt);
+        let (i_gd, g_gd) = junction(self.is, v_gd, vt);
+        let gmin = self.gmin;
+
+        OperatingPoint {
+            region,
+            reverse,
+            id,
+            did_dvgs,
+            did_dvgd,
+            i_gs: i_gs + gmin * v_gs,
+            g_gs: g_gs + gmin,
+            i_gd: i_gd + gmin * v_gd,
+            g_gd: g_gd + gmin,
+        }
+    }
+
+    /// Returns the region, the drain current and its derivatives by `v_gs` and `v_ds` of the
+    /// channel in the forward mode - Shichman-Hodges equations.
+    fn channel(&self, v_gs: Numeric, v_ds: Numeric) -> (&'static str, Numeric, Numeric, Numeric) {
+        let (vto, beta, lambda) = (self.vto, self.beta, self.options.lambda);
+        let v_gst = v_gs - vto;
+        let clm = 1.0 + lambda * v_ds;
+
+        if v_gst <= Numeric::zero() {
+            ("cutoff", Numeric::zero(), Numeric::zero(), Numeric::zero())
+        } else if v_ds >= v_gst {
+            (
+                "saturation",
+                beta * v_gst * v_gst * clm,
+                2.0 * beta * v_gst * clm,
+                beta * lambda * v_gst * v_gst,
+            )
+        } else {
+            (
+                "linear",
+                beta * v_ds * (2.0 * v_gst - v_ds) * clm,
+                2.0 * beta * v_ds * clm,
+                2.0 * beta * (v_gst - v_ds) * clm + beta * lambda * v_ds * (2.0 * v_gst - v_ds),
+            )
+        }
+    }
+
+    /// Returns the gate-source and gate-drain depletion charges at the solution `x_vec`.
+    pub fn charges(&self, x_vec: &[Numeric]) -> [Charge; 2] {
+        let options = &self.options;
+        let [v_gs, v_gd] = self.voltages(x_vec);
+        let (q_gs, c_gs) = junction_charge(v_gs, options.cgs, options.pb, GRADING, options.fc);
+        let (q_gd, c_gd) = junction_charge(v_gd, options.cgd, options.pb, GRADING, options.fc);
+
+        // The charges of a P-channel transistor are stored from the source and drain to the gate
+        let (gs_nodes, gd_nodes) = match options.polarity {
+            JfetPolarity::Njf => ((self.g_idx(), self.si_idx()), (self.g_idx(), self.di_idx())),
+            JfetPolarity::Pjf => ((self.si_idx(), self.g_idx()), (self.di_idx(), self.g_idx())),
+        };
+        [
+            Charge::new(gs_nodes.0, gs_nodes.1, v_gs, q_gs, c_gs),
+            Charge::new(gd_nodes.0, gd_nodes.1, v_gd, q_gd, c_gd),
+        ]
+    }
+
+    /// Returns the internal drain, the gate and the internal source, in the order of the rows
+    /// of the conductance matrix.
+    fn internal_nodes(&self) -> [Option<usize>; 3] {
+        [self.di_idx(), self.g_idx(), self.si_idx()]
+    }
+
+    /// Returns the indices of the internal nodes behind the terminal resistances.
+    pub fn internal_node_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.resistor_nodes().map(|(_, internal)| internal)
+    }
+
+    /// Returns the terminal and the internal node of each terminal resistance.
+    pub fn resistor_nodes(&self) -> impl Iterator<Item = (Option<usize>, usize)> + '_ {
+        [
+            (&self.drain, &self.internal_drain),
+            (&self.source, &self.internal_source),
+        ]
+        .into_iter()
+        .filter_map(|(terminal, internal)| {
+            let internal = internal.as_ref()?.idx();
+            Some((terminal.as_ref().map(Variable::idx), internal))
+        })
+    }
+
+    pub fn d_idx(&self) -> Option<usize> {
+        self.drain.as_ref().map(|v| v.idx())
+    }
+
+    pub fn g_idx(&self) -> Option<usize> {
+        self.gate.as_ref().map(|v| v.idx())
+    }
+
+    pub fn s_idx(&self) -> Option<usize> {
+        self.source.as_ref().map(|v| v.idx())
+    }
+
+    /// Returns the index of the internal drain node, which is the drain without `rd`.
+    pub fn di_idx(&self) -> Option<usize> {
+        self.internal_drain
+            .as_ref()
+            .map(|v| v.idx())
+            .or(self.d_idx())
+    }
+
+    /// Returns the index of the internal source node, which is the source without `rs`.
+    pub fn si_idx(&self) -> Option<usize> {
+        self.internal_source
+            .as_ref()
+            .map(|v| v.idx())
+            .or(self.s_idx())
+    }
+}
+
+/// The currents of an N-channel transistor and their derivatives by the gate-source and
+/// gate-drain voltages.
+#[derive(Debug, Clone, Copy)]
+struct OperatingPoint {
+    /// The name of the operating region of the channel
+    region: &'static str,
+    /// Whether drain and source have swapped their roles
+    reverse: bool,
+    /// The channel current from drain to source
+    id: Numeric,
+    did_dvgs: Numeric,
+    did_dvgd: Numeric,
+    /// The current of the gate-source junction
+    i_gs: Numeric,
+    g_gs: Numeric,
+    /// The current of the gate-drain junction
+    i_gd: Numeric,
+    g_gd: Numeric,
+}
+
+impl OperatingPoint {
+    /// Returns the currents into the drain, gate and source with their derivatives by `v_gs`
+    /// and `v_gd`.
+    fn currents(&self) -> [(Numeric, Numeric, Numeric); 3] {
+        [
+            (
+                self.id - self.i_gd,
+                self.did_dvgs,
+                self.did_dvgd - self.g_gd,
+            ),
+            (self.i_gs + self.i_gd, self.g_gs, self.g_gd),
+            (
+                -self.id - self.i_gs,
+                -self.did_dvgs - self.g_gs,
+                -self.did_dvgd,
+            ),
+        ]
+    }
+
+    /// Returns the derivatives of the currents into the drain, gate and source by the
+    /// voltages of the internal drain, the gate and the internal source.
+    /// They are the same for both polarities, as the voltages and currents invert together.
+    fn conductances(&self) -> [[Numeric; 3]; 3] {
+        self.currents().map(|(_, g_gs, g_gd)| {
+            let mut row = [0.0; 3];
+            row[DRAIN] = -g_gd;
+            row[GATE] = g_gs + g_gd;
+            row[SOURCE] = -g_gs;
+            row
+        })
+    }
+}
+
+/// Returns the current `is * (exp(v / vt) - 1)` of a gate junction and its conductance.
+fn junction(is: Numeric, voltage: Numeric, vt: Numeric) -> (Numeric, Numeric) {
+    let exp = Numeric::exp(voltage / vt);
+    (is * (exp - Numeric::one()), is * exp / vt)
+}
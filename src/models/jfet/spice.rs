@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use log::warn;
+
+use super::{add_internal_nodes, JfetOptions, JfetPolarity};
+use crate::frontends::get_variable;
+use crate::frontends::spice::{ApplySpiceModel, ProcessSpiceElement, SpiceModel};
+use crate::frontends::spice_parser_helpers::SpiceElementParser;
+use crate::frontends::FrontendError;
+use crate::models::{DeviceTemperature, Element, JfetBundle, Unit};
+
+impl ProcessSpiceElement for JfetBundle {
+    fn process(
+        element: pest::iterators::Pair<crate::frontends::spice::Rule>,
+        variables: &mut Vec<crate::models::Variable>,
+        elements: &mut Vec<crate::models::Element>,
+        var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
+    ) -> Result<(), crate::frontends::FrontendError> {
+        let mut parser = SpiceElementParser::new(element);
+
+        let name = parser.parse_name("jfet")?;
+        let drain = parser.parse_node("jfet", name, "drain")?;
+        let gate = parser.parse_node("jfet", name, "gate")?;
+        let source = parser.parse_node("jfet", name, "source")?;
+        let model = parser.parse_model();
+
+        let mut temperature = DeviceTemperature::default();
+        for (parameter, value) in parser.parse_named_parameters("jfet", name)? {
+            temperature.set_parameter(&parameter, value);
+        }
+
+        let mut jfet = JfetBundle::new(
+            Arc::from(name),
+            get_variable(drain, Unit::Volt, variables, var_map),
+            get_variable(gate, Unit::Volt, variables, var_map),
+            get_variable(source, Unit::Volt, variables, var_map),
+            None,
+        );
+        if let Some(model) = model {
+            jfet.set_model(Arc::from(model));
+        }
+        jfet.set_temperature(temperature);
+        elements.push(Element::Jfet(jfet));
+        Ok(())
+    }
+}
+
+impl ApplySpiceModel for JfetBundle {
+    fn model_name(&self) -> Option<Arc<str>> {
+        self.model()
+    }
+
+    fn apply_model(
+        &mut self,
+        model: &SpiceModel,
+        variables: &mut Vec<crate::models::Variable>,
+        var_map: &mut std::collections::HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError> {
+        let polarity =
+            model
+                .kind
+                .parse::<JfetPolarity>()
+                .map_err(|reason| FrontendError::InvalidModel {
+                    model: model.name.to_string(),
+                    reason,
+                })?;
+        let mut options = JfetOptions::default().with_polarity(polarity);
+        for (parameter, value) in &model.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of model {}",
+                    model.name
+                );
+            }
+        }
+        self.set_options(options);
+        add_internal_nodes(self, variables, var_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+// Basic tests for JFET model
+use super::{JfetBundle, JfetOptions, JfetPolarity};
+use crate::models::{Unit, Variable};
+use crate::spot::{Numeric, UT};
+use std::sync::Arc;
+
+/// Creates a transistor with the drain, gate and source at the indices 0, 1 and 2.
+fn jfet(options: Option<JfetOptions>) -> JfetBundle {
+    let drain = Variable::new(Arc::from("drain"), Unit::Volt, 0);
+    let gate = Variable::new(Arc::from("gate"), Unit::Volt, 1);
+    let source = Variable::new(Arc::from("source"), Unit::Volt, 2);
+
+    JfetBundle::new(
+        Arc::from("J1"),
+        Some(drain),
+        Some(gate),
+        Some(source),
+        options,
+    )
+}
+
+/// Returns the currents into the nodes of the transistor at `x_vec`, which are the linearised
+/// currents `G * x - b` of its stamps.
+fn node_currents(jfet: &JfetBundle, x_vec: &[Numeric]) -> Vec<Numeric> {
+    let mut currents = vec![0.0; x_vec.len()];
+    for &(row, col, value) in &jfet.triples(x_vec) {
+        currents[row] += value * x_vec[col];
+    }
+    for &(row, value) in &jfet.pairs(x_vec) {
+        currents[row] -= value;
+    }
+    currents
+}
+
+/// Returns the current of a reverse biased gate junction with the default parameters.
+fn gate_leakage(voltage: Numeric) -> Numeric {
+    1e-14 * ((voltage / UT).exp() - 1.0) + 1e-12 * voltage
+}
+
+#[test]
+fn test_jfet_creation() {
+    let jfet = jfet(None);
+
+    assert_eq!(jfet.name(), "J1".into());
+    assert_eq!(jfet.d_idx(), Some(0));
+    assert_eq!(jfet.g_idx(), Some(1));
+    assert_eq!(jfet.s_idx(), Some(2));
+    // Without terminal resistances the channel sits at the terminals
+    assert_eq!(jfet.di_idx(), Some(0));
+    assert_eq!(jfet.si_idx(), Some(2));
+    assert_eq!(jfet.internal_node_indices().count(), 0);
+}
+
+#[test]
+fn test_jfet_set_parameter() {
+    let mut options = JfetOptions::default();
+    assert!(options.set_parameter("RD", 10.0));
+    assert!(options.set_parameter("vto", -1.5));
+    assert!(options.set_parameter("BETA", 2e-3));
+    assert!(!options.set_parameter("kf", 1.0));
+    assert_eq!(options.resistances(), [10.0, 0.0]);
+}
+
+#[test]
+fn test_jfet_polarity_from_str() {
+    assert_eq!("NJF".parse::<JfetPolarity>(), Ok(JfetPolarity::Njf));
+    assert_eq!("pjf".parse::<JfetPolarity>(), Ok(JfetPolarity::Pjf));
+    assert!("npn".parse::<JfetPolarity>().is_err());
+}
+
+#[test]
+fn test_jfet_saturation_current() {
+    let jfet = jfet(None);
+    let (beta, vto) = (1e-4, -2.0);
+
+    // In saturation the drain current follows BETA * (VGS - VTO)² and is IDSS at VGS = 0
+    for v_gs in [-1.5, -1.0, -0.5, 0.0] {
+        let currents = node_currents(&jfet, &[10.0, v_gs, 0.0]);
+        let id = beta * (v_gs - vto) * (v_gs - vto) - gate_leakage(v_gs - 10.0);
+        assert!(
+            (currents[0] - id).abs() < 1e-9 * id,
+            "Drain current at {v_gs}V: {} != {id}",
+            currents[0]
+        );
+        assert!((currents[0] + currents[1] + currents[2]).abs() < 1e-15);
+        assert_eq!(jfet.region(v_gs, v_gs - 10.0), "saturation");
+    }
+    assert_eq!(jfet.region(-2.5, -12.5), "cutoff");
+}
+
+#[test]
+fn test_jfet_temperature_scaling() {
+    use crate::models::DeviceTemperature;
+    use crate::sim::options::SimulatorOptions;
+
+    let mut options = JfetOptions::default();
+    for (name, value) in [("vtotc", -2e-3), ("betatce", -0.5), ("eg", 1.2)] {
+        assert!(options.set_parameter(name, value));
+    }
+    let mut jfet = jfet(Some(options));
+
+    // 50K above the nominal temperature the threshold drops by 0.1V and BETA by 0.5%/K
+    jfet.set_temperature(DeviceTemperature::new(None, Some(50.0)));
+    jfet.apply_options(&SimulatorOptions::default());
+    let vto = -2.0 - 2e-3 * 50.0;
+    let beta = 1e-4 * (1.01 as Numeric).powf(-0.5 * 50.0);
+
+    let currents = node_currents(&jfet, &[10.0, -1.0, 0.0]);
+    let id = beta * (-1.0 - vto) * (-1.0 - vto);
+    assert!(
+        (currents[0] - id).abs() < 1e-6 * id,
+        "Drain current: {} != {id}",
+        currents[0]
+    );
+}
+
+#[test]
+fn test_jfet_linear_region() {
+    let mut options = JfetOptions::default();
+    options.set_parameter("lambda", 0.02);
+    let jfet = jfet(Some(options));
+    let (beta, vto, lambda) = (1e-4, -2.0, 0.02);
+
+    // V_GS = -0.5V, V_DS = 0.5V below V_GS - VTO = 1.5V
+    let currents = node_currents(&jfet, &[0.5, -0.5, 0.0]);
+    let v_gst = -0.5 - vto;
+    let id = beta * 0.5 * (2.0 * v_gst - 0.5) * (1.0 + lambda * 0.5) - gate_leakage(-1.0);
+
+    assert!(
+        (currents[0] - id).abs() < 1e-9 * id,
+        "Drain current {}",
+        currents[0]
+    );
+    assert_eq!(jfet.region(-0.5, -1.0), "linear");
+}
+
+#[test]
+fn test_jfet_jacobian() {
+    let mut options = JfetOptions::default();
+    options.set_parameter("lambda", 0.05);
+    let jfet = jfet(Some(options));
+    let h = 1e-7;
+
+    // Saturation, linear and reverse mode
+    for x_vec in [[6.0, -0.8, 0.1], [0.4, -0.6, 0.1], [-0.3, -0.5, 0.2]] {
+        let mut jacobian = [[0.0; 3]; 3];
+        for &(row, col, value) in &jfet.triples(&x_vec) {
+            jacobian[row][col] += value;
+        }
+        for col in 0..3 {
+            let mut x_plus = x_vec;
+            let mut x_minus = x_vec;
+            x_plus[col] += h;
+            x_minus[col] -= h;
+            let plus = node_currents(&jfet, &x_plus);
+            let minus = node_currents(&jfet, &x_minus);
+            for row in 0..3 {
+                let numeric = (plus[row] - minus[row]) / (2.0 * h);
+                let tolerance = 1e-5 * numeric.abs() + 1e-10;
+                assert!(
+                    (jacobian[row][col] - numeric).abs() < tolerance,
+                    "dI{row}/dV{col} at {x_vec:?}: {} != {numeric}",
+                    jacobian[row][col]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_jfet_reverse_mode() {
+    let jfet = jfet(None);
+
+    // Swapping the voltages of drain and source swaps their currents
+    let forward = node_currents(&jfet, &[1.0, -0.5, 0.0]);
+    let reverse = node_currents(&jfet, &[0.0, -0.5, 1.0]);
+
+    assert!((forward[0] - reverse[2]).abs() < 1e-12 * forward[0]);
+    assert!((forward[2] - reverse[0]).abs() < 1e-12 * forward[0]);
+    assert_eq!(jfet.region(-1.5, -0.5), "linear, reverse");
+}
+
+#[test]
+fn test_jfet_pjf_mirrors_njf() {
+    let njf = jfet(None);
+    let pjf = jfet(Some(
+        JfetOptions::default().with_polarity(JfetPolarity::Pjf),
+    ));
+
+    let njf_currents = node_currents(&njf, &[5.0, -1.0, 0.0]);
+    let pjf_currents = node_currents(&pjf, &[-5.0, 1.0, 0.0]);
+
+    for (njf, pjf) in njf_currents.iter().zip(&pjf_currents) {
+        assert!((njf + pjf).abs() < 1e-12 * njf.abs().max(1e-12));
+    }
+    assert_eq!(
+        pjf.voltages(&[-5.0, 1.0, 0.0]),
+        njf.voltages(&[5.0, -1.0, 0.0])
+    );
+}
+
+#[test]
+fn test_jfet_internal_nodes() {
+    let mut options = JfetOptions::default();
+    options.set_parameter("rd", 100.0);
+    options.set_parameter("rs", 10.0);
+    let mut jfet = jfet(Some(options));
+    let internal_drain = Variable::new(Arc::from("J1#drain"), Unit::Volt, 3);
+    let internal_source = Variable::new(Arc::from("J1#source"), Unit::Volt, 4);
+    jfet.set_internal_nodes(Some(internal_drain), Some(internal_source));
+
+    assert_eq!(jfet.di_idx(), Some(3));
+    assert_eq!(jfet.si_idx(), Some(4));
+    assert_eq!(jfet.internal_node_indices().collect::<Vec<_>>(), vec![3, 4]);
+
+    let x_vec = [5.0, -1.0, 0.0, 5.0, 0.0];
+    let triples = jfet.triples(&x_vec);
+    let conductance = |row, col| {
+        triples
+            .into_iter()
+            .filter(|&&(r, c, _)| (r, c) == (row, col))
+            .map(|&(_, _, value)| value)
+            .sum::<Numeric>()
+    };
+    assert_eq!(conductance(0, 0), 0.01);
+    assert_eq!(conductance(0, 3), -0.01);
+    assert_eq!(conductance(2, 4), -0.1);
+    // 9 entries of the intrinsic transistor and 4 of each resistance
+    assert_eq!(jfet.triple_idx().unwrap().len(), 9 + 4 + 4);
+}
+
+#[test]
+fn test_jfet_ac_triples() {
+    let mut options = JfetOptions::default();
+    options.set_parameter("cgs", 2e-12);
+    let jfet = jfet(Some(options));
+    let x_vec = [5.0, -1.0, 0.0];
+
+    let triples = jfet.triples(&x_vec);
+    let ac_triples = jfet.ac_triples(0.0, &x_vec);
+
+    assert_eq!(triples.len(), ac_triples.len());
+    for (real, complex) in triples.into_iter().zip(&ac_triples) {
+        assert_eq!((real.0, real.1), (complex.0, complex.1));
+        assert_eq!(real.2, complex.2.re);
+        assert_eq!(complex.2.im, 0.0);
+    }
+
+    // The gate-source capacitance at the reverse bias of 1V with PB = 1 and M = 0.5
+    let freq = 1e6;
+    let c_gs = 2e-12 / 2.0_f64.sqrt();
+    let ac_triples = jfet.ac_triples(freq, &x_vec);
+    let gate = ac_triples
+        .into_iter()
+        .find(|&&(row, col, _)| (row, col) == (1, 1))
+        .unwrap();
+    let expected = 2.0 * std::f64::consts::PI * freq * c_gs;
+    assert!((gate.2.im - expected).abs() < 1e-9 * expected);
+}
@@ -0,0 +1,119 @@
+/// The Limiting Module. Newton-Raphson steps of the junction voltages of nonlinear devices are
+/// limited to regions where the linearisation of the device is still meaningful, as in SPICE.
+use crate::spot::*;
+
+/// Returns the critical voltage of a pn junction, above which the junction voltage is limited.
+///
+/// # Arguments
+///
+/// * `vt` - The thermal voltage `n * UT` of the junction.
+/// * `is` - The saturation current of the junction.
+pub(crate) fn pn_critical_voltage(vt: Numeric, is: Numeric) -> Numeric {
+    vt * (vt / (std::f64::consts::SQRT_2 * is)).ln()
+}
+
+/// Limits the step of a pn junction voltage from `v_old` to `v_new` (SPICE `pnjlim`).
+/// Above the critical voltage the junction current may only grow by a bounded factor,
+/// which keeps `exp(v / vt)` from overflowing.
+///
+/// Returns the limited voltage and whether it was limited.
+pub(crate) fn pn_junction(
+    v_new: Numeric,
+    v_old: Numeric,
+    vt: Numeric,
+    v_crit: Numeric,
+) -> (Numeric, bool) {
+    if v_new <= v_crit || (v_new - v_old).abs() <= 2.0 * vt {
+        return (v_new, false);
+    }
+
+    if v_old > 0.0 {
+        let arg = 1.0 + (v_new - v_old) / vt;
+        if arg > 0.0 {
+            (v_old + vt * arg.ln(), true)
+        } else {
+            (v_crit, true)
+        }
+    } else {
+        (vt * (v_new / vt).ln(), true)
+    }
+}
+
+/// Limits the step of a gate-source voltage around the threshold voltage `vto` (SPICE `fetlim`).
+///
+/// Returns the limited voltage and whether it was limited.
+pub(crate) fn fet_gate(v_new: Numeric, v_old: Numeric, vto: Numeric) -> (Numeric, bool) {
+    let vtsthi = (2.0 * (v_old - vto)).abs() + 2.0;
+    let vtstlo = vtsthi / 2.0 + 2.0;
+    let vtox = vto + 3.5;
+    let delta = v_new - v_old;
+
+    let limited = if v_old >= vto {
+        if v_old >= vtox {
+            if delta <= 0.0 {
+                // Turning off
+                if v_new >= vtox {
+                    if -delta > vtstlo {
+                        v_old - vtstlo
+                    } else {
+                        v_new
+                    }
+                } else {
+                    v_new.max(vto + 2.0)
+                }
+            } else if delta >= vtsthi {
+                v_old + vtsthi
+            } else {
+                v_new
+            }
+        } else if delta <= 0.0 {
+            // Middle region, turning off
+            v_new.max(vto - 0.5)
+        } else {
+            // Middle region, turning on
+            v_new.min(vto + 4.0)
+        }
+    } else if delta <= 0.0 {
+        // Off, turning further off
+        if -delta > vtsthi {
+            v_old - vtsthi
+        } else {
+            v_new
+        }
+    } else {
+        // Off, turning on
+        let v_on = vto + 0.5;
+        if v_new <= v_on {
+            if delta > vtstlo {
+                v_old + vtstlo
+            } else {
+                v_new
+            }
+        } else {
+            v_on
+        }
+    };
+
+    (limited, limited != v_new)
+}
+
+/// Limits the step of a drain-source voltage (SPICE `limvds`).
+///
+/// Returns the limited voltage and whether it was limited.
+pub(crate) fn fet_drain(v_new: Numeric, v_old: Numeric) -> (Numeric, bool) {
+    let limited = if v_old >= 3.5 {
+        if v_new > v_old {
+            v_new.min(3.0 * v_old + 2.0)
+        } else if v_new < 3.5 {
+            v_new.max(2.0)
+        } else {
+            v_new
+        }
+    } else if v_new > v_old {
+        v_new.min(4.0)
+    } else {
+        v_new.max(-0.5)
+    };
+
+    (limited, limited != v_new)
+}
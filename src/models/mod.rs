@@ -1,5 +1,7 @@
+pub mod behavioral;
 pub mod bjt;
 pub mod capacitor;
+pub mod charge;
 pub mod controlled_sources;
 pub mod coupled_inductors;
 pub mod diode;
@@ -7,9 +9,13 @@ pub mod gain;
 pub mod inductor;
 pub mod integration;
 pub mod isource;
+pub mod jfet;
+pub mod limiting;
 pub mod mosfet;
 pub mod pairs;
 pub mod resistor;
+pub mod switch;
+pub mod temperature;
 pub mod triples;
 pub mod vsource;
 pub mod vsource_sine;
@@ -21,20 +27,28 @@ use std::sync::Arc;
 
 use num::{One, Zero};
 
+use crate::sim::options::SimulatorOptions;
 use crate::spot::*;
 use serde::Serialize;
 
+pub use self::behavioral::{BehavioralBundle, BehavioralOutput};
+pub use self::bjt::BjtBundle;
 pub use self::capacitor::CapacitorBundle;
+pub use self::charge::Charge;
 pub use self::controlled_sources::{CCCSBundle, CCVSBundle, VCCSBundle, VCVSBundle};
 pub use self::coupled_inductors::serde::SerdeCoupledInductors;
 pub use self::coupled_inductors::CoupledInductorsBundle;
 pub use self::diode::DiodeBundle;
 pub use self::gain::GainBundle;
 pub use self::inductor::InductorBundle;
+pub use self::integration::{IntegrationMethod, StateHistory};
 pub use self::isource::ISourceBundle;
-pub use self::mosfet::Mos0Bundle;
+pub use self::jfet::JfetBundle;
+pub use self::mosfet::{Mos0Bundle, Mos1Bundle};
 pub use self::pairs::Pairs;
 pub use self::resistor::ResistorBundle;
+pub use self::switch::SwitchBundle;
+pub use self::temperature::DeviceTemperature;
 pub use self::triples::{TripleIdx, Triples};
 pub use self::vsource::VSourceBundle;
 pub use self::vsource_sine::VSourceSinBundle;
@@ -107,6 +121,35 @@ impl From<(Arc<str>, Unit, usize)> for Variable {
     }
 }
 
+/// The way a branch of an element couples its two nodes at DC, as seen by the topology check.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum BranchKind {
+    /// Conducts a current depending on the voltage, like resistors and diodes.
+    Conductive,
+    /// Fixes the voltage between its nodes, like voltage sources and inductors at DC.
+    Voltage,
+    /// Fixes the current between its nodes, like current sources and capacitors at DC.
+    Current,
+    /// Senses the voltage between its nodes without drawing a current.
+    Control,
+}
+
+/// A branch of an element between two nodes. `None` is the ground node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Branch {
+    pub(crate) kind: BranchKind,
+    pub(crate) nodes: (Option<usize>, Option<usize>),
+}
+
+impl Branch {
+    fn new(kind: BranchKind, node0: Option<usize>, node1: Option<usize>) -> Self {
+        Self {
+            kind,
+            nodes: (node0, node1),
+        }
+    }
+}
+
 /// An enum representing different types of circuit elements.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Element {
@@ -116,6 +159,12 @@ pub enum Element {
     Resistor(ResistorBundle),
     Diode(DiodeBundle),
     Mos0(Mos0Bundle),
+    Mos1(Mos1Bundle),
+    /// Boxed, as the model parameters make the transistor the largest element
+    Bjt(Box<BjtBundle>),
+    Jfet(JfetBundle),
+    Switch(SwitchBundle),
+    Behavioral(BehavioralBundle),
     VSource(VSourceBundle),
     VSourceSin(VSourceSinBundle),
     VSourceStep(VSourceStepBundle),
@@ -135,11 +184,13 @@ impl Element {
             Element::VSource(ele) => Some(ele.triples()),
             Element::VSourceStep(ele) => Some(ele.triples()),
             Element::Resistor(ele) => Some(ele.triples()),
+            Element::Inductor(ele) => Some(ele.triples()),
             Element::Gain(ele) => Some(ele.triples()), // Gain ist linear und konstant
             Element::VCVS(ele) => Some(ele.triples()),
             Element::VCCS(ele) => Some(ele.triples()),
             Element::CCCS(ele) => Some(ele.triples()),
             Element::CCVS(ele) => Some(ele.triples()),
+            Element::Behavioral(ele) => ele.constant_triples(),
             _ => None,
         }
     }
@@ -157,27 +208,29 @@ impl Element {
     /// Returns the time variant triples of the element, if applicable.
     pub(crate) fn get_time_variant_triples(
         &self,
-        delta_t: &Numeric,
+        integrator: &dyn IntegrationMethod,
     ) -> Option<Triples<Numeric, 4>> {
         match self {
-            Element::Capacitor(ele) => Some(ele.triples(Some(delta_t))),
-            Element::Inductor(ele) => Some(ele.triples(Some(delta_t))),
-            Element::CoupledInductors(ele) => Some(ele.get_time_variant_triples(delta_t)),
+            Element::Inductor(ele) => Some(ele.transient_triples(integrator)),
+            Element::CoupledInductors(ele) => {
+                Some(ele.get_time_variant_triples(&integrator.delta_t()))
+            }
             _ => None,
         }
     }
 
     /// Returns the time variant pairs of the element, if applicable.
+    /// `states` are the histories of the element's charges and fluxes.
     pub(crate) fn get_time_variant_pairs(
         &self,
         time: Option<&Numeric>,
-        delta_t: &Numeric,
+        integrator: &dyn IntegrationMethod,
+        states: &[StateHistory],
     ) -> Option<Pairs<Numeric, 2>> {
         match self {
             Element::VSourceSin(ele) => Some(ele.pairs(time)),
             Element::VSourceStep(ele) => Some(ele.pairs(time)),
-            Element::Capacitor(ele) => Some(ele.pairs(delta_t)),
-            Element::Inductor(ele) => Some(ele.pairs(delta_t)),
+            Element::Inductor(ele) => Some(ele.pairs(&states[0], integrator)),
             Element::CoupledInductors(ele) => Some(ele.get_pairs()),
             Element::VCVS(_) => None,
             Element::VCCS(_) => None,
@@ -187,18 +240,131 @@ impl Element {
         }
     }
 
-    /// Returns the time variant pairs of the element using trapezoidal integration, if applicable.
-    pub(crate) fn get_time_variant_pairs_trapezoidal(
+    /// Returns the number of charge and flux states, whose histories are kept by the simulator.
+    pub(crate) fn state_count(&self) -> usize {
+        match self {
+            Element::Capacitor(_) => 1,
+            Element::Inductor(_) => 1,
+            Element::Diode(_) => 1,
+            Element::Mos0(_) => 2,
+            Element::Mos1(_) => 3,
+            Element::Bjt(_) => 2,
+            Element::Jfet(_) => 2,
+            _ => 0,
+        }
+    }
+
+    /// Returns the charges stored by the element at the solution `x_vec`.
+    /// The charges are in the same order as the element's states, whose `history` the charges
+    /// of the Meyer capacitances are integrated from. An empty `history` starts them anew.
+    pub(crate) fn get_charges(&self, x_vec: &[Numeric], history: &[StateHistory]) -> Vec<Charge> {
+        match self {
+            Element::Capacitor(ele) => vec![ele.charge(x_vec)],
+            Element::Diode(ele) => vec![ele.charge(x_vec)],
+            Element::Mos0(ele) => ele.charges(x_vec).to_vec(),
+            Element::Mos1(ele) => ele.charges(x_vec, history).to_vec(),
+            Element::Bjt(ele) => ele.charges(x_vec).to_vec(),
+            Element::Jfet(ele) => ele.charges(x_vec).to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the number of junction voltages, whose Newton steps are limited by the simulator.
+    pub(crate) fn junction_count(&self) -> usize {
+        match self {
+            Element::Diode(_) => 1,
+            Element::Mos0(_) => 2,
+            Element::Mos1(_) => 3,
+            Element::Bjt(_) => 2,
+            Element::Jfet(_) => 2,
+            Element::Switch(_) => 2,
+            Element::Behavioral(ele) => ele.input_count(),
+            _ => 0,
+        }
+    }
+
+    /// Returns the junction voltages of the element at the solution `x_vec`.
+    pub(crate) fn get_junction_voltages(&self, x_vec: &[Numeric]) -> Vec<Numeric> {
+        match self {
+            Element::Diode(ele) => vec![ele.voltage(x_vec)],
+            Element::Mos0(ele) => ele.voltages(x_vec).to_vec(),
+            Element::Mos1(ele) => ele.voltages(x_vec).to_vec(),
+            Element::Bjt(ele) => ele.voltages(x_vec).to_vec(),
+            Element::Jfet(ele) => ele.voltages(x_vec).to_vec(),
+            Element::Switch(ele) => ele.voltages(x_vec).to_vec(),
+            Element::Behavioral(ele) => ele.voltages(x_vec),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Moves the junction voltages towards the solution `x_vec`, limiting the steps.
+    /// Returns whether any of the junction voltages was limited.
+    pub(crate) fn limit_junction_voltages(
         &self,
-        time: Option<&Numeric>,
-        delta_t: &Numeric,
-    ) -> Option<Pairs<Numeric, 2>> {
+        x_vec: &[Numeric],
+        junctions: &mut [Numeric],
+    ) -> bool {
         match self {
-            Element::VSourceSin(ele) => Some(ele.pairs(time)), // Time sources use same method
-            Element::VSourceStep(ele) => Some(ele.pairs(time)), // Time sources use same method
-            Element::Capacitor(ele) => Some(ele.pairs_trapezoidal(delta_t)),
-            Element::Inductor(ele) => Some(ele.pairs_trapezoidal(delta_t)),
-            Element::CoupledInductors(ele) => Some(ele.get_pairs()), // Same as Euler for now
+            Element::Diode(ele) => {
+                let (voltage, limited) = ele.limit_voltage(ele.voltage(x_vec), junctions[0]);
+                junctions[0] = voltage;
+                limited
+            }
+            Element::Mos0(ele) => {
+                let old = [junctions[0], junctions[1]];
+                let (voltages, limited) = ele.limit_voltages(ele.voltages(x_vec), old);
+                junctions.copy_from_slice(&voltages);
+                limited
+            }
+            Element::Mos1(ele) => {
+                let old = [junctions[0], junctions[1], junctions[2]];
+                let (voltages, limited) = ele.limit_voltages(ele.voltages(x_vec), old);
+                junctions.copy_from_slice(&voltages);
+                limited
+            }
+            Element::Bjt(ele) => {
+                let old = [junctions[0], junctions[1]];
+                let (voltages, limited) = ele.limit_voltages(ele.voltages(x_vec), old);
+                junctions.copy_from_slice(&voltages);
+                limited
+            }
+            Element::Jfet(ele) => {
+                let old = [junctions[0], junctions[1]];
+                let (voltages, limited) = ele.limit_voltages(ele.voltages(x_vec), old);
+                junctions.copy_from_slice(&voltages);
+                limited
+            }
+            Element::Switch(ele) => {
+                let old = [junctions[0], junctions[1]];
+                let (voltages, limited) = ele.limit_voltages(ele.voltages(x_vec), old);
+                junctions.copy_from_slice(&voltages);
+                limited
+            }
+            // The exact Jacobian of the expression needs no limiting
+            Element::Behavioral(ele) => {
+                junctions.copy_from_slice(&ele.voltages(x_vec));
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the nonlinear triples, linearised at the junction voltages of the element.
+    pub(crate) fn get_nonlinear_triples(
+        &self,
+        junctions: &[Numeric],
+    ) -> Option<Triples<Numeric, 24>> {
+        match self {
+            Element::Diode(ele) => Some(ele.triples_at(junctions[0]).widen()),
+            Element::Mos0(ele) => Some(ele.triples_at(junctions[0], junctions[1]).widen()),
+            Element::Mos1(ele) => Some(
+                ele.triples_at(junctions[0], junctions[1], junctions[2])
+                    .widen(),
+            ),
+            Element::Bjt(ele) => Some(ele.triples_at(junctions[0], junctions[1])),
+            Element::Jfet(ele) => Some(ele.triples_at(junctions[0], junctions[1])),
+            Element::Switch(ele) => Some(ele.triples_at(junctions[0], junctions[1]).widen()),
+            Element::Behavioral(ele) => Some(ele.triples_at(junctions)),
             Element::VCVS(_) => None,
             Element::VCCS(_) => None,
             Element::CCCS(_) => None,
@@ -207,11 +373,19 @@ impl Element {
         }
     }
 
-    /// Returns the nonlinear triples. Nonlinear Triples are dependent on Vector x.
-    pub(crate) fn get_nonlinear_triples(&self, x_vec: &[Numeric]) -> Option<Triples<Numeric, 4>> {
+    /// Returns the nonlinear pairs, linearised at the junction voltages of the element.
+    pub(crate) fn get_nonlinear_pairs(&self, junctions: &[Numeric]) -> Option<Pairs<Numeric, 4>> {
         match self {
-            Element::Diode(ele) => Some(ele.triples(x_vec)),
-            Element::Mos0(ele) => Some(ele.triples(x_vec)),
+            Element::Diode(ele) => Some(ele.pairs_at(junctions[0]).widen()),
+            Element::Mos0(ele) => Some(ele.pairs_at(junctions[0], junctions[1]).widen()),
+            Element::Mos1(ele) => Some(
+                ele.pairs_at(junctions[0], junctions[1], junctions[2])
+                    .widen(),
+            ),
+            Element::Bjt(ele) => Some(ele.pairs_at(junctions[0], junctions[1])),
+            Element::Jfet(ele) => Some(ele.pairs_at(junctions[0], junctions[1])),
+            Element::Switch(ele) => Some(ele.pairs_at(junctions[0], junctions[1]).widen()),
+            Element::Behavioral(ele) => Some(ele.pairs_at(junctions)),
             Element::VCVS(_) => None,
             Element::VCCS(_) => None,
             Element::CCCS(_) => None,
@@ -220,15 +394,43 @@ impl Element {
         }
     }
 
-    /// Returns the nonlinear pairs of the element, if applicable.
-    pub(crate) fn get_nonlinear_pairs(&self, x_vec: &[Numeric]) -> Option<Pairs<Numeric, 2>> {
+    /// Returns the operating region of a nonlinear element at its junction voltages.
+    pub(crate) fn operating_region(&self, junctions: &[Numeric]) -> Option<&'static str> {
         match self {
-            Element::Diode(ele) => Some(ele.pairs(x_vec)),
-            Element::Mos0(ele) => Some(ele.pairs(x_vec)),
-            Element::VCVS(_) => None,
-            Element::VCCS(_) => None,
-            Element::CCCS(_) => None,
-            Element::CCVS(_) => None,
+            Element::Diode(ele) => Some(ele.region(junctions[0])),
+            Element::Mos0(ele) => Some(ele.region(junctions[0], junctions[1])),
+            Element::Mos1(ele) => Some(ele.region(junctions[0], junctions[1], junctions[2])),
+            Element::Bjt(ele) => Some(ele.region(junctions[0], junctions[1])),
+            Element::Jfet(ele) => Some(ele.region(junctions[0], junctions[1])),
+            Element::Switch(ele) => Some(ele.region(junctions[1])),
+            _ => None,
+        }
+    }
+
+    /// Checks if the element stamps into the equation of the variable at `idx`.
+    pub(crate) fn is_connected_to(&self, idx: usize) -> bool {
+        let in_triples = self
+            .get_triple_indices()
+            .is_some_and(|triples| triples.rows().any(|row| row == idx));
+        let in_nonlinear_triples = self
+            .get_nonlinear_triple_indices()
+            .is_some_and(|triples| triples.rows().any(|row| row == idx));
+        let in_pairs = self
+            .get_constant_pairs()
+            .is_some_and(|pairs| pairs.rows().any(|row| row == idx));
+        in_triples || in_nonlinear_triples || in_pairs
+    }
+
+    /// Returns the indices of the nonlinear triples for the element.
+    pub(crate) fn get_nonlinear_triple_indices(&self) -> Option<TripleIdx<24>> {
+        match self {
+            Element::Diode(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::Mos0(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::Mos1(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::Bjt(ele) => ele.triple_idx(),
+            Element::Jfet(ele) => ele.triple_idx(),
+            Element::Switch(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::Behavioral(ele) => ele.triple_idx(),
             _ => None,
         }
     }
@@ -237,26 +439,67 @@ impl Element {
     pub(crate) fn is_nonlinear(&self) -> bool {
         matches!(
             self,
-            Element::Diode(_) | Element::Mos0(_) | Element::CoupledInductors(_)
+            Element::Diode(_)
+                | Element::Mos0(_)
+                | Element::Mos1(_)
+                | Element::Bjt(_)
+                | Element::Jfet(_)
+                | Element::Switch(_)
+                | Element::Behavioral(_)
+                | Element::CoupledInductors(_)
         )
     }
 
+    /// Returns the small-signal triples of a nonlinear element at the frequency `freq`,
+    /// linearised at the operating point `x_op`.
+    pub(crate) fn get_small_signal_triples(
+        &self,
+        freq: Numeric,
+        x_op: &[Numeric],
+    ) -> Option<Triples<ComplexNumeric, 24>> {
+        match self {
+            Element::Diode(ele) => Some(ele.ac_triples(freq, x_op).widen()),
+            Element::Mos1(ele) => Some(ele.ac_triples(freq, x_op).widen()),
+            Element::Bjt(ele) => Some(ele.ac_triples(freq, x_op)),
+            Element::Jfet(ele) => Some(ele.ac_triples(freq, x_op)),
+            Element::Switch(ele) => Some(ele.ac_triples(x_op).widen()),
+            Element::Behavioral(ele) => Some(ele.small_signal_triples(x_op)),
+            _ => None,
+        }
+    }
+
+    /// Returns the indices of the internal nodes of the element, which are not connected to
+    /// other elements, like the nodes behind the terminal resistances of a transistor.
+    pub(crate) fn internal_nodes(&self) -> Vec<usize> {
+        match self {
+            Element::Diode(ele) => ele.internal_node_index().into_iter().collect(),
+            Element::Bjt(ele) => ele.internal_node_indices().collect(),
+            Element::Jfet(ele) => ele.internal_node_indices().collect(),
+            _ => Vec::new(),
+        }
+    }
+
     /// Returns the AC triples. AC Triples are dependent on frequency f.
-    pub(crate) fn get_ac_triples(&self, freq: Numeric) -> Option<Triples<ComplexNumeric, 4>> {
+    pub(crate) fn get_ac_triples(&self, freq: Numeric) -> Option<Triples<ComplexNumeric, 5>> {
         match self {
             Element::Diode(_) => None,
             Element::Mos0(_) => None,
-            Element::Capacitor(cap) => Some(cap.ac_triples(freq)),
+            Element::Mos1(_) => None,
+            Element::Bjt(_) => None,
+            Element::Jfet(_) => None,
+            Element::Switch(_) => None,
+            Element::Behavioral(ele) => ele.ac_triples().map(Triples::widen),
+            Element::Capacitor(cap) => Some(cap.ac_triples(freq).widen()),
             Element::Inductor(ind) => Some(ind.ac_triples(freq)),
-            Element::CoupledInductors(coupled) => Some(coupled.get_ac_triples(freq)),
-            Element::Resistor(res) => Some(res.ac_triples()),
-            Element::VSource(vsource) => Some(vsource.ac_triples()),
+            Element::CoupledInductors(coupled) => Some(coupled.get_ac_triples(freq).widen()),
+            Element::Resistor(res) => Some(res.ac_triples().widen()),
+            Element::VSource(vsource) => Some(vsource.ac_triples().widen()),
             Element::VSourceStep(_) => None,
-            Element::Gain(gain) => Some(gain.ac_triples()),
-            Element::VCVS(vcvs) => Some(vcvs.ac_triples()),
-            Element::VCCS(vccs) => Some(vccs.ac_triples()),
-            Element::CCCS(cccs) => Some(cccs.ac_triples()),
-            Element::CCVS(ccvs) => Some(ccvs.ac_triples()),
+            Element::Gain(gain) => Some(gain.ac_triples().widen()),
+            Element::VCVS(vcvs) => Some(vcvs.ac_triples().widen()),
+            Element::VCCS(vccs) => Some(vccs.ac_triples().widen()),
+            Element::CCCS(cccs) => Some(cccs.ac_triples().widen()),
+            Element::CCVS(ccvs) => Some(ccvs.ac_triples().widen()),
             Element::ISource(_) => None,
             Element::VSourceSin(_) => None,
         }
@@ -267,6 +510,11 @@ impl Element {
         match self {
             Element::Diode(_) => None,
             Element::Mos0(_) => None,
+            Element::Mos1(_) => None,
+            Element::Bjt(_) => None,
+            Element::Jfet(_) => None,
+            Element::Switch(_) => None,
+            Element::Behavioral(_) => None,
             Element::Capacitor(_) => None,
             Element::Inductor(_) => None,
             Element::Resistor(_) => None,
@@ -283,6 +531,193 @@ impl Element {
         }
     }
 
+    /// Applies the simulator settings, like the circuit temperature, to the element.
+    pub(crate) fn apply_options(&mut self, options: &SimulatorOptions) {
+        match self {
+            Element::Resistor(ele) => ele.apply_options(options),
+            Element::Diode(ele) => ele.apply_options(options),
+            Element::Mos0(ele) => ele.apply_options(options),
+            Element::Mos1(ele) => ele.apply_options(options),
+            Element::Bjt(ele) => ele.apply_options(options),
+            Element::Jfet(ele) => ele.apply_options(options),
+            Element::Behavioral(ele) => ele.apply_options(options),
+            _ => {}
+        }
+    }
+
+    /// Returns the branches the element connects its nodes with.
+    pub(crate) fn branches(&self) -> Vec<Branch> {
+        use BranchKind::*;
+        match self {
+            Element::Resistor(ele) => {
+                vec![Branch::new(Conductive, ele.node0_idx(), ele.node1_idx())]
+            }
+            Element::Diode(ele) => {
+                let mut branches = vec![Branch::new(Conductive, ele.ai_idx(), ele.c_idx())];
+                if let Some(internal) = ele.internal_node_index() {
+                    branches.push(Branch::new(Conductive, ele.a_idx(), Some(internal)));
+                }
+                branches
+            }
+            Element::Mos0(ele) => vec![
+                Branch::new(Conductive, ele.d_idx(), ele.s_idx()),
+                Branch::new(Control, ele.g_idx(), ele.s_idx()),
+            ],
+            Element::Mos1(ele) => vec![
+                Branch::new(Conductive, ele.d_idx(), ele.s_idx()),
+                Branch::new(Control, ele.g_idx(), ele.s_idx()),
+                Branch::new(Control, ele.b_idx(), ele.s_idx()),
+            ],
+            Element::Bjt(ele) => {
+                let mut branches = vec![
+                    Branch::new(Conductive, ele.bi_idx(), ele.ei_idx()),
+                    Branch::new(Conductive, ele.ci_idx(), ele.ei_idx()),
+                ];
+                branches.extend(
+                    ele.resistor_nodes().map(|(terminal, internal)| {
+                        Branch::new(Conductive, terminal, Some(internal))
+                    }),
+                );
+                branches
+            }
+            Element::Jfet(ele) => {
+                let mut branches = vec![
+                    Branch::new(Conductive, ele.di_idx(), ele.si_idx()),
+                    Branch::new(Conductive, ele.g_idx(), ele.si_idx()),
+                    Branch::new(Conductive, ele.g_idx(), ele.di_idx()),
+                ];
+                branches.extend(
+                    ele.resistor_nodes().map(|(terminal, internal)| {
+                        Branch::new(Conductive, terminal, Some(internal))
+                    }),
+                );
+                branches
+            }
+            Element::Switch(ele) => {
+                let mut branches = vec![Branch::new(Conductive, ele.node0_idx(), ele.node1_idx())];
+                if let Some((positive, negative)) = ele.control_node_indices() {
+                    branches.push(Branch::new(Control, positive, negative));
+                }
+                branches
+            }
+            Element::Behavioral(ele) => {
+                let kind = match ele.output() {
+                    BehavioralOutput::Voltage(_) => Voltage,
+                    BehavioralOutput::Current => Current,
+                };
+                let mut branches = vec![Branch::new(kind, ele.node0_idx(), ele.node1_idx())];
+                branches.extend(
+                    ele.input_node_indices()
+                        .map(|node| Branch::new(Control, Some(node), None)),
+                );
+                branches
+            }
+            Element::Inductor(ele) => vec![Branch::new(Voltage, ele.node0_idx(), ele.node1_idx())],
+            Element::VSource(ele) => vec![Branch::new(Voltage, ele.node0_idx(), ele.node1_idx())],
+            Element::VSourceSin(ele) => {
+                vec![Branch::new(Voltage, ele.node0_idx(), ele.node1_idx())]
+            }
+            Element::VSourceStep(ele) => {
+                vec![Branch::new(Voltage, ele.node0_idx(), ele.node1_idx())]
+            }
+            Element::Capacitor(ele) => vec![Branch::new(Current, ele.node0_idx(), ele.node1_idx())],
+            Element::ISource(ele) => vec![Branch::new(Current, ele.node0_idx(), ele.node1_idx())],
+            Element::Gain(ele) => vec![
+                Branch::new(Voltage, ele.output_idx(), None),
+                Branch::new(Control, ele.input_idx(), None),
+            ],
+            Element::VCVS(ele) => {
+                let (pos, neg, ctrl_pos, ctrl_neg) = ele.node_indices();
+                vec![
+                    Branch::new(Voltage, pos, neg),
+                    Branch::new(Control, ctrl_pos, ctrl_neg),
+                ]
+            }
+            Element::VCCS(ele) => {
+                let (pos, neg, ctrl_pos, ctrl_neg) = ele.node_indices();
+                vec![
+                    Branch::new(Current, pos, neg),
+                    Branch::new(Control, ctrl_pos, ctrl_neg),
+                ]
+            }
+            Element::CCCS(ele) => {
+                let (pos, neg) = ele.node_indices();
+                vec![Branch::new(Current, pos, neg)]
+            }
+            Element::CCVS(ele) => {
+                let (pos, neg) = ele.node_indices();
+                vec![Branch::new(Voltage, pos, neg)]
+            }
+            // The coupling is magnetic, the inductors themselves carry the branches
+            Element::CoupledInductors(_) => vec![],
+        }
+    }
+
+    /// Returns the branch, whose current controls the element, for current controlled sources.
+    /// The inner `None` marks a current controlled source without a controlling branch.
+    pub(crate) fn controlling_branch(&self) -> Option<Option<&Variable>> {
+        match self {
+            Element::CCCS(ele) => Some(ele.controlling_branch()),
+            Element::CCVS(ele) => Some(ele.controlling_branch()),
+            Element::Switch(ele) => ele.controlling_branch(),
+            _ => None,
+        }
+    }
+
+    /// Records the accepted solution `x_vec` in the elements with a memory, like the state of a
+    /// switch. Returns whether an element switched, which is a breakpoint of a transient analysis.
+    pub(crate) fn accept_solution(&mut self, x_vec: &[Numeric]) -> bool {
+        match self {
+            Element::Switch(ele) => ele.accept(x_vec),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the element, stamped at the junction voltages `junctions`, changes its
+    /// state at the solution `x_vec`.
+    pub(crate) fn switches_at(&self, x_vec: &[Numeric], junctions: &[Numeric]) -> bool {
+        match self {
+            Element::Switch(ele) => {
+                let old = [junctions[0], junctions[1]];
+                ele.limit_voltages(ele.voltages(x_vec), old).1
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the fraction of the step from `x_prev` to `x_new`, at which the element switches,
+    /// if it changes its state at `x_new`.
+    pub(crate) fn switch_crossing(&self, x_prev: &[Numeric], x_new: &[Numeric]) -> Option<Numeric> {
+        match self {
+            Element::Switch(ele) => ele.crossing(x_prev, x_new),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements with a memory to their initial state at the start of an analysis.
+    pub(crate) fn reset_state(&mut self) {
+        match self {
+            Element::Switch(ele) => ele.reset(),
+            Element::Behavioral(ele) => ele.set_time(Numeric::zero()),
+            _ => {}
+        }
+    }
+
+    /// Sets the simulation time of the elements depending on it, like behavioral sources.
+    pub(crate) fn set_time(&mut self, time: Numeric) {
+        if let Element::Behavioral(ele) = self {
+            ele.set_time(time);
+        }
+    }
+
+    /// Checks if the element is an independent voltage source.
+    pub(crate) fn is_voltage_source(&self) -> bool {
+        matches!(
+            self,
+            Element::VSource(_) | Element::VSourceSin(_) | Element::VSourceStep(_)
+        )
+    }
+
     /// Returns the name of the element.
     pub(crate) fn name(&self) -> Arc<str> {
         match self {
@@ -291,6 +726,11 @@ impl Element {
             Element::Resistor(ele) => ele.name(),
             Element::Diode(ele) => ele.name(),
             Element::Mos0(ele) => ele.name(),
+            Element::Mos1(ele) => ele.name(),
+            Element::Bjt(ele) => ele.name(),
+            Element::Jfet(ele) => ele.name(),
+            Element::Switch(ele) => ele.name(),
+            Element::Behavioral(ele) => ele.name(),
             Element::VSource(ele) => ele.name(),
             Element::VSourceStep(ele) => ele.name(),
             Element::ISource(ele) => ele.name(),
@@ -305,43 +745,55 @@ impl Element {
     }
 
     /// Returns the indices of the triples for the element.
-    pub(crate) fn get_triple_indices(&self) -> Option<TripleIdx<4>> {
+    pub(crate) fn get_triple_indices(&self) -> Option<TripleIdx<5>> {
         match self {
-            Element::Capacitor(ele) => ele.triple_idx(),
+            Element::Capacitor(ele) => ele.triple_idx().map(TripleIdx::widen),
             Element::Inductor(ele) => ele.triple_idx(),
-            Element::CoupledInductors(ele) => ele.get_triple_indices(),
-            Element::Resistor(ele) => ele.triple_idx(),
-            Element::Diode(ele) => ele.triple_idx(),
-            Element::Mos0(ele) => ele.triple_idx(),
-            Element::VSource(ele) => ele.triple_idx(),
-            Element::VSourceStep(ele) => ele.triple_idx(),
-            Element::Gain(ele) => ele.triple_idx(),
-            Element::VCVS(ele) => ele.triple_idx(),
-            Element::VCCS(ele) => ele.triple_idx(),
-            Element::CCCS(ele) => ele.triple_idx(),
-            Element::CCVS(ele) => ele.triple_idx(),
+            Element::CoupledInductors(ele) => ele.get_triple_indices().map(TripleIdx::widen),
+            Element::Resistor(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::Diode(_) => None,
+            Element::Mos0(_) => None,
+            Element::Mos1(_) => None,
+            Element::Bjt(_) => None,
+            Element::Jfet(_) => None,
+            Element::Switch(_) => None,
+            Element::Behavioral(ele) => ele.constant_triple_idx().map(TripleIdx::widen),
+            Element::VSource(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::VSourceStep(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::Gain(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::VCVS(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::VCCS(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::CCCS(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::CCVS(ele) => ele.triple_idx().map(TripleIdx::widen),
             Element::ISource(_) => None,
-            Element::VSourceSin(ele) => ele.triple_idx(),
+            Element::VSourceSin(ele) => ele.triple_idx().map(TripleIdx::widen),
         }
     }
     /// Returns the indices of the complex triples for the element.
-    pub(crate) fn get_cplx_triple_indices(&self) -> Option<TripleIdx<4>> {
+    pub(crate) fn get_cplx_triple_indices(&self) -> Option<TripleIdx<5>> {
         match self {
             Element::Diode(_) => None,
             Element::Mos0(_) => None,
-            Element::Capacitor(cap) => cap.triple_idx(),
+            Element::Mos1(_) => None,
+            Element::Bjt(_) => None,
+            Element::Jfet(_) => None,
+            Element::Switch(_) => None,
+            Element::Behavioral(ele) => ele.constant_triple_idx().map(TripleIdx::widen),
+            Element::Capacitor(cap) => cap.triple_idx().map(TripleIdx::widen),
             Element::Inductor(ind) => ind.triple_idx(),
-            Element::CoupledInductors(coupled) => coupled.get_cplx_triple_indices(),
-            Element::Resistor(res) => res.triple_idx(),
-            Element::VSource(vsource) => vsource.triple_idx(),
-            Element::VSourceStep(vsource) => vsource.triple_idx(),
-            Element::Gain(ele) => ele.triple_idx(),
-            Element::VCVS(ele) => ele.triple_idx(),
-            Element::VCCS(ele) => ele.triple_idx(),
-            Element::CCCS(ele) => ele.triple_idx(),
-            Element::CCVS(ele) => ele.triple_idx(),
+            Element::CoupledInductors(coupled) => {
+                coupled.get_cplx_triple_indices().map(TripleIdx::widen)
+            }
+            Element::Resistor(res) => res.triple_idx().map(TripleIdx::widen),
+            Element::VSource(vsource) => vsource.triple_idx().map(TripleIdx::widen),
+            Element::VSourceStep(vsource) => vsource.triple_idx().map(TripleIdx::widen),
+            Element::Gain(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::VCVS(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::VCCS(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::CCCS(ele) => ele.triple_idx().map(TripleIdx::widen),
+            Element::CCVS(ele) => ele.triple_idx().map(TripleIdx::widen),
             Element::ISource(_) => None,
-            Element::VSourceSin(ele) => ele.triple_idx(),
+            Element::VSourceSin(ele) => ele.triple_idx().map(TripleIdx::widen),
         }
     }
 
@@ -488,5 +940,8 @@ fn has_valid_connections(node0_idx: Option<usize>, node1_idx: Option<usize>) ->
 
 #[cfg(test)]
 mod tests {
+    pub mod charge_tests;
     pub mod integration_tests;
+    pub mod limiting_tests;
+    pub mod temperature_tests;
 }
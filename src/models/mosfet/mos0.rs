@@ -2,7 +2,11 @@ use std::sync::Arc;
 
 use num::Zero;
 
+use super::super::charge::{voltage_between, Charge};
+use super::super::limiting;
+use super::super::temperature::{self, DeviceTemperature};
 use super::super::*;
+use crate::sim::options::SimulatorOptions;
 
 /// A structure representing a Mos0 Mosfet.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -12,6 +16,11 @@ pub struct Mos0Bundle {
     drain: Option<Variable>,
     source: Option<Variable>,
     options: Mos0Options,
+    temperature: DeviceTemperature,
+    /// The threshold voltage at the temperature of the transistor
+    vt0: Numeric,
+    /// The transconductance at the temperature of the transistor
+    kp: Numeric,
 }
 
 /// An enum representing possible Mosfet MOS0 options.
@@ -21,6 +30,14 @@ pub struct Mos0Options {
     vt0: Numeric,
     /// Tranceconductance
     kp: Numeric,
+    /// Gate-source capacitance
+    cgs: Numeric,
+    /// Gate-drain capacitance
+    cgd: Numeric,
+    /// Temperature coefficient of the threshold voltage in V/K
+    tcv: Numeric,
+    /// Temperature exponent of the mobility
+    bex: Numeric,
 }
 
 impl Default for Mos0Options {
@@ -28,10 +45,30 @@ impl Default for Mos0Options {
         Self {
             vt0: 0.43,
             kp: 118e-6,
+            cgs: 0.0,
+            cgd: 0.0,
+            tcv: 1e-3,
+            bex: -1.5,
         }
     }
 }
 
+impl Mos0Options {
+    /// Sets the gate-source and gate-drain capacitances.
+    pub fn with_gate_capacitances(mut self, cgs: Numeric, cgd: Numeric) -> Self {
+        self.cgs = cgs;
+        self.cgd = cgd;
+        self
+    }
+
+    /// Sets the temperature coefficient of the threshold voltage and the mobility exponent.
+    pub fn with_temperature_scaling(mut self, tcv: Numeric, bex: Numeric) -> Self {
+        self.tcv = tcv;
+        self.bex = bex;
+        self
+    }
+}
+
 impl Mos0Bundle {
     pub fn new(
         name: Arc<str>,
@@ -40,89 +77,202 @@ impl Mos0Bundle {
         source: Option<Variable>,
         options: Option<Mos0Options>,
     ) -> Mos0Bundle {
+        let options = options.unwrap_or_default();
         Mos0Bundle {
             name,
             gate,
             drain,
             source,
-            options: options.unwrap_or_default(),
+            vt0: options.vt0,
+            kp: options.kp,
+            options,
+            temperature: DeviceTemperature::default(),
         }
     }
 
+    /// Sets the instance temperature of the transistor.
+    pub fn set_temperature(&mut self, temperature: DeviceTemperature) {
+        self.temperature = temperature;
+    }
+
+    /// Scales the threshold voltage and the transconductance to the temperature of the
+    /// transistor. The transconductance follows the mobility of the channel.
+    pub fn apply_options(&mut self, options: &SimulatorOptions) {
+        let temp = self.temperature.kelvin(options);
+        let tnom = options.tnom_kelvin();
+        self.vt0 = self.options.vt0 - self.options.tcv * (temp - tnom);
+        self.kp = temperature::mobility(self.options.kp, self.options.bex, temp, tnom);
+    }
+
     /// Returns the name of the diode bundle.
     pub fn name(&self) -> Arc<str> {
         self.name.clone()
     }
 
     /// Returns a reference to the triples representing matrix A.
-    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 4> {
-        let kp = self.options.kp;
-        let vt0 = self.options.vt0;
-        let g_voltage = match self.g_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
-        };
-        let s_voltage = match self.s_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
-        };
-        let v_gs = g_voltage - s_voltage;
-        let v_gs_eff = v_gs - vt0;
-
-        let g0 = kp * v_gs_eff * v_gs_eff;
-
-        match (self.d_idx(), self.s_idx()) {
-            (None, None) => Triples::new(&[]),
-            (None, Some(s_idx)) => Triples::new(&[(s_idx, s_idx, g0)]),
-            (Some(d_idx), None) => Triples::new(&[(d_idx, d_idx, g0)]),
-            (Some(d_idx), Some(s_idx)) => Triples::new(&[
-                (d_idx, d_idx, g0),
-                (s_idx, s_idx, g0),
-                (d_idx, s_idx, -g0),
-                (s_idx, d_idx, -g0),
-            ]),
-        }
+    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 6> {
+        let [v_gs, v_ds] = self.voltages(x_vec);
+        self.triples_at(v_gs, v_ds)
+    }
+
+    /// Returns the triples of the output conductance and transconductance linearised at
+    /// `v_gs` and `v_ds`.
+    pub fn triples_at(&self, v_gs: Numeric, v_ds: Numeric) -> Triples<Numeric, 6> {
+        let op = self.operating_point(v_gs, v_ds);
+        let (d_idx, s_idx) = op.channel(self);
+
+        // The drain current depends on v(d) - v(s) by gds and on v(g) - v(s) by gm
+        let entries = [
+            (d_idx, d_idx, op.gds),
+            (d_idx, s_idx, -op.gds - op.gm),
+            (d_idx, self.g_idx(), op.gm),
+            (s_idx, d_idx, -op.gds),
+            (s_idx, s_idx, op.gds + op.gm),
+            (s_idx, self.g_idx(), -op.gm),
+        ];
+        let triples: Vec<_> = entries
+            .into_iter()
+            .filter_map(|(row, col, value)| Some((row?, col?, value)))
+            .collect();
+        Triples::new(&triples)
     }
 
     /// Returns a reference to the triples representing matrix A.
-    pub fn triple_idx(&self) -> Option<TripleIdx<4>> {
-        match (self.d_idx(), self.s_idx()) {
-            (None, None) => Some(TripleIdx::new(&[])),
-            (None, Some(s_idx)) => Some(TripleIdx::new(&[(s_idx, s_idx)])),
-            (Some(d_idx), None) => Some(TripleIdx::new(&[(d_idx, d_idx)])),
-            (Some(d_idx), Some(s_idx)) => Some(TripleIdx::new(&[
-                (d_idx, d_idx),
-                (s_idx, s_idx),
-                (d_idx, s_idx),
-                (s_idx, d_idx),
-            ])),
-        }
+    pub fn triple_idx(&self) -> Option<TripleIdx<6>> {
+        // Either terminal of the channel may act as drain, so both get the gate entry
+        let entries = [
+            (self.d_idx(), self.d_idx()),
+            (self.d_idx(), self.s_idx()),
+            (self.d_idx(), self.g_idx()),
+            (self.s_idx(), self.d_idx()),
+            (self.s_idx(), self.s_idx()),
+            (self.s_idx(), self.g_idx()),
+        ];
+        let indices: Vec<_> = entries
+            .into_iter()
+            .filter_map(|(row, col)| Some((row?, col?)))
+            .collect();
+        Some(TripleIdx::new(&indices))
     }
 
     /// Returns a reference to the pairs representing vector b.
     pub fn pairs(&self, x_vec: &[Numeric]) -> Pairs<Numeric, 2> {
-        let kp = self.options.kp;
-        let vt0 = self.options.vt0;
-        let g_voltage = match self.g_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
+        let [v_gs, v_ds] = self.voltages(x_vec);
+        self.pairs_at(v_gs, v_ds)
+    }
+
+    /// Returns the pairs of the equivalent current source `id - gds * vds - gm * vgs`
+    /// linearised at `v_gs` and `v_ds`.
+    pub fn pairs_at(&self, v_gs: Numeric, v_ds: Numeric) -> Pairs<Numeric, 2> {
+        let op = self.operating_point(v_gs, v_ds);
+        let (d_idx, s_idx) = op.channel(self);
+        let i_eq = op.id - op.gds * op.v_ds - op.gm * op.v_gs;
+
+        match (d_idx, s_idx) {
+            (None, None) => Pairs::new(&[]),
+            (None, Some(s_idx)) => Pairs::new(&[(s_idx, i_eq)]),
+            (Some(d_idx), None) => Pairs::new(&[(d_idx, -i_eq)]),
+            (Some(d_idx), Some(s_idx)) => Pairs::new(&[(d_idx, -i_eq), (s_idx, i_eq)]),
+        }
+    }
+
+    /// Returns the gate-source and drain-source voltages at the solution `x_vec`.
+    pub fn voltages(&self, x_vec: &[Numeric]) -> [Numeric; 2] {
+        [
+            voltage_between(self.g_idx(), self.s_idx(), x_vec),
+            voltage_between(self.d_idx(), self.s_idx(), x_vec),
+        ]
+    }
+
+    /// Limits the Newton steps of the gate-source and drain-source voltages.
+    /// Returns the limited voltages and whether any of them was limited.
+    pub fn limit_voltages(&self, new: [Numeric; 2], old: [Numeric; 2]) -> ([Numeric; 2], bool) {
+        let vto = self.vt0;
+        let [v_gs_old, v_ds_old] = old;
+        let [mut v_gs, mut v_ds] = new;
+        let v_gd = v_gs - v_ds;
+
+        // The limits are defined for the forward mode, so they are applied to the terminal
+        // acting as source, as in SPICE. The drain-source voltage follows the limited gate
+        // voltage before it is limited itself.
+        let (gs_limited, ds_limited) = if v_ds_old >= 0.0 {
+            let (gs, gs_limited) = limiting::fet_gate(v_gs, v_gs_old, vto);
+            let (ds, ds_limited) = limiting::fet_drain(gs - v_gd, v_ds_old);
+            v_gs = gs;
+            v_ds = ds;
+            (gs_limited, ds_limited)
+        } else {
+            let v_gd_old = v_gs_old - v_ds_old;
+            let (gd, gs_limited) = limiting::fet_gate(v_gd, v_gd_old, vto);
+            let (sd, ds_limited) = limiting::fet_drain(gd - v_gs, -v_ds_old);
+            v_ds = -sd;
+            v_gs = gd + v_ds;
+            (gs_limited, ds_limited)
+        };
+
+        ([v_gs, v_ds], gs_limited || ds_limited)
+    }
+
+    /// Returns the operating region at `v_gs` and `v_ds`.
+    pub fn region(&self, v_gs: Numeric, v_ds: Numeric) -> &'static str {
+        let op = self.operating_point(v_gs, v_ds);
+        // In reverse mode drain and source have swapped their roles
+        match (op.region, op.reverse) {
+            ("linear", true) => "linear, reverse",
+            ("saturation", true) => "saturation, reverse",
+            (region, _) => region,
+        }
+    }
+
+    /// Evaluates the square law at `v_gs` and `v_ds`. For negative `v_ds` drain and source
+    /// swap their roles.
+    fn operating_point(&self, v_gs: Numeric, v_ds: Numeric) -> OperatingPoint {
+        let reverse = v_ds < Numeric::zero();
+        let (v_gs, v_ds) = if reverse {
+            (v_gs - v_ds, -v_ds)
+        } else {
+            (v_gs, v_ds)
         };
-        let s_voltage = match self.s_idx() {
-            Some(idx) => x_vec[idx],
-            None => Numeric::zero(),
+
+        let kp = self.kp;
+        let v_ov = v_gs - self.vt0;
+        let (region, id, gm, gds) = if v_ov <= Numeric::zero() {
+            ("cutoff", Numeric::zero(), Numeric::zero(), Numeric::zero())
+        } else if v_ds >= v_ov {
+            (
+                "saturation",
+                0.5 * kp * v_ov * v_ov,
+                kp * v_ov,
+                Numeric::zero(),
+            )
+        } else {
+            (
+                "linear",
+                kp * (v_ov * v_ds - 0.5 * v_ds * v_ds),
+                kp * v_ds,
+                kp * (v_ov - v_ds),
+            )
         };
-        let v_gs = g_voltage - s_voltage;
-        let v_gs_eff = v_gs - vt0;
-        let i_ds = kp * v_gs_eff;
 
-        match (self.d_idx(), self.s_idx()) {
-            (None, None) => Pairs::new(&[]),
-            (None, Some(s_idx)) => Pairs::new(&[(s_idx, -i_ds)]),
-            (Some(d_idx), None) => Pairs::new(&[(d_idx, i_ds)]),
-            (Some(d_idx), Some(s_idx)) => Pairs::new(&[(d_idx, i_ds), (s_idx, -i_ds)]),
+        OperatingPoint {
+            region,
+            reverse,
+            v_gs,
+            v_ds,
+            id,
+            gm,
+            gds,
         }
     }
 
+    /// Returns the gate-source and gate-drain charges at the solution `x_vec`.
+    pub fn charges(&self, x_vec: &[Numeric]) -> [Charge; 2] {
+        [
+            Charge::linear(self.g_idx(), self.s_idx(), self.options.cgs, x_vec),
+            Charge::linear(self.g_idx(), self.d_idx(), self.options.cgd, x_vec),
+        ]
+    }
+
     pub fn g_idx(&self) -> Option<usize> {
         self.gate.as_ref().map(|v| v.idx())
     }
@@ -135,3 +285,28 @@ impl Mos0Bundle {
         self.source.as_ref().map(|v| v.idx())
     }
 }
+
+/// The linearisation of the channel current in the mode the transistor operates in.
+#[derive(Debug, Clone, Copy)]
+struct OperatingPoint {
+    /// The name of the operating region
+    region: &'static str,
+    /// Whether drain and source have swapped their roles
+    reverse: bool,
+    v_gs: Numeric,
+    v_ds: Numeric,
+    id: Numeric,
+    gm: Numeric,
+    gds: Numeric,
+}
+
+impl OperatingPoint {
+    /// Returns the indices of the terminals acting as drain and source.
+    fn channel(&self, mosfet: &Mos0Bundle) -> (Option<usize>, Option<usize>) {
+        if self.reverse {
+            (mosfet.s_idx(), mosfet.d_idx())
+        } else {
+            (mosfet.d_idx(), mosfet.s_idx())
+        }
+    }
+}
@@ -0,0 +1,606 @@
+use std::ops::Neg;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use num::traits::FloatConst;
+use num::Zero;
+use serde::{Deserialize, Serialize};
+
+use super::super::charge::{voltage_between, Charge};
+use super::super::limiting;
+use super::super::temperature::{self, DeviceTemperature};
+use super::super::*;
+use crate::sim::options::SimulatorOptions;
+
+/// The permittivity of silicon dioxide in F/m.
+const EPS_OX: Numeric = 3.9 * 8.854_187_817e-12;
+
+/// The rows and columns of the terminals in the conductance matrix of the transistor.
+const DRAIN: usize = 0;
+const GATE: usize = 1;
+const SOURCE: usize = 2;
+const BULK: usize = 3;
+
+/// A structure representing a four-terminal MOSFET with the level-1 Shichman-Hodges model.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Mos1Bundle {
+    name: Arc<str>,
+    drain: Option<Variable>,
+    gate: Option<Variable>,
+    source: Option<Variable>,
+    bulk: Option<Variable>,
+    /// The name of the `.model` card of the transistor
+    model: Option<Arc<str>>,
+    options: Mos1Options,
+    /// The channel width in m
+    w: Numeric,
+    /// The channel length in m
+    l: Numeric,
+    temperature: DeviceTemperature,
+    /// The zero-bias threshold voltage at the temperature of the transistor
+    vto: Numeric,
+    /// The transconductance parameter at the temperature of the transistor
+    kp: Numeric,
+}
+
+/// The polarity of a MOSFET.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MosPolarity {
+    #[default]
+    Nmos,
+    Pmos,
+}
+
+impl MosPolarity {
+    /// Returns the sign of the terminal voltages and currents of an NMOS transistor.
+    fn sign(self) -> Numeric {
+        match self {
+            MosPolarity::Nmos => 1.0,
+            MosPolarity::Pmos => -1.0,
+        }
+    }
+}
+
+impl FromStr for MosPolarity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nmos" => Ok(MosPolarity::Nmos),
+            "pmos" => Ok(MosPolarity::Pmos),
+            _ => Err(format!("Unknown MOSFET model type '{s}'. Use NMOS or PMOS")),
+        }
+    }
+}
+
+/// A struct representing the level-1 parameters of a MOSFET, as in a SPICE `.model` card.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Mos1Options {
+    polarity: MosPolarity,
+    /// Zero-bias threshold voltage, negative for an enhancement PMOS
+    vto: Numeric,
+    /// Transconductance parameter in A/V²
+    kp: Numeric,
+    /// Bulk threshold parameter in √V
+    gamma: Numeric,
+    /// Surface potential
+    phi: Numeric,
+    /// Channel-length modulation in 1/V
+    lambda: Numeric,
+    /// Oxide thickness in m. Zero disables the Meyer capacitances of the channel.
+    tox: Numeric,
+    /// Gate-source overlap capacitance per channel width
+    cgso: Numeric,
+    /// Gate-drain overlap capacitance per channel width
+    cgdo: Numeric,
+    /// Gate-bulk overlap capacitance per channel length
+    cgbo: Numeric,
+    /// Temperature coefficient of the threshold voltage in V/K
+    tcv: Numeric,
+    /// Temperature exponent of the mobility
+    bex: Numeric,
+}
+
+impl Default for Mos1Options {
+    fn default() -> Self {
+        Self {
+            polarity: MosPolarity::Nmos,
+            vto: 0.0,
+            kp: 2e-5,
+            gamma: 0.0,
+            phi: 0.6,
+            lambda: 0.0,
+            tox: 0.0,
+            cgso: 0.0,
+            cgdo: 0.0,
+            cgbo: 0.0,
+            tcv: 1e-3,
+            bex: -1.5,
+        }
+    }
+}
+
+impl Mos1Options {
+    /// Sets the polarity of the transistor.
+    pub fn with_polarity(mut self, polarity: MosPolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Sets the model parameter `name` with its SPICE name, like `VTO` or `LAMBDA`.
+    /// Returns false if `name` is not a parameter of the model.
+    pub fn set_parameter(&mut self, name: &str, value: Numeric) -> bool {
+        let parameter = match name.to_ascii_lowercase().as_str() {
+            "vto" | "vt0" => &mut self.vto,
+            "kp" => &mut self.kp,
+            "gamma" => &mut self.gamma,
+            "phi" => &mut self.phi,
+            "lambda" => &mut self.lambda,
+            "tox" => &mut self.tox,
+            "cgso" => &mut self.cgso,
+            "cgdo" => &mut self.cgdo,
+            "cgbo" => &mut self.cgbo,
+            "tcv" => &mut self.tcv,
+            "bex" => &mut self.bex,
+            _ => return false,
+        };
+        *parameter = value;
+        true
+    }
+
+    /// Returns the gate oxide capacitance per area, which is zero without `tox`.
+    fn cox(&self) -> Numeric {
+        if self.tox > 0.0 {
+            EPS_OX / self.tox
+        } else {
+            Numeric::zero()
+        }
+    }
+}
+
+impl Mos1Bundle {
+    /// The channel width and length in m without `W=` and `L=`, as in SPICE.
+    pub const DEFAULT_GEOMETRY: Numeric = 1e-4;
+
+    /// Creates a new `Mos1Bundle` object with the terminals in SPICE order.
+    pub fn new(
+        name: Arc<str>,
+        drain: Option<Variable>,
+        gate: Option<Variable>,
+        source: Option<Variable>,
+        bulk: Option<Variable>,
+        options: Option<Mos1Options>,
+    ) -> Mos1Bundle {
+        let options = options.unwrap_or_default();
+
+        Mos1Bundle {
+            name,
+            drain,
+            gate,
+            source,
+            bulk,
+            model: None,
+            vto: options.vto,
+            kp: options.kp,
+            options,
+            w: Self::DEFAULT_GEOMETRY,
+            l: Self::DEFAULT_GEOMETRY,
+            temperature: DeviceTemperature::default(),
+        }
+    }
+
+    /// Sets the name of the `.model` card of the transistor.
+    pub fn set_model(&mut self, model: Arc<str>) {
+        self.model = Some(model);
+    }
+
+    /// Returns the name of the `.model` card of the transistor.
+    pub fn model(&self) -> Option<Arc<str>> {
+        self.model.clone()
+    }
+
+    /// Replaces the model parameters of the transistor.
+    pub fn set_options(&mut self, options: Mos1Options) {
+        self.vto = options.vto;
+        self.kp = options.kp;
+        self.options = options;
+    }
+
+    /// Returns the model parameters of the transistor.
+    pub fn options(&self) -> &Mos1Options {
+        &self.options
+    }
+
+    /// Sets the channel width and length in m.
+    pub fn set_geometry(&mut self, w: Numeric, l: Numeric) {
+        self.w = w;
+        self.l = l;
+    }
+
+    /// Sets the instance temperature of the transistor.
+    pub fn set_temperature(&mut self, temperature: DeviceTemperature) {
+        self.temperature = temperature;
+    }
+
+    /// Scales the threshold voltage and the transconductance parameter to the temperature of
+    /// the transistor. The magnitude of the threshold drops by `tcv` per kelvin and the
+    /// transconductance follows the mobility of the channel. The surface potential `PHI` keeps
+    /// its nominal value, `tcv` covers the whole shift of the threshold as in level 0.
+    pub fn apply_options(&mut self, options: &SimulatorOptions) {
+        let temp = self.temperature.kelvin(options);
+        let tnom = options.tnom_kelvin();
+        let sign = self.options.polarity.sign();
+        self.vto = self.options.vto - sign * self.options.tcv * (temp - tnom);
+        self.kp = temperature::mobility(self.options.kp, self.options.bex, temp, tnom);
+    }
+
+    /// Returns the name of the MOSFET bundle.
+    pub fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    /// Returns a reference to the triples representing matrix A.
+    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 16> {
+        let [v_gs, v_ds, v_bs] = self.voltages(x_vec);
+        self.triples_at(v_gs, v_ds, v_bs)
+    }
+
+    /// Returns the triples of the output conductance, the transconductance and the bulk
+    /// transconductance linearised at `v_gs`, `v_ds` and `v_bs`.
+    pub fn triples_at(&self, v_gs: Numeric, v_ds: Numeric, v_bs: Numeric) -> Triples<Numeric, 16> {
+        self.stamps(self.operating_point(v_gs, v_ds, v_bs).conductances())
+    }
+
+    /// Returns the indices of the triples representing matrix A.
+    pub fn triple_idx(&self) -> Option<TripleIdx<16>> {
+        let indices: Vec<_> = self
+            .stamps([[0.0; 4]; 4])
+            .into_iter()
+            .map(|&(row, col, _)| (row, col))
+            .collect();
+        Some(TripleIdx::new(&indices))
+    }
+
+    /// Returns a reference to the pairs representing vector b.
+    pub fn pairs(&self, x_vec: &[Numeric]) -> Pairs<Numeric, 2> {
+        let [v_gs, v_ds, v_bs] = self.voltages(x_vec);
+        self.pairs_at(v_gs, v_ds, v_bs)
+    }
+
+    /// Returns the pairs of the equivalent current source `id - gds * vds - gm * vgs -
+    /// gmbs * vbs` linearised at `v_gs`, `v_ds` and `v_bs`.
+    pub fn pairs_at(&self, v_gs: Numeric, v_ds: Numeric, v_bs: Numeric) -> Pairs<Numeric, 2> {
+        let op = self.operating_point(v_gs, v_ds, v_bs);
+        let (d_idx, s_idx) = op.channel(self);
+        let sign = self.options.polarity.sign();
+        let i_eq = sign * (op.id - op.gds * op.v_ds - op.gm * op.v_gs - op.gmbs * op.v_bs);
+
+        match (d_idx, s_idx) {
+            (None, None) => Pairs::new(&[]),
+            (None, Some(s_idx)) => Pairs::new(&[(s_idx, i_eq)]),
+            (Some(d_idx), None) => Pairs::new(&[(d_idx, -i_eq)]),
+            (Some(d_idx), Some(s_idx)) => Pairs::new(&[(d_idx, -i_eq), (s_idx, i_eq)]),
+        }
+    }
+
+    /// Returns the small-signal triples at the frequency `freq` in Hz, linearised at the
+    /// operating point `x_vec`, with the gate capacitances as admittances.
+    pub fn ac_triples(&self, freq: Numeric, x_vec: &[Numeric]) -> Triples<ComplexNumeric, 16> {
+        let [v_gs, v_ds, v_bs] = self.voltages(x_vec);
+        let conductances = self.operating_point(v_gs, v_ds, v_bs).conductances();
+        let omega = 2.0 * Numeric::PI() * freq;
+        let [c_gs, c_gd, c_gb] = self.capacitances(x_vec);
+
+        let mut admittances = conductances.map(|row| row.map(ComplexNumeric::from));
+        for (node, c) in [(SOURCE, c_gs), (DRAIN, c_gd), (BULK, c_gb)] {
+            let y = ComplexNumeric::new(0.0, omega * c);
+            admittances[GATE][GATE] += y;
+            admittances[node][node] += y;
+            admittances[GATE][node] -= y;
+            admittances[node][GATE] -= y;
+        }
+        self.stamps(admittances)
+    }
+
+    /// Returns the triples of the admittance matrix between drain, gate, source and bulk.
+    /// All entries are stamped, as either terminal of the channel may act as drain.
+    fn stamps<T>(&self, matrix: [[T; 4]; 4]) -> Triples<T, 16>
+    where
+        T: Copy + Default + From<Numeric> + Neg<Output = T>,
+    {
+        let nodes = self.nodes();
+        let mut triples = Vec::with_capacity(16);
+        for (row, values) in nodes.iter().zip(matrix) {
+            for (col, value) in nodes.iter().zip(values) {
+                if let (Some(row), Some(col)) = (row, col) {
+                    triples.push((*row, *col, value));
+                }
+            }
+        }
+        Triples::new(&triples)
+    }
+
+    /// Returns the gate-source, drain-source and bulk-source voltages of an NMOS transistor at
+    /// the solution `x_vec`. The voltages of a PMOS transistor are inverted.
+    pub fn voltages(&self, x_vec: &[Numeric]) -> [Numeric; 3] {
+        let sign = self.options.polarity.sign();
+        [
+            sign * voltage_between(self.g_idx(), self.s_idx(), x_vec),
+            sign * voltage_between(self.d_idx(), self.s_idx(), x_vec),
+            sign * voltage_between(self.b_idx(), self.s_idx(), x_vec),
+        ]
+    }
+
+    /// Limits the Newton steps of the gate-source and drain-source voltages.
+    /// Returns the limited voltages and whether any of them was limited.
+    pub fn limit_voltages(&self, new: [Numeric; 3], old: [Numeric; 3]) -> ([Numeric; 3], bool) {
+        let [v_gs_old, v_ds_old, v_bs_old] = old;
+        let [mut v_gs, mut v_ds, v_bs] = new;
+        let v_gd = v_gs - v_ds;
+
+        // The limits are defined for the forward mode, so they are applied to the terminal
+        // acting as source, as in SPICE. They are anchored at the threshold including the body
+        // effect of the previous iteration.
+        let (gs_limited, ds_limited) = if v_ds_old >= 0.0 {
+            let (von, _) = self.threshold(v_bs_old);
+            let (gs, gs_limited) = limiting::fet_gate(v_gs, v_gs_old, von);
+            let (ds, ds_limited) = limiting::fet_drain(gs - v_gd, v_ds_old);
+            v_gs = gs;
+            v_ds = ds;
+            (gs_limited, ds_limited)
+        } else {
+            let (von, _) = self.threshold(v_bs_old - v_ds_old);
+            let v_gd_old = v_gs_old - v_ds_old;
+            let (gd, gs_limited) = limiting::fet_gate(v_gd, v_gd_old, von);
+            let (sd, ds_limited) = limiting::fet_drain(gd - v_gs, -v_ds_old);
+            v_ds = -sd;
+            v_gs = gd + v_ds;
+            (gs_limited, ds_limited)
+        };
+
+        ([v_gs, v_ds, v_bs], gs_limited || ds_limited)
+    }
+
+    /// Returns the operating region at `v_gs`, `v_ds` and `v_bs`.
+    pub fn region(&self, v_gs: Numeric, v_ds: Numeric, v_bs: Numeric) -> &'static str {
+        let op = self.operating_point(v_gs, v_ds, v_bs);
+        // In reverse mode drain and source have swapped their roles
+        match (op.region, op.reverse) {
+            ("linear", true) => "linear, reverse",
+            ("saturation", true) => "saturation, reverse",
+            (region, _) => region,
+        }
+    }
+
+    /// Returns the threshold voltage at `v_bs` in the forward mode and its derivative by
+    /// `v_bs`, which is raised by the body effect.
+    fn threshold(&self, v_bs: Numeric) -> (Numeric, Numeric) {
+        let Mos1Options {
+            gamma,
+            phi,
+            polarity,
+            ..
+        } = self.options;
+        let vto = self.vto;
+        let sqrt_phi = phi.sqrt();
+        // The square root is continued by its tangent for a forward biased bulk, as in SPICE
+        let (sarg, dsarg) = if v_bs <= 0.0 {
+            let sarg = (phi - v_bs).sqrt();
+            (sarg, -0.5 / sarg)
+        } else {
+            let sarg = sqrt_phi / (1.0 + 0.5 * v_bs / phi);
+            (sarg, -sarg * sarg / (2.0 * phi * sqrt_phi))
+        };
+        (
+            polarity.sign() * vto + gamma * (sarg - sqrt_phi),
+            gamma * dsarg,
+        )
+    }
+
+    /// Evaluates the Shichman-Hodges equations at `v_gs`, `v_ds` and `v_bs`. For negative
+    /// `v_ds` drain and source swap their roles.
+    fn operating_point(&self, v_gs: Numeric, v_ds: Numeric, v_bs: Numeric) -> OperatingPoint {
+        let reverse = v_ds < Numeric::zero();
+        let (v_gs, v_ds, v_bs) = if reverse {
+            (v_gs - v_ds, -v_ds, v_bs - v_ds)
+        } else {
+            (v_gs, v_ds, v_bs)
+        };
+
+        let (von, dvon_dvbs) = self.threshold(v_bs);
+        let beta = self.kp * self.w / self.l;
+        let lambda = self.options.lambda;
+        let clm = 1.0 + lambda * v_ds;
+        let v_gst = v_gs - von;
+        let (region, id, gm, gds) = if v_gst <= Numeric::zero() {
+            ("cutoff", Numeric::zero(), Numeric::zero(), Numeric::zero())
+        } else if v_ds >= v_gst {
+            (
+                "saturation",
+                0.5 * beta * v_gst * v_gst * clm,
+                beta * v_gst * clm,
+                0.5 * beta * v_gst * v_gst * lambda,
+            )
+        } else {
+            (
+                "linear",
+                beta * (v_gst - 0.5 * v_ds) * v_ds * clm,
+                beta * v_ds * clm,
+                beta * (v_gst - v_ds) * clm + beta * lambda * v_ds * (v_gst - 0.5 * v_ds),
+            )
+        };
+
+        OperatingPoint {
+            region,
+            reverse,
+            v_gs,
+            v_ds,
+            v_bs,
+            v_gst,
+            id,
+            gm,
+            gds,
+            gmbs: -gm * dvon_dvbs,
+        }
+    }
+
+    /// Returns the gate-source, gate-drain and gate-bulk capacitances at the solution `x_vec`:
+    /// the Meyer capacitances of the channel, which split the oxide capacitance by the
+    /// operating region, and the overlap capacitances.
+    pub fn capacitances(&self, x_vec: &[Numeric]) -> [Numeric; 3] {
+        let [v_gs, v_ds, v_bs] = self.voltages(x_vec);
+        self.capacitances_at(v_gs, v_ds, v_bs)
+    }
+
+    /// Returns the gate-source, gate-drain and gate-bulk capacitances at `v_gs`, `v_ds` and
+    /// `v_bs`.
+    fn capacitances_at(&self, v_gs: Numeric, v_ds: Numeric, v_bs: Numeric) -> [Numeric; 3] {
+        let op = self.operating_point(v_gs, v_ds, v_bs);
+        let (mut c_gs, mut c_gd, c_gb) = self.meyer(op.v_gst, op.v_ds);
+        if op.reverse {
+            std::mem::swap(&mut c_gs, &mut c_gd);
+        }
+
+        let Mos1Options {
+            cgso, cgdo, cgbo, ..
+        } = self.options;
+        [
+            c_gs + cgso * self.w,
+            c_gd + cgdo * self.w,
+            c_gb + cgbo * self.l,
+        ]
+    }
+
+    /// Returns the gate-source, gate-drain and gate-bulk charges at the solution `x_vec`.
+    ///
+    /// The Meyer capacitances are not the derivatives of a charge, so the charges are
+    /// integrated as in SPICE: from the charge `x1` and voltage `v1` of the previous time point
+    /// in `history` the charge grows by the average of the capacitances at both time points
+    /// times the change of the voltage. The current `dq/dt` is then free of the jumps of the
+    /// capacitances between the operating regions. Without a history the charges are the
+    /// capacitances times the voltages.
+    pub fn charges(&self, x_vec: &[Numeric], history: &[StateHistory]) -> [Charge; 3] {
+        let nodes = [self.s_idx(), self.d_idx(), self.b_idx()];
+        let capacitances = self.capacitances(x_vec);
+        let [gs, gd, gb] = history else {
+            return std::array::from_fn(|idx| {
+                Charge::linear(self.g_idx(), nodes[idx], capacitances[idx], x_vec)
+            });
+        };
+
+        // The terminal voltages of the previous time point follow from the gate voltages
+        let sign = self.options.polarity.sign();
+        let previous =
+            self.capacitances_at(sign * gs.v1, sign * (gs.v1 - gd.v1), sign * (gs.v1 - gb.v1));
+        let states = [gs, gd, gb];
+        std::array::from_fn(|idx| {
+            let voltage = voltage_between(self.g_idx(), nodes[idx], x_vec);
+            let capacitance = (capacitances[idx] + previous[idx]) / 2.0;
+            let charge = states[idx].x1 + capacitance * (voltage - states[idx].v1);
+            Charge::new(self.g_idx(), nodes[idx], voltage, charge, capacitance)
+        })
+    }
+
+    /// Returns the gate-source, gate-drain and gate-bulk Meyer capacitances in the forward mode
+    /// at the gate overdrive `v_gst` and `v_ds`.
+    fn meyer(&self, v_gst: Numeric, v_ds: Numeric) -> (Numeric, Numeric, Numeric) {
+        let cox = self.options.cox() * self.w * self.l;
+        let phi = self.options.phi;
+
+        if v_gst <= -phi {
+            (0.0, 0.0, cox)
+        } else if v_gst <= -phi / 2.0 {
+            (0.0, 0.0, -v_gst * cox / phi)
+        } else if v_gst <= 0.0 {
+            (
+                4.0 * v_gst * cox / (3.0 * phi) + 2.0 * cox / 3.0,
+                0.0,
+                -v_gst * cox / phi,
+            )
+        } else if v_ds >= v_gst {
+            (2.0 * cox / 3.0, 0.0, 0.0)
+        } else {
+            // In the linear region the drain takes a share of the channel charge
+            let denominator = (2.0 * v_gst - v_ds).powi(2);
+            (
+                2.0 / 3.0 * cox * (1.0 - (v_gst - v_ds).powi(2) / denominator),
+                2.0 / 3.0 * cox * (1.0 - v_gst * v_gst / denominator),
+                0.0,
+            )
+        }
+    }
+
+    /// Returns the drain, gate, source and bulk nodes, in the order of the rows of the
+    /// conductance matrix.
+    fn nodes(&self) -> [Option<usize>; 4] {
+        [self.d_idx(), self.g_idx(), self.s_idx(), self.b_idx()]
+    }
+
+    pub fn d_idx(&self) -> Option<usize> {
+        self.drain.as_ref().map(|v| v.idx())
+    }
+
+    pub fn g_idx(&self) -> Option<usize> {
+        self.gate.as_ref().map(|v| v.idx())
+    }
+
+    pub fn s_idx(&self) -> Option<usize> {
+        self.source.as_ref().map(|v| v.idx())
+    }
+
+    pub fn b_idx(&self) -> Option<usize> {
+        self.bulk.as_ref().map(|v| v.idx())
+    }
+}
+
+/// The linearisation of the channel current in the mode the transistor operates in.
+#[derive(Debug, Clone, Copy)]
+struct OperatingPoint {
+    /// The name of the operating region
+    region: &'static str,
+    /// Whether drain and source have swapped their roles
+    reverse: bool,
+    v_gs: Numeric,
+    v_ds: Numeric,
+    v_bs: Numeric,
+    /// The gate overdrive above the threshold voltage
+    v_gst: Numeric,
+    id: Numeric,
+    gm: Numeric,
+    gds: Numeric,
+    gmbs: Numeric,
+}
+
+impl OperatingPoint {
+    /// Returns the indices of the terminals acting as drain and source.
+    fn channel(&self, mosfet: &Mos1Bundle) -> (Option<usize>, Option<usize>) {
+        if self.reverse {
+            (mosfet.s_idx(), mosfet.d_idx())
+        } else {
+            (mosfet.d_idx(), mosfet.s_idx())
+        }
+    }
+
+    /// Returns the derivatives of the currents into drain, gate, source and bulk by their
+    /// voltages. They are the same for both polarities, as the voltages and currents invert
+    /// together.
+    fn conductances(&self) -> [[Numeric; 4]; 4] {
+        let (drain, source) = if self.reverse {
+            (SOURCE, DRAIN)
+        } else {
+            (DRAIN, SOURCE)
+        };
+
+        let mut row = [0.0; 4];
+        row[drain] = self.gds;
+        row[GATE] = self.gm;
+        row[BULK] = self.gmbs;
+        row[source] = -self.gds - self.gm - self.gmbs;
+
+        let mut matrix = [[0.0; 4]; 4];
+        matrix[drain] = row;
+        matrix[source] = row.map(Neg::neg);
+        matrix
+    }
+}
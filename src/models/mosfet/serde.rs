@@ -1,9 +1,13 @@
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+use log::warn;
 use serde::{Deserialize, Serialize};
 
+use super::{Mos1Options, MosPolarity};
 use crate::frontends::{get_variable, serde::ProcessSerdeElement};
-use crate::models::{Element, Mos0Bundle, Unit};
+use crate::models::{DeviceTemperature, Element, Mos0Bundle, Mos1Bundle, Unit};
+use crate::spot::Numeric;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SerdeMos0 {
@@ -11,6 +15,10 @@ pub struct SerdeMos0 {
     pub gate: String,
     pub drain: String,
     pub source: String,
+    /// Instance temperature in °C
+    pub temp: Option<Numeric>,
+    /// Difference to the circuit temperature in K
+    pub dtemp: Option<Numeric>,
 }
 
 impl ProcessSerdeElement for SerdeMos0 {
@@ -21,13 +29,71 @@ impl ProcessSerdeElement for SerdeMos0 {
         var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
     ) {
         let name = self.name.as_str();
-        let ele = Mos0Bundle::new(
+        let mut ele = Mos0Bundle::new(
             Arc::from(name),
             get_variable(&self.gate, Unit::Volt, variables, var_map),
             get_variable(&self.drain, Unit::Volt, variables, var_map),
             get_variable(&self.source, Unit::Volt, variables, var_map),
             None,
         );
+        ele.set_temperature(DeviceTemperature::new(self.temp, self.dtemp));
         elements.push(Element::Mos0(ele));
     }
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SerdeMos1 {
+    pub name: String,
+    pub drain: String,
+    pub gate: String,
+    pub source: String,
+    pub bulk: String,
+    /// `nmos` or `pmos`, NMOS by default
+    #[serde(default)]
+    pub polarity: MosPolarity,
+    /// Channel width in m
+    pub w: Option<Numeric>,
+    /// Channel length in m
+    pub l: Option<Numeric>,
+    /// The level-1 parameters with their SPICE names, like `vto: 0.7`
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Numeric>,
+    /// Instance temperature in °C
+    pub temp: Option<Numeric>,
+    /// Difference to the circuit temperature in K
+    pub dtemp: Option<Numeric>,
+}
+
+impl ProcessSerdeElement for SerdeMos1 {
+    fn process(
+        &self,
+        variables: &mut Vec<crate::models::Variable>,
+        elements: &mut Vec<Element>,
+        var_map: &mut HashMap<std::sync::Arc<str>, usize>,
+    ) {
+        let mut options = Mos1Options::default().with_polarity(self.polarity);
+        for (parameter, value) in &self.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of {}",
+                    self.name
+                );
+            }
+        }
+
+        let mut ele = Mos1Bundle::new(
+            Arc::from(self.name.as_str()),
+            get_variable(&self.drain, Unit::Volt, variables, var_map),
+            get_variable(&self.gate, Unit::Volt, variables, var_map),
+            get_variable(&self.source, Unit::Volt, variables, var_map),
+            get_variable(&self.bulk, Unit::Volt, variables, var_map),
+            Some(options),
+        );
+        ele.set_geometry(
+            self.w.unwrap_or(Mos1Bundle::DEFAULT_GEOMETRY),
+            self.l.unwrap_or(Mos1Bundle::DEFAULT_GEOMETRY),
+        );
+        ele.set_temperature(DeviceTemperature::new(self.temp, self.dtemp));
+        elements.push(Element::Mos1(ele));
+    }
+}
@@ -1,9 +1,13 @@
 use std::sync::Arc;
 
+use log::warn;
+
+use super::{Mos1Options, MosPolarity};
 use crate::frontends::get_variable;
-use crate::frontends::spice::ProcessSpiceElement;
+use crate::frontends::spice::{ApplySpiceModel, ProcessSpiceElement, SpiceModel};
 use crate::frontends::spice_parser_helpers::SpiceElementParser;
-use crate::models::{Element, Mos0Bundle, Unit};
+use crate::frontends::FrontendError;
+use crate::models::{DeviceTemperature, Element, Mos0Bundle, Mos1Bundle, Unit};
 
 impl ProcessSpiceElement for Mos0Bundle {
     fn process(
@@ -21,15 +25,104 @@ impl ProcessSpiceElement for Mos0Bundle {
         let drain_node = parser.parse_node("MOSFET", name, "drain node")?;
         let source_node = parser.parse_node("MOSFET", name, "source node")?;
 
+        let mut temperature = DeviceTemperature::default();
+        for (parameter, value) in parser.parse_named_parameters("MOSFET", name)? {
+            temperature.set_parameter(&parameter, value);
+        }
+
         // Create the MOSFET element
-        let mosfet = Mos0Bundle::new(
+        let mut mosfet = Mos0Bundle::new(
             Arc::from(name),
             get_variable(gate_node, Unit::Volt, variables, var_map),
             get_variable(drain_node, Unit::Volt, variables, var_map),
             get_variable(source_node, Unit::Volt, variables, var_map),
             None,
         );
+        mosfet.set_temperature(temperature);
         elements.push(Element::Mos0(mosfet));
         Ok(())
     }
 }
+
+impl ProcessSpiceElement for Mos1Bundle {
+    fn process(
+        element: pest::iterators::Pair<crate::frontends::spice::Rule>,
+        variables: &mut Vec<crate::models::Variable>,
+        elements: &mut Vec<crate::models::Element>,
+        var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
+    ) -> Result<(), crate::frontends::FrontendError> {
+        let mut parser = SpiceElementParser::new(element);
+
+        let name = parser.parse_name("MOSFET")?;
+        let drain = parser.parse_node("MOSFET", name, "drain node")?;
+        let gate = parser.parse_node("MOSFET", name, "gate node")?;
+        let source = parser.parse_node("MOSFET", name, "source node")?;
+        let bulk = parser.parse_node("MOSFET", name, "bulk node")?;
+        let model = parser.parse_model();
+
+        let mut temperature = DeviceTemperature::default();
+        let mut w = Mos1Bundle::DEFAULT_GEOMETRY;
+        let mut l = Mos1Bundle::DEFAULT_GEOMETRY;
+        for (parameter, value) in parser.parse_named_parameters("MOSFET", name)? {
+            match parameter.as_str() {
+                "w" => w = value,
+                "l" => l = value,
+                _ => {
+                    temperature.set_parameter(&parameter, value);
+                }
+            }
+        }
+
+        let mut mosfet = Mos1Bundle::new(
+            Arc::from(name),
+            get_variable(drain, Unit::Volt, variables, var_map),
+            get_variable(gate, Unit::Volt, variables, var_map),
+            get_variable(source, Unit::Volt, variables, var_map),
+            get_variable(bulk, Unit::Volt, variables, var_map),
+            None,
+        );
+        if let Some(model) = model {
+            mosfet.set_model(Arc::from(model));
+        }
+        mosfet.set_geometry(w, l);
+        mosfet.set_temperature(temperature);
+        elements.push(Element::Mos1(mosfet));
+        Ok(())
+    }
+}
+
+impl ApplySpiceModel for Mos1Bundle {
+    fn model_name(&self) -> Option<Arc<str>> {
+        self.model()
+    }
+
+    fn apply_model(
+        &mut self,
+        model: &SpiceModel,
+        _variables: &mut Vec<crate::models::Variable>,
+        _var_map: &mut std::collections::HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError> {
+        let invalid = |reason: String| FrontendError::InvalidModel {
+            model: model.name.to_string(),
+            reason,
+        };
+        let polarity = model.kind.parse::<MosPolarity>().map_err(invalid)?;
+        let mut options = Mos1Options::default().with_polarity(polarity);
+        for (parameter, value) in &model.parameters {
+            if parameter == "level" {
+                if *value != 1.0 {
+                    return Err(invalid(format!(
+                        "MOSFET level {value} is not supported. Use LEVEL=1"
+                    )));
+                }
+            } else if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of model {}",
+                    model.name
+                );
+            }
+        }
+        self.set_options(options);
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 // Basic tests for MOSFET model
 use super::mos0::{Mos0Bundle, Mos0Options};
-use crate::models::{Unit, Variable};
+use super::mos1::{Mos1Bundle, Mos1Options, MosPolarity};
+use crate::models::{StateHistory, Unit, Variable};
+use crate::spot::Numeric;
 use std::sync::Arc;
 
 #[test]
@@ -197,8 +199,8 @@ fn test_mosfet_partial_connections() {
     assert_eq!(mosfet.d_idx(), None);
     assert_eq!(mosfet.s_idx(), Some(1));
 
-    // Should still work with partial connections
-    let x_vec = vec![1.0, 0.0];
+    // Should still work with partial connections, the grounded drain is 1V above the source
+    let x_vec = vec![1.0, -1.0];
     let pairs = mosfet.pairs(&x_vec);
     let pairs_data = pairs.data();
 
@@ -406,3 +408,384 @@ fn test_mosfet_symmetry() {
         current_diff2
     );
 }
+
+#[test]
+fn test_mosfet_transconductance_stamp() {
+    let gate = Variable::new(Arc::from("gate"), Unit::Volt, 0);
+    let drain = Variable::new(Arc::from("drain"), Unit::Volt, 1);
+    let source = Variable::new(Arc::from("source"), Unit::Volt, 2);
+
+    let mosfet = Mos0Bundle::new(Arc::from("M1"), Some(gate), Some(drain), Some(source), None);
+
+    // Saturation at V_gs = 1.43V: gm = kp * (V_gs - Vt) and gds = 0
+    let triples = mosfet.triples_at(1.43, 2.0);
+    let gm = triples
+        .data()
+        .into_iter()
+        .find(|&(row, col, _)| row == 1 && col == 0)
+        .map(|(_, _, val)| val)
+        .unwrap();
+    assert!((gm - 118e-6).abs() < 1e-12);
+
+    // The companion reproduces the drain current id = kp / 2 * (V_gs - Vt)^2
+    let pairs = mosfet.pairs_at(1.43, 2.0);
+    let drain_rhs = pairs[0].1;
+    let id = gm * 1.43 - drain_rhs;
+    assert!((id - 0.5 * 118e-6).abs() < 1e-12);
+}
+
+#[test]
+fn test_mosfet_reverse_mode() {
+    let gate = Variable::new(Arc::from("gate"), Unit::Volt, 0);
+    let drain = Variable::new(Arc::from("drain"), Unit::Volt, 1);
+    let source = Variable::new(Arc::from("source"), Unit::Volt, 2);
+
+    let mosfet = Mos0Bundle::new(Arc::from("M1"), Some(gate), Some(drain), Some(source), None);
+
+    // Swapping drain and source mirrors the companion current
+    let forward = mosfet.pairs(&[2.0, 1.0, 0.0]);
+    let reverse = mosfet.pairs(&[2.0, 0.0, 1.0]);
+    let rhs = |pairs: &crate::models::pairs::Pairs<f64, 2>, idx: usize| {
+        pairs
+            .data()
+            .into_iter()
+            .find(|&(row, _)| row == idx)
+            .map(|(_, val)| val)
+            .unwrap()
+    };
+    assert!((rhs(&forward, 1) - rhs(&reverse, 2)).abs() < 1e-15);
+    assert!((rhs(&forward, 2) - rhs(&reverse, 1)).abs() < 1e-15);
+}
+
+#[test]
+fn test_mosfet_limit_voltages() {
+    let mosfet = Mos0Bundle::new(Arc::from("M1"), None, None, None, None);
+
+    let ([v_gs, v_ds], limited) = mosfet.limit_voltages([10.0, 10.0], [0.0, 0.0]);
+    assert!(limited);
+    assert!(v_gs < 1.0);
+    // The gate-drain voltage is kept, so the drain follows the limited gate
+    assert_eq!(v_ds, v_gs);
+
+    // With the gate-drain voltage of 7V kept, the drain falls below the source and is limited
+    let ([v_gs, v_ds], _) = mosfet.limit_voltages([10.0, 3.0], [0.0, 0.0]);
+    assert!(v_gs < 1.0);
+    assert_eq!(v_ds, -0.5);
+}
+
+#[test]
+fn test_mosfet_temperature_scaling() {
+    use crate::models::DeviceTemperature;
+    use crate::sim::options::SimulatorOptions;
+
+    let gate = Variable::new(Arc::from("gate"), Unit::Volt, 0);
+    let drain = Variable::new(Arc::from("drain"), Unit::Volt, 1);
+    let source = Variable::new(Arc::from("source"), Unit::Volt, 2);
+
+    let options = Mos0Options::default().with_temperature_scaling(2e-3, -1.5);
+    let mut mosfet = Mos0Bundle::new(
+        Arc::from("M1"),
+        Some(gate),
+        Some(drain),
+        Some(source),
+        Some(options),
+    );
+    let gm = |mosfet: &Mos0Bundle| {
+        mosfet
+            .triples_at(2.0, 5.0)
+            .data()
+            .into_iter()
+            .find(|&(row, col, _)| row == 1 && col == 0)
+            .map(|(_, _, val)| val)
+            .unwrap()
+    };
+    let cold = gm(&mosfet);
+
+    // 50K above the nominal temperature: the threshold drops by tcv and kp by the mobility
+    mosfet.set_temperature(DeviceTemperature::new(None, Some(50.0)));
+    mosfet.apply_options(&SimulatorOptions::default());
+    let hot = gm(&mosfet);
+
+    let tnom = crate::spot::TEMP;
+    let kp = 118e-6 * ((tnom + 50.0) / tnom).powf(-1.5);
+    let expected = kp * (2.0 - (0.43 - 2e-3 * 50.0));
+    assert!((hot - expected).abs() < 1e-12, "{hot} != {expected}");
+    assert!(hot < cold);
+}
+
+/// Creates a level-1 transistor with the drain, gate, source and bulk at the indices 0 to 3
+/// and W/L = 10.
+fn mos1(options: Mos1Options) -> Mos1Bundle {
+    let node = |name: &str, idx| Some(Variable::new(Arc::from(name), Unit::Volt, idx));
+    let mut mosfet = Mos1Bundle::new(
+        Arc::from("M1"),
+        node("drain", 0),
+        node("gate", 1),
+        node("source", 2),
+        node("bulk", 3),
+        Some(options),
+    );
+    mosfet.set_geometry(10e-6, 1e-6);
+    mosfet
+}
+
+/// Returns level-1 parameters with `VTO=0.7`, `KP=1e-4`, `GAMMA=0.5` and `LAMBDA=0.02`.
+fn mos1_options() -> Mos1Options {
+    let mut options = Mos1Options::default();
+    for (name, value) in [("vto", 0.7), ("kp", 1e-4), ("gamma", 0.5), ("lambda", 0.02)] {
+        assert!(options.set_parameter(name, value));
+    }
+    options
+}
+
+/// Returns the currents into the terminals of the transistor at `x_vec`, which are the
+/// linearised currents `G * x - b` of its stamps.
+fn mos1_currents(mosfet: &Mos1Bundle, x_vec: &[Numeric]) -> Vec<Numeric> {
+    let mut currents = vec![0.0; x_vec.len()];
+    for &(row, col, value) in &mosfet.triples(x_vec) {
+        currents[row] += value * x_vec[col];
+    }
+    for &(row, value) in &mosfet.pairs(x_vec) {
+        currents[row] -= value;
+    }
+    currents
+}
+
+#[test]
+fn test_mos1_polarity_from_str() {
+    assert_eq!("NMOS".parse::<MosPolarity>(), Ok(MosPolarity::Nmos));
+    assert_eq!("pmos".parse::<MosPolarity>(), Ok(MosPolarity::Pmos));
+    assert!("npn".parse::<MosPolarity>().is_err());
+    assert!(!Mos1Options::default().set_parameter("uo", 600.0));
+}
+
+#[test]
+fn test_mos1_saturation_current() {
+    let mosfet = mos1(mos1_options());
+    // V_GS = 2V, V_DS = 3V, bulk at the source
+    let currents = mos1_currents(&mosfet, &[3.0, 2.0, 0.0, 0.0]);
+
+    let expected = 0.5 * 1e-4 * 10.0 * 1.3 * 1.3 * (1.0 + 0.02 * 3.0);
+    assert!((currents[0] - expected).abs() < 1e-12);
+    assert!((currents[0] + currents[2]).abs() < 1e-15);
+    assert_eq!(currents[1], 0.0);
+    assert_eq!(currents[3], 0.0);
+    assert_eq!(mosfet.region(2.0, 3.0, 0.0), "saturation");
+}
+
+#[test]
+fn test_mos1_linear_current() {
+    let mosfet = mos1(mos1_options());
+    let currents = mos1_currents(&mosfet, &[0.5, 2.0, 0.0, 0.0]);
+
+    let expected = 1e-4 * 10.0 * (1.3 - 0.25) * 0.5 * (1.0 + 0.02 * 0.5);
+    assert!((currents[0] - expected).abs() < 1e-12);
+    assert_eq!(mosfet.region(2.0, 0.5, 0.0), "linear");
+}
+
+#[test]
+fn test_mos1_body_effect() {
+    let mosfet = mos1(mos1_options());
+
+    // A reverse biased bulk raises the threshold by gamma * (sqrt(phi - vbs) - sqrt(phi))
+    let threshold = 0.7 + 0.5 * ((0.6 + 1.0 as Numeric).sqrt() - (0.6 as Numeric).sqrt());
+    assert_eq!(mosfet.region(threshold - 1e-3, 3.0, -1.0), "cutoff");
+    assert_eq!(mosfet.region(threshold + 1e-3, 3.0, -1.0), "saturation");
+
+    let id_0 = mos1_currents(&mosfet, &[3.0, 2.0, 0.0, 0.0])[0];
+    let id_1 = mos1_currents(&mosfet, &[3.0, 2.0, 0.0, -1.0])[0];
+    assert!(id_1 < id_0);
+}
+
+#[test]
+fn test_mos1_limit_voltages() {
+    let mosfet = mos1(mos1_options());
+
+    // Turning on from cutoff stops just above the threshold including the body effect
+    let threshold = 0.7 + 0.5 * ((0.6 + 5.0 as Numeric).sqrt() - (0.6 as Numeric).sqrt());
+    let ([v_gs, v_ds, v_bs], limited) = mosfet.limit_voltages([10.0, 10.0, -5.0], [0.0, 0.0, -5.0]);
+    assert!(limited);
+    assert!((v_gs - (threshold + 0.5)).abs() < 1e-12);
+    assert_eq!(v_ds, v_gs);
+    assert_eq!(v_bs, -5.0);
+
+    // In reverse mode the gate-drain voltage is limited at the threshold of the forward biased
+    // bulk-drain junction, then the drain-source step
+    let sqrt_phi = (0.6 as Numeric).sqrt();
+    let threshold = 0.7 + 0.5 * (sqrt_phi / (1.0 + 0.5 / 0.6) - sqrt_phi);
+    let ([v_gs, v_ds, _], limited) = mosfet.limit_voltages([0.0, -10.0, 0.0], [0.0, -1.0, 0.0]);
+    assert!(limited);
+    assert_eq!(v_ds, -4.0);
+    assert!((v_gs - v_ds - (threshold + 4.0)).abs() < 1e-12);
+}
+
+#[test]
+fn test_mos1_jacobian() {
+    let mosfet = mos1(mos1_options());
+    let h = 1e-7;
+
+    // Saturation, linear and reverse mode, with a reverse and a forward biased bulk
+    for x_vec in [
+        [3.0, 2.0, 0.1, -0.5],
+        [0.6, 2.5, 0.1, 0.2],
+        [0.1, 2.0, 0.8, -0.5],
+    ] {
+        let mut jacobian = [[0.0; 4]; 4];
+        for &(row, col, value) in &mosfet.triples(&x_vec) {
+            jacobian[row][col] += value;
+        }
+        for col in 0..4 {
+            let mut x_plus = x_vec;
+            let mut x_minus = x_vec;
+            x_plus[col] += h;
+            x_minus[col] -= h;
+            let plus = mos1_currents(&mosfet, &x_plus);
+            let minus = mos1_currents(&mosfet, &x_minus);
+            for row in 0..4 {
+                let numeric = (plus[row] - minus[row]) / (2.0 * h);
+                let tolerance = 1e-5 * numeric.abs() + 1e-10;
+                assert!(
+                    (jacobian[row][col] - numeric).abs() < tolerance,
+                    "dI{row}/dV{col} at {x_vec:?}: {} != {numeric}",
+                    jacobian[row][col]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mos1_reverse_mode() {
+    let mosfet = mos1(mos1_options());
+
+    // Swapping the drain and source voltages swaps the currents
+    let forward = mos1_currents(&mosfet, &[3.0, 2.0, 0.0, 0.0]);
+    let reverse = mos1_currents(&mosfet, &[0.0, 2.0, 3.0, 0.0]);
+    assert!((forward[0] - reverse[2]).abs() < 1e-15);
+    assert!((forward[2] - reverse[0]).abs() < 1e-15);
+    assert_eq!(mosfet.region(-1.0, -3.0, -3.0), "saturation, reverse");
+}
+
+#[test]
+fn test_mos1_pmos_mirrors_nmos() {
+    let nmos = mos1(mos1_options());
+    let mut options = mos1_options().with_polarity(MosPolarity::Pmos);
+    options.set_parameter("vto", -0.7);
+    let pmos = mos1(options);
+
+    let nmos_currents = mos1_currents(&nmos, &[3.0, 2.0, 0.0, -0.5]);
+    let pmos_currents = mos1_currents(&pmos, &[-3.0, -2.0, 0.0, 0.5]);
+    for (nmos, pmos) in nmos_currents.iter().zip(&pmos_currents) {
+        assert!((nmos + pmos).abs() < 1e-15);
+    }
+    assert_eq!(
+        pmos.voltages(&[-3.0, -2.0, 0.0, 0.5]),
+        nmos.voltages(&[3.0, 2.0, 0.0, -0.5])
+    );
+}
+
+#[test]
+fn test_mos1_temperature_scaling() {
+    use crate::models::DeviceTemperature;
+    use crate::sim::options::SimulatorOptions;
+
+    let mut nmos = mos1(mos1_options());
+    let mut options = mos1_options().with_polarity(MosPolarity::Pmos);
+    options.set_parameter("vto", -0.7);
+    let mut pmos = mos1(options);
+
+    // 50K above the nominal temperature: the threshold drops by tcv and kp by the mobility
+    for mosfet in [&mut nmos, &mut pmos] {
+        mosfet.set_temperature(DeviceTemperature::new(None, Some(50.0)));
+        mosfet.apply_options(&SimulatorOptions::default());
+    }
+
+    let tnom = crate::spot::TEMP;
+    let kp = 1e-4 * ((tnom + 50.0) / tnom).powf(-1.5);
+    let v_gst: Numeric = 2.0 - (0.7 - 1e-3 * 50.0);
+    let expected = 0.5 * kp * 10.0 * v_gst.powi(2) * (1.0 + 0.02 * 3.0);
+    let currents = mos1_currents(&nmos, &[3.0, 2.0, 0.0, 0.0]);
+    assert!(
+        (currents[0] - expected).abs() < 1e-12,
+        "{} != {expected}",
+        currents[0]
+    );
+
+    // The magnitude of the PMOS threshold drops as well
+    let currents = mos1_currents(&pmos, &[-3.0, -2.0, 0.0, 0.0]);
+    assert!(
+        (currents[0] + expected).abs() < 1e-12,
+        "{} != {expected}",
+        -currents[0]
+    );
+}
+
+#[test]
+fn test_mos1_meyer_capacitances() {
+    let mut options = mos1_options();
+    options.set_parameter("tox", 1e-8);
+    options.set_parameter("cgso", 1e-10);
+    let mosfet = mos1(options);
+    let cox = 3.9 * 8.854_187_817e-12 / 1e-8 * 10e-6 * 1e-6;
+    let capacitances = |x_vec: &[Numeric]| mosfet.capacitances(x_vec);
+
+    // Accumulation: the oxide capacitance lies between gate and bulk
+    let [c_gs, c_gd, c_gb] = capacitances(&[3.0, -1.0, 0.0, 0.0]);
+    assert!((c_gs - 1e-15).abs() < 1e-12 * cox);
+    assert_eq!(c_gd, 0.0);
+    assert!((c_gb - cox).abs() < 1e-12 * cox);
+
+    // Saturation: two thirds of it between gate and source
+    let [c_gs, c_gd, c_gb] = capacitances(&[3.0, 2.0, 0.0, 0.0]);
+    assert!((c_gs - 2.0 / 3.0 * cox - 1e-15).abs() < 1e-12 * cox);
+    assert_eq!([c_gd, c_gb], [0.0, 0.0]);
+
+    // Without a drain-source voltage the channel charge is shared equally
+    let [c_gs, c_gd, _] = capacitances(&[0.0, 2.0, 0.0, 0.0]);
+    assert!((c_gs - 1e-15 - 0.5 * cox).abs() < 1e-12 * cox);
+    assert!((c_gd - 0.5 * cox).abs() < 1e-12 * cox);
+}
+
+#[test]
+fn test_mos1_meyer_charges_average_capacitances() {
+    let mut options = mos1_options();
+    options.set_parameter("tox", 1e-8);
+    let mosfet = mos1(options);
+
+    // From saturation into the linear region
+    let x_prev = [1.0, 1.5, 0.0, 0.0];
+    let x_vec = [1.0, 2.5, 0.0, 0.0];
+    let history = mosfet.charges(&x_prev, &[]).map(|charge| {
+        let mut state = StateHistory::new(charge.charge);
+        state.v1 = charge.voltage;
+        state
+    });
+    let charges = mosfet.charges(&x_vec, &history);
+
+    let c_prev = mosfet.capacitances(&x_prev);
+    let c_new = mosfet.capacitances(&x_vec);
+    for (idx, charge) in charges.iter().enumerate() {
+        let capacitance = (c_prev[idx] + c_new[idx]) / 2.0;
+        let dv = charge.voltage - history[idx].v1;
+        assert_eq!(charge.capacitance, capacitance);
+        assert!((charge.charge - history[idx].x1 - capacitance * dv).abs() < 1e-30);
+    }
+}
+
+#[test]
+fn test_mos1_triple_idx() {
+    let mosfet = mos1(mos1_options());
+    assert_eq!(mosfet.triple_idx().unwrap().len(), 16);
+
+    // The AC stamps add the gate capacitances to the same pattern
+    let x_vec = [3.0, 2.0, 0.0, 0.0];
+    let triples = mosfet.triples(&x_vec);
+    let ac_triples = mosfet.ac_triples(0.0, &x_vec);
+    assert_eq!(triples.len(), ac_triples.len());
+    for (real, complex) in triples.into_iter().zip(&ac_triples) {
+        assert_eq!(
+            (real.0, real.1, real.2),
+            (complex.0, complex.1, complex.2.re)
+        );
+    }
+}
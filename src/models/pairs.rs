@@ -30,6 +30,16 @@ impl<T: Copy + Default, const N: usize> Pairs<T, N> {
     pub(crate) fn data(&self) -> [(usize, T); N] {
         self.data
     }
+
+    /// Returns the rows of the stored pairs.
+    pub(crate) fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.data[..self.length].iter().map(|&(row, _)| row)
+    }
+
+    /// Returns the same pairs with the larger capacity `M`.
+    pub(crate) fn widen<const M: usize>(self) -> Pairs<T, M> {
+        Pairs::new(&self.data[..self.length])
+    }
 }
 
 impl<T: Copy + Default, const N: usize> Index<usize> for Pairs<T, N> {
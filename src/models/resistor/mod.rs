@@ -7,7 +7,9 @@ use std::sync::Arc;
 
 use num::{Complex, One, Zero};
 
+use super::temperature::{self, DeviceTemperature};
 use super::*;
+use crate::sim::options::SimulatorOptions;
 
 /// A structure representing a bundle of resistors.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -16,6 +18,13 @@ pub struct ResistorBundle {
     node0: Option<Variable>,
     node1: Option<Variable>,
     value: Numeric,
+    /// Linear temperature coefficient in 1/K
+    tc1: Numeric,
+    /// Quadratic temperature coefficient in 1/K²
+    tc2: Numeric,
+    temperature: DeviceTemperature,
+    /// The resistance at the temperature of the resistor
+    resistance: Numeric,
 }
 
 impl ResistorBundle {
@@ -42,9 +51,30 @@ impl ResistorBundle {
             node0,
             node1,
             value,
+            tc1: Numeric::zero(),
+            tc2: Numeric::zero(),
+            temperature: DeviceTemperature::default(),
+            resistance: value,
         }
     }
 
+    /// Sets the temperature coefficients `TC1` and `TC2` of the resistance.
+    pub fn set_temperature_coefficients(&mut self, tc1: Numeric, tc2: Numeric) {
+        self.tc1 = tc1;
+        self.tc2 = tc2;
+    }
+
+    /// Sets the instance temperature of the resistor.
+    pub fn set_temperature(&mut self, temperature: DeviceTemperature) {
+        self.temperature = temperature;
+    }
+
+    /// Scales the resistance from the nominal temperature to the temperature of the resistor.
+    pub fn apply_options(&mut self, options: &SimulatorOptions) {
+        let dt = self.temperature.kelvin(options) - options.tnom_kelvin();
+        self.resistance = temperature::polynomial(self.value, self.tc1, self.tc2, dt);
+    }
+
     /// Returns the name of the resistor bundle.
     pub fn name(&self) -> Arc<str> {
         self.name.clone()
@@ -59,14 +89,19 @@ impl ResistorBundle {
         self.node1.as_ref().map(|v| v.idx())
     }
 
-    /// Returns the value of the resistor.
+    /// Returns the value of the resistor at the nominal temperature.
     pub fn value(&self) -> Numeric {
         self.value
     }
 
+    /// Returns the resistance at the temperature of the resistor.
+    pub fn resistance(&self) -> Numeric {
+        self.resistance
+    }
+
     /// Returns triples representing this elements contribution to the a matrix
     pub fn triples(&self) -> Triples<Numeric, 4> {
-        let conductance = Numeric::one() / self.value;
+        let conductance = Numeric::one() / self.resistance;
         let node0_idx = self.node0_idx();
         let node1_idx = self.node1_idx();
 
@@ -114,7 +149,7 @@ impl ResistorBundle {
     /// Returns triples representing this elements contribution to the a matrix
     pub fn ac_triples(&self) -> Triples<ComplexNumeric, 4> {
         let conductance = Complex {
-            re: Numeric::one() / self.value,
+            re: Numeric::one() / self.resistance,
             im: Numeric::zero(),
         };
 
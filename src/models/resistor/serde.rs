@@ -3,15 +3,23 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 use crate::frontends::{get_variable, serde::ProcessSerdeElement};
-use crate::models::{Element, ResistorBundle, Unit};
+use crate::models::{DeviceTemperature, Element, ResistorBundle, Unit};
 use crate::spot::Numeric;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct SerdeResistor {
     pub name: String,
     pub node0: String,
     pub node1: String,
     pub value: Numeric,
+    /// Linear temperature coefficient in 1/K
+    pub tc1: Option<Numeric>,
+    /// Quadratic temperature coefficient in 1/K²
+    pub tc2: Option<Numeric>,
+    /// Instance temperature in °C
+    pub temp: Option<Numeric>,
+    /// Difference to the circuit temperature in K
+    pub dtemp: Option<Numeric>,
 }
 
 impl ProcessSerdeElement for SerdeResistor {
@@ -21,12 +29,17 @@ impl ProcessSerdeElement for SerdeResistor {
         elements: &mut Vec<Element>,
         var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
     ) {
-        let res = ResistorBundle::new(
+        let mut res = ResistorBundle::new(
             Arc::from(self.name.as_str()),
             get_variable(self.node0.as_str(), Unit::Volt, variables, var_map),
             get_variable(self.node1.as_str(), Unit::Volt, variables, var_map),
             self.value,
         );
+        res.set_temperature_coefficients(
+            self.tc1.unwrap_or_default(),
+            self.tc2.unwrap_or_default(),
+        );
+        res.set_temperature(DeviceTemperature::new(self.temp, self.dtemp));
         elements.push(Element::Resistor(res));
     }
 }
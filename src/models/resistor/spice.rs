@@ -3,7 +3,7 @@ use std::sync::Arc;
 use crate::frontends::get_variable;
 use crate::frontends::spice::ProcessSpiceElement;
 use crate::frontends::spice_parser_helpers::SpiceElementParser;
-use crate::models::{Element, ResistorBundle, Unit};
+use crate::models::{DeviceTemperature, Element, ResistorBundle, Unit};
 
 impl ProcessSpiceElement for ResistorBundle {
     fn process(
@@ -21,13 +21,27 @@ impl ProcessSpiceElement for ResistorBundle {
         let node1 = parser.parse_node("resistor", name, "node1")?;
         let value = parser.parse_value("resistor", name, "value")?;
 
+        let (mut tc1, mut tc2) = (0.0, 0.0);
+        let mut temperature = DeviceTemperature::default();
+        for (parameter, value) in parser.parse_named_parameters("resistor", name)? {
+            match parameter.as_str() {
+                "tc1" => tc1 = value,
+                "tc2" => tc2 = value,
+                _ => {
+                    temperature.set_parameter(&parameter, value);
+                }
+            }
+        }
+
         // Create the resistor element
-        let res = ResistorBundle::new(
+        let mut res = ResistorBundle::new(
             Arc::from(name),
             get_variable(node0, Unit::Volt, variables, var_map),
             get_variable(node1, Unit::Volt, variables, var_map),
             value,
         );
+        res.set_temperature_coefficients(tc1, tc2);
+        res.set_temperature(temperature);
         elements.push(Element::Resistor(res));
         Ok(())
     }
@@ -1,4 +1,5 @@
 use super::*;
+use crate::models::DeviceTemperature;
 
 fn create_variable(name: &str, unit: Unit, idx: usize) -> Variable {
     Variable(Arc::from(name.to_string()), unit, idx)
@@ -45,3 +46,28 @@ fn test_triples() {
         Triples::new(&[(0, 0, 0.1), (1, 1, 0.1), (0, 1, -0.1), (1, 0, -0.1),])
     );
 }
+
+#[test]
+fn test_temperature_coefficients() {
+    let mut resistor_bundle = ResistorBundle::new(
+        Arc::from("R1"),
+        Some(create_variable("1", Unit::Volt, 0)),
+        None,
+        1000.0,
+    );
+    resistor_bundle.set_temperature_coefficients(1e-3, 1e-5);
+
+    let options = SimulatorOptions {
+        temp: 30.0,
+        tnom: 20.0,
+        ..Default::default()
+    };
+    resistor_bundle.apply_options(&options);
+    assert!((resistor_bundle.resistance() - 1011.0).abs() < 1e-9);
+    assert_eq!(resistor_bundle.value(), 1000.0);
+
+    // The instance temperature replaces the circuit temperature
+    resistor_bundle.set_temperature(DeviceTemperature::new(Some(20.0), None));
+    resistor_bundle.apply_options(&options);
+    assert_eq!(resistor_bundle.resistance(), 1000.0);
+}
@@ -0,0 +1,394 @@
+/// The Switch Module. As every module this module encapsulates exerything regarding a switch bundle
+/// This includes parsing from various formats as well as the conductance-behaviour.
+pub(crate) mod serde;
+pub(crate) mod spice;
+
+#[cfg(test)]
+mod tests;
+
+use std::ops::Neg;
+use std::sync::Arc;
+
+use num::Zero;
+
+use super::charge::voltage_between;
+use super::*;
+use crate::spot::*;
+
+/// The element controlling a switch.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum SwitchControl {
+    /// The voltage between the positive and the negative node - S element
+    Voltage(Option<Variable>, Option<Variable>),
+    /// The current through the branch of a voltage source - W element
+    Current(Option<Variable>),
+}
+
+/// A structure representing a voltage or current controlled switch.
+///
+/// The switch remembers its state of the last accepted solution, which decides the state
+/// within the hysteresis band `VT - VH..VT + VH`.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct SwitchBundle {
+    name: Arc<str>,
+    positive: Option<Variable>,
+    negative: Option<Variable>,
+    control: SwitchControl,
+    /// The name of the `.model` card of the switch
+    model: Option<Arc<str>>,
+    options: SwitchOptions,
+    /// The state at the start of every analysis
+    initial_on: bool,
+    /// The state at the last accepted solution
+    on: bool,
+}
+
+/// A struct representing the parameters of a switch, as in a SPICE `.model` card.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct SwitchOptions {
+    /// On resistance
+    ron: Numeric,
+    /// Off resistance
+    roff: Numeric,
+    /// Threshold voltage or current
+    threshold: Numeric,
+    /// Hysteresis voltage or current. A negative value selects a smooth transition over
+    /// `VT - |VH|..VT + |VH|` instead of the hysteresis.
+    hysteresis: Numeric,
+}
+
+impl Default for SwitchOptions {
+    fn default() -> Self {
+        Self {
+            ron: 1.0,
+            roff: GMIN.recip(),
+            threshold: 0.0,
+            hysteresis: 0.0,
+        }
+    }
+}
+
+impl SwitchOptions {
+    /// Sets the on and off resistances.
+    pub fn with_resistances(mut self, ron: Numeric, roff: Numeric) -> Self {
+        self.ron = ron;
+        self.roff = roff;
+        self
+    }
+
+    /// Sets the threshold and the hysteresis of the control.
+    pub fn with_threshold(mut self, threshold: Numeric, hysteresis: Numeric) -> Self {
+        self.threshold = threshold;
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Sets the model parameter `name` with its SPICE name, like `RON` or `VT`.
+    /// The thresholds of current controlled switches `IT` and `IH` are aliases of `VT` and `VH`.
+    /// Returns false if `name` is not a parameter of the model.
+    pub fn set_parameter(&mut self, name: &str, value: Numeric) -> bool {
+        let parameter = match name.to_ascii_lowercase().as_str() {
+            "ron" => &mut self.ron,
+            "roff" => &mut self.roff,
+            "vt" | "it" => &mut self.threshold,
+            "vh" | "ih" => &mut self.hysteresis,
+            _ => return false,
+        };
+        *parameter = value;
+        true
+    }
+
+    /// Returns whether the switch changes smoothly instead of with hysteresis.
+    fn is_smooth(&self) -> bool {
+        self.hysteresis < Numeric::zero()
+    }
+}
+
+impl SwitchBundle {
+    /// Creates a new `SwitchBundle` object, which is off at the start of an analysis.
+    pub fn new(
+        name: Arc<str>,
+        positive: Option<Variable>,
+        negative: Option<Variable>,
+        control: SwitchControl,
+        options: Option<SwitchOptions>,
+    ) -> SwitchBundle {
+        SwitchBundle {
+            name,
+            positive,
+            negative,
+            control,
+            model: None,
+            options: options.unwrap_or_default(),
+            initial_on: false,
+            on: false,
+        }
+    }
+
+    /// Sets the name of the `.model` card of the switch.
+    pub fn set_model(&mut self, model: Arc<str>) {
+        self.model = Some(model);
+    }
+
+    /// Returns the name of the `.model` card of the switch.
+    pub fn model(&self) -> Option<Arc<str>> {
+        self.model.clone()
+    }
+
+    /// Replaces the model parameters of the switch.
+    pub fn set_options(&mut self, options: SwitchOptions) {
+        self.options = options;
+    }
+
+    /// Returns the model parameters of the switch.
+    pub fn options(&self) -> &SwitchOptions {
+        &self.options
+    }
+
+    /// Sets the state of the switch at the start of an analysis, `ON` or `OFF` in SPICE.
+    pub fn set_initial_state(&mut self, on: bool) {
+        self.initial_on = on;
+        self.on = on;
+    }
+
+    /// Returns whether the switch was on at the last accepted solution.
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Returns the name of the switch bundle.
+    pub fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    /// Returns whether the switch is current controlled.
+    pub fn is_current_controlled(&self) -> bool {
+        matches!(self.control, SwitchControl::Current(_))
+    }
+
+    /// Returns the variable of the branch, whose current controls a W element.
+    pub fn controlling_branch(&self) -> Option<Option<&Variable>> {
+        match &self.control {
+            SwitchControl::Current(branch) => Some(branch.as_ref()),
+            SwitchControl::Voltage(..) => None,
+        }
+    }
+
+    /// Returns the voltage across the switch and the value of its control at the solution
+    /// `x_vec`.
+    pub fn voltages(&self, x_vec: &[Numeric]) -> [Numeric; 2] {
+        let [control_pos, control_neg] = self.control_indices();
+        [
+            voltage_between(self.node0_idx(), self.node1_idx(), x_vec),
+            voltage_between(control_pos, control_neg, x_vec),
+        ]
+    }
+
+    /// Moves the control to its new value. Returns whether the switch changed its state with the
+    /// step, which needs another Newton iteration.
+    pub fn limit_voltages(&self, new: [Numeric; 2], old: [Numeric; 2]) -> ([Numeric; 2], bool) {
+        let switched = !self.options.is_smooth() && self.state(new[1]) != self.state(old[1]);
+        (new, switched)
+    }
+
+    /// Returns the state of the switch at the control value `control`.
+    /// Within the hysteresis band the switch keeps the state of the last accepted solution.
+    fn state(&self, control: Numeric) -> bool {
+        let SwitchOptions {
+            threshold,
+            hysteresis,
+            ..
+        } = self.options;
+        let hysteresis = hysteresis.abs();
+        if control > threshold + hysteresis {
+            true
+        } else if control < threshold - hysteresis {
+            false
+        } else {
+            self.on && hysteresis > Numeric::zero()
+        }
+    }
+
+    /// Returns the conductance of the switch and its derivative by the control at `control`.
+    /// The smooth transition interpolates the logarithm of the conductance with a cubic.
+    fn conductance(&self, control: Numeric) -> (Numeric, Numeric) {
+        let SwitchOptions {
+            ron,
+            roff,
+            threshold,
+            hysteresis,
+        } = self.options;
+        let (g_on, g_off) = (ron.recip(), roff.recip());
+        if !self.options.is_smooth() {
+            let g = if self.state(control) { g_on } else { g_off };
+            return (g, Numeric::zero());
+        }
+
+        let width = -2.0 * hysteresis;
+        let u = ((control - threshold) / width + 0.5).clamp(0.0, 1.0);
+        let log_ratio = (g_on / g_off).ln();
+        let g = g_off * (log_ratio * u * u * (3.0 - 2.0 * u)).exp();
+        (g, g * log_ratio * 6.0 * u * (1.0 - u) / width)
+    }
+
+    /// Returns a reference to the triples representing matrix A.
+    pub fn triples(&self, x_vec: &[Numeric]) -> Triples<Numeric, 8> {
+        let [voltage, control] = self.voltages(x_vec);
+        self.triples_at(voltage, control)
+    }
+
+    /// Returns the triples of the switch linearised at `voltage` and `control`.
+    pub fn triples_at(&self, voltage: Numeric, control: Numeric) -> Triples<Numeric, 8> {
+        let (g, dg) = self.conductance(control);
+        self.stamps(g, dg * voltage)
+    }
+
+    /// Returns the indices of the triples representing matrix A.
+    pub fn triple_idx(&self) -> Option<TripleIdx<8>> {
+        let indices: Vec<_> = self
+            .stamps(1.0, 1.0)
+            .into_iter()
+            .map(|&(row, col, _)| (row, col))
+            .collect();
+        (!indices.is_empty()).then(|| TripleIdx::new(&indices))
+    }
+
+    /// Returns a reference to the pairs representing vector b.
+    pub fn pairs(&self, x_vec: &[Numeric]) -> Pairs<Numeric, 2> {
+        let [voltage, control] = self.voltages(x_vec);
+        self.pairs_at(voltage, control)
+    }
+
+    /// Returns the pairs of the equivalent current source `dg * v * control` of the smooth
+    /// transition linearised at `voltage` and `control`, which flows into the positive node.
+    pub fn pairs_at(&self, voltage: Numeric, control: Numeric) -> Pairs<Numeric, 2> {
+        let (_, dg) = self.conductance(control);
+        let current = dg * voltage * control;
+        let pairs: Vec<_> = [(self.node0_idx(), current), (self.node1_idx(), -current)]
+            .into_iter()
+            .filter_map(|(node, value)| Some((node?, value)))
+            .collect();
+        Pairs::new(&pairs)
+    }
+
+    /// Returns the small-signal triples linearised at the operating point `x_vec`.
+    pub fn ac_triples(&self, x_vec: &[Numeric]) -> Triples<ComplexNumeric, 8> {
+        let [voltage, control] = self.voltages(x_vec);
+        let (g, dg) = self.conductance(control);
+        self.stamps(ComplexNumeric::from(g), ComplexNumeric::from(dg * voltage))
+    }
+
+    /// Returns the triples of the conductance `g` between the nodes of the switch and of the
+    /// transconductance `gc` of its current by the control.
+    fn stamps<T>(&self, g: T, gc: T) -> Triples<T, 8>
+    where
+        T: Copy + Default + Neg<Output = T>,
+    {
+        let nodes = [self.node0_idx(), self.node1_idx()];
+        let [control_pos, control_neg] = self.control_indices();
+        let columns = [
+            (nodes[0], g),
+            (nodes[1], -g),
+            (control_pos, gc),
+            (control_neg, -gc),
+        ];
+
+        let mut triples = Vec::with_capacity(8);
+        for (row, sign) in [(nodes[0], false), (nodes[1], true)] {
+            for &(col, value) in &columns {
+                if let (Some(row), Some(col)) = (row, col) {
+                    triples.push((row, col, if sign { -value } else { value }));
+                }
+            }
+        }
+        Triples::new(&triples)
+    }
+
+    /// Returns the operating region at the control value `control`.
+    pub fn region(&self, control: Numeric) -> &'static str {
+        let SwitchOptions {
+            threshold,
+            hysteresis,
+            ..
+        } = self.options;
+        let hysteresis = hysteresis.abs();
+        if !self.options.is_smooth() {
+            return if self.state(control) { "on" } else { "off" };
+        }
+        if control >= threshold + hysteresis {
+            "on"
+        } else if control <= threshold - hysteresis {
+            "off"
+        } else {
+            "transition"
+        }
+    }
+
+    /// Returns the fraction of the step from `x_prev` to `x_new`, at which the control crosses
+    /// the threshold, if the switch changes its state at `x_new`. The control is interpolated
+    /// linearly over the step.
+    pub fn crossing(&self, x_prev: &[Numeric], x_new: &[Numeric]) -> Option<Numeric> {
+        let [_, control] = self.voltages(x_new);
+        let on = self.state(control);
+        if on == self.on || self.options.is_smooth() {
+            return None;
+        }
+        let [_, control_prev] = self.voltages(x_prev);
+        let hysteresis = self.options.hysteresis.abs();
+        let level = if on {
+            self.options.threshold + hysteresis
+        } else {
+            self.options.threshold - hysteresis
+        };
+        let change = control - control_prev;
+        if change == Numeric::zero() {
+            return Some(Numeric::zero());
+        }
+        Some(((level - control_prev) / change).clamp(Numeric::zero(), 1.0))
+    }
+
+    /// Records the state of the switch at the accepted solution `x_vec`.
+    /// Returns whether the switch changed its state.
+    pub fn accept(&mut self, x_vec: &[Numeric]) -> bool {
+        let [_, control] = self.voltages(x_vec);
+        let on = self.state(control);
+        let switched = on != self.on && !self.options.is_smooth();
+        self.on = on;
+        switched
+    }
+
+    /// Returns the switch to its initial state.
+    pub fn reset(&mut self) {
+        self.on = self.initial_on;
+    }
+
+    /// Returns the indices of the nodes or the branch of the control.
+    fn control_indices(&self) -> [Option<usize>; 2] {
+        match &self.control {
+            SwitchControl::Voltage(positive, negative) => [
+                positive.as_ref().map(Variable::idx),
+                negative.as_ref().map(Variable::idx),
+            ],
+            SwitchControl::Current(branch) => [branch.as_ref().map(Variable::idx), None],
+        }
+    }
+
+    pub fn node0_idx(&self) -> Option<usize> {
+        self.positive.as_ref().map(|v| v.idx())
+    }
+
+    pub fn node1_idx(&self) -> Option<usize> {
+        self.negative.as_ref().map(|v| v.idx())
+    }
+
+    /// Returns the indices of the control nodes of a voltage controlled switch.
+    pub fn control_node_indices(&self) -> Option<(Option<usize>, Option<usize>)> {
+        match &self.control {
+            SwitchControl::Voltage(positive, negative) => Some((
+                positive.as_ref().map(Variable::idx),
+                negative.as_ref().map(Variable::idx),
+            )),
+            SwitchControl::Current(_) => None,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+/// The Switch - yaml parsing module
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::{SwitchControl, SwitchOptions};
+use crate::frontends::{get_variable, serde::ProcessSerdeElement};
+use crate::models::{Element, SwitchBundle, Unit, Variable};
+use crate::spot::Numeric;
+
+/// A voltage controlled switch - S element
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SerdeSwitch {
+    pub name: String,
+    pub positive: String,
+    pub negative: String,
+    pub controlling_positive: String,
+    pub controlling_negative: String,
+    /// The model parameters with their SPICE names, like `ron: 0.1`
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Numeric>,
+    /// Whether the switch is on at the start of an analysis
+    #[serde(default)]
+    pub on: bool,
+}
+
+/// A current controlled switch - W element
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SerdeCSwitch {
+    pub name: String,
+    pub positive: String,
+    pub negative: String,
+    /// The name of the voltage source, whose current controls the switch
+    pub controlling_source: String,
+    /// The model parameters with their SPICE names, like `it: 1e-3`
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Numeric>,
+    /// Whether the switch is on at the start of an analysis
+    #[serde(default)]
+    pub on: bool,
+}
+
+impl ProcessSerdeElement for SerdeSwitch {
+    fn process(
+        &self,
+        variables: &mut Vec<Variable>,
+        elements: &mut Vec<Element>,
+        var_map: &mut HashMap<Arc<str>, usize>,
+    ) {
+        let nodes = [
+            get_variable(self.positive.as_str(), Unit::Volt, variables, var_map),
+            get_variable(self.negative.as_str(), Unit::Volt, variables, var_map),
+        ];
+        let control = SwitchControl::Voltage(
+            get_variable(
+                self.controlling_positive.as_str(),
+                Unit::Volt,
+                variables,
+                var_map,
+            ),
+            get_variable(
+                self.controlling_negative.as_str(),
+                Unit::Volt,
+                variables,
+                var_map,
+            ),
+        );
+        let switch = switch(&self.name, nodes, control, &self.parameters, self.on);
+        elements.push(Element::Switch(switch));
+    }
+}
+
+impl ProcessSerdeElement for SerdeCSwitch {
+    fn process(
+        &self,
+        variables: &mut Vec<Variable>,
+        elements: &mut Vec<Element>,
+        var_map: &mut HashMap<Arc<str>, usize>,
+    ) {
+        let nodes = [
+            get_variable(self.positive.as_str(), Unit::Volt, variables, var_map),
+            get_variable(self.negative.as_str(), Unit::Volt, variables, var_map),
+        ];
+        let branch = format!("{}#branch", self.controlling_source);
+        let control =
+            SwitchControl::Current(get_variable(&branch, Unit::Ampere, variables, var_map));
+        let switch = switch(&self.name, nodes, control, &self.parameters, self.on);
+        elements.push(Element::Switch(switch));
+    }
+}
+
+/// Creates a switch between `positive` and `negative` with the model parameters `parameters`.
+fn switch(
+    name: &str,
+    [positive, negative]: [Option<Variable>; 2],
+    control: SwitchControl,
+    parameters: &BTreeMap<String, Numeric>,
+    on: bool,
+) -> SwitchBundle {
+    let mut options = SwitchOptions::default();
+    for (parameter, value) in parameters {
+        if !options.set_parameter(parameter, *value) {
+            warn!("Ignoring unsupported parameter '{parameter}' of {name}");
+        }
+    }
+
+    let mut switch = SwitchBundle::new(Arc::from(name), positive, negative, control, Some(options));
+    switch.set_initial_state(on);
+    switch
+}
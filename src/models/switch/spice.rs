@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use log::warn;
+
+use super::{SwitchControl, SwitchOptions};
+use crate::frontends::get_variable;
+use crate::frontends::spice::{ApplySpiceModel, ProcessSpiceElement, Rule, SpiceModel};
+use crate::frontends::spice_parser_helpers::SpiceElementParser;
+use crate::frontends::FrontendError;
+use crate::models::{Element, SwitchBundle, Unit};
+
+impl ProcessSpiceElement for SwitchBundle {
+    fn process(
+        element: pest::iterators::Pair<crate::frontends::spice::Rule>,
+        variables: &mut Vec<crate::models::Variable>,
+        elements: &mut Vec<crate::models::Element>,
+        var_map: &mut std::collections::HashMap<std::sync::Arc<str>, usize>,
+    ) -> Result<(), crate::frontends::FrontendError> {
+        let current_controlled = element.as_rule() == Rule::ELE_CSWITCH;
+        let mut parser = SpiceElementParser::new(element);
+
+        let name = parser.parse_name("switch")?;
+        let positive = parser.parse_node("switch", name, "positive node")?;
+        let positive = get_variable(positive, Unit::Volt, variables, var_map);
+        let negative = parser.parse_node("switch", name, "negative node")?;
+        let negative = get_variable(negative, Unit::Volt, variables, var_map);
+        // A W element refers to the voltage source, whose branch current controls it
+        let control = if current_controlled {
+            let source = parser.parse_node("switch", name, "controlling source")?;
+            let branch = format!("{source}#branch");
+            SwitchControl::Current(get_variable(&branch, Unit::Ampere, variables, var_map))
+        } else {
+            let control_positive = parser.parse_node("switch", name, "positive control node")?;
+            let control_negative = parser.parse_node("switch", name, "negative control node")?;
+            SwitchControl::Voltage(
+                get_variable(control_positive, Unit::Volt, variables, var_map),
+                get_variable(control_negative, Unit::Volt, variables, var_map),
+            )
+        };
+        let model = parser.parse_model();
+        let initial_state = parser.parse_switch_state();
+
+        let mut switch = SwitchBundle::new(Arc::from(name), positive, negative, control, None);
+        if let Some(model) = model {
+            switch.set_model(Arc::from(model));
+        }
+        if let Some(on) = initial_state {
+            switch.set_initial_state(on);
+        }
+        elements.push(Element::Switch(switch));
+        Ok(())
+    }
+}
+
+impl ApplySpiceModel for SwitchBundle {
+    fn model_name(&self) -> Option<Arc<str>> {
+        self.model()
+    }
+
+    fn apply_model(
+        &mut self,
+        model: &SpiceModel,
+        _variables: &mut Vec<crate::models::Variable>,
+        _var_map: &mut std::collections::HashMap<Arc<str>, usize>,
+    ) -> Result<(), FrontendError> {
+        let kind = if self.is_current_controlled() {
+            "CSW"
+        } else {
+            "SW"
+        };
+        if !model.kind.eq_ignore_ascii_case(kind) {
+            return Err(FrontendError::InvalidModel {
+                model: model.name.to_string(),
+                reason: format!(
+                    "Unknown model type '{}' of {}. Use {kind}",
+                    model.kind,
+                    self.name()
+                ),
+            });
+        }
+        let mut options = SwitchOptions::default();
+        for (parameter, value) in &model.parameters {
+            if !options.set_parameter(parameter, *value) {
+                warn!(
+                    "Ignoring unsupported parameter '{parameter}' of model {}",
+                    model.name
+                );
+            }
+        }
+        self.set_options(options);
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+// Basic tests for switch model
+use super::{SwitchBundle, SwitchControl, SwitchOptions};
+use crate::models::{Unit, Variable};
+use crate::spot::Numeric;
+use std::sync::Arc;
+
+/// Creates a voltage controlled switch between the indices 0 and 1, controlled by the voltage
+/// at the index 2.
+fn switch(options: SwitchOptions) -> SwitchBundle {
+    let positive = Variable::new(Arc::from("pos"), Unit::Volt, 0);
+    let negative = Variable::new(Arc::from("neg"), Unit::Volt, 1);
+    let control = Variable::new(Arc::from("ctrl"), Unit::Volt, 2);
+
+    SwitchBundle::new(
+        Arc::from("S1"),
+        Some(positive),
+        Some(negative),
+        SwitchControl::Voltage(Some(control), None),
+        Some(options),
+    )
+}
+
+/// Returns the currents into the nodes of the switch at `x_vec`, which are the linearised
+/// currents `G * x - b` of its stamps.
+fn node_currents(switch: &SwitchBundle, x_vec: &[Numeric]) -> Vec<Numeric> {
+    let mut currents = vec![0.0; x_vec.len()];
+    for &(row, col, value) in &switch.triples(x_vec) {
+        currents[row] += value * x_vec[col];
+    }
+    for &(row, value) in &switch.pairs(x_vec) {
+        currents[row] -= value;
+    }
+    currents
+}
+
+#[test]
+fn test_switch_set_parameter() {
+    let mut options = SwitchOptions::default();
+    assert!(options.set_parameter("RON", 0.1));
+    assert!(options.set_parameter("roff", 1e6));
+    assert!(options.set_parameter("VT", 2.5));
+    assert!(options.set_parameter("ih", 0.5));
+    assert!(!options.set_parameter("lambda", 1.0));
+    assert_eq!(
+        options,
+        SwitchOptions::default()
+            .with_resistances(0.1, 1e6)
+            .with_threshold(2.5, 0.5)
+    );
+}
+
+#[test]
+fn test_switch_on_off() {
+    let switch = switch(SwitchOptions::default().with_resistances(10.0, 1e6));
+
+    let on = node_currents(&switch, &[1.0, 0.0, 1.0]);
+    assert_eq!(on[0], 0.1);
+    assert_eq!(on[1], -0.1);
+    assert_eq!(on[2], 0.0);
+    assert_eq!(switch.region(1.0), "on");
+
+    let off = node_currents(&switch, &[1.0, 0.0, -1.0]);
+    assert_eq!(off[0], 1e-6);
+    assert_eq!(switch.region(-1.0), "off");
+}
+
+#[test]
+fn test_switch_hysteresis() {
+    let mut switch = switch(SwitchOptions::default().with_threshold(1.0, 0.5));
+    assert!(!switch.is_on());
+
+    // Within the band VT ± VH the switch keeps its state
+    assert!(!switch.accept(&[0.0, 0.0, 1.4]));
+    assert_eq!(switch.region(1.4), "off");
+    assert!(switch.accept(&[0.0, 0.0, 1.6]));
+    assert!(switch.is_on());
+    assert!(!switch.accept(&[0.0, 0.0, 0.6]));
+    assert_eq!(switch.region(0.6), "on");
+    assert!(switch.accept(&[0.0, 0.0, 0.4]));
+    assert!(!switch.is_on());
+
+    // A step across the threshold needs another Newton iteration
+    let (_, limited) = switch.limit_voltages([0.0, 1.6], [0.0, 1.4]);
+    assert!(limited);
+    let (_, limited) = switch.limit_voltages([0.0, 1.2], [0.0, 1.4]);
+    assert!(!limited);
+}
+
+#[test]
+fn test_switch_initial_state() {
+    let mut switch = switch(SwitchOptions::default().with_threshold(1.0, 0.5));
+    switch.set_initial_state(true);
+    assert!(switch.is_on());
+
+    assert!(switch.accept(&[0.0, 0.0, 0.0]));
+    assert!(!switch.is_on());
+    switch.reset();
+    assert!(switch.is_on());
+}
+
+#[test]
+fn test_switch_smooth_jacobian() {
+    let switch = switch(
+        SwitchOptions::default()
+            .with_resistances(1.0, 1e4)
+            .with_threshold(1.0, -0.5),
+    );
+    let h = 1e-7;
+
+    assert_eq!(switch.region(1.2), "transition");
+    // Within and at both sides of the transition
+    for x_vec in [
+        [2.0, 0.5, 1.2],
+        [-1.0, 0.0, 0.8],
+        [1.0, 0.0, 2.0],
+        [1.0, 0.0, 0.0],
+    ] {
+        let mut jacobian = [[0.0; 3]; 3];
+        for &(row, col, value) in &switch.triples(&x_vec) {
+            jacobian[row][col] += value;
+        }
+        for col in 0..3 {
+            let mut x_plus = x_vec;
+            let mut x_minus = x_vec;
+            x_plus[col] += h;
+            x_minus[col] -= h;
+            let plus = node_currents(&switch, &x_plus);
+            let minus = node_currents(&switch, &x_minus);
+            for row in 0..3 {
+                let numeric = (plus[row] - minus[row]) / (2.0 * h);
+                let tolerance = 1e-5 * numeric.abs() + 1e-10;
+                assert!(
+                    (jacobian[row][col] - numeric).abs() < tolerance,
+                    "dI{row}/dV{col} at {x_vec:?}: {} != {numeric}",
+                    jacobian[row][col]
+                );
+            }
+        }
+    }
+
+    // The conductance reaches RON and ROFF at the ends of the transition
+    assert!((node_currents(&switch, &[1.0, 0.0, 1.5])[0] - 1.0).abs() < 1e-12);
+    assert!((node_currents(&switch, &[1.0, 0.0, 0.5])[0] - 1e-4).abs() < 1e-16);
+}
+
+#[test]
+fn test_switch_current_controlled() {
+    let positive = Variable::new(Arc::from("pos"), Unit::Volt, 0);
+    let branch = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 1);
+    let mut switch = SwitchBundle::new(
+        Arc::from("W1"),
+        Some(positive),
+        None,
+        SwitchControl::Current(Some(branch.clone())),
+        Some(SwitchOptions::default().with_threshold(1e-3, 0.0)),
+    );
+
+    assert!(switch.is_current_controlled());
+    assert_eq!(switch.controlling_branch(), Some(Some(&branch)));
+    assert_eq!(switch.control_node_indices(), None);
+    assert_eq!(switch.voltages(&[2.0, 5e-3]), [2.0, 5e-3]);
+    assert!(switch.accept(&[2.0, 5e-3]));
+    assert_eq!(node_currents(&switch, &[2.0, 5e-3]), vec![2.0, 0.0]);
+}
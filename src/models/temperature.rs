@@ -0,0 +1,78 @@
+/// The Temperature Module. Device parameters are given at the nominal temperature `tnom` and
+/// scaled to the temperature of each device instance, as in SPICE.
+use crate::sim::options::SimulatorOptions;
+use crate::spot::*;
+
+/// The temperature of a device instance, set by the `TEMP=` and `DTEMP=` instance parameters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct DeviceTemperature {
+    /// The temperature of the device in °C, which replaces the circuit temperature
+    pub temp: Option<Numeric>,
+    /// The difference of the device to the circuit temperature in K
+    pub dtemp: Numeric,
+}
+
+impl DeviceTemperature {
+    /// Creates the temperature of a device instance. Without parameters the device follows
+    /// the circuit temperature.
+    pub fn new(temp: Option<Numeric>, dtemp: Option<Numeric>) -> Self {
+        Self {
+            temp,
+            dtemp: dtemp.unwrap_or_default(),
+        }
+    }
+
+    /// Sets the instance parameter `name` (`temp` or `dtemp`).
+    /// Returns false if `name` is not a temperature parameter.
+    pub fn set_parameter(&mut self, name: &str, value: Numeric) -> bool {
+        match name.to_ascii_lowercase().as_str() {
+            "temp" => self.temp = Some(value),
+            "dtemp" => self.dtemp = value,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns the temperature of the device in K. An absolute `temp` takes precedence over
+    /// the offset `dtemp` to the circuit temperature.
+    pub fn kelvin(&self, options: &SimulatorOptions) -> Numeric {
+        self.temp.unwrap_or(options.temp + self.dtemp) + CELSIUS_TO_KELVIN
+    }
+}
+
+/// Returns the thermal voltage `k * T / q` at `temp` in K.
+pub fn thermal_voltage(temp: Numeric) -> Numeric {
+    KB * temp / ELE_CHRG
+}
+
+/// Scales the saturation current `is` of a pn junction from `tnom` to `temp` (both in K).
+///
+/// # Arguments
+///
+/// * `n` - The emission coefficient of the junction.
+/// * `eg` - The band gap energy in eV.
+/// * `xti` - The temperature exponent of the saturation current.
+pub fn saturation_current(
+    is: Numeric,
+    n: Numeric,
+    eg: Numeric,
+    xti: Numeric,
+    temp: Numeric,
+    tnom: Numeric,
+) -> Numeric {
+    let ratio = temp / tnom;
+    let exponent = (ratio - 1.0) * eg / (n * thermal_voltage(temp));
+    is * ratio.powf(xti / n) * exponent.exp()
+}
+
+/// Scales a parameter proportional to the carrier mobility, like the transconductance `KP`,
+/// from `tnom` to `temp` (both in K). The mobility falls with `(T / Tnom)^bex`.
+pub fn mobility(value: Numeric, bex: Numeric, temp: Numeric, tnom: Numeric) -> Numeric {
+    value * (temp / tnom).powf(bex)
+}
+
+/// Scales `value` by the polynomial `1 + tc1 * dt + tc2 * dt²` of the temperature difference
+/// `dt` in K, as the `TC1`/`TC2` coefficients of resistors.
+pub fn polynomial(value: Numeric, tc1: Numeric, tc2: Numeric, dt: Numeric) -> Numeric {
+    value * (1.0 + tc1 * dt + tc2 * dt * dt)
+}
@@ -0,0 +1,151 @@
+use crate::models::charge::*;
+use crate::models::diode::DiodeOptions;
+use crate::models::integration::{BackwardEuler, StateHistory};
+use crate::models::mosfet::Mos0Options;
+use crate::models::{DiodeBundle, Mos0Bundle, Unit, Variable};
+use std::sync::Arc;
+
+#[test]
+fn test_linear_charge() {
+    let charge = Charge::linear(Some(0), Some(1), 1e-6, &[3.0, 1.0]);
+    assert_eq!(charge.voltage, 2.0);
+    assert_eq!(charge.charge, 2e-6);
+    assert_eq!(charge.capacitance, 1e-6);
+}
+
+#[test]
+fn test_linear_charge_companion_backward_euler() {
+    // C = 1µF, dt = 1µs: G = C/dt = 1, history current = C/dt * v_prev
+    let integrator = BackwardEuler::new(1e-6);
+    let charge = Charge::linear(Some(0), Some(1), 1e-6, &[3.0, 1.0]);
+    let history = StateHistory::new(5e-6);
+
+    let triples = charge.triples(&integrator);
+    assert_eq!(triples.len(), 4);
+    assert!((triples[0].2 - 1.0).abs() < 1e-12);
+    assert!((triples[2].2 + 1.0).abs() < 1e-12);
+
+    let pairs = charge.pairs(&history, &integrator);
+    assert_eq!(pairs.len(), 2);
+    assert!((pairs[0].1 - 5.0).abs() < 1e-9);
+    assert!((pairs[1].1 + 5.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_grounded_charge_pairs() {
+    let integrator = BackwardEuler::new(1.0);
+    let history = StateHistory::new(2.0);
+
+    let node0_only = Charge::linear(Some(0), None, 1.0, &[0.0]);
+    assert_eq!(node0_only.pairs(&history, &integrator)[0].1, 2.0);
+
+    let node1_only = Charge::linear(None, Some(0), 1.0, &[0.0]);
+    assert_eq!(node1_only.pairs(&history, &integrator)[0].1, -2.0);
+}
+
+#[test]
+fn test_junction_charge_without_capacitance() {
+    assert_eq!(junction_charge(0.3, 0.0, 0.7, 0.5, 0.5), (0.0, 0.0));
+}
+
+#[test]
+fn test_junction_capacitance_is_charge_derivative() {
+    let (cj0, vj, m, fc) = (1e-12, 0.7, 0.4, 0.5);
+    let dv = 1e-6;
+    for voltage in [-5.0, -1.0, 0.0, 0.2, 0.35, 0.5, 0.8] {
+        let (_, capacitance) = junction_charge(voltage, cj0, vj, m, fc);
+        let (q_plus, _) = junction_charge(voltage + dv, cj0, vj, m, fc);
+        let (q_minus, _) = junction_charge(voltage - dv, cj0, vj, m, fc);
+        let numeric = (q_plus - q_minus) / (2.0 * dv);
+        assert!(
+            (numeric - capacitance).abs() < 1e-6 * cj0,
+            "v = {voltage}: {numeric} != {capacitance}"
+        );
+    }
+}
+
+#[test]
+fn test_junction_charge_with_unit_grading() {
+    let (cj0, vj, m, fc) = (1e-12, 0.7, 1.0, 0.5);
+    let dv = 1e-6;
+    for voltage in [-5.0, 0.0, 0.2, 0.5] {
+        let (charge, capacitance) = junction_charge(voltage, cj0, vj, m, fc);
+        let (q_plus, _) = junction_charge(voltage + dv, cj0, vj, m, fc);
+        let (q_minus, _) = junction_charge(voltage - dv, cj0, vj, m, fc);
+        assert!(charge.is_finite() && capacitance.is_finite());
+        assert!(
+            ((q_plus - q_minus) / (2.0 * dv) - capacitance).abs() < 1e-6 * cj0,
+            "v = {voltage}"
+        );
+    }
+
+    // The charge approaches the limit -cj0 * vj * ln(1 - v / vj) of the power law
+    let (charge, _) = junction_charge(-1.0, cj0, vj, m, fc);
+    let (close, _) = junction_charge(-1.0, cj0, vj, 1.0 - 1e-4, fc);
+    assert!((charge + cj0 * vj * (1.0 + 1.0 / vj).ln()).abs() < 1e-24);
+    assert!((charge - close).abs() < 1e-4 * charge.abs());
+}
+
+#[test]
+fn test_junction_charge_is_continuous_at_fc() {
+    let (cj0, vj, m, fc) = (1e-12, 0.7, 0.4, 0.5);
+    let v_fc = fc * vj;
+    let below = junction_charge(v_fc - 1e-9, cj0, vj, m, fc);
+    let above = junction_charge(v_fc, cj0, vj, m, fc);
+    assert!((below.0 - above.0).abs() < 1e-20);
+    assert!((below.1 - above.1).abs() < 1e-18);
+}
+
+#[test]
+fn test_diode_charge() {
+    let diode = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(Variable::new(Arc::from("a"), Unit::Volt, 0)),
+        None,
+        Some(
+            DiodeOptions::default()
+                .with_junction_capacitance(1e-12, 0.7, 0.5)
+                .with_transit_time(1e-9),
+        ),
+    );
+
+    let reverse = diode.charge(&[-2.0]);
+    let (q_dep, c_dep) = junction_charge(-2.0, 1e-12, 0.7, 0.5, 0.5);
+    assert_eq!(reverse.voltage, -2.0);
+    assert!((reverse.charge - q_dep).abs() < 1e-20);
+    assert!((reverse.capacitance - c_dep).abs() < 1e-20);
+
+    // In forward bias the diffusion charge tt * id dominates
+    let forward = diode.charge(&[0.7]);
+    assert!(forward.capacitance > 1e-10);
+}
+
+#[test]
+fn test_diode_without_capacitance_has_no_charge() {
+    let diode = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(Variable::new(Arc::from("a"), Unit::Volt, 0)),
+        Some(Variable::new(Arc::from("c"), Unit::Volt, 1)),
+        None,
+    );
+    let charge = diode.charge(&[-1.0, 0.0]);
+    assert_eq!(charge.charge, 0.0);
+    assert_eq!(charge.capacitance, 0.0);
+}
+
+#[test]
+fn test_mos0_gate_charges() {
+    let mosfet = Mos0Bundle::new(
+        Arc::from("M1"),
+        Some(Variable::new(Arc::from("g"), Unit::Volt, 0)),
+        Some(Variable::new(Arc::from("d"), Unit::Volt, 1)),
+        None,
+        Some(Mos0Options::default().with_gate_capacitances(2e-15, 1e-15)),
+    );
+
+    let [cgs, cgd] = mosfet.charges(&[1.0, 3.0]);
+    assert_eq!((cgs.node0, cgs.node1), (Some(0), None));
+    assert_eq!(cgs.charge, 2e-15);
+    assert_eq!((cgd.node0, cgd.node1), (Some(0), Some(1)));
+    assert_eq!(cgd.charge, -2e-15);
+}
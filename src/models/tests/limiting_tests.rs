@@ -0,0 +1,49 @@
+use crate::models::limiting::*;
+use crate::spot::*;
+
+#[test]
+fn test_pn_junction_small_step_is_not_limited() {
+    let v_crit = pn_critical_voltage(UT, 1e-14);
+    assert_eq!(pn_junction(0.61, 0.6, UT, v_crit), (0.61, false));
+    assert_eq!(pn_junction(0.3, 0.0, UT, v_crit), (0.3, false));
+}
+
+#[test]
+fn test_pn_junction_large_forward_step() {
+    let v_crit = pn_critical_voltage(UT, 1e-14);
+    let (voltage, limited) = pn_junction(100.0, 0.7, UT, v_crit);
+
+    // The current may only grow by the factor (v_new - v_old) / vt
+    assert!(limited);
+    assert!(voltage > 0.7 && voltage < 1.0, "Limited to {voltage}V");
+    assert!((voltage - (0.7 + UT * (1.0 + 99.3 / UT).ln())).abs() < 1e-12);
+}
+
+#[test]
+fn test_pn_junction_from_reverse_bias() {
+    let v_crit = pn_critical_voltage(UT, 1e-14);
+    let (voltage, limited) = pn_junction(10.0, -1.0, UT, v_crit);
+    assert!(limited);
+    assert!((voltage - UT * (10.0 / UT).ln()).abs() < 1e-12);
+}
+
+#[test]
+fn test_fet_gate_turning_on() {
+    // From off, the gate voltage may not jump far beyond the threshold
+    assert_eq!(fet_gate(5.0, 0.0, 0.5), (1.0, true));
+    assert_eq!(fet_gate(0.8, 0.0, 0.5), (0.8, false));
+}
+
+#[test]
+fn test_fet_gate_in_middle_region() {
+    assert_eq!(fet_gate(10.0, 1.0, 0.5), (4.5, true));
+    assert_eq!(fet_gate(-5.0, 1.0, 0.5), (0.0, true));
+}
+
+#[test]
+fn test_fet_drain() {
+    assert_eq!(fet_drain(10.0, 1.0), (4.0, true));
+    assert_eq!(fet_drain(-3.0, 1.0), (-0.5, true));
+    assert_eq!(fet_drain(20.0, 4.0), (14.0, true));
+    assert_eq!(fet_drain(2.5, 2.0), (2.5, false));
+}
@@ -0,0 +1,62 @@
+use crate::models::temperature::*;
+use crate::sim::options::SimulatorOptions;
+use crate::spot::*;
+
+#[test]
+fn test_thermal_voltage_at_default_temperature() {
+    assert_eq!(thermal_voltage(TEMP), UT);
+}
+
+#[test]
+fn test_device_temperature_follows_circuit() {
+    let options = SimulatorOptions {
+        temp: 50.0,
+        ..Default::default()
+    };
+
+    let circuit = DeviceTemperature::default();
+    assert_eq!(circuit.kelvin(&options), 50.0 + CELSIUS_TO_KELVIN);
+
+    let offset = DeviceTemperature::new(None, Some(10.0));
+    assert_eq!(offset.kelvin(&options), 60.0 + CELSIUS_TO_KELVIN);
+
+    // An absolute instance temperature ignores the circuit temperature and the offset
+    let absolute = DeviceTemperature::new(Some(100.0), Some(10.0));
+    assert_eq!(absolute.kelvin(&options), 100.0 + CELSIUS_TO_KELVIN);
+}
+
+#[test]
+fn test_device_temperature_parameters() {
+    let mut temperature = DeviceTemperature::default();
+    assert!(temperature.set_parameter("TEMP", 85.0));
+    assert!(temperature.set_parameter("dtemp", -5.0));
+    assert!(!temperature.set_parameter("tc1", 1e-3));
+    assert_eq!(temperature, DeviceTemperature::new(Some(85.0), Some(-5.0)));
+}
+
+#[test]
+fn test_saturation_current_scaling() {
+    let tnom = TEMP;
+    assert_eq!(saturation_current(1e-14, 1.0, 1.11, 3.0, tnom, tnom), 1e-14);
+
+    // Roughly doubles every 5K for silicon at room temperature
+    let ratio = saturation_current(1e-14, 1.0, 1.11, 3.0, tnom + 5.0, tnom) / 1e-14;
+    assert!(ratio > 1.8 && ratio < 2.4, "Ratio {ratio}");
+
+    let cold = saturation_current(1e-14, 1.0, 1.11, 3.0, tnom - 50.0, tnom);
+    assert!(cold < 1e-16, "Saturation current {cold}A");
+}
+
+#[test]
+fn test_mobility_scaling() {
+    assert_eq!(mobility(1e-4, -1.5, TEMP, TEMP), 1e-4);
+    let hot = mobility(1e-4, -1.5, 2.0 * TEMP, TEMP);
+    assert!((hot - 1e-4 / 2.0_f64.powf(1.5)).abs() < 1e-18);
+}
+
+#[test]
+fn test_polynomial_scaling() {
+    assert_eq!(polynomial(1000.0, 1e-3, 0.0, 0.0), 1000.0);
+    assert!((polynomial(1000.0, 1e-3, 1e-5, 10.0) - 1011.0).abs() < 1e-9);
+    assert!((polynomial(1000.0, 1e-3, 0.0, -10.0) - 990.0).abs() < 1e-9);
+}
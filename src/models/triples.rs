@@ -30,6 +30,11 @@ impl<T: Copy + Default, const N: usize> Triples<T, N> {
     pub(crate) fn data(&self) -> [(usize, usize, T); N] {
         self.data
     }
+
+    /// Returns the same triples with the larger capacity `M`.
+    pub(crate) fn widen<const M: usize>(self) -> Triples<T, M> {
+        Triples::new(&self.data[..self.length])
+    }
 }
 
 impl<T: Copy + Default, const N: usize> Index<usize> for Triples<T, N> {
@@ -130,6 +135,16 @@ impl<const N: usize> TripleIdx<N> {
     pub fn data(&self) -> [(usize, usize); N] {
         self.data
     }
+
+    /// Returns the rows of the stored indices.
+    pub(crate) fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.data[..self.length].iter().map(|&(row, _)| row)
+    }
+
+    /// Returns the same indices with the larger capacity `M`.
+    pub(crate) fn widen<const M: usize>(self) -> TripleIdx<M> {
+        TripleIdx::new(&self.data[..self.length])
+    }
 }
 
 #[cfg(test)]
@@ -185,9 +185,8 @@ impl VSourceSinBundle {
     /// Returns the pairs representing vector b.
     pub fn pairs(&self, time: Option<&Numeric>) -> Pairs<Numeric, 2> {
         let value = if let Some(t) = time {
-            // For transient analysis: DC offset + AC component (sinusoidal part)
-            self.dc_offset + self.amplitude
-                * (2.0 * std::f64::consts::PI * self.frequency * t + self.phase).sin()
+            // For transient analysis: only the AC component, the DC offset is a constant pair
+            self.amplitude * (2.0 * std::f64::consts::PI * self.frequency * t + self.phase).sin()
         } else {
             // For OP analysis: only DC offset
             self.dc_offset
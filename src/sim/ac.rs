@@ -26,7 +26,11 @@ impl<SO: Solver> AcSimulation<SO> for Simulator<SO> {
     ) -> Result<Sim, SimulatorError> {
         info!("Run ac analysis");
         info!("Find operating point");
-        self.find_op()?;
+        let x_op: Vec<Numeric> = self
+            .find_op()?
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
 
         // Calculate frequencies in the range from [fstart;fend]
         // TODO: Consider refactoring to reduce nesting complexity
@@ -59,13 +63,11 @@ impl<SO: Solver> AcSimulation<SO> for Simulator<SO> {
 
         let mut ac_results = Vec::new();
         for freq in freqs {
-            self.build_ac_a_mat(freq);
+            self.build_ac_a_mat(freq, &x_op);
             self.build_ac_b_vec(freq);
+            self.dump_cplx_system(freq)?;
 
-            let x_new = match self.solver.solve_cplx().cloned() {
-                Ok(solution) => solution,
-                Err(err) => return Err(err.into()),
-            };
+            let x_new = self.solver.solve_cplx().cloned()?;
 
             let x_new = self.add_complex_var_name(x_new);
 
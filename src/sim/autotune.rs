@@ -2,7 +2,7 @@ use log::info;
 
 use crate::models::Element;
 use crate::sim::commands::SimulationCommand;
-use crate::sim::options::{IntegrationMethod, SimulationOption};
+use crate::sim::options::{IntegrationMethod, SimulatorOptions};
 use crate::spot::Numeric;
 
 /// Represents a time constant analysis result
@@ -25,13 +25,12 @@ struct CircuitCharacteristics {
     has_mosfets: bool,
 }
 
-/// Analyzes the circuit and suggests optimal simulation settings
+/// Analyzes the circuit and applies the suggested simulation settings to `options`
 pub fn analyze_circuit_and_suggest_settings(
     elements: &[Element],
     commands: &[SimulationCommand],
-) -> Vec<SimulationOption> {
-    let mut options = Vec::new();
-
+    options: &mut SimulatorOptions,
+) {
     info!("Analyzing circuit for optimal simulation settings...");
 
     // Perform comprehensive circuit analysis
@@ -40,7 +39,7 @@ pub fn analyze_circuit_and_suggest_settings(
     // Analyze simulation commands
     let has_transient = commands
         .iter()
-        .any(|cmd| matches!(cmd, SimulationCommand::Tran(_, _)));
+        .any(|cmd| matches!(cmd, SimulationCommand::Tran(..)));
     let has_ac = commands
         .iter()
         .any(|cmd| matches!(cmd, SimulationCommand::Ac(_, _, _, _)));
@@ -76,9 +75,7 @@ pub fn analyze_circuit_and_suggest_settings(
         circuit_analysis.has_capacitors,
         circuit_analysis.has_inductors,
     );
-    options.push(SimulationOption::IntegrationMethod(
-        integration_method.clone(),
-    ));
+    options.method = integration_method.clone();
 
     // Suggest optimal timestep for transient simulations
     if has_transient {
@@ -87,9 +84,8 @@ pub fn analyze_circuit_and_suggest_settings(
                 "  -> Suggested max timestep: {:.2e} s for transient analysis",
                 suggested_timestep
             );
-            // Note: Time step suggestions for transient analysis
-            // Current limitation: Time step is part of Tran command structure
-            // TODO: Integrate time step autotuning directly into Tran command processing
+            // Only bounds the adaptive timestep, a tmax of the Tran command takes precedence
+            options.timestep.max = suggested_timestep.max(options.timestep.min);
         }
     }
 
@@ -100,8 +96,6 @@ pub fn analyze_circuit_and_suggest_settings(
 
     info!("Autotune suggestions applied:");
     info!("  Integration method: {:?}", integration_method);
-
-    options
 }
 
 /// Analyzes circuit elements and extracts characteristics
@@ -140,10 +134,13 @@ fn analyze_circuit_elements(elements: &[Element]) -> CircuitCharacteristics {
                 characteristics.has_diodes = true;
                 characteristics.has_nonlinear_elements = true;
             }
-            Element::Mos0(_) => {
+            Element::Mos0(_) | Element::Mos1(_) => {
                 characteristics.has_mosfets = true;
                 characteristics.has_nonlinear_elements = true;
             }
+            Element::Bjt(_) | Element::Jfet(_) | Element::Switch(_) | Element::Behavioral(_) => {
+                characteristics.has_nonlinear_elements = true
+            }
             _ => {}
         }
     }
@@ -8,7 +8,13 @@ pub enum SimulationCommand {
     /// Represents an operating point analysis command.
     Op,
     /// Represents a transient analysis command.
-    Tran(Numeric, Numeric), //Transient (Timestep, Stop Time)
+    Tran(
+        Numeric,         // Output Timestep
+        Numeric,         // Stop Time
+        Numeric,         // Start Time of the output
+        Option<Numeric>, // Optional maximum internal Timestep
+        bool,            // Use initial conditions (skip the operating point)
+    ),
     /// Represents an AC analysis command.
     Ac(Numeric, Numeric, usize, ACMode),
     /// Represents a DC analysis command.
@@ -0,0 +1,185 @@
+use log::{debug, info, warn};
+use num::{One, Zero};
+
+use crate::models::Unit;
+use crate::sim::options::OpStrategy;
+use crate::sim::report::ConvergenceReport;
+use crate::sim::SimulatorError;
+use crate::solver::Solver;
+use crate::spot::*;
+use crate::Simulator;
+
+/// The modifications of the circuit equations used by the continuation strategies.
+#[derive(Debug, Clone, Copy)]
+struct Homotopy<'a> {
+    /// Conductance from every node to ground in S
+    gmin: Numeric,
+    /// Scaling of all independent sources
+    source_factor: Numeric,
+    /// Conductance tying every node to the given voltages, as a backward Euler step would
+    pseudo_transient: Option<(Numeric, &'a [Numeric])>,
+}
+
+impl Default for Homotopy<'_> {
+    fn default() -> Self {
+        Self {
+            gmin: Numeric::zero(),
+            source_factor: Numeric::one(),
+            pseudo_transient: None,
+        }
+    }
+}
+
+impl<SO: Solver> Simulator<SO> {
+    /// Finds the operating point of a nonlinear circuit.
+    /// The configured strategies are tried in order until one of them converges.
+    pub(super) fn solve_op_continuation(
+        &mut self,
+        initial_conditions: &[(usize, Numeric)],
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        let x_guess = self.generate_initial_guess()?;
+        let mut last_error = SimulatorError::NonConvergentMaxIter {
+            max_iter: self.settings.itl1,
+            tol: self.settings.reltol,
+            report: ConvergenceReport::default(),
+        };
+
+        for strategy in self.settings.op_strategies.clone() {
+            let result = match strategy {
+                OpStrategy::Newton => {
+                    self.op_newton(&x_guess, initial_conditions, Homotopy::default())
+                }
+                OpStrategy::GminStepping => self.op_gmin_stepping(&x_guess, initial_conditions),
+                OpStrategy::SourceStepping => self.op_source_stepping(initial_conditions),
+                OpStrategy::PseudoTransient => {
+                    self.op_pseudo_transient(&x_guess, initial_conditions)
+                }
+            };
+
+            match result {
+                Ok(x) => {
+                    info!("Operating point found with {strategy}");
+                    return Ok(x);
+                }
+                Err(err) if err.is_backend_switch() => return Err(err),
+                Err(err) => {
+                    warn!("Operating point did not converge with {strategy}: {err}");
+                    last_error = err;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Starts with a large conductance from every node to ground, which is reduced by a decade
+    /// per step down to `gmin` and finally removed.
+    fn op_gmin_stepping(
+        &mut self,
+        x_guess: &[Numeric],
+        initial_conditions: &[(usize, Numeric)],
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        let mut x = x_guess.to_vec();
+        for step in (0..=self.settings.gmin_steps).rev() {
+            let gmin = self.settings.gmin * (10.0 as Numeric).powi(step as i32);
+            debug!("gmin stepping with gmin = {gmin}");
+            let homotopy = Homotopy {
+                gmin,
+                ..Default::default()
+            };
+            x = self.op_newton(&x, initial_conditions, homotopy)?;
+        }
+        self.op_newton(&x, initial_conditions, Homotopy::default())
+    }
+
+    /// Ramps all independent sources up from zero, starting each step from the previous solution.
+    fn op_source_stepping(
+        &mut self,
+        initial_conditions: &[(usize, Numeric)],
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        // Without sources the solution is zero
+        let mut x = vec![Numeric::zero(); self.vars.len()];
+        let steps = self.settings.source_steps.max(1);
+        for step in 1..=steps {
+            let source_factor = step as Numeric / steps as Numeric;
+            debug!("Source stepping with factor {source_factor}");
+            let homotopy = Homotopy {
+                source_factor,
+                ..Default::default()
+            };
+            x = self.op_newton(&x, initial_conditions, homotopy)?;
+        }
+        Ok(x)
+    }
+
+    /// Ties every node to its previous value with a conductance, like a backward Euler step
+    /// of capacitors to ground. The conductance is reduced while the steps converge and
+    /// increased when they fail, until the circuit has settled.
+    fn op_pseudo_transient(
+        &mut self,
+        x_guess: &[Numeric],
+        initial_conditions: &[(usize, Numeric)],
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        let mut x = x_guess.to_vec();
+        let mut conductance = PTRAN_CONDUCTANCE;
+        for _ in 0..self.settings.pseudo_transient_steps {
+            let homotopy = Homotopy {
+                pseudo_transient: Some((conductance, &x)),
+                ..Default::default()
+            };
+            match self.op_newton(&x, initial_conditions, homotopy) {
+                Ok(x_new) => {
+                    let settled = self.has_converged(&x, &x_new);
+                    x = x_new;
+                    if settled {
+                        return self.op_newton(&x, initial_conditions, Homotopy::default());
+                    }
+                    conductance /= 2.0;
+                }
+                Err(err) if err.is_backend_switch() => return Err(err),
+                Err(err) => {
+                    debug!("Pseudo transient step failed with G = {conductance}: {err}");
+                    conductance *= 10.0;
+                }
+            }
+        }
+        Err(SimulatorError::NonConvergentMaxIter {
+            max_iter: self.settings.pseudo_transient_steps,
+            tol: self.settings.reltol,
+            report: ConvergenceReport::default(),
+        })
+    }
+
+    /// Newton-Raphson iteration of the operating point equations modified by `homotopy`.
+    fn op_newton(
+        &mut self,
+        x_guess: &[Numeric],
+        initial_conditions: &[(usize, Numeric)],
+        homotopy: Homotopy,
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        self.newton_raphson(x_guess, self.settings.itl1, |sim, _| {
+            sim.build_scaled_constant_b_vec(homotopy.source_factor);
+            sim.build_initial_conditions(initial_conditions);
+            sim.build_homotopy(&homotopy);
+        })
+    }
+
+    fn build_homotopy(&mut self, homotopy: &Homotopy) {
+        let nodes = self
+            .vars
+            .iter()
+            .filter(|var| var.unit() == Unit::Volt)
+            .map(|var| var.idx())
+            .collect::<Vec<_>>();
+
+        for &idx in &nodes {
+            if homotopy.gmin > Numeric::zero() {
+                self.solver.insert_a(&(idx, idx, homotopy.gmin));
+            }
+            if let Some((conductance, x_prev)) = homotopy.pseudo_transient {
+                self.solver.insert_a(&(idx, idx, conductance));
+                self.solver.insert_b(&(idx, conductance * x_prev[idx]));
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use log::info;
+
+use crate::models::Variable;
+use crate::sim::options::DumpAnalysis;
+use crate::sim::SimulatorError;
+use crate::solver::matrix_market::{write_matrix, write_vector, MarketValue};
+use crate::solver::Solver;
+use crate::spot::*;
+use crate::Simulator;
+
+/// The point of the simulation, which selects the systems written by the matrix dump.
+#[derive(Debug, Clone, Default)]
+pub(super) struct DumpPoint {
+    /// The analysis being run.
+    analysis: Option<DumpAnalysis>,
+    /// The end of the transient timestep being solved.
+    time: Option<Numeric>,
+    /// The end of the timestep, which was selected by the dump time.
+    selected_time: Option<Numeric>,
+    /// The number of written systems, which numbers the files.
+    count: usize,
+}
+
+impl DumpPoint {
+    /// Starts an analysis, in which the dump time selects a timestep again.
+    pub(super) fn start(&mut self, analysis: DumpAnalysis) {
+        self.analysis = Some(analysis);
+        self.time = None;
+        self.selected_time = None;
+    }
+
+    /// Sets the end of the transient timestep being solved.
+    pub(super) fn set_time(&mut self, time: Numeric) {
+        self.time = Some(time);
+    }
+
+    /// Describes the point for the comments of the written files.
+    fn describe(&self) -> String {
+        let analysis = match self.analysis {
+            Some(analysis) => format!("{analysis} analysis"),
+            None => "analysis".to_string(),
+        };
+        match self.time {
+            Some(time) => format!("{analysis} at t = {time:e} s"),
+            None => analysis,
+        }
+    }
+}
+
+impl<SO: Solver> Simulator<SO> {
+    /// Returns true if the system of the Newton `iteration` at the current point of the
+    /// simulation is written by the matrix dump.
+    pub(super) fn is_dump_point(&self, iteration: usize) -> bool {
+        let options = &self.settings.dump;
+        let Some(analysis) = options.analysis else {
+            return false;
+        };
+        if analysis != DumpAnalysis::All && self.dump.analysis != Some(analysis) {
+            return false;
+        }
+        if options
+            .iteration
+            .is_some_and(|selected| selected != iteration)
+        {
+            return false;
+        }
+        match (options.time, self.dump.time) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            // Once a timestep is written, all its iterations are written but no later timestep
+            (Some(time), Some(current)) => match self.dump.selected_time {
+                Some(selected) => current == selected,
+                None => current >= time,
+            },
+        }
+    }
+
+    /// Writes the assembled system of the Newton `iteration`, if it is a dump point.
+    pub(super) fn dump_system(&mut self, iteration: usize) -> Result<(), SimulatorError> {
+        if !self.is_dump_point(iteration) {
+            return Ok(());
+        }
+        let (a_mat, b_vec) = self.solver.system();
+        let description = format!("{}, iteration {iteration}", self.dump.describe());
+        self.write_dump(&a_mat, &b_vec, &description)
+    }
+
+    /// Writes the assembled complex system at the frequency `freq` in Hz, if it is a dump point.
+    pub(super) fn dump_cplx_system(&mut self, freq: Numeric) -> Result<(), SimulatorError> {
+        if !self.is_dump_point(1) {
+            return Ok(());
+        }
+        let (a_mat, b_vec) = self.solver.cplx_system();
+        let description = format!("{} at f = {freq:e} Hz", self.dump.describe());
+        self.write_dump(&a_mat, &b_vec, &description)
+    }
+
+    /// Writes `A` and `b` to the files `<prefix>-<count>.a.mtx` and `<prefix>-<count>.b.mtx`
+    /// with the variables of their indices in `<prefix>-<count>.vars`.
+    fn write_dump<T: MarketValue>(
+        &mut self,
+        a_mat: &[(usize, usize, T)],
+        b_vec: &[T],
+        description: &str,
+    ) -> Result<(), SimulatorError> {
+        if self.settings.dump.time.is_some() {
+            self.dump.selected_time = self.dump.time;
+        }
+        self.dump.count += 1;
+        let path = format!("{}-{}", self.settings.dump.prefix, self.dump.count);
+
+        let vars = self.vars.len();
+        write_file(&format!("{path}.a.mtx"), |writer| {
+            write_matrix(writer, vars, a_mat, description)
+        })?;
+        write_file(&format!("{path}.b.mtx"), |writer| {
+            write_vector(writer, b_vec, description)
+        })?;
+        write_file(&format!("{path}.vars"), |writer| {
+            write_variables(writer, &self.vars)
+        })?;
+        info!("Wrote the system of the {description} to {path}.a.mtx");
+        Ok(())
+    }
+}
+
+/// Creates the file at `path` and writes it with `write`.
+fn write_file(
+    path: &str,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> Result<(), SimulatorError> {
+    File::create(path)
+        .map(BufWriter::new)
+        .and_then(|mut writer| {
+            write(&mut writer)?;
+            writer.flush()
+        })
+        .map_err(|cause| SimulatorError::DumpFailed {
+            path: path.to_string(),
+            cause,
+        })
+}
+
+/// Writes the index, name and unit of every variable. The indices start at 1 like the ones of
+/// the Matrix Market files.
+fn write_variables(writer: &mut impl Write, vars: &[Variable]) -> io::Result<()> {
+    writeln!(writer, "% index name unit")?;
+    for var in vars {
+        let unit = var.unit().to_string();
+        writeln!(writer, "{}\t{}\t{}", var.idx() + 1, var.name(), unit.trim())?;
+    }
+    Ok(())
+}
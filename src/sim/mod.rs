@@ -4,8 +4,15 @@ pub(crate) mod simulation_result;
 
 mod ac;
 pub mod autotune;
+mod continuation;
 mod dc;
+mod dump;
+mod newton;
 mod op;
+mod report;
+mod stamps;
+mod states;
+pub(crate) mod topology;
 mod tran;
 
 use std::collections::HashMap;
@@ -13,16 +20,24 @@ use std::fmt::{self, Debug};
 use std::sync::Arc;
 
 use itertools::{izip, Itertools};
+use log::info;
 use miette::Diagnostic;
-use num::{Complex, One, Zero};
+use num::{Complex, One};
 use options::SimulationOption;
 use thiserror::Error;
 
-use crate::models::{Element, Variable};
+use crate::models::charge::voltage_between;
+use crate::models::integration;
+use crate::models::{Element, StateHistory, Variable};
 use crate::sim::ac::AcSimulation;
 use crate::sim::dc::DcSimulation;
+use crate::sim::dump::DumpPoint;
 use crate::sim::op::OpSimulation;
-use crate::sim::options::IntegrationMethod;
+use crate::sim::options::{DumpAnalysis, SimulatorOptions};
+use crate::sim::report::ConvergenceReport;
+use crate::sim::stamps::{StampSlots, StepStamps};
+use crate::sim::states::{JunctionStore, StateStore};
+use crate::sim::topology::TopologyError;
 use crate::sim::tran::TranSimulation;
 use crate::solver::{Solver, SolverError};
 use crate::spot::*;
@@ -36,9 +51,19 @@ pub(crate) enum SimulatorError {
     #[error("{0}")]
     BackendError(SolverError),
 
-    #[error("The Simulation did not converge after {max_iter} steps (VECTOL={tol})")]
-    #[diagnostic(help("Try increasing VECTOL (current: {tol}) or check for unstable elements"))]
-    NonConvergentMaxIter { max_iter: usize, tol: Numeric },
+    #[error("The Simulation did not converge after {max_iter} steps (RELTOL={tol})")]
+    #[diagnostic(help("Try increasing RELTOL (current: {tol}) or check for unstable elements"))]
+    NonConvergentMaxIter {
+        max_iter: usize,
+        tol: Numeric,
+        /// The variables which did not converge in the last iteration
+        #[diagnostic_source]
+        report: ConvergenceReport,
+    },
+
+    #[error("Node {0} not found")]
+    #[diagnostic(help("Check the nodes in your .ic and .nodeset commands"))]
+    NodeNotFound(String),
 
     #[error("Voltage source {0} not found")]
     #[diagnostic(help("Check the source in your .dc command"))]
@@ -47,6 +72,54 @@ pub(crate) enum SimulatorError {
     #[error("{0}")]
     #[diagnostic(help("Check your circuit for coupling errors"))]
     CircuitError(String),
+
+    #[error("Found {} problem(s) in the circuit topology", .findings.len())]
+    #[diagnostic(help("Fix the listed nodes and elements"))]
+    InvalidTopology {
+        /// The solver error, which triggered the topology check
+        #[source]
+        cause: Option<SolverError>,
+        #[related]
+        findings: Vec<TopologyError>,
+    },
+
+    #[error("The conductance matrix is singular at the variable {variable}")]
+    SingularPivot {
+        variable: Arc<str>,
+        /// The devices connected to the variable and their operating region
+        #[help]
+        devices: Option<String>,
+        /// The solver error with the index of the pivot
+        #[source]
+        cause: SolverError,
+    },
+
+    #[error("Failed to write the matrix dump {path}")]
+    #[diagnostic(help("Check that the directory of the dump prefix exists and is writable"))]
+    DumpFailed {
+        path: String,
+        #[source]
+        cause: std::io::Error,
+    },
+}
+
+impl SimulatorError {
+    /// Records the time point of the analysis in the report of a non-convergent error.
+    pub(crate) fn at_time(mut self, time: Numeric) -> Self {
+        if let SimulatorError::NonConvergentMaxIter { report, .. } = &mut self {
+            report.time = Some(time);
+        }
+        self
+    }
+
+    /// Returns true if the solver switched its backend, which needs the analysis to be repeated
+    /// instead of being handled like a failed solve.
+    pub(crate) fn is_backend_switch(&self) -> bool {
+        matches!(
+            self,
+            SimulatorError::BackendError(SolverError::BackendSwitched { .. })
+        )
+    }
 }
 
 impl From<SolverError> for SimulatorError {
@@ -62,76 +135,162 @@ pub(super) struct Simulator<SO: Solver> {
     commands: Vec<SimulationCommand>,
     /// The simulation options for the backend.
     options: Vec<SimulationOption>,
+    /// The settings resolved from the simulation options.
+    settings: SimulatorOptions,
     /// The variables used in the simulation.
     vars: Vec<Variable>,
+    /// The charge and flux histories of the reactive elements.
+    states: StateStore,
+    /// The junction voltages of the nonlinear elements in the Newton iteration.
+    junctions: JunctionStore,
+    /// The matrix slots of the time variant, nonlinear and charge stamps.
+    stamps: StampSlots,
+    /// The stamps of the current timestep of a transient analysis.
+    step: StepStamps,
+    /// The point of the simulation, which selects the systems of the matrix dump.
+    dump: DumpPoint,
     /// The backend used for solving the circuit equations.
     solver: SO,
 }
 
 impl<SO: Solver> Simulator<SO> {
-    /// Returns the integration method to use for transient simulation
-    /// Defaults to BackwardEuler for stability
-    fn get_integration_method(&self) -> IntegrationMethod {
-        self.options
-            .iter()
-            .find_map(|opt| {
-                // TODO: Consider refactoring to reduce nesting complexity
-                if let SimulationOption::IntegrationMethod(method) = opt {
-                    Some(method.clone())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(IntegrationMethod::BackwardEuler)
+    /// Tunes the settings to the circuit. The options of the circuit still take precedence.
+    pub fn with_autotune(mut self) -> Self {
+        let mut settings = SimulatorOptions::default();
+        autotune::analyze_circuit_and_suggest_settings(
+            &self.elements,
+            &self.commands,
+            &mut settings,
+        );
+        for option in &self.options {
+            settings.apply(option);
+        }
+        self.set_settings(settings);
+        self
+    }
+
+    /// Sets the settings of the simulator and passes them on to the elements.
+    fn set_settings(&mut self, settings: SimulatorOptions) {
+        for element in &mut self.elements {
+            element.apply_options(&settings);
+        }
+        self.solver.set_condition_limit(settings.condition_limit);
+        self.settings = settings;
     }
 
     pub fn run(&mut self) -> Result<SimulationResults, SimulatorError> {
         //Inits matrices and sparsity patterns
-        self.init_solver();
+        self.init_solver()?;
 
         let commands = self.commands.clone();
         let mut results = SimulationResults {
             options: self.options.clone(),
             ..Default::default()
         };
-        for com in commands {
-            let error = self.execute_command(&com);
-            match error {
-                Ok(res) => results.results.push(res),
-                Err(err) => return Err(err),
+        // The results of all analyses follow each other for every temperature of `.temp`
+        let temps = self.settings.temps.clone();
+        for &temp in &temps {
+            if temps.len() > 1 {
+                info!("Run analyses at {temp}°C");
+            }
+            self.set_temperature(temp);
+            for com in &commands {
+                // The fallback of a solver, which found the matrix singular, starts without any
+                // stamps, so the analysis is repeated from its first stamp
+                let error = loop {
+                    match self.execute_command(com) {
+                        Err(err) if err.is_backend_switch() => continue,
+                        result => break result,
+                    }
+                };
+                match error {
+                    Ok(res) => {
+                        results.results.push(res);
+                        results.temperatures.push(temp);
+                    }
+                    Err(SimulatorError::BackendError(
+                        err @ SolverError::MatrixNonInvertible { .. },
+                    )) => return Err(self.diagnose_singular_matrix(err)),
+                    Err(err) => return Err(err),
+                }
             }
         }
 
         Ok(results)
     }
 
-    fn init_solver(&mut self) {
+    /// Sets the circuit temperature in °C and scales the device parameters to it.
+    fn set_temperature(&mut self, temp: Numeric) {
+        if self.settings.temp != temp {
+            let settings = SimulatorOptions {
+                temp,
+                ..self.settings.clone()
+            };
+            self.set_settings(settings);
+        }
+    }
+
+    fn init_solver(&mut self) -> Result<(), SimulatorError> {
         let a_mat: Vec<(usize, usize)> = self
             .elements
             .iter()
             .filter_map(|ele| ele.get_triple_indices())
             .flat_map(|ele| ele.data())
+            .chain(
+                self.elements
+                    .iter()
+                    .filter_map(|ele| ele.get_nonlinear_triple_indices())
+                    .flat_map(|ele| ele.data()),
+            )
             .collect();
 
+        // Only an AC analysis solves complex systems
+        let has_ac = self
+            .commands
+            .iter()
+            .any(|com| matches!(com, SimulationCommand::Ac(..)));
+        // The small-signal models of the nonlinear elements stamp into their nonlinear pattern
         let cplx_a_mat: Vec<(usize, usize)> = self
             .elements
             .iter()
+            .filter(|_| has_ac)
             .filter_map(|ele| ele.get_cplx_triple_indices())
             .flat_map(|ele| ele.data())
+            .chain(
+                self.elements
+                    .iter()
+                    .filter(|_| has_ac)
+                    .filter_map(|ele| ele.get_nonlinear_triple_indices())
+                    .flat_map(|ele| ele.data()),
+            )
             .collect();
 
         self.solver.init(a_mat, cplx_a_mat);
+        // The analysis of the pattern is reused by the factorisations of all analyses
+        self.solver.analyse()?;
+        Ok(())
     }
 
     fn execute_command(&mut self, comm: &SimulationCommand) -> Result<Sim, SimulatorError> {
-        self.solver.reset();
+        // The constant triples depend on the temperature of the analysis
+        self.build_base_a_mat();
+        // Every analysis starts with the switches in their initial state
+        self.elements.iter_mut().for_each(Element::reset_state);
         let res = match comm {
-            SimulationCommand::Op => self.run_op()?,
-            SimulationCommand::Tran(tstep, tstop) => self.run_tran(tstep, tstop)?,
+            SimulationCommand::Op => {
+                self.dump.start(DumpAnalysis::Op);
+                self.run_op()?
+            }
+            SimulationCommand::Tran(tstep, tstop, tstart, tmax, uic) => {
+                self.dump.start(DumpAnalysis::Tran);
+                self.run_tran(tstep, tstop, tstart, tmax, *uic)?
+            }
             SimulationCommand::Ac(fstart, fend, steps, options) => {
+                self.dump.start(DumpAnalysis::Ac);
                 self.run_ac(fstart, fend, steps, options)?
             }
             SimulationCommand::Dc(vs, vstart, vstop, vstep, optional) => {
+                self.dump.start(DumpAnalysis::Dc);
                 self.run_dc(vs, vstart, vstop, vstep, optional)?
             }
         };
@@ -160,39 +319,101 @@ impl<SO: Solver> Simulator<SO> {
     }
 
     fn find_op(&mut self) -> Result<Vec<(Variable, Numeric)>, SimulatorError> {
-        self.solver.reset();
-        self.build_constant_a_mat();
+        self.solve_op(&[])
+    }
+
+    /// Solves the operating point while holding the given nodes at their initial condition.
+    fn solve_op(
+        &mut self,
+        initial_conditions: &[(usize, Numeric)],
+    ) -> Result<Vec<(Variable, Numeric)>, SimulatorError> {
+        self.build_base_a_mat();
         self.build_constant_b_vec();
+        self.build_initial_conditions(initial_conditions);
 
         if !self.has_nonlinear_elements() {
+            self.dump_system(1)?;
             let x_vec = self.solver.solve()?.clone();
             let res = self.add_var_name(x_vec);
             return Ok(res);
         }
 
-        let mut x = self.generate_initial_guess();
+        let x = self.solve_op_continuation(initial_conditions)?;
+        // The switches keep their state for the following sweep points and timesteps
+        self.accept_solution(&x);
+        Ok(self.add_var_name(x))
+    }
 
-        for _ in 0..MAXITER {
-            self.solver.reset();
-            self.build_constant_a_mat();
-            self.build_constant_b_vec();
-            self.build_nonlinear_a_mat(&x);
-            self.build_nonlinear_b_vec(&x);
+    /// Records the accepted solution `x_vec` in the elements with a memory.
+    /// Returns whether any switch changed its state.
+    fn accept_solution(&mut self, x_vec: &[Numeric]) -> bool {
+        // Every element records the solution, so this must not short-circuit
+        let mut switched = false;
+        for element in &mut self.elements {
+            switched |= element.accept_solution(x_vec);
+        }
+        switched
+    }
 
-            let x_new = self.solver.solve()?.clone();
+    /// Returns the fraction of the step from `x_prev` to `x_new` at the first switching event.
+    fn switch_crossing(&self, x_prev: &[Numeric], x_new: &[Numeric]) -> Option<Numeric> {
+        self.elements
+            .iter()
+            .filter_map(|element| element.switch_crossing(x_prev, x_new))
+            .reduce(Numeric::min)
+    }
 
-            if self.has_converged(&x, &x_new, VECTOL) {
-                let res = self.add_var_name(x_new.clone());
-                return Ok(res);
-            }
+    fn has_switches(&self) -> bool {
+        self.elements
+            .iter()
+            .any(|element| matches!(element, Element::Switch(_)))
+    }
 
-            x = x_new;
+    /// Holds nodes at their initial condition with a large Norton conductance.
+    fn build_initial_conditions(&mut self, initial_conditions: &[(usize, Numeric)]) {
+        for &(idx, value) in initial_conditions {
+            self.solver.insert_a(&(idx, idx, IC_CONDUCTANCE));
+            self.solver.insert_b(&(idx, IC_CONDUCTANCE * value));
         }
+    }
+
+    /// Returns the variable indices and values of all `.ic` node voltages.
+    fn initial_conditions(&self) -> Result<Vec<(usize, Numeric)>, SimulatorError> {
+        self.resolve_node_voltages(self.options.iter().filter_map(|opt| match opt {
+            SimulationOption::InitialConditions(values) => Some(values),
+            _ => None,
+        }))
+    }
 
-        Err(SimulatorError::NonConvergentMaxIter {
-            max_iter: MAXITER,
-            tol: VECTOL,
-        })
+    /// Returns the variable indices and values of all `.nodeset` node voltages.
+    fn nodesets(&self) -> Result<Vec<(usize, Numeric)>, SimulatorError> {
+        self.resolve_node_voltages(self.options.iter().filter_map(|opt| match opt {
+            SimulationOption::NodeSet(values) => Some(values),
+            _ => None,
+        }))
+    }
+
+    fn resolve_node_voltages<'a>(
+        &self,
+        values: impl Iterator<Item = &'a Vec<(Arc<str>, Numeric)>>,
+    ) -> Result<Vec<(usize, Numeric)>, SimulatorError> {
+        values
+            .flatten()
+            .map(|(node, value)| {
+                self.vars
+                    .iter()
+                    .find(|var| var.name() == *node)
+                    .map(|var| (var.idx(), *value))
+                    .ok_or_else(|| SimulatorError::NodeNotFound(node.to_string()))
+            })
+            .collect()
+    }
+
+    /// Stamps the constant triples into the base matrix, which every assembly starts from.
+    fn build_base_a_mat(&mut self) {
+        self.solver.reset();
+        self.build_constant_a_mat();
+        self.solver.store_base();
     }
 
     fn build_constant_a_mat(&mut self) {
@@ -204,59 +425,138 @@ impl<SO: Solver> Simulator<SO> {
     }
 
     fn build_constant_b_vec(&mut self) {
+        self.build_scaled_constant_b_vec(Numeric::one());
+    }
+
+    /// Builds the constant RHS with all independent sources scaled by `factor`.
+    fn build_scaled_constant_b_vec(&mut self, factor: Numeric) {
         self.elements
             .iter()
             .filter_map(|ele| ele.get_constant_pairs())
             .flat_map(|pairs| pairs.data())
-            .for_each(|pair| self.solver.insert_b(&pair));
+            .for_each(|(idx, value)| self.solver.insert_b(&(idx, factor * value)));
     }
 
-    fn build_time_variant_a_mat(&mut self, delta_t: &Numeric) {
-        self.elements
-            .iter()
-            .filter_map(|ele| ele.get_time_variant_triples(delta_t))
-            .flat_map(|triples| triples.data())
-            .for_each(|triplet| self.solver.insert_a(&triplet));
-    }
+    /// Evaluates the stamps of a timestep ending at `time`, which stay the same in all Newton
+    /// iterations: the constant RHS, the time variant triples and pairs and the charges of the
+    /// linear elements.
+    fn build_step_stamps(
+        &mut self,
+        x_vec: &[Numeric],
+        time: &Numeric,
+        integrator: &dyn integration::IntegrationMethod,
+    ) {
+        let step = &mut self.step;
+        step.clear();
 
-    fn build_time_variant_b_vec(&mut self, time: &Numeric, delta_t: &Numeric) {
         self.elements
             .iter()
-            .filter_map(|ele| ele.get_time_variant_pairs(Some(time), delta_t))
+            .filter_map(|ele| ele.get_constant_pairs())
             .flat_map(|pairs| pairs.data())
-            .for_each(|pair| self.solver.insert_b(&pair));
-    }
+            .for_each(|pair| step.insert_b(&pair));
+
+        for (ele, slots) in self.elements.iter().zip(&mut self.stamps.time_variant) {
+            if let Some(triples) = ele.get_time_variant_triples(integrator) {
+                for (idx, triplet) in triples.into_iter().enumerate() {
+                    step.insert_a(&mut self.solver, slots, idx, triplet);
+                }
+            }
+        }
 
-    fn build_time_variant_b_vec_trapezoidal(&mut self, time: &Numeric, delta_t: &Numeric) {
         self.elements
             .iter()
-            .filter_map(|ele| ele.get_time_variant_pairs_trapezoidal(Some(time), delta_t))
+            .enumerate()
+            .filter_map(|(idx, ele)| {
+                ele.get_time_variant_pairs(Some(time), integrator, self.states.element(idx))
+            })
             .flat_map(|pairs| pairs.data())
-            .for_each(|pair| self.solver.insert_b(&pair));
+            .for_each(|pair| step.insert_b(&pair));
+
+        // The capacitance of linear charges does not depend on the voltage
+        for (idx, element) in self.elements.iter().enumerate() {
+            if element.is_nonlinear() {
+                continue;
+            }
+            let slots = &mut self.stamps.charges[idx];
+            let mut stamp = 0;
+            let states = self.states.element(idx);
+            for (charge, history) in element.get_charges(x_vec, states).iter().zip(states) {
+                for triplet in &charge.triples(integrator) {
+                    step.insert_a(&mut self.solver, slots, stamp, triplet);
+                    stamp += 1;
+                }
+                charge
+                    .pairs(history, integrator)
+                    .data()
+                    .iter()
+                    .for_each(|pair| step.insert_b(pair));
+            }
+        }
     }
 
-    fn build_nonlinear_a_mat(&mut self, x_vec: &[Numeric]) {
-        self.elements
-            .iter()
-            .filter_map(|ele| ele.get_nonlinear_triples(x_vec))
-            .flat_map(|triples| triples.data())
-            .for_each(|triplet| self.solver.insert_a(&triplet));
+    /// Stamps the companion models of the charges of the nonlinear elements, linearised around
+    /// `x_vec`.
+    fn build_charge_mats(
+        &mut self,
+        x_vec: &[Numeric],
+        integrator: &dyn integration::IntegrationMethod,
+    ) {
+        for (idx, element) in self.elements.iter().enumerate() {
+            if !element.is_nonlinear() {
+                continue;
+            }
+            let slots = &mut self.stamps.charges[idx];
+            let mut stamp = 0;
+            let states = self.states.element(idx);
+            for (charge, history) in element.get_charges(x_vec, states).iter().zip(states) {
+                for triplet in &charge.triples(integrator) {
+                    slots.stamp(&mut self.solver, stamp, triplet);
+                    stamp += 1;
+                }
+                charge
+                    .pairs(history, integrator)
+                    .data()
+                    .iter()
+                    .for_each(|pair| self.solver.insert_b(pair));
+            }
+        }
     }
 
-    fn build_nonlinear_b_vec(&mut self, x_vec: &[Numeric]) {
+    /// Builds the nonlinear triples, linearised at the current junction voltages.
+    fn build_nonlinear_a_mat(&mut self) {
+        for (idx, ele) in self.elements.iter().enumerate() {
+            if let Some(triples) = ele.get_nonlinear_triples(self.junctions.element(idx)) {
+                let slots = &mut self.stamps.nonlinear[idx];
+                for (stamp, triplet) in triples.into_iter().enumerate() {
+                    slots.stamp(&mut self.solver, stamp, triplet);
+                }
+            }
+        }
+    }
+
+    /// Builds the nonlinear pairs, linearised at the current junction voltages.
+    fn build_nonlinear_b_vec(&mut self) {
         self.elements
             .iter()
-            .filter_map(|ele| ele.get_nonlinear_pairs(x_vec))
+            .enumerate()
+            .filter_map(|(idx, ele)| ele.get_nonlinear_pairs(self.junctions.element(idx)))
             .flat_map(|pairs| pairs.data())
             .for_each(|pair| self.solver.insert_b(&pair));
     }
 
-    fn build_ac_a_mat(&mut self, freq: Numeric) {
+    /// Builds the complex matrix at the frequency `freq`. The nonlinear elements are linearised
+    /// at the operating point `x_op`.
+    fn build_ac_a_mat(&mut self, freq: Numeric, x_op: &[Numeric]) {
         self.elements
             .iter()
             .filter_map(|ele| ele.get_ac_triples(freq))
             .flat_map(|triples| triples.data())
             .for_each(|triplet| self.solver.insert_cplx_a(&triplet));
+        self.elements
+            .iter()
+            .filter_map(|ele| ele.get_small_signal_triples(freq, x_op))
+            .flat_map(|triples| triples.data())
+            .for_each(|triplet| self.solver.insert_cplx_a(&triplet));
     }
 
     fn build_ac_b_vec(&mut self, freq: Numeric) {
@@ -267,7 +567,7 @@ impl<SO: Solver> Simulator<SO> {
             .for_each(|pair| self.solver.insert_cplx_b(&pair));
     }
 
-    fn generate_initial_guess(&self) -> Vec<Numeric> {
+    fn generate_initial_guess(&self) -> Result<Vec<Numeric>, SimulatorError> {
         let len = self.vars.len();
         let mut acc = vec![0.0; len];
 
@@ -308,14 +608,16 @@ impl<SO: Solver> Simulator<SO> {
             }
         }
 
-        acc
-    }
+        // Nodesets and initial conditions replace the heuristic guess
+        for (idx, value) in self
+            .nodesets()?
+            .into_iter()
+            .chain(self.initial_conditions()?)
+        {
+            acc[idx] = value;
+        }
 
-    fn has_converged(&self, x_old: &[Numeric], x_new: &[Numeric], tolerance: Numeric) -> bool {
-        x_old
-            .iter()
-            .zip(x_new.iter())
-            .all(|(&old, &new)| (old - new).abs() < tolerance)
+        Ok(acc)
     }
 
     /// Adjusts the timestep based on error estimation for adaptive timestep control
@@ -341,103 +643,92 @@ impl<SO: Solver> Simulator<SO> {
         let normalized_error = error_estimate / x_prev.len().max(1) as Numeric;
 
         // Calculate the optimal timestep based on the error
-        let error_ratio = (ADAPTIVE_TOLERANCE / normalized_error).sqrt();
-        let mut new_timestep = current_timestep * error_ratio * ADAPTIVE_SAFETY_FACTOR;
+        let options = &self.settings.timestep;
+        let error_ratio = (options.tolerance / normalized_error).sqrt();
+        let mut new_timestep = current_timestep * error_ratio * options.safety_factor;
 
         // Apply growth factor limits to prevent too rapid changes
         let growth_factor = new_timestep / current_timestep;
-        if growth_factor > ADAPTIVE_MAX_GROWTH_FACTOR {
-            new_timestep = current_timestep * ADAPTIVE_MAX_GROWTH_FACTOR;
-        } else if growth_factor < ADAPTIVE_MIN_GROWTH_FACTOR {
-            new_timestep = current_timestep * ADAPTIVE_MIN_GROWTH_FACTOR;
+        if growth_factor > options.max_growth {
+            new_timestep = current_timestep * options.max_growth;
+        } else if growth_factor < options.min_growth {
+            new_timestep = current_timestep * options.min_growth;
         }
 
         // Clamp the timestep to the allowed range
-        new_timestep = new_timestep.clamp(ADAPTIVE_MIN_TIMESTEP, ADAPTIVE_MAX_TIMESTEP);
+        new_timestep = new_timestep.clamp(options.min, options.max);
 
         new_timestep
     }
 
-    /// Updates capacitor voltages after each time step for proper transient simulation
-    /// This is crucial for correct integration of capacitor behavior
-    // CHECK: Check if this can be part of the inductor and capacitor elements
-    fn update_capacitor_voltages(&mut self, x_vec: &[Numeric]) {
-        // TODO: Consider refactoring to reduce nesting complexity
-        for element in &mut self.elements {
-            if let Element::Capacitor(cap) = element {
-                // Calculate voltage across capacitor: V = V(node0) - V(node1)
-                let v_node0 = cap
-                    .node0
-                    .as_ref()
-                    .map(|n| x_vec[n.idx()])
-                    .unwrap_or(Numeric::zero());
-                let v_node1 = cap
-                    .node1
-                    .as_ref()
-                    .map(|n| x_vec[n.idx()])
-                    .unwrap_or(Numeric::zero());
-                let voltage_across = v_node0 - v_node1;
-
-                // Always update with the actual voltage across the capacitor
-                // This works for both OP initialization and transient steps
-                cap.update_previous_voltage(voltage_across);
+    /// Resets the charge and flux histories to the solution `x_vec`, as if the circuit had been
+    /// at rest so far. With UIC the `IC=` values of the elements take precedence.
+    fn init_reactive_states(&mut self, x_vec: &[Numeric], uic: bool) {
+        for (idx, element) in self.elements.iter().enumerate() {
+            let states = self.states.element_mut(idx);
+            for (state, charge) in states.iter_mut().zip(element.get_charges(x_vec, &[])) {
+                *state = StateHistory::new(charge.charge);
+                state.v1 = charge.voltage;
+            }
+
+            match element {
+                Element::Capacitor(cap) if uic => {
+                    if let Some(voltage) = cap.initial_condition() {
+                        states[0] = StateHistory::new(cap.value * voltage);
+                    }
+                }
+                // The operating point carries the DC current in the branch of the inductor
+                Element::Inductor(ind) => {
+                    let current = match ind.initial_condition() {
+                        Some(current) if uic => current,
+                        _ => x_vec[ind.branch_idx()],
+                    };
+                    states[0] = StateHistory::new(ind.flux(current));
+                }
+                _ => {}
             }
         }
+        self.update_coupled_inductor_currents();
     }
 
-    /// This is crucial for correct integration of inductor behavior
-    // CHECK: Check if this can be part of the inductor and capacitor elements themselves
-    fn update_inductor_currents(&mut self, x_vec: &[Numeric], delta_t: &Numeric) {
-        // First, collect all inductor currents
-        let mut inductor_currents: HashMap<Arc<str>, Numeric> = HashMap::new();
+    /// Updates the charge and flux histories of the reactive elements after a time step.
+    /// This is crucial for correct integration of the reactive elements
+    fn update_reactive_states(
+        &mut self,
+        x_vec: &[Numeric],
+        integrator: &dyn integration::IntegrationMethod,
+    ) {
+        for (idx, element) in self.elements.iter().enumerate() {
+            let charges = element.get_charges(x_vec, self.states.element(idx));
+            let states = self.states.element_mut(idx);
+            for (state, charge) in states.iter_mut().zip(charges) {
+                let current = integrator.derivative(charge.charge, state);
+                state.push(charge.charge, current);
+                state.v1 = charge.voltage;
+            }
 
-        // TODO: Consider refactoring to reduce nesting complexity
-        for element in &self.elements {
             if let Element::Inductor(ind) = element {
-                // Calculate current through inductor using the equivalent conductance
-                // I = G * (V(node0) - V(node1)), where G = Δt/L
-                let v_node0 = ind
-                    .node0
-                    .as_ref()
-                    .map(|n| x_vec[n.idx()])
-                    .unwrap_or(Numeric::zero());
-                let v_node1 = ind
-                    .node1
-                    .as_ref()
-                    .map(|n| x_vec[n.idx()])
-                    .unwrap_or(Numeric::zero());
-                let voltage_across = v_node0 - v_node1;
-
-                // The equivalent conductance is Δt/L, so current = (Δt/L) * voltage
-                // But we need to extract the actual current from the solution
-                // In MNA, the current through the inductor is related to the voltage difference
-                // For a transient simulation, we can calculate the current as:
-                // I = (V(node0) - V(node1)) * (Δt / L)
-                // However, since we're using backward Euler, the current is actually:
-                // I_current = I_prev + (Δt / L) * (V_current - V_prev)
-                // But for updating, we just need the current through the inductor
-
-                // For now, we'll use a simplified approach and calculate current from voltage
-                // This is an approximation and may need refinement
-                let equivalent_conductance = delta_t / ind.value;
-                let current = voltage_across * equivalent_conductance;
-
-                inductor_currents.insert(ind.name.clone(), current);
+                let voltage = voltage_between(ind.node0_idx(), ind.node1_idx(), x_vec);
+                states[0].push(ind.flux(x_vec[ind.branch_idx()]), voltage);
             }
         }
+        self.update_coupled_inductor_currents();
+    }
 
-        // Update inductor currents
-        // TODO: Consider refactoring to reduce nesting complexity
-        for element in &mut self.elements {
-            if let Element::Inductor(ind) = element {
-                if let Some(&current) = inductor_currents.get(&ind.name) {
-                    ind.update_previous_current(current);
+    /// Passes the inductor currents of the flux histories to the coupled inductors.
+    fn update_coupled_inductor_currents(&mut self) {
+        let inductor_currents: HashMap<Arc<str>, Numeric> = self
+            .elements
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, element)| match element {
+                Element::Inductor(ind) => {
+                    Some((ind.name(), self.states.element(idx)[0].x1 / ind.value))
                 }
-            }
-        }
+                _ => None,
+            })
+            .collect();
 
-        // Update coupled inductor currents
-        // TODO: Consider refactoring to reduce nesting complexity
         for element in &mut self.elements {
             if let Element::CoupledInductors(coupled) = element {
                 let inductor1_name = coupled.inductor1();
@@ -472,13 +763,22 @@ impl<SO: Solver> From<Simulation> for Simulator<SO> {
         let backend = SO::new(variables.len())
             .expect("Failed to create solver backend. This indicates a system resource limitation or invalid configuration.");
 
-        Simulator {
+        let settings = SimulatorOptions::from(options.as_slice());
+        let mut simulator = Simulator {
+            states: StateStore::new(&elements),
+            junctions: JunctionStore::new(&elements),
+            stamps: StampSlots::new(&elements),
+            step: StepStamps::default(),
+            dump: DumpPoint::default(),
             elements,
             commands,
             options,
+            settings: SimulatorOptions::default(),
             solver: backend,
             vars: variables,
-        }
+        };
+        simulator.set_settings(settings);
+        simulator
     }
 }
 
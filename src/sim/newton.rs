@@ -0,0 +1,202 @@
+use itertools::izip;
+use log::trace;
+
+use crate::models::{Unit, Variable};
+use crate::sim::report::{ConvergenceReport, NonConvergentVariable};
+use crate::sim::SimulatorError;
+use crate::solver::Solver;
+use crate::spot::*;
+use crate::Simulator;
+
+impl<SO: Solver> Simulator<SO> {
+    /// Solves the nonlinear circuit equations with at most `max_iter` Newton-Raphson iterations,
+    /// starting at `x_guess`. Every iteration starts from the base matrix of the constant triples.
+    /// `build` stamps everything else but the nonlinear elements, which are linearised at their
+    /// limited junction voltages.
+    pub(super) fn newton_raphson(
+        &mut self,
+        x_guess: &[Numeric],
+        max_iter: usize,
+        build: impl Fn(&mut Self, &[Numeric]),
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        self.init_junctions(x_guess);
+        let mut x = x_guess.to_vec();
+        let mut x_old = x.clone();
+        let mut damping = Damping::default();
+
+        for iteration in 1..=max_iter {
+            let limited = self.limit_junctions(&x);
+
+            self.solver.load_base();
+            build(self, &x);
+            self.build_nonlinear_a_mat();
+            self.build_nonlinear_b_vec();
+
+            self.dump_system(iteration)?;
+            let x_solved = self.solver.solve()?.clone();
+
+            // An overflowing junction can not recover
+            if x_solved.iter().any(|value| !value.is_finite()) {
+                x_old = std::mem::replace(&mut x, x_solved);
+                break;
+            }
+
+            let (x_new, damped) = damping.apply(&x, &x_solved);
+
+            if !limited && !damped && self.has_converged(&x, &x_new) && !self.switches_at(&x_new) {
+                return Ok(x_new);
+            }
+
+            x_old = std::mem::replace(&mut x, x_new);
+        }
+
+        Err(SimulatorError::NonConvergentMaxIter {
+            max_iter,
+            tol: self.settings.reltol,
+            report: self.convergence_report(&x_old, &x),
+        })
+    }
+
+    /// Returns whether every variable changed by less than `reltol` of its value plus `vntol`
+    /// for voltages or `abstol` for currents.
+    pub(super) fn has_converged(&self, x_old: &[Numeric], x_new: &[Numeric]) -> bool {
+        izip!(&self.vars, x_old, x_new)
+            .all(|(var, &old, &new)| (new - old).abs() <= self.tolerance(var, old, new))
+    }
+
+    /// Returns the report of the worst variables, which did not converge in the step from
+    /// `x_old` to `x_new`, together with their connected devices.
+    pub(super) fn convergence_report(
+        &self,
+        x_old: &[Numeric],
+        x_new: &[Numeric],
+    ) -> ConvergenceReport {
+        let mut variables: Vec<_> = izip!(&self.vars, x_old, x_new)
+            .filter_map(|(var, &old, &new)| {
+                let ratio = (new - old).abs() / self.tolerance(var, old, new);
+                // Overflowed values are the worst of all
+                let ratio = if ratio.is_nan() {
+                    Numeric::INFINITY
+                } else {
+                    ratio
+                };
+                (ratio > 1.0).then(|| {
+                    let variable = NonConvergentVariable {
+                        name: var.name(),
+                        unit: var.unit(),
+                        value: new,
+                        delta: new - old,
+                        devices: self.connected_devices(var.idx()),
+                    };
+                    (ratio, variable)
+                })
+            })
+            .collect();
+
+        variables.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        variables.truncate(REPORT_MAX_VARIABLES);
+
+        ConvergenceReport {
+            time: None,
+            variables: variables
+                .into_iter()
+                .map(|(_, variable)| variable)
+                .collect(),
+        }
+    }
+
+    /// Lists the elements connected to the variable at `idx` with their operating region.
+    pub(super) fn connected_devices(&self, idx: usize) -> Option<String> {
+        let devices: Vec<_> = self
+            .elements
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| element.is_connected_to(idx))
+            .map(|(element_idx, element)| {
+                match element.operating_region(self.junctions.element(element_idx)) {
+                    Some(region) => format!("{} ({region})", element.name()),
+                    None => element.name().to_string(),
+                }
+            })
+            .collect();
+
+        (!devices.is_empty()).then(|| format!("Connected devices: {}", devices.join(", ")))
+    }
+
+    /// Sets the junction voltages of all nonlinear elements to their values at `x_vec`.
+    fn init_junctions(&mut self, x_vec: &[Numeric]) {
+        for (idx, element) in self.elements.iter().enumerate() {
+            self.junctions
+                .element_mut(idx)
+                .copy_from_slice(&element.get_junction_voltages(x_vec));
+        }
+    }
+
+    /// Moves the junction voltages towards `x_vec` with limited steps.
+    /// Returns whether any junction voltage was limited.
+    fn limit_junctions(&mut self, x_vec: &[Numeric]) -> bool {
+        let mut limited = false;
+        for (idx, element) in self.elements.iter().enumerate() {
+            limited |= element.limit_junction_voltages(x_vec, self.junctions.element_mut(idx));
+        }
+        limited
+    }
+
+    /// Returns whether a switch changes its state at `x_vec` from the state at its junction
+    /// voltages, in which it was stamped.
+    fn switches_at(&self, x_vec: &[Numeric]) -> bool {
+        self.elements
+            .iter()
+            .enumerate()
+            .any(|(idx, element)| element.switches_at(x_vec, self.junctions.element(idx)))
+    }
+
+    /// Returns the tolerance of the step of `var` from `old` to `new`: `reltol` of its value
+    /// plus `vntol` for voltages or `abstol` for currents.
+    fn tolerance(&self, var: &Variable, old: Numeric, new: Numeric) -> Numeric {
+        let abs_tol = match var.unit() {
+            Unit::Ampere => self.settings.abstol,
+            Unit::Volt | Unit::None => self.settings.vntol,
+        };
+        self.settings.reltol * old.abs().max(new.abs()) + abs_tol
+    }
+}
+
+/// Damps Newton steps, which grow by more than `NEWTON_MAX_STEP_GROWTH` over the previous
+/// step. Such steps are a sign of an oscillating iteration.
+#[derive(Debug, Clone, Copy, Default)]
+struct Damping {
+    /// The largest change of a variable in the previous step
+    previous_step: Option<Numeric>,
+}
+
+impl Damping {
+    /// Returns the damped step from `x` towards `x_solved` and whether it was damped.
+    fn apply(&mut self, x: &[Numeric], x_solved: &[Numeric]) -> (Vec<Numeric>, bool) {
+        let step = x
+            .iter()
+            .zip(x_solved)
+            .map(|(old, new)| (new - old).abs())
+            .fold(0.0, Numeric::max);
+
+        let factor = match self.previous_step {
+            Some(previous) if step > NEWTON_MAX_STEP_GROWTH * previous && previous > 0.0 => {
+                NEWTON_MAX_STEP_GROWTH * previous / step
+            }
+            _ => 1.0,
+        };
+        self.previous_step = Some(step * factor);
+
+        if factor < 1.0 {
+            trace!("Damping Newton step by {factor}");
+            let x_damped = x
+                .iter()
+                .zip(x_solved)
+                .map(|(old, new)| old + factor * (new - old))
+                .collect();
+            (x_damped, true)
+        } else {
+            (x_solved.to_vec(), false)
+        }
+    }
+}
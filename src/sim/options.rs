@@ -1,18 +1,366 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
 
+use crate::spot::*;
+
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum SimulationOption {
     Out(Vec<Arc<str>>),
     IntegrationMethod(IntegrationMethod),
+    /// Node voltages for the start of a transient analysis (`.ic`).
+    InitialConditions(Vec<(Arc<str>, Numeric)>),
+    /// Node voltages used as initial guess for the operating point (`.nodeset`).
+    NodeSet(Vec<(Arc<str>, Numeric)>),
+    /// The strategies tried in order to find the operating point (`.options opstrategy=`).
+    OpStrategies(Vec<OpStrategy>),
+    /// The number of decades the gmin shunt is reduced in (`.options gminsteps=`).
+    GminSteps(usize),
+    /// The number of steps the sources are ramped up in (`.options srcsteps=`).
+    SourceSteps(usize),
+    /// The maximum number of pseudo transient steps (`.options ptransteps=`).
+    PseudoTransientSteps(usize),
+    /// The relative tolerance of the Newton-Raphson convergence (`.options reltol=`).
+    RelTol(Numeric),
+    /// The absolute tolerance of currents in A (`.options abstol=`).
+    AbsTol(Numeric),
+    /// The absolute tolerance of voltages in V (`.options vntol=`).
+    VnTol(Numeric),
+    /// The minimum conductance of junctions in S (`.options gmin=`).
+    Gmin(Numeric),
+    /// The maximum Newton iterations of operating points and DC sweeps (`.options itl1=`).
+    OpMaxIter(usize),
+    /// The maximum Newton iterations of a transient time step (`.options itl4=`).
+    TranMaxIter(usize),
+    /// The circuit temperature in °C (`.options temp=`).
+    Temperature(Numeric),
+    /// The circuit temperatures in °C, all analyses run once per temperature (`.temp`).
+    Temperatures(Vec<Numeric>),
+    /// The nominal temperature of the device parameters in °C (`.options tnom=`).
+    NominalTemperature(Numeric),
+    /// The condition number above which the solver warns about an ill-conditioned matrix,
+    /// which enables the condition estimate (`.options condlimit=`).
+    ConditionLimit(Numeric),
+    /// The analysis whose assembled systems are written to Matrix Market files (`.options dump=`).
+    DumpAnalysis(DumpAnalysis),
+    /// Only the systems of this Newton iteration are written (`.options dumpiter=`).
+    DumpIteration(usize),
+    /// Only the systems of the first timestep ending at or after this time in s are written
+    /// (`.options dumptime=`).
+    DumpTime(Numeric),
+    /// The path prefix of the written files (`.options dumpprefix=`).
+    DumpPrefix(Arc<str>),
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum IntegrationMethod {
+    #[default]
     #[serde(rename = "be")]
     BackwardEuler,
     #[serde(rename = "trapezoidal")]
     Trapezoidal,
+    /// Second order backward differentiation formula (BDF2)
+    #[serde(rename = "gear")]
+    Gear,
+    /// Trapezoidal stage followed by a BDF2 stage within each time step
+    #[serde(rename = "trbdf2")]
+    TrBdf2,
+}
+
+impl FromStr for IntegrationMethod {
+    type Err = String;
+
+    /// Parses the integration method as given in `.options method=<Method>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "be" | "euler" => Ok(IntegrationMethod::BackwardEuler),
+            "trap" | "trapezoidal" => Ok(IntegrationMethod::Trapezoidal),
+            "gear" | "bdf2" => Ok(IntegrationMethod::Gear),
+            "trbdf2" => Ok(IntegrationMethod::TrBdf2),
+            _ => Err(format!(
+                "Unknown integration method '{s}'. Use be, trap, gear or trbdf2"
+            )),
+        }
+    }
+}
+
+/// A strategy to find the operating point of a nonlinear circuit
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum OpStrategy {
+    /// Plain Newton-Raphson iteration from the initial guess
+    #[serde(rename = "newton")]
+    Newton,
+    /// Newton iterations with a conductance to ground at every node, which is reduced stepwise
+    #[serde(rename = "gmin")]
+    GminStepping,
+    /// Newton iterations while all independent sources are ramped up from zero
+    #[serde(rename = "source")]
+    SourceStepping,
+    /// Pseudo transient continuation, where every node is tied to its previous value
+    #[serde(rename = "ptran")]
+    PseudoTransient,
+}
+
+/// The default order in which the operating point strategies are tried
+pub const DEFAULT_OP_STRATEGIES: [OpStrategy; 4] = [
+    OpStrategy::Newton,
+    OpStrategy::GminStepping,
+    OpStrategy::SourceStepping,
+    OpStrategy::PseudoTransient,
+];
+
+impl FromStr for OpStrategy {
+    type Err = String;
+
+    /// Parses a single strategy as given in `.options opstrategy=<Strategy>,<Strategy>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "newton" => Ok(OpStrategy::Newton),
+            "gmin" => Ok(OpStrategy::GminStepping),
+            "source" | "src" => Ok(OpStrategy::SourceStepping),
+            "ptran" | "pseudotran" => Ok(OpStrategy::PseudoTransient),
+            _ => Err(format!(
+                "Unknown operating point strategy '{s}'. Use newton, gmin, source or ptran"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OpStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpStrategy::Newton => write!(f, "Newton-Raphson"),
+            OpStrategy::GminStepping => write!(f, "gmin stepping"),
+            OpStrategy::SourceStepping => write!(f, "source stepping"),
+            OpStrategy::PseudoTransient => write!(f, "pseudo transient"),
+        }
+    }
+}
+
+/// The analysis whose assembled systems are written to Matrix Market files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+pub enum DumpAnalysis {
+    #[serde(rename = "op")]
+    Op,
+    #[serde(rename = "dc")]
+    Dc,
+    #[serde(rename = "tran")]
+    Tran,
+    #[serde(rename = "ac")]
+    Ac,
+    /// The systems of all analyses
+    #[serde(rename = "all")]
+    All,
+}
+
+impl FromStr for DumpAnalysis {
+    type Err = String;
+
+    /// Parses the analysis as given in `.options dump=<Analysis>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "op" => Ok(DumpAnalysis::Op),
+            "dc" => Ok(DumpAnalysis::Dc),
+            "tran" => Ok(DumpAnalysis::Tran),
+            "ac" => Ok(DumpAnalysis::Ac),
+            "all" => Ok(DumpAnalysis::All),
+            _ => Err(format!(
+                "Unknown analysis '{s}' to dump. Use op, dc, tran, ac or all"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DumpAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpAnalysis::Op => write!(f, "op"),
+            DumpAnalysis::Dc => write!(f, "dc"),
+            DumpAnalysis::Tran => write!(f, "tran"),
+            DumpAnalysis::Ac => write!(f, "ac"),
+            DumpAnalysis::All => write!(f, "all"),
+        }
+    }
+}
+
+/// The settings of the simulator. They start at the defaults in `spot` and are overridden by the
+/// options of the circuit in the order they are given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatorOptions {
+    /// The relative tolerance of the Newton-Raphson convergence
+    pub reltol: Numeric,
+    /// The absolute tolerance of currents in A
+    pub abstol: Numeric,
+    /// The absolute tolerance of voltages in V
+    pub vntol: Numeric,
+    /// The minimum conductance of junctions and the final gmin stepping conductance in S
+    pub gmin: Numeric,
+    /// The maximum Newton iterations of operating points and DC sweeps
+    pub itl1: usize,
+    /// The maximum Newton iterations of a transient time step
+    pub itl4: usize,
+    /// The circuit temperature in °C
+    pub temp: Numeric,
+    /// The circuit temperatures in °C, all analyses run once per temperature
+    pub temps: Vec<Numeric>,
+    /// The nominal temperature in °C, at which the device parameters are given
+    pub tnom: Numeric,
+    /// The integration method of transient analyses
+    pub method: IntegrationMethod,
+    /// The strategies tried in order to find the operating point
+    pub op_strategies: Vec<OpStrategy>,
+    /// The number of decades the gmin shunt is reduced in
+    pub gmin_steps: usize,
+    /// The number of steps the sources are ramped up in
+    pub source_steps: usize,
+    /// The maximum number of pseudo transient steps
+    pub pseudo_transient_steps: usize,
+    /// The adaptive time step control of transient analyses
+    pub timestep: TimestepOptions,
+    /// The condition number above which the solver warns, `None` skips the condition estimate
+    pub condition_limit: Option<Numeric>,
+    /// The systems written to Matrix Market files for debugging
+    pub dump: DumpOptions,
+}
+
+impl Default for SimulatorOptions {
+    fn default() -> Self {
+        Self {
+            reltol: RELTOL,
+            abstol: ABSTOL,
+            vntol: VNTOL,
+            gmin: GMIN,
+            itl1: MAXITER,
+            itl4: MAXITER,
+            temp: TEMP - CELSIUS_TO_KELVIN,
+            temps: vec![TEMP - CELSIUS_TO_KELVIN],
+            tnom: TEMP - CELSIUS_TO_KELVIN,
+            method: IntegrationMethod::default(),
+            op_strategies: DEFAULT_OP_STRATEGIES.to_vec(),
+            gmin_steps: GMIN_STEPS,
+            source_steps: SOURCE_STEPS,
+            pseudo_transient_steps: PTRAN_STEPS,
+            timestep: TimestepOptions::default(),
+            condition_limit: None,
+            dump: DumpOptions::default(),
+        }
+    }
+}
+
+impl SimulatorOptions {
+    /// Overrides the setting given by `option`. Options without a setting are ignored.
+    pub fn apply(&mut self, option: &SimulationOption) {
+        match option {
+            SimulationOption::IntegrationMethod(method) => self.method = method.clone(),
+            SimulationOption::OpStrategies(strategies) => self.op_strategies = strategies.clone(),
+            SimulationOption::GminSteps(steps) => self.gmin_steps = *steps,
+            SimulationOption::SourceSteps(steps) => self.source_steps = *steps,
+            SimulationOption::PseudoTransientSteps(steps) => self.pseudo_transient_steps = *steps,
+            SimulationOption::RelTol(value) => self.reltol = *value,
+            SimulationOption::AbsTol(value) => self.abstol = *value,
+            SimulationOption::VnTol(value) => self.vntol = *value,
+            SimulationOption::Gmin(value) => self.gmin = *value,
+            SimulationOption::OpMaxIter(iterations) => self.itl1 = *iterations,
+            SimulationOption::TranMaxIter(iterations) => self.itl4 = *iterations,
+            SimulationOption::Temperature(temp) => {
+                self.temp = *temp;
+                self.temps = vec![*temp];
+            }
+            SimulationOption::Temperatures(temps) => {
+                if let Some(&temp) = temps.first() {
+                    self.temp = temp;
+                    self.temps = temps.clone();
+                }
+            }
+            SimulationOption::NominalTemperature(tnom) => self.tnom = *tnom,
+            SimulationOption::ConditionLimit(limit) => self.condition_limit = Some(*limit),
+            SimulationOption::DumpAnalysis(analysis) => self.dump.analysis = Some(*analysis),
+            SimulationOption::DumpIteration(iteration) => self.dump.iteration = Some(*iteration),
+            SimulationOption::DumpTime(time) => self.dump.time = Some(*time),
+            SimulationOption::DumpPrefix(prefix) => self.dump.prefix = prefix.clone(),
+            SimulationOption::Out(_)
+            | SimulationOption::InitialConditions(_)
+            | SimulationOption::NodeSet(_) => {}
+        }
+    }
+
+    /// Returns the circuit temperature in K.
+    pub fn temp_kelvin(&self) -> Numeric {
+        self.temp + CELSIUS_TO_KELVIN
+    }
+
+    /// Returns the nominal temperature in K.
+    pub fn tnom_kelvin(&self) -> Numeric {
+        self.tnom + CELSIUS_TO_KELVIN
+    }
+}
+
+impl From<&[SimulationOption]> for SimulatorOptions {
+    fn from(options: &[SimulationOption]) -> Self {
+        let mut settings = Self::default();
+        options.iter().for_each(|option| settings.apply(option));
+        settings
+    }
+}
+
+/// The settings of the adaptive time step control
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestepOptions {
+    /// The smallest time step in s
+    pub min: Numeric,
+    /// The largest time step in s, unless the analysis gives its own maximum
+    pub max: Numeric,
+    /// The first time step in s. Smaller output steps switch the adaptive control on.
+    pub initial: Numeric,
+    /// The tolerated relative change of the solution per step
+    pub tolerance: Numeric,
+    /// The safety factor applied to the estimated optimal step
+    pub safety_factor: Numeric,
+    /// The largest factor the step may grow by
+    pub max_growth: Numeric,
+    /// The smallest factor the step may shrink by
+    pub min_growth: Numeric,
+}
+
+impl Default for TimestepOptions {
+    fn default() -> Self {
+        Self {
+            min: ADAPTIVE_MIN_TIMESTEP,
+            max: ADAPTIVE_MAX_TIMESTEP,
+            initial: ADAPTIVE_INITIAL_TIMESTEP,
+            tolerance: ADAPTIVE_TOLERANCE,
+            safety_factor: ADAPTIVE_SAFETY_FACTOR,
+            max_growth: ADAPTIVE_MAX_GROWTH_FACTOR,
+            min_growth: ADAPTIVE_MIN_GROWTH_FACTOR,
+        }
+    }
+}
+
+/// The points of the simulation at which the assembled system is written to Matrix Market
+/// files. A point is written if it matches all given settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DumpOptions {
+    /// The analysis whose systems are written, `None` writes no systems
+    pub analysis: Option<DumpAnalysis>,
+    /// The Newton iteration, counted from 1. Linear circuits are solved in the first iteration.
+    pub iteration: Option<usize>,
+    /// The time in s, only the systems of the first transient timestep ending at or after it
+    /// are written
+    pub time: Option<Numeric>,
+    /// The path prefix of the written files
+    pub prefix: Arc<str>,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self {
+            analysis: None,
+            iteration: None,
+            time: None,
+            prefix: Arc::from(DUMP_PREFIX),
+        }
+    }
 }
 
 impl Serialize for SimulationOption {
@@ -49,6 +397,129 @@ impl Serialize for SimulationOption {
                 }
                 .serialize(serializer)
             }
+            SimulationOption::InitialConditions(values) => {
+                NodeVoltagesWrapper::new("ic", values).serialize(serializer)
+            }
+            SimulationOption::NodeSet(values) => {
+                NodeVoltagesWrapper::new("nodeset", values).serialize(serializer)
+            }
+            SimulationOption::OpStrategies(strategies) => {
+                #[derive(Serialize)]
+                struct OpStrategiesWrapper<'a> {
+                    r#type: &'static str,
+                    strategies: &'a [OpStrategy],
+                }
+                OpStrategiesWrapper {
+                    r#type: "opstrategy",
+                    strategies,
+                }
+                .serialize(serializer)
+            }
+            SimulationOption::GminSteps(steps) => {
+                StepsWrapper::new("gminsteps", *steps).serialize(serializer)
+            }
+            SimulationOption::SourceSteps(steps) => {
+                StepsWrapper::new("srcsteps", *steps).serialize(serializer)
+            }
+            SimulationOption::PseudoTransientSteps(steps) => {
+                StepsWrapper::new("ptransteps", *steps).serialize(serializer)
+            }
+            SimulationOption::RelTol(value) => {
+                ValueWrapper::new("reltol", *value).serialize(serializer)
+            }
+            SimulationOption::AbsTol(value) => {
+                ValueWrapper::new("abstol", *value).serialize(serializer)
+            }
+            SimulationOption::VnTol(value) => {
+                ValueWrapper::new("vntol", *value).serialize(serializer)
+            }
+            SimulationOption::Gmin(value) => {
+                ValueWrapper::new("gmin", *value).serialize(serializer)
+            }
+            SimulationOption::OpMaxIter(iterations) => {
+                ValueWrapper::new("itl1", *iterations).serialize(serializer)
+            }
+            SimulationOption::TranMaxIter(iterations) => {
+                ValueWrapper::new("itl4", *iterations).serialize(serializer)
+            }
+            SimulationOption::Temperature(temp) => {
+                ValueWrapper::new("temp", *temp).serialize(serializer)
+            }
+            SimulationOption::Temperatures(temps) => {
+                ValueWrapper::new("temps", temps.clone()).serialize(serializer)
+            }
+            SimulationOption::NominalTemperature(tnom) => {
+                ValueWrapper::new("tnom", *tnom).serialize(serializer)
+            }
+            SimulationOption::ConditionLimit(limit) => {
+                ValueWrapper::new("condlimit", *limit).serialize(serializer)
+            }
+            SimulationOption::DumpAnalysis(analysis) => {
+                ValueWrapper::new("dump", *analysis).serialize(serializer)
+            }
+            SimulationOption::DumpIteration(iteration) => {
+                ValueWrapper::new("dumpiter", *iteration).serialize(serializer)
+            }
+            SimulationOption::DumpTime(time) => {
+                ValueWrapper::new("dumptime", *time).serialize(serializer)
+            }
+            SimulationOption::DumpPrefix(prefix) => {
+                ValueWrapper::new("dumpprefix", prefix.to_string()).serialize(serializer)
+            }
+        }
+    }
+}
+
+/// Serializer struct for the step counts of the operating point strategies
+#[derive(Serialize)]
+struct StepsWrapper {
+    r#type: &'static str,
+    steps: usize,
+}
+
+impl StepsWrapper {
+    fn new(r#type: &'static str, steps: usize) -> Self {
+        Self { r#type, steps }
+    }
+}
+
+/// Serializer struct for options with a single value
+#[derive(Serialize)]
+struct ValueWrapper<T> {
+    r#type: &'static str,
+    value: T,
+}
+
+impl<T> ValueWrapper<T> {
+    fn new(r#type: &'static str, value: T) -> Self {
+        Self { r#type, value }
+    }
+}
+
+/// Serializer struct for the node voltages of `.ic` and `.nodeset`
+#[derive(Serialize)]
+struct NodeVoltagesWrapper {
+    r#type: &'static str,
+    nodes: Vec<NodeVoltage>,
+}
+
+#[derive(Serialize)]
+struct NodeVoltage {
+    node: String,
+    value: Numeric,
+}
+
+impl NodeVoltagesWrapper {
+    fn new(r#type: &'static str, values: &[(Arc<str>, Numeric)]) -> Self {
+        Self {
+            r#type,
+            nodes: values
+                .iter()
+                .map(|(node, value)| NodeVoltage {
+                    node: node.to_string(),
+                    value: *value,
+                })
+                .collect(),
         }
     }
 }
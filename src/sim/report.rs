@@ -0,0 +1,49 @@
+use std::fmt;
+use std::sync::Arc;
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::models::Unit;
+use crate::spot::Numeric;
+
+/// The variables which did not converge in the last Newton-Raphson iteration of an analysis.
+#[derive(Debug, Clone, Default, Diagnostic)]
+pub(crate) struct ConvergenceReport {
+    /// The time point of the transient analysis, `None` for the operating point.
+    pub(crate) time: Option<Numeric>,
+    /// The non-converging variables, worst first.
+    #[related]
+    pub(crate) variables: Vec<NonConvergentVariable>,
+}
+
+impl fmt::Display for ConvergenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.time {
+            Some(time) => write!(f, "Last Newton iteration at t = {time}s"),
+            None => write!(f, "Last Newton iteration of the operating point"),
+        }?;
+        if self.variables.is_empty() {
+            write!(f, " did not report any variables")
+        } else {
+            write!(f, " did not converge in {} variables", self.variables.len())
+        }
+    }
+}
+
+impl std::error::Error for ConvergenceReport {}
+
+/// A variable whose last Newton step was above its tolerance.
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("{name} = {value}{unit} moved by {delta}{unit} in the last iteration")]
+pub(crate) struct NonConvergentVariable {
+    pub(crate) name: Arc<str>,
+    pub(crate) unit: Unit,
+    /// The value of the last iteration
+    pub(crate) value: Numeric,
+    /// The change of the value in the last iteration
+    pub(crate) delta: Numeric,
+    /// The devices connected to the variable and their operating region
+    #[help]
+    pub(crate) devices: Option<String>,
+}
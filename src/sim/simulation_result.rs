@@ -99,4 +99,16 @@ impl Serialize for Sim {
 pub struct SimulationResults {
     pub options: Vec<SimulationOption>,
     pub results: Vec<Sim>,
+    /// The circuit temperature in °C each of `results` was simulated at
+    pub temperatures: Vec<Numeric>,
+}
+
+impl SimulationResults {
+    /// Whether the results were simulated at more than one temperature, as by `.temp 0 50 100`,
+    /// so that each result has to be labelled with its temperature.
+    pub fn is_temperature_sweep(&self) -> bool {
+        self.temperatures
+            .first()
+            .is_some_and(|first| self.temperatures.iter().any(|temp| temp != first))
+    }
 }
@@ -0,0 +1,140 @@
+use crate::models::Element;
+use crate::solver::Solver;
+use crate::spot::*;
+
+/// The slots of the matrix entries stamped by an element, resolved by the solver on first use.
+///
+/// The `idx`-th stamp of an element usually hits the same entry in every iteration, so its slot
+/// is reused as long as the row and column match and resolved again otherwise.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SlotCache {
+    /// The row, column and slot of each stamp.
+    entries: Vec<(usize, usize, usize)>,
+}
+
+impl SlotCache {
+    /// Returns the slot of the `idx`-th stamp of the element at `(row, col)`.
+    pub(crate) fn slot<SO: Solver>(
+        &mut self,
+        solver: &mut SO,
+        idx: usize,
+        row: usize,
+        col: usize,
+    ) -> usize {
+        match self.entries.get(idx) {
+            Some(&(cached_row, cached_col, slot)) if (cached_row, cached_col) == (row, col) => slot,
+            _ => {
+                let slot = solver.a_slot(row, col);
+                if idx < self.entries.len() {
+                    self.entries[idx] = (row, col, slot);
+                } else {
+                    self.entries.push((row, col, slot));
+                }
+                slot
+            }
+        }
+    }
+
+    /// Adds the `idx`-th stamp of the element to the matrix `A` of `solver`.
+    pub(crate) fn stamp<SO: Solver>(
+        &mut self,
+        solver: &mut SO,
+        idx: usize,
+        triplet: &(usize, usize, Numeric),
+    ) {
+        let (row, col, value) = *triplet;
+        let slot = self.slot(solver, idx, row, col);
+        solver.insert_a_slot(slot, value);
+    }
+}
+
+/// The slot caches of the stamps, which change during an analysis.
+/// The constant stamps are part of the base matrix and need no slots.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StampSlots {
+    /// The slots of the time variant triples of each element.
+    pub(crate) time_variant: Vec<SlotCache>,
+    /// The slots of the nonlinear triples of each element.
+    pub(crate) nonlinear: Vec<SlotCache>,
+    /// The slots of the triples of all charges of each element.
+    pub(crate) charges: Vec<SlotCache>,
+}
+
+impl StampSlots {
+    /// Creates empty slot caches for the given elements.
+    pub(crate) fn new(elements: &[Element]) -> Self {
+        let caches = vec![SlotCache::default(); elements.len()];
+        Self {
+            time_variant: caches.clone(),
+            nonlinear: caches.clone(),
+            charges: caches,
+        }
+    }
+}
+
+/// The stamps of a timestep, which are evaluated once per timestep and added to the base matrix
+/// in every Newton iteration.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StepStamps {
+    /// The slot and value of each stamp into `A`.
+    a: Vec<(usize, Numeric)>,
+    /// The stamps into `b`.
+    b: Vec<(usize, Numeric)>,
+    /// The stamps into `A` of the last factorised timestep of a linear circuit.
+    factored: Option<Vec<(usize, Numeric)>>,
+}
+
+impl StepStamps {
+    /// Removes the stamps of the previous timestep.
+    pub(crate) fn clear(&mut self) {
+        self.a.clear();
+        self.b.clear();
+    }
+
+    /// Records the `idx`-th stamp of an element into `A`, resolving its slot with `slots`.
+    pub(crate) fn insert_a<SO: Solver>(
+        &mut self,
+        solver: &mut SO,
+        slots: &mut SlotCache,
+        idx: usize,
+        triplet: &(usize, usize, Numeric),
+    ) {
+        let (row, col, value) = *triplet;
+        self.a.push((slots.slot(solver, idx, row, col), value));
+    }
+
+    /// Records a stamp into `b`.
+    pub(crate) fn insert_b(&mut self, pair: &(usize, Numeric)) {
+        self.b.push(*pair);
+    }
+
+    /// Adds the stamps into `A` and `b` to `solver`.
+    pub(crate) fn stamp<SO: Solver>(&self, solver: &mut SO) {
+        for &(slot, value) in &self.a {
+            solver.insert_a_slot(slot, value);
+        }
+        self.stamp_b(solver);
+    }
+
+    /// Adds only the stamps into `b` to `solver`.
+    pub(crate) fn stamp_b<SO: Solver>(&self, solver: &mut SO) {
+        self.b.iter().for_each(|pair| solver.insert_b(pair));
+    }
+
+    /// Returns true if the stamps into `A` match the last factorised timestep, whose
+    /// factorisation can be reused.
+    pub(crate) fn is_factored(&self) -> bool {
+        self.factored.as_ref() == Some(&self.a)
+    }
+
+    /// Marks the stamps into `A` as factorised.
+    pub(crate) fn set_factored(&mut self) {
+        let factored = self.factored.get_or_insert_with(Vec::new);
+        factored.clone_from(&self.a);
+    }
+
+    /// Forgets the factorised timestep, after the solver factorised another matrix.
+    pub(crate) fn clear_factored(&mut self) {
+        self.factored = None;
+    }
+}
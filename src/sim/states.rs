@@ -0,0 +1,62 @@
+use crate::models::{Element, StateHistory};
+use crate::spot::Numeric;
+
+/// Per element values kept by the simulator, so that the elements themselves stay immutable
+/// during the analyses. Each element owns a contiguous slice of values.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ElementStore<T> {
+    /// The index of the first value of each element.
+    offsets: Vec<usize>,
+    /// The values of all elements.
+    values: Vec<T>,
+}
+
+/// The histories of the charges and fluxes of all elements in the circuit.
+/// Each element owns `Element::state_count` states.
+pub(crate) type StateStore = ElementStore<StateHistory>;
+
+/// The junction voltages the nonlinear elements were linearised at in the last Newton iteration.
+/// Each element owns `Element::junction_count` voltages.
+pub(crate) type JunctionStore = ElementStore<Numeric>;
+
+impl<T: Clone + Default> ElementStore<T> {
+    /// Creates `count(element)` default values for each of the given elements.
+    fn with_counts(elements: &[Element], count: impl Fn(&Element) -> usize) -> Self {
+        let mut offsets = Vec::with_capacity(elements.len() + 1);
+        let mut total = 0;
+        for element in elements {
+            offsets.push(total);
+            total += count(element);
+        }
+        offsets.push(total);
+
+        Self {
+            offsets,
+            values: vec![T::default(); total],
+        }
+    }
+
+    /// Returns the values of the element at `idx`.
+    pub(crate) fn element(&self, idx: usize) -> &[T] {
+        &self.values[self.offsets[idx]..self.offsets[idx + 1]]
+    }
+
+    /// Returns the mutable values of the element at `idx`.
+    pub(crate) fn element_mut(&mut self, idx: usize) -> &mut [T] {
+        &mut self.values[self.offsets[idx]..self.offsets[idx + 1]]
+    }
+}
+
+impl StateStore {
+    /// Creates the states for the given elements, all at zero.
+    pub(crate) fn new(elements: &[Element]) -> Self {
+        Self::with_counts(elements, Element::state_count)
+    }
+}
+
+impl JunctionStore {
+    /// Creates the junction voltages for the given elements, all at zero.
+    pub(crate) fn new(elements: &[Element]) -> Self {
+        Self::with_counts(elements, Element::junction_count)
+    }
+}
@@ -3,14 +3,33 @@
 use std::sync::Arc;
 
 use crate::frontends::Simulation;
+use crate::models::behavioral::expression::Expression;
+use crate::models::behavioral::spice::parse_expression_str;
+use crate::models::charge::junction_charge;
+use crate::models::controlled_sources::nonlinear::{polynomial, table, voltage_control};
+use crate::models::diode::DiodeOptions;
+use crate::models::jfet::JfetOptions;
+use crate::models::mosfet::{Mos1Options, MosPolarity};
+use crate::models::switch::{SwitchControl, SwitchOptions};
 use crate::models::{
-    CapacitorBundle, Element, ResistorBundle, Unit, VSourceBundle, VSourceSinBundle,
-    VSourceStepBundle, Variable,
+    BehavioralBundle, BehavioralOutput, BjtBundle, CCCSBundle, CapacitorBundle, DiodeBundle,
+    Element, ISourceBundle, InductorBundle, JfetBundle, Mos0Bundle, Mos1Bundle, ResistorBundle,
+    SwitchBundle, Unit, VSourceBundle, VSourceSinBundle, VSourceStepBundle, Variable,
 };
 use crate::sim::commands::{ACMode, SimulationCommand};
+use crate::sim::options::{
+    DumpAnalysis, IntegrationMethod, OpStrategy, SimulationOption, SimulatorOptions,
+};
+use crate::sim::report::ConvergenceReport;
 use crate::sim::simulation_result::Sim;
-use crate::sim::Simulator;
-use crate::solver::{FaerSolver, NalgebraSolver, RSparseSolver};
+use crate::sim::states::StateStore;
+use crate::sim::topology::{check_topology, TopologyError};
+use crate::sim::{Simulator, SimulatorError};
+use crate::solver::tests::SingularSolver;
+use crate::solver::{
+    AutoSolver, FaerSolver, FaerSparseSolver, KrylovSolver, NalgebraSolver, RSparseSolver, Solver,
+    SolverError, Solvers,
+};
 use crate::spot::*;
 
 // TODO: Refactor init_sim_x tests to use helper functions and reduce code duplication
@@ -184,7 +203,7 @@ fn run_sim_tran() {
     // This test verifies that the transient simulation correctly calculates
     // the current and voltage over time for a simple circuit with a constant
     // voltage source and a resistor.
-    let commands = vec![SimulationCommand::Tran(1.0, 10.0)];
+    let commands = vec![SimulationCommand::Tran(1.0, 10.0, 0.0, None, false)];
     let options = vec![];
 
     let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
@@ -328,7 +347,7 @@ fn test_vsource_sin_tran() {
     //
     // This test verifies that the sinusoidal voltage source correctly generates
     // a sinusoidal waveform in transient analysis.
-    let commands = vec![SimulationCommand::Tran(0.1, 1.0)]; // Small time step for accuracy
+    let commands = vec![SimulationCommand::Tran(0.1, 1.0, 0.0, None, false)]; // Small time step for accuracy
     let options = vec![];
 
     let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
@@ -458,7 +477,7 @@ fn test_rc_sinusoidal_tran() {
     // Cutoff frequency: fc = 1/(2πRC) ≈ 159.15 Hz
     // At 100Hz (below cutoff), we expect significant attenuation and phase shift
 
-    let commands = vec![SimulationCommand::Tran(0.00001, 0.1)]; // 10µs step, 100ms total
+    let commands = vec![SimulationCommand::Tran(0.00001, 0.1, 0.0, None, false)]; // 10µs step, 100ms total
     let options = vec![];
 
     let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1); // Input node
@@ -623,7 +642,7 @@ fn test_rc_constant_tran() {
     // With a constant 10V input, the capacitor should charge up and the output
     // should approach 10V over time (RC time constant = 1ms).
 
-    let commands = vec![SimulationCommand::Tran(0.00001, 0.01)]; // 10µs step, 10ms total
+    let commands = vec![SimulationCommand::Tran(0.00001, 0.01, 0.0, None, false)]; // 10µs step, 10ms total
     let options = vec![];
 
     let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1); // Input node
@@ -986,7 +1005,7 @@ fn test_rc_step_response() {
     // Test RC circuit with step voltage source in transient simulation.
     // This test uses a step function (0V to 10V) to verify capacitor charging behavior.
 
-    let commands = vec![SimulationCommand::Tran(0.0001, 0.01)]; // 100µs step, 10ms total
+    let commands = vec![SimulationCommand::Tran(0.0001, 0.01, 0.0, None, false)]; // 100µs step, 10ms total
     let options = vec![];
 
     let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1); // Input node
@@ -1145,3 +1164,2249 @@ fn test_adaptive_timestep_clamping() {
     assert!(ADAPTIVE_SAFETY_FACTOR < 1.0);
     assert!(ADAPTIVE_SAFETY_FACTOR > 0.5);
 }
+
+/// Builds an RC low-pass (R = 1kΩ, C = 1µF, τ = 1ms) driven by a 0V to 10V step at t=0.
+fn rc_step_simulation(command: SimulationCommand) -> Simulation {
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let node_2 = Variable::new(Arc::from("2"), Unit::Volt, 2);
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+
+    let vsource = Element::VSourceStep(VSourceStepBundle::new(
+        Arc::from("V1"),
+        branch_1.clone(),
+        None,
+        Some(node_1.clone()),
+        0.0,
+        10.0,
+        0.0,
+        None,
+    ));
+    let resistor = Element::Resistor(ResistorBundle::new(
+        Arc::from("R1"),
+        Some(node_1.clone()),
+        Some(node_2.clone()),
+        1000.0,
+    ));
+    let capacitor = Element::Capacitor(CapacitorBundle::new(
+        Arc::from("C1"),
+        Some(node_2.clone()),
+        None,
+        0.000_001,
+    ));
+
+    Simulation {
+        commands: vec![command],
+        options: vec![],
+        elements: vec![vsource, resistor, capacitor],
+        variables: vec![branch_1, node_1, node_2],
+    }
+}
+
+fn run_tran_results(sim: Simulation) -> Vec<(Numeric, Vec<(Variable, Numeric)>)> {
+    let mut simulator: Simulator<NalgebraSolver> = Simulator::from(sim);
+    match simulator.run().unwrap().results.remove(0) {
+        Sim::Tran(results) => results,
+        _ => panic!("Expected transient results"),
+    }
+}
+
+#[test]
+fn test_tran_output_grid_with_tstart() {
+    let results = run_tran_results(rc_step_simulation(SimulationCommand::Tran(
+        1e-3, 5e-3, 2e-3, None, false,
+    )));
+
+    let times: Vec<Numeric> = results.iter().map(|(t, _)| *t).collect();
+    assert_eq!(times.len(), 4);
+    for (time, expected) in times.iter().zip([2e-3, 3e-3, 4e-3, 5e-3]) {
+        assert!((time - expected).abs() < 1e-12, "{time} != {expected}");
+    }
+}
+
+#[test]
+fn test_tran_tmax_decouples_internal_step() {
+    // A coarse output grid with a fine internal step must still be accurate
+    let results = run_tran_results(rc_step_simulation(SimulationCommand::Tran(
+        1e-3,
+        2e-3,
+        0.0,
+        Some(1e-6),
+        false,
+    )));
+
+    assert_eq!(results.len(), 3);
+    let (time, values) = results.last().unwrap();
+    let expected = 10.0 * (1.0 - (-time / 1e-3).exp());
+    assert!(
+        (values[2].1 - expected).abs() < 0.01,
+        "Expected {expected}V, got {}V",
+        values[2].1
+    );
+}
+
+#[test]
+fn test_tran_uic_skips_operating_point() {
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let elements = vec![
+        Element::VSource(VSourceBundle::new(
+            Arc::from("V1"),
+            branch_1.clone(),
+            None,
+            Some(node_1.clone()),
+            10.0,
+            None,
+        )),
+        Element::Resistor(ResistorBundle::new(
+            Arc::from("R1"),
+            Some(node_1.clone()),
+            None,
+            10.0,
+        )),
+    ];
+    let sim = |uic| Simulation {
+        commands: vec![SimulationCommand::Tran(1.0, 2.0, 0.0, None, uic)],
+        options: vec![],
+        elements: elements.clone(),
+        variables: vec![branch_1.clone(), node_1.clone()],
+    };
+
+    let with_op = run_tran_results(sim(false));
+    let with_uic = run_tran_results(sim(true));
+
+    assert!((with_op[0].1[1].1 - 10.0).abs() < 1e-9);
+    assert_eq!(with_uic[0].1[1].1, 0.0);
+    assert!((with_uic.last().unwrap().1[1].1 - 10.0).abs() < 1e-9);
+}
+
+/// Builds an unpowered RC (R = 1kΩ, C = 1µF, τ = 1ms) discharging from its initial condition.
+fn rc_discharge_simulation(
+    options: Vec<SimulationOption>,
+    cap_ic: Option<Numeric>,
+    uic: bool,
+) -> Simulation {
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 0);
+
+    let mut capacitor = CapacitorBundle::new(Arc::from("C1"), Some(node_1.clone()), None, 1e-6);
+    capacitor.set_initial_condition(cap_ic);
+
+    Simulation {
+        commands: vec![SimulationCommand::Tran(1e-3, 1e-3, 0.0, Some(1e-6), uic)],
+        options,
+        elements: vec![
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(node_1.clone()),
+                None,
+                1000.0,
+            )),
+            Element::Capacitor(capacitor),
+        ],
+        variables: vec![node_1],
+    }
+}
+
+fn assert_rc_discharge(results: &[(Numeric, Vec<(Variable, Numeric)>)], v0: Numeric) {
+    let (_, initial) = &results[0];
+    let (time, last) = results.last().unwrap();
+    let expected = v0 * (-time / 1e-3).exp();
+
+    assert!((initial[0].1 - v0).abs() < 1e-6, "Initial {}", initial[0].1);
+    assert!(
+        (last[0].1 - expected).abs() < 0.01,
+        "Expected {expected}V, got {}V",
+        last[0].1
+    );
+}
+
+#[test]
+fn test_tran_ic_holds_operating_point() {
+    let options = vec![SimulationOption::InitialConditions(vec![(
+        Arc::from("1"),
+        5.0,
+    )])];
+    let results = run_tran_results(rc_discharge_simulation(options, None, false));
+
+    assert_rc_discharge(&results, 5.0);
+}
+
+#[test]
+fn test_tran_ic_with_uic() {
+    let options = vec![SimulationOption::InitialConditions(vec![(
+        Arc::from("1"),
+        5.0,
+    )])];
+    let results = run_tran_results(rc_discharge_simulation(options, None, true));
+
+    assert_rc_discharge(&results, 5.0);
+}
+
+#[test]
+fn test_tran_element_ic_with_uic() {
+    let results = run_tran_results(rc_discharge_simulation(vec![], Some(3.0), true));
+
+    // The node voltage starts at zero, the capacitor state at its IC
+    assert_eq!(results[0].1[0].1, 0.0);
+    let (time, last) = results.last().unwrap();
+    let expected = 3.0 * (-time / 1e-3).exp();
+    assert!((last[0].1 - expected).abs() < 0.01);
+}
+
+#[test]
+fn test_tran_with_krylov_solver() {
+    let sim = rc_discharge_simulation(vec![], Some(3.0), true);
+    let mut simulator: Simulator<KrylovSolver> = Simulator::from(sim);
+    let results = match simulator.run().unwrap().results.remove(0) {
+        Sim::Tran(results) => results,
+        _ => panic!("Expected transient results"),
+    };
+
+    let (time, last) = results.last().unwrap();
+    let expected = 3.0 * (-time / 1e-3).exp();
+    assert!((last[0].1 - expected).abs() < 0.01);
+}
+
+#[test]
+fn test_tran_with_auto_solver() {
+    let sim = rc_discharge_simulation(vec![], Some(3.0), true);
+    let mut simulator: Simulator<AutoSolver> = Simulator::from(sim);
+    let results = match simulator.run().unwrap().results.remove(0) {
+        Sim::Tran(results) => results,
+        _ => panic!("Expected transient results"),
+    };
+    assert_eq!(simulator.solver.kind(), Solvers::Nalgebra);
+
+    let (time, last) = results.last().unwrap();
+    let expected = 3.0 * (-time / 1e-3).exp();
+    assert!((last[0].1 - expected).abs() < 0.01);
+}
+
+#[test]
+fn test_tran_repeated_after_solver_switch() {
+    let sim = rc_discharge_simulation(vec![], Some(3.0), true);
+    let mut simulator: Simulator<AutoSolver> = Simulator::from(sim);
+    simulator
+        .solver
+        .set_backend(Solvers::Nalgebra, Box::new(SingularSolver));
+    let results = match simulator.run().unwrap().results.remove(0) {
+        Sim::Tran(results) => results,
+        _ => panic!("Expected transient results"),
+    };
+    assert_eq!(simulator.solver.kind(), Solvers::Rsparse);
+
+    // The fallback solves the whole analysis stamped again from the start
+    let sim = rc_discharge_simulation(vec![], Some(3.0), true);
+    let mut reference: Simulator<NalgebraSolver> = Simulator::from(sim);
+    let expected = match reference.run().unwrap().results.remove(0) {
+        Sim::Tran(results) => results,
+        _ => panic!("Expected transient results"),
+    };
+    assert_eq!(results.len(), expected.len());
+    for ((time, values), (expected_time, expected_values)) in results.iter().zip(&expected) {
+        assert_eq!(time, expected_time);
+        assert!((values[0].1 - expected_values[0].1).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_ic_unknown_node() {
+    let options = vec![SimulationOption::InitialConditions(vec![(
+        Arc::from("x"),
+        5.0,
+    )])];
+    let mut simulator: Simulator<NalgebraSolver> =
+        Simulator::from(rc_discharge_simulation(options, None, false));
+
+    assert!(matches!(
+        simulator.run(),
+        Err(SimulatorError::NodeNotFound(node)) if node == "x"
+    ));
+}
+
+#[test]
+fn test_nodeset_initial_guess() {
+    let options = vec![SimulationOption::NodeSet(vec![(Arc::from("1"), 0.7)])];
+    let simulator: Simulator<NalgebraSolver> =
+        Simulator::from(rc_discharge_simulation(options, None, false));
+
+    assert_eq!(simulator.generate_initial_guess().unwrap(), vec![0.7]);
+}
+
+/// Returns the deviation of the RC discharge from the analytic solution after three time constants,
+/// integrated with a fixed step of a tenth of the time constant.
+fn rc_discharge_error(method: IntegrationMethod) -> Numeric {
+    let mut sim = rc_discharge_simulation(
+        vec![SimulationOption::IntegrationMethod(method)],
+        Some(1.0),
+        true,
+    );
+    sim.commands = vec![SimulationCommand::Tran(1e-4, 3e-3, 0.0, None, true)];
+
+    let results = run_tran_results(sim);
+    let (time, values) = results.last().unwrap();
+    (values[0].1 - (-time / 1e-3).exp()).abs()
+}
+
+#[test]
+fn test_tran_second_order_methods_accuracy() {
+    let backward_euler = rc_discharge_error(IntegrationMethod::BackwardEuler);
+    for method in [
+        IntegrationMethod::Trapezoidal,
+        IntegrationMethod::Gear,
+        IntegrationMethod::TrBdf2,
+    ] {
+        let error = rc_discharge_error(method.clone());
+        assert!(error < 5e-4, "{method:?}: error {error}");
+        assert!(
+            error < backward_euler / 10.0,
+            "{method:?}: error {error} not better than backward Euler {backward_euler}"
+        );
+    }
+}
+
+#[test]
+fn test_tran_rl_step_gear() {
+    // V = 10V, R = 10Ω, L = 10mH, τ = 1ms: the inductor voltage decays from 10V
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let node_2 = Variable::new(Arc::from("2"), Unit::Volt, 2);
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let branch_l1 = Variable::new(Arc::from("L1#branch"), Unit::Ampere, 3);
+
+    let sim = Simulation {
+        commands: vec![SimulationCommand::Tran(1e-4, 3e-3, 0.0, None, true)],
+        options: vec![SimulationOption::IntegrationMethod(IntegrationMethod::Gear)],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(node_1.clone()),
+                10.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(node_1.clone()),
+                Some(node_2.clone()),
+                10.0,
+            )),
+            Element::Inductor(InductorBundle::new(
+                Arc::from("L1"),
+                branch_l1.clone(),
+                Some(node_2.clone()),
+                None,
+                10e-3,
+            )),
+        ],
+        variables: vec![branch_1, node_1, node_2, branch_l1],
+    };
+
+    for (time, values) in run_tran_results(sim).iter().skip(1) {
+        let expected = 10.0 * (-time / 1e-3).exp();
+        assert!(
+            (values[2].1 - expected).abs() < 0.1,
+            "t = {time}: expected {expected}V, got {}V",
+            values[2].1
+        );
+    }
+}
+
+#[test]
+fn test_tran_dc_biased_inductor_stays_flat() {
+    // V = 10V, R = 10Ω: the operating point already carries 1A through the inductor
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let node_2 = Variable::new(Arc::from("2"), Unit::Volt, 2);
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let branch_l1 = Variable::new(Arc::from("L1#branch"), Unit::Ampere, 3);
+
+    for method in [IntegrationMethod::Trapezoidal, IntegrationMethod::Gear] {
+        let sim = Simulation {
+            commands: vec![SimulationCommand::Tran(1e-4, 3e-3, 0.0, None, false)],
+            options: vec![SimulationOption::IntegrationMethod(method)],
+            elements: vec![
+                Element::VSource(VSourceBundle::new(
+                    Arc::from("V1"),
+                    branch_1.clone(),
+                    None,
+                    Some(node_1.clone()),
+                    10.0,
+                    None,
+                )),
+                Element::Resistor(ResistorBundle::new(
+                    Arc::from("R1"),
+                    Some(node_1.clone()),
+                    Some(node_2.clone()),
+                    10.0,
+                )),
+                Element::Inductor(InductorBundle::new(
+                    Arc::from("L1"),
+                    branch_l1.clone(),
+                    Some(node_2.clone()),
+                    None,
+                    10e-3,
+                )),
+            ],
+            variables: vec![
+                branch_1.clone(),
+                node_1.clone(),
+                node_2.clone(),
+                branch_l1.clone(),
+            ],
+        };
+
+        for (time, values) in run_tran_results(sim) {
+            assert!(values[2].1.abs() < 1e-9, "t = {time}: {}V", values[2].1);
+            assert!(
+                (values[3].1 - 1.0).abs() < 1e-9,
+                "t = {time}: {}A",
+                values[3].1
+            );
+        }
+    }
+}
+
+#[test]
+fn test_tran_nonlinear_junction_charge_is_conserved() {
+    // A reverse biased junction capacitance charged through R = 1kΩ
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let node_2 = Variable::new(Arc::from("2"), Unit::Volt, 2);
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let (cj0, vj, m) = (1e-6, 0.7, 0.5);
+
+    let sim = Simulation {
+        commands: vec![SimulationCommand::Tran(1e-5, 5e-3, 0.0, None, true)],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(node_1.clone()),
+                5.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(node_1.clone()),
+                Some(node_2.clone()),
+                1000.0,
+            )),
+            Element::Diode(DiodeBundle::new(
+                Arc::from("D1"),
+                None,
+                Some(node_2.clone()),
+                Some(DiodeOptions::default().with_junction_capacitance(cj0, vj, m)),
+            )),
+        ],
+        variables: vec![branch_1, node_1, node_2],
+    };
+    let results = run_tran_results(sim);
+
+    // With backward Euler the charge delivered through the resistor equals the stored charge
+    let delivered: Numeric = results
+        .iter()
+        .skip(1)
+        .map(|(_, values)| (values[1].1 - values[2].1) / 1000.0 * 1e-5)
+        .sum();
+    let v_final = results.last().unwrap().1[2].1;
+    let (stored, _) = junction_charge(-v_final, cj0, vj, m, 0.5);
+
+    assert!(
+        v_final > 4.9,
+        "The junction should be charged, got {v_final}V"
+    );
+    assert!(
+        (delivered + stored).abs() < 1e-3 * stored.abs(),
+        "Delivered {delivered}C, stored {stored}C"
+    );
+}
+
+#[test]
+fn test_state_store_slices_per_element() {
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 0);
+    let elements = vec![
+        Element::Capacitor(CapacitorBundle::new(
+            Arc::from("C1"),
+            Some(node_1.clone()),
+            None,
+            1e-6,
+        )),
+        Element::Resistor(ResistorBundle::new(
+            Arc::from("R1"),
+            Some(node_1.clone()),
+            None,
+            1.0,
+        )),
+        Element::Inductor(InductorBundle::new(
+            Arc::from("L1"),
+            Variable::new(Arc::from("L1#branch"), Unit::Ampere, 1),
+            Some(node_1),
+            None,
+            1e-3,
+        )),
+    ];
+    let mut states = StateStore::new(&elements);
+
+    assert_eq!(states.element(0).len(), 1);
+    assert!(states.element(1).is_empty());
+    assert_eq!(states.element(2).len(), 1);
+
+    states.element_mut(2)[0].push(1.0, 2.0);
+    assert_eq!(states.element(2)[0].x1, 1.0);
+    assert_eq!(states.element(0)[0].x1, 0.0);
+}
+
+/// A diode forward biased by `voltage` through `resistance`
+fn diode_simulation(
+    voltage: Numeric,
+    resistance: Numeric,
+    options: Vec<SimulationOption>,
+) -> Simulation {
+    let node_1 = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let node_2 = Variable::new(Arc::from("2"), Unit::Volt, 2);
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+
+    Simulation {
+        commands: vec![SimulationCommand::Op],
+        options,
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(node_1.clone()),
+                voltage,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(node_1.clone()),
+                Some(node_2.clone()),
+                resistance,
+            )),
+            Element::Diode(DiodeBundle::new(
+                Arc::from("D1"),
+                Some(node_2.clone()),
+                None,
+                None,
+            )),
+        ],
+        variables: vec![branch_1, node_1, node_2],
+    }
+}
+
+fn diode_op(
+    voltage: Numeric,
+    resistance: Numeric,
+    strategies: Vec<OpStrategy>,
+) -> Result<Vec<(Variable, Numeric)>, SimulatorError> {
+    let options = vec![SimulationOption::OpStrategies(strategies)];
+    let mut simulator: Simulator<NalgebraSolver> =
+        Simulator::from(diode_simulation(voltage, resistance, options));
+    simulator.init_solver().unwrap();
+    simulator.find_op()
+}
+
+#[test]
+fn test_op_newton_limits_hard_diode() {
+    // Without junction voltage limiting the diode current overflows at 100V across 1Ω
+    let op = diode_op(100.0, 1.0, vec![OpStrategy::Newton]).unwrap();
+    let v_diode = op[2].1;
+    assert!(v_diode > 0.7 && v_diode < 1.2, "Diode voltage {v_diode}V");
+}
+
+#[test]
+fn test_op_continuation_strategies_converge() {
+    for strategy in [OpStrategy::SourceStepping, OpStrategy::PseudoTransient] {
+        let op =
+            diode_op(100.0, 1.0, vec![strategy]).unwrap_or_else(|err| panic!("{strategy}: {err}"));
+        let v_diode = op[2].1;
+        assert!(
+            v_diode > 0.7 && v_diode < 1.2,
+            "{strategy} found a diode voltage of {v_diode}V"
+        );
+    }
+}
+
+#[test]
+fn test_op_gmin_stepping_matches_newton() {
+    let newton = diode_op(5.0, 1000.0, vec![OpStrategy::Newton]).unwrap();
+    let gmin = diode_op(5.0, 1000.0, vec![OpStrategy::GminStepping]).unwrap();
+    assert!((newton[2].1 - gmin[2].1).abs() < RELTOL * newton[2].1);
+}
+
+#[test]
+fn test_op_default_strategies_fall_back() {
+    let mut simulator: Simulator<NalgebraSolver> =
+        Simulator::from(diode_simulation(100.0, 1.0, vec![]));
+    simulator.init_solver().unwrap();
+    let op = simulator.find_op().unwrap();
+
+    // Nearly all of the 100V drop across the resistor
+    let current = (op[1].1 - op[2].1) / 1.0;
+    assert!((current - 99.0).abs() < 0.5, "Diode current {current}A");
+}
+
+#[test]
+fn test_op_mosfet_common_source() {
+    // VDD = 5V through RD = 10kΩ to the drain, the gate is held 1V above threshold
+    let gate = Variable::new(Arc::from("g"), Unit::Volt, 2);
+    let supply = Variable::new(Arc::from("vdd"), Unit::Volt, 3);
+    let drain = Variable::new(Arc::from("d"), Unit::Volt, 4);
+    let branch_g = Variable::new(Arc::from("VG#branch"), Unit::Ampere, 0);
+    let branch_dd = Variable::new(Arc::from("VDD#branch"), Unit::Ampere, 1);
+
+    let sim = Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![SimulationOption::OpStrategies(vec![OpStrategy::Newton])],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("VG"),
+                branch_g.clone(),
+                None,
+                Some(gate.clone()),
+                1.43,
+                None,
+            )),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("VDD"),
+                branch_dd.clone(),
+                None,
+                Some(supply.clone()),
+                5.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("RD"),
+                Some(supply.clone()),
+                Some(drain.clone()),
+                10e3,
+            )),
+            Element::Mos0(Mos0Bundle::new(
+                Arc::from("M1"),
+                Some(gate.clone()),
+                Some(drain.clone()),
+                None,
+                None,
+            )),
+        ],
+        variables: vec![branch_g, branch_dd, gate, supply, drain],
+    };
+    let mut simulator: Simulator<NalgebraSolver> = Simulator::from(sim);
+    simulator.init_solver().unwrap();
+    let op = simulator.find_op().unwrap();
+
+    // Saturation: id = kp / 2 * 1V^2 = 59µA
+    let v_drain = op[4].1;
+    assert!(
+        (v_drain - (5.0 - 59e-6 * 10e3)).abs() < 1e-3,
+        "Drain at {v_drain}V"
+    );
+}
+
+#[test]
+fn test_convergence_report_names_worst_variables() {
+    let mut simulator: Simulator<NalgebraSolver> =
+        Simulator::from(diode_simulation(5.0, 1000.0, vec![]));
+    simulator.init_solver().unwrap();
+
+    let x_old = [0.0, 5.0, 0.0];
+    let x_new = [1e-2, 5.0, 0.7];
+    let report = simulator.convergence_report(&x_old, &x_new);
+
+    // The converged supply node is left out, the branch current moved the most
+    let names: Vec<_> = report
+        .variables
+        .iter()
+        .map(|var| var.name.clone())
+        .collect();
+    assert_eq!(names, vec![Arc::from("V1#branch"), Arc::from("2")]);
+    assert_eq!(report.variables[1].delta, 0.7);
+
+    let devices = report.variables[1].devices.as_deref().unwrap();
+    assert!(devices.contains("R1"), "{devices}");
+    assert!(devices.contains("D1 (forward biased)"), "{devices}");
+    assert!(!devices.contains("V1"), "{devices}");
+}
+
+#[test]
+fn test_convergence_report_records_time() {
+    let err = SimulatorError::NonConvergentMaxIter {
+        max_iter: MAXITER,
+        tol: RELTOL,
+        report: ConvergenceReport::default(),
+    }
+    .at_time(1e-3);
+
+    let SimulatorError::NonConvergentMaxIter { report, .. } = err else {
+        panic!("Expected a non-convergent error");
+    };
+    assert_eq!(report.time, Some(1e-3));
+    assert!(report.to_string().contains("t = 0.001s"));
+}
+
+#[test]
+fn test_simulator_options_last_option_wins() {
+    let settings = SimulatorOptions::from(
+        [
+            SimulationOption::RelTol(1e-4),
+            SimulationOption::Temperature(85.0),
+            SimulationOption::RelTol(1e-2),
+        ]
+        .as_slice(),
+    );
+
+    assert_eq!(settings.reltol, 1e-2);
+    assert_eq!(settings.temp, 85.0);
+    assert_eq!(settings.abstol, ABSTOL);
+    assert_eq!(settings.itl1, MAXITER);
+    assert_eq!(settings.method, IntegrationMethod::BackwardEuler);
+}
+
+#[test]
+fn test_op_itl1_limits_newton() {
+    let options = vec![
+        SimulationOption::OpStrategies(vec![OpStrategy::Newton]),
+        SimulationOption::OpMaxIter(3),
+    ];
+    let mut simulator: Simulator<NalgebraSolver> =
+        Simulator::from(diode_simulation(100.0, 1.0, options));
+    simulator.init_solver().unwrap();
+
+    let Err(SimulatorError::NonConvergentMaxIter { max_iter, .. }) = simulator.find_op() else {
+        panic!("Expected the iteration limit to stop Newton");
+    };
+    assert_eq!(max_iter, 3);
+}
+
+#[test]
+fn test_op_temperature_lowers_diode_voltage() {
+    let diode_voltage = |temp: Numeric| {
+        let options = vec![SimulationOption::Temperature(temp)];
+        let mut simulator: Simulator<NalgebraSolver> =
+            Simulator::from(diode_simulation(5.0, 1000.0, options));
+        simulator.init_solver().unwrap();
+        simulator.find_op().unwrap()[2].1
+    };
+
+    // The growing saturation current outweighs the thermal voltage, about -2mV/K
+    let cold = diode_voltage(20.0);
+    let hot = diode_voltage(100.0);
+    let slope = (hot - cold) / 80.0;
+    assert!(slope < -1e-3 && slope > -3e-3, "{slope}V/K");
+}
+
+#[test]
+fn test_temperature_sweep_runs_all_analyses() {
+    let options = vec![SimulationOption::Temperatures(vec![0.0, 50.0, 100.0])];
+    let mut simulation = diode_simulation(5.0, 1000.0, options);
+    simulation.commands.push(SimulationCommand::Op);
+    let mut simulator: Simulator<NalgebraSolver> = Simulator::from(simulation);
+    let results = simulator.run().unwrap();
+
+    let voltages: Vec<_> = results
+        .results
+        .iter()
+        .map(|result| match result {
+            Sim::Op(op) => op[2].1,
+            _ => panic!("Expected an operating point"),
+        })
+        .collect();
+    assert_eq!(voltages.len(), 6);
+    assert_eq!(
+        results.temperatures,
+        vec![0.0, 0.0, 50.0, 50.0, 100.0, 100.0]
+    );
+    assert!(results.is_temperature_sweep());
+    assert_eq!(voltages[0], voltages[1]);
+    assert!(voltages[1] > voltages[3] && voltages[3] > voltages[5]);
+}
+
+#[test]
+fn test_autotune_keeps_user_options() {
+    let options = vec![
+        SimulationOption::IntegrationMethod(IntegrationMethod::Trapezoidal),
+        SimulationOption::RelTol(1e-4),
+    ];
+    let simulator: Simulator<NalgebraSolver> =
+        Simulator::from(diode_simulation(5.0, 1000.0, options)).with_autotune();
+
+    assert_eq!(simulator.settings.method, IntegrationMethod::Trapezoidal);
+    assert_eq!(simulator.settings.reltol, 1e-4);
+}
+
+fn node(name: &str, idx: usize) -> Variable {
+    Variable::new(Arc::from(name), Unit::Volt, idx)
+}
+
+fn names(names: &[Arc<str>]) -> Vec<&str> {
+    names.iter().map(|name| name.as_ref()).collect()
+}
+
+/// V1 drives node 1, R1 connects node 1 to node 2 and R2 node 2 to ground.
+fn topology_divider() -> (Vec<Element>, Vec<Variable>) {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let elements = vec![
+        Element::VSource(VSourceBundle::new(
+            Arc::from("V1"),
+            branch_1.clone(),
+            None,
+            Some(node("1", 1)),
+            1.0,
+            None,
+        )),
+        Element::Resistor(ResistorBundle::new(
+            Arc::from("R1"),
+            Some(node("1", 1)),
+            Some(node("2", 2)),
+            1.0,
+        )),
+        Element::Resistor(ResistorBundle::new(
+            Arc::from("R2"),
+            Some(node("2", 2)),
+            None,
+            1.0,
+        )),
+    ];
+    (elements, vec![branch_1, node("1", 1), node("2", 2)])
+}
+
+#[test]
+fn test_topology_valid_circuit() {
+    let (elements, vars) = topology_divider();
+    assert!(check_topology(&elements, &vars).is_empty());
+
+    let sim = diode_simulation(1.0, 1.0, vec![]);
+    assert!(check_topology(&sim.elements, &sim.variables).is_empty());
+}
+
+#[test]
+fn test_topology_single_connection() {
+    let (mut elements, mut vars) = topology_divider();
+    vars.push(node("3", 3));
+    elements.push(Element::Resistor(ResistorBundle::new(
+        Arc::from("R3"),
+        Some(node("2", 2)),
+        Some(node("3", 3)),
+        1.0,
+    )));
+
+    let findings = check_topology(&elements, &vars);
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+        TopologyError::SingleConnection { node, element } => {
+            assert_eq!(node.as_ref(), "3");
+            assert_eq!(element.as_ref(), "R3");
+        }
+        finding => panic!("Unexpected finding: {finding}"),
+    }
+}
+
+#[test]
+fn test_topology_no_dc_path() {
+    let (mut elements, mut vars) = topology_divider();
+    vars.extend([node("3", 3), node("4", 4)]);
+    for name in ["R3", "R4"] {
+        elements.push(Element::Resistor(ResistorBundle::new(
+            Arc::from(name),
+            Some(node("3", 3)),
+            Some(node("4", 4)),
+            1.0,
+        )));
+    }
+
+    let findings = check_topology(&elements, &vars);
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+        TopologyError::NoDcPath { nodes, elements } => {
+            assert_eq!(names(nodes), ["3", "4"]);
+            assert_eq!(names(elements), ["R3", "R4"]);
+        }
+        finding => panic!("Unexpected finding: {finding}"),
+    }
+}
+
+#[test]
+fn test_topology_current_cut_set() {
+    let (mut elements, mut vars) = topology_divider();
+    vars.push(node("3", 3));
+    elements.push(Element::Capacitor(CapacitorBundle::new(
+        Arc::from("C1"),
+        Some(node("2", 2)),
+        Some(node("3", 3)),
+        1e-6,
+    )));
+    elements.push(Element::ISource(ISourceBundle::new(
+        Arc::from("I1"),
+        Some(node("3", 3)),
+        None,
+        1e-3,
+    )));
+
+    let findings = check_topology(&elements, &vars);
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+        TopologyError::CurrentCutSet { nodes, elements } => {
+            assert_eq!(names(nodes), ["3"]);
+            assert_eq!(names(elements), ["C1", "I1"]);
+        }
+        finding => panic!("Unexpected finding: {finding}"),
+    }
+}
+
+#[test]
+fn test_topology_voltage_loop() {
+    let (mut elements, vars) = topology_divider();
+    elements.push(Element::Inductor(InductorBundle::new(
+        Arc::from("L1"),
+        Variable::new(Arc::from("L1#branch"), Unit::Ampere, 3),
+        Some(node("2", 2)),
+        Some(node("1", 1)),
+        1e-3,
+    )));
+    elements.push(Element::Inductor(InductorBundle::new(
+        Arc::from("L2"),
+        Variable::new(Arc::from("L2#branch"), Unit::Ampere, 4),
+        Some(node("2", 2)),
+        None,
+        1e-3,
+    )));
+
+    let findings = check_topology(&elements, &vars);
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+        TopologyError::VoltageLoop { nodes, elements } => {
+            assert_eq!(names(nodes), ["2", "1", "0"]);
+            assert_eq!(names(elements), ["L1", "V1", "L2"]);
+        }
+        finding => panic!("Unexpected finding: {finding}"),
+    }
+}
+
+#[test]
+fn test_topology_missing_controlling_branch() {
+    let (mut elements, mut vars) = topology_divider();
+    let control = Variable::new(Arc::from("Vx"), Unit::Volt, 3);
+    vars.push(control.clone());
+    elements.push(Element::CCCS(CCCSBundle::new(
+        Arc::from("F1"),
+        Some(node("2", 2)),
+        None,
+        Some(control),
+        None,
+    )));
+
+    let findings = check_topology(&elements, &vars);
+    assert_eq!(findings.len(), 1);
+    match &findings[0] {
+        TopologyError::MissingControllingBranch { element, branch } => {
+            assert_eq!(element.as_ref(), "F1");
+            assert_eq!(branch.as_ref(), "Vx");
+        }
+        finding => panic!("Unexpected finding: {finding}"),
+    }
+
+    // Controlled by the current through V1
+    if let Some(Element::CCCS(cccs)) = elements.last_mut() {
+        *cccs = CCCSBundle::new(
+            Arc::from("F1"),
+            Some(node("2", 2)),
+            None,
+            Some(Variable::new(Arc::from("V1"), Unit::Volt, 3)),
+            None,
+        );
+    }
+    assert!(check_topology(&elements, &vars).is_empty());
+}
+
+/// Creates the divider with a second voltage source in parallel to V1.
+fn voltage_loop_simulation() -> Simulation {
+    let (mut elements, vars) = topology_divider();
+    let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 3);
+    elements.push(Element::VSource(VSourceBundle::new(
+        Arc::from("V2"),
+        branch_2.clone(),
+        None,
+        Some(node("1", 1)),
+        2.0,
+        None,
+    )));
+    Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![],
+        elements,
+        variables: [vars, vec![branch_2]].concat(),
+    }
+}
+
+/// Asserts that the singular matrix of the simulator runs the topology check, which finds the
+/// loop of voltage sources.
+fn assert_voltage_loop_found<SO: Solver>(simulator: &mut Simulator<SO>) {
+    match simulator.run() {
+        Err(SimulatorError::InvalidTopology {
+            cause: Some(_),
+            findings,
+        }) => {
+            assert!(matches!(
+                findings.as_slice(),
+                [TopologyError::VoltageLoop { .. }]
+            ));
+        }
+        Err(err) => panic!("Unexpected error: {err}"),
+        Ok(_) => panic!("A loop of voltage sources must not be solvable"),
+    }
+}
+
+#[test]
+fn test_singular_matrix_runs_topology_check() {
+    let mut simulator: Simulator<NalgebraSolver> = Simulator::from(voltage_loop_simulation());
+    assert_voltage_loop_found(&mut simulator);
+}
+
+#[test]
+fn test_singular_matrix_with_default_solver() {
+    let mut simulator: Simulator<FaerSparseSolver> = Simulator::from(voltage_loop_simulation());
+    assert_voltage_loop_found(&mut simulator);
+
+    let mut simulator: Simulator<FaerSparseSolver> = Simulator::from(floating_subnet_simulation());
+    assert_floating_subnet_found(&mut simulator);
+}
+
+#[test]
+fn test_singular_matrix_with_krylov_solver() {
+    // The iteration converges for the floating nodes, but must not return a solution
+    let mut simulator: Simulator<KrylovSolver> = Simulator::from(floating_subnet_simulation());
+    assert_floating_subnet_found(&mut simulator);
+}
+
+/// Returns the divider with two resistors in parallel between two floating nodes, which leave no
+/// empty row in the matrix.
+fn floating_subnet_simulation() -> Simulation {
+    let (mut elements, mut vars) = topology_divider();
+    vars.extend([node("3", 3), node("4", 4)]);
+    for name in ["R3", "R4"] {
+        elements.push(Element::Resistor(ResistorBundle::new(
+            Arc::from(name),
+            Some(node("3", 3)),
+            Some(node("4", 4)),
+            1.0,
+        )));
+    }
+    Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![],
+        elements,
+        variables: vars,
+    }
+}
+
+/// Asserts that the singular matrix of the simulator runs the topology check, which finds the
+/// floating nodes without a DC path to ground.
+fn assert_floating_subnet_found<SO: Solver>(simulator: &mut Simulator<SO>) {
+    match simulator.run() {
+        Err(SimulatorError::InvalidTopology {
+            cause: Some(_),
+            findings,
+        }) => {
+            assert!(matches!(
+                findings.as_slice(),
+                [TopologyError::NoDcPath { .. }]
+            ));
+        }
+        Err(err) => panic!("Unexpected error: {err}"),
+        Ok(_) => panic!("Floating nodes must not be solvable"),
+    }
+}
+
+#[test]
+fn test_singular_matrix_with_auto_solver() {
+    // Every fallback finds the matrix singular, the last one reports it
+    let mut simulator: Simulator<AutoSolver> = Simulator::from(voltage_loop_simulation());
+    assert_voltage_loop_found(&mut simulator);
+    assert_eq!(simulator.solver.kind(), Solvers::FaerSparse);
+}
+
+#[test]
+fn test_singular_matrix_reports_pivot_variable() {
+    // A negative resistor cancels the conductance of node 2, the topology is fine
+    let (mut elements, vars) = topology_divider();
+    elements.push(Element::Resistor(ResistorBundle::new(
+        Arc::from("R3"),
+        Some(node("2", 2)),
+        None,
+        -0.5,
+    )));
+    let sim = Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![],
+        elements,
+        variables: vars,
+    };
+
+    let mut simulator: Simulator<NalgebraSolver> = Simulator::from(sim);
+    match simulator.run() {
+        Err(SimulatorError::SingularPivot {
+            variable,
+            devices,
+            cause,
+        }) => {
+            assert_eq!(variable.as_ref(), "2");
+            assert_eq!(devices.as_deref(), Some("Connected devices: R1, R2, R3"));
+            assert_eq!(cause, SolverError::MatrixNonInvertible { pivot: Some(2) });
+        }
+        Err(err) => panic!("Unexpected error: {err}"),
+        Ok(_) => panic!("The matrix must be singular"),
+    }
+}
+
+/// A solver counting the solves with a new factorisation and the solves reusing it.
+struct CountingSolver {
+    solver: NalgebraSolver,
+    factorisations: usize,
+    reuses: usize,
+}
+
+impl Solver for CountingSolver {
+    fn new(vars: usize) -> Result<Self, SolverError> {
+        Ok(Self {
+            solver: NalgebraSolver::new(vars)?,
+            factorisations: 0,
+            reuses: 0,
+        })
+    }
+
+    fn init(&mut self, a_matrix: Vec<(usize, usize)>, cplx_a_matrix: Vec<(usize, usize)>) {
+        self.solver.init(a_matrix, cplx_a_matrix);
+    }
+
+    fn insert_a(&mut self, a_trpl: &(usize, usize, Numeric)) {
+        self.solver.insert_a(a_trpl);
+    }
+
+    fn insert_b(&mut self, b_pair: &(usize, Numeric)) {
+        self.solver.insert_b(b_pair);
+    }
+
+    fn a_slot(&mut self, row: usize, col: usize) -> usize {
+        self.solver.a_slot(row, col)
+    }
+
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric) {
+        self.solver.insert_a_slot(slot, value);
+    }
+
+    fn store_base(&mut self) {
+        self.solver.store_base();
+    }
+
+    fn load_base(&mut self) {
+        self.solver.load_base();
+    }
+
+    fn insert_cplx_a(&mut self, a_trpl: &(usize, usize, ComplexNumeric)) {
+        self.solver.insert_cplx_a(a_trpl);
+    }
+
+    fn insert_cplx_b(&mut self, b_pair: &(usize, ComplexNumeric)) {
+        self.solver.insert_cplx_b(b_pair);
+    }
+
+    fn refactor(&mut self) -> Result<(), SolverError> {
+        self.solver.refactor()
+    }
+
+    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        self.factorisations += 1;
+        self.solver.solve()
+    }
+
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        self.reuses += 1;
+        self.solver.solve_factored()
+    }
+
+    fn solve_cplx(&mut self) -> Result<&Vec<ComplexNumeric>, SolverError> {
+        self.solver.solve_cplx()
+    }
+
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        self.solver.system()
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        self.solver.cplx_system()
+    }
+
+    fn reset(&mut self) {
+        self.solver.reset();
+    }
+}
+
+#[test]
+fn test_tran_linear_reuses_factorisation() {
+    // Eight backward Euler steps of h = τ/8, all of them exactly representable
+    let mut sim = rc_discharge_simulation(
+        vec![SimulationOption::IntegrationMethod(
+            IntegrationMethod::BackwardEuler,
+        )],
+        Some(1.0),
+        true,
+    );
+    let tstep = 1e-3 / 8.0;
+    sim.commands = vec![SimulationCommand::Tran(tstep, 1e-3, 0.0, None, true)];
+
+    let mut simulator: Simulator<CountingSolver> = Simulator::from(sim);
+    let results = match simulator.run().unwrap().results.remove(0) {
+        Sim::Tran(results) => results,
+        _ => panic!("Expected transient results"),
+    };
+
+    // One factorisation for the timestep, the following steps only solve for a new RHS
+    assert_eq!(simulator.solver.factorisations, 1);
+    assert_eq!(simulator.solver.reuses, 7);
+
+    let (_, last) = results.last().unwrap();
+    let expected = Numeric::powi(8.0 / 9.0, 8);
+    assert!((last[0].1 - expected).abs() < 1e-12, "{}", last[0].1);
+}
+
+#[test]
+fn test_tran_dump_selects_one_timestep() {
+    let prefix = std::env::temp_dir().join(format!("splice-dump-test-{}", std::process::id()));
+    let prefix = prefix.to_str().unwrap();
+    let mut sim = rc_discharge_simulation(
+        vec![
+            SimulationOption::IntegrationMethod(IntegrationMethod::BackwardEuler),
+            SimulationOption::DumpAnalysis(DumpAnalysis::Tran),
+            SimulationOption::DumpTime(4e-4),
+            SimulationOption::DumpPrefix(Arc::from(prefix)),
+        ],
+        Some(1.0),
+        true,
+    );
+    let tstep = 1e-3 / 8.0;
+    sim.commands = vec![SimulationCommand::Tran(tstep, 1e-3, 0.0, None, true)];
+
+    let mut simulator: Simulator<CountingSolver> = Simulator::from(sim);
+    let results = match simulator.run().unwrap().results.remove(0) {
+        Sim::Tran(results) => results,
+        _ => panic!("Expected transient results"),
+    };
+    let a_mat = std::fs::read_to_string(format!("{prefix}-1.a.mtx")).unwrap();
+    let b_vec = std::fs::read_to_string(format!("{prefix}-1.b.mtx")).unwrap();
+    let vars = std::fs::read_to_string(format!("{prefix}-1.vars")).unwrap();
+    let second = std::path::Path::new(&format!("{prefix}-2.a.mtx")).exists();
+    for file in ["a.mtx", "b.mtx", "vars"] {
+        std::fs::remove_file(format!("{prefix}-1.{file}")).unwrap();
+    }
+
+    // Only the first timestep at or after the dump time, t = 4h, is written
+    assert!(!second);
+    let mut lines = a_mat.lines();
+    assert_eq!(
+        lines.next(),
+        Some("%%MatrixMarket matrix coordinate real general")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("% tran analysis at t = 5e-4 s, iteration 1")
+    );
+    assert_eq!(lines.next(), Some("1 1 1"));
+    let (_, value) = lines.next().unwrap().rsplit_once(' ').unwrap();
+    // G + C/h = 1 mS + 8 mS
+    assert!((value.parse::<Numeric>().unwrap() - 9e-3).abs() < 1e-15);
+    assert!(b_vec.starts_with("%%MatrixMarket matrix array real general\n"));
+    assert_eq!(vars, "% index name unit\n1\t1\tV\n");
+
+    // The dumped timestep does not change the solution
+    let (_, last) = results.last().unwrap();
+    let expected = Numeric::powi(8.0 / 9.0, 8);
+    assert!((last[0].1 - expected).abs() < 1e-12, "{}", last[0].1);
+}
+
+/// A common-emitter amplifier with the base biased through 430kΩ and a 1kΩ collector resistor.
+/// The AC input couples into the base through a large capacitor.
+fn common_emitter_simulation(commands: Vec<SimulationCommand>) -> Simulation {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let supply = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let base = Variable::new(Arc::from("b"), Unit::Volt, 2);
+    let collector = Variable::new(Arc::from("c"), Unit::Volt, 3);
+    let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 4);
+    let input = Variable::new(Arc::from("in"), Unit::Volt, 5);
+
+    Simulation {
+        commands,
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(supply.clone()),
+                5.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("RB"),
+                Some(supply.clone()),
+                Some(base.clone()),
+                430e3,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("RC"),
+                Some(supply.clone()),
+                Some(collector.clone()),
+                1e3,
+            )),
+            Element::Bjt(Box::new(BjtBundle::new(
+                Arc::from("Q1"),
+                Some(collector.clone()),
+                Some(base.clone()),
+                None,
+                None,
+            ))),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V2"),
+                branch_2.clone(),
+                None,
+                Some(input.clone()),
+                0.0,
+                Some(1.0),
+            )),
+            Element::Capacitor(CapacitorBundle::new(
+                Arc::from("C1"),
+                Some(input.clone()),
+                Some(base.clone()),
+                1e-3,
+            )),
+        ],
+        variables: vec![branch_1, supply, base, collector, branch_2, input],
+    }
+}
+
+#[test]
+fn test_bjt_common_emitter() {
+    let commands = vec![
+        SimulationCommand::Op,
+        SimulationCommand::Ac(1e3, 1e3, 1, ACMode::Lin),
+    ];
+    let mut simulator: Simulator<FaerSolver> = Simulator::from(common_emitter_simulation(commands));
+    let results = simulator.run().unwrap();
+
+    let Sim::Op(op) = &results.results[0] else {
+        panic!("Expected OP results");
+    };
+    let (v_base, v_collector) = (op[2].1, op[3].1);
+    let i_base = (5.0 - v_base) / 430e3;
+    let i_collector = (5.0 - v_collector) / 1e3;
+    assert!(v_base > 0.6 && v_base < 0.8, "Base voltage {v_base}V");
+    assert!(
+        (i_collector / i_base - 100.0).abs() < 1e-3,
+        "Current gain {}",
+        i_collector / i_base
+    );
+
+    // The small-signal gain of the stage is -gm * RC
+    let Sim::Ac(ac) = &results.results[1] else {
+        panic!("Expected AC results");
+    };
+    let gain = ac[0].1[3].1;
+    let expected = -i_collector / UT * 1e3;
+    assert!(
+        (gain.re - expected).abs() < 1e-3 * expected.abs(),
+        "Gain {gain} != {expected}"
+    );
+    assert!(gain.im.abs() < 1e-3 * expected.abs());
+}
+
+/// Creates a level-1 MOSFET with W/L = 10, `VTO=±0.7`, `KP=1e-4` and the channel-length
+/// modulation `lambda`.
+fn mos1(
+    name: &str,
+    polarity: MosPolarity,
+    lambda: Numeric,
+    [drain, gate, source, bulk]: [Option<Variable>; 4],
+) -> Element {
+    let mut options = Mos1Options::default().with_polarity(polarity);
+    let vto = match polarity {
+        MosPolarity::Nmos => 0.7,
+        MosPolarity::Pmos => -0.7,
+    };
+    options.set_parameter("vto", vto);
+    options.set_parameter("kp", 1e-4);
+    options.set_parameter("lambda", lambda);
+    let mut mosfet = Mos1Bundle::new(Arc::from(name), drain, gate, source, bulk, Some(options));
+    mosfet.set_geometry(10e-6, 1e-6);
+    Element::Mos1(mosfet)
+}
+
+#[test]
+fn test_mos1_common_source() {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let supply = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let drain = Variable::new(Arc::from("d"), Unit::Volt, 2);
+    let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 3);
+    let gate = Variable::new(Arc::from("g"), Unit::Volt, 4);
+
+    let simulation = Simulation {
+        commands: vec![
+            SimulationCommand::Op,
+            SimulationCommand::Ac(1e3, 1e3, 1, ACMode::Lin),
+        ],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(supply.clone()),
+                5.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("RD"),
+                Some(supply.clone()),
+                Some(drain.clone()),
+                10e3,
+            )),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V2"),
+                branch_2.clone(),
+                None,
+                Some(gate.clone()),
+                1.5,
+                Some(1.0),
+            )),
+            mos1(
+                "M1",
+                MosPolarity::Nmos,
+                0.02,
+                [Some(drain.clone()), Some(gate.clone()), None, None],
+            ),
+        ],
+        variables: vec![branch_1, supply, drain, branch_2, gate],
+    };
+    let mut simulator: Simulator<FaerSolver> = Simulator::from(simulation);
+    let results = simulator.run().unwrap();
+
+    // The drain current of the saturated transistor flows through RD
+    let Sim::Op(op) = &results.results[0] else {
+        panic!("Expected OP results");
+    };
+    let v_drain = op[2].1;
+    let beta = 1e-4 * 10.0;
+    let v_gst: Numeric = 1.5 - 0.7;
+    let i_drain = 0.5 * beta * v_gst.powi(2) * (1.0 + 0.02 * v_drain);
+    assert!(v_drain > v_gst, "Drain voltage {v_drain}V");
+    assert!(((5.0 - v_drain) / 10e3 - i_drain).abs() < 1e-9);
+
+    // The small-signal gain is -gm * (RD || 1 / gds)
+    let Sim::Ac(ac) = &results.results[1] else {
+        panic!("Expected AC results");
+    };
+    let gain = ac[0].1[2].1;
+    let gm = beta * v_gst * (1.0 + 0.02 * v_drain);
+    let gds = 0.5 * beta * v_gst.powi(2) * 0.02;
+    let expected = -gm / (1.0 / 10e3 + gds);
+    assert!(
+        (gain.re - expected).abs() < 1e-6 * expected.abs(),
+        "Gain {gain} != {expected}"
+    );
+}
+
+#[test]
+fn test_mos1_cmos_inverter() {
+    for (input, output) in [(0.0, 5.0), (5.0, 0.0)] {
+        let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+        let supply = Variable::new(Arc::from("1"), Unit::Volt, 1);
+        let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 2);
+        let gate = Variable::new(Arc::from("in"), Unit::Volt, 3);
+        let drain = Variable::new(Arc::from("out"), Unit::Volt, 4);
+
+        let simulation = Simulation {
+            commands: vec![SimulationCommand::Op],
+            options: vec![],
+            elements: vec![
+                Element::VSource(VSourceBundle::new(
+                    Arc::from("V1"),
+                    branch_1.clone(),
+                    None,
+                    Some(supply.clone()),
+                    5.0,
+                    None,
+                )),
+                Element::VSource(VSourceBundle::new(
+                    Arc::from("V2"),
+                    branch_2.clone(),
+                    None,
+                    Some(gate.clone()),
+                    input,
+                    None,
+                )),
+                mos1(
+                    "MN",
+                    MosPolarity::Nmos,
+                    0.0,
+                    [Some(drain.clone()), Some(gate.clone()), None, None],
+                ),
+                mos1(
+                    "MP",
+                    MosPolarity::Pmos,
+                    0.0,
+                    [
+                        Some(drain.clone()),
+                        Some(gate.clone()),
+                        Some(supply.clone()),
+                        Some(supply.clone()),
+                    ],
+                ),
+            ],
+            variables: vec![branch_1, supply, branch_2, gate, drain],
+        };
+        let mut simulator: Simulator<FaerSolver> = Simulator::from(simulation);
+        let results = simulator.run().unwrap();
+
+        let Sim::Op(op) = &results.results[0] else {
+            panic!("Expected OP results");
+        };
+        assert!(
+            (op[4].1 - output).abs() < 1e-6,
+            "Output {}V at the input {input}V",
+            op[4].1
+        );
+    }
+}
+
+#[test]
+fn test_mos1_gate_ramp_without_current_spike() {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let drain = node("d", 1);
+    let branch_2 = Variable::new(Arc::from("B1#branch"), Unit::Ampere, 2);
+    let gate = node("g", 3);
+    let variables = vec![
+        branch_1.clone(),
+        drain.clone(),
+        branch_2.clone(),
+        gate.clone(),
+    ];
+
+    let Element::Mos1(mut mosfet) = mos1(
+        "M1",
+        MosPolarity::Nmos,
+        0.0,
+        [Some(drain.clone()), Some(gate.clone()), None, None],
+    ) else {
+        unreachable!()
+    };
+    let mut options = mosfet.options().clone();
+    options.set_parameter("tox", 1e-8);
+    mosfet.set_options(options);
+
+    // The gate ramps up at 3V/us through the threshold at 0.7V and the boundary between
+    // saturation and the linear region at 1.7V
+    let slope = 3e6;
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Tran(1e-8, 1e-6, 0.0, Some(1e-8), false)],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1,
+                None,
+                Some(drain),
+                1.0,
+                None,
+            )),
+            behavioral(
+                "B1",
+                &gate,
+                BehavioralOutput::Voltage(Some(branch_2)),
+                "3e6 * time",
+                &variables,
+            ),
+            Element::Mos1(mosfet),
+        ],
+        variables,
+    };
+    let results = run_tran_results(simulation);
+
+    // The gate current never exceeds the oxide capacitance times the slope of the ramp
+    let cox = 3.9 * 8.854_187_817e-12 / 1e-8 * 10e-6 * 1e-6;
+    for (t, values) in &results {
+        let i_gate = values[2].1.abs();
+        assert!(
+            i_gate < 1.05 * cox * slope,
+            "Gate current {i_gate}A at {t}s"
+        );
+    }
+}
+
+#[test]
+fn test_diode_zener_regulator() {
+    let branch = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let supply = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let output = Variable::new(Arc::from("out"), Unit::Volt, 2);
+
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch.clone(),
+                None,
+                Some(supply.clone()),
+                10.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(supply.clone()),
+                Some(output.clone()),
+                1e3,
+            )),
+            Element::Diode(DiodeBundle::new(
+                Arc::from("DZ"),
+                None,
+                Some(output.clone()),
+                Some(DiodeOptions::default().with_breakdown(5.1, 1e-3)),
+            )),
+        ],
+        variables: vec![branch, supply, output],
+    };
+    let mut simulator: Simulator<FaerSolver> = Simulator::from(simulation);
+    let results = simulator.run().unwrap();
+
+    // The breakdown current through R1 sets the output to bv + UT * ln(i / ibv), within the
+    // Newton tolerance of reltol
+    let Sim::Op(op) = &results.results[0] else {
+        panic!("Expected OP results");
+    };
+    let v_out = op[2].1;
+    let current = (10.0 - v_out) / 1e3;
+    let expected = 5.1 + UT * (current / 1e-3).ln();
+    assert!(
+        (v_out - expected).abs() < 1e-3 * expected,
+        "Output {v_out}V != {expected}V"
+    );
+}
+
+#[test]
+fn test_diode_series_resistance() {
+    let branch = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let anode = Variable::new(Arc::from("a"), Unit::Volt, 1);
+    let internal = Variable::new(Arc::from("D1#anode"), Unit::Volt, 2);
+
+    let mut diode = DiodeBundle::new(
+        Arc::from("D1"),
+        Some(anode.clone()),
+        None,
+        Some(DiodeOptions::default().with_series_resistance(100.0)),
+    );
+    diode.set_internal_node(Some(internal.clone()));
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch.clone(),
+                None,
+                Some(anode.clone()),
+                1.0,
+                None,
+            )),
+            Element::Diode(diode),
+        ],
+        variables: vec![branch, anode, internal],
+    };
+    let mut simulator: Simulator<FaerSolver> = Simulator::from(simulation);
+    let results = simulator.run().unwrap();
+
+    // The current through RS flows through the junction behind it, within the Newton tolerance
+    let Sim::Op(op) = &results.results[0] else {
+        panic!("Expected OP results");
+    };
+    let v_junction = op[2].1;
+    let current = (1.0 - v_junction) / 100.0;
+    let expected = UT * (current / 1e-14 + 1.0).ln();
+    assert!(
+        (v_junction - expected).abs() < 1e-3,
+        "Junction {v_junction}V != {expected}V"
+    );
+}
+
+#[test]
+fn test_diode_varactor_ac() {
+    let branch = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let input = Variable::new(Arc::from("in"), Unit::Volt, 1);
+    let output = Variable::new(Arc::from("out"), Unit::Volt, 2);
+    // Reverse biased by 3V the junction capacitance halves to 0.5nF
+    let corner = 1.0 / (2.0 * std::f64::consts::PI * 1e3 * 0.5e-9);
+
+    let simulation = Simulation {
+        commands: vec![
+            SimulationCommand::Op,
+            SimulationCommand::Ac(corner, corner, 1, ACMode::Lin),
+        ],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch.clone(),
+                None,
+                Some(input.clone()),
+                3.0,
+                Some(1.0),
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(input.clone()),
+                Some(output.clone()),
+                1e3,
+            )),
+            Element::Diode(DiodeBundle::new(
+                Arc::from("D1"),
+                None,
+                Some(output.clone()),
+                Some(DiodeOptions::default().with_junction_capacitance(1e-9, 1.0, 0.5)),
+            )),
+        ],
+        variables: vec![branch, input, output],
+    };
+    let mut simulator: Simulator<FaerSolver> = Simulator::from(simulation);
+    let results = simulator.run().unwrap();
+
+    // The RC low pass falls to 1/sqrt(2) at its corner frequency
+    let Sim::Ac(ac) = &results.results[1] else {
+        panic!("Expected AC results");
+    };
+    let gain = ac[0].1[2].1;
+    assert!(
+        (gain.norm() - 0.5_f64.sqrt()).abs() < 1e-6,
+        "Gain {gain} at {corner}Hz"
+    );
+}
+
+#[test]
+fn test_jfet_common_source() {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let supply = Variable::new(Arc::from("1"), Unit::Volt, 1);
+    let drain = Variable::new(Arc::from("d"), Unit::Volt, 2);
+    let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 3);
+    let gate = Variable::new(Arc::from("g"), Unit::Volt, 4);
+
+    let mut options = JfetOptions::default();
+    options.set_parameter("vto", -2.0);
+    options.set_parameter("beta", 1e-3);
+    options.set_parameter("lambda", 0.01);
+    let simulation = Simulation {
+        commands: vec![
+            SimulationCommand::Op,
+            SimulationCommand::Ac(1e3, 1e3, 1, ACMode::Lin),
+        ],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(supply.clone()),
+                10.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("RD"),
+                Some(supply.clone()),
+                Some(drain.clone()),
+                2e3,
+            )),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V2"),
+                branch_2.clone(),
+                None,
+                Some(gate.clone()),
+                -1.0,
+                Some(1.0),
+            )),
+            Element::Jfet(JfetBundle::new(
+                Arc::from("J1"),
+                Some(drain.clone()),
+                Some(gate.clone()),
+                None,
+                Some(options),
+            )),
+        ],
+        variables: vec![branch_1, supply, drain, branch_2, gate],
+    };
+    let mut simulator: Simulator<FaerSolver> = Simulator::from(simulation);
+    let results = simulator.run().unwrap();
+
+    // The drain current of the saturated transistor flows through RD
+    let Sim::Op(op) = &results.results[0] else {
+        panic!("Expected OP results");
+    };
+    let v_drain = op[2].1;
+    let v_gst: Numeric = -1.0 + 2.0;
+    let i_drain = 1e-3 * v_gst.powi(2) * (1.0 + 0.01 * v_drain);
+    assert!(v_drain > v_gst, "Drain voltage {v_drain}V");
+    assert!(((10.0 - v_drain) / 2e3 - i_drain).abs() < 1e-9);
+
+    // The small-signal gain is -gm * (RD || 1 / gds)
+    let Sim::Ac(ac) = &results.results[1] else {
+        panic!("Expected AC results");
+    };
+    let gain = ac[0].1[2].1;
+    let gm = 2.0 * 1e-3 * v_gst * (1.0 + 0.01 * v_drain);
+    let gds = 0.01 * 1e-3 * v_gst.powi(2);
+    let expected = -gm / (1.0 / 2e3 + gds);
+    assert!(
+        (gain.re - expected).abs() < 1e-6 * expected.abs(),
+        "Gain {gain} != {expected}"
+    );
+    assert!(gain.im.abs() < 1e-6 * expected.abs());
+}
+
+#[test]
+fn test_cswitch_dc_sweep_hysteresis() {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let input = Variable::new(Arc::from("in"), Unit::Volt, 1);
+    let sense = Variable::new(Arc::from("s"), Unit::Volt, 2);
+    let branch_2 = Variable::new(Arc::from("VSENSE#branch"), Unit::Ampere, 3);
+    let output = Variable::new(Arc::from("out"), Unit::Volt, 4);
+
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Dc(Arc::from("V1"), 0.0, 5.0, 0.25, None)],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(input.clone()),
+                0.0,
+                None,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(input.clone()),
+                Some(sense.clone()),
+                1e3,
+            )),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("VSENSE"),
+                branch_2.clone(),
+                None,
+                Some(sense.clone()),
+                0.0,
+                None,
+            )),
+            Element::Switch(SwitchBundle::new(
+                Arc::from("W1"),
+                Some(input.clone()),
+                Some(output.clone()),
+                SwitchControl::Current(Some(branch_2.clone())),
+                Some(SwitchOptions::default().with_threshold(2.6e-3, 0.5e-3)),
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R2"),
+                Some(output.clone()),
+                None,
+                1e3,
+            )),
+        ],
+        variables: vec![branch_1, input, sense, branch_2, output],
+    };
+    assert!(check_topology(&simulation.elements, &simulation.variables).is_empty());
+    let mut simulator: Simulator<FaerSolver> = Simulator::from(simulation);
+    let results = simulator.run().unwrap();
+
+    // Rising from off, the switch turns on above IT + IH = 3.1mA instead of at 2.6mA
+    let Sim::Dc(dc) = &results.results[0] else {
+        panic!("Expected DC results");
+    };
+    for point in dc {
+        let (v_in, v_out) = (point[1].1, point[4].1);
+        let expected = if v_in > 3.1 {
+            v_in * 1e3 / (1e3 + 1.0)
+        } else {
+            v_in * 1e3 / (1e3 + 1e12)
+        };
+        assert!(
+            (v_out - expected).abs() < 1e-9,
+            "Output {v_out}V != {expected}V at {v_in}V"
+        );
+    }
+}
+
+#[test]
+fn test_switch_tran_hysteresis() {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let control = Variable::new(Arc::from("c"), Unit::Volt, 1);
+    let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 2);
+    let supply = Variable::new(Arc::from("1"), Unit::Volt, 3);
+    let output = Variable::new(Arc::from("out"), Unit::Volt, 4);
+
+    let options = SwitchOptions::default()
+        .with_resistances(1.0, 1e6)
+        .with_threshold(2.5, 0.5);
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Tran(1e-3, 1.0, 0.0, None, false)],
+        options: vec![],
+        elements: vec![
+            Element::VSourceSin(VSourceSinBundle::new(
+                Arc::from("V1"),
+                branch_1.clone(),
+                None,
+                Some(control.clone()),
+                2.5,
+                1.5,
+                1.0,
+                0.0,
+                None,
+            )),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V2"),
+                branch_2.clone(),
+                None,
+                Some(supply.clone()),
+                5.0,
+                None,
+            )),
+            Element::Switch(SwitchBundle::new(
+                Arc::from("S1"),
+                Some(supply.clone()),
+                Some(output.clone()),
+                SwitchControl::Voltage(Some(control.clone()), None),
+                Some(options),
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("RL"),
+                Some(output.clone()),
+                None,
+                1e3,
+            )),
+        ],
+        variables: vec![branch_1, control, branch_2, supply, output],
+    };
+    let results = run_tran_results(simulation);
+
+    // The switch turns on once the control rises above 3V and off once it falls below 2V,
+    // which is at sin(2 pi t) = ±1/3
+    let t_on = (1.0_f64 / 3.0).asin() / (2.0 * std::f64::consts::PI);
+    let t_off = 0.5 + t_on;
+    let mut transitions = 0;
+    let mut was_on = false;
+    for (t, values) in &results {
+        let v_out = values[4].1;
+        let on = v_out > 2.5;
+        if (t - t_on).abs() > 2e-3 && (t - t_off).abs() > 2e-3 {
+            assert_eq!(on, *t > t_on && *t < t_off, "Output {v_out}V at {t}s");
+        }
+        if on {
+            assert!((v_out - 5.0 * 1e3 / (1e3 + 1.0)).abs() < 1e-6);
+        }
+        transitions += usize::from(on != was_on);
+        was_on = on;
+    }
+    assert_eq!(transitions, 2);
+}
+
+#[test]
+fn test_switch_tran_event_location() {
+    let branch_1 = Variable::new(Arc::from("B1#branch"), Unit::Ampere, 0);
+    let control = node("c", 1);
+    let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 2);
+    let supply = node("1", 3);
+    let output = node("out", 4);
+    let cap = node("cap", 5);
+    let variables = vec![
+        branch_1.clone(),
+        control.clone(),
+        branch_2.clone(),
+        supply.clone(),
+        output.clone(),
+        cap.clone(),
+    ];
+
+    // The ramp crosses VT + VH = 3V between two steps of the coarse timestep
+    let slope = 1100.0;
+    let options = SwitchOptions::default()
+        .with_resistances(1.0, 1e12)
+        .with_threshold(2.5, 0.5);
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Tran(1e-4, 5e-3, 0.0, None, true)],
+        options: vec![SimulationOption::IntegrationMethod(
+            IntegrationMethod::Trapezoidal,
+        )],
+        elements: vec![
+            behavioral(
+                "B1",
+                &control,
+                BehavioralOutput::Voltage(Some(branch_1)),
+                "1100 * time",
+                &variables,
+            ),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V2"),
+                branch_2,
+                None,
+                Some(supply.clone()),
+                5.0,
+                None,
+            )),
+            Element::Switch(SwitchBundle::new(
+                Arc::from("S1"),
+                Some(supply),
+                Some(output.clone()),
+                SwitchControl::Voltage(Some(control), None),
+                Some(options),
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(output),
+                Some(cap.clone()),
+                9999.0,
+            )),
+            Element::Capacitor(CapacitorBundle::new(Arc::from("C1"), Some(cap), None, 1e-6)),
+        ],
+        variables,
+    };
+    let results = run_tran_results(simulation);
+
+    // The capacitor charges from the switching instant on, which is recovered from its voltage
+    let t_on = 3.0 / slope;
+    let tau = 1e4 * 1e-6;
+    for (t, values) in results.iter().filter(|(t, _)| *t > t_on + 5e-4) {
+        let v_cap = values[5].1;
+        let t_switch = t + tau * (1.0 - v_cap / 5.0).ln();
+        assert!(
+            (t_switch - t_on).abs() < 2e-6,
+            "Switched at {t_switch}s instead of {t_on}s"
+        );
+    }
+}
+
+/// Creates the behavioral source `name`, whose expression `text` refers to the nodes and
+/// branches in `variables`.
+fn behavioral(
+    name: &str,
+    positive: &Variable,
+    output: BehavioralOutput,
+    text: &str,
+    variables: &[Variable],
+) -> Element {
+    let expression = parse_expression_str(text).unwrap();
+    bound_source(name, positive, output, expression, variables)
+}
+
+/// Creates a behavioral source from the parsed `expression`.
+fn bound_source(
+    name: &str,
+    positive: &Variable,
+    output: BehavioralOutput,
+    expression: Expression,
+    variables: &[Variable],
+) -> Element {
+    let mut expression = expression.define(&[]).unwrap();
+    let inputs = expression
+        .bind()
+        .unwrap()
+        .into_iter()
+        .map(|(input, _)| variables.iter().find(|var| var.name() == input).cloned())
+        .collect();
+    Element::Behavioral(BehavioralBundle::new(
+        Arc::from(name),
+        Some(positive.clone()),
+        None,
+        output,
+        expression,
+        inputs,
+    ))
+}
+
+#[test]
+fn test_behavioral_op() {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let branch_2 = Variable::new(Arc::from("V2#branch"), Unit::Ampere, 1);
+    let branch_3 = Variable::new(Arc::from("B1#branch"), Unit::Ampere, 2);
+    let variables = vec![
+        branch_1.clone(),
+        branch_2.clone(),
+        branch_3.clone(),
+        node("a", 3),
+        node("b", 4),
+        node("out", 5),
+        node("sq", 6),
+    ];
+
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![SimulationOption::Temperature(50.0)],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1,
+                None,
+                Some(node("a", 3)),
+                2.0,
+                None,
+            )),
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V2"),
+                branch_2,
+                None,
+                Some(node("b", 4)),
+                3.0,
+                None,
+            )),
+            // A multiplier and a square law current, which flows into its load
+            behavioral(
+                "B1",
+                &node("out", 5),
+                BehavioralOutput::Voltage(Some(branch_3)),
+                "V(a) * V(b) + temp / 100",
+                &variables,
+            ),
+            behavioral(
+                "B2",
+                &node("sq", 6),
+                BehavioralOutput::Current,
+                "-limit(V(a) ** 2 / 1000, 0, 1) - I(V1)",
+                &variables,
+            ),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(node("out", 5)),
+                None,
+                1e3,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R2"),
+                Some(node("sq", 6)),
+                None,
+                1e3,
+            )),
+        ],
+        variables,
+    };
+    let mut simulator: Simulator<NalgebraSolver> = Simulator::from(simulation);
+    simulator.init_solver().unwrap();
+    let op = simulator.find_op().unwrap();
+
+    assert!((op[5].1 - 6.5).abs() < 1e-9, "V(out) = {}", op[5].1);
+    // The source delivers the current into its load
+    assert!((op[2].1 + 6.5e-3).abs() < 1e-12, "I(B1) = {}", op[2].1);
+    assert!((op[6].1 - 4.0).abs() < 1e-9, "V(sq) = {}", op[6].1);
+}
+
+#[test]
+fn test_behavioral_tran_time() {
+    let branch = Variable::new(Arc::from("B1#branch"), Unit::Ampere, 0);
+    let output = node("out", 1);
+    let variables = vec![branch.clone(), output.clone()];
+
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Tran(1e-2, 1.0, 0.0, None, false)],
+        options: vec![],
+        elements: vec![
+            behavioral(
+                "B1",
+                &output,
+                BehavioralOutput::Voltage(Some(branch)),
+                "{2 * sin(2 * pi * time) * if(time < 0.5, 1, V(out) / 2 + 1)}",
+                &variables,
+            ),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(output),
+                None,
+                1e3,
+            )),
+        ],
+        variables,
+    };
+    let results = run_tran_results(simulation);
+
+    assert_eq!(results.len(), 101);
+    for (t, values) in &results {
+        let source = 2.0 * (2.0 * std::f64::consts::PI * t).sin();
+        // In the second half the output solves v = source * (v / 2 + 1)
+        let expected = if *t < 0.5 {
+            source
+        } else {
+            source / (1.0 - source / 2.0)
+        };
+        assert!(
+            (values[1].1 - expected).abs() < 1e-6,
+            "V(out) = {} != {expected} at {t}s",
+            values[1].1
+        );
+    }
+}
+
+#[test]
+fn test_controlled_nonlinear_op() {
+    let branch_1 = Variable::new(Arc::from("V1#branch"), Unit::Ampere, 0);
+    let branch_2 = Variable::new(Arc::from("E1#branch"), Unit::Ampere, 1);
+    let variables = vec![
+        branch_1.clone(),
+        branch_2.clone(),
+        node("in", 2),
+        node("out", 3),
+        node("out2", 4),
+    ];
+
+    let simulation = Simulation {
+        commands: vec![SimulationCommand::Op],
+        options: vec![],
+        elements: vec![
+            Element::VSource(VSourceBundle::new(
+                Arc::from("V1"),
+                branch_1,
+                None,
+                Some(node("in", 2)),
+                0.5,
+                None,
+            )),
+            // An amplifier with a gain of 10, which saturates at 10 V
+            bound_source(
+                "E1",
+                &node("out", 3),
+                BehavioralOutput::Voltage(Some(branch_2)),
+                table(voltage_control("in", "0"), &[(-1.0, -10.0), (1.0, 10.0)]).unwrap(),
+                &variables,
+            ),
+            // 1e-3 V(in)² + 1e-4 V(in) V(out), which flows out of its positive node
+            bound_source(
+                "G1",
+                &node("out2", 4),
+                BehavioralOutput::Current,
+                polynomial(
+                    &[voltage_control("in", "0"), voltage_control("out", "0")],
+                    &[0.0, 0.0, 0.0, 1e-3, 1e-4],
+                ),
+                &variables,
+            ),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R1"),
+                Some(node("out", 3)),
+                None,
+                1e3,
+            )),
+            Element::Resistor(ResistorBundle::new(
+                Arc::from("R2"),
+                Some(node("out2", 4)),
+                None,
+                1e3,
+            )),
+        ],
+        variables,
+    };
+    let mut simulator: Simulator<NalgebraSolver> = Simulator::from(simulation);
+    simulator.init_solver().unwrap();
+    let op = simulator.find_op().unwrap();
+
+    assert!((op[3].1 - 5.0).abs() < 1e-9, "V(out) = {}", op[3].1);
+    assert!((op[4].1 + 0.5).abs() < 1e-9, "V(out2) = {}", op[4].1);
+}
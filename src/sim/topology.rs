@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::frontends::Simulation;
+use crate::models::{BranchKind, Element, Unit, Variable};
+use crate::sim::SimulatorError;
+use crate::solver::{Solver, SolverError};
+use crate::Simulator;
+
+/// The name of the ground node in the findings.
+const GROUND: &str = "0";
+
+/// A problem in the topology of the circuit, which leaves the circuit equations singular.
+#[derive(Debug, Clone, Error, Diagnostic)]
+pub(crate) enum TopologyError {
+    #[error("Node {node} is not connected to any element")]
+    #[diagnostic(help("Connect the node or remove it from the circuit"))]
+    UnconnectedNode { node: Arc<str> },
+
+    #[error("Node {node} is only connected to {element}")]
+    #[diagnostic(help("Every node needs at least two connections, check the nodes of {element}"))]
+    SingleConnection { node: Arc<str>, element: Arc<str> },
+
+    #[error("Nodes {} have no DC path to ground", .nodes.join(", "))]
+    #[diagnostic(help(
+        "Connect the nodes to ground, for example through a resistor. Connected elements: {}",
+        .elements.join(", ")
+    ))]
+    NoDcPath {
+        nodes: Vec<Arc<str>>,
+        elements: Vec<Arc<str>>,
+    },
+
+    #[error("Voltage sources and inductors {} form a loop", .elements.join(", "))]
+    #[diagnostic(help(
+        "Add a series resistance to the loop through the nodes {}",
+        .nodes.join(", ")
+    ))]
+    VoltageLoop {
+        nodes: Vec<Arc<str>>,
+        elements: Vec<Arc<str>>,
+    },
+
+    #[error(
+        "Current sources and capacitors {} cut off nodes {} from ground",
+        .elements.join(", "),
+        .nodes.join(", ")
+    )]
+    #[diagnostic(help("Add a resistance from one of the nodes to ground"))]
+    CurrentCutSet {
+        nodes: Vec<Arc<str>>,
+        elements: Vec<Arc<str>>,
+    },
+
+    #[error("{element} has no controlling branch")]
+    #[diagnostic(help("Current controlled sources need the name of a voltage source"))]
+    NoControllingBranch { element: Arc<str> },
+
+    #[error("{element} is controlled by the current of {branch}, which is not a voltage source")]
+    #[diagnostic(help("Current controlled sources sense the current through a voltage source"))]
+    MissingControllingBranch { element: Arc<str>, branch: Arc<str> },
+}
+
+/// Runs the topology check on the circuit of a simulation.
+pub(crate) fn check_simulation(simulation: &Simulation) -> Result<(), SimulatorError> {
+    let findings = check_topology(&simulation.elements, &simulation.variables);
+    if findings.is_empty() {
+        Ok(())
+    } else {
+        Err(SimulatorError::InvalidTopology {
+            cause: None,
+            findings,
+        })
+    }
+}
+
+impl<SO: Solver> Simulator<SO> {
+    /// Runs the topology check after the solver failed on a singular matrix.
+    /// Returns the findings of the check. If the topology looks fine, returns the variable of the
+    /// singular pivot, or else the solver error.
+    pub(super) fn diagnose_singular_matrix(&self, error: SolverError) -> SimulatorError {
+        let findings = check_topology(&self.elements, &self.vars);
+        if findings.is_empty() {
+            let pivot = match error {
+                SolverError::MatrixNonInvertible { pivot } => pivot,
+                _ => None,
+            };
+            match pivot.and_then(|pivot| self.vars.get(pivot)) {
+                Some(var) => SimulatorError::SingularPivot {
+                    variable: var.name(),
+                    devices: self.connected_devices(var.idx()),
+                    cause: error,
+                },
+                None => SimulatorError::BackendError(error),
+            }
+        } else {
+            SimulatorError::InvalidTopology {
+                cause: Some(error),
+                findings,
+            }
+        }
+    }
+}
+
+/// Checks the graph of the circuit for floating nodes, nodes without a DC path to ground, loops
+/// of voltage sources and inductors, cut-sets of current sources and capacitors and current
+/// controlled sources without a controlling voltage source.
+pub(crate) fn check_topology(elements: &[Element], vars: &[Variable]) -> Vec<TopologyError> {
+    let graph = Graph::new(elements, vars);
+    let mut findings = graph.check_connections();
+    findings.extend(graph.check_dc_paths());
+    findings.extend(graph.check_voltage_loops());
+    findings.extend(check_controlling_branches(elements));
+    findings
+}
+
+/// The circuit as a graph of nodes, which are the voltage variables plus ground.
+struct Graph<'a> {
+    elements: &'a [Element],
+    vars: &'a [Variable],
+    /// The nodes of the circuit, without the controlling branches of current controlled sources
+    /// and the internal nodes of the elements
+    nodes: Vec<usize>,
+    /// The element indices connected to each node
+    connections: Vec<Vec<usize>>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(elements: &'a [Element], vars: &'a [Variable]) -> Self {
+        let controlling: HashSet<usize> = elements
+            .iter()
+            .filter_map(|element| element.controlling_branch().flatten())
+            .map(Variable::idx)
+            .collect();
+        let internal: HashSet<usize> = elements.iter().flat_map(Element::internal_nodes).collect();
+        let nodes = vars
+            .iter()
+            .filter(|var| var.unit() == Unit::Volt)
+            .filter(|var| !controlling.contains(&var.idx()) && !internal.contains(&var.idx()))
+            .map(Variable::idx)
+            .collect();
+
+        let mut connections = vec![Vec::new(); vars.len() + 1];
+        for (element_idx, element) in elements.iter().enumerate() {
+            for branch in element.branches() {
+                for node in [branch.nodes.0, branch.nodes.1] {
+                    let node = node.unwrap_or(vars.len());
+                    if !connections[node].contains(&element_idx) {
+                        connections[node].push(element_idx);
+                    }
+                }
+            }
+        }
+
+        Self {
+            elements,
+            vars,
+            nodes,
+            connections,
+        }
+    }
+
+    /// The index of the ground node.
+    fn ground(&self) -> usize {
+        self.vars.len()
+    }
+
+    fn node_name(&self, node: usize) -> Arc<str> {
+        self.vars
+            .get(node)
+            .map_or_else(|| Arc::from(GROUND), Variable::name)
+    }
+
+    fn element_names(&self, element_indices: impl IntoIterator<Item = usize>) -> Vec<Arc<str>> {
+        element_indices
+            .into_iter()
+            .unique()
+            .map(|idx| self.elements[idx].name())
+            .collect()
+    }
+
+    /// Returns the edges between the nodes formed by the branches of the given kinds.
+    fn edges(&self, kinds: &[BranchKind]) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        let kinds = kinds.to_vec();
+        let ground = self.ground();
+        self.elements
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, element)| element.branches().into_iter().map(move |b| (idx, b)))
+            .filter(move |(_, branch)| kinds.contains(&branch.kind))
+            .map(move |(idx, branch)| {
+                let (node0, node1) = branch.nodes;
+                (node0.unwrap_or(ground), node1.unwrap_or(ground), idx)
+            })
+    }
+
+    /// Finds nodes without any or with a single connection.
+    fn check_connections(&self) -> Vec<TopologyError> {
+        self.nodes
+            .iter()
+            .filter_map(|&node| match self.connections[node].as_slice() {
+                [] => Some(TopologyError::UnconnectedNode {
+                    node: self.node_name(node),
+                }),
+                [element] => Some(TopologyError::SingleConnection {
+                    node: self.node_name(node),
+                    element: self.elements[*element].name(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Finds groups of nodes, which are not connected to ground by conducting branches or
+    /// voltage sources. Groups cut off by current sources or capacitors are reported as cut-sets.
+    fn check_dc_paths(&self) -> Vec<TopologyError> {
+        let mut sets = DisjointSet::new(self.vars.len() + 1);
+        for (node0, node1, _) in self.edges(&[BranchKind::Conductive, BranchKind::Voltage]) {
+            sets.union(node0, node1);
+        }
+
+        let ground = sets.find(self.ground());
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &node in &self.nodes {
+            let root = sets.find(node);
+            if root != ground && !self.connections[node].is_empty() {
+                groups.entry(root).or_default().push(node);
+            }
+        }
+
+        groups
+            .into_values()
+            .map(|group| {
+                let nodes = group.iter().map(|&node| self.node_name(node)).collect();
+                let cut_set: Vec<_> = self
+                    .edges(&[BranchKind::Current])
+                    .filter(|(node0, node1, _)| group.contains(node0) != group.contains(node1))
+                    .map(|(_, _, idx)| idx)
+                    .collect();
+
+                if cut_set.is_empty() {
+                    let connected = group
+                        .iter()
+                        .flat_map(|&node| self.connections[node].iter().copied());
+                    TopologyError::NoDcPath {
+                        nodes,
+                        elements: self.element_names(connected),
+                    }
+                } else {
+                    TopologyError::CurrentCutSet {
+                        nodes,
+                        elements: self.element_names(cut_set),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Finds loops of voltage sources and inductors. Each branch closing a loop is reported
+    /// together with the path of voltage branches between its nodes.
+    fn check_voltage_loops(&self) -> Vec<TopologyError> {
+        let mut sets = DisjointSet::new(self.vars.len() + 1);
+        let mut tree: Vec<Vec<(usize, usize)>> = vec![Vec::new(); self.vars.len() + 1];
+        let mut findings = Vec::new();
+
+        for (node0, node1, idx) in self.edges(&[BranchKind::Voltage]) {
+            if sets.union(node0, node1) {
+                tree[node0].push((node1, idx));
+                tree[node1].push((node0, idx));
+                continue;
+            }
+
+            let path = tree_path(&tree, node0, node1);
+            let mut nodes = vec![self.node_name(node0)];
+            nodes.extend(path.iter().map(|&(node, _)| self.node_name(node)));
+            nodes.dedup();
+            let elements = path.iter().map(|&(_, element)| element).chain([idx]);
+            findings.push(TopologyError::VoltageLoop {
+                nodes,
+                elements: self.element_names(elements),
+            });
+        }
+
+        findings
+    }
+}
+
+/// Finds the current controlled sources, whose controlling branch is not a voltage source.
+fn check_controlling_branches(elements: &[Element]) -> Vec<TopologyError> {
+    elements
+        .iter()
+        .filter_map(|element| match element.controlling_branch()? {
+            None => Some(TopologyError::NoControllingBranch {
+                element: element.name(),
+            }),
+            Some(branch) => {
+                let name = branch.name();
+                let source = name.trim_end_matches("#branch");
+                let found = elements.iter().any(|other| {
+                    other.is_voltage_source() && other.name().eq_ignore_ascii_case(source)
+                });
+                (!found).then(|| TopologyError::MissingControllingBranch {
+                    element: element.name(),
+                    branch: Arc::from(source),
+                })
+            }
+        })
+        .collect()
+}
+
+/// Returns the path from `start` to `end` in the spanning forest `tree` as pairs of the next
+/// node and the element leading to it.
+fn tree_path(tree: &[Vec<(usize, usize)>], start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut previous: Vec<Option<(usize, usize)>> = vec![None; tree.len()];
+    let mut queue = VecDeque::from([start]);
+    let mut visited = vec![false; tree.len()];
+    visited[start] = true;
+
+    while let Some(node) = queue.pop_front() {
+        if node == end {
+            break;
+        }
+        for &(next, element) in &tree[node] {
+            if !visited[next] {
+                visited[next] = true;
+                previous[next] = Some((node, element));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut node = end;
+    while let Some((prev, element)) = previous[node] {
+        path.push((node, element));
+        node = prev;
+    }
+    path.reverse();
+    path
+}
+
+/// Union-find over the node indices.
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, mut idx: usize) -> usize {
+        while self.parent[idx] != idx {
+            self.parent[idx] = self.parent[self.parent[idx]];
+            idx = self.parent[idx];
+        }
+        idx
+    }
+
+    /// Merges the sets of `a` and `b`. Returns false if they already were in the same set.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+        self.parent[root_a] = root_b;
+        true
+    }
+}
@@ -1,114 +1,342 @@
 use log::{debug, info};
 
-use crate::models::Element;
+use crate::models::integration::{
+    self, tr_bdf2_stages, BackwardEuler, Bdf2, Trapezoidal, TR_BDF2_GAMMA,
+};
+use crate::models::Variable;
 use crate::sim::options::IntegrationMethod;
 use crate::sim::simulation_result::Sim;
 use crate::sim::SimulatorError;
-use crate::solver::Solver;
+use crate::solver::{Solver, SolverError};
 use crate::spot::*;
 use crate::Simulator;
 use num::Zero;
 
 pub(super) trait TranSimulation<SO: Solver> {
-    fn run_tran(&mut self, tstep: &Numeric, tstop: &Numeric) -> Result<Sim, SimulatorError>;
+    fn run_tran(
+        &mut self,
+        tstep: &Numeric,
+        tstop: &Numeric,
+        tstart: &Numeric,
+        tmax: &Option<Numeric>,
+        uic: bool,
+    ) -> Result<Sim, SimulatorError>;
 }
 
 impl<SO: Solver> TranSimulation<SO> for Simulator<SO> {
-    fn run_tran(&mut self, tstep: &Numeric, tstop: &Numeric) -> Result<Sim, SimulatorError> {
+    fn run_tran(
+        &mut self,
+        tstep: &Numeric,
+        tstop: &Numeric,
+        tstart: &Numeric,
+        tmax: &Option<Numeric>,
+        uic: bool,
+    ) -> Result<Sim, SimulatorError> {
         info!("Run transient analysis");
 
         let mut t = Numeric::zero();
         let mut tran_results = Vec::new();
 
-        let mut x_prev: Vec<Numeric> = self.find_op()?.iter().map(|op| op.1).collect();
-
-        // Initialize capacitor voltages and inductor currents for transient analysis
-        for element in &mut self.elements {
-            if let Element::Capacitor(cap) = element {
-                cap.update_previous_voltage(Numeric::zero());
-            } else if let Element::Inductor(ind) = element {
-                ind.update_previous_current(Numeric::zero());
+        // With UIC the operating point is skipped and the simulation starts from the `.ic` values
+        let initial_conditions = self.initial_conditions()?;
+        let mut x_prev: Vec<Numeric> = if uic {
+            let mut x = vec![Numeric::zero(); self.vars.len()];
+            for &(idx, value) in &initial_conditions {
+                x[idx] = value;
             }
-        }
+            x
+        } else {
+            // The operating point is the solution at t = 0
+            self.dump.set_time(Numeric::zero());
+            self.solve_op(&initial_conditions)?
+                .iter()
+                .map(|op| op.1)
+                .collect()
+        };
 
-        let mut x_current = x_prev.clone();
+        self.init_reactive_states(&x_prev, uic);
+        // The operating point left another factorisation in the solver
+        self.step.clear_factored();
 
-        // Store the initial condition (t=0)
-        tran_results.push((Numeric::zero(), self.add_var_name(x_current.clone())));
+        // The output grid is fixed by tstep, the internal timestep is independent of it
+        let mut output = OutputGrid::new(*tstep, *tstop, *tstart);
+        output.push(&mut tran_results, self, t, &x_prev, t, &x_prev);
 
         // Use adaptive timestep if the provided tstep is very small (indication for adaptive mode)
-        let use_adaptive = *tstep <= ADAPTIVE_INITIAL_TIMESTEP;
+        let timestep = &self.settings.timestep;
+        let use_adaptive = *tstep <= timestep.initial;
+        let max_timestep = match tmax {
+            Some(tmax) => *tmax,
+            None if use_adaptive => timestep.max,
+            None => *tstep,
+        };
         let mut current_timestep = if use_adaptive {
-            ADAPTIVE_INITIAL_TIMESTEP
+            timestep.initial.min(max_timestep)
         } else {
-            *tstep
+            tstep.min(max_timestep)
         };
 
+        let method = self.settings.method.clone();
+        info!("Integration method: {:?}", method);
+        let mut previous_delta_t = Numeric::zero();
+        let event_tolerance = EVENT_TOLERANCE * tstop;
+        let has_switches = self.has_switches();
+
         // Transient simulation time loop
-        // TODO: Consider refactoring to reduce nesting complexity
         while t < *tstop {
-            // For subsequent time steps, use the previous solution as initial guess
-            x_current = x_prev.clone();
-
-            // Newton-Raphson iteration within each time step
-            let mut converged = false;
-            let mut x_new_final = x_prev.clone();
-
-            for _ in 0..MAXITER {
-                self.solver.reset();
-                self.build_constant_a_mat();
-                self.build_constant_b_vec();
-                self.build_time_variant_a_mat(&current_timestep);
-
-                // Use trapezoidal integration if specified
-                let integration_method = self.get_integration_method();
-                match integration_method {
-                    IntegrationMethod::BackwardEuler => {
-                        self.build_time_variant_b_vec(&t, &current_timestep);
-                    }
-                    IntegrationMethod::Trapezoidal => {
-                        self.build_time_variant_b_vec_trapezoidal(&t, &current_timestep);
+            // Never step past the stop time
+            let mut delta_t = current_timestep.min(*tstop - t);
+            // The histories before the step, restored when a switching event rejects the step
+            let states = has_switches.then(|| self.states.clone());
+            let mut rejections = 0;
+
+            let x_new = loop {
+                let x_new =
+                    self.integrate_step(&method, &x_prev, &t, &delta_t, &previous_delta_t)?;
+                // A switching event within the step rejects it, unless the step brackets the
+                // crossing of the control closely. The step is repeated up to just before the
+                // crossing, interpolated linearly over the step, and the following step ends
+                // just past it. The output is thus never interpolated across the event.
+                let crossing = self
+                    .switch_crossing(&x_prev, &x_new)
+                    .map(|fraction| fraction * delta_t);
+                match (&states, crossing) {
+                    (Some(states), Some(crossing))
+                        if (crossing > event_tolerance || delta_t > crossing + event_tolerance)
+                            && rejections < EVENT_MAX_REJECTIONS =>
+                    {
+                        self.states.clone_from(states);
+                        delta_t = if crossing > event_tolerance {
+                            crossing - 0.5 * event_tolerance
+                        } else {
+                            crossing + 0.5 * event_tolerance
+                        };
+                        rejections += 1;
+                        debug!("Switching event near t = {}", t + crossing);
                     }
+                    _ => break x_new,
                 }
+            };
+            let t_next = t + delta_t;
 
-                self.build_nonlinear_a_mat(&x_current);
-                self.build_nonlinear_b_vec(&x_current);
-
-                let x_new = self.solver.solve()?.clone();
-
-                if self.has_converged(&x_current, &x_new, VECTOL) {
-                    x_new_final = x_new.clone();
-                    converged = true;
-                    break;
-                }
+            output.push(&mut tran_results, self, t, &x_prev, t_next, &x_new);
 
-                x_current = x_new;
+            // A switching event is a breakpoint: the integration restarts with backward Euler,
+            // as the trapezoidal rule rings at the discontinuity, and the adaptive timestep
+            // restarts from its initial value
+            let breakpoint = self.accept_solution(&x_new);
+            if breakpoint {
+                debug!("Switching event at t = {}", t_next);
             }
 
-            if !converged {
-                return Err(SimulatorError::NonConvergentMaxIter {
-                    max_iter: MAXITER,
-                    tol: VECTOL,
-                });
+            // Adaptive timestep control
+            if use_adaptive {
+                current_timestep = if breakpoint {
+                    self.settings.timestep.initial.min(max_timestep)
+                } else {
+                    self.adjust_timestep(&x_prev, &x_new, current_timestep)
+                        .min(max_timestep)
+                };
+                debug!("Adaptive timestep: {} at t = {}", current_timestep, t_next);
             }
 
-            // Store results
-            tran_results.push((t, self.add_var_name(x_new_final.clone())));
-            x_prev = x_new_final.clone();
+            x_prev = x_new;
+            previous_delta_t = if breakpoint { Numeric::zero() } else { delta_t };
+            t = t_next;
+        }
 
-            // Update capacitor voltages and inductor currents for next time step
-            self.update_capacitor_voltages(&x_new_final);
-            self.update_inductor_currents(&x_new_final, &current_timestep);
+        Ok(Sim::Tran(tran_results))
+    }
+}
 
-            // Adaptive timestep control
-            if use_adaptive {
-                current_timestep = self.adjust_timestep(&x_prev, &x_new_final, current_timestep);
-                debug!("Adaptive timestep: {} at t = {}", current_timestep, t);
+impl<SO: Solver> Simulator<SO> {
+    /// Advances the solution `x_prev` at time `t` by `delta_t` with the given integration method.
+    /// The histories of the reactive elements are updated with the accepted solution.
+    fn integrate_step(
+        &mut self,
+        method: &IntegrationMethod,
+        x_prev: &[Numeric],
+        t: &Numeric,
+        delta_t: &Numeric,
+        previous_delta_t: &Numeric,
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        let t_next = t + delta_t;
+        // Without a previous step there is no derivative history for the trapezoidal rule,
+        // so the first step is taken with backward Euler
+        let first_step = *previous_delta_t <= Numeric::zero();
+        match method {
+            IntegrationMethod::BackwardEuler => {
+                self.integrate(x_prev, &t_next, &BackwardEuler::new(*delta_t))
+            }
+            IntegrationMethod::Trapezoidal if first_step => {
+                self.integrate(x_prev, &t_next, &BackwardEuler::new(*delta_t))
+            }
+            IntegrationMethod::Trapezoidal => {
+                self.integrate(x_prev, &t_next, &Trapezoidal::new(*delta_t))
+            }
+            IntegrationMethod::Gear => {
+                self.integrate(x_prev, &t_next, &Bdf2::new(*delta_t, *previous_delta_t))
             }
+            IntegrationMethod::TrBdf2 => {
+                let (trapezoidal, bdf2) = tr_bdf2_stages(*delta_t);
+                let t_stage = t + TR_BDF2_GAMMA * delta_t;
+                let x_stage = if first_step {
+                    let backward_euler = BackwardEuler::new(TR_BDF2_GAMMA * delta_t);
+                    self.integrate(x_prev, &t_stage, &backward_euler)?
+                } else {
+                    self.integrate(x_prev, &t_stage, &trapezoidal)?
+                };
+                self.integrate(&x_stage, &t_next, &bdf2)
+            }
+        }
+    }
+
+    /// Solves a single step of the integrator ending at time `t` and accepts the solution.
+    fn integrate(
+        &mut self,
+        x_prev: &[Numeric],
+        t: &Numeric,
+        integrator: &dyn integration::IntegrationMethod,
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        let x_new = self.solve_timestep(x_prev, t, integrator)?;
+        self.update_reactive_states(&x_new, integrator);
+        Ok(x_new)
+    }
+
+    /// Solves the circuit at time `t`. The stamps of the timestep are evaluated once, linear
+    /// circuits are solved directly and nonlinear circuits with the Newton-Raphson iteration,
+    /// using the previous solution as initial guess.
+    fn solve_timestep(
+        &mut self,
+        x_prev: &[Numeric],
+        t: &Numeric,
+        integrator: &dyn integration::IntegrationMethod,
+    ) -> Result<Vec<Numeric>, SimulatorError> {
+        for element in &mut self.elements {
+            element.set_time(*t);
+        }
+        self.build_step_stamps(x_prev, t, integrator);
+        self.dump.set_time(*t);
+        if !self.has_nonlinear_elements() {
+            return self.solve_linear_timestep().map_err(|err| err.at_time(*t));
+        }
+
+        self.newton_raphson(x_prev, self.settings.itl4, |sim, x_current| {
+            sim.step.stamp(&mut sim.solver);
+            sim.build_charge_mats(x_current, integrator);
+        })
+        .map_err(|err| err.at_time(*t))
+    }
 
-            t += current_timestep;
+    /// Solves a timestep of a linear circuit with a single solve. The factorisation is reused
+    /// as long as the timestep and the integration method leave the matrix unchanged. A dumped
+    /// timestep assembles the whole matrix again.
+    fn solve_linear_timestep(&mut self) -> Result<Vec<Numeric>, SimulatorError> {
+        let x_new = if self.step.is_factored() && !self.is_dump_point(1) {
+            self.step.stamp_b(&mut self.solver);
+            self.solver.solve_factored()?.clone()
+        } else {
+            self.solver.load_base();
+            self.step.stamp(&mut self.solver);
+            self.dump_system(1)?;
+            let x_new = self.solver.solve()?.clone();
+            self.step.set_factored();
+            x_new
+        };
+
+        // The solution of a nearly singular matrix may not be finite
+        if x_new.iter().any(|value| !value.is_finite()) {
+            return Err(SolverError::MatrixNonInvertible { pivot: None }.into());
         }
+        Ok(x_new)
+    }
+}
 
-        Ok(Sim::Tran(tran_results))
+/// The output time points of a transient analysis: `tstart + k * tstep` up to `tstop`.
+/// Solutions of the internal timesteps are linearly interpolated onto these points.
+struct OutputGrid {
+    tstep: Numeric,
+    tstop: Numeric,
+    tstart: Numeric,
+    next: usize,
+}
+
+impl OutputGrid {
+    fn new(tstep: Numeric, tstop: Numeric, tstart: Numeric) -> Self {
+        Self {
+            tstep,
+            tstop,
+            tstart,
+            next: 0,
+        }
+    }
+
+    /// Returns the next output time point, if it is not beyond the stop time.
+    fn next_time(&self) -> Option<Numeric> {
+        let time = self.tstart + self.next as Numeric * self.tstep;
+        // Allow for accumulated rounding errors at the stop time
+        (time <= self.tstop + self.tstep * 1e-9).then_some(time.min(self.tstop))
+    }
+
+    /// Pushes all output points within `[t0, t1]` which have not been emitted yet.
+    fn push<SO: Solver>(
+        &mut self,
+        results: &mut Vec<(Numeric, Vec<(Variable, Numeric)>)>,
+        sim: &Simulator<SO>,
+        t0: Numeric,
+        x0: &[Numeric],
+        t1: Numeric,
+        x1: &[Numeric],
+    ) {
+        let tol = self.tstep * 1e-9;
+        while let Some(time) = self.next_time() {
+            if time > t1 + tol {
+                break;
+            }
+            let x = interpolate(t0, x0, t1, x1, time);
+            results.push((time, sim.add_var_name(x)));
+            self.next += 1;
+        }
+    }
+}
+
+/// Linearly interpolates between the solutions `x0` at `t0` and `x1` at `t1`.
+fn interpolate(
+    t0: Numeric,
+    x0: &[Numeric],
+    t1: Numeric,
+    x1: &[Numeric],
+    t: Numeric,
+) -> Vec<Numeric> {
+    if t1 <= t0 {
+        return x1.to_vec();
+    }
+    let ratio = ((t - t0) / (t1 - t0)).clamp(Numeric::zero(), 1.0);
+    x0.iter()
+        .zip(x1)
+        .map(|(a, b)| a + (b - a) * ratio)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolate_midpoint() {
+        let x = interpolate(0.0, &[0.0, 2.0], 2.0, &[4.0, 0.0], 1.0);
+        assert_eq!(x, vec![2.0, 1.0]);
+    }
+
+    #[test]
+    fn interpolate_degenerate_interval() {
+        let x = interpolate(1.0, &[0.0], 1.0, &[3.0], 1.0);
+        assert_eq!(x, vec![3.0]);
+    }
+
+    #[test]
+    fn output_grid_respects_tstart() {
+        let grid = OutputGrid::new(0.5, 2.0, 1.0);
+        assert_eq!(grid.next_time(), Some(1.0));
     }
 }
@@ -0,0 +1,167 @@
+use log::warn;
+use nalgebra as na;
+use num::Zero;
+
+use crate::spot::*;
+
+/// The condition estimate of the factorisations of a solver, see [`Solver::set_condition_limit`].
+///
+/// [`Solver::set_condition_limit`]: super::Solver::set_condition_limit
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConditionMonitor {
+    /// The condition number above which a warning is logged, `None` disables the estimate.
+    limit: Option<Numeric>,
+    /// The estimate of the last factorisation.
+    estimate: Option<Numeric>,
+    /// Whether the warning was logged since the estimate last exceeded the limit.
+    warned: bool,
+}
+
+impl ConditionMonitor {
+    /// Sets the limit, `None` disables the estimate.
+    pub(crate) fn set_limit(&mut self, limit: Option<Numeric>) {
+        self.limit = limit;
+        self.estimate = None;
+        self.warned = false;
+    }
+
+    /// Returns true if the factorisations are estimated.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.limit.is_some()
+    }
+
+    /// Returns the estimate of the last factorisation.
+    pub(crate) fn estimate(&self) -> Option<Numeric> {
+        self.estimate
+    }
+
+    /// Records the estimate `‖A‖₁ ‖A⁻¹‖₁` of a factorisation. The warning is logged once until
+    /// the estimate drops below the limit again, as the Newton iterations and time steps
+    /// factorise nearly the same matrix many times.
+    pub(crate) fn record(&mut self, norm: Numeric, inverse_norm: Numeric) {
+        let Some(limit) = self.limit else {
+            return;
+        };
+        let estimate = norm * inverse_norm;
+        self.estimate = Some(estimate);
+        if estimate <= limit {
+            self.warned = false;
+            return;
+        }
+        if !self.warned {
+            warn!(
+                "The conductance matrix is ill-conditioned, its estimated condition number \
+                 {estimate:.3e} exceeds {limit:.3e}"
+            );
+            self.warned = true;
+        }
+    }
+}
+
+/// Returns the 1-norm `max_j Σ_i |a_ij|` of a `vars`×`vars` matrix given by its entries.
+pub(crate) fn one_norm(
+    vars: usize,
+    entries: impl IntoIterator<Item = (usize, usize, Numeric)>,
+) -> Numeric {
+    let mut columns = vec![Numeric::zero(); vars];
+    for (_, col, val) in entries {
+        columns[col] += val.abs();
+    }
+    columns.into_iter().fold(Numeric::zero(), Numeric::max)
+}
+
+/// Estimates `‖A⁻¹‖₁` with the method of Hager, as refined by Higham for LAPACK.
+///
+/// `solve` overwrites its argument `b` with `A⁻¹ b` and `solve_transpose` with `A⁻ᵀ b`, both
+/// with an existing factorisation of `A`. The estimate needs a few of these solves instead of
+/// the inverse and is usually within a factor of 3 of the exact norm, never above it.
+pub(crate) fn inverse_norm_estimate(
+    vars: usize,
+    mut solve: impl FnMut(&mut [Numeric]),
+    mut solve_transpose: impl FnMut(&mut [Numeric]),
+) -> Numeric {
+    if vars == 0 {
+        return Numeric::zero();
+    }
+
+    let mut x = vec![1.0 / vars as Numeric; vars];
+    let mut estimate = Numeric::zero();
+    let mut last = None;
+    for iteration in 0..CONDITION_ESTIMATE_ITERATIONS {
+        solve(&mut x);
+        let norm: Numeric = x.iter().map(|val| val.abs()).sum();
+        if !norm.is_finite() {
+            return Numeric::INFINITY;
+        }
+        if iteration > 0 && norm <= estimate {
+            break;
+        }
+        estimate = norm;
+
+        x.iter_mut()
+            .for_each(|val| *val = if *val < 0.0 { -1.0 } else { 1.0 });
+        solve_transpose(&mut x);
+        let (idx, max) = x.iter().map(|val| val.abs()).enumerate().fold(
+            (0, Numeric::zero()),
+            |best, (idx, val)| {
+                if val > best.1 {
+                    (idx, val)
+                } else {
+                    best
+                }
+            },
+        );
+        // The gradient does not point to a better unit vector than the current one
+        if last == Some(idx) || last.is_some_and(|last| max <= x[last]) {
+            break;
+        }
+        last = Some(idx);
+        x.fill(Numeric::zero());
+        x[idx] = 1.0;
+    }
+
+    // An alternating vector catches the matrices which mislead the gradient
+    let scale = (vars.max(2) - 1) as Numeric;
+    x.iter_mut().enumerate().for_each(|(idx, val)| {
+        let sign = if idx % 2 == 0 { 1.0 } else { -1.0 };
+        *val = sign * (1.0 + idx as Numeric / scale);
+    });
+    solve(&mut x);
+    let alternative = 2.0 * x.iter().map(|val| val.abs()).sum::<Numeric>() / (3 * vars) as Numeric;
+    if !alternative.is_finite() {
+        return Numeric::INFINITY;
+    }
+    estimate.max(alternative)
+}
+
+/// Returns the variable of the first vanishing pivot of a singular `vars`×`vars` matrix, given by
+/// its entries. A column or a row without a nonzero entry is reported directly. Otherwise up to
+/// [`SINGULAR_PIVOT_DENSE_MAX_VARS`] variables, the dense LU with partial pivoting finds the first
+/// column which depends on the columns before it.
+pub(crate) fn singular_pivot(
+    vars: usize,
+    entries: impl IntoIterator<Item = (usize, usize, Numeric)> + Clone,
+) -> Option<usize> {
+    let mut cols = vec![false; vars];
+    let mut rows = vec![false; vars];
+    for (row, col, val) in entries.clone() {
+        if !val.is_zero() {
+            rows[row] = true;
+            cols[col] = true;
+        }
+    }
+    if let Some(pivot) = cols.iter().chain(&rows).position(|&nonzero| !nonzero) {
+        return Some(pivot % vars);
+    }
+    if vars > SINGULAR_PIVOT_DENSE_MAX_VARS {
+        return None;
+    }
+
+    let mut mat = na::DMatrix::<Numeric>::zeros(vars, vars);
+    for (row, col, val) in entries {
+        mat[(row, col)] += val;
+    }
+    let tolerance = mat.amax() * vars as Numeric * Numeric::EPSILON;
+    let u = na::LU::new(mat).u();
+    (0..vars).find(|&idx| u[(idx, idx)].abs() <= tolerance)
+}
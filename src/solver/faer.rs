@@ -1,6 +1,8 @@
+use faer::linalg::solvers::PartialPivLu;
 use faer::prelude::*;
 use num::Zero;
 
+use super::diagnostics::{inverse_norm_estimate, ConditionMonitor};
 use super::{Solver, SolverError};
 use crate::spot::*;
 
@@ -23,6 +25,19 @@ pub struct FaerSolver {
     /// This vector stores the solution to the system of equations `Ax = b`.
     x_vec: Vec<Numeric>,
 
+    /// The LU factorisation of `A`.
+    /// It is computed by `refactor` or `solve` and kept for `solve_factored`.
+    lu: Option<PartialPivLu<Numeric>>,
+
+    /// Whether `lu` is the factorisation of the current values of `A`.
+    factored: bool,
+
+    /// The optional condition estimate of `lu`.
+    condition: ConditionMonitor,
+
+    /// The base matrix, which every assembly of `A` starts from.
+    base: Mat<Numeric>,
+
     /// The conductance matrix `A` for complex numbers.
     /// This matrix is used for AC analysis where complex numbers are required.
     cplx_a_mat: Mat<c64>,
@@ -54,6 +69,10 @@ impl Solver for FaerSolver {
             a_mat: Mat::zeros(vars, vars),
             b_vec: Mat::full(vars, 1, 0.0),
             x_vec: vec![0.0; vars],
+            lu: None,
+            factored: false,
+            condition: ConditionMonitor::default(),
+            base: Mat::zeros(vars, vars),
             cplx_a_mat: Mat::zeros(vars, vars),
             cplx_b_vec: Mat::full(vars, 1, c64 { re: 0.0, im: 0.0 }),
             cplx_x_vec: vec![num::Complex { re: 0.0, im: 0.0 }; vars],
@@ -68,6 +87,7 @@ impl Solver for FaerSolver {
         // * `a_mat` - A tuple containing the row index, column index, and value to insert.
         let (row, col, val) = *a_mat;
         self.a_mat[(row, col)] += val;
+        self.factored = false;
     }
 
     fn insert_b(&mut self, b_vec: &(usize, Numeric)) {
@@ -81,6 +101,31 @@ impl Solver for FaerSolver {
         *value += val;
     }
 
+    fn a_slot(&mut self, row: usize, col: usize) -> usize {
+        // The slot is the index of the entry in column-major order.
+        col * self.a_mat.nrows() + row
+    }
+
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric) {
+        let rows = self.a_mat.nrows();
+        self.a_mat[(slot % rows, slot / rows)] += value;
+        self.factored = false;
+    }
+
+    fn store_base(&mut self) {
+        self.base.copy_from(&self.a_mat);
+    }
+
+    fn load_base(&mut self) {
+        // Starts the assembly of `A` from the base matrix with an empty vector `b`.
+        self.a_mat.copy_from(&self.base);
+        self.b_vec
+            .row_iter_mut()
+            .flat_map(|row| row.iter_mut())
+            .for_each(|val| *val = Numeric::zero());
+        self.factored = false;
+    }
+
     fn insert_cplx_a(&mut self, a_mat: &(usize, usize, ComplexNumeric)) {
         // Inserts a complex value into the conductance matrix `A`.
         //
@@ -101,6 +146,24 @@ impl Solver for FaerSolver {
         let value = self.cplx_b_vec.get_mut(row, 0);
         *value += val;
     }
+    fn refactor(&mut self) -> Result<(), SolverError> {
+        // Computes the LU decomposition with partial pivoting of `A` for the next `solve`.
+        // The decomposition of a singular matrix succeeds with a zero pivot, whose column is
+        // reported instead of a solution which is not finite.
+        let lu = self.a_mat.partial_piv_lu();
+        let u = lu.U();
+        if let Some(pivot) = (0..u.nrows()).find(|&idx| u[(idx, idx)] == 0.0) {
+            self.lu = None;
+            return Err(SolverError::MatrixNonInvertible { pivot: Some(pivot) });
+        }
+        if self.condition.is_enabled() {
+            self.estimate_condition(&lu);
+        }
+        self.lu = Some(lu);
+        self.factored = true;
+        Ok(())
+    }
+
     fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
         // Solves the system of equations `Ax = b` for real numbers.
         //
@@ -114,17 +177,28 @@ impl Solver for FaerSolver {
         // # Errors
         //
         // Returns `SolverError::MatrixNonInvertible` if the matrix is singular and cannot be inverted.
-        let lu = self.a_mat.partial_piv_lu();
+        if !self.factored {
+            self.refactor()?;
+        }
+        self.a_mat
+            .row_iter_mut()
+            .flat_map(|row| row.iter_mut())
+            .for_each(|val| *val = Numeric::zero());
+        self.factored = false;
+
+        self.solve_factored()
+    }
+
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        // Solves the system of equations `Ax = b` with the LU decomposition of the last solve.
+        // Only the vector `b` is reset afterwards.
+        let lu = self.lu.as_ref().ok_or(SolverError::NotFactorised)?;
         let res = lu.solve(&self.b_vec);
 
         for (idx, val) in res.col_as_slice(0).iter().enumerate() {
             self.x_vec[idx] = *val;
         }
 
-        self.a_mat
-            .row_iter_mut()
-            .flat_map(|row| row.iter_mut())
-            .for_each(|val| *val = Numeric::zero());
         self.b_vec
             .row_iter_mut()
             .flat_map(|row| row.iter_mut())
@@ -165,6 +239,20 @@ impl Solver for FaerSolver {
         Ok(&self.cplx_x_vec)
     }
 
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        (
+            dense_triples(&self.a_mat),
+            self.b_vec.col_as_slice(0).to_vec(),
+        )
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        (
+            dense_triples(&self.cplx_a_mat),
+            self.cplx_b_vec.col_as_slice(0).to_vec(),
+        )
+    }
+
     fn init(&mut self, _a_matrix: Vec<(usize, usize)>, _cplx_a_matrix: Vec<(usize, usize)>) {}
 
     fn reset(&mut self) {
@@ -177,6 +265,8 @@ impl Solver for FaerSolver {
             .row_iter_mut()
             .flat_map(|row| row.iter_mut())
             .for_each(|val| *val = Numeric::zero());
+        self.lu = None;
+        self.factored = false;
         self.b_vec
             .row_iter_mut()
             .flat_map(|row| row.iter_mut())
@@ -190,6 +280,39 @@ impl Solver for FaerSolver {
             .flat_map(|row| row.iter_mut())
             .for_each(|val| *val = ComplexNumeric::zero());
     }
+
+    fn set_condition_limit(&mut self, limit: Option<Numeric>) {
+        self.condition.set_limit(limit);
+    }
+
+    fn condition_estimate(&self) -> Option<Numeric> {
+        self.condition.estimate()
+    }
+}
+
+impl FaerSolver {
+    /// Estimates the condition number of `A` with its factorisation `lu`.
+    fn estimate_condition(&mut self, lu: &PartialPivLu<Numeric>) {
+        let norm = self
+            .a_mat
+            .col_iter()
+            .map(|col| col.iter().map(|val| val.abs()).sum::<Numeric>())
+            .fold(Numeric::zero(), Numeric::max);
+        let inverse_norm = inverse_norm_estimate(
+            self.x_vec.len(),
+            |b| lu.solve_in_place(ColMut::from_slice_mut(b)),
+            |b| lu.solve_transpose_in_place(ColMut::from_slice_mut(b)),
+        );
+        self.condition.record(norm, inverse_norm);
+    }
+}
+
+/// Returns the nonzero entries of `mat` as `(row, col, value)` triples.
+fn dense_triples<T: Copy + Zero>(mat: &Mat<T>) -> Vec<(usize, usize, T)> {
+    (0..mat.ncols())
+        .flat_map(|col| (0..mat.nrows()).map(move |row| (row, col, mat[(row, col)])))
+        .filter(|(_, _, val)| !val.is_zero())
+        .collect()
 }
 
 #[cfg(test)]
@@ -1,9 +1,18 @@
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
 use faer::prelude::*;
+use faer::sparse::linalg::solvers::{Lu, SymbolicLu};
 use faer::sparse::linalg::LuError;
-use faer::sparse::SparseColMat;
-use faer::sparse::Triplet;
+use faer::sparse::{Pair, SparseColMatRef, SymbolicSparseColMat};
+use faer::traits::ComplexField;
+use log::trace;
 use num::Zero;
+use rustc_hash::FxHashMap;
 
+use super::diagnostics::{inverse_norm_estimate, one_norm, singular_pivot, ConditionMonitor};
+use super::slots::SlotMatrix;
 use super::{Solver, SolverError};
 use crate::spot::*;
 
@@ -12,9 +21,29 @@ use crate::spot::*;
 ///
 /// This solver uses sparse matrix representations for improved memory efficiency
 /// and performance on large, sparse circuit matrices typical in electronic simulations.
+///
+/// The entries of `A` are stamped through slots. The sparsity pattern of the slots is analysed
+/// once by [`Solver::analyse`], which computes the symbolic factorisation including the
+/// fill-reducing column ordering. The numeric refactorisation in every Newton iteration and
+/// time step reuses it. New entries extend the pattern and trigger a new analysis.
+///
+/// The complex matrix of the AC analysis has its own pattern, which is analysed at the first
+/// frequency and reused by the complex LU of all further frequencies.
 pub struct FaerSparseSolver {
     /// The conductance matrix `A`.
-    a_mat: Vec<Triplet<usize, usize, Numeric>>,
+    a_mat: SlotMatrix<Numeric>,
+
+    /// The analysed sparsity pattern of `A`.
+    pattern: SparsePattern<Numeric>,
+
+    /// The numeric factorisation of `A`, kept for `solve_factored`.
+    lu: Option<Lu<usize, Numeric>>,
+
+    /// Whether `lu` is the factorisation of the current values of `A`.
+    factored: bool,
+
+    /// The optional condition estimate of `lu`.
+    condition: ConditionMonitor,
 
     /// The vector `b`.
     b_vec: Mat<Numeric>,
@@ -22,8 +51,11 @@ pub struct FaerSparseSolver {
     /// The Solution vector
     x_vec: Vec<Numeric>,
 
-    /// The conductance matrix `A`.
-    cplx_a_mat: Vec<Triplet<usize, usize, ComplexNumeric>>,
+    /// The complex conductance matrix `A`.
+    cplx_a_mat: SlotMatrix<ComplexNumeric>,
+
+    /// The analysed sparsity pattern of the complex `A`.
+    cplx_pattern: SparsePattern<ComplexNumeric>,
 
     /// The vector `b`.
     cplx_b_vec: Mat<c64>,
@@ -38,10 +70,15 @@ impl Solver for FaerSparseSolver {
         Self: Sized,
     {
         Ok(FaerSparseSolver {
-            a_mat: Vec::new(),
+            a_mat: SlotMatrix::default(),
+            pattern: SparsePattern::new(vars)?,
+            lu: None,
+            factored: false,
+            condition: ConditionMonitor::default(),
             b_vec: Mat::full(vars, 1, 0.0),
             x_vec: vec![0.0; vars],
-            cplx_a_mat: Vec::new(),
+            cplx_a_mat: SlotMatrix::default(),
+            cplx_pattern: SparsePattern::new(vars)?,
             cplx_b_vec: Mat::full(vars, 1, c64 { re: 0.0, im: 0.0 }),
             cplx_x_vec: vec![num::Complex { re: 0.0, im: 0.0 }; vars],
         })
@@ -49,7 +86,17 @@ impl Solver for FaerSparseSolver {
 
     fn insert_a(&mut self, a_mat: &(usize, usize, Numeric)) {
         let (row, col, val) = *a_mat;
-        self.a_mat.push(Triplet::new(row, col, val));
+        self.a_mat.insert(row, col, val);
+        self.factored = false;
+    }
+
+    fn a_slot(&mut self, row: usize, col: usize) -> usize {
+        self.a_mat.slot(row, col)
+    }
+
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric) {
+        self.a_mat.add(slot, value);
+        self.factored = false;
     }
 
     fn insert_b(&mut self, b_vec: &(usize, Numeric)) {
@@ -60,7 +107,7 @@ impl Solver for FaerSparseSolver {
 
     fn insert_cplx_a(&mut self, a_mat: &(usize, usize, ComplexNumeric)) {
         let (row, col, val) = *a_mat;
-        self.cplx_a_mat.push(Triplet::new(row, col, val));
+        self.cplx_a_mat.insert(row, col, val);
     }
 
     fn insert_cplx_b(&mut self, b_vec: &(usize, ComplexNumeric)) {
@@ -69,23 +116,58 @@ impl Solver for FaerSparseSolver {
         *value += val;
     }
 
-    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
-        let a_mat =
-            SparseColMat::try_new_from_triplets(self.x_vec.len(), self.x_vec.len(), &self.a_mat)
-                .expect("Failed to create sparse matrix from triplets. This indicates invalid matrix dimensions or data.");
+    fn analyse(&mut self) -> Result<(), SolverError> {
+        trace!(
+            "Analyse the sparsity pattern with {} entries",
+            self.a_mat.entries().len()
+        );
+        self.pattern.analyse(self.a_mat.entries())?;
+        self.a_mat.set_analysed();
+        self.lu = None;
+        self.factored = false;
+        Ok(())
+    }
 
-        let lu = match a_mat.sp_lu() {
+    fn refactor(&mut self) -> Result<(), SolverError> {
+        if !self.pattern.is_analysed() || !self.a_mat.is_analysed() {
+            self.analyse()?;
+        }
+        let lu = match self.pattern.factor(self.a_mat.values()) {
             Ok(lu) => lu,
-            Err(_) => return Err(SolverError::MatrixNonInvertible),
+            Err(SolverError::MatrixNonInvertible { pivot: None }) => {
+                let entries = self.a_mat.entries().iter().zip(self.a_mat.values());
+                let pivot = singular_pivot(
+                    self.x_vec.len(),
+                    entries.map(|(&(row, col), &val)| (row, col, val)),
+                );
+                return Err(SolverError::MatrixNonInvertible { pivot });
+            }
+            Err(err) => return Err(err),
         };
+        if self.condition.is_enabled() {
+            self.estimate_condition(&lu);
+        }
+        self.lu = Some(lu);
+        self.factored = true;
+        Ok(())
+    }
+
+    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        if !self.factored {
+            self.refactor()?;
+        }
+        self.clear_a_mat();
+        self.solve_factored()
+    }
+
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        let lu = self.lu.as_ref().ok_or(SolverError::NotFactorised)?;
 
         let res = lu.solve(&self.b_vec);
         for (idx, val) in res.col_as_slice(0).iter().enumerate() {
             self.x_vec[idx] = *val;
         }
 
-        self.a_mat.clear();
-
         for idx in 0..self.x_vec.len() {
             let value = self.b_vec.get_mut(idx, 0);
             *value = Numeric::zero();
@@ -94,47 +176,254 @@ impl Solver for FaerSparseSolver {
     }
 
     fn solve_cplx(&mut self) -> Result<&Vec<ComplexNumeric>, SolverError> {
-        let a_mat = SparseColMat::try_new_from_triplets(
-            self.x_vec.len(),
-            self.x_vec.len(),
-            &self.cplx_a_mat,
-        )
-        .expect("Failed to create complex sparse matrix from triplets. This indicates invalid matrix dimensions or data.");
-
-        let lu = match a_mat.sp_lu() {
-            Ok(lu) => lu,
-            Err(_) => return Err(SolverError::MatrixNonInvertible),
-        };
+        if !self.cplx_pattern.is_analysed() || !self.cplx_a_mat.is_analysed() {
+            trace!(
+                "Analyse the complex sparsity pattern with {} entries",
+                self.cplx_a_mat.entries().len()
+            );
+            self.cplx_pattern.analyse(self.cplx_a_mat.entries())?;
+            self.cplx_a_mat.set_analysed();
+        }
+        let lu = self.cplx_pattern.factor(self.cplx_a_mat.values());
+        self.cplx_a_mat.clear_values();
+        let lu = lu?;
 
         let res = lu.solve(&self.cplx_b_vec);
         for (idx, val) in res.col_as_slice(0).iter().enumerate() {
             self.cplx_x_vec[idx] = *val;
         }
 
+        self.cplx_b_vec
+            .row_iter_mut()
+            .flat_map(|row| row.iter_mut())
+            .for_each(|val| *val = ComplexNumeric::zero());
         Ok(&self.cplx_x_vec)
     }
 
-    fn init(&mut self, _a_matrix: Vec<(usize, usize)>, _cplx_a_matrix: Vec<(usize, usize)>) {}
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        (self.a_mat.triples(), self.b_vec.col_as_slice(0).to_vec())
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        (
+            self.cplx_a_mat.triples(),
+            self.cplx_b_vec.col_as_slice(0).to_vec(),
+        )
+    }
+
+    fn init(&mut self, a_matrix: Vec<(usize, usize)>, cplx_a_matrix: Vec<(usize, usize)>) {
+        for (row, col) in a_matrix {
+            self.a_mat.slot(row, col);
+        }
+        // Gmin stepping, pseudo transient continuation and initial conditions stamp the diagonal
+        for idx in 0..self.x_vec.len() {
+            self.a_mat.slot(idx, idx);
+        }
+        for (row, col) in cplx_a_matrix {
+            self.cplx_a_mat.slot(row, col);
+        }
+        self.pattern.clear();
+        self.cplx_pattern.clear();
+        self.lu = None;
+        self.factored = false;
+    }
+
+    fn store_base(&mut self) {
+        self.a_mat.store_base();
+    }
+
+    fn load_base(&mut self) {
+        self.a_mat.load_base();
+        self.factored = false;
+        self.b_vec
+            .row_iter_mut()
+            .flat_map(|row| row.iter_mut())
+            .for_each(|val| *val = Numeric::zero());
+    }
 
     fn reset(&mut self) {
-        self.a_mat.clear();
+        self.clear_a_mat();
+        self.lu = None;
         self.b_vec
             .row_iter_mut()
             .flat_map(|row| row.iter_mut())
             .for_each(|val| *val = Numeric::zero());
-        self.cplx_a_mat.clear();
+        self.cplx_a_mat.clear_values();
         self.cplx_b_vec
             .row_iter_mut()
             .flat_map(|row| row.iter_mut())
             .for_each(|val| *val = ComplexNumeric::zero());
     }
+
+    fn set_condition_limit(&mut self, limit: Option<Numeric>) {
+        self.condition.set_limit(limit);
+    }
+
+    fn condition_estimate(&self) -> Option<Numeric> {
+        self.condition.estimate()
+    }
+}
+
+impl FaerSparseSolver {
+    /// Clears the values of `A`, but keeps its slots and factorisations.
+    fn clear_a_mat(&mut self) {
+        self.a_mat.clear_values();
+        self.factored = false;
+    }
+
+    /// Estimates the condition number of `A` with its factorisation `lu`.
+    fn estimate_condition(&mut self, lu: &Lu<usize, Numeric>) {
+        let entries = self.a_mat.entries().iter().zip(self.a_mat.values());
+        let norm = one_norm(
+            self.x_vec.len(),
+            entries.map(|(&(row, col), &val)| (row, col, val)),
+        );
+        let inverse_norm = inverse_norm_estimate(
+            self.x_vec.len(),
+            |b| lu.solve_in_place(ColMut::from_slice_mut(b)),
+            |b| lu.solve_transpose_in_place(ColMut::from_slice_mut(b)),
+        );
+        self.condition.record(norm, inverse_norm);
+    }
+}
+
+/// The analysed sparsity pattern of a slot matrix and its symbolic factorisation.
+struct SparsePattern<T> {
+    /// The sparsity pattern in compressed column form.
+    pattern: SymbolicSparseColMat<usize>,
+
+    /// The values of the matrix in the order of `pattern`.
+    values: Vec<T>,
+
+    /// The position of each slot in `values`.
+    positions: Vec<usize>,
+
+    /// The symbolic factorisation of `pattern`.
+    symbolic: Option<SymbolicLu<usize>>,
+}
+
+impl<T: ComplexField + Copy + Zero> SparsePattern<T> {
+    /// Creates an empty pattern of a `vars`×`vars` matrix.
+    fn new(vars: usize) -> Result<Self, SolverError> {
+        let (pattern, _) = SymbolicSparseColMat::try_new_from_indices(vars, vars, &[])
+            .map_err(|_| SolverError::MatrixNonInvertible { pivot: None })?;
+        Ok(Self {
+            pattern,
+            values: Vec::new(),
+            positions: Vec::new(),
+            symbolic: None,
+        })
+    }
+
+    /// Returns true if the symbolic factorisation is available.
+    fn is_analysed(&self) -> bool {
+        self.symbolic.is_some()
+    }
+
+    /// Drops the symbolic factorisation, so the next factorisation analyses the pattern again.
+    fn clear(&mut self) {
+        self.symbolic = None;
+    }
+
+    /// Builds the pattern of the slots `entries` and computes its symbolic factorisation.
+    fn analyse(&mut self, entries: &[(usize, usize)]) -> Result<(), SolverError> {
+        let vars = self.pattern.nrows();
+        let indices: Vec<_> = entries
+            .iter()
+            .map(|&(row, col)| Pair::new(row, col))
+            .collect();
+        let (pattern, _) = SymbolicSparseColMat::try_new_from_indices(vars, vars, &indices)
+            .map_err(|_| SolverError::MatrixNonInvertible { pivot: None })?;
+
+        let positions: FxHashMap<(usize, usize), usize> = (0..vars)
+            .flat_map(|col| pattern.col_range(col).map(move |pos| (col, pos)))
+            .map(|(col, pos)| ((pattern.row_idx()[pos], col), pos))
+            .collect();
+        self.positions = entries.iter().map(|entry| positions[entry]).collect();
+        self.values = vec![T::zero(); pattern.row_idx().len()];
+
+        let symbolic = SymbolicLu::try_new(pattern.as_ref())
+            .map_err(|_| SolverError::MatrixNonInvertible { pivot: None })?;
+        self.pattern = pattern;
+        self.symbolic = Some(symbolic);
+        Ok(())
+    }
+
+    /// Computes the numeric factorisation of the slot values `values` with the analysed pattern.
+    fn factor(&mut self, values: &[T]) -> Result<Lu<usize, T>, SolverError> {
+        let Some(symbolic) = self.symbolic.clone() else {
+            return Err(SolverError::MatrixNonInvertible { pivot: None });
+        };
+
+        for (&pos, &val) in self.positions.iter().zip(values) {
+            self.values[pos] = val;
+        }
+        // An empty row or column is reported with its index
+        if let Some(pivot) = self.zero_line() {
+            return Err(SolverError::MatrixNonInvertible { pivot: Some(pivot) });
+        }
+        let mat = SparseColMatRef::new(self.pattern.as_ref(), &self.values);
+        // The LU of faer panics on an exactly zero pivot of a numerically singular matrix
+        catch_zero_pivot(|| Lu::try_new_with_symbolic(symbolic, mat))
+            .ok_or(SolverError::MatrixNonInvertible { pivot: None })?
+            .map_err(SolverError::from)
+    }
+}
+
+thread_local! {
+    /// Set while a factorisation of this thread runs, whose panic is caught.
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+static SILENT_HOOK: Once = Once::new();
+
+/// Runs the factorisation `factor` and returns `None` if it panics. The panic is not reported,
+/// as it only signals a zero pivot. Panics of other threads are reported as before.
+fn catch_zero_pivot<R>(factor: impl FnOnce() -> R) -> Option<R> {
+    SILENT_HOOK.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(Cell::get) {
+                hook(info);
+            }
+        }));
+    });
+    CATCHING.with(|catching| catching.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(factor));
+    CATCHING.with(|catching| catching.set(false));
+    result.ok()
+}
+
+impl<T: Zero> SparsePattern<T> {
+    /// Returns the index of the first column, or else row, of the values without a nonzero
+    /// entry.
+    fn zero_line(&self) -> Option<usize> {
+        let vars = self.pattern.nrows();
+        let mut rows = vec![false; vars];
+        for col in 0..vars {
+            let mut nonzero = false;
+            for pos in self.pattern.col_range(col) {
+                if !self.values[pos].is_zero() {
+                    rows[self.pattern.row_idx()[pos]] = true;
+                    nonzero = true;
+                }
+            }
+            if !nonzero {
+                return Some(col);
+            }
+        }
+        rows.iter().position(|&row| !row)
+    }
 }
 
 impl From<LuError> for SolverError {
     fn from(value: LuError) -> Self {
         match value {
-            LuError::Generic(_) => SolverError::MatrixNonInvertible,
-            LuError::SymbolicSingular { index: _ } => SolverError::MatrixNonInvertible,
+            LuError::Generic(_) => SolverError::MatrixNonInvertible { pivot: None },
+            // The index counts the steps of the permuted factorisation, not the variables
+            LuError::SymbolicSingular { index: _ } => {
+                SolverError::MatrixNonInvertible { pivot: None }
+            }
         }
     }
 }
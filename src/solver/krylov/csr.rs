@@ -0,0 +1,97 @@
+use num::Zero;
+
+use crate::spot::*;
+
+/// A square sparse matrix in compressed row form with sorted columns in every row.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CsrMatrix {
+    /// The start of each row in `cols` and `values`, followed by the number of entries.
+    row_ptr: Vec<usize>,
+    /// The column of each entry.
+    cols: Vec<usize>,
+    /// The value of each entry.
+    values: Vec<Numeric>,
+}
+
+impl CsrMatrix {
+    /// Builds the pattern of a `vars`×`vars` matrix with the `entries` and the full diagonal.
+    /// Returns the matrix with zero values and the position of each entry in its values.
+    pub(crate) fn from_entries(vars: usize, entries: &[(usize, usize)]) -> (Self, Vec<usize>) {
+        let mut pattern: Vec<(usize, usize)> = entries
+            .iter()
+            .copied()
+            .chain((0..vars).map(|idx| (idx, idx)))
+            .collect();
+        pattern.sort_unstable();
+        pattern.dedup();
+
+        let mut row_ptr = vec![0; vars + 1];
+        for &(row, _) in &pattern {
+            row_ptr[row + 1] += 1;
+        }
+        for row in 0..vars {
+            row_ptr[row + 1] += row_ptr[row];
+        }
+
+        let cols: Vec<usize> = pattern.iter().map(|&(_, col)| col).collect();
+        let positions = entries
+            .iter()
+            .map(|entry| {
+                pattern
+                    .binary_search(entry)
+                    .expect("Every entry is part of the pattern")
+            })
+            .collect();
+
+        let matrix = Self {
+            row_ptr,
+            values: vec![Numeric::zero(); cols.len()],
+            cols,
+        };
+        (matrix, positions)
+    }
+
+    /// Returns the number of rows.
+    pub(crate) fn vars(&self) -> usize {
+        self.row_ptr.len().saturating_sub(1)
+    }
+
+    /// Returns the columns and values of `row`.
+    pub(crate) fn row(&self, row: usize) -> (&[usize], &[Numeric]) {
+        let range = self.row_ptr[row]..self.row_ptr[row + 1];
+        (&self.cols[range.clone()], &self.values[range])
+    }
+
+    /// Returns the row and column of each entry in the order of the values.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (usize, usize, Numeric)> + '_ {
+        (0..self.vars()).flat_map(move |row| {
+            let (cols, values) = self.row(row);
+            cols.iter()
+                .zip(values)
+                .map(move |(&col, &value)| (row, col, value))
+        })
+    }
+
+    /// Returns the values for assembly.
+    pub(crate) fn values_mut(&mut self) -> &mut [Numeric] {
+        &mut self.values
+    }
+
+    /// Computes `y = A x`.
+    pub(crate) fn mul_vec(&self, x: &[Numeric], y: &mut [Numeric]) {
+        for (row, y_row) in y.iter_mut().enumerate() {
+            let (cols, values) = self.row(row);
+            *y_row = cols
+                .iter()
+                .zip(values)
+                .map(|(&col, &value)| value * x[col])
+                .sum();
+        }
+    }
+
+    /// Computes the residual `r = b - A x`.
+    pub(crate) fn residual(&self, b: &[Numeric], x: &[Numeric], r: &mut [Numeric]) {
+        self.mul_vec(x, r);
+        r.iter_mut().zip(b).for_each(|(r, b)| *r = b - *r);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeSet;
+
+use num::Zero;
+
+use super::csr::CsrMatrix;
+use crate::solver::SolverError;
+use crate::spot::*;
+
+/// Pivots smaller than this fraction of the largest entry of their row are replaced.
+const PIVOT_THRESHOLD: Numeric = 1e-14;
+
+/// An incomplete LU factorisation `A ≈ L U` with a unit lower triangular `L`.
+///
+/// Every row holds the entries of `L` left of the diagonal, the diagonal of `U` and the entries
+/// of `U` right of the diagonal, each with sorted columns.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Ilu {
+    /// The start of each row in `cols` and `values`, followed by the number of entries.
+    row_ptr: Vec<usize>,
+    /// The column of each entry.
+    cols: Vec<usize>,
+    /// The value of each entry.
+    values: Vec<Numeric>,
+    /// The position of the diagonal of each row.
+    diag: Vec<usize>,
+    /// The first row whose pivot cancelled, although the diagonal of `A` is not negligible,
+    /// which happens for singular matrices, e.g. of floating subcircuits.
+    cancelled: Option<usize>,
+}
+
+impl Ilu {
+    /// Computes the ILU(0) factorisation, which keeps the sparsity pattern of `a`.
+    pub(crate) fn ilu0(a: &CsrMatrix) -> Result<Self, SolverError> {
+        let vars = a.vars();
+        let mut ilu = Self {
+            row_ptr: vec![0],
+            ..Self::default()
+        };
+        for row in 0..vars {
+            let (cols, values) = a.row(row);
+            ilu.cols.extend_from_slice(cols);
+            ilu.values.extend_from_slice(values);
+            ilu.row_ptr.push(ilu.cols.len());
+            let diag = cols
+                .binary_search(&row)
+                .expect("The diagonal is part of the pattern");
+            ilu.diag.push(ilu.row_ptr[row] + diag);
+        }
+
+        // The position of each column in the current row
+        let mut positions = vec![usize::MAX; vars];
+        for row in 0..vars {
+            let (start, end) = (ilu.row_ptr[row], ilu.row_ptr[row + 1]);
+            let row_max = max_abs(row, &ilu.values[start..end])?;
+            let diag = ilu.values[ilu.diag[row]];
+            for pos in start..end {
+                positions[ilu.cols[pos]] = pos;
+            }
+
+            for pos in start..ilu.diag[row] {
+                let k = ilu.cols[pos];
+                let factor = ilu.values[pos] / ilu.values[ilu.diag[k]];
+                ilu.values[pos] = factor;
+                for upper in ilu.diag[k] + 1..ilu.row_ptr[k + 1] {
+                    let col_pos = positions[ilu.cols[upper]];
+                    if col_pos != usize::MAX {
+                        ilu.values[col_pos] -= factor * ilu.values[upper];
+                    }
+                }
+            }
+            ilu.values[ilu.diag[row]] = ilu.pivot(row, diag, ilu.values[ilu.diag[row]], row_max);
+
+            for pos in start..end {
+                positions[ilu.cols[pos]] = usize::MAX;
+            }
+        }
+        Ok(ilu)
+    }
+
+    /// Computes the ILUT factorisation, which drops the entries smaller than `drop_tol` relative
+    /// to the norm of their row of `a` and keeps at most `fill` entries of `L` and `U` per row.
+    pub(crate) fn ilut(a: &CsrMatrix, drop_tol: Numeric, fill: usize) -> Result<Self, SolverError> {
+        let vars = a.vars();
+        let mut ilu = Self {
+            row_ptr: vec![0],
+            ..Self::default()
+        };
+
+        let mut work = vec![Numeric::zero(); vars];
+        let mut in_row = vec![false; vars];
+        let mut nonzeros = Vec::new();
+        let mut lower = BTreeSet::new();
+        for row in 0..vars {
+            let (cols, values) = a.row(row);
+            let row_max = max_abs(row, values)?;
+            let tol = drop_tol * values.iter().map(|val| val * val).sum::<Numeric>().sqrt();
+            for (&col, &value) in cols.iter().zip(values) {
+                work[col] = value;
+                in_row[col] = true;
+                nonzeros.push(col);
+                if col < row {
+                    lower.insert(col);
+                }
+            }
+            let diag = work[row];
+
+            while let Some(k) = lower.pop_first() {
+                let factor = work[k] / ilu.values[ilu.diag[k]];
+                if factor.abs() < tol {
+                    work[k] = Numeric::zero();
+                    continue;
+                }
+                work[k] = factor;
+                for upper in ilu.diag[k] + 1..ilu.row_ptr[k + 1] {
+                    let col = ilu.cols[upper];
+                    if !in_row[col] {
+                        in_row[col] = true;
+                        work[col] = Numeric::zero();
+                        nonzeros.push(col);
+                        if col < row {
+                            lower.insert(col);
+                        }
+                    }
+                    work[col] -= factor * ilu.values[upper];
+                }
+            }
+
+            let mut l_row: Vec<(usize, Numeric)> = nonzeros
+                .iter()
+                .filter(|&&col| col < row && !work[col].is_zero())
+                .map(|&col| (col, work[col]))
+                .collect();
+            let mut u_row: Vec<(usize, Numeric)> = nonzeros
+                .iter()
+                .filter(|&&col| col > row && work[col].abs() >= tol)
+                .map(|&col| (col, work[col]))
+                .collect();
+            keep_largest(&mut l_row, fill);
+            keep_largest(&mut u_row, fill);
+
+            for (col, value) in l_row {
+                ilu.cols.push(col);
+                ilu.values.push(value);
+            }
+            ilu.diag.push(ilu.cols.len());
+            ilu.cols.push(row);
+            let pivot = ilu.pivot(row, diag, work[row], row_max);
+            ilu.values.push(pivot);
+            for (col, value) in u_row {
+                ilu.cols.push(col);
+                ilu.values.push(value);
+            }
+            ilu.row_ptr.push(ilu.cols.len());
+
+            for &col in &nonzeros {
+                work[col] = Numeric::zero();
+                in_row[col] = false;
+            }
+            nonzeros.clear();
+        }
+        Ok(ilu)
+    }
+
+    /// Returns the first row whose pivot cancelled, in which case `A` may be singular.
+    pub(crate) fn cancelled(&self) -> Option<usize> {
+        self.cancelled
+    }
+
+    /// Replaces a vanishing pivot, e.g. of the branch rows of voltage sources, by the largest
+    /// entry of its row. The preconditioner then only approximates `A`, which the iteration
+    /// corrects. A pivot, which only vanishes by the elimination of a non-negligible `diag`, is
+    /// recorded as cancelled.
+    fn pivot(&mut self, row: usize, diag: Numeric, value: Numeric, row_max: Numeric) -> Numeric {
+        if value.abs() > PIVOT_THRESHOLD * row_max {
+            return value;
+        }
+        if diag.abs() > PIVOT_THRESHOLD * row_max {
+            self.cancelled.get_or_insert(row);
+        }
+        row_max
+    }
+
+    /// Returns the number of entries of `L` and `U`.
+    pub(crate) fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Solves `L U z = r`.
+    pub(crate) fn solve(&self, r: &[Numeric], z: &mut [Numeric]) {
+        let vars = self.diag.len();
+        for row in 0..vars {
+            let lower = self.row_ptr[row]..self.diag[row];
+            z[row] = r[row]
+                - self.cols[lower.clone()]
+                    .iter()
+                    .zip(&self.values[lower])
+                    .map(|(&col, &value)| value * z[col])
+                    .sum::<Numeric>();
+        }
+        for row in (0..vars).rev() {
+            let upper = self.diag[row] + 1..self.row_ptr[row + 1];
+            let sum: Numeric = self.cols[upper.clone()]
+                .iter()
+                .zip(&self.values[upper])
+                .map(|(&col, &value)| value * z[col])
+                .sum();
+            z[row] = (z[row] - sum) / self.values[self.diag[row]];
+        }
+    }
+}
+
+/// Returns the largest absolute value of `row` of `A`, which must have a finite nonzero entry.
+fn max_abs(row: usize, values: &[Numeric]) -> Result<Numeric, SolverError> {
+    let row_max = values
+        .iter()
+        .fold(Numeric::zero(), |max, val| max.max(val.abs()));
+    if row_max.is_zero() || !values.iter().all(|val| val.is_finite()) {
+        return Err(SolverError::MatrixNonInvertible { pivot: Some(row) });
+    }
+    Ok(row_max)
+}
+
+/// Keeps the `fill` entries with the largest magnitude, sorted by column.
+fn keep_largest(entries: &mut Vec<(usize, Numeric)>, fill: usize) {
+    if entries.len() > fill {
+        entries.select_nth_unstable_by(fill, |(_, a), (_, b)| b.abs().total_cmp(&a.abs()));
+        entries.truncate(fill);
+    }
+    entries.sort_unstable_by_key(|&(col, _)| col);
+}
@@ -0,0 +1,213 @@
+use num::{One, Zero};
+
+use super::csr::CsrMatrix;
+use super::ilu::Ilu;
+use super::KrylovOptions;
+use crate::spot::*;
+
+/// A restart cycle of GMRES stagnates if it reduces the residual by less than this ratio.
+const GMRES_STAGNATION_RATIO: Numeric = 0.99;
+
+/// BiCGSTAB stagnates if its best residual does not improve within this number of iterations.
+const BICGSTAB_STAGNATION_WINDOW: usize = 50;
+
+/// The outcome of an iterative solve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// The residual reached the tolerance within the number of iterations.
+    Converged(usize),
+    /// The residual stopped decreasing or the iteration broke down after the number of
+    /// iterations.
+    Stagnated(usize),
+}
+
+/// Solves `A x = b` with restarted GMRES, right preconditioned by `precond`, starting from `x`.
+pub(crate) fn gmres(
+    a: &CsrMatrix,
+    precond: &Ilu,
+    b: &[Numeric],
+    x: &mut [Numeric],
+    options: &KrylovOptions,
+) -> Outcome {
+    let vars = a.vars();
+    let restart = options.restart.clamp(1, vars.max(1));
+    let target = options.tolerance * norm(b);
+
+    let mut r = vec![Numeric::zero(); vars];
+    let mut w = vec![Numeric::zero(); vars];
+    let mut z = vec![Numeric::zero(); vars];
+    let mut basis: Vec<Vec<Numeric>> = Vec::with_capacity(restart);
+    // The Hessenberg matrix by columns, reduced to upper triangular form by Givens rotations
+    let mut hessenberg = vec![vec![Numeric::zero(); restart + 1]; restart];
+    let mut rotations = vec![(Numeric::zero(), Numeric::zero()); restart];
+    let mut g = vec![Numeric::zero(); restart + 1];
+
+    let mut iterations = 0;
+    a.residual(b, x, &mut r);
+    let mut r_norm = norm(&r);
+    loop {
+        if r_norm <= target {
+            return Outcome::Converged(iterations);
+        }
+        if iterations >= options.max_iterations || !r_norm.is_finite() {
+            return Outcome::Stagnated(iterations);
+        }
+
+        basis.clear();
+        basis.push(r.iter().map(|val| val / r_norm).collect());
+        g.fill(Numeric::zero());
+        g[0] = r_norm;
+
+        let mut steps = 0;
+        for step in 0..restart {
+            precond.solve(&basis[step], &mut z);
+            a.mul_vec(&z, &mut w);
+
+            // Modified Gram-Schmidt
+            let column = &mut hessenberg[step];
+            for (idx, vec) in basis.iter().enumerate() {
+                column[idx] = dot(&w, vec);
+                axpy(-column[idx], vec, &mut w);
+            }
+            let w_norm = norm(&w);
+
+            for (idx, &(c, s)) in rotations.iter().enumerate().take(step) {
+                let upper = c * column[idx] + s * column[idx + 1];
+                column[idx + 1] = -s * column[idx] + c * column[idx + 1];
+                column[idx] = upper;
+            }
+            let (c, s) = givens(column[step], w_norm);
+            rotations[step] = (c, s);
+            column[step] = c * column[step] + s * w_norm;
+            column[step + 1] = Numeric::zero();
+            g[step + 1] = -s * g[step];
+            g[step] *= c;
+
+            steps = step + 1;
+            iterations += 1;
+            if g[step + 1].abs() <= target
+                || w_norm.is_zero()
+                || iterations >= options.max_iterations
+            {
+                break;
+            }
+            basis.push(w.iter().map(|val| val / w_norm).collect());
+        }
+
+        // Back substitution of the triangular system for the coefficients of the basis
+        let mut y = g[..steps].to_vec();
+        for col in (0..steps).rev() {
+            let column = &hessenberg[col];
+            y[col] /= column[col];
+            let y_col = y[col];
+            for (y_row, h) in y.iter_mut().zip(column).take(col) {
+                *y_row -= h * y_col;
+            }
+        }
+        w.fill(Numeric::zero());
+        for (coeff, vec) in y.iter().zip(&basis) {
+            axpy(*coeff, vec, &mut w);
+        }
+        precond.solve(&w, &mut z);
+        axpy(Numeric::one(), &z, x);
+
+        let previous = r_norm;
+        a.residual(b, x, &mut r);
+        r_norm = norm(&r);
+        if r_norm > target && r_norm > GMRES_STAGNATION_RATIO * previous {
+            return Outcome::Stagnated(iterations);
+        }
+    }
+}
+
+/// Solves `A x = b` with BiCGSTAB, right preconditioned by `precond`, starting from `x`.
+pub(crate) fn bicgstab(
+    a: &CsrMatrix,
+    precond: &Ilu,
+    b: &[Numeric],
+    x: &mut [Numeric],
+    options: &KrylovOptions,
+) -> Outcome {
+    let vars = a.vars();
+    let target = options.tolerance * norm(b);
+
+    let mut r = vec![Numeric::zero(); vars];
+    a.residual(b, x, &mut r);
+    let r_hat = r.clone();
+    let mut p = vec![Numeric::zero(); vars];
+    let mut v = vec![Numeric::zero(); vars];
+    let mut p_hat = vec![Numeric::zero(); vars];
+    let mut s_hat = vec![Numeric::zero(); vars];
+    let mut t = vec![Numeric::zero(); vars];
+    let (mut rho, mut alpha, mut omega) = (Numeric::one(), Numeric::one(), Numeric::one());
+
+    let mut best = norm(&r);
+    let mut best_iteration = 0;
+    for iteration in 0..options.max_iterations {
+        let r_norm = norm(&r);
+        if r_norm <= target {
+            return Outcome::Converged(iteration);
+        }
+        if !r_norm.is_finite() || iteration - best_iteration > BICGSTAB_STAGNATION_WINDOW {
+            return Outcome::Stagnated(iteration);
+        }
+        if r_norm < best {
+            best = r_norm;
+            best_iteration = iteration;
+        }
+
+        let rho_next = dot(&r_hat, &r);
+        if rho_next.is_zero() || omega.is_zero() {
+            return Outcome::Stagnated(iteration);
+        }
+        let beta = (rho_next / rho) * (alpha / omega);
+        rho = rho_next;
+        for ((p, r), v) in p.iter_mut().zip(&r).zip(&v) {
+            *p = r + beta * (*p - omega * v);
+        }
+
+        precond.solve(&p, &mut p_hat);
+        a.mul_vec(&p_hat, &mut v);
+        alpha = rho / dot(&r_hat, &v);
+        // `r` becomes the intermediate residual `s`
+        axpy(-alpha, &v, &mut r);
+        axpy(alpha, &p_hat, x);
+        if norm(&r) <= target {
+            return Outcome::Converged(iteration + 1);
+        }
+
+        precond.solve(&r, &mut s_hat);
+        a.mul_vec(&s_hat, &mut t);
+        let t_norm = dot(&t, &t);
+        omega = if t_norm.is_zero() {
+            Numeric::zero()
+        } else {
+            dot(&t, &r) / t_norm
+        };
+        axpy(omega, &s_hat, x);
+        axpy(-omega, &t, &mut r);
+    }
+    Outcome::Stagnated(options.max_iterations)
+}
+
+/// Returns the Givens rotation `(c, s)` which eliminates `b` from `(a, b)`.
+fn givens(a: Numeric, b: Numeric) -> (Numeric, Numeric) {
+    if b.is_zero() {
+        return (Numeric::one(), Numeric::zero());
+    }
+    let r = a.hypot(b);
+    (a / r, b / r)
+}
+
+fn dot(a: &[Numeric], b: &[Numeric]) -> Numeric {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn norm(a: &[Numeric]) -> Numeric {
+    dot(a, a).sqrt()
+}
+
+/// Computes `y += alpha x`.
+fn axpy(alpha: Numeric, x: &[Numeric], y: &mut [Numeric]) {
+    y.iter_mut().zip(x).for_each(|(y, x)| *y += alpha * x);
+}
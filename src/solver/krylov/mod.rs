@@ -0,0 +1,335 @@
+mod csr;
+mod ilu;
+mod iterative;
+
+use log::{trace, warn};
+use num::Zero;
+
+use super::slots::SlotMatrix;
+use super::{FaerSparseSolver, Solver, SolverError};
+use crate::spot::*;
+use csr::CsrMatrix;
+use ilu::Ilu;
+use iterative::Outcome;
+
+/// The Krylov subspace method of the [`KrylovSolver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KrylovMethod {
+    /// Restarted GMRES, robust for the nonsymmetric matrices of circuits.
+    #[default]
+    Gmres,
+    /// BiCGSTAB, which needs less memory than GMRES but may break down.
+    BiCgStab,
+}
+
+/// The incomplete LU preconditioner of the [`KrylovSolver`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Preconditioner {
+    /// ILU(0), which keeps the sparsity pattern of the conductance matrix.
+    #[default]
+    Ilu0,
+    /// ILUT, which drops the entries smaller than `drop_tol` relative to the norm of their row
+    /// and keeps at most `fill` entries of `L` and `U` per row.
+    Ilut { drop_tol: Numeric, fill: usize },
+}
+
+/// The settings of the [`KrylovSolver`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KrylovOptions {
+    /// The Krylov subspace method.
+    pub method: KrylovMethod,
+    /// The preconditioner.
+    pub preconditioner: Preconditioner,
+    /// The number of GMRES iterations before a restart.
+    pub restart: usize,
+    /// The tolerance of the residual relative to the norm of `b`.
+    pub tolerance: Numeric,
+    /// The maximum number of iterations before falling back to the direct LU.
+    pub max_iterations: usize,
+}
+
+impl Default for KrylovOptions {
+    fn default() -> Self {
+        Self {
+            method: KrylovMethod::default(),
+            preconditioner: Preconditioner::default(),
+            restart: 30,
+            tolerance: 1e-12,
+            max_iterations: 1000,
+        }
+    }
+}
+
+/// A Solver implementation using preconditioned Krylov subspace methods.
+///
+/// The direct LU of the sparse solvers needs memory for the fill-in of its factors, which
+/// exceeds the available memory for very large resistive meshes. This solver iterates with
+/// restarted GMRES or BiCGSTAB instead and only stores the conductance matrix, an incomplete LU
+/// preconditioner and the vectors of the iteration.
+///
+/// The sparsity pattern of `A` is analysed by [`Solver::analyse`], [`Solver::refactor`] builds
+/// the preconditioner and every solve starts from the previous solution, which is usually close
+/// within Newton iterations and time steps. If the iteration stagnates, the system is solved by
+/// the direct LU of a [`FaerSparseSolver`] instead. The iteration may also converge for a
+/// singular `A`, e.g. of a floating subcircuit, so a cancelled pivot of the preconditioner hands
+/// the system to the direct LU, which rejects it as [`SolverError::MatrixNonInvertible`]. The
+/// complex systems of the AC analysis are always solved by the direct LU.
+pub struct KrylovSolver {
+    /// The settings of the iteration.
+    options: KrylovOptions,
+
+    /// The conductance matrix `A`, addressed by slots.
+    a_mat: SlotMatrix<Numeric>,
+
+    /// The analysed `A` in compressed row form, holding the values of the last refactorisation.
+    csr: CsrMatrix,
+
+    /// The position of each slot in the values of `csr`.
+    csr_positions: Vec<usize>,
+
+    /// Whether `csr` holds the pattern of the slots.
+    analysed: bool,
+
+    /// The preconditioner of `csr`, kept for `solve_factored`.
+    precond: Option<Ilu>,
+
+    /// Whether `precond` belongs to the current values of `A`.
+    factored: bool,
+
+    /// The direct solver for stagnating iterations and the AC analysis.
+    direct: FaerSparseSolver,
+
+    /// Whether `direct` holds the factorisation of `csr`.
+    direct_factored: bool,
+
+    /// The vector `b`.
+    b_vec: Vec<Numeric>,
+
+    /// The Solution vector, which is the initial guess of the next solve.
+    x_vec: Vec<Numeric>,
+}
+
+impl KrylovSolver {
+    /// Creates a new instance of the Solver with the given number of variables and settings.
+    pub fn with_options(vars: usize, options: KrylovOptions) -> Result<Self, SolverError> {
+        Ok(Self {
+            options,
+            a_mat: SlotMatrix::default(),
+            csr: CsrMatrix::default(),
+            csr_positions: Vec::new(),
+            analysed: false,
+            precond: None,
+            factored: false,
+            direct: FaerSparseSolver::new(vars)?,
+            direct_factored: false,
+            b_vec: vec![Numeric::zero(); vars],
+            x_vec: vec![Numeric::zero(); vars],
+        })
+    }
+
+    /// Returns the settings of the iteration.
+    pub fn options(&self) -> &KrylovOptions {
+        &self.options
+    }
+
+    /// Clears the values of `A`, but keeps its slots and the preconditioner.
+    fn clear_a_mat(&mut self) {
+        self.a_mat.clear_values();
+        self.factored = false;
+    }
+
+    /// Factorises `A` with the direct LU, which is kept for `solve_factored`.
+    fn factor_direct(&mut self) -> Result<(), SolverError> {
+        self.csr
+            .entries()
+            .for_each(|triplet| self.direct.insert_a(&triplet));
+        self.direct.solve()?;
+        self.direct_factored = true;
+        Ok(())
+    }
+
+    /// Solves the system with the direct LU.
+    fn solve_direct(&mut self) -> Result<(), SolverError> {
+        if !self.direct_factored {
+            self.factor_direct()?;
+        }
+        for (idx, &val) in self.b_vec.iter().enumerate() {
+            self.direct.insert_b(&(idx, val));
+        }
+        self.direct
+            .solve_factored()
+            .map(|x| self.x_vec.copy_from_slice(x))
+    }
+}
+
+impl Solver for KrylovSolver {
+    fn new(vars: usize) -> Result<Self, SolverError> {
+        Self::with_options(vars, KrylovOptions::default())
+    }
+
+    fn init(&mut self, a_matrix: Vec<(usize, usize)>, cplx_a_matrix: Vec<(usize, usize)>) {
+        for (row, col) in a_matrix {
+            self.a_mat.slot(row, col);
+        }
+        self.analysed = false;
+        self.precond = None;
+        self.factored = false;
+        self.direct_factored = false;
+        self.direct.init(Vec::new(), cplx_a_matrix);
+    }
+
+    fn insert_a(&mut self, a_trpl: &(usize, usize, Numeric)) {
+        let (row, col, val) = *a_trpl;
+        self.a_mat.insert(row, col, val);
+        self.factored = false;
+    }
+
+    fn insert_b(&mut self, b_pair: &(usize, Numeric)) {
+        let (row, val) = *b_pair;
+        self.b_vec[row] += val;
+    }
+
+    fn a_slot(&mut self, row: usize, col: usize) -> usize {
+        self.a_mat.slot(row, col)
+    }
+
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric) {
+        self.a_mat.add(slot, value);
+        self.factored = false;
+    }
+
+    fn store_base(&mut self) {
+        self.a_mat.store_base();
+    }
+
+    fn load_base(&mut self) {
+        self.a_mat.load_base();
+        self.b_vec.iter_mut().for_each(|val| *val = Numeric::zero());
+        self.factored = false;
+    }
+
+    fn insert_cplx_a(&mut self, a_trpl: &(usize, usize, ComplexNumeric)) {
+        self.direct.insert_cplx_a(a_trpl);
+    }
+
+    fn insert_cplx_b(&mut self, b_pair: &(usize, ComplexNumeric)) {
+        self.direct.insert_cplx_b(b_pair);
+    }
+
+    fn analyse(&mut self) -> Result<(), SolverError> {
+        trace!(
+            "Analyse the sparsity pattern with {} entries",
+            self.a_mat.entries().len()
+        );
+        (self.csr, self.csr_positions) =
+            CsrMatrix::from_entries(self.x_vec.len(), self.a_mat.entries());
+        self.a_mat.set_analysed();
+        self.analysed = true;
+        self.precond = None;
+        self.factored = false;
+        self.direct_factored = false;
+        Ok(())
+    }
+
+    fn refactor(&mut self) -> Result<(), SolverError> {
+        if !self.analysed || !self.a_mat.is_analysed() {
+            self.analyse()?;
+        }
+
+        let values = self.csr.values_mut();
+        values.iter_mut().for_each(|val| *val = Numeric::zero());
+        for (&pos, &val) in self.csr_positions.iter().zip(self.a_mat.values()) {
+            values[pos] += val;
+        }
+        let precond = match self.options.preconditioner {
+            Preconditioner::Ilu0 => Ilu::ilu0(&self.csr)?,
+            Preconditioner::Ilut { drop_tol, fill } => Ilu::ilut(&self.csr, drop_tol, fill)?,
+        };
+        trace!("Preconditioner with {} entries", precond.nnz());
+        let cancelled = precond.cancelled();
+        self.precond = Some(precond);
+        self.factored = true;
+        self.direct_factored = false;
+        if let Some(row) = cancelled {
+            warn!("The pivot of row {row} cancelled, solving with the direct LU");
+            self.factor_direct()?;
+        }
+        Ok(())
+    }
+
+    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        if !self.factored {
+            self.refactor()?;
+        }
+        self.clear_a_mat();
+        self.solve_factored()
+    }
+
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        let precond = self.precond.as_ref().ok_or(SolverError::NotFactorised)?;
+
+        if !self.x_vec.iter().all(|val| val.is_finite()) {
+            self.x_vec.iter_mut().for_each(|val| *val = Numeric::zero());
+        }
+        let result = if self.direct_factored {
+            self.solve_direct()
+        } else {
+            let outcome = match self.options.method {
+                KrylovMethod::Gmres => iterative::gmres(
+                    &self.csr,
+                    precond,
+                    &self.b_vec,
+                    &mut self.x_vec,
+                    &self.options,
+                ),
+                KrylovMethod::BiCgStab => iterative::bicgstab(
+                    &self.csr,
+                    precond,
+                    &self.b_vec,
+                    &mut self.x_vec,
+                    &self.options,
+                ),
+            };
+            match outcome {
+                Outcome::Converged(iterations) => {
+                    trace!("Converged after {iterations} iterations");
+                    Ok(())
+                }
+                Outcome::Stagnated(iterations) => {
+                    warn!("Stagnated after {iterations} iterations, solving with the direct LU");
+                    self.solve_direct()
+                }
+            }
+        };
+        self.b_vec.iter_mut().for_each(|val| *val = Numeric::zero());
+        result.map(|_| &self.x_vec)
+    }
+
+    fn solve_cplx(&mut self) -> Result<&Vec<ComplexNumeric>, SolverError> {
+        self.direct.solve_cplx()
+    }
+
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        (self.a_mat.triples(), self.b_vec.clone())
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        self.direct.cplx_system()
+    }
+
+    fn reset(&mut self) {
+        self.clear_a_mat();
+        self.precond = None;
+        self.direct_factored = false;
+        self.b_vec.iter_mut().for_each(|val| *val = Numeric::zero());
+        self.direct.reset();
+    }
+}
+
+#[cfg(test)]
+impl KrylovSolver {
+    /// Returns true if the last solve fell back to the direct LU.
+    pub fn is_direct(&self) -> bool {
+        self.direct_factored
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+
+use crate::spot::{ComplexNumeric, Numeric};
+
+/// A value type of the Matrix Market format.
+pub(crate) trait MarketValue: Copy {
+    /// The field of the header, `real` or `complex`.
+    const FIELD: &'static str;
+
+    /// Writes the value as the whitespace separated numbers of an entry.
+    fn write_value(&self, writer: &mut impl Write) -> io::Result<()>;
+}
+
+impl MarketValue for Numeric {
+    const FIELD: &'static str = "real";
+
+    fn write_value(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(writer, "{self:e}")
+    }
+}
+
+impl MarketValue for ComplexNumeric {
+    const FIELD: &'static str = "complex";
+
+    fn write_value(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(writer, "{:e} {:e}", self.re, self.im)
+    }
+}
+
+/// Writes the `vars`×`vars` matrix given by its `(row, col, value)` triples in the coordinate
+/// format. Every line of `comment` becomes a comment line after the header.
+pub(crate) fn write_matrix<T: MarketValue>(
+    writer: &mut impl Write,
+    vars: usize,
+    triples: &[(usize, usize, T)],
+    comment: &str,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "%%MatrixMarket matrix coordinate {} general",
+        T::FIELD
+    )?;
+    write_comment(writer, comment)?;
+    writeln!(writer, "{vars} {vars} {}", triples.len())?;
+    for (row, col, value) in triples {
+        // The indices of the format start at 1
+        write!(writer, "{} {} ", row + 1, col + 1)?;
+        value.write_value(writer)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Writes the vector `values` as a single column in the array format. Every line of `comment`
+/// becomes a comment line after the header.
+pub(crate) fn write_vector<T: MarketValue>(
+    writer: &mut impl Write,
+    values: &[T],
+    comment: &str,
+) -> io::Result<()> {
+    writeln!(writer, "%%MatrixMarket matrix array {} general", T::FIELD)?;
+    write_comment(writer, comment)?;
+    writeln!(writer, "{} 1", values.len())?;
+    for value in values {
+        value.write_value(writer)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+fn write_comment(writer: &mut impl Write, comment: &str) -> io::Result<()> {
+    for line in comment.lines() {
+        writeln!(writer, "% {line}")?;
+    }
+    Ok(())
+}
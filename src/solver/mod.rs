@@ -1,8 +1,12 @@
+mod diagnostics;
 pub mod faer;
 pub mod faer_sparse;
+pub mod krylov;
+pub(crate) mod matrix_market;
 pub mod nalgebra;
 pub mod rsparse;
 pub mod selector;
+mod slots;
 
 use clap::ValueEnum;
 use miette::Diagnostic;
@@ -10,30 +14,63 @@ use thiserror::Error;
 
 pub use faer::FaerSolver;
 pub use faer_sparse::FaerSparseSolver;
+pub use krylov::KrylovSolver;
 pub use nalgebra::NalgebraSolver;
 pub use rsparse::RSparseSolver;
-pub use selector::{SolverSelectionStrategy, SolverSelector};
+pub use selector::{AutoSolver, MatrixProfile, SolverSelectionStrategy, SolverSelector};
 
 use crate::spot::{ComplexNumeric, Numeric};
 
 /// Errors that can occur in the Solver.
 #[derive(Debug, Error, Diagnostic, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SolverError {
-    /// Error indicating that the conductance matrix is not invertible.
-    #[error("The conductance matrix is not invertible")]
+    /// Error indicating that the conductance matrix is not invertible. The `pivot` is the index
+    /// of the variable whose pivot vanished in the factorisation, if the backend could locate it.
+    #[error(
+        "The conductance matrix is not invertible{}",
+        .pivot.map(|pivot| format!(" (singular pivot at variable {pivot})")).unwrap_or_default()
+    )]
     #[diagnostic(help(
         "This is the case when the Matrix is singular,\n
         which happens when, for example, two ideal voltage sources short each other."
     ))]
-    MatrixNonInvertible,
+    MatrixNonInvertible { pivot: Option<usize> },
+
+    /// Error indicating that there is no factorisation of the conductance matrix to reuse.
+    #[error("The conductance matrix is not factorised")]
+    #[diagnostic(help("Solve the system once before solving it again for a new vector `b`"))]
+    NotFactorised,
+
+    /// Error indicating that the backend of an [`AutoSolver`] found the matrix singular and was
+    /// replaced by the fallback `to`, which starts without the stamps of the failed backend.
+    #[error("The {from:?} solver found the matrix singular, switched to the {to:?} solver")]
+    #[diagnostic(help("Stamp the system again and solve it with the new solver"))]
+    BackendSwitched { from: Solvers, to: Solvers },
 }
 
-#[derive(Copy, Clone, ValueEnum, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, ValueEnum, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Solvers {
     Rsparse,
     Nalgebra,
     Faer,
     FaerSparse,
+    Krylov,
+    /// Selects the solver by the size and sparsity of the circuit
+    Auto,
+}
+
+impl Solvers {
+    /// Creates a solver of this kind with the given number of variables.
+    pub fn create(self, vars: usize) -> Result<Box<dyn Solver>, SolverError> {
+        Ok(match self {
+            Solvers::Rsparse => Box::new(RSparseSolver::new(vars)?),
+            Solvers::Nalgebra => Box::new(NalgebraSolver::new(vars)?),
+            Solvers::Faer => Box::new(FaerSolver::new(vars)?),
+            Solvers::FaerSparse => Box::new(FaerSparseSolver::new(vars)?),
+            Solvers::Krylov => Box::new(KrylovSolver::new(vars)?),
+            Solvers::Auto => Box::new(AutoSolver::new(vars)?),
+        })
+    }
 }
 
 /// Trait defining the Solver interface.
@@ -52,19 +89,79 @@ pub trait Solver {
     /// Inserts a Value into the  known values Vector (`a`) of the Solver.
     fn insert_b(&mut self, b_pair: &(usize, Numeric));
 
+    /// Returns the slot of the entry `(row, col)` in the values of the conductance matrix (`a`).
+    /// Stamping through the slot skips the lookup of the entry. Slots stay valid for the
+    /// lifetime of the Solver.
+    fn a_slot(&mut self, row: usize, col: usize) -> usize;
+
+    /// Adds `value` to the entry of the conductance matrix (`a`) at `slot`.
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric);
+
+    /// Stores the current conductance matrix (`a`) as the base matrix, usually the constant
+    /// stamps of the circuit.
+    fn store_base(&mut self);
+
+    /// Replaces the conductance matrix (`a`) by the base matrix and clears `b`, as the start of
+    /// an assembly.
+    fn load_base(&mut self);
+
     /// Inserts a Value into the conductance matrix (`a`) of the Solver.
     fn insert_cplx_a(&mut self, a_trpl: &(usize, usize, ComplexNumeric));
 
     /// Inserts a Value into the  known values Vector (`a`) of the Solver.
     fn insert_cplx_b(&mut self, b_pair: &(usize, ComplexNumeric));
 
+    /// Analyses the sparsity pattern of the conductance matrix (`a`). Sparse solvers compute the
+    /// fill-reducing ordering and the symbolic factorisation, which [`Solver::refactor`] reuses
+    /// as long as the pattern does not change. Dense solvers have nothing to analyse.
+    fn analyse(&mut self) -> Result<(), SolverError> {
+        Ok(())
+    }
+
+    /// Factorises the conductance matrix (`a`) from scratch, including a new analysis.
+    fn factor(&mut self) -> Result<(), SolverError> {
+        self.analyse()?;
+        self.refactor()
+    }
+
+    /// Computes the numeric factorisation of the conductance matrix (`a`) with the analysis of
+    /// the last [`Solver::analyse`]. Sparse solvers analyse the pattern again if values were
+    /// inserted outside of it.
+    fn refactor(&mut self) -> Result<(), SolverError>;
+
+    /// Solves `Ax = b` and clears `a` and `b` for the next assembly. The conductance matrix is
+    /// refactorised first, unless it was already factorised after the last insert.
     fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError>;
 
+    /// Solves `Ax = b` for a new `b` with the factorisation of the last [`Solver::solve`] and
+    /// clears `b`. Only `b` is assembled in between, which saves the numeric factorisation when
+    /// the conductance matrix did not change.
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError>;
+
     fn solve_cplx(&mut self) -> Result<&Vec<ComplexNumeric>, SolverError>;
 
+    /// Returns the assembled conductance matrix (`a`) as `(row, col, value)` triples and the
+    /// vector `b`, as the next [`Solver::solve`] solves them. Dense solvers leave out the zeros.
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>);
+
+    /// Returns the assembled complex conductance matrix (`a`) as `(row, col, value)` triples and
+    /// the vector `b`, as the next [`Solver::solve_cplx`] solves them.
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>);
+
+    /// Enables the estimate of the 1-norm condition number after every factorisation of the
+    /// conductance matrix (`a`), which logs a warning when it exceeds `limit`. `None` disables
+    /// the estimate, which costs a few extra solves per factorisation. Backends without an
+    /// estimate ignore the limit.
+    fn set_condition_limit(&mut self, _limit: Option<Numeric>) {}
+
+    /// Returns the condition estimate of the last factorisation, if it is enabled.
+    fn condition_estimate(&self) -> Option<Numeric> {
+        None
+    }
+
     /// Resets the solver to a clean state, clearing all matrices and vectors.
     fn reset(&mut self);
 }
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
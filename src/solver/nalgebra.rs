@@ -1,5 +1,6 @@
 use num::Zero;
 
+use super::diagnostics::{inverse_norm_estimate, ConditionMonitor};
 use super::{Solver, SolverError};
 use crate::spot::{ComplexNumeric, Numeric}; // Ensure these are correctly imported
 use na::LU;
@@ -20,6 +21,14 @@ pub struct NalgebraSolver {
     b_vec: na::DVector<Numeric>,
     /// The Solution vector
     x_vec: na::DVector<Numeric>,
+    /// The LU factorisation of `A`, kept for `solve_factored`.
+    lu: Option<LU<Numeric, na::Dyn, na::Dyn>>,
+    /// Whether `lu` is the factorisation of the current values of `A`.
+    factored: bool,
+    /// The optional condition estimate of `lu`.
+    condition: ConditionMonitor,
+    /// The base matrix, which every assembly of `A` starts from.
+    base: na::DMatrix<Numeric>,
     /// The conductance matrix `A`.
     cplx_a_mat: na::DMatrix<ComplexNumeric>,
     /// The vector `b`.
@@ -43,6 +52,10 @@ impl Solver for NalgebraSolver {
             a_mat: a,
             b_vec: b,
             x_vec: x,
+            lu: None,
+            factored: false,
+            condition: ConditionMonitor::default(),
+            base: na::DMatrix::zeros(vars, vars),
             cplx_a_mat: cplx_a,
             cplx_b_vec: cplx_b,
             cplx_x_vec: cplx_x,
@@ -52,6 +65,7 @@ impl Solver for NalgebraSolver {
     fn insert_a(&mut self, a_mat: &(usize, usize, Numeric)) {
         let (row, col, val) = *a_mat;
         self.a_mat[(row, col)] += val;
+        self.factored = false;
     }
 
     fn insert_b(&mut self, b_vec: &(usize, Numeric)) {
@@ -59,6 +73,25 @@ impl Solver for NalgebraSolver {
         self.b_vec[row] += val;
     }
 
+    fn a_slot(&mut self, row: usize, col: usize) -> usize {
+        col * self.a_mat.nrows() + row
+    }
+
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric) {
+        self.a_mat[slot] += value;
+        self.factored = false;
+    }
+
+    fn store_base(&mut self) {
+        self.base.copy_from(&self.a_mat);
+    }
+
+    fn load_base(&mut self) {
+        self.a_mat.copy_from(&self.base);
+        self.b_vec.fill(Numeric::zero());
+        self.factored = false;
+    }
+
     fn insert_cplx_a(&mut self, a_mat: &(usize, usize, ComplexNumeric)) {
         let (row, col, val) = *a_mat;
         self.cplx_a_mat[(row, col)] += val;
@@ -69,15 +102,40 @@ impl Solver for NalgebraSolver {
         self.cplx_b_vec[row] += val;
     }
 
-    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+    fn refactor(&mut self) -> Result<(), SolverError> {
         let lu = LU::new(self.a_mat.clone());
+        if !lu.is_invertible() {
+            self.lu = None;
+            return Err(SolverError::MatrixNonInvertible {
+                pivot: zero_pivot(&lu),
+            });
+        }
+        if self.condition.is_enabled() {
+            self.estimate_condition(&lu);
+        }
+        self.lu = Some(lu);
+        self.factored = true;
+        Ok(())
+    }
+
+    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        if !self.factored {
+            self.refactor()?;
+        }
+        self.a_mat.fill(Numeric::zero());
+        self.factored = false;
+
+        self.solve_factored()
+    }
+
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        let lu = self.lu.as_ref().ok_or(SolverError::NotFactorised)?;
 
         self.x_vec = match lu.solve(&self.b_vec) {
             Some(v) => v,
-            None => return Err(SolverError::MatrixNonInvertible),
+            None => return Err(SolverError::MatrixNonInvertible { pivot: None }),
         };
 
-        self.a_mat.fill(Numeric::zero());
         self.b_vec.fill(Numeric::zero());
 
         Ok(self.x_vec.data.as_vec())
@@ -88,7 +146,11 @@ impl Solver for NalgebraSolver {
 
         self.cplx_x_vec = match lu.solve(&self.cplx_b_vec) {
             Some(v) => v,
-            None => return Err(SolverError::MatrixNonInvertible),
+            None => {
+                return Err(SolverError::MatrixNonInvertible {
+                    pivot: zero_pivot(&lu),
+                })
+            }
         };
 
         self.cplx_a_mat.fill(ComplexNumeric::zero());
@@ -97,6 +159,20 @@ impl Solver for NalgebraSolver {
         Ok(self.cplx_x_vec.data.as_vec())
     }
 
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        (
+            dense_triples(&self.a_mat),
+            self.b_vec.iter().copied().collect(),
+        )
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        (
+            dense_triples(&self.cplx_a_mat),
+            self.cplx_b_vec.iter().copied().collect(),
+        )
+    }
+
     fn init(&mut self, a_matrix: Vec<(usize, usize)>, cplx_a_matrix: Vec<(usize, usize)>) {
         a_matrix
             .iter()
@@ -109,10 +185,69 @@ impl Solver for NalgebraSolver {
 
     fn reset(&mut self) {
         self.a_mat.fill(Numeric::zero());
+        self.lu = None;
+        self.factored = false;
         self.b_vec.fill(Numeric::zero());
         self.cplx_a_mat.fill(ComplexNumeric::zero());
         self.cplx_b_vec.fill(ComplexNumeric::zero());
     }
+
+    fn set_condition_limit(&mut self, limit: Option<Numeric>) {
+        self.condition.set_limit(limit);
+    }
+
+    fn condition_estimate(&self) -> Option<Numeric> {
+        self.condition.estimate()
+    }
+}
+
+impl NalgebraSolver {
+    /// Estimates the condition number of `A` with its factorisation `lu`.
+    fn estimate_condition(&mut self, lu: &LU<Numeric, na::Dyn, na::Dyn>) {
+        let norm = self
+            .a_mat
+            .column_iter()
+            .map(|col| col.iter().map(|val| val.abs()).sum::<Numeric>())
+            .fold(Numeric::zero(), Numeric::max);
+
+        // With `P A = L U`, the transpose solves `Uᵀ Lᵀ P x = b`
+        let (l, u) = (lu.l(), lu.u());
+        let vars = self.a_mat.nrows();
+        let inverse_norm = inverse_norm_estimate(
+            vars,
+            |b| {
+                lu.solve_mut(&mut na::DVectorViewMut::from_slice(b, vars));
+            },
+            |b| {
+                let mut b = na::DVectorViewMut::from_slice(b, vars);
+                u.tr_solve_upper_triangular_mut(&mut b);
+                l.tr_solve_lower_triangular_mut(&mut b);
+                lu.p().inv_permute_rows(&mut b);
+            },
+        );
+        self.condition.record(norm, inverse_norm);
+    }
+}
+
+/// Returns the column of the first zero pivot of a singular LU factorisation.
+fn zero_pivot<T: na::ComplexField>(lu: &LU<T, na::Dyn, na::Dyn>) -> Option<usize> {
+    let u = lu.u();
+    (0..u.nrows()).find(|&idx| u[(idx, idx)].is_zero())
+}
+
+/// Returns the nonzero entries of `mat` as `(row, col, value)` triples.
+fn dense_triples<T: na::Scalar + Zero + Copy>(mat: &na::DMatrix<T>) -> Vec<(usize, usize, T)> {
+    mat.column_iter()
+        .enumerate()
+        .flat_map(|(col, column)| {
+            column
+                .iter()
+                .enumerate()
+                .filter(|(_, val)| !val.is_zero())
+                .map(move |(row, &val)| (row, col, val))
+                .collect::<Vec<_>>()
+        })
+        .collect()
 }
 
 impl std::fmt::Debug for NalgebraSolver {
@@ -4,28 +4,45 @@
 // but performance degraded after refactoring. Requires profiling and optimization.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::hash::Hash;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+use super::diagnostics::{inverse_norm_estimate, one_norm, singular_pivot, ConditionMonitor};
+use super::slots::SlotMatrix;
 use super::{Solver, SolverError};
 use crate::models::{Pairs, Triples};
 use crate::spot::*;
 use log::trace;
 use log::warn;
 use num::complex::ComplexFloat;
-use num::{Complex, Zero};
+use num::{Complex, One, Zero};
 use rsparse::data::{Nmrc, Sprs, Symb, Trpl};
 use rsparse::lusol;
+use rustc_hash::FxHashMap;
 
 // TODO: Enhance documentation to match the comprehensive style of FaerSolver (faer.rs)
 /// A Solver implementation using the RSparse library.
 ///
 /// This solver provides sparse matrix support for efficient handling of large,
 /// sparse circuit matrices commonly encountered in electronic circuit simulations.
+///
+/// The sparsity pattern of `A` and its column ordering are analysed once and reused for the
+/// numeric factorisation in every Newton iteration and time step.
+///
+/// The complex matrix of the AC analysis is factorised by the same sparse LU with [`Phasor`]
+/// entries. Its pattern and ordering are analysed once and reused for all frequencies.
 #[derive(Debug)]
 pub struct RSparseSolver {
     vars: usize,
-    /// The conductance matrix `A` as a sparse matrix.
-    a_mat: HashMap<(usize, usize), Numeric>,
+    /// The conductance matrix `A`, addressed by slots.
+    a_mat: SlotMatrix<Numeric>,
+    /// The position of each slot in the values of `sprs`.
+    sprs_positions: Vec<usize>,
+    /// Whether `lu` is the factorisation of the current values of `sprs`.
+    factored: bool,
+    /// The optional condition estimate of `lu`.
+    condition: ConditionMonitor,
     /// The vector `b` as a dense vector.
     b_vec: Vec<Numeric>,
     /// The Solution vector `x`.
@@ -34,44 +51,53 @@ pub struct RSparseSolver {
     // Sparse Matrix Workspace
     sprs: Sprs<Numeric>,
     symb: Option<Symb>,
-    lu: Nmrc<Numeric>,
-
-    /// The conductance matrix `A` as a sparse matrix.
-    cplx_a_mat: HashMap<(usize, usize), ComplexNumeric>,
-    /// The vector `b` as a dense vector.
-    cplx_b_vec: Vec<Numeric>,
+    /// The factorisation of `sprs` with the ordering of `symb`, kept for `solve_factored`.
+    lu: Option<Nmrc<Numeric>>,
+
+    /// The complex conductance matrix `A`, addressed by slots.
+    cplx_a_mat: SlotMatrix<ComplexNumeric>,
+    /// The position of each slot in the values of `cplx_sprs`.
+    cplx_sprs_positions: Vec<usize>,
+    /// The complex vector `b` as a dense vector.
+    cplx_b_vec: Vec<Phasor>,
+    /// The workspace of the triangular solves.
+    cplx_work: Vec<Phasor>,
     /// The Solution vector `x`.
     cplx_x_vec: Vec<Complex<Numeric>>,
 
     //Complex Sparse Matrix Workspace
-    cplx_sprs: Sprs<Numeric>,
+    cplx_sprs: Sprs<Phasor>,
     cplx_symb: Option<Symb>,
 }
 
 impl Solver for RSparseSolver {
     /// Creates a new instance of the Solver with the given number of variables.
     fn new(vars: usize) -> Result<Self, SolverError> {
-        let a_mat = HashMap::new();
+        let a_mat = SlotMatrix::default();
         let b_vec = vec![0.; vars];
         let x_vec = vec![0.; vars];
         let sprs = Sprs::new();
-        let lu = Nmrc::new();
 
-        let cplx_a_mat = HashMap::new();
-        let cplx_b_vec = vec![0.; 2 * vars];
+        let cplx_a_mat = SlotMatrix::default();
+        let cplx_b_vec = vec![Phasor::default(); vars];
         let cplx_x_vec = vec![ComplexNumeric { re: 0.0, im: 0.0 }; vars];
         let cplx_sprs = Sprs::new();
 
         Ok(Self {
             vars,
             a_mat,
+            sprs_positions: Vec::new(),
+            factored: false,
+            condition: ConditionMonitor::default(),
             b_vec,
             x_vec,
             sprs,
             symb: None,
-            lu,
+            lu: None,
             cplx_a_mat,
+            cplx_sprs_positions: Vec::new(),
             cplx_b_vec,
+            cplx_work: vec![Phasor::default(); vars],
             cplx_x_vec,
             cplx_sprs,
             cplx_symb: None,
@@ -80,12 +106,8 @@ impl Solver for RSparseSolver {
 
     fn insert_a(&mut self, a_mat: &(usize, usize, Numeric)) {
         let (row, col, val) = *a_mat;
-        match self.a_mat.get_mut(&(row, col)) {
-            Some(v) => *v += val,
-            None => {
-                self.a_mat.insert((row, col), val);
-            }
-        };
+        self.a_mat.insert(row, col, val);
+        self.factored = false;
     }
 
     fn insert_b(&mut self, b_vec: &(usize, Numeric)) {
@@ -93,161 +115,253 @@ impl Solver for RSparseSolver {
         self.b_vec[row] += val;
     }
 
+    fn a_slot(&mut self, row: usize, col: usize) -> usize {
+        self.a_mat.slot(row, col)
+    }
+
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric) {
+        self.a_mat.add(slot, value);
+        self.factored = false;
+    }
+
+    fn store_base(&mut self) {
+        self.a_mat.store_base();
+    }
+
+    fn load_base(&mut self) {
+        self.a_mat.load_base();
+        self.b_vec.iter_mut().for_each(|val| *val = Numeric::zero());
+        self.factored = false;
+    }
+
     fn insert_cplx_a(&mut self, a_mat: &(usize, usize, ComplexNumeric)) {
         let (row, col, val) = *a_mat;
-        match self.cplx_a_mat.get_mut(&(row, col)) {
-            Some(v) => *v += val,
-            None => {
-                self.cplx_a_mat.insert((row, col), val);
-            }
-        };
+        self.cplx_a_mat.insert(row, col, val);
     }
 
     fn insert_cplx_b(&mut self, b_vec: &(usize, ComplexNumeric)) {
         let (row, val) = *b_vec;
-        let pivot = self.vars;
-        self.cplx_b_vec[row] += val.re;
-        self.cplx_b_vec[row + pivot] += val.im;
+        self.cplx_b_vec[row] += Phasor(val);
+    }
+
+    fn analyse(&mut self) -> Result<(), SolverError> {
+        trace!(
+            "Analyse the sparsity pattern with {} entries",
+            self.a_mat.entries().len()
+        );
+        self.update_from_hashmap();
+        self.symb = Some(symbolic(&self.sprs));
+        self.a_mat.set_analysed();
+        self.lu = None;
+        self.factored = false;
+        Ok(())
+    }
+
+    fn refactor(&mut self) -> Result<(), SolverError> {
+        if self.symb.is_none() || !self.a_mat.is_analysed() {
+            self.analyse()?;
+        } else {
+            let values = self.a_mat.values();
+            for (&pos, &val) in self.sprs_positions.iter().zip(values) {
+                self.sprs.x[pos] = val;
+            }
+        }
+        let symb = self
+            .symb
+            .as_mut()
+            .ok_or(SolverError::MatrixNonInvertible { pivot: None })?;
+
+        let Ok(lu) = rsparse::lu(&self.sprs, symb, 1e-6) else {
+            // The LU of rsparse does not tell at which column it found no pivot
+            let entries = self.a_mat.entries().iter().zip(self.a_mat.values());
+            let pivot = singular_pivot(
+                self.vars,
+                entries.map(|(&(row, col), &val)| (row, col, val)),
+            );
+            return Err(SolverError::MatrixNonInvertible { pivot });
+        };
+        self.lu = Some(lu);
+        if self.condition.is_enabled() {
+            self.estimate_condition();
+        }
+        self.factored = true;
+        Ok(())
     }
 
     /// Solves the system of equations (Ax = B for x) and returns a reference to the solution.
     fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
-        self.update_from_hashmap();
-        if self.symb.is_none() {
-            self.symb = Some(rsparse::sqr(&self.sprs, 1, false))
+        if !self.factored {
+            self.refactor()?;
         }
-        // TODO: Consider proper error handling instead of using expect()
-        let mut symb = self.symb.take()
-            .expect("Symbolic analysis data missing. This indicates the solver was not properly initialized.");
+        self.clear_a_mat();
+        self.solve_factored()
+    }
 
-        // TODO: Consider proper error handling instead of using expect()
-        self.lu = rsparse::lu(&self.sprs, &mut symb, 1e-6).expect(
-            "LU decomposition failed. This indicates a singular or ill-conditioned matrix.",
-        );
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        let (Some(symb), Some(lu)) = (&self.symb, &self.lu) else {
+            return Err(SolverError::NotFactorised);
+        };
 
-        ipvec(self.sprs.n, &self.lu.pinv, &self.b_vec, &mut self.x_vec[..]);
-        rsparse::lsolve(&self.lu.l, &mut self.x_vec);
-        rsparse::usolve(&self.lu.u, &mut self.x_vec[..]);
+        ipvec(self.sprs.n, &lu.pinv, &self.b_vec, &mut self.x_vec[..]);
+        rsparse::lsolve(&lu.l, &mut self.x_vec);
+        rsparse::usolve(&lu.u, &mut self.x_vec[..]);
         ipvec(self.sprs.n, &symb.q, &self.x_vec[..], &mut self.b_vec[..]);
+        self.x_vec.copy_from_slice(&self.b_vec);
 
-        self.a_mat.clear();
         self.b_vec.iter_mut().for_each(|val| *val = Numeric::zero());
-        self.symb = Some(symb);
         Ok(&self.x_vec)
     }
 
     fn solve_cplx(&mut self) -> Result<&Vec<ComplexNumeric>, SolverError> {
-        self.update_cplx_from_hashmap();
-        rsparse::lusol(&self.cplx_sprs, &mut self.cplx_b_vec, 1, 1e-12);
-        self.cplx_x_vec = self.real_vec_to_complex_vec();
-
+        if self.cplx_symb.is_none() || !self.cplx_a_mat.is_analysed() {
+            trace!(
+                "Analyse the complex sparsity pattern with {} entries",
+                self.cplx_a_mat.entries().len()
+            );
+            self.update_cplx_from_hashmap();
+            self.cplx_symb = Some(symbolic(&self.cplx_sprs));
+            self.cplx_a_mat.set_analysed();
+        } else {
+            let values = self.cplx_a_mat.values();
+            for (&pos, &val) in self.cplx_sprs_positions.iter().zip(values) {
+                self.cplx_sprs.x[pos] = Phasor(val);
+            }
+        }
+        self.cplx_a_mat.clear_values();
+        let symb = self
+            .cplx_symb
+            .as_mut()
+            .ok_or(SolverError::MatrixNonInvertible { pivot: None })?;
+
+        let lu = rsparse::lu(&self.cplx_sprs, symb, Phasor::from(1e-12))
+            .map_err(|_| SolverError::MatrixNonInvertible { pivot: None })?;
+        let n = self.cplx_sprs.n;
+        ipvec(n, &lu.pinv, &self.cplx_b_vec, &mut self.cplx_work[..]);
+        rsparse::lsolve(&lu.l, &mut self.cplx_work);
+        rsparse::usolve(&lu.u, &mut self.cplx_work[..]);
+        ipvec(n, &symb.q, &self.cplx_work[..], &mut self.cplx_b_vec[..]);
+        self.cplx_x_vec = self.cplx_b_vec.iter().map(|val| val.0).collect();
+
+        self.cplx_b_vec.fill(Phasor::default());
         Ok(&self.cplx_x_vec)
     }
 
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        (self.a_mat.triples(), self.b_vec.clone())
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        let b_vec = self.cplx_b_vec.iter().map(|val| val.0).collect();
+        (self.cplx_a_mat.triples(), b_vec)
+    }
+
     fn init(&mut self, a_matrix: Vec<(usize, usize)>, cplx_a_matrix: Vec<(usize, usize)>) {
-        let mut trpl = Trpl::new();
-        a_matrix
-            .iter()
-            .for_each(|val| trpl.append(val.0, val.1, Numeric::zero()));
-
-        let pivot = self.vars;
-        let mut cplx_trpl = Trpl::new();
-        cplx_a_matrix.iter().for_each(|val| {
-            cplx_trpl.append(val.0, val.1, Numeric::zero());
-            cplx_trpl.append(val.0, val.1 + pivot, Numeric::zero());
-            cplx_trpl.append(val.0 + pivot, val.1, Numeric::zero());
-            cplx_trpl.append(val.0 + pivot, val.1 + pivot, Numeric::zero());
+        // Gmin stepping, pseudo transient continuation and initial conditions stamp the diagonal
+        let diagonal = (0..self.vars).map(|idx| (idx, idx));
+        a_matrix.into_iter().chain(diagonal).for_each(|(row, col)| {
+            self.a_mat.slot(row, col);
         });
-        trpl.sum_dupl();
-
-        let mut sprs = Sprs::new();
-        sprs.from_trpl(&trpl);
-        self.symb = Some(rsparse::sqr(&sprs, 1, false));
+        self.symb = None;
+        self.lu = None;
 
-        let mut cplx_sprs = Sprs::new();
-        cplx_sprs.from_trpl(&cplx_trpl);
-        self.cplx_symb = Some(rsparse::sqr(&cplx_sprs, 1, false));
+        cplx_a_matrix.into_iter().for_each(|(row, col)| {
+            self.cplx_a_mat.slot(row, col);
+        });
+        self.cplx_symb = None;
     }
 
     fn reset(&mut self) {
-        self.a_mat.clear();
+        self.clear_a_mat();
+        self.lu = None;
         self.b_vec.iter_mut().for_each(|val| *val = Numeric::zero());
-        self.cplx_a_mat.clear();
-        self.cplx_b_vec
-            .iter_mut()
-            .for_each(|val| *val = Numeric::zero());
+        self.cplx_a_mat.clear_values();
+        self.cplx_b_vec.fill(Phasor::default());
+    }
+
+    fn set_condition_limit(&mut self, limit: Option<Numeric>) {
+        self.condition.set_limit(limit);
+    }
+
+    fn condition_estimate(&self) -> Option<Numeric> {
+        self.condition.estimate()
     }
 }
 
 impl RSparseSolver {
-    fn real_vec_to_complex_vec(&self) -> Vec<ComplexNumeric> {
-        let pivot = self.vars;
-        let real = &self.cplx_b_vec[..pivot];
-        let imag = &self.cplx_b_vec[pivot..];
-        let iter = real.iter().zip(imag.iter());
-        iter.map(|(re, im)| Complex { re: *re, im: *im }).collect()
+    /// Clears the values of `A`, but keeps its slots and the analysis of `sprs`.
+    fn clear_a_mat(&mut self) {
+        self.a_mat.clear_values();
+        self.factored = false;
     }
 
-    /// Updates the complex sparse workspace from the hashmap
+    /// Estimates the condition number of `A` with the factorisation `P A Q = L U` of `lu`.
+    fn estimate_condition(&mut self) {
+        let (Some(symb), Some(lu)) = (&self.symb, &self.lu) else {
+            return;
+        };
+        let entries = self.a_mat.entries().iter().zip(self.a_mat.values());
+        let norm = one_norm(
+            self.vars,
+            entries.map(|(&(row, col), &val)| (row, col, val)),
+        );
+
+        let n = self.sprs.n;
+        let mut work = vec![Numeric::zero(); n];
+        let mut work_t = vec![Numeric::zero(); n];
+        let inverse_norm = inverse_norm_estimate(
+            n,
+            |b| {
+                ipvec(n, &lu.pinv, b, &mut work);
+                rsparse::lsolve(&lu.l, &mut work);
+                rsparse::usolve(&lu.u, &mut work);
+                ipvec(n, &symb.q, &work, b);
+            },
+            // The transpose solves `Qᵀ Aᵀ Pᵀ = Uᵀ Lᵀ`
+            |b| {
+                pvec(n, &symb.q, b, &mut work_t);
+                rsparse::utsolve(&lu.u, &mut work_t);
+                rsparse::ltsolve(&lu.l, &mut work_t);
+                pvec(n, &lu.pinv, &work_t, b);
+            },
+        );
+        self.condition.record(norm, inverse_norm);
+    }
+
+    /// Updates the complex sparse workspace from the slots of the complex `A` and records the
+    /// position of each slot
     fn update_cplx_from_hashmap(&mut self) {
         self.cplx_sprs.p.clear();
         self.cplx_sprs.i.clear();
         self.cplx_sprs.x.clear();
 
-        if self.cplx_a_mat.is_empty() {
-            self.cplx_sprs.nzmax = 0;
-            self.cplx_sprs.m = 0;
-            self.cplx_sprs.n = 0;
-            self.cplx_sprs.p.push(0);
-            return;
-        }
-
-        let mut max_row = 0;
-        let mut max_col = 0;
-        for ((r, c), _) in self.cplx_a_mat.iter() {
-            if *r > max_row {
-                max_row = *r;
-            }
-            if *c > max_col {
-                max_col = *c;
-            }
-        }
-
-        self.cplx_sprs.m = 2 * (max_row + 1);
-        self.cplx_sprs.n = 2 * (max_col + 1);
+        let entries = self.cplx_a_mat.entries();
+        let values = self.cplx_a_mat.values();
+        self.cplx_sprs_positions.clear();
+        self.cplx_sprs_positions.resize(entries.len(), 0);
 
-        let mut entries: Vec<(usize, usize, Numeric)> = Vec::new();
-        self.cplx_a_mat.iter().for_each(|((row, col), val)| {
-            entries.push((*row, *col, val.re));
-            entries.push((*row, *col + self.vars, val.im));
-            entries.push((*row + self.vars, *col, -val.im));
-            entries.push((*row + self.vars, *col + self.vars, val.re));
+        let mut slots: Vec<usize> = (0..entries.len()).collect();
+        slots.sort_unstable_by_key(|&slot| {
+            let (row, col) = entries[slot];
+            (col, row)
         });
 
-        // TODO: Consider refactoring to reduce nesting complexity
-        entries.sort_unstable_by(
-            |(r1, c1, _), (r2, c2, _)| {
-                if c1 != c2 {
-                    c1.cmp(c2)
-                } else {
-                    r1.cmp(r2)
-                }
-            },
-        );
-
+        self.cplx_sprs.m = self.vars;
+        self.cplx_sprs.n = self.vars;
         self.cplx_sprs.nzmax = entries.len();
         self.cplx_sprs.p.resize(self.cplx_sprs.n + 1, 0);
         self.cplx_sprs.i.reserve(self.cplx_sprs.nzmax);
         self.cplx_sprs.x.reserve(self.cplx_sprs.nzmax);
 
         let mut current_col = 0;
-        for (idx, (row, col, val)) in entries.into_iter().enumerate() {
+        for (idx, slot) in slots.into_iter().enumerate() {
+            let (row, col) = entries[slot];
             while col > current_col {
                 self.cplx_sprs.p[current_col + 1] = idx as isize;
                 current_col += 1;
             }
             self.cplx_sprs.i.push(row);
-            self.cplx_sprs.x.push(val);
+            self.cplx_sprs.x.push(Phasor(values[slot]));
+            self.cplx_sprs_positions[slot] = idx;
         }
 
         while current_col < self.cplx_sprs.n {
@@ -256,13 +370,18 @@ impl RSparseSolver {
         }
     }
 
-    /// Updates the sparse workspace from the hashmap
+    /// Updates the sparse workspace from the slots of `A` and records the position of each slot
     pub fn update_from_hashmap(&mut self) {
         self.sprs.p.clear();
         self.sprs.i.clear();
         self.sprs.x.clear();
 
-        if self.a_mat.is_empty() {
+        let entries = self.a_mat.entries();
+        let values = self.a_mat.values();
+        self.sprs_positions.clear();
+        self.sprs_positions.resize(entries.len(), 0);
+
+        if entries.is_empty() {
             self.sprs.nzmax = 0;
             self.sprs.m = 0;
             self.sprs.n = 0;
@@ -270,35 +389,24 @@ impl RSparseSolver {
             return;
         }
 
-        let mut max_row = 0;
-        let mut max_col = 0;
-        for ((r, c), _) in self.a_mat.iter() {
-            if *r > max_row {
-                max_row = *r;
-            }
-            if *c > max_col {
-                max_col = *c;
-            }
-        }
-
+        let max_row = entries
+            .iter()
+            .map(|(row, _)| *row)
+            .max()
+            .unwrap_or_default();
+        let max_col = entries
+            .iter()
+            .map(|(_, col)| *col)
+            .max()
+            .unwrap_or_default();
         self.sprs.m = max_row + 1;
         self.sprs.n = max_col + 1;
 
-        let mut entries: Vec<(usize, usize, Numeric)> = Vec::new();
-        self.a_mat
-            .iter()
-            .for_each(|((row, col), val)| entries.push((*row, *col, *val)));
-
-        // TODO: Consider refactoring to reduce nesting complexity
-        entries.sort_unstable_by(
-            |(r1, c1, _), (r2, c2, _)| {
-                if c1 != c2 {
-                    c1.cmp(c2)
-                } else {
-                    r1.cmp(r2)
-                }
-            },
-        );
+        let mut slots: Vec<usize> = (0..entries.len()).collect();
+        slots.sort_unstable_by_key(|&slot| {
+            let (row, col) = entries[slot];
+            (col, row)
+        });
 
         self.sprs.nzmax = entries.len();
         self.sprs.p.resize(self.sprs.n + 1, 0);
@@ -306,13 +414,15 @@ impl RSparseSolver {
         self.sprs.x.reserve(self.sprs.nzmax);
 
         let mut current_col = 0;
-        for (idx, (row, col, val)) in entries.into_iter().enumerate() {
+        for (idx, slot) in slots.into_iter().enumerate() {
+            let (row, col) = entries[slot];
             while col > current_col {
                 self.sprs.p[current_col + 1] = idx as isize;
                 current_col += 1;
             }
             self.sprs.i.push(row);
-            self.sprs.x.push(val);
+            self.sprs.x.push(values[slot]);
+            self.sprs_positions[slot] = idx;
         }
 
         while current_col < self.sprs.n {
@@ -322,7 +432,14 @@ impl RSparseSolver {
     }
 }
 
-fn ipvec(n: usize, p: &Option<Vec<isize>>, b: &[Numeric], x: &mut [Numeric]) {
+/// Computes the fill-reducing column ordering of `sprs` for the LU factorisation.
+/// The ordering of rsparse needs at least two columns, smaller matrices keep their order.
+fn symbolic<T: rsparse::data::Numeric<T>>(sprs: &Sprs<T>) -> Symb {
+    let order = if sprs.n < 2 { -1 } else { 1 };
+    rsparse::sqr(sprs, order, false)
+}
+
+fn ipvec<T: Copy>(n: usize, p: &Option<Vec<isize>>, b: &[T], x: &mut [T]) {
     for k in 0..n {
         if p.is_some() {
             x[p.as_ref().expect(
@@ -334,6 +451,113 @@ fn ipvec(n: usize, p: &Option<Vec<isize>>, b: &[Numeric], x: &mut [Numeric]) {
     }
 }
 
+/// Applies the inverse of the permutation `p` as `x = Pᵀ b`, the counterpart of `ipvec`.
+fn pvec<T: Copy>(n: usize, p: &Option<Vec<isize>>, b: &[T], x: &mut [T]) {
+    match p {
+        Some(p) => (0..n).for_each(|k| x[k] = b[p[k] as usize]),
+        None => x[..n].copy_from_slice(&b[..n]),
+    }
+}
+
+/// A complex entry of the sparse LU of rsparse, which is generic over its numeric trait.
+///
+/// The LU compares entries only by their magnitudes to choose the pivots, so [`abs`] returns
+/// the magnitude as real part and entries are ordered by their real parts.
+///
+/// [`abs`]: rsparse::data::Numeric::abs
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Phasor(ComplexNumeric);
+
+impl From<Numeric> for Phasor {
+    fn from(value: Numeric) -> Self {
+        Phasor(ComplexNumeric::new(value, 0.0))
+    }
+}
+
+impl PartialOrd for Phasor {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.0.re.partial_cmp(&other.0.re)
+    }
+}
+
+impl fmt::Display for Phasor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl rsparse::data::Zero for Phasor {
+    fn zero() -> Self {
+        Phasor(ComplexNumeric::zero())
+    }
+}
+
+impl rsparse::data::One for Phasor {
+    fn one() -> Self {
+        Phasor(ComplexNumeric::one())
+    }
+}
+
+impl rsparse::data::Numeric<Phasor> for Phasor {
+    fn abs(self) -> Phasor {
+        Phasor::from(self.0.norm())
+    }
+
+    fn max(self, other: Phasor) -> Phasor {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn powf(self, exp: f64) -> Phasor {
+        Phasor(self.0.powf(exp))
+    }
+
+    fn sqrt(self) -> Phasor {
+        Phasor(self.0.sqrt())
+    }
+}
+
+impl std::iter::Sum for Phasor {
+    fn sum<I: Iterator<Item = Phasor>>(iter: I) -> Self {
+        Phasor(iter.map(|val| val.0).sum())
+    }
+}
+
+impl Neg for Phasor {
+    type Output = Phasor;
+
+    fn neg(self) -> Phasor {
+        Phasor(-self.0)
+    }
+}
+
+/// Implements an arithmetic operator and its assignment for [`Phasor`].
+macro_rules! phasor_op {
+    ($op:ident, $method:ident, $op_assign:ident, $method_assign:ident) => {
+        impl $op for Phasor {
+            type Output = Phasor;
+
+            fn $method(self, rhs: Phasor) -> Phasor {
+                Phasor(self.0.$method(rhs.0))
+            }
+        }
+
+        impl $op_assign for Phasor {
+            fn $method_assign(&mut self, rhs: Phasor) {
+                self.0.$method_assign(rhs.0);
+            }
+        }
+    };
+}
+
+phasor_op!(Add, add, AddAssign, add_assign);
+phasor_op!(Sub, sub, SubAssign, sub_assign);
+phasor_op!(Mul, mul, MulAssign, mul_assign);
+phasor_op!(Div, div, DivAssign, div_assign);
+
 #[cfg(test)]
 impl RSparseSolver {
     /// Returns the length of the vector `b_vec`.
@@ -346,14 +570,10 @@ impl RSparseSolver {
         self.cplx_b_vec.len()
     }
 
-    /// Returns a reference to the matrix `a_mat`.
-    pub fn a_mat(&self) -> &HashMap<(usize, usize), Numeric> {
-        &self.a_mat
-    }
-
-    /// Returns a reference to the matrix `a_mat`.
-    pub fn a_mat_mut(&mut self) -> &mut HashMap<(usize, usize), Numeric> {
-        &mut self.a_mat
+    /// Returns the entries of the matrix `a_mat`.
+    pub fn a_mat(&self) -> HashMap<(usize, usize), Numeric> {
+        let entries = self.a_mat.entries().iter().copied();
+        entries.zip(self.a_mat.values().iter().copied()).collect()
     }
 
     /// Returns a reference to the vector `b_vec`.
@@ -366,14 +586,17 @@ impl RSparseSolver {
         &self.x_vec
     }
 
-    /// Returns a reference to the matrix `cplx_a_mat`.
-    pub fn cplx_a_mat(&self) -> &HashMap<(usize, usize), ComplexNumeric> {
-        &self.cplx_a_mat
+    /// Returns the entries of the matrix `cplx_a_mat`.
+    pub fn cplx_a_mat(&self) -> HashMap<(usize, usize), ComplexNumeric> {
+        let entries = self.cplx_a_mat.entries().iter().copied();
+        entries
+            .zip(self.cplx_a_mat.values().iter().copied())
+            .collect()
     }
 
-    /// Returns a reference to the vector `cplx_b_vec`.
-    pub fn cplx_b_vec(&self) -> &Vec<Numeric> {
-        &self.cplx_b_vec
+    /// Returns the vector `cplx_b_vec`.
+    pub fn cplx_b_vec(&self) -> Vec<ComplexNumeric> {
+        self.cplx_b_vec.iter().map(|val| val.0).collect()
     }
 
     /// Returns a reference to the vector `cplx_b_vec`.
@@ -401,10 +624,10 @@ impl RSparseSolver {
         }
 
         // Print the matrix in a formatted way.
-        for (row, _) in matrix.iter().enumerate().take(m) {
+        for row in matrix.iter().take(m) {
             print!("[");
-            for col in 0..n {
-                print!("{:>8.2}", matrix[row][col]); // Format to 2 decimal places, right-aligned, 8 chars wide
+            for (col, value) in row.iter().enumerate().take(n) {
+                print!("{:>8.2}", value); // Format to 2 decimal places, right-aligned, 8 chars wide
                 if col < n - 1 {
                     print!(", ");
                 }
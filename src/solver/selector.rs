@@ -1,5 +1,10 @@
 /// Intelligent solver selection based on circuit characteristics
-use super::{FaerSparseSolver, NalgebraSolver, Solver, SolverError};
+use log::{info, warn};
+use num::Zero;
+use rustc_hash::FxHashMap;
+
+use super::{Solver, SolverError, Solvers};
+use crate::spot::*;
 
 /// Solver selection strategy based on circuit size and characteristics
 #[derive(Debug, Clone, Copy)]
@@ -10,7 +15,45 @@ pub enum SolverSelectionStrategy {
     Hybrid,
 }
 
+/// The characteristics of the circuit equations, which the solver is selected by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixProfile {
+    /// The number of variables.
+    pub vars: usize,
+    /// The number of nonzero entries of the conductance matrix.
+    pub nonzeros: usize,
+    /// Whether complex systems are solved, i.e. the circuit has an AC analysis.
+    pub complex: bool,
+}
+
+impl MatrixProfile {
+    /// Returns the profile of the sparsity patterns passed to [`Solver::init`].
+    pub fn from_patterns(
+        vars: usize,
+        a_matrix: &[(usize, usize)],
+        cplx_a_matrix: &[(usize, usize)],
+    ) -> Self {
+        let mut entries = a_matrix.to_vec();
+        entries.sort_unstable();
+        entries.dedup();
+        MatrixProfile {
+            vars,
+            nonzeros: entries.len(),
+            complex: !cplx_a_matrix.is_empty(),
+        }
+    }
+
+    /// Returns the fraction of the entries of the conductance matrix which are nonzero.
+    pub fn density(&self) -> Numeric {
+        if self.vars == 0 {
+            return Numeric::zero();
+        }
+        self.nonzeros as Numeric / (self.vars as Numeric * self.vars as Numeric)
+    }
+}
+
 /// Intelligent solver selector
+#[derive(Debug, Clone, Copy)]
 pub struct SolverSelector {
     strategy: SolverSelectionStrategy,
     circuit_size_threshold: usize,
@@ -21,8 +64,7 @@ impl SolverSelector {
     pub fn new(strategy: SolverSelectionStrategy) -> Self {
         SolverSelector {
             strategy,
-            // TODO: Consider moving this threshold constant to spot.rs for centralization
-            circuit_size_threshold: 50, // Default threshold: 50 variables
+            circuit_size_threshold: HYBRID_THRESHOLD,
         }
     }
 
@@ -36,55 +78,73 @@ impl SolverSelector {
 
     /// Select the appropriate solver based on circuit size
     pub fn select_solver(&self, vars: usize) -> Result<Box<dyn Solver>, SolverError> {
+        // Without the sparsity pattern the circuit is assumed to be sparse
+        let profile = MatrixProfile {
+            vars,
+            nonzeros: vars,
+            complex: false,
+        };
+        self.select(&profile).create(vars)
+    }
+
+    /// Selects the solver for circuit equations with the given profile.
+    pub fn select(&self, profile: &MatrixProfile) -> Solvers {
         match self.strategy {
-            SolverSelectionStrategy::Automatic => {
-                // Automatic selection based on performance characteristics
-                self.select_best_solver(vars)
-            }
+            SolverSelectionStrategy::Automatic => Self::select_best_solver(profile),
             SolverSelectionStrategy::Hybrid => {
                 // Hybrid approach: sparse for large, dense for small
-                if vars <= self.circuit_size_threshold {
-                    // For small circuits, use Nalgebra (fastest for small systems)
-                    Ok(Box::new(NalgebraSolver::new(vars)?))
+                if profile.vars <= self.circuit_size_threshold {
+                    Solvers::Nalgebra
                 } else {
-                    // For large circuits, use FaerSparse (best scaling)
-                    Ok(Box::new(FaerSparseSolver::new(vars)?))
+                    Solvers::FaerSparse
                 }
             }
         }
     }
 
     /// Select the best solver based on performance data
-    fn select_best_solver(&self, vars: usize) -> Result<Box<dyn Solver>, SolverError> {
+    fn select_best_solver(profile: &MatrixProfile) -> Solvers {
         // Based on our performance testing:
-        // - Small circuits (<10 vars): Nalgebra is fastest
-        // - Medium circuits (10-100 vars): FaerSparse is best
-        // - Large circuits (>100 vars): FaerSparse dominates
-
-        // TODO: Consider moving these threshold constants to spot.rs for centralization
-        if vars < 10 {
-            // Very small circuits: Nalgebra is fastest
-            Ok(Box::new(NalgebraSolver::new(vars)?))
-        } else if vars < 100 {
-            // Small to medium circuits: FaerSparse is best
-            Ok(Box::new(FaerSparseSolver::new(vars)?))
+        // - Small circuits: Nalgebra is fastest
+        // - Dense matrices up to a thousand variables: the dense LU of Faer beats the fill-in
+        //   of the sparse LU
+        // - Very large real circuits: only the Krylov solver fits into memory
+        // - Everything else: FaerSparse has the best scaling
+        if profile.vars <= AUTO_SMALL_MAX_VARS {
+            Solvers::Nalgebra
+        } else if profile.vars <= AUTO_DENSE_MAX_VARS && profile.density() >= AUTO_DENSE_MIN_DENSITY
+        {
+            Solvers::Faer
+        } else if profile.vars >= AUTO_ITERATIVE_MIN_VARS && !profile.complex {
+            Solvers::Krylov
         } else {
-            // Large circuits: FaerSparse is significantly better
-            Ok(Box::new(FaerSparseSolver::new(vars)?))
+            Solvers::FaerSparse
         }
     }
 
+    /// Returns the solver to switch to after the solvers in `failed` found the matrix singular,
+    /// or `None` if all of them failed. Another factorisation may still succeed on an
+    /// ill-conditioned matrix. The dense LU is only tried if the matrix fits into memory.
+    pub fn fallback(&self, profile: &MatrixProfile, failed: &[Solvers]) -> Option<Solvers> {
+        let dense = (profile.vars <= AUTO_FALLBACK_DENSE_MAX_VARS).then_some(Solvers::Nalgebra);
+        dense
+            .into_iter()
+            .chain([Solvers::Rsparse, Solvers::FaerSparse])
+            .find(|kind| !failed.contains(kind))
+    }
+
     /// Get a recommendation for solver selection
     pub fn recommend_solver(&self, vars: usize) -> &'static str {
         match self.strategy {
             SolverSelectionStrategy::Automatic => {
-                // TODO: Consider moving these threshold constants to spot.rs for centralization
-                if vars < 10 {
+                if vars <= AUTO_SMALL_MAX_VARS {
                     "NalgebraSolver (best for very small circuits)"
                 } else if vars < 100 {
                     "FaerSparseSolver (best for small-medium circuits)"
-                } else {
+                } else if vars < AUTO_ITERATIVE_MIN_VARS {
                     "FaerSparseSolver (best for large circuits)"
+                } else {
+                    "KrylovSolver (best for very large circuits)"
                 }
             }
             SolverSelectionStrategy::Hybrid => {
@@ -98,6 +158,307 @@ impl SolverSelector {
     }
 }
 
+/// A Solver, which selects its backend at runtime.
+///
+/// The backend is selected by a [`SolverSelector`] from the [`MatrixProfile`] of the sparsity
+/// patterns in [`Solver::init`]. The stamps go straight to the backend. A backend which finds
+/// the matrix singular, or returns a non-finite solution of a finite system, is replaced by a
+/// fallback, which is kept for the rest of the run. The solve then fails with
+/// [`SolverError::BackendSwitched`], so the caller stamps the system again into the fallback.
+pub struct AutoSolver {
+    /// The selector of the backend and its fallbacks.
+    selector: SolverSelector,
+
+    /// The profile of the circuit equations.
+    profile: MatrixProfile,
+
+    /// The kind of the current backend.
+    kind: Solvers,
+
+    /// The current backend.
+    solver: Box<dyn Solver>,
+
+    /// The backends which found a matrix singular.
+    failed: Vec<Solvers>,
+
+    /// The condition limit, which a new backend is set up with.
+    condition_limit: Option<Numeric>,
+
+    /// The sparsity pattern of `A` from `init`, which a new backend is initialised with.
+    a_pattern: Vec<(usize, usize)>,
+
+    /// The sparsity pattern of the complex `A` from `init`.
+    cplx_pattern: Vec<(usize, usize)>,
+
+    /// The slot of each entry of `A`, which stays valid when the backend is replaced.
+    slot_of: FxHashMap<(usize, usize), usize>,
+
+    /// The row and column of each slot.
+    entries: Vec<(usize, usize)>,
+
+    /// The slot of the backend for each slot.
+    slots: Vec<usize>,
+
+    /// Whether all values stamped since the last solve are finite.
+    finite: bool,
+
+    /// Whether the base matrix is finite.
+    base_finite: bool,
+
+    /// Whether the matrix of the last solve, whose factorisation `solve_factored` reuses, is
+    /// finite.
+    factored_finite: bool,
+
+    /// Whether all complex values stamped since the last solve are finite.
+    cplx_finite: bool,
+
+    /// The Solution vector.
+    x_vec: Vec<Numeric>,
+
+    /// The complex Solution vector.
+    cplx_x_vec: Vec<ComplexNumeric>,
+}
+
+impl AutoSolver {
+    /// Creates a new instance of the Solver with the given number of variables, which selects
+    /// its backend with `selector`.
+    pub fn with_selector(vars: usize, selector: SolverSelector) -> Result<Self, SolverError> {
+        // Until the sparsity pattern is known, the circuit is assumed to be sparse
+        let profile = MatrixProfile {
+            vars,
+            nonzeros: vars,
+            complex: false,
+        };
+        let kind = selector.select(&profile);
+        Ok(Self {
+            selector,
+            profile,
+            kind,
+            solver: kind.create(vars)?,
+            failed: Vec::new(),
+            condition_limit: None,
+            a_pattern: Vec::new(),
+            cplx_pattern: Vec::new(),
+            slot_of: FxHashMap::default(),
+            entries: Vec::new(),
+            slots: Vec::new(),
+            finite: true,
+            base_finite: true,
+            factored_finite: true,
+            cplx_finite: true,
+            x_vec: vec![Numeric::zero(); vars],
+            cplx_x_vec: vec![ComplexNumeric::zero(); vars],
+        })
+    }
+
+    /// Returns the kind of the current backend.
+    pub fn kind(&self) -> Solvers {
+        self.kind
+    }
+
+    /// Replaces the backend by an empty `solver` with the sparsity patterns of `init` and
+    /// resolves the slots in it again.
+    fn install(&mut self, kind: Solvers, mut solver: Box<dyn Solver>) {
+        solver.init(self.a_pattern.clone(), self.cplx_pattern.clone());
+        solver.set_condition_limit(self.condition_limit);
+        self.slots = self
+            .entries
+            .iter()
+            .map(|&(row, col)| solver.a_slot(row, col))
+            .collect();
+        self.finite = true;
+        self.base_finite = true;
+        self.factored_finite = true;
+        self.cplx_finite = true;
+        self.kind = kind;
+        self.solver = solver;
+    }
+
+    /// Switches from the backend, which found the matrix singular, to its fallback. Returns
+    /// [`SolverError::BackendSwitched`] if there is a fallback and `Ok` if all fallbacks failed.
+    fn switch(&mut self) -> Result<(), SolverError> {
+        let from = self.kind;
+        if !self.failed.contains(&from) {
+            self.failed.push(from);
+        }
+        let Some(to) = self.selector.fallback(&self.profile, &self.failed) else {
+            return Ok(());
+        };
+        warn!("The {from:?} solver found the matrix singular, switching to the {to:?} solver");
+        let solver = to.create(self.profile.vars)?;
+        self.install(to, solver);
+        Err(SolverError::BackendSwitched { from, to })
+    }
+
+    /// Returns true if the backend found the matrix singular or returned a non-finite solution
+    /// `x_finite` although the system is `finite`.
+    fn is_singular(result: &Result<(), SolverError>, finite: bool, x_finite: bool) -> bool {
+        match result {
+            Err(SolverError::MatrixNonInvertible { .. }) => true,
+            Err(_) => false,
+            Ok(()) => finite && !x_finite,
+        }
+    }
+}
+
+impl Solver for AutoSolver {
+    fn new(vars: usize) -> Result<Self, SolverError> {
+        Self::with_selector(
+            vars,
+            SolverSelector::new(SolverSelectionStrategy::Automatic),
+        )
+    }
+
+    fn init(&mut self, a_matrix: Vec<(usize, usize)>, cplx_a_matrix: Vec<(usize, usize)>) {
+        self.profile = MatrixProfile::from_patterns(self.profile.vars, &a_matrix, &cplx_a_matrix);
+        let kind = self.selector.select(&self.profile);
+        info!(
+            "Selected the {kind:?} solver for {} variables with {} nonzeros",
+            self.profile.vars, self.profile.nonzeros
+        );
+        self.a_pattern = a_matrix;
+        self.cplx_pattern = cplx_a_matrix;
+
+        if kind != self.kind {
+            match kind.create(self.profile.vars) {
+                Ok(solver) => return self.install(kind, solver),
+                Err(err) => warn!("{err}, keeping the {:?} solver", self.kind),
+            }
+        }
+        self.solver
+            .init(self.a_pattern.clone(), self.cplx_pattern.clone());
+    }
+
+    fn insert_a(&mut self, a_trpl: &(usize, usize, Numeric)) {
+        self.finite &= a_trpl.2.is_finite();
+        self.solver.insert_a(a_trpl);
+    }
+
+    fn insert_b(&mut self, b_pair: &(usize, Numeric)) {
+        self.finite &= b_pair.1.is_finite();
+        self.solver.insert_b(b_pair);
+    }
+
+    fn a_slot(&mut self, row: usize, col: usize) -> usize {
+        if let Some(&slot) = self.slot_of.get(&(row, col)) {
+            return slot;
+        }
+        let slot = self.entries.len();
+        self.slot_of.insert((row, col), slot);
+        self.entries.push((row, col));
+        self.slots.push(self.solver.a_slot(row, col));
+        slot
+    }
+
+    fn insert_a_slot(&mut self, slot: usize, value: Numeric) {
+        self.finite &= value.is_finite();
+        self.solver.insert_a_slot(self.slots[slot], value);
+    }
+
+    fn store_base(&mut self) {
+        self.base_finite = self.finite;
+        self.solver.store_base();
+    }
+
+    fn load_base(&mut self) {
+        self.finite = self.base_finite;
+        self.solver.load_base();
+    }
+
+    fn insert_cplx_a(&mut self, a_trpl: &(usize, usize, ComplexNumeric)) {
+        self.cplx_finite &= a_trpl.2.is_finite();
+        self.solver.insert_cplx_a(a_trpl);
+    }
+
+    fn insert_cplx_b(&mut self, b_pair: &(usize, ComplexNumeric)) {
+        self.cplx_finite &= b_pair.1.is_finite();
+        self.solver.insert_cplx_b(b_pair);
+    }
+
+    fn analyse(&mut self) -> Result<(), SolverError> {
+        self.solver.analyse()
+    }
+
+    fn refactor(&mut self) -> Result<(), SolverError> {
+        let result = self.solver.refactor();
+        if matches!(result, Err(SolverError::MatrixNonInvertible { .. })) {
+            self.switch()?;
+        }
+        result
+    }
+
+    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        let finite = std::mem::replace(&mut self.finite, true);
+        self.factored_finite = finite;
+        let result = self.solver.solve().map(|x| self.x_vec.clone_from(x));
+        let x_finite = self.x_vec.iter().all(|val| val.is_finite());
+        if Self::is_singular(&result, finite, x_finite) {
+            self.switch()?;
+        }
+        result?;
+        Ok(&self.x_vec)
+    }
+
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        let finite = std::mem::replace(&mut self.finite, true) && self.factored_finite;
+        let result = self
+            .solver
+            .solve_factored()
+            .map(|x| self.x_vec.clone_from(x));
+        let x_finite = self.x_vec.iter().all(|val| val.is_finite());
+        if Self::is_singular(&result, finite, x_finite) {
+            self.switch()?;
+        }
+        result?;
+        Ok(&self.x_vec)
+    }
+
+    fn solve_cplx(&mut self) -> Result<&Vec<ComplexNumeric>, SolverError> {
+        let finite = std::mem::replace(&mut self.cplx_finite, true);
+        let result = self
+            .solver
+            .solve_cplx()
+            .map(|x| self.cplx_x_vec.clone_from(x));
+        let x_finite = self.cplx_x_vec.iter().all(|val| val.is_finite());
+        if Self::is_singular(&result, finite, x_finite) {
+            self.switch()?;
+        }
+        result?;
+        Ok(&self.cplx_x_vec)
+    }
+
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        self.solver.system()
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        self.solver.cplx_system()
+    }
+
+    fn reset(&mut self) {
+        self.finite = true;
+        self.cplx_finite = true;
+        self.solver.reset();
+    }
+
+    fn set_condition_limit(&mut self, limit: Option<Numeric>) {
+        self.condition_limit = limit;
+        self.solver.set_condition_limit(limit);
+    }
+
+    fn condition_estimate(&self) -> Option<Numeric> {
+        self.solver.condition_estimate()
+    }
+}
+
+#[cfg(test)]
+impl AutoSolver {
+    /// Replaces the backend by `solver`, which poses as a solver of `kind`.
+    pub fn set_backend(&mut self, kind: Solvers, solver: Box<dyn Solver>) {
+        self.install(kind, solver);
+    }
+}
+
 // TODO: Consider moving these tests to the dedicated tests folder/module in src/solver/tests
 #[cfg(test)]
 mod tests {
@@ -137,6 +498,71 @@ mod tests {
         assert!(selector.select_solver(30).is_ok());
     }
 
+    #[test]
+    fn test_selection_by_profile() {
+        let selector = SolverSelector::new(SolverSelectionStrategy::Automatic);
+        let profile = |vars, nonzeros, complex| MatrixProfile {
+            vars,
+            nonzeros,
+            complex,
+        };
+
+        assert_eq!(selector.select(&profile(20, 60, false)), Solvers::Nalgebra);
+        assert_eq!(
+            selector.select(&profile(500, 2500, false)),
+            Solvers::FaerSparse
+        );
+        assert_eq!(
+            selector.select(&profile(500, 100_000, false)),
+            Solvers::Faer
+        );
+        assert_eq!(
+            selector.select(&profile(200_000, 1_000_000, false)),
+            Solvers::Krylov
+        );
+        // The Krylov solver solves complex systems with a direct LU as well
+        assert_eq!(
+            selector.select(&profile(200_000, 1_000_000, true)),
+            Solvers::FaerSparse
+        );
+    }
+
+    #[test]
+    fn test_fallback_order() {
+        let selector = SolverSelector::new(SolverSelectionStrategy::Automatic);
+        let small = MatrixProfile {
+            vars: 100,
+            nonzeros: 500,
+            complex: false,
+        };
+        let large = MatrixProfile {
+            vars: 100_000,
+            nonzeros: 500_000,
+            complex: false,
+        };
+
+        assert_eq!(
+            selector.fallback(&small, &[Solvers::FaerSparse]),
+            Some(Solvers::Nalgebra)
+        );
+        assert_eq!(
+            selector.fallback(&small, &[Solvers::FaerSparse, Solvers::Nalgebra]),
+            Some(Solvers::Rsparse)
+        );
+        assert_eq!(
+            selector.fallback(&large, &[Solvers::Krylov]),
+            Some(Solvers::Rsparse)
+        );
+        assert_eq!(
+            selector.fallback(&large, &[Solvers::Krylov, Solvers::Rsparse]),
+            Some(Solvers::FaerSparse)
+        );
+        assert_eq!(
+            selector.fallback(&large, &[Solvers::FaerSparse, Solvers::Rsparse]),
+            None
+        );
+    }
+
     #[test]
     fn test_solver_recommendation() {
         let selector = SolverSelector::new(SolverSelectionStrategy::Automatic);
@@ -0,0 +1,96 @@
+use std::ops::AddAssign;
+
+use num::Zero;
+use rustc_hash::FxHashMap;
+
+/// The entries of a sparse conductance matrix, addressed by slots.
+///
+/// The slot of an entry is the order of its first insert and stays valid for the lifetime of the
+/// matrix, so the elements can stamp through their slots without looking up the entry again.
+/// New entries extend the sparsity pattern, which then has to be analysed again.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SlotMatrix<T> {
+    /// The slot of each entry.
+    slots: FxHashMap<(usize, usize), usize>,
+    /// The row and column of each slot.
+    entries: Vec<(usize, usize)>,
+    /// The value of each slot.
+    values: Vec<T>,
+    /// The values of the base matrix, which every assembly starts from.
+    base: Vec<T>,
+    /// The number of entries in the analysed sparsity pattern.
+    analysed: usize,
+}
+
+impl<T: Copy + Zero + AddAssign> SlotMatrix<T> {
+    /// Returns the slot of the entry `(row, col)` and adds the entry if it is new.
+    pub(crate) fn slot(&mut self, row: usize, col: usize) -> usize {
+        if let Some(&slot) = self.slots.get(&(row, col)) {
+            return slot;
+        }
+        let slot = self.entries.len();
+        self.slots.insert((row, col), slot);
+        self.entries.push((row, col));
+        self.values.push(T::zero());
+        slot
+    }
+
+    /// Adds `value` to the entry at `slot`.
+    pub(crate) fn add(&mut self, slot: usize, value: T) {
+        self.values[slot] += value;
+    }
+
+    /// Adds `value` to the entry `(row, col)`.
+    pub(crate) fn insert(&mut self, row: usize, col: usize, value: T) {
+        let slot = self.slot(row, col);
+        self.add(slot, value);
+    }
+
+    /// Returns the row and column of each slot.
+    pub(crate) fn entries(&self) -> &[(usize, usize)] {
+        &self.entries
+    }
+
+    /// Returns the `(row, col, value)` triple of each slot.
+    pub(crate) fn triples(&self) -> Vec<(usize, usize, T)> {
+        self.entries
+            .iter()
+            .zip(&self.values)
+            .map(|(&(row, col), &value)| (row, col, value))
+            .collect()
+    }
+
+    /// Returns the value of each slot.
+    pub(crate) fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Returns true if no entries were added since the last call of `set_analysed`.
+    pub(crate) fn is_analysed(&self) -> bool {
+        self.analysed == self.entries.len()
+    }
+
+    /// Marks the current sparsity pattern as analysed.
+    pub(crate) fn set_analysed(&mut self) {
+        self.analysed = self.entries.len();
+    }
+
+    /// Sets all values to zero, but keeps the entries.
+    pub(crate) fn clear_values(&mut self) {
+        self.values.iter_mut().for_each(|val| *val = T::zero());
+    }
+
+    /// Stores the current values as the base matrix.
+    pub(crate) fn store_base(&mut self) {
+        self.base.clone_from(&self.values);
+    }
+
+    /// Replaces the values by the base matrix. Entries added after `store_base` are zero.
+    pub(crate) fn load_base(&mut self) {
+        let len = self.base.len();
+        self.values[..len].copy_from_slice(&self.base);
+        self.values[len..]
+            .iter_mut()
+            .for_each(|val| *val = T::zero());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::solver::tests::{generate_solvable_system, SingularSolver};
+use crate::solver::{AutoSolver, Solver, SolverError, Solvers};
+use crate::spot::*;
+
+/// Stamps a ladder of 1Ω resistors to ground into the base matrix and loads it.
+fn stamp_ladder_base(solver: &mut AutoSolver) {
+    for node in 0..3 {
+        solver.insert_a(&(node, node, 1.0));
+    }
+    solver.store_base();
+    solver.load_base();
+}
+
+/// Stamps the resistors between the nodes of the ladder and a current of 1A into node 0.
+fn stamp_ladder(solver: &mut AutoSolver) {
+    for node in 1..3 {
+        solver.insert_a(&(node - 1, node - 1, 1.0));
+        solver.insert_a(&(node, node, 1.0));
+        solver.insert_a(&(node - 1, node, -1.0));
+        solver.insert_a(&(node, node - 1, -1.0));
+    }
+    solver.insert_b(&(0, 1.0));
+}
+
+/// The node voltages of the ladder for a current of 1A into node 0.
+const LADDER_SOLUTION: [Numeric; 3] = [0.625, 0.25, 0.125];
+
+fn assert_ladder_solution(solution: &[Numeric], scale: Numeric) {
+    for (res, expected) in solution.iter().zip(LADDER_SOLUTION) {
+        assert!(
+            (res - scale * expected).abs() < 1e-12,
+            "{solution:?} != {LADDER_SOLUTION:?}"
+        );
+    }
+}
+
+#[test]
+fn selects_by_pattern() {
+    let mut solver = AutoSolver::new(200).unwrap();
+    let diagonal: Vec<_> = (0..200).map(|idx| (idx, idx)).collect();
+    solver.init(diagonal, vec![]);
+    assert_eq!(solver.kind(), Solvers::FaerSparse);
+
+    let mut solver = AutoSolver::new(200).unwrap();
+    let dense: Vec<_> = (0..200)
+        .flat_map(|row| (0..200).map(move |col| (row, col)))
+        .collect();
+    solver.init(dense, vec![]);
+    assert_eq!(solver.kind(), Solvers::Faer);
+
+    let solver = AutoSolver::new(5).unwrap();
+    assert_eq!(solver.kind(), Solvers::Nalgebra);
+}
+
+#[test]
+fn solve_random_systems() {
+    for size in [10, 60] {
+        let (a_mat, b_vec, x_vec) = generate_solvable_system(size, 0.05);
+        let pattern = (0..size)
+            .flat_map(|row| (0..size).map(move |col| (row, col)))
+            .filter(|&(row, col)| a_mat[row][col] != 0.0)
+            .collect();
+        let mut solver = AutoSolver::new(size).unwrap();
+        solver.init(pattern, vec![]);
+
+        for (row, values) in a_mat.iter().enumerate() {
+            for (col, val) in values.iter().enumerate() {
+                if *val != 0.0 {
+                    solver.insert_a(&(row, col, *val));
+                }
+            }
+        }
+        for (row, val) in b_vec.iter().enumerate() {
+            solver.insert_b(&(row, *val));
+        }
+
+        let solution = solver.solve().unwrap();
+        for (res, expected) in solution.iter().zip(&x_vec) {
+            assert!((res - expected).abs() < 1e-6, "{res} != {expected}");
+        }
+    }
+}
+
+#[test]
+fn switches_on_singular_matrix() {
+    let mut solver = AutoSolver::new(3).unwrap();
+    solver.set_backend(Solvers::FaerSparse, Box::new(SingularSolver));
+    stamp_ladder_base(&mut solver);
+    stamp_ladder(&mut solver);
+
+    assert_eq!(
+        solver.solve(),
+        Err(SolverError::BackendSwitched {
+            from: Solvers::FaerSparse,
+            to: Solvers::Nalgebra
+        })
+    );
+    assert_eq!(solver.kind(), Solvers::Nalgebra);
+
+    // The fallback starts empty and solves the system stamped again
+    stamp_ladder_base(&mut solver);
+    stamp_ladder(&mut solver);
+    let solution = solver.solve().unwrap();
+    assert_ladder_solution(solution, 1.0);
+
+    // The next assembly starts from the base matrix of the fallback
+    solver.load_base();
+    stamp_ladder(&mut solver);
+    let solution = solver.solve().unwrap();
+    assert_ladder_solution(solution, 1.0);
+}
+
+#[test]
+fn switches_on_singular_factorisation() {
+    let mut solver = AutoSolver::new(3).unwrap();
+    stamp_ladder_base(&mut solver);
+    stamp_ladder(&mut solver);
+    solver.solve().unwrap();
+
+    solver.set_backend(Solvers::FaerSparse, Box::new(SingularSolver));
+    solver.insert_b(&(0, 2.0));
+    assert!(matches!(
+        solver.solve_factored(),
+        Err(SolverError::BackendSwitched { .. })
+    ));
+    assert_eq!(solver.kind(), Solvers::Nalgebra);
+
+    stamp_ladder_base(&mut solver);
+    stamp_ladder(&mut solver);
+    solver.insert_b(&(0, 1.0));
+    let solution = solver.solve().unwrap();
+    assert_ladder_solution(solution, 2.0);
+}
+
+/// Stamps a complex system with the solution `x = (1, 1)`: `(1 + j) - 1 = j` and `-1 + 2 = 1`.
+fn stamp_cplx_system(solver: &mut AutoSolver) {
+    let j = ComplexNumeric::new(0.0, 1.0);
+    let one = ComplexNumeric::new(1.0, 0.0);
+    solver.insert_cplx_a(&(0, 0, one + j));
+    solver.insert_cplx_a(&(0, 1, -one));
+    solver.insert_cplx_a(&(1, 0, -one));
+    solver.insert_cplx_a(&(1, 1, 2.0 * one));
+    solver.insert_cplx_b(&(0, j));
+    solver.insert_cplx_b(&(1, one));
+}
+
+#[test]
+fn switches_on_singular_cplx_matrix() {
+    let mut solver = AutoSolver::new(2).unwrap();
+    solver.set_backend(Solvers::FaerSparse, Box::new(SingularSolver));
+    stamp_cplx_system(&mut solver);
+    assert!(matches!(
+        solver.solve_cplx(),
+        Err(SolverError::BackendSwitched { .. })
+    ));
+    assert_eq!(solver.kind(), Solvers::Nalgebra);
+
+    stamp_cplx_system(&mut solver);
+    let solution = solver.solve_cplx().unwrap();
+    let one = ComplexNumeric::new(1.0, 0.0);
+    assert!((solution[0] - one).norm() < 1e-12, "{solution:?}");
+    assert!((solution[1] - one).norm() < 1e-12, "{solution:?}");
+}
+
+#[test]
+fn singular_matrix() {
+    let mut solver = AutoSolver::new(2).unwrap();
+
+    // Every fallback finds the matrix singular as well
+    let error = loop {
+        solver.insert_a(&(0, 0, 1.0));
+        solver.insert_a(&(0, 1, 1.0));
+        solver.insert_b(&(0, 1.0));
+        solver.insert_b(&(1, 1.0));
+        match solver.solve() {
+            Err(SolverError::BackendSwitched { .. }) => continue,
+            result => break result.unwrap_err(),
+        }
+    };
+    assert_eq!(error, SolverError::MatrixNonInvertible { pivot: Some(1) });
+    assert_eq!(solver.kind(), Solvers::FaerSparse);
+}
+
+#[test]
+fn stamps_only_into_backend() {
+    let mut solver = AutoSolver::new(3).unwrap();
+    stamp_ladder_base(&mut solver);
+    stamp_ladder(&mut solver);
+
+    // The assembled system is the one of the backend
+    let (a_mat, b_vec) = solver.system();
+    assert_eq!(b_vec, vec![1.0, 0.0, 0.0]);
+    assert!(a_mat.contains(&(0, 0, 2.0)));
+    assert!(a_mat.contains(&(1, 1, 3.0)));
+}
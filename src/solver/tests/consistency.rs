@@ -1,7 +1,10 @@
 // Consistency tests between different solvers
 // These tests verify that all solvers produce similar results for the same problems
 
-use crate::solver::{FaerSolver, NalgebraSolver, Solver, SolverError};
+use crate::solver::{
+    FaerSolver, FaerSparseSolver, NalgebraSolver, RSparseSolver, Solver, SolverError,
+};
+use crate::spot::*;
 
 /// Test that all solvers produce consistent results for a simple 2x2 system
 fn test_simple_2x2_system() -> Result<(), String> {
@@ -197,7 +200,7 @@ fn test_ill_conditioned_consistency_helper() -> Result<(), String> {
                     i, solution[0], solution[1]
                 );
             }
-            Err(SolverError::MatrixNonInvertible) => {
+            Err(SolverError::MatrixNonInvertible { .. }) => {
                 println!("Solver {} detected matrix as non-invertible", i);
                 results.push(vec![f64::NAN, f64::NAN]); // Mark as failed
             }
@@ -510,3 +513,142 @@ fn test_performance_consistency() {
         }
     }
 }
+
+/// Stamps the AC system of an RC ladder with `nodes` nodes, driven by a voltage source at the
+/// first node, at the frequency `freq`.
+fn stamp_rc_ladder_ac(solver: &mut dyn Solver, nodes: usize, freq: Numeric) {
+    let g = ComplexNumeric::new(1e-3, 0.0);
+    let y = ComplexNumeric::new(0.0, 2.0 * std::f64::consts::PI * freq * 1e-6);
+    let one = ComplexNumeric::new(1.0, 0.0);
+
+    for node in 1..nodes {
+        solver.insert_cplx_a(&(node - 1, node - 1, g));
+        solver.insert_cplx_a(&(node, node, g));
+        solver.insert_cplx_a(&(node - 1, node, -g));
+        solver.insert_cplx_a(&(node, node - 1, -g));
+        solver.insert_cplx_a(&(node, node, y));
+    }
+    solver.insert_cplx_a(&(0, nodes, one));
+    solver.insert_cplx_a(&(nodes, 0, one));
+    solver.insert_cplx_b(&(nodes, one));
+}
+
+/// Returns the entries of the AC system of the RC ladder.
+fn rc_ladder_ac_pattern(nodes: usize) -> Vec<(usize, usize)> {
+    let mut pattern = vec![(0, nodes), (nodes, 0)];
+    for node in 1..nodes {
+        pattern.extend([
+            (node - 1, node - 1),
+            (node, node),
+            (node - 1, node),
+            (node, node - 1),
+        ]);
+    }
+    pattern
+}
+
+/// Test that the sparse solvers match `NalgebraSolver` for a frequency sweep, which reuses the
+/// analysis of the complex pattern for all frequencies
+fn test_complex_sweep_consistency_helper(nodes: usize) -> Result<(), String> {
+    let vars = nodes + 1;
+    let mut solvers: Vec<Box<dyn Solver>> = vec![
+        Box::new(NalgebraSolver::new(vars).map_err(|e| e.to_string())?),
+        Box::new(FaerSparseSolver::new(vars).map_err(|e| e.to_string())?),
+        Box::new(RSparseSolver::new(vars).map_err(|e| e.to_string())?),
+    ];
+    for solver in solvers.iter_mut() {
+        solver.init(vec![], rc_ladder_ac_pattern(nodes));
+    }
+
+    let tolerance = 1e-9;
+    for freq in [1.0, 10.0, 100.0, 1e3, 1e4, 1e5] {
+        let mut results = Vec::new();
+        for (i, solver) in solvers.iter_mut().enumerate() {
+            stamp_rc_ladder_ac(solver.as_mut(), nodes, freq);
+            let result = solver
+                .solve_cplx()
+                .map_err(|e| format!("Solver {} failed at {} Hz: {:?}", i, freq, e))?;
+            results.push(result.clone());
+        }
+
+        let base_result = &results[0];
+        for (i, result) in results.iter().enumerate().skip(1) {
+            for (j, (val, base)) in result.iter().zip(base_result).enumerate() {
+                let diff = (val - base).norm();
+                assert!(
+                    diff < tolerance * (1.0 + base.norm()),
+                    "Solver {} value {} differs by {} from solver 0 at {} Hz",
+                    i,
+                    j,
+                    diff,
+                    freq
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_complex_sweep_consistency() {
+    test_complex_sweep_consistency_helper(3).unwrap();
+    test_complex_sweep_consistency_helper(50).unwrap();
+}
+
+/// Test that the sparse solvers analyse the complex pattern again, if a frequency stamps an
+/// entry outside of the initial pattern
+fn test_complex_pattern_extension_helper() -> Result<(), String> {
+    let nodes = 4;
+    let vars = nodes + 1;
+    let mut solvers: Vec<Box<dyn Solver>> = vec![
+        Box::new(NalgebraSolver::new(vars).map_err(|e| e.to_string())?),
+        Box::new(FaerSparseSolver::new(vars).map_err(|e| e.to_string())?),
+        Box::new(RSparseSolver::new(vars).map_err(|e| e.to_string())?),
+    ];
+    for solver in solvers.iter_mut() {
+        solver.init(vec![], rc_ladder_ac_pattern(nodes));
+    }
+
+    let bridge = ComplexNumeric::new(5e-4, 1e-4);
+    let tolerance = 1e-9;
+    for (step, freq) in [100.0, 1e3, 1e4].into_iter().enumerate() {
+        let mut results = Vec::new();
+        for (i, solver) in solvers.iter_mut().enumerate() {
+            stamp_rc_ladder_ac(solver.as_mut(), nodes, freq);
+            if step > 0 {
+                // An admittance between the first and the last node
+                solver.insert_cplx_a(&(0, 0, bridge));
+                solver.insert_cplx_a(&(nodes - 1, nodes - 1, bridge));
+                solver.insert_cplx_a(&(0, nodes - 1, -bridge));
+                solver.insert_cplx_a(&(nodes - 1, 0, -bridge));
+            }
+            let result = solver
+                .solve_cplx()
+                .map_err(|e| format!("Solver {} failed at {} Hz: {:?}", i, freq, e))?;
+            results.push(result.clone());
+        }
+
+        let base_result = &results[0];
+        for (i, result) in results.iter().enumerate().skip(1) {
+            for (j, (val, base)) in result.iter().zip(base_result).enumerate() {
+                let diff = (val - base).norm();
+                assert!(
+                    diff < tolerance * (1.0 + base.norm()),
+                    "Solver {} value {} differs by {} from solver 0 at {} Hz",
+                    i,
+                    j,
+                    diff,
+                    freq
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_complex_pattern_extension_consistency() {
+    test_complex_pattern_extension_helper().unwrap();
+}
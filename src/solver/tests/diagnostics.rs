@@ -0,0 +1,149 @@
+use nalgebra as na;
+
+use crate::solver::tests::generate_solvable_system;
+use crate::solver::{
+    AutoSolver, FaerSolver, FaerSparseSolver, KrylovSolver, NalgebraSolver, RSparseSolver, Solver,
+    SolverError,
+};
+use crate::spot::*;
+
+/// Inserts the dense matrix `a` with all its entries, including the zeros, and solves it.
+fn solve_dense<S: Solver>(solver: &mut S, a: &[Vec<Numeric>]) -> Result<Vec<Numeric>, SolverError> {
+    let pattern = (0..a.len())
+        .flat_map(|row| (0..a.len()).map(move |col| (row, col)))
+        .collect();
+    solver.init(pattern, vec![]);
+    for (row, values) in a.iter().enumerate() {
+        for (col, &val) in values.iter().enumerate() {
+            solver.insert_a(&(row, col, val));
+        }
+        solver.insert_b(&(row, 1.0));
+    }
+    solver.solve().cloned()
+}
+
+/// The exact 1-norm condition number of `a`.
+fn condition_number(a: &[Vec<Numeric>]) -> Numeric {
+    let mat = na::DMatrix::from_fn(a.len(), a.len(), |row, col| a[row][col]);
+    let norm = |mat: &na::DMatrix<Numeric>| {
+        mat.column_iter()
+            .map(|col| col.iter().map(|val| val.abs()).sum::<Numeric>())
+            .fold(0.0, Numeric::max)
+    };
+    norm(&mat) * norm(&mat.clone().try_inverse().unwrap())
+}
+
+/// A floating node leaves the variable 1 without any conductance.
+fn floating_node<S: Solver>() {
+    let a = vec![
+        vec![2.0, 0.0, -1.0],
+        vec![0.0, 0.0, 0.0],
+        vec![-1.0, 0.0, 2.0],
+    ];
+    let mut solver = S::new(3).unwrap();
+    assert_eq!(
+        solve_dense(&mut solver, &a),
+        Err(SolverError::MatrixNonInvertible { pivot: Some(1) }),
+        "{}",
+        std::any::type_name::<S>()
+    );
+}
+
+/// Two voltage sources at node 0 with the branches 1 and 2. The branch current of the second
+/// source depends on the first one.
+fn parallel_sources<S: Solver>() {
+    let a = vec![
+        vec![1.0, 1.0, 1.0],
+        vec![1.0, 0.0, 0.0],
+        vec![1.0, 0.0, 0.0],
+    ];
+    let mut solver = S::new(3).unwrap();
+    assert_eq!(
+        solve_dense(&mut solver, &a),
+        Err(SolverError::MatrixNonInvertible { pivot: Some(2) }),
+        "{}",
+        std::any::type_name::<S>()
+    );
+}
+
+#[test]
+fn pivot_of_floating_node() {
+    floating_node::<NalgebraSolver>();
+    floating_node::<FaerSolver>();
+    floating_node::<FaerSparseSolver>();
+    floating_node::<RSparseSolver>();
+    floating_node::<KrylovSolver>();
+}
+
+#[test]
+fn pivot_of_parallel_sources() {
+    parallel_sources::<NalgebraSolver>();
+    parallel_sources::<FaerSolver>();
+    parallel_sources::<RSparseSolver>();
+}
+
+#[test]
+fn pivot_in_error_message() {
+    let error = SolverError::MatrixNonInvertible { pivot: Some(2) };
+    assert_eq!(
+        error.to_string(),
+        "The conductance matrix is not invertible (singular pivot at variable 2)"
+    );
+    let error = SolverError::MatrixNonInvertible { pivot: None };
+    assert_eq!(
+        error.to_string(),
+        "The conductance matrix is not invertible"
+    );
+}
+
+/// The estimate never exceeds the exact condition number and is usually close to it.
+fn estimate_condition<S: Solver>() {
+    const SIZE: usize = 20;
+    let (a, _, _) = generate_solvable_system(SIZE, 0.3);
+    let exact = condition_number(&a);
+
+    let mut solver = S::new(SIZE).unwrap();
+    solve_dense(&mut solver, &a).unwrap();
+    assert_eq!(solver.condition_estimate(), None);
+
+    solver.set_condition_limit(Some(1e12));
+    solve_dense(&mut solver, &a).unwrap();
+    let estimate = solver.condition_estimate().unwrap();
+    assert!(
+        estimate <= exact * (1.0 + 1e-9) && estimate >= exact / 3.0,
+        "{}: {estimate} vs. {exact}",
+        std::any::type_name::<S>()
+    );
+}
+
+#[test]
+fn condition_estimate() {
+    estimate_condition::<NalgebraSolver>();
+    estimate_condition::<FaerSolver>();
+    estimate_condition::<FaerSparseSolver>();
+    estimate_condition::<RSparseSolver>();
+    estimate_condition::<AutoSolver>();
+}
+
+/// A conductance of 1 S next to one of 1 pS is solvable, but ill-conditioned.
+fn ill_conditioned<S: Solver>() {
+    let a = vec![vec![1.0, -1.0], vec![-1.0, 1.0 + 1e-12]];
+    let mut solver = S::new(2).unwrap();
+    solver.set_condition_limit(Some(1e9));
+    solve_dense(&mut solver, &a).unwrap();
+
+    let estimate = solver.condition_estimate().unwrap();
+    assert!(
+        estimate > 1e12,
+        "{}: {estimate}",
+        std::any::type_name::<S>()
+    );
+}
+
+#[test]
+fn condition_estimate_of_ill_conditioned_matrix() {
+    ill_conditioned::<NalgebraSolver>();
+    ill_conditioned::<FaerSolver>();
+    ill_conditioned::<FaerSparseSolver>();
+    ill_conditioned::<RSparseSolver>();
+}
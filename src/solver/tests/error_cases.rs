@@ -47,7 +47,7 @@ where
             );
             Ok(())
         }
-        Err(SolverError::MatrixNonInvertible) => Ok(()),
+        Err(SolverError::MatrixNonInvertible { .. }) => Ok(()),
         #[allow(unreachable_patterns)]
         Err(e) => {
             eprintln!(
@@ -115,7 +115,7 @@ where
             );
             Ok(())
         }
-        Err(SolverError::MatrixNonInvertible) => Ok(()),
+        Err(SolverError::MatrixNonInvertible { .. }) => Ok(()),
         #[allow(unreachable_patterns)]
         Err(e) => {
             // Some solvers might return other errors, which we also accept
@@ -187,7 +187,7 @@ where
             }
             Ok(())
         }
-        Err(SolverError::MatrixNonInvertible) => Ok(()),
+        Err(SolverError::MatrixNonInvertible { .. }) => Ok(()),
         #[allow(unreachable_patterns)]
         #[allow(unreachable_patterns)]
         Err(e) => {
@@ -253,7 +253,7 @@ where
             }
             Ok(())
         }
-        Err(SolverError::MatrixNonInvertible) => Ok(()),
+        Err(SolverError::MatrixNonInvertible { .. }) => Ok(()),
         #[allow(unreachable_patterns)]
         Err(e) => {
             eprintln!(
@@ -317,7 +317,7 @@ where
             }
             Ok(())
         }
-        Err(SolverError::MatrixNonInvertible) => Ok(()),
+        Err(SolverError::MatrixNonInvertible { .. }) => Ok(()),
         #[allow(unreachable_patterns)]
         Err(e) => {
             eprintln!(
@@ -268,3 +268,60 @@ pub fn faer_solve() {
         assert!((solution[idx] - x_vec[idx]) < 100.0 * Numeric::EPSILON);
     }
 }
+
+#[test]
+fn solve_singular_matrix() {
+    let mut solver = FaerSparseSolver::new(2).unwrap();
+    solver.init(vec![(0, 0), (0, 1), (1, 0), (1, 1)], vec![]);
+    solver.analyse().unwrap();
+
+    [(0, 0, 1.0), (0, 1, 1.0), (1, 0, 1.0), (1, 1, 1.0)]
+        .iter()
+        .for_each(|trpl| solver.insert_a(trpl));
+    solver.insert_b(&(0, 1.0));
+    solver.insert_b(&(1, 1.0));
+
+    // The second column depends on the first one
+    assert_eq!(
+        solver.solve(),
+        Err(crate::solver::SolverError::MatrixNonInvertible { pivot: Some(1) })
+    );
+}
+
+#[test]
+fn solve_floating_subnet() {
+    // A resistor between two nodes without a path to ground leaves a singular block
+    let mut solver = FaerSparseSolver::new(3).unwrap();
+    solver.init(vec![(0, 0), (1, 1), (1, 2), (2, 1), (2, 2)], vec![]);
+    solver.analyse().unwrap();
+
+    [
+        (0, 0, 1.0),
+        (1, 1, 1e-3),
+        (1, 2, -1e-3),
+        (2, 1, -1e-3),
+        (2, 2, 1e-3),
+    ]
+    .iter()
+    .for_each(|trpl| solver.insert_a(trpl));
+    solver.insert_b(&(0, 1.0));
+
+    assert!(matches!(
+        solver.solve(),
+        Err(crate::solver::SolverError::MatrixNonInvertible { .. })
+    ));
+}
+
+#[test]
+fn solve_zero_row() {
+    // A floating node leaves an empty row and column in the matrix
+    let mut solver = FaerSparseSolver::new(2).unwrap();
+    solver.init(vec![(0, 0), (1, 1)], vec![]);
+    solver.insert_a(&(0, 0, 1.0));
+    solver.insert_b(&(0, 1.0));
+
+    assert_eq!(
+        solver.solve(),
+        Err(crate::solver::SolverError::MatrixNonInvertible { pivot: Some(1) })
+    );
+}
@@ -0,0 +1,239 @@
+use crate::solver::krylov::{KrylovMethod, KrylovOptions, Preconditioner};
+use crate::solver::{tests::generate_solvable_system, KrylovSolver, Solver, SolverError};
+use crate::spot::*;
+
+/// The combinations of methods and preconditioners.
+fn all_options() -> Vec<KrylovOptions> {
+    let preconditioners = [
+        Preconditioner::Ilu0,
+        Preconditioner::Ilut {
+            drop_tol: 1e-4,
+            fill: 10,
+        },
+    ];
+    [KrylovMethod::Gmres, KrylovMethod::BiCgStab]
+        .into_iter()
+        .flat_map(|method| {
+            preconditioners.map(|preconditioner| KrylovOptions {
+                method,
+                preconditioner,
+                ..KrylovOptions::default()
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn solve_small() {
+    let a_matrix = [(0, 0, 5.0), (0, 1, 2.0), (1, 0, 5.0), (1, 1, -2.0)];
+    let b_vector = [7.0, 3.0];
+
+    let mut solver = KrylovSolver::new(2).unwrap();
+
+    a_matrix.iter().for_each(|trpl| solver.insert_a(trpl));
+    b_vector
+        .iter()
+        .enumerate()
+        .for_each(|(idx, val)| solver.insert_b(&(idx, *val)));
+
+    let solution = solver.solve().unwrap();
+    assert!((solution[0] - 1.0).abs() < 1e-12, "{solution:?}");
+    assert!((solution[1] - 1.0).abs() < 1e-12, "{solution:?}");
+}
+
+#[test]
+fn solve_random_systems() {
+    const SIZE: usize = 60;
+
+    for options in all_options() {
+        let (a_mat, b_vec, x_vec) = generate_solvable_system(SIZE, 0.05);
+        let mut solver = KrylovSolver::with_options(SIZE, options).unwrap();
+
+        for (row, values) in a_mat.iter().enumerate() {
+            for (col, val) in values.iter().enumerate() {
+                if *val != 0.0 {
+                    solver.insert_a(&(row, col, *val));
+                }
+            }
+        }
+        for (row, val) in b_vec.iter().enumerate() {
+            solver.insert_b(&(row, *val));
+        }
+
+        let solution = solver.solve().unwrap().clone();
+        assert!(
+            !solver.is_direct(),
+            "{options:?} fell back to the direct LU"
+        );
+        for (res, expected) in solution.iter().zip(&x_vec) {
+            assert!(
+                (res - expected).abs() < 1e-6,
+                "{options:?}: {res} != {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn solve_voltage_source_branches() {
+    // A voltage source of 3V at node 0 drives a ladder of 1Ω resistors to ground.
+    // The branch row of the source has no diagonal entry.
+    const NODES: usize = 20;
+    let branch = NODES;
+
+    for options in all_options() {
+        let mut solver = KrylovSolver::with_options(NODES + 1, options).unwrap();
+        for node in 0..NODES {
+            solver.insert_a(&(node, node, 1.0));
+            if node > 0 {
+                solver.insert_a(&(node - 1, node - 1, 1.0));
+                solver.insert_a(&(node, node, 1.0));
+                solver.insert_a(&(node - 1, node, -1.0));
+                solver.insert_a(&(node, node - 1, -1.0));
+            }
+        }
+        solver.insert_a(&(0, branch, 1.0));
+        solver.insert_a(&(branch, 0, 1.0));
+        solver.insert_b(&(branch, 3.0));
+
+        let solution = solver.solve().unwrap();
+        assert!(
+            (solution[0] - 3.0).abs() < 1e-9,
+            "{options:?}: {solution:?}"
+        );
+        for node in 1..NODES {
+            assert!(
+                solution[node] < solution[node - 1],
+                "{options:?}: {solution:?}"
+            );
+        }
+        // The source supplies the current through the first resistor and to ground.
+        let current = (solution[0] - solution[1]) + solution[0];
+        assert!(
+            (solution[branch] + current).abs() < 1e-9,
+            "{options:?}: {solution:?}"
+        );
+    }
+}
+
+#[test]
+fn falls_back_to_direct_lu() {
+    const SIZE: usize = 30;
+    let options = KrylovOptions {
+        preconditioner: Preconditioner::Ilut {
+            drop_tol: 1.0,
+            fill: 0,
+        },
+        max_iterations: 2,
+        ..KrylovOptions::default()
+    };
+
+    let (a_mat, b_vec, x_vec) = generate_solvable_system(SIZE, 0.3);
+    let mut solver = KrylovSolver::with_options(SIZE, options).unwrap();
+    for (row, values) in a_mat.iter().enumerate() {
+        for (col, val) in values.iter().enumerate() {
+            solver.insert_a(&(row, col, *val));
+        }
+    }
+    for (row, val) in b_vec.iter().enumerate() {
+        solver.insert_b(&(row, *val));
+    }
+
+    let solution = solver.solve().unwrap().clone();
+    assert!(solver.is_direct());
+    for (res, expected) in solution.iter().zip(&x_vec) {
+        assert!((res - expected).abs() < 1e-6, "{res} != {expected}");
+    }
+
+    // The direct factorisation is reused for a new `b`
+    for (row, val) in b_vec.iter().enumerate() {
+        solver.insert_b(&(row, 2.0 * val));
+    }
+    let solution = solver.solve_factored().unwrap();
+    for (res, expected) in solution.iter().zip(&x_vec) {
+        assert!((res - 2.0 * expected).abs() < 1e-6, "{res} != {expected}");
+    }
+}
+
+#[test]
+fn singular_matrix() {
+    let mut solver = KrylovSolver::new(2).unwrap();
+    solver.insert_a(&(0, 0, 1.0));
+    solver.insert_a(&(0, 1, 1.0));
+    solver.insert_b(&(0, 1.0));
+    solver.insert_b(&(1, 1.0));
+
+    assert_eq!(
+        solver.solve(),
+        Err(SolverError::MatrixNonInvertible { pivot: Some(1) })
+    );
+}
+
+#[test]
+fn singular_floating_subnet() {
+    // A resistor between two nodes without a path to ground leaves a singular block, for which
+    // the iteration converges to an arbitrary solution
+    for options in all_options() {
+        let mut solver = KrylovSolver::with_options(3, options).unwrap();
+        [
+            (0, 0, 1.0),
+            (1, 1, 1e-3),
+            (1, 2, -1e-3),
+            (2, 1, -1e-3),
+            (2, 2, 1e-3),
+        ]
+        .iter()
+        .for_each(|trpl| solver.insert_a(trpl));
+        solver.insert_b(&(0, 1.0));
+
+        assert!(
+            matches!(solver.solve(), Err(SolverError::MatrixNonInvertible { .. })),
+            "{options:?}"
+        );
+    }
+}
+
+#[test]
+fn solve_cancelled_pivot() {
+    // A voltage source of 1V at node 0 with a 1Ω resistor to node 1, which has no other
+    // connection. The pivot of node 1 cancels, although the matrix is regular.
+    for options in all_options() {
+        let mut solver = KrylovSolver::with_options(3, options).unwrap();
+        [
+            (0, 0, 1.0),
+            (0, 1, -1.0),
+            (1, 0, -1.0),
+            (1, 1, 1.0),
+            (0, 2, 1.0),
+            (2, 0, 1.0),
+        ]
+        .iter()
+        .for_each(|trpl| solver.insert_a(trpl));
+        solver.insert_b(&(2, 1.0));
+
+        let solution = solver.solve().unwrap().clone();
+        assert!(solver.is_direct(), "{options:?}");
+        for (res, expected) in solution.iter().zip([1.0, 1.0, 0.0]) {
+            assert!((res - expected).abs() < 1e-12, "{options:?}: {solution:?}");
+        }
+    }
+}
+
+#[test]
+fn solve_cplx_with_direct_lu() {
+    let mut solver = KrylovSolver::new(2).unwrap();
+    solver.init(vec![], vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+    let j = ComplexNumeric::new(0.0, 1.0);
+    let one = ComplexNumeric::new(1.0, 0.0);
+    solver.insert_cplx_a(&(0, 0, one + j));
+    solver.insert_cplx_a(&(0, 1, -one));
+    solver.insert_cplx_a(&(1, 0, -one));
+    solver.insert_cplx_a(&(1, 1, 2.0 * one));
+    solver.insert_cplx_b(&(0, j));
+    solver.insert_cplx_b(&(1, one));
+
+    // x = (1, 1): (1 + j) - 1 = j and -1 + 2 = 1
+    let solution = solver.solve_cplx().unwrap();
+    assert!((solution[0] - one).norm() < 1e-12, "{solution:?}");
+    assert!((solution[1] - one).norm() < 1e-12, "{solution:?}");
+}
@@ -0,0 +1,230 @@
+use std::fs;
+
+use num::Num;
+
+use crate::solver::matrix_market::{write_matrix, write_vector, MarketValue};
+use crate::solver::Solvers;
+use crate::spot::*;
+
+/// The directory of the regression matrices, written by the matrix dump of the simulator.
+const MATRIX_FILES: &str = "src/solver/tests/matrix_files";
+
+/// The backends the regression matrices are replayed against.
+const BACKENDS: [Solvers; 6] = [
+    Solvers::Rsparse,
+    Solvers::Nalgebra,
+    Solvers::Faer,
+    Solvers::FaerSparse,
+    Solvers::Krylov,
+    Solvers::Auto,
+];
+
+/// A value type of the Matrix Market format, which can be read back.
+trait ReadValue: MarketValue + Num {
+    /// Reads the value from the whitespace separated numbers of an entry.
+    fn read_value<'a>(numbers: &mut impl Iterator<Item = &'a str>) -> Self;
+
+    /// Returns the absolute value.
+    fn magnitude(&self) -> Numeric;
+}
+
+impl ReadValue for Numeric {
+    fn read_value<'a>(numbers: &mut impl Iterator<Item = &'a str>) -> Self {
+        numbers.next().unwrap().parse().unwrap()
+    }
+
+    fn magnitude(&self) -> Numeric {
+        self.abs()
+    }
+}
+
+impl ReadValue for ComplexNumeric {
+    fn read_value<'a>(numbers: &mut impl Iterator<Item = &'a str>) -> Self {
+        let re = Numeric::read_value(numbers);
+        let im = Numeric::read_value(numbers);
+        ComplexNumeric::new(re, im)
+    }
+
+    fn magnitude(&self) -> Numeric {
+        self.norm()
+    }
+}
+
+/// Checks the header of a Matrix Market file and returns the lines after the comments.
+fn data_lines<'a, T: ReadValue>(content: &'a str, format: &str) -> impl Iterator<Item = &'a str> {
+    let mut lines = content.lines();
+    assert_eq!(
+        lines.next(),
+        Some(format!("%%MatrixMarket matrix {format} {} general", T::FIELD).as_str())
+    );
+    lines.filter(|line| !line.starts_with('%') && !line.trim().is_empty())
+}
+
+/// Parses the whitespace separated sizes of a Matrix Market file.
+fn sizes(line: Option<&str>) -> Vec<usize> {
+    line.unwrap()
+        .split_whitespace()
+        .map(|size| size.parse().unwrap())
+        .collect()
+}
+
+/// Reads a square matrix in the coordinate format and returns its size and its
+/// `(row, col, value)` triples with indices starting at 0.
+fn read_matrix<T: ReadValue>(content: &str) -> (usize, Vec<(usize, usize, T)>) {
+    let mut lines = data_lines::<T>(content, "coordinate");
+    let [rows, cols, entries] = sizes(lines.next())[..] else {
+        panic!("The size line has three entries");
+    };
+    assert_eq!(rows, cols, "The matrix is square");
+
+    let triples: Vec<_> = lines
+        .map(|line| {
+            let mut numbers = line.split_whitespace();
+            let row: usize = numbers.next().unwrap().parse().unwrap();
+            let col: usize = numbers.next().unwrap().parse().unwrap();
+            (row - 1, col - 1, T::read_value(&mut numbers))
+        })
+        .collect();
+    assert_eq!(triples.len(), entries);
+    (rows, triples)
+}
+
+/// Reads a vector, which is a single column in the array format.
+fn read_vector<T: ReadValue>(content: &str) -> Vec<T> {
+    let mut lines = data_lines::<T>(content, "array");
+    let [rows, 1] = sizes(lines.next())[..] else {
+        panic!("The vector is a single column");
+    };
+
+    let values: Vec<_> = lines
+        .map(|line| T::read_value(&mut line.split_whitespace()))
+        .collect();
+    assert_eq!(values.len(), rows);
+    values
+}
+
+/// Reads the system `<name>.a.mtx` and `<name>.b.mtx` of the regression matrices.
+fn read_system<T: ReadValue>(name: &str) -> (usize, Vec<(usize, usize, T)>, Vec<T>) {
+    let read = |file: &str| fs::read_to_string(format!("{MATRIX_FILES}/{name}.{file}")).unwrap();
+    let (vars, a_mat) = read_matrix(&read("a.mtx"));
+    let b_vec = read_vector(&read("b.mtx"));
+    assert_eq!(b_vec.len(), vars);
+    (vars, a_mat, b_vec)
+}
+
+/// Returns the nonzero triples sorted by row and column, as dense solvers leave out the zeros.
+fn nonzeros<T: ReadValue>(mut triples: Vec<(usize, usize, T)>) -> Vec<(usize, usize, T)> {
+    triples.retain(|(_, _, val)| !val.is_zero());
+    triples.sort_by_key(|&(row, col, _)| (row, col));
+    triples
+}
+
+/// Returns `‖Ax - b‖∞` relative to `‖A‖∞ ‖x‖∞ + ‖b‖∞`.
+fn relative_residual<T: ReadValue>(
+    a_mat: &[(usize, usize, T)],
+    b_vec: &[T],
+    x_vec: &[T],
+) -> Numeric {
+    let max = |values: &[T]| values.iter().map(T::magnitude).fold(0.0, Numeric::max);
+    let mut residual: Vec<T> = b_vec.iter().map(|&val| T::zero() - val).collect();
+    let mut row_sums = vec![0.0; b_vec.len()];
+    for &(row, col, val) in a_mat {
+        residual[row] = residual[row] + val * x_vec[col];
+        row_sums[row] += val.magnitude();
+    }
+    let a_norm = row_sums.into_iter().fold(0.0, Numeric::max);
+    max(&residual) / (a_norm * max(x_vec) + max(b_vec))
+}
+
+/// Replays a dumped real system against every backend.
+fn replay(name: &str) {
+    let (vars, a_mat, b_vec) = read_system::<Numeric>(name);
+    for kind in BACKENDS {
+        let mut solver = kind.create(vars).unwrap();
+        solver.init(
+            a_mat.iter().map(|&(row, col, _)| (row, col)).collect(),
+            vec![],
+        );
+        a_mat.iter().for_each(|triple| solver.insert_a(triple));
+        b_vec
+            .iter()
+            .enumerate()
+            .for_each(|(row, &val)| solver.insert_b(&(row, val)));
+
+        let (system_a, system_b) = solver.system();
+        assert_eq!(nonzeros(system_a), nonzeros(a_mat.clone()), "{kind:?}");
+        assert_eq!(system_b, b_vec, "{kind:?}");
+
+        let x_vec = solver.solve().unwrap().clone();
+        let residual = relative_residual(&a_mat, &b_vec, &x_vec);
+        assert!(residual < 1e-12, "{kind:?} on {name}: {residual:e}");
+    }
+}
+
+/// Replays a dumped complex system against every backend.
+fn replay_cplx(name: &str) {
+    let (vars, a_mat, b_vec) = read_system::<ComplexNumeric>(name);
+    for kind in BACKENDS {
+        let mut solver = kind.create(vars).unwrap();
+        solver.init(
+            vec![],
+            a_mat.iter().map(|&(row, col, _)| (row, col)).collect(),
+        );
+        a_mat.iter().for_each(|triple| solver.insert_cplx_a(triple));
+        b_vec
+            .iter()
+            .enumerate()
+            .for_each(|(row, &val)| solver.insert_cplx_b(&(row, val)));
+
+        let (system_a, system_b) = solver.cplx_system();
+        assert_eq!(nonzeros(system_a), nonzeros(a_mat.clone()), "{kind:?}");
+        assert_eq!(system_b, b_vec, "{kind:?}");
+
+        let x_vec = solver.solve_cplx().unwrap().clone();
+        let residual = relative_residual(&a_mat, &b_vec, &x_vec);
+        assert!(residual < 1e-12, "{kind:?} on {name}: {residual:e}");
+    }
+}
+
+#[test]
+fn write_and_read_matrix() {
+    let triples = vec![(0, 0, 1.5), (2, 1, -1e-12), (1, 2, 0.1), (2, 2, 0.0)];
+    let mut file = Vec::new();
+    write_matrix(&mut file, 3, &triples, "A test\nwith two lines").unwrap();
+    let content = String::from_utf8(file).unwrap();
+
+    assert!(content.starts_with(
+        "%%MatrixMarket matrix coordinate real general\n% A test\n% with two lines\n3 3 4\n"
+    ));
+    assert_eq!(read_matrix::<Numeric>(&content), (3, triples));
+}
+
+#[test]
+fn write_and_read_complex_vector() {
+    let values = vec![
+        ComplexNumeric::new(1.0, -2.0),
+        ComplexNumeric::new(1.0 / 3.0, 0.0),
+        ComplexNumeric::new(0.0, 6.02e23),
+    ];
+    let mut file = Vec::new();
+    write_vector(&mut file, &values, "").unwrap();
+    let content = String::from_utf8(file).unwrap();
+
+    assert!(content.starts_with("%%MatrixMarket matrix array complex general\n3 1\n1e0 -2e0\n"));
+    assert_eq!(read_vector::<ComplexNumeric>(&content), values);
+}
+
+#[test]
+fn replay_transient_timestep() {
+    replay("rc_tran");
+}
+
+#[test]
+fn replay_nonlinear_operating_point() {
+    replay("diode_ladder_op");
+}
+
+#[test]
+fn replay_ac_frequency() {
+    replay_cplx("rc_ladder_ac");
+}
@@ -1,7 +1,11 @@
+mod auto;
 mod consistency;
+mod diagnostics;
 mod error_cases;
 mod faer;
 mod faer_sparse;
+mod krylov;
+mod matrix_market;
 mod nalgebra;
 mod rsparse;
 
@@ -12,9 +16,12 @@ use rand::prelude::*;
 use rand::rng;
 
 use crate::solver::FaerSolver;
+use crate::solver::FaerSparseSolver;
+use crate::solver::KrylovSolver;
 use crate::solver::NalgebraSolver;
 use crate::solver::RSparseSolver;
 use crate::solver::Solver;
+use crate::solver::SolverError;
 use crate::spot::*;
 
 pub fn generate_solvable_system(
@@ -44,9 +51,7 @@ pub fn generate_solvable_system(
     let mut a = vec![vec![Numeric::zero(); n]; n];
     for i in 0..n {
         for j in 0..n {
-            for k in 0..n {
-                a[i][j] += l[i][k] * l[j][k];
-            }
+            a[i][j] += l[i].iter().zip(&l[j]).map(|(x, y)| x * y).sum::<Numeric>();
         }
     }
 
@@ -149,3 +154,277 @@ fn rsparse_solver_resets() {
     // between solves, ensuring that old values do not affect new calculations.
     test_solver_reset::<RSparseSolver>().unwrap();
 }
+
+fn test_solver_refactor<SolverT>() -> Result<(), String>
+where
+    SolverT: Solver,
+{
+    // Tests that the analysis of the pattern is reused by the refactorisations.
+    //
+    // The tridiagonal system is scaled in every step, so each solve needs a new
+    // numeric factorisation. The last step stamps entries outside of the pattern,
+    // which has to be extended and analysed again.
+    let mut solver = SolverT::new(3).map_err(|e| e.to_string())?;
+    let pattern = vec![(0, 0), (0, 1), (1, 0), (1, 1), (1, 2), (2, 1), (2, 2)];
+    solver.init(pattern, vec![]);
+    solver.analyse().map_err(|e| e.to_string())?;
+
+    for scale in [1.0, 2.0, 10.0] {
+        for (row, col, val) in [(0, 0, 2.0), (0, 1, -1.0), (1, 0, -1.0), (1, 1, 2.0)] {
+            solver.insert_a(&(row, col, scale * val));
+        }
+        for (row, col, val) in [(1, 2, -1.0), (2, 1, -1.0), (2, 2, 2.0)] {
+            solver.insert_a(&(row, col, scale * val));
+        }
+        solver.insert_b(&(2, scale * 4.0));
+        solver.refactor().map_err(|e| e.to_string())?;
+
+        let res = solver.solve().map_err(|e| e.to_string())?;
+        for (idx, expected) in [1.0, 2.0, 3.0].iter().enumerate() {
+            assert!(
+                (res[idx] - expected).abs() < 1e-12,
+                "{res:?} at scale {scale}"
+            );
+        }
+    }
+
+    let a_matrix = [
+        (0, 0, 2.0),
+        (0, 1, -1.0),
+        (0, 2, 1.0),
+        (1, 0, -1.0),
+        (1, 1, 2.0),
+        (1, 2, -1.0),
+        (2, 0, 1.0),
+        (2, 1, -1.0),
+        (2, 2, 2.0),
+    ];
+    a_matrix.iter().for_each(|trpl| solver.insert_a(trpl));
+    solver.insert_b(&(0, 3.0));
+    solver.insert_b(&(2, 5.0));
+
+    let res = solver.solve().map_err(|e| e.to_string())?;
+    for (idx, expected) in [1.0, 2.0, 3.0].iter().enumerate() {
+        assert!((res[idx] - expected).abs() < 1e-12, "{res:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn faer_solver_refactors() {
+    test_solver_refactor::<FaerSolver>().unwrap();
+}
+
+#[test]
+fn faer_sparse_solver_refactors() {
+    test_solver_refactor::<FaerSparseSolver>().unwrap();
+}
+
+#[test]
+fn krylov_solver_refactors() {
+    test_solver_refactor::<KrylovSolver>().unwrap();
+}
+
+#[test]
+fn nalgebra_solver_refactors() {
+    test_solver_refactor::<NalgebraSolver>().unwrap();
+}
+
+#[test]
+fn rsparse_solver_refactors() {
+    test_solver_refactor::<RSparseSolver>().unwrap();
+}
+
+fn test_solver_base<SolverT>() -> Result<(), String>
+where
+    SolverT: Solver,
+{
+    // Tests the assembly from a stored base matrix through pre-resolved slots.
+    //
+    // The upper left block is stamped once into the base. Every assembly starts
+    // from the base and only stamps the remaining entries through their slots.
+    // Slots added after the base was stored start at zero.
+    let mut solver = SolverT::new(3).map_err(|e| e.to_string())?;
+    let pattern = vec![(0, 0), (0, 1), (1, 0), (1, 1), (1, 2), (2, 1), (2, 2)];
+    solver.init(pattern, vec![]);
+    solver.analyse().map_err(|e| e.to_string())?;
+
+    for (row, col, val) in [(0, 0, 2.0), (0, 1, -1.0), (1, 0, -1.0), (1, 1, 2.0)] {
+        solver.insert_a(&(row, col, val));
+    }
+    solver.store_base();
+    let slots = [solver.a_slot(1, 2), solver.a_slot(2, 1), solver.a_slot(2, 2)];
+
+    for g in [1.0, 2.0, 10.0] {
+        solver.load_base();
+        for (slot, val) in slots.iter().zip([-g, -g, 2.0 * g]) {
+            solver.insert_a_slot(*slot, val);
+        }
+        solver.insert_b(&(1, 3.0 - 3.0 * g));
+        solver.insert_b(&(2, 4.0 * g));
+
+        let res = solver.solve().map_err(|e| e.to_string())?;
+        for (idx, expected) in [1.0, 2.0, 3.0].iter().enumerate() {
+            assert!((res[idx] - expected).abs() < 1e-12, "{res:?} at g = {g}");
+        }
+    }
+
+    solver.load_base();
+    let corners = [solver.a_slot(0, 2), solver.a_slot(2, 0)];
+    for (slot, val) in slots.iter().chain(&corners).zip([-1.0, -1.0, 2.0, 1.0, 1.0]) {
+        solver.insert_a_slot(*slot, val);
+    }
+    solver.insert_b(&(0, 3.0));
+    solver.insert_b(&(2, 5.0));
+
+    let res = solver.solve().map_err(|e| e.to_string())?;
+    for (idx, expected) in [1.0, 2.0, 3.0].iter().enumerate() {
+        assert!((res[idx] - expected).abs() < 1e-12, "{res:?}");
+    }
+
+    solver.load_base();
+    for (slot, val) in slots.iter().zip([-1.0, -1.0, 2.0]) {
+        solver.insert_a_slot(*slot, val);
+    }
+    solver.insert_b(&(2, 4.0));
+
+    let res = solver.solve().map_err(|e| e.to_string())?;
+    for (idx, expected) in [1.0, 2.0, 3.0].iter().enumerate() {
+        assert!((res[idx] - expected).abs() < 1e-12, "{res:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn faer_solver_stamps_base() {
+    test_solver_base::<FaerSolver>().unwrap();
+}
+
+#[test]
+fn faer_sparse_solver_stamps_base() {
+    test_solver_base::<FaerSparseSolver>().unwrap();
+}
+
+#[test]
+fn krylov_solver_stamps_base() {
+    test_solver_base::<KrylovSolver>().unwrap();
+}
+
+#[test]
+fn nalgebra_solver_stamps_base() {
+    test_solver_base::<NalgebraSolver>().unwrap();
+}
+
+#[test]
+fn rsparse_solver_stamps_base() {
+    test_solver_base::<RSparseSolver>().unwrap();
+}
+
+fn test_solver_solve_factored<SolverT>() -> Result<(), String>
+where
+    SolverT: Solver,
+{
+    // Tests that the factorisation of the last solve is reused for a new vector `b`.
+    let mut solver = SolverT::new(2).map_err(|e| e.to_string())?;
+    solver.init(vec![(0, 0), (0, 1), (1, 0), (1, 1)], vec![]);
+    assert_eq!(solver.solve_factored(), Err(SolverError::NotFactorised));
+
+    let a_matrix = [(0, 0, 2.0), (0, 1, 1.0), (1, 0, 1.0), (1, 1, 3.0)];
+    a_matrix.iter().for_each(|trpl| solver.insert_a(trpl));
+    solver.insert_b(&(0, 3.0));
+    solver.insert_b(&(1, 4.0));
+    let res = solver.solve().map_err(|e| e.to_string())?;
+    assert!((res[0] - 1.0).abs() < 1e-12 && (res[1] - 1.0).abs() < 1e-12, "{res:?}");
+
+    for (x_0, x_1) in [(1.0, 2.0), (-3.0, 0.5)] {
+        solver.insert_b(&(0, 2.0 * x_0 + x_1));
+        solver.insert_b(&(1, x_0 + 3.0 * x_1));
+        let res = solver.solve_factored().map_err(|e| e.to_string())?;
+        assert!((res[0] - x_0).abs() < 1e-12, "{res:?}");
+        assert!((res[1] - x_1).abs() < 1e-12, "{res:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn faer_solver_solves_factored() {
+    test_solver_solve_factored::<FaerSolver>().unwrap();
+}
+
+#[test]
+fn faer_sparse_solver_solves_factored() {
+    test_solver_solve_factored::<FaerSparseSolver>().unwrap();
+}
+
+#[test]
+fn krylov_solver_solves_factored() {
+    test_solver_solve_factored::<KrylovSolver>().unwrap();
+}
+
+#[test]
+fn nalgebra_solver_solves_factored() {
+    test_solver_solve_factored::<NalgebraSolver>().unwrap();
+}
+
+#[test]
+fn rsparse_solver_solves_factored() {
+    test_solver_solve_factored::<RSparseSolver>().unwrap();
+}
+
+/// A backend which finds every matrix singular.
+pub(crate) struct SingularSolver;
+
+impl Solver for SingularSolver {
+    fn new(_vars: usize) -> Result<Self, SolverError> {
+        Ok(SingularSolver)
+    }
+
+    fn init(&mut self, _a_matrix: Vec<(usize, usize)>, _cplx_a_matrix: Vec<(usize, usize)>) {}
+
+    fn insert_a(&mut self, _a_trpl: &(usize, usize, Numeric)) {}
+
+    fn insert_b(&mut self, _b_pair: &(usize, Numeric)) {}
+
+    fn a_slot(&mut self, _row: usize, _col: usize) -> usize {
+        0
+    }
+
+    fn insert_a_slot(&mut self, _slot: usize, _value: Numeric) {}
+
+    fn store_base(&mut self) {}
+
+    fn load_base(&mut self) {}
+
+    fn insert_cplx_a(&mut self, _a_trpl: &(usize, usize, ComplexNumeric)) {}
+
+    fn insert_cplx_b(&mut self, _b_pair: &(usize, ComplexNumeric)) {}
+
+    fn refactor(&mut self) -> Result<(), SolverError> {
+        Err(SolverError::MatrixNonInvertible { pivot: None })
+    }
+
+    fn solve(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        Err(SolverError::MatrixNonInvertible { pivot: None })
+    }
+
+    fn solve_factored(&mut self) -> Result<&Vec<Numeric>, SolverError> {
+        Err(SolverError::MatrixNonInvertible { pivot: None })
+    }
+
+    fn solve_cplx(&mut self) -> Result<&Vec<ComplexNumeric>, SolverError> {
+        Err(SolverError::MatrixNonInvertible { pivot: None })
+    }
+
+    fn system(&self) -> (Vec<(usize, usize, Numeric)>, Vec<Numeric>) {
+        (Vec::new(), Vec::new())
+    }
+
+    fn cplx_system(&self) -> (Vec<(usize, usize, ComplexNumeric)>, Vec<ComplexNumeric>) {
+        (Vec::new(), Vec::new())
+    }
+
+    fn reset(&mut self) {}
+}
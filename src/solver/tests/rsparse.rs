@@ -57,7 +57,7 @@ fn init_solver_cplx() {
         .enumerate()
         .for_each(|(idx, val)| solver.insert_cplx_b(&(idx, *val)));
 
-    assert_eq!(solver.cplx_b_vec_len(), 6);
+    assert_eq!(solver.cplx_b_vec_len(), 3);
 }
 
 #[test]
@@ -191,6 +191,29 @@ fn solve_complex_small_wo_imag() {
     assert_eq!(solution[1], ComplexNumeric { re: 1.0, im: 0.0 });
 }
 
+#[test]
+fn solve_complex_reactive_across_frequencies() {
+    // An inductor branch jωL in series with 1Ω, driven by 1V: the pivots have no real part
+    let mut solver = RSparseSolver::new(2).unwrap();
+    for omega in [1.0, 1e3] {
+        let a_matrix = [
+            (0, 0, ComplexNumeric::new(1.0, 0.0)),
+            (0, 1, ComplexNumeric::new(1.0, 0.0)),
+            (1, 0, ComplexNumeric::new(1.0, 0.0)),
+            (1, 1, ComplexNumeric::new(0.0, -omega)),
+        ];
+        a_matrix.iter().for_each(|trpl| solver.insert_cplx_a(trpl));
+        solver.insert_cplx_b(&(0, ComplexNumeric::new(1.0, 0.0)));
+
+        let solution = solver.solve_cplx().unwrap();
+        // v = 1 - i and v = jω i
+        let current = ComplexNumeric::new(1.0, 0.0) / ComplexNumeric::new(1.0, omega);
+        assert!((solution[1] - current).norm() < 1e-12);
+        assert!((solution[0] - (1.0 - current)).norm() < 1e-12);
+        assert_eq!(solver.cplx_b_vec_len(), 2);
+    }
+}
+
 #[test]
 fn insert_add_a() {
     let a_matrix_elements = [(0, 0, 1.0), (0, 1, 2.0), (1, 0, 3.0), (1, 1, 4.0)];
@@ -514,3 +537,36 @@ pub fn newton_raphson_test() {
 
     println!("Solution: {x_current:?}");
 }
+
+#[test]
+fn solve_with_fill_reducing_ordering() {
+    // The dense first row and column of the arrow matrix are moved to the end by the ordering
+    const SIZE: usize = 6;
+    let mut a_matrix = vec![(0, 0, SIZE as Numeric)];
+    for idx in 1..SIZE {
+        a_matrix.extend([(0, idx, 1.0), (idx, 0, 1.0), (idx, idx, 2.0)]);
+    }
+    let x_expected: Vec<Numeric> = (1..=SIZE).map(|val| val as Numeric).collect();
+
+    let mut solver = RSparseSolver::new(SIZE).unwrap();
+    solver.init(
+        a_matrix.iter().map(|(row, col, _)| (*row, *col)).collect(),
+        vec![],
+    );
+    solver.analyse().unwrap();
+
+    let mut b_vector = [0.0; SIZE];
+    for (row, col, val) in &a_matrix {
+        solver.insert_a(&(*row, *col, *val));
+        b_vector[*row] += val * x_expected[*col];
+    }
+    b_vector
+        .iter()
+        .enumerate()
+        .for_each(|(idx, val)| solver.insert_b(&(idx, *val)));
+
+    let solution = solver.solve().unwrap();
+    for (value, expected) in solution.iter().zip(&x_expected) {
+        assert!((value - expected).abs() < 1e-12, "{solution:?}");
+    }
+}
@@ -15,22 +15,45 @@ pub const KB: Numeric = 1.380_649e-23;
 /// Elementary Charge
 pub const ELE_CHRG: Numeric = 1.602_176_634e-19;
 
-/// Room temperature in K
+/// The default circuit temperature and nominal temperature of the device parameters in K
 pub const TEMP: Numeric = 293.15;
 
-/// The default conductance for inductors in S
+/// The offset between the Celsius and the Kelvin scale
+pub const CELSIUS_TO_KELVIN: Numeric = 273.15;
+
+/// The default conductance for inductors in DC analyses in S
 pub const DEFAULT_CONDUCTANCE: Numeric = 1e24;
 
-/// The dfault temperature voltage
+/// The conductance used to hold nodes at their `.ic` value during the initial operating point in S
+pub const IC_CONDUCTANCE: Numeric = 1e12;
+
+/// The thermal voltage at the default temperature
 pub const UT: Numeric = KB * TEMP / ELE_CHRG;
 
 pub const DIO_GUESS: Numeric = 0.4;
 
-/// Define tolerance and maximum number of iterations
-pub const VECTOL: Numeric = 1e-3;
+/// Default tolerances and maximum number of iterations of the Newton-Raphson iteration.
+/// A variable has converged if its step is below `RELTOL` of its value plus `VNTOL` for
+/// voltages or `ABSTOL` for currents. They can be changed with `.options`.
+pub const RELTOL: Numeric = 1e-3;
+pub const VNTOL: Numeric = 1e-6;
+pub const ABSTOL: Numeric = 1e-12;
 pub const MAXITER: usize = 1000;
 
-/// Constants for adaptive timestep control
+/// The maximum number of variables listed in a convergence failure report
+pub(crate) const REPORT_MAX_VARIABLES: usize = 5;
+
+/// A Newton step may grow by this factor over the previous step before it is damped
+pub(crate) const NEWTON_MAX_STEP_GROWTH: Numeric = 10.0;
+
+/// Defaults of the operating point continuation strategies
+pub(crate) const GMIN: Numeric = 1e-12;
+pub(crate) const GMIN_STEPS: usize = 10;
+pub(crate) const SOURCE_STEPS: usize = 10;
+pub(crate) const PTRAN_STEPS: usize = 100;
+pub(crate) const PTRAN_CONDUCTANCE: Numeric = 1.0;
+
+/// Defaults of the adaptive timestep control
 pub(crate) const ADAPTIVE_MIN_TIMESTEP: Numeric = 1e-9;
 pub(crate) const ADAPTIVE_MAX_TIMESTEP: Numeric = 1e-3;
 pub(crate) const ADAPTIVE_INITIAL_TIMESTEP: Numeric = 1e-6;
@@ -38,3 +61,25 @@ pub(crate) const ADAPTIVE_TOLERANCE: Numeric = 1e-4;
 pub(crate) const ADAPTIVE_SAFETY_FACTOR: Numeric = 0.9;
 pub(crate) const ADAPTIVE_MAX_GROWTH_FACTOR: Numeric = 2.0;
 pub(crate) const ADAPTIVE_MIN_GROWTH_FACTOR: Numeric = 0.5;
+
+/// A switching event is located, when a step brackets it within this fraction of the stop time.
+/// The event search rejects a step at most `EVENT_MAX_REJECTIONS` times.
+pub(crate) const EVENT_TOLERANCE: Numeric = 1e-9;
+pub(crate) const EVENT_MAX_REJECTIONS: usize = 10;
+
+/// Thresholds of the automatic solver selection, measured with the solve times of the backends
+pub(crate) const AUTO_SMALL_MAX_VARS: usize = 30;
+pub(crate) const AUTO_DENSE_MIN_DENSITY: Numeric = 0.1;
+pub(crate) const AUTO_DENSE_MAX_VARS: usize = 1000;
+pub(crate) const AUTO_ITERATIVE_MIN_VARS: usize = 100_000;
+pub(crate) const AUTO_FALLBACK_DENSE_MAX_VARS: usize = 2000;
+pub(crate) const HYBRID_THRESHOLD: usize = 50;
+
+/// The maximum number of gradient steps of the condition estimate
+pub(crate) const CONDITION_ESTIMATE_ITERATIONS: usize = 5;
+
+/// Sparse solvers locate the pivot of a singular matrix with a dense LU up to this size
+pub(crate) const SINGULAR_PIVOT_DENSE_MAX_VARS: usize = 2000;
+
+/// The path prefix of the Matrix Market files of the matrix dumps
+pub(crate) const DUMP_PREFIX: &str = "splice-dump";